@@ -2,7 +2,9 @@
 
 use crate::{transactions, transactions::RAYON_EXEC_POOL};
 use aptos_bitvec::BitVec;
-use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
+use aptos_block_executor::{
+    executor::BlockExecutorConfig, txn_commit_hook::NoOpTransactionCommitHook,
+};
 use aptos_block_partitioner::{
     v2::config::PartitionerV2Config, BlockPartitioner, PartitionerConfig,
 };
@@ -216,10 +218,14 @@ where
             transactions,
             self.state_view.as_ref(),
             1,
-            maybe_block_gas_limit,
+            BlockExecutorConfig {
+                maybe_block_gas_limit,
+                maybe_block_output_size_limit: None,
+            },
             None,
         )
-        .expect("VM should not fail to start");
+        .expect("VM should not fail to start")
+        .into_transaction_outputs();
         let exec_time = timer.elapsed().as_millis();
 
         (output, block_size * 1000 / exec_time as usize)
@@ -265,10 +271,14 @@ where
             transactions,
             self.state_view.as_ref(),
             concurrency_level_per_shard,
-            maybe_block_gas_limit,
+            BlockExecutorConfig {
+                maybe_block_gas_limit,
+                maybe_block_output_size_limit: None,
+            },
             None,
         )
-        .expect("VM should not fail to start");
+        .expect("VM should not fail to start")
+        .into_transaction_outputs();
         let exec_time = timer.elapsed().as_millis();
 
         (output, block_size * 1000 / exec_time as usize)
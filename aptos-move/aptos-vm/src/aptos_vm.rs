@@ -17,7 +17,10 @@ use crate::{
     verifier, VMExecutor, VMValidator,
 };
 use anyhow::{anyhow, Result};
-use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
+use aptos_block_executor::{
+    executor::{BlockExecutorConfig, BlockOutput},
+    txn_commit_hook::NoOpTransactionCommitHook,
+};
 use aptos_crypto::HashValue;
 use aptos_framework::natives::code::PublishRequest;
 use aptos_gas_algebra::Gas;
@@ -90,6 +93,7 @@ use std::{
 static EXECUTION_CONCURRENCY_LEVEL: OnceCell<usize> = OnceCell::new();
 static NUM_EXECUTION_SHARD: OnceCell<usize> = OnceCell::new();
 static NUM_PROOF_READING_THREADS: OnceCell<usize> = OnceCell::new();
+static BLOCK_OUTPUT_SIZE_LIMIT: OnceCell<Option<u64>> = OnceCell::new();
 static PARANOID_TYPE_CHECKS: OnceCell<bool> = OnceCell::new();
 static PROCESSED_TRANSACTIONS_DETAILED_COUNTERS: OnceCell<bool> = OnceCell::new();
 static TIMED_FEATURE_OVERRIDE: OnceCell<TimedFeatureOverride> = OnceCell::new();
@@ -219,6 +223,21 @@ impl AptosVM {
         }
     }
 
+    /// Sets the limit on the accumulated output size of the transactions committed in a block
+    /// when invoked the first time.
+    pub fn set_block_output_size_limit_once(maybe_block_output_size_limit: Option<u64>) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        BLOCK_OUTPUT_SIZE_LIMIT
+            .set(maybe_block_output_size_limit)
+            .ok();
+    }
+
+    /// Returns the limit on the accumulated output size of a block if already set, otherwise
+    /// return default None (no limit).
+    pub fn get_block_output_size_limit() -> Option<u64> {
+        BLOCK_OUTPUT_SIZE_LIMIT.get().copied().flatten()
+    }
+
     /// Sets additional details in counters when invoked the first time.
     pub fn set_processed_transactions_detailed_counters() {
         // Only the first call succeeds, due to OnceCell semantics.
@@ -1827,14 +1846,17 @@ impl VMExecutor for AptosVM {
             transactions,
             state_view,
             Self::get_concurrency_level(),
-            maybe_block_gas_limit,
+            BlockExecutorConfig {
+                maybe_block_gas_limit,
+                maybe_block_output_size_limit: Self::get_block_output_size_limit(),
+            },
             None,
         );
         if ret.is_ok() {
             // Record the histogram count for transactions per block.
             BLOCK_TRANSACTION_COUNT.observe(count as f64);
         }
-        ret
+        ret.map(BlockOutput::into_transaction_outputs)
     }
 
     fn execute_block_sharded<S: StateView + Sync + Send + 'static, C: ExecutorClient<S>>(
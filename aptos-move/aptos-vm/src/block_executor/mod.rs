@@ -6,14 +6,19 @@ pub(crate) mod vm_wrapper;
 
 use crate::{
     block_executor::vm_wrapper::AptosExecutorTask,
-    counters::{BLOCK_EXECUTOR_CONCURRENCY, BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS},
+    counters::{
+        BLOCK_EXECUTOR_ACCUMULATED_GAS, BLOCK_EXECUTOR_ACCUMULATED_OUTPUT_SIZE,
+        BLOCK_EXECUTOR_CONCURRENCY, BLOCK_EXECUTOR_CUT_BLOCKS,
+        BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS,
+    },
 };
 use aptos_aggregator::{
     delayed_change::DelayedChange, delta_change_set::DeltaOp, types::DelayedFieldID,
 };
 use aptos_block_executor::{
-    errors::Error, executor::BlockExecutor,
-    task::TransactionOutput as BlockExecutorTransactionOutput,
+    errors::Error,
+    executor::{BlockExecutor, BlockExecutorConfig, BlockOutput},
+    task::{ReadSummary, SkipReason, TransactionOutput as BlockExecutorTransactionOutput},
     txn_commit_hook::TransactionCommitHook,
 };
 use aptos_infallible::Mutex;
@@ -27,7 +32,7 @@ use aptos_types::{
         signature_verified_transaction::SignatureVerifiedTransaction, BlockExecutableTransaction,
         TransactionOutput, TransactionStatus,
     },
-    write_set::WriteOp,
+    write_set::{TransactionWrite, WriteOp},
 };
 use aptos_vm_logging::{flush_speculative_logs, init_speculative_logs};
 use aptos_vm_types::output::VMOutput;
@@ -36,6 +41,9 @@ use once_cell::sync::OnceCell;
 use rayon::ThreadPool;
 use std::{collections::BTreeMap, sync::Arc};
 
+/// The size of the serialized value of an aggregator v1, which is a u128.
+const AGGREGATOR_V1_VALUE_SIZE: usize = std::mem::size_of::<u128>();
+
 /// Output type wrapper used by block executor. VM output is stored first, then
 /// transformed into TransactionOutput type that is returned.
 #[derive(Debug)]
@@ -43,6 +51,8 @@ pub struct AptosTransactionOutput {
     // Note: should these mutexes be changed to ExplicitSyncSwapper?
     vm_output: Mutex<Option<VMOutput>>,
     committed_output: OnceCell<TransactionOutput>,
+    read_summary: OnceCell<ReadSummary<StateKey, StructTag, DelayedFieldID>>,
+    skip_reason: Option<SkipReason>,
 }
 
 impl AptosTransactionOutput {
@@ -50,6 +60,8 @@ impl AptosTransactionOutput {
         Self {
             vm_output: Mutex::new(Some(output)),
             committed_output: OnceCell::new(),
+            read_summary: OnceCell::new(),
+            skip_reason: None,
         }
     }
 
@@ -82,8 +94,15 @@ impl BlockExecutorTransactionOutput for AptosTransactionOutput {
 
     /// Execution output for transactions that comes after SkipRest signal or when there was a
     /// problem creating the output (e.g. group serialization issue).
-    fn skip_output() -> Self {
-        Self::new(VMOutput::empty_with_status(TransactionStatus::Retry))
+    fn skip_output(reason: SkipReason) -> Self {
+        Self {
+            skip_reason: Some(reason),
+            ..Self::new(VMOutput::empty_with_status(TransactionStatus::Retry))
+        }
+    }
+
+    fn skip_reason(&self) -> Option<SkipReason> {
+        self.skip_reason
     }
 
     // TODO: get rid of the cloning data-structures in the following APIs.
@@ -291,11 +310,61 @@ impl BlockExecutorTransactionOutput for AptosTransactionOutput {
             .expect("Output to be set to get fee statement")
             .fee_statement()
     }
+
+    /// Return the size of the writes and events, as counted by the change set limits: the
+    /// bytes of each write with its key, the serialized size of each written resource group
+    /// with its key, and the data of each event. An aggregator v1 delta counts as its key and
+    /// a u128 value, like its materialized write, so the size does not depend on whether the
+    /// deltas were materialized during execution.
+    /// Should never be called after vm_output is consumed.
+    fn output_approx_size(&self) -> u64 {
+        let vm_output = self.vm_output.lock();
+        let change_set = vm_output
+            .as_ref()
+            .expect("Output to be set to get output size")
+            .change_set();
+        let write_size: u64 = change_set
+            .write_set_iter()
+            .filter_map(|(key, op)| op.bytes().map(|bytes| (bytes.len() + key.size()) as u64))
+            .sum();
+        let delta_size: u64 = change_set
+            .aggregator_v1_delta_set()
+            .keys()
+            .map(|key| (key.size() + AGGREGATOR_V1_VALUE_SIZE) as u64)
+            .sum();
+        let group_write_size: u64 = change_set
+            .resource_group_write_set()
+            .iter()
+            .map(|(key, group_write)| {
+                key.size() as u64 + group_write.maybe_group_op_size().unwrap_or(0)
+            })
+            .sum();
+        let event_size: u64 = change_set
+            .events()
+            .iter()
+            .map(|(event, _)| event.event_data().len() as u64)
+            .sum();
+        write_size + delta_size + group_write_size + event_size
+    }
+
+    fn set_read_summary(&self, read_summary: ReadSummary<StateKey, StructTag, DelayedFieldID>) {
+        assert!(
+            self.read_summary.set(read_summary).is_ok(),
+            "Read summary must only be set once"
+        );
+    }
+
+    fn read_summary(&self) -> Option<&ReadSummary<StateKey, StructTag, DelayedFieldID>> {
+        self.read_summary.get()
+    }
 }
 
 pub struct BlockAptosVM();
 
 impl BlockAptosVM {
+    /// Executes the block, returning the outputs of its transactions together with the
+    /// accumulated gas and output size of the committed transactions, and where and why the
+    /// block was cut, if it was.
     pub fn execute_block<
         S: StateView + Sync,
         L: TransactionCommitHook<Output = AptosTransactionOutput>,
@@ -304,9 +373,9 @@ impl BlockAptosVM {
         signature_verified_block: &[SignatureVerifiedTransaction],
         state_view: &S,
         concurrency_level: usize,
-        maybe_block_gas_limit: Option<u64>,
+        config: BlockExecutorConfig,
         transaction_commit_listener: Option<L>,
-    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+    ) -> Result<BlockOutput<TransactionOutput>, VMStatus> {
         let _timer = BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS.start_timer();
         let num_txns = signature_verified_block.len();
         if state_view.id() != StateViewId::Miscellaneous {
@@ -322,23 +391,23 @@ impl BlockAptosVM {
             S,
             L,
             ExecutableTestType,
-        >::new(
+        >::from_config(
             concurrency_level,
             executor_thread_pool,
-            maybe_block_gas_limit,
+            config,
             transaction_commit_listener,
         );
 
         let ret = executor.execute_block(state_view, signature_verified_block, state_view);
         match ret {
-            Ok(outputs) => {
-                let output_vec: Vec<TransactionOutput> = outputs
-                    .into_iter()
-                    .map(|output| output.take_output())
-                    .collect();
+            Ok(block_output) => {
+                let block_output =
+                    block_output.map_transaction_outputs(|output| output.take_output());
 
                 // Flush the speculative logs of the committed transactions.
-                let pos = output_vec.partition_point(|o| !o.status().is_retry());
+                let pos = block_output
+                    .transaction_outputs()
+                    .partition_point(|o| !o.status().is_retry());
 
                 if state_view.id() != StateViewId::Miscellaneous {
                     // Speculation is disabled in Miscellaneous context, which is used by testing and
@@ -346,7 +415,16 @@ impl BlockAptosVM {
                     flush_speculative_logs(pos);
                 }
 
-                Ok(output_vec)
+                if let Some(reason) = block_output.cut_reason() {
+                    BLOCK_EXECUTOR_CUT_BLOCKS
+                        .with_label_values(&[reason.as_str()])
+                        .inc();
+                }
+                BLOCK_EXECUTOR_ACCUMULATED_GAS.observe(block_output.accumulated_gas() as f64);
+                BLOCK_EXECUTOR_ACCUMULATED_OUTPUT_SIZE
+                    .observe(block_output.accumulated_output_size() as f64);
+
+                Ok(block_output)
             },
             Err(Error::FallbackToSequential(e)) => {
                 unreachable!(
@@ -5,9 +5,8 @@
 use crate::{
     aptos_vm::AptosVM, block_executor::AptosTransactionOutput, data_cache::AsMoveResolver,
 };
-use aptos_block_executor::task::{ExecutionStatus, ExecutorTask};
+use aptos_block_executor::task::{ExecutionContext, ExecutionStatus, ExecutorTask, SkipReason};
 use aptos_logger::{enabled, Level};
-use aptos_mvhashmap::types::TxnIndex;
 use aptos_state_view::StateView;
 use aptos_types::transaction::{
     signature_verified_transaction::SignatureVerifiedTransaction, Transaction, WriteSetPayload,
@@ -44,7 +43,7 @@ impl<'a, S: 'a + StateView + Sync> ExecutorTask for AptosExecutorTask<'a, S> {
         &self,
         executor_with_group_view: &(impl ExecutorView + ResourceGroupView),
         txn: &SignatureVerifiedTransaction,
-        txn_idx: TxnIndex,
+        context: ExecutionContext,
         materialize_deltas: bool,
     ) -> ExecutionStatus<AptosTransactionOutput, VMStatus> {
         if (executor_with_group_view.is_delayed_field_optimization_capable()
@@ -54,7 +53,7 @@ impl<'a, S: 'a + StateView + Sync> ExecutorTask for AptosExecutorTask<'a, S> {
             return ExecutionStatus::DirectWriteSetTransactionNotCapableError;
         }
 
-        let log_context = AdapterLogSchema::new(self.base_view.id(), txn_idx as usize);
+        let log_context = AdapterLogSchema::new(self.base_view.id(), context.txn_idx as usize);
         let resolver = self
             .vm
             .as_move_resolver_with_group_view(executor_with_group_view);
@@ -70,19 +69,24 @@ impl<'a, S: 'a + StateView + Sync> ExecutorTask for AptosExecutorTask<'a, S> {
                         .expect("Delta materialization failed");
                 }
 
+                // The logs of earlier incarnations are cleared on re-execution, so the incarnation
+                // in the logs tells whether the transaction was re-executed.
                 if vm_output.status().is_discarded() {
                     match sender {
                         Some(s) => speculative_trace!(
                             &log_context,
                             format!(
-                                "Transaction discarded, sender: {}, error: {:?}",
-                                s, vm_status
+                                "Transaction discarded in incarnation {}, sender: {}, error: {:?}",
+                                context.incarnation, s, vm_status
                             ),
                         ),
                         None => {
                             speculative_trace!(
                                 &log_context,
-                                format!("Transaction malformed, error: {:?}", vm_status),
+                                format!(
+                                    "Transaction malformed in incarnation {}, error: {:?}",
+                                    context.incarnation, vm_status
+                                ),
                             )
                         },
                     };
@@ -99,9 +103,15 @@ impl<'a, S: 'a + StateView + Sync> ExecutorTask for AptosExecutorTask<'a, S> {
                 } else if AptosVM::should_restart_execution(&vm_output) {
                     speculative_info!(
                         &log_context,
-                        "Reconfiguration occurred: restart required".into()
+                        format!(
+                            "Reconfiguration occurred in incarnation {}: restart required",
+                            context.incarnation
+                        )
                     );
-                    ExecutionStatus::SkipRest(AptosTransactionOutput::new(vm_output))
+                    ExecutionStatus::SkipRest(
+                        AptosTransactionOutput::new(vm_output),
+                        SkipReason::Reconfiguration,
+                    )
                 } else {
                     ExecutionStatus::Success(AptosTransactionOutput::new(vm_output))
                 }
@@ -32,6 +32,35 @@ pub static BLOCK_EXECUTOR_CONCURRENCY: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count the number of blocks whose trailing transactions were skipped, with a "reason" label
+/// telling why the block was cut.
+pub static BLOCK_EXECUTOR_CUT_BLOCKS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "block_executor_cut_blocks_count",
+        "Number of blocks cut before their last transaction, by reason",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+pub static BLOCK_EXECUTOR_ACCUMULATED_GAS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "block_executor_accumulated_gas",
+        "The execution and io gas of the committed transactions of a block",
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 2.0, /*count=*/ 30).unwrap(),
+    )
+    .unwrap()
+});
+
+pub static BLOCK_EXECUTOR_ACCUMULATED_OUTPUT_SIZE: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "block_executor_accumulated_output_size_bytes",
+        "The approximate output size of the committed transactions of a block",
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 2.0, /*count=*/ 30).unwrap(),
+    )
+    .unwrap()
+});
+
 pub static BLOCK_EXECUTOR_SIGNATURE_VERIFICATION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
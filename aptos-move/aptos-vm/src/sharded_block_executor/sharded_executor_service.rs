@@ -15,6 +15,7 @@ use crate::{
         ExecutorShardCommand,
     },
 };
+use aptos_block_executor::executor::{BlockExecutorConfig, BlockOutput};
 use aptos_logger::{info, trace};
 use aptos_state_view::StateView;
 use aptos_types::{
@@ -140,9 +141,13 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                     &signature_verified_transactions,
                     aggr_overridden_state_view.as_ref(),
                     concurrency_level,
-                    maybe_block_gas_limit,
+                    BlockExecutorConfig {
+                        maybe_block_gas_limit,
+                        maybe_block_output_size_limit: None,
+                    },
                     cross_shard_commit_sender,
-                );
+                )
+                .map(BlockOutput::into_transaction_outputs);
                 if let Some(shard_id) = shard_id {
                     trace!(
                         "executed sub block for shard {} and round {}",
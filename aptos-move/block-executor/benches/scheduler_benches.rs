@@ -18,6 +18,19 @@ fn random_benches(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, random_benches);
+// Compares transfer-like transactions with and without inferred accesses, which reduce the
+// number of aborts (see the speculative abort counter).
+fn transfer_benches(c: &mut Criterion) {
+    c.bench_function("transfer_benches", |b| {
+        let bencher = Bencher::<[u8; 32], [u8; 32]>::new_transfers(10000, 100);
+        bencher.bench(&any::<[u8; 32]>(), b)
+    });
+    c.bench_function("hinted_transfer_benches", |b| {
+        let bencher = Bencher::<[u8; 32], [u8; 32]>::new_transfers(10000, 100).with_access_hints();
+        bencher.bench(&any::<[u8; 32]>(), b)
+    });
+}
+
+criterion_group!(benches, random_benches, transfer_benches);
 
 criterion_main!(benches);
@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::task::ReadSummary;
 use anyhow::bail;
 use aptos_aggregator::{
     delta_math::DeltaHistory,
@@ -668,6 +669,25 @@ impl<T: Transaction> CapturedReads<T> {
     pub(crate) fn mark_incorrect_use(&mut self) {
         self.incorrect_use = true;
     }
+
+    /// Returns the keys, group resources, modules and delayed fields that were read.
+    pub(crate) fn read_summary(&self) -> ReadSummary<T::Key, T::Tag, T::Identifier> {
+        ReadSummary {
+            resources: self.data_reads.keys().cloned().collect(),
+            group_resources: self
+                .group_reads
+                .iter()
+                .flat_map(|(group_key, group_read)| {
+                    group_read
+                        .inner_reads
+                        .keys()
+                        .map(|tag| (group_key.clone(), tag.clone()))
+                })
+                .collect(),
+            modules: self.module_reads.iter().cloned().collect(),
+            delayed_fields: self.delayed_field_reads.keys().cloned().collect(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -82,6 +82,16 @@ pub static EXCEED_PER_BLOCK_GAS_LIMIT_COUNT: Lazy<IntCounterVec> = Lazy::new(||
     .unwrap()
 });
 
+/// Count of times the BlockSTM cut a block due to exceeding the per-block output size limit.
+pub static EXCEED_PER_BLOCK_OUTPUT_SIZE_LIMIT_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_output_size_limit_count",
+        "Count of times the BlockSTM cut a block due to exceeding the per-block output size limit",
+        &["mode"]
+    )
+    .unwrap()
+});
+
 pub static PARALLEL_EXECUTION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -11,7 +11,9 @@ use crate::{
     errors::*,
     explicit_sync_wrapper::ExplicitSyncWrapper,
     scheduler::{DependencyStatus, ExecutionTaskType, Scheduler, SchedulerTask, Wave},
-    task::{ExecutionStatus, ExecutorTask, TransactionOutput},
+    task::{
+        ExecutionContext, ExecutionStatus, ExecutorTask, ReadSummary, SkipReason, TransactionOutput,
+    },
     txn_commit_hook::TransactionCommitHook,
     txn_last_input_output::{KeyKind, TxnLastInputOutput},
     view::{LatestView, ParallelState, SequentialState, ViewState},
@@ -33,7 +35,7 @@ use aptos_state_view::TStateView;
 use aptos_types::{
     aggregator::PanicError,
     contract_event::TransactionEvent,
-    executable::Executable,
+    executable::{Executable, ModulePath},
     fee_statement::FeeStatement,
     transaction::BlockExecutableTransaction as Transaction,
     write_set::{TransactionWrite, WriteOp},
@@ -53,12 +55,150 @@ use std::{
     sync::{atomic::AtomicU32, Arc},
 };
 
+/// The outputs of the transactions of a block, with the accumulated gas and output size of the
+/// committed transactions, and where and why the block was cut, if it was.
+#[derive(Debug)]
+pub struct BlockOutput<Output> {
+    transaction_outputs: Vec<Output>,
+    accumulated_fee_statement: FeeStatement,
+    accumulated_output_size: u64,
+    cut: Option<(TxnIndex, SkipReason)>,
+}
+
+impl<Output> BlockOutput<Output> {
+    pub fn new(
+        transaction_outputs: Vec<Output>,
+        accumulated_fee_statement: FeeStatement,
+        accumulated_output_size: u64,
+        cut: Option<(TxnIndex, SkipReason)>,
+    ) -> Self {
+        Self {
+            transaction_outputs,
+            accumulated_fee_statement,
+            accumulated_output_size,
+            cut,
+        }
+    }
+
+    pub fn transaction_outputs(&self) -> &[Output] {
+        &self.transaction_outputs
+    }
+
+    pub fn into_transaction_outputs(self) -> Vec<Output> {
+        self.transaction_outputs
+    }
+
+    /// Converts the outputs of the transactions, keeping the accumulated values and the cut.
+    pub fn map_transaction_outputs<U>(self, f: impl FnMut(Output) -> U) -> BlockOutput<U> {
+        BlockOutput {
+            transaction_outputs: self.transaction_outputs.into_iter().map(f).collect(),
+            accumulated_fee_statement: self.accumulated_fee_statement,
+            accumulated_output_size: self.accumulated_output_size,
+            cut: self.cut,
+        }
+    }
+
+    /// The sum of the fee statements of the committed transactions.
+    pub fn accumulated_fee_statement(&self) -> &FeeStatement {
+        &self.accumulated_fee_statement
+    }
+
+    /// The execution and io gas of the committed transactions, which is what counts towards
+    /// the per-block gas limit.
+    pub fn accumulated_gas(&self) -> u64 {
+        self.accumulated_fee_statement.execution_gas_used()
+            + self.accumulated_fee_statement.io_gas_used()
+    }
+
+    /// The sum of the approximate output sizes of the committed transactions.
+    pub fn accumulated_output_size(&self) -> u64 {
+        self.accumulated_output_size
+    }
+
+    /// The index of the transaction which caused the block to be cut, if any. The following
+    /// transactions are not committed, and have skip outputs recording the reason, so they can
+    /// be retried in a later block. The transaction itself is committed, unless the block was
+    /// cut due to the output size limit.
+    pub fn cut_at(&self) -> Option<TxnIndex> {
+        self.cut.map(|(txn_idx, _)| txn_idx)
+    }
+
+    /// The reason for which the block was cut, if it was. When several apply to the same
+    /// transaction, this is the one which was detected first.
+    pub fn cut_reason(&self) -> Option<SkipReason> {
+        self.cut.map(|(_, reason)| reason)
+    }
+
+    /// The index of the transaction at which the block was cut due to the per-block output
+    /// size limit, if any. This and the following transactions are not committed.
+    pub fn output_size_cut_at(&self) -> Option<TxnIndex> {
+        match self.cut {
+            Some((txn_idx, SkipReason::OutputSizeLimit)) => Some(txn_idx),
+            _ => None,
+        }
+    }
+
+    /// Whether the block was cut due to the per-block output size limit.
+    pub fn is_output_size_limit_reached(&self) -> bool {
+        self.output_size_cut_at().is_some()
+    }
+}
+
+impl<Output: TransactionOutput> BlockOutput<Output> {
+    /// The read summaries of the committed transactions, by index, if the executor collected
+    /// them (see `BlockExecutor::with_read_summaries`).
+    pub fn read_summaries(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            TxnIndex,
+            &ReadSummary<
+                <Output::Txn as Transaction>::Key,
+                <Output::Txn as Transaction>::Tag,
+                <Output::Txn as Transaction>::Identifier,
+            >,
+        ),
+    > + '_ {
+        self.transaction_outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, output)| Some((idx as TxnIndex, output.read_summary()?)))
+    }
+}
+
+/// The accumulated output size of the committed transactions during parallel execution, and
+/// where and why the block was cut, if it was.
+#[derive(Default)]
+struct BlockCutState {
+    accumulated_output_size: u64,
+    cut: Option<(TxnIndex, SkipReason)>,
+}
+
+impl BlockCutState {
+    /// Records that the block is cut at the transaction, unless it was already cut earlier.
+    fn cut(&mut self, txn_idx: TxnIndex, reason: SkipReason) {
+        self.cut.get_or_insert((txn_idx, reason));
+    }
+}
+
+/// The limits of a block which the executor enforces while committing its transactions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockExecutorConfig {
+    /// The limit on the accumulated gas of the committed transactions.
+    pub maybe_block_gas_limit: Option<u64>,
+    /// The limit on the accumulated approximate output size of the committed transactions (see
+    /// `BlockExecutor::with_block_output_size_limit`).
+    pub maybe_block_output_size_limit: Option<u64>,
+}
+
 pub struct BlockExecutor<T, E, S, L, X> {
     // Number of active concurrent tasks, corresponding to the maximum number of rayon
     // threads that may be concurrently participating in parallel execution.
     concurrency_level: usize,
     executor_thread_pool: Arc<ThreadPool>,
-    maybe_block_gas_limit: Option<u64>,
+    config: BlockExecutorConfig,
+    collect_read_summaries: bool,
+    infer_access_hints: bool,
     transaction_commit_hook: Option<L>,
     phantom: PhantomData<(T, E, S, L, X)>,
 }
@@ -78,6 +218,24 @@ where
         executor_thread_pool: Arc<ThreadPool>,
         maybe_block_gas_limit: Option<u64>,
         transaction_commit_hook: Option<L>,
+    ) -> Self {
+        Self::from_config(
+            concurrency_level,
+            executor_thread_pool,
+            BlockExecutorConfig {
+                maybe_block_gas_limit,
+                maybe_block_output_size_limit: None,
+            },
+            transaction_commit_hook,
+        )
+    }
+
+    /// Creates an executor enforcing the limits of the config, see `new`.
+    pub fn from_config(
+        concurrency_level: usize,
+        executor_thread_pool: Arc<ThreadPool>,
+        config: BlockExecutorConfig,
+        transaction_commit_hook: Option<L>,
     ) -> Self {
         assert!(
             concurrency_level > 0 && concurrency_level <= num_cpus::get(),
@@ -87,15 +245,100 @@ where
         Self {
             concurrency_level,
             executor_thread_pool,
-            maybe_block_gas_limit,
+            config,
+            collect_read_summaries: false,
+            infer_access_hints: false,
             transaction_commit_hook,
             phantom: PhantomData,
         }
     }
 
+    /// Sets the limit on the accumulated approximate output size of the committed transactions
+    /// of a block. The first transaction whose output would make the accumulated size exceed
+    /// the limit is not committed, and neither are the transactions after it. The first
+    /// transaction of a block is always committed, so that every block makes progress.
+    pub fn with_block_output_size_limit(
+        mut self,
+        maybe_block_output_size_limit: Option<u64>,
+    ) -> Self {
+        self.config.maybe_block_output_size_limit = maybe_block_output_size_limit;
+        self
+    }
+
+    /// Sets whether to record the reads of the committed incarnation of each transaction in its
+    /// output (see `TransactionOutput::read_summary`). Disabled by default, as the summaries
+    /// are kept in memory with the outputs of the block.
+    pub fn with_read_summaries(mut self, collect_read_summaries: bool) -> Self {
+        self.collect_read_summaries = collect_read_summaries;
+        self
+    }
+
+    /// Sets whether parallel execution uses the accesses inferred by the executor task before
+    /// execution (see `ExecutorTask::infer_accesses`). Disabled by default, as the inference
+    /// requires an additional instance of the executor task for each block. Has no effect
+    /// with executor tasks which do not infer accesses, like the one of the AptosVM.
+    pub fn with_access_hints(mut self, infer_access_hints: bool) -> Self {
+        self.infer_access_hints = infer_access_hints;
+        self
+    }
+
+    /// Infers the accesses of the transactions in the block if enabled, returning the keys
+    /// each transaction is inferred to write (other than modules), and for each transaction,
+    /// the closest lower transaction inferred to write to a key that it is inferred to read.
+    fn infer_access_hints(
+        &self,
+        executor_arguments: E::Argument,
+        block: &[T],
+    ) -> Option<(Vec<Vec<T::Key>>, Vec<Option<TxnIndex>>)> {
+        if !self.infer_access_hints {
+            return None;
+        }
+
+        let executor = E::init(executor_arguments);
+        let mut last_writers: HashMap<T::Key, TxnIndex> = HashMap::new();
+        let mut hinted_writes = Vec::with_capacity(block.len());
+        let mut hinted_dependencies = Vec::with_capacity(block.len());
+        for (idx, txn) in block.iter().enumerate() {
+            let Some(accesses) = executor.infer_accesses(txn) else {
+                hinted_writes.push(vec![]);
+                hinted_dependencies.push(None);
+                continue;
+            };
+
+            hinted_dependencies.push(
+                accesses
+                    .keys_read
+                    .iter()
+                    .filter_map(|key| last_writers.get(key).copied())
+                    .max(),
+            );
+            let keys_written: Vec<_> = accesses
+                .keys_written
+                .into_iter()
+                .filter(|key| key.module_path().is_none())
+                .collect();
+            for key in &keys_written {
+                last_writers.insert(key.clone(), idx as TxnIndex);
+            }
+            hinted_writes.push(keys_written);
+        }
+        Some((hinted_writes, hinted_dependencies))
+    }
+
+    /// Returns whether a transaction with the given output size would make the accumulated
+    /// output size exceed the limit, in which case the block is cut before the transaction.
+    fn exceeds_output_size_limit(&self, txn_idx: TxnIndex, accumulated: u64, size: u64) -> bool {
+        txn_idx > 0
+            && self
+                .config
+                .maybe_block_output_size_limit
+                .map_or(false, |limit| accumulated + size > limit)
+    }
+
     fn execute(
         idx_to_execute: TxnIndex,
         incarnation: Incarnation,
+        hinted_writes: &[T::Key],
         signature_verified_block: &[T],
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
@@ -108,11 +351,24 @@ where
 
         // VM execution.
         let sync_view = LatestView::new(base_view, ViewState::Sync(latest_view), idx_to_execute);
-        let execute_result = executor.execute_transaction(&sync_view, txn, idx_to_execute, false);
+        let context = ExecutionContext {
+            txn_idx: idx_to_execute,
+            incarnation,
+            is_sequential: false,
+        };
+        let execute_result = executor.execute_transaction(&sync_view, txn, context, false);
 
         let mut prev_modified_keys = last_input_output
             .modified_keys(idx_to_execute)
             .map_or(HashMap::new(), |keys| keys.collect());
+        // The estimates recorded for the hinted writes before the first incarnation are
+        // overwritten or removed like the entries of a previous incarnation. Since the readers
+        // never observed values below the estimates, they do not require revalidation.
+        prev_modified_keys.extend(
+            hinted_writes
+                .iter()
+                .map(|key| (key.clone(), KeyKind::Resource)),
+        );
 
         let mut prev_modified_delayed_fields = last_input_output
             .delayed_field_keys(idx_to_execute)
@@ -232,10 +488,10 @@ where
                 apply_updates(&output)?;
                 ExecutionStatus::Success(output)
             },
-            ExecutionStatus::SkipRest(output) => {
+            ExecutionStatus::SkipRest(output, reason) => {
                 // Apply the writes/deltas and record status indicating skip.
                 apply_updates(&output)?;
-                ExecutionStatus::SkipRest(output)
+                ExecutionStatus::SkipRest(output, reason)
             },
             ExecutionStatus::Abort(err) => {
                 // Record the status indicating abort.
@@ -423,6 +679,7 @@ where
             FeeStatement,
             Vec<FeeStatement>,
             Option<Error<E::Error>>,
+            BlockCutState,
         )>,
         base_view: &S,
         start_shared_counter: u32,
@@ -431,7 +688,7 @@ where
         block: &[T],
     ) -> ::std::result::Result<(), PanicOr<IntentionalFallbackToSequential>> {
         let mut shared_commit_state_guard = shared_commit_state.acquire();
-        let (accumulated_fee_statement, txn_fee_statements, shared_maybe_error) =
+        let (accumulated_fee_statement, txn_fee_statements, shared_maybe_error, block_cut) =
             shared_commit_state_guard.dereference_mut();
 
         let update_counters_and_log_info =
@@ -468,6 +725,7 @@ where
                 let _updates_outside = Self::execute(
                     txn_idx,
                     incarnation + 1,
+                    &[],
                     block,
                     last_input_output,
                     versioned_cache,
@@ -497,6 +755,38 @@ where
                 }
             }
 
+            // When the output of the txn would make the accumulated output size of the committed
+            // txns exceed the per-block output size limit, the block is cut before the txn, which
+            // is not committed: it and the rest of the txns keep skip outputs, to be retried.
+            let output_approx_size = last_input_output.output_approx_size(txn_idx).unwrap_or(0);
+            if self.exceeds_output_size_limit(
+                txn_idx,
+                block_cut.accumulated_output_size,
+                output_approx_size,
+            ) {
+                counters::EXCEED_PER_BLOCK_OUTPUT_SIZE_LIMIT_COUNT
+                    .with_label_values(&[counters::Mode::PARALLEL])
+                    .inc();
+                info!(
+                    "[BlockSTM]: Parallel execution cut the block before txn {} due to \
+                     accumulated output size {} + {} > PER_BLOCK_OUTPUT_SIZE_LIMIT {:?}",
+                    txn_idx,
+                    block_cut.accumulated_output_size,
+                    output_approx_size,
+                    self.config.maybe_block_output_size_limit,
+                );
+                block_cut.cut(txn_idx, SkipReason::OutputSizeLimit);
+                if scheduler.halt() {
+                    update_counters_and_log_info(
+                        txn_idx - 1,
+                        accumulated_fee_statement,
+                        txn_fee_statements,
+                    );
+                }
+                break;
+            }
+            block_cut.accumulated_output_size += output_approx_size;
+
             defer! {
                 scheduler.add_to_commit_queue(txn_idx);
             }
@@ -513,7 +803,13 @@ where
                     let accumulated_non_storage_gas = accumulated_fee_statement
                         .execution_gas_used()
                         + accumulated_fee_statement.io_gas_used();
-                    if accumulated_non_storage_gas >= per_block_gas_limit {
+                    // A txn which returned SkipRest already skips the rest of the txns, so the
+                    // block is cut for its reason, which was detected first.
+                    if accumulated_non_storage_gas >= per_block_gas_limit
+                        && last_input_output
+                            .block_skip_reason_at_idx(txn_idx)
+                            .is_none()
+                    {
                         counters::EXCEED_PER_BLOCK_GAS_LIMIT_COUNT
                             .with_label_values(&[counters::Mode::PARALLEL])
                             .inc();
@@ -523,8 +819,11 @@ where
                             accumulated_non_storage_gas, per_block_gas_limit,
                         );
 
+                        if txn_idx + 1 < scheduler.num_txns() {
+                            block_cut.cut(txn_idx, SkipReason::BlockGasLimit);
+                        }
                         // Set the execution output status to be SkipRest, to skip the rest of the txns.
-                        last_input_output.update_to_skip_rest(txn_idx);
+                        last_input_output.update_to_skip_rest(txn_idx, SkipReason::BlockGasLimit);
                     }
                 }
             }
@@ -631,14 +930,15 @@ where
 
             // We cover cases 3 and 4 here: Either all txn committed,
             // or a committed txn caused an early halt.
-            if txn_idx + 1 == scheduler.num_txns()
-                || last_input_output.block_skips_rest_at_idx(txn_idx)
-            {
+            let skip_reason = last_input_output.block_skip_reason_at_idx(txn_idx);
+            if txn_idx + 1 == scheduler.num_txns() || skip_reason.is_some() {
                 if txn_idx + 1 == scheduler.num_txns() {
                     assert!(
                         !matches!(scheduler_task, SchedulerTask::ExecutionTask(_, _, _)),
                         "All transactions can be committed, can't have execution task"
                     );
+                } else if let Some(reason) = skip_reason {
+                    block_cut.cut(txn_idx, reason);
                 }
 
                 if scheduler.halt() {
@@ -843,7 +1143,6 @@ where
         shared_counter: &AtomicU32,
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
         base_view: &S,
-        final_results: &ExplicitSyncWrapper<Vec<E::Output>>,
     ) -> ::std::result::Result<(), PanicOr<IntentionalFallbackToSequential>> {
         let parallel_state = ParallelState::<T, X>::new(
             versioned_cache,
@@ -890,12 +1189,27 @@ where
             patched_events,
             serialized_groups,
         );
+        if self.collect_read_summaries {
+            last_input_output.record_read_summary(txn_idx);
+        }
+        scheduler.finish_materialization(txn_idx);
+        Ok(())
+    }
+
+    /// Invokes the commit hook for the materialized transaction, and records its final output.
+    /// Invoked for the transactions in the order of their indices, by one worker at a time.
+    fn dispatch_commit_hook(
+        &self,
+        txn_idx: TxnIndex,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        final_results: &ExplicitSyncWrapper<Vec<E::Output>>,
+    ) {
         if let Some(txn_commit_listener) = &self.transaction_commit_hook {
             let txn_output = last_input_output.txn_output(txn_idx).unwrap();
             let execution_status = txn_output.output_status();
 
             match execution_status {
-                ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+                ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output, _) => {
                     txn_commit_listener.on_transaction_committed(txn_idx, output);
                 },
                 ExecutionStatus::Abort(_) => {
@@ -915,8 +1229,10 @@ where
 
         let mut final_results = final_results.acquire();
         match last_input_output.take_output(txn_idx) {
-            ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
-                final_results[txn_idx as usize] = t;
+            ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
+                // The outputs are recorded in the order of the transactions.
+                assert_eq!(final_results.len(), txn_idx as usize);
+                final_results.push(t);
             },
             ExecutionStatus::Abort(_) => (),
             ExecutionStatus::DirectWriteSetTransactionNotCapableError => {
@@ -929,13 +1245,13 @@ where
                 panic!("Cannot be materializing with {}", msg);
             },
         };
-        Ok(())
     }
 
     fn worker_loop(
         &self,
         executor_arguments: &E::Argument,
         block: &[T],
+        hinted_writes: &[Vec<T::Key>],
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
         scheduler: &Scheduler,
@@ -947,6 +1263,7 @@ where
             FeeStatement,
             Vec<FeeStatement>,
             Option<Error<E::Error>>,
+            BlockCutState,
         )>,
         final_results: &ExplicitSyncWrapper<Vec<E::Output>>,
     ) -> ::std::result::Result<(), PanicOr<IntentionalFallbackToSequential>> {
@@ -969,9 +1286,17 @@ where
                         shared_counter,
                         last_input_output,
                         base_view,
-                        final_results,
                     )?;
                 }
+                // Commit hooks are dispatched in the order of txn indices, by the worker which
+                // holds the lock. A slow hook thus only delays this worker, while the others keep
+                // executing, validating, committing and materializing txns.
+                while scheduler.should_dispatch_commit_hooks() {
+                    while let Some(txn_idx) = scheduler.next_commit_hook() {
+                        self.dispatch_commit_hook(txn_idx, last_input_output, final_results);
+                    }
+                    scheduler.dispatching_commit_hooks_mark_done();
+                }
                 Ok(())
             };
 
@@ -979,7 +1304,7 @@ where
             // Priorotize committing validated transactions
             while scheduler.should_coordinate_commits() {
                 self.prepare_and_queue_commit_ready_txns(
-                    self.maybe_block_gas_limit,
+                    self.config.maybe_block_gas_limit,
                     scheduler,
                     versioned_cache,
                     &mut scheduler_task,
@@ -1014,9 +1339,15 @@ where
                     incarnation,
                     ExecutionTaskType::Execution,
                 ) => {
+                    // Estimates of the hinted writes are only recorded for the first incarnation.
+                    let hinted_writes = match hinted_writes.get(txn_idx as usize) {
+                        Some(keys) if incarnation == 0 => keys.as_slice(),
+                        _ => &[],
+                    };
                     let updates_outside = Self::execute(
                         txn_idx,
                         incarnation,
+                        hinted_writes,
                         block,
                         last_input_output,
                         versioned_cache,
@@ -1055,7 +1386,7 @@ where
         executor_initial_arguments: E::Argument,
         signature_verified_block: &[T],
         base_view: &S,
-    ) -> Result<Vec<E::Output>, E::Error> {
+    ) -> Result<BlockOutput<E::Output>, E::Error> {
         let _timer = PARALLEL_EXECUTION_SECONDS.start_timer();
         // Using parallel execution with 1 thread currently will not work as it
         // will only have a coordinator role but no workers for rolling commit.
@@ -1068,7 +1399,7 @@ where
         let shared_counter = AtomicU32::new(start_shared_counter);
 
         if signature_verified_block.is_empty() {
-            return Ok(vec![]);
+            return Ok(BlockOutput::new(vec![], FeeStatement::zero(), 0, None));
         }
 
         let num_txns = signature_verified_block.len();
@@ -1077,20 +1408,34 @@ where
             FeeStatement::zero(),
             Vec::<FeeStatement>::with_capacity(num_txns),
             None,
+            BlockCutState::default(),
         ));
 
         let final_results = ExplicitSyncWrapper::new(Vec::with_capacity(num_txns));
 
-        {
-            final_results
-                .acquire()
-                .resize_with(num_txns, E::Output::skip_output);
-        }
-
         let num_txns = num_txns as u32;
 
         let last_input_output = TxnLastInputOutput::new(num_txns);
-        let scheduler = Scheduler::new(num_txns);
+        let mut scheduler = Scheduler::new(num_txns);
+
+        let access_hints =
+            self.infer_access_hints(executor_initial_arguments, signature_verified_block);
+        let hinted_writes = match access_hints {
+            Some((hinted_writes, hinted_dependencies)) => {
+                // Readers of the hinted writes wait for the first incarnation of the writer,
+                // instead of reading a prior value and being aborted when validated.
+                for (txn_idx, keys) in hinted_writes.iter().enumerate() {
+                    for key in keys {
+                        versioned_cache
+                            .data()
+                            .add_estimate(key.clone(), txn_idx as TxnIndex);
+                    }
+                }
+                scheduler = scheduler.with_hinted_dependencies(hinted_dependencies);
+                hinted_writes
+            },
+            None => vec![],
+        };
 
         let timer = RAYON_EXECUTION_SECONDS.start_timer();
         self.executor_thread_pool.scope(|s| {
@@ -1099,6 +1444,7 @@ where
                     if let Err(e) = self.worker_loop(
                         &executor_initial_arguments,
                         signature_verified_block,
+                        &hinted_writes,
                         &last_input_output,
                         &versioned_cache,
                         &scheduler,
@@ -1110,7 +1456,8 @@ where
                     ) {
                         if scheduler.halt() {
                             let mut shared_commit_state_guard = shared_commit_state.acquire();
-                            let (_, _, maybe_error) = shared_commit_state_guard.dereference_mut();
+                            let (_, _, maybe_error, _) =
+                                shared_commit_state_guard.dereference_mut();
                             *maybe_error = Some(Error::FallbackToSequential(e));
                         }
                     }
@@ -1120,10 +1467,23 @@ where
         drop(timer);
         // Explicit async drops.
         DEFAULT_DROPPER.schedule_drop((last_input_output, scheduler, versioned_cache));
-        let (_, _, maybe_error) = shared_commit_state.into_inner();
+        let (accumulated_fee_statement, _, maybe_error, block_cut) =
+            shared_commit_state.into_inner();
         match maybe_error {
             Some(err) => Err(err),
-            None => Ok(final_results.into_inner()),
+            None => {
+                let mut final_results = final_results.into_inner();
+                // The transactions after the cut are not committed, and get skip outputs.
+                if let Some((_, reason)) = block_cut.cut {
+                    final_results.resize_with(num_txns as usize, || E::Output::skip_output(reason));
+                }
+                Ok(BlockOutput::new(
+                    final_results,
+                    accumulated_fee_statement,
+                    block_cut.accumulated_output_size,
+                    block_cut.cut,
+                ))
+            },
         }
     }
 
@@ -1211,7 +1571,7 @@ where
         signature_verified_block: &[T],
         base_view: &S,
         dynamic_change_set_optimizations_enabled: bool,
-    ) -> Result<Vec<E::Output>, E::Error> {
+    ) -> Result<BlockOutput<E::Output>, E::Error> {
         let num_txns = signature_verified_block.len();
         let init_timer = VM_INIT_SECONDS.start_timer();
         let executor = E::init(executor_arguments);
@@ -1222,29 +1582,67 @@ where
         let unsync_map = UnsyncMap::new();
         let mut ret = Vec::with_capacity(num_txns);
         let mut accumulated_fee_statement = FeeStatement::zero();
+        let mut accumulated_output_size = 0;
+        let mut block_cut = None;
 
         for (idx, txn) in signature_verified_block.iter().enumerate() {
+            let mut sequential_state = SequentialState::new(
+                &unsync_map,
+                start_counter,
+                &counter,
+                dynamic_change_set_optimizations_enabled,
+            );
+            if self.collect_read_summaries {
+                sequential_state = sequential_state.with_read_summary();
+            }
             let latest_view = LatestView::<T, S, X>::new(
                 base_view,
-                ViewState::Unsync(SequentialState::new(
-                    &unsync_map,
-                    start_counter,
-                    &counter,
-                    dynamic_change_set_optimizations_enabled,
-                )),
+                ViewState::Unsync(sequential_state),
                 idx as TxnIndex,
             );
-            let res = executor.execute_transaction(&latest_view, txn, idx as TxnIndex, true);
+            let context = ExecutionContext {
+                txn_idx: idx as TxnIndex,
+                incarnation: 0,
+                is_sequential: true,
+            };
+            let res = executor.execute_transaction(&latest_view, txn, context, true);
 
-            let must_skip = matches!(res, ExecutionStatus::SkipRest(_));
+            let skip_reason = match &res {
+                ExecutionStatus::SkipRest(_, reason) => Some(*reason),
+                _ => None,
+            };
             match res {
-                ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+                ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output, _) => {
                     assert_eq!(
                         output.aggregator_v1_delta_set().len(),
                         0,
                         "Sequential execution must materialize deltas"
                     );
 
+                    // When the output of the txn would make the accumulated output size exceed
+                    // the per-block output size limit, cut the block before the txn.
+                    let output_approx_size = output.output_approx_size();
+                    if self.exceeds_output_size_limit(
+                        idx as TxnIndex,
+                        accumulated_output_size,
+                        output_approx_size,
+                    ) {
+                        counters::EXCEED_PER_BLOCK_OUTPUT_SIZE_LIMIT_COUNT
+                            .with_label_values(&[counters::Mode::SEQUENTIAL])
+                            .inc();
+                        info!(
+                            "[Execution]: Sequential execution cut the block before txn {} due to \
+                            accumulated output size {} + {} > PER_BLOCK_OUTPUT_SIZE_LIMIT {:?}",
+                            idx,
+                            accumulated_output_size,
+                            output_approx_size,
+                            self.config.maybe_block_output_size_limit,
+                        );
+                        block_cut = Some((idx as TxnIndex, SkipReason::OutputSizeLimit));
+                        break;
+                    }
+                    accumulated_output_size += output_approx_size;
+
                     // Calculating the accumulated gas costs of the committed txns.
                     let fee_statement = output.fee_statement();
                     accumulated_fee_statement.add_fee_statement(&fee_statement);
@@ -1340,6 +1738,9 @@ where
                         panic!("Incorrect use in sequential execution")
                     }
 
+                    if let Some(read_summary) = latest_view.take_read_summary() {
+                        output.set_read_summary(read_summary);
+                    }
                     if let Some(commit_hook) = &self.transaction_commit_hook {
                         commit_hook.on_transaction_committed(idx as TxnIndex, &output);
                     }
@@ -1371,12 +1772,16 @@ where
                     )));
                 },
             }
-            // When the txn is a SkipRest txn, halt sequential execution.
-            if must_skip {
+            // When the txn is a SkipRest txn, halt sequential execution. This takes precedence
+            // over the gas limit, which is not checked for the txn.
+            if let Some(reason) = skip_reason {
+                if idx + 1 < num_txns {
+                    block_cut = Some((idx as TxnIndex, reason));
+                }
                 break;
             }
 
-            if let Some(per_block_gas_limit) = self.maybe_block_gas_limit {
+            if let Some(per_block_gas_limit) = self.config.maybe_block_gas_limit {
                 // When the accumulated gas of the committed txns
                 // exceeds per_block_gas_limit, halt sequential execution.
                 let accumulated_non_storage_gas = accumulated_fee_statement.execution_gas_used()
@@ -1392,6 +1797,9 @@ where
                         per_block_gas_limit,
                         ret.len()
                     );
+                    if idx + 1 < num_txns {
+                        block_cut = Some((idx as TxnIndex, SkipReason::BlockGasLimit));
+                    }
                     break;
                 }
             }
@@ -1406,13 +1814,20 @@ where
                 ret.len(),
                 num_txns,
                 accumulated_non_storage_gas,
-                self.maybe_block_gas_limit,
+                self.config.maybe_block_gas_limit,
             );
         }
 
         counters::update_sequential_block_gas_counters(&accumulated_fee_statement, ret.len());
-        ret.resize_with(num_txns, E::Output::skip_output);
-        Ok(ret)
+        if let Some((_, reason)) = block_cut {
+            ret.resize_with(num_txns, || E::Output::skip_output(reason));
+        }
+        Ok(BlockOutput::new(
+            ret,
+            accumulated_fee_statement,
+            accumulated_output_size,
+            block_cut,
+        ))
     }
 
     pub fn execute_block(
@@ -1420,7 +1835,7 @@ where
         executor_arguments: E::Argument,
         signature_verified_block: &[T],
         base_view: &S,
-    ) -> Result<Vec<E::Output>, E::Error> {
+    ) -> Result<BlockOutput<E::Output>, E::Error> {
         let dynamic_change_set_optimizations_enabled = signature_verified_block.len() != 1
             || E::is_transaction_dynamic_change_set_capable(&signature_verified_block[0]);
 
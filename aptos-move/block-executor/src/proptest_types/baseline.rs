@@ -109,7 +109,7 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
                     status = BaselineStatus::Aborted;
                     break;
                 },
-                MockTransaction::SkipRest => {
+                MockTransaction::SkipRest(_) => {
                     // In executor, SkipRest skips from the next index. Test assumes it's an empty
                     // transaction, so create a successful empty reads and deltas.
                     read_values.push(Ok(vec![]));
@@ -121,6 +121,7 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
                 MockTransaction::Write {
                     incarnation_counter,
                     incarnation_behaviors,
+                    ..
                 } => {
                     // Determine the behavior of the latest incarnation of the transaction. The index
                     // is based on the value of the incarnation counter prior to the fetch_add during
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    executor::BlockExecutor,
+    executor::{BlockExecutor, BlockOutput},
     proptest_types::{
         baseline::BaselineOutput,
         types::{
@@ -29,6 +29,7 @@ pub struct Bencher<K, V, E> {
     transaction_size: usize,
     transaction_gen_param: TransactionGenParams,
     universe_size: usize,
+    access_hints: bool,
     phantom: PhantomData<(K, V, E)>,
 }
 
@@ -38,6 +39,7 @@ pub(crate) struct BencherState<
 > {
     transactions: Vec<MockTransaction<KeyType<K>, E>>,
     baseline_output: BaselineOutput<KeyType<K>>,
+    access_hints: bool,
 }
 
 impl<K, V, E> Bencher<K, V, E>
@@ -52,10 +54,25 @@ where
             transaction_size,
             transaction_gen_param: TransactionGenParams::default(),
             universe_size,
+            access_hints: false,
             phantom: PhantomData,
         }
     }
 
+    /// A bencher for transactions that, like transfers, read and write a couple of keys.
+    pub fn new_transfers(transaction_size: usize, universe_size: usize) -> Self {
+        Self {
+            transaction_gen_param: TransactionGenParams::new_transfers(),
+            ..Self::new(transaction_size, universe_size)
+        }
+    }
+
+    /// Makes the executor infer the accesses of each transaction (from its behavior).
+    pub fn with_access_hints(mut self) -> Self {
+        self.access_hints = true;
+        self
+    }
+
     pub fn bench(&self, key_strategy: &impl Strategy<Value = K>, bencher: &mut CBencher) {
         bencher.iter_batched(
             || {
@@ -63,6 +80,7 @@ where
                     vec(key_strategy, self.universe_size),
                     self.transaction_size,
                     self.transaction_gen_param,
+                    self.access_hints,
                 )
             },
             |state| state.run(),
@@ -85,6 +103,7 @@ where
         universe_strategy: impl Strategy<Value = Vec<K>>,
         num_transactions: usize,
         transaction_params: TransactionGenParams,
+        access_hints: bool,
     ) -> Self {
         let mut runner = TestRunner::default();
         let key_universe = universe_strategy
@@ -102,7 +121,15 @@ where
 
         let transactions: Vec<_> = transaction_gens
             .into_iter()
-            .map(|txn_gen| txn_gen.materialize(&key_universe, (false, false)))
+            .map(|txn_gen| {
+                let txn = txn_gen.materialize(&key_universe, (false, false));
+                if !access_hints {
+                    return txn;
+                }
+                let behaviors = txn.into_behaviors();
+                let accesses = behaviors[0].accesses();
+                MockTransaction::from_behaviors(behaviors).with_access_hints(accesses)
+            })
             .collect();
 
         let baseline_output = BaselineOutput::generate(&transactions, None);
@@ -110,6 +137,7 @@ where
         Self {
             transactions,
             baseline_output,
+            access_hints,
         }
     }
 
@@ -132,7 +160,9 @@ where
             NoOpTransactionCommitHook<MockOutput<KeyType<K>, E>, usize>,
             ExecutableTestType,
         >::new(num_cpus::get(), executor_thread_pool, None, None)
-        .execute_transactions_parallel((), &self.transactions, &data_view);
+        .with_access_hints(self.access_hints)
+        .execute_transactions_parallel((), &self.transactions, &data_view)
+        .map(BlockOutput::into_transaction_outputs);
 
         self.baseline_output.assert_output(&output);
     }
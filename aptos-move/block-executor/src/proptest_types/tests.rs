@@ -4,7 +4,7 @@
 
 use crate::{
     errors::{Error, IntentionalFallbackToSequential},
-    executor::BlockExecutor,
+    executor::{BlockExecutor, BlockOutput},
     proptest_types::{
         baseline::BaselineOutput,
         types::{
@@ -54,7 +54,7 @@ fn run_transactions<K, V, E>(
         *transactions.get_mut(i.index(length)).unwrap() = MockTransaction::Abort;
     }
     for i in skip_rest_transactions {
-        *transactions.get_mut(i.index(length)).unwrap() = MockTransaction::SkipRest;
+        *transactions.get_mut(i.index(length)).unwrap() = MockTransaction::SkipRest(0);
     }
 
     let data_view = EmptyDataView::<KeyType<K>> {
@@ -81,7 +81,8 @@ fn run_transactions<K, V, E>(
             maybe_block_gas_limit,
             None,
         )
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view)
+        .map(BlockOutput::into_transaction_outputs);
 
         if module_access.0 && module_access.1 {
             assert_eq!(
@@ -221,7 +222,8 @@ fn deltas_writes_mixed_with_block_gas_limit(num_txns: usize, maybe_block_gas_lim
             maybe_block_gas_limit,
             None,
         )
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view)
+        .map(BlockOutput::into_transaction_outputs);
 
         BaselineOutput::generate(&transactions, maybe_block_gas_limit).assert_output(&output);
     }
@@ -272,7 +274,8 @@ fn deltas_resolver_with_block_gas_limit(num_txns: usize, maybe_block_gas_limit:
             maybe_block_gas_limit,
             None,
         )
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view)
+        .map(BlockOutput::into_transaction_outputs);
 
         BaselineOutput::generate(&transactions, maybe_block_gas_limit).assert_output(&output);
     }
@@ -387,8 +390,8 @@ fn publishing_fixed_params_with_block_gas_limit(
     let w_index = indices[0].index(num_txns);
     match transactions.get_mut(w_index).unwrap() {
         MockTransaction::Write {
-            incarnation_counter: _,
             incarnation_behaviors,
+            ..
         } => {
             incarnation_behaviors.iter_mut().for_each(|behavior| {
                 assert!(!behavior.writes.is_empty());
@@ -428,15 +431,16 @@ fn publishing_fixed_params_with_block_gas_limit(
         maybe_block_gas_limit,
         None,
     )
-    .execute_transactions_parallel((), &transactions, &data_view);
+    .execute_transactions_parallel((), &transactions, &data_view)
+    .map(BlockOutput::into_transaction_outputs);
     assert_ok!(output);
 
     // Adjust the reads of txn indices[2] to contain module read to key 42.
     let r_index = indices[2].index(num_txns);
     match transactions.get_mut(r_index).unwrap() {
         MockTransaction::Write {
-            incarnation_counter: _,
             incarnation_behaviors,
+            ..
         } => {
             incarnation_behaviors.iter_mut().for_each(|behavior| {
                 assert!(!behavior.reads.is_empty());
@@ -471,7 +475,8 @@ fn publishing_fixed_params_with_block_gas_limit(
             Some(max(w_index, r_index) as u64 * MAX_GAS_PER_TXN + 1),
             None,
         ) // Ensure enough gas limit to commit the module txns (4 is maximum gas per txn)
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view)
+        .map(BlockOutput::into_transaction_outputs);
 
         assert_eq!(
             output.unwrap_err(),
@@ -551,7 +556,8 @@ fn non_empty_group(
             NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>,
             ExecutableTestType,
         >::new(num_cpus::get(), executor_thread_pool.clone(), None, None)
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view)
+        .map(BlockOutput::into_transaction_outputs);
 
         BaselineOutput::generate(&transactions, None).assert_output(&output);
     }
@@ -564,7 +570,8 @@ fn non_empty_group(
             NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>,
             ExecutableTestType,
         >::new(num_cpus::get(), executor_thread_pool.clone(), None, None)
-        .execute_transactions_sequential((), &transactions, &data_view, true);
+        .execute_transactions_sequential((), &transactions, &data_view, true)
+        .map(BlockOutput::into_transaction_outputs);
         // TODO: test dynamic disabled as well.
 
         BaselineOutput::generate(&transactions, None).assert_output(&output);
@@ -4,14 +4,16 @@
 
 use crate::{
     explicit_sync_wrapper::ExplicitSyncWrapper,
-    task::{ExecutionStatus, ExecutorTask, TransactionOutput},
+    task::{
+        Accesses, ExecutionContext, ExecutionStatus, ExecutorTask, ReadSummary, SkipReason,
+        TransactionOutput,
+    },
 };
 use aptos_aggregator::{
     delayed_change::DelayedChange,
     delta_change_set::{delta_add, delta_sub, serialize, DeltaOp},
     types::DelayedFieldID,
 };
-use aptos_mvhashmap::types::TxnIndex;
 use aptos_state_view::{StateViewId, TStateView};
 use aptos_types::{
     access_path::AccessPath,
@@ -395,6 +397,28 @@ impl<K, E> MockIncarnation<K, E> {
     }
 }
 
+impl<K: Clone, E> MockIncarnation<K, E> {
+    /// The accesses of the incarnation, as they would be inferred before executing it.
+    pub(crate) fn accesses(&self) -> Accesses<K> {
+        Accesses {
+            keys_read: self
+                .reads
+                .iter()
+                .cloned()
+                .chain(self.group_reads.iter().map(|(key, _)| key.clone()))
+                .chain(self.group_sizes.iter().cloned())
+                .collect(),
+            keys_written: self
+                .writes
+                .iter()
+                .map(|(key, _)| key.clone())
+                .chain(self.group_writes.iter().map(|(key, _)| key.clone()))
+                .chain(self.deltas.iter().map(|(key, _)| key.clone()))
+                .collect(),
+        }
+    }
+}
+
 /// A mock transaction that could be used to test the correctness and throughput of the system.
 /// To test transaction behavior where reads and writes might be dynamic (depend on previously
 /// read values), different read and writes sets are generated and used depending on the incarnation
@@ -410,9 +434,12 @@ pub(crate) enum MockTransaction<K, E> {
         /// A vector of mock behaviors prescribed for each incarnation of the transaction, chosen
         /// round robin depending on the incarnation counter value).
         incarnation_behaviors: Vec<MockIncarnation<K, E>>,
+        /// Accesses inferred for the transaction by the mock executor, which need not match
+        /// the behaviors.
+        access_hints: Option<Accesses<K>>,
     },
-    /// Skip the execution of trailing transactions.
-    SkipRest,
+    /// Skip the execution of trailing transactions, with the given execution gas.
+    SkipRest(u64),
     /// Abort the execution.
     Abort,
 }
@@ -422,6 +449,7 @@ impl<K, E> MockTransaction<K, E> {
         Self::Write {
             incarnation_counter: Arc::new(AtomicUsize::new(0)),
             incarnation_behaviors: vec![behavior],
+            access_hints: None,
         }
     }
 
@@ -429,16 +457,26 @@ impl<K, E> MockTransaction<K, E> {
         Self::Write {
             incarnation_counter: Arc::new(AtomicUsize::new(0)),
             incarnation_behaviors: behaviors,
+            access_hints: None,
         }
     }
 
+    /// Sets the accesses inferred for the transaction, which is a no-op for transactions
+    /// without behaviors.
+    pub(crate) fn with_access_hints(mut self, accesses: Accesses<K>) -> Self {
+        if let Self::Write { access_hints, .. } = &mut self {
+            *access_hints = Some(accesses);
+        }
+        self
+    }
+
     pub(crate) fn into_behaviors(self) -> Vec<MockIncarnation<K, E>> {
         match self {
             Self::Write {
                 incarnation_behaviors,
                 ..
             } => incarnation_behaviors,
-            Self::SkipRest => unreachable!("SkipRest does not contain incarnation behaviors"),
+            Self::SkipRest(_) => unreachable!("SkipRest does not contain incarnation behaviors"),
             Self::Abort => unreachable!("Abort does not contain incarnation behaviors"),
         }
     }
@@ -465,6 +503,15 @@ impl TransactionGenParams {
             incarnation_alternatives: 5,
         }
     }
+
+    /// Transactions that, like transfers, read and write a couple of keys.
+    pub fn new_transfers() -> Self {
+        TransactionGenParams {
+            read_size: 3,
+            output_size: 3,
+            incarnation_alternatives: 1,
+        }
+    }
 }
 
 impl Default for TransactionGenParams {
@@ -839,13 +886,14 @@ where
         view: &(impl TExecutorView<K, u32, MoveTypeLayout, DelayedFieldID, ValueType>
               + TResourceGroupView<GroupKey = K, ResourceTag = u32, Layout = MoveTypeLayout>),
         txn: &Self::Txn,
-        txn_idx: TxnIndex,
+        context: ExecutionContext,
         _materialize_deltas: bool,
     ) -> ExecutionStatus<Self::Output, Self::Error> {
         match txn {
             MockTransaction::Write {
                 incarnation_counter,
                 incarnation_behaviors,
+                ..
             } => {
                 // Use incarnation counter value as an index to determine the read-
                 // and write-sets of the execution. Increment incarnation counter to
@@ -958,16 +1006,32 @@ where
                     read_group_sizes,
                     materialized_delta_writes: OnceCell::new(),
                     total_gas: behavior.gas,
+                    read_summary: OnceCell::new(),
+                    skip_reason: None,
                 })
             },
-            MockTransaction::SkipRest => ExecutionStatus::SkipRest(MockOutput::skip_output()),
-            MockTransaction::Abort => ExecutionStatus::Abort(txn_idx as usize),
+            MockTransaction::SkipRest(gas) => ExecutionStatus::SkipRest(
+                MockOutput {
+                    total_gas: *gas,
+                    skip_reason: None,
+                    ..MockOutput::skip_output(SkipReason::ExplicitHalt)
+                },
+                SkipReason::ExplicitHalt,
+            ),
+            MockTransaction::Abort => ExecutionStatus::Abort(context.txn_idx as usize),
         }
     }
 
     fn is_transaction_dynamic_change_set_capable(_txn: &Self::Txn) -> bool {
         true
     }
+
+    fn infer_accesses(&self, txn: &Self::Txn) -> Option<Accesses<K>> {
+        match txn {
+            MockTransaction::Write { access_hints, .. } => access_hints.clone(),
+            MockTransaction::SkipRest(_) | MockTransaction::Abort => None,
+        }
+    }
 }
 
 pub(crate) fn raw_metadata(v: u64) -> StateValueMetadataKind {
@@ -987,6 +1051,8 @@ pub(crate) struct MockOutput<K, E> {
     pub(crate) read_group_sizes: Vec<(K, u64)>,
     pub(crate) materialized_delta_writes: OnceCell<Vec<(K, WriteOp)>>,
     pub(crate) total_gas: u64,
+    pub(crate) read_summary: OnceCell<ReadSummary<K, u32, DelayedFieldID>>,
+    pub(crate) skip_reason: Option<SkipReason>,
 }
 
 impl<K, E> TransactionOutput for MockOutput<K, E>
@@ -1080,7 +1146,7 @@ where
             .collect()
     }
 
-    fn skip_output() -> Self {
+    fn skip_output(reason: SkipReason) -> Self {
         Self {
             writes: vec![],
             group_writes: vec![],
@@ -1090,9 +1156,15 @@ where
             read_group_sizes: vec![],
             materialized_delta_writes: OnceCell::new(),
             total_gas: 0,
+            read_summary: OnceCell::new(),
+            skip_reason: Some(reason),
         }
     }
 
+    fn skip_reason(&self) -> Option<SkipReason> {
+        self.skip_reason
+    }
+
     fn incorporate_materialized_txn_output(
         &self,
         aggregator_v1_writes: Vec<(<Self::Txn as Transaction>::Key, WriteOp)>,
@@ -1128,6 +1200,43 @@ where
             0,
         )
     }
+
+    fn output_approx_size(&self) -> u64 {
+        // The mock keys are not serialized, so they are counted by their in-memory size.
+        let key_size = std::mem::size_of::<K>() as u64;
+        let write_size: u64 = self
+            .writes
+            .iter()
+            .filter_map(|(_, value)| value.bytes().map(|bytes| key_size + bytes.len() as u64))
+            .sum();
+        // Deltas count as a u128 value, whether or not they were materialized.
+        let delta_size = self.deltas.len() as u64 * (key_size + std::mem::size_of::<u128>() as u64);
+        let group_write_size: u64 = self
+            .group_writes
+            .iter()
+            .map(|(_, _, inner_ops)| {
+                key_size
+                    + inner_ops
+                        .values()
+                        .filter_map(|value| value.bytes().map(|bytes| bytes.len() as u64))
+                        .sum::<u64>()
+            })
+            .sum();
+        let event_size: u64 = self
+            .events
+            .iter()
+            .map(|event| event.get_event_data().len() as u64)
+            .sum();
+        write_size + delta_size + group_write_size + event_size
+    }
+
+    fn set_read_summary(&self, read_summary: ReadSummary<K, u32, DelayedFieldID>) {
+        assert_ok!(self.read_summary.set(read_summary));
+    }
+
+    fn read_summary(&self) -> Option<&ReadSummary<K, u32, DelayedFieldID>> {
+        self.read_summary.get()
+    }
 }
 
 #[derive(Clone, Debug)]
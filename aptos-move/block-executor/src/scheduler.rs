@@ -277,6 +277,25 @@ pub struct Scheduler {
     queueing_commits_lock: CachePadded<ArmedLock>,
 
     commit_queue: ConcurrentQueue<u32>,
+
+    /// An index i maps to whether transaction i is committed and its output materialized, so
+    /// that its commit hook can be dispatched. A single index does not suffice, as workers
+    /// materialize the transactions popped from the commit queue concurrently, so they may
+    /// finish out of order. Each flag is written once, so the flags are not padded.
+    materialized: Vec<AtomicBool>,
+    /// The next transaction whose commit hook is to be dispatched. Only accessed while holding
+    /// the dispatching_commit_hooks_lock, so the hooks are dispatched in the order of indices.
+    next_commit_hook_idx: CachePadded<AtomicU32>,
+
+    dispatching_commit_hooks_lock: CachePadded<ArmedLock>,
+
+    /// An index i maps to the closest lower transaction that is inferred to write to what
+    /// transaction i is inferred to read, if any. Empty if there are no inferred accesses.
+    hinted_dependencies: Vec<Option<TxnIndex>>,
+    /// An index i maps to indices of transactions whose first execution was deferred until
+    /// transaction i finishes its first execution, due to hinted dependencies. Unlike for
+    /// txn_dependency, the status of the deferred transactions is not changed.
+    hinted_dependents: Vec<CachePadded<Mutex<Vec<TxnIndex>>>>,
 }
 
 /// Public Interfaces for the Scheduler
@@ -305,9 +324,28 @@ impl Scheduler {
             has_halted: CachePadded::new(AtomicBool::new(false)),
             queueing_commits_lock: CachePadded::new(ArmedLock::new()),
             commit_queue: ConcurrentQueue::<u32>::bounded(num_txns as usize),
+            materialized: (0..num_txns).map(|_| AtomicBool::new(false)).collect(),
+            next_commit_hook_idx: CachePadded::new(AtomicU32::new(0)),
+            dispatching_commit_hooks_lock: CachePadded::new(ArmedLock::new()),
+            hinted_dependencies: vec![],
+            hinted_dependents: vec![],
         }
     }
 
+    /// Sets the dependencies of transactions inferred before execution, i.e. for each index,
+    /// the closest lower transaction expected to write to what it reads. The first execution
+    /// of a transaction is deferred while its hinted dependency has not been executed, so that
+    /// the transactions whose hinted reads are satisfied are executed first.
+    pub fn with_hinted_dependencies(mut self, hinted_dependencies: Vec<Option<TxnIndex>>) -> Self {
+        assert_eq!(hinted_dependencies.len(), self.num_txns as usize);
+
+        self.hinted_dependents = (0..self.num_txns)
+            .map(|_| CachePadded::new(Mutex::new(Vec::new())))
+            .collect();
+        self.hinted_dependencies = hinted_dependencies;
+        self
+    }
+
     pub fn num_txns(&self) -> TxnIndex {
         self.num_txns
     }
@@ -334,6 +372,35 @@ impl Scheduler {
         self.queueing_commits_lock.try_lock()
     }
 
+    /// Marks the committed transaction as materialized, so its commit hook can be dispatched
+    /// once the hooks of all the previous transactions are.
+    pub fn finish_materialization(&self, txn_idx: TxnIndex) {
+        self.materialized[txn_idx as usize].store(true, Ordering::Release);
+        self.dispatching_commit_hooks_lock.arm();
+    }
+
+    pub fn should_dispatch_commit_hooks(&self) -> bool {
+        self.dispatching_commit_hooks_lock.try_lock()
+    }
+
+    pub fn dispatching_commit_hooks_mark_done(&self) {
+        self.dispatching_commit_hooks_lock.unlock()
+    }
+
+    /// Returns the index of the next transaction whose commit hook is to be dispatched, if it
+    /// is materialized. Must only be called after should_dispatch_commit_hooks returned true,
+    /// and before dispatching_commit_hooks_mark_done.
+    pub fn next_commit_hook(&self) -> Option<TxnIndex> {
+        let txn_idx = self.next_commit_hook_idx.load(Ordering::Relaxed);
+        if txn_idx < self.num_txns && self.materialized[txn_idx as usize].load(Ordering::Acquire) {
+            self.next_commit_hook_idx
+                .store(txn_idx + 1, Ordering::Relaxed);
+            Some(txn_idx)
+        } else {
+            None
+        }
+    }
+
     /// If successful, returns Some(TxnIndex), the index of committed transaction.
     pub fn try_commit(&self) -> Option<(TxnIndex, Incarnation)> {
         let mut commit_state = self.commit_state.acquire();
@@ -478,6 +545,8 @@ impl Scheduler {
 
                 dep
             })
+            // Transactions deferred due to hints are still ready, and only need to be reached.
+            .chain(self.take_hinted_dependents(txn_idx))
             .min();
         if let Some(execution_target_idx) = min_dep {
             // Decrease the execution index as necessary to ensure resolved dependencies
@@ -847,7 +916,7 @@ impl Scheduler {
     fn try_execute_next_version(&self) -> Option<(TxnIndex, Incarnation, ExecutionTaskType)> {
         let idx_to_execute = self.execution_idx.fetch_add(1, Ordering::SeqCst);
 
-        if idx_to_execute >= self.num_txns {
+        if idx_to_execute >= self.num_txns || self.defer_to_hinted_dependency(idx_to_execute) {
             return None;
         }
 
@@ -859,6 +928,46 @@ impl Scheduler {
             })
     }
 
+    /// If the transaction is ready for its first execution, and has a hinted dependency that
+    /// has not been executed, defers the transaction by recording it among the hinted
+    /// dependents and returns true. The execution index is decreased for the hinted dependents
+    /// once the dependency finishes execution, at which point they are no longer deferred.
+    fn defer_to_hinted_dependency(&self, txn_idx: TxnIndex) -> bool {
+        let Some(dep_txn_idx) = self
+            .hinted_dependencies
+            .get(txn_idx as usize)
+            .copied()
+            .flatten()
+        else {
+            return false;
+        };
+
+        // Hold the lock while checking the status of dep_txn_idx, same as in wait_for_dependency,
+        // so that the deferred transaction is not lost if the dependency finishes execution.
+        let mut stored_deps = self.hinted_dependents[dep_txn_idx as usize].lock();
+        if !self.never_executed(dep_txn_idx)
+            || !matches!(
+                *self.txn_status[txn_idx as usize].0.read(),
+                ExecutionStatus::Ready(0, ExecutionTaskType::Execution)
+            )
+        {
+            return false;
+        }
+
+        if !stored_deps.contains(&txn_idx) {
+            stored_deps.push(txn_idx);
+        }
+        true
+    }
+
+    /// Takes the transactions deferred until the first execution of the transaction.
+    fn take_hinted_dependents(&self, txn_idx: TxnIndex) -> Vec<TxnIndex> {
+        match self.hinted_dependents.get(txn_idx as usize) {
+            Some(stored_deps) => std::mem::take(&mut stored_deps.lock()),
+            None => vec![],
+        }
+    }
+
     /// Put a transaction in a suspended state, with a condition variable that can be
     /// used to wake it up after the dependency is resolved.
     /// Return true when the txn is successfully suspended.
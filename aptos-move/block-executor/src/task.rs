@@ -3,14 +3,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_aggregator::{delayed_change::DelayedChange, delta_change_set::DeltaOp};
-use aptos_mvhashmap::types::TxnIndex;
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
 use aptos_types::{
     fee_statement::FeeStatement, transaction::BlockExecutableTransaction as Transaction,
     write_set::WriteOp,
 };
 use aptos_vm_types::resolver::{TExecutorView, TResourceGroupView};
+use derivative::Derivative;
 use move_core_types::value::MoveTypeLayout;
-use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    sync::Arc,
+};
 
 /// The execution result of a transaction
 #[derive(Debug)]
@@ -21,8 +26,8 @@ pub enum ExecutionStatus<O, E> {
     /// the error back to the caller.
     Abort(E),
     /// Transaction was executed successfully, but will skip the execution of the trailing
-    /// transactions in the list
-    SkipRest(O),
+    /// transactions in the list, for the given reason.
+    SkipRest(O, SkipReason),
     /// There is a DirectWriteTransaction with resolver not capable to handle it.
     DirectWriteSetTransactionNotCapableError,
     /// Transaction detected that it is in inconsistent state due to speculative
@@ -33,12 +38,69 @@ pub enum ExecutionStatus<O, E> {
     DelayedFieldsCodeInvariantError(String),
 }
 
+/// The reason for which the trailing transactions of a block are skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The transaction changed the epoch, so the rest of the block must be executed with the
+    /// new configuration.
+    Reconfiguration,
+    /// The accumulated execution and io gas of the committed transactions reached the per-block
+    /// gas limit.
+    BlockGasLimit,
+    /// The output of a transaction would have made the accumulated output size of the committed
+    /// transactions exceed the per-block output size limit. The transaction is skipped as well.
+    OutputSizeLimit,
+    /// The transaction explicitly requested to halt the execution of the block.
+    ExplicitHalt,
+}
+
+impl SkipReason {
+    /// A short name of the reason, e.g. to label metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::Reconfiguration => "reconfiguration",
+            SkipReason::BlockGasLimit => "block_gas_limit",
+            SkipReason::OutputSizeLimit => "output_size_limit",
+            SkipReason::ExplicitHalt => "explicit_halt",
+        }
+    }
+}
+
+/// The context in which a transaction is executed by the block executor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecutionContext {
+    /// Index of the transaction in the block.
+    pub txn_idx: TxnIndex,
+    /// The number of prior executions of the transaction during parallel execution, i.e. 0 for
+    /// the first attempt and for sequential execution.
+    pub incarnation: Incarnation,
+    /// Whether the transaction is executed as a part of sequential execution.
+    pub is_sequential: bool,
+}
+
 /// Inference result of a transaction.
+#[derive(Clone, Debug)]
 pub struct Accesses<K> {
     pub keys_read: Vec<K>,
     pub keys_written: Vec<K>,
 }
 
+/// The reads of the committed incarnation of a transaction, as recorded by the view of the
+/// block executor (i.e. not as reported by the VM), with the key, tag and delayed field
+/// identifier types of the transaction.
+#[derive(Clone, Debug, Derivative, PartialEq, Eq)]
+#[derivative(Default(bound = ""))]
+pub struct ReadSummary<K, T, I> {
+    /// Keys of the resources read, including aggregator v1 state.
+    pub resources: BTreeSet<K>,
+    /// Resources read from resource groups, as pairs of the group key and the resource tag.
+    pub group_resources: BTreeSet<(K, T)>,
+    /// Keys of the modules read.
+    pub modules: BTreeSet<K>,
+    /// Identifiers of the delayed fields read.
+    pub delayed_fields: BTreeSet<I>,
+}
+
 /// Trait for single threaded transaction executor.
 // TODO: Sync should not be required. Sync is only introduced because this trait occurs as a phantom type of executor struct.
 pub trait ExecutorTask: Sync {
@@ -73,11 +135,24 @@ pub trait ExecutorTask: Sync {
             Layout = MoveTypeLayout,
         >),
         txn: &Self::Txn,
-        txn_idx: TxnIndex,
+        context: ExecutionContext,
         materialize_deltas: bool,
     ) -> ExecutionStatus<Self::Output, Self::Error>;
 
     fn is_transaction_dynamic_change_set_capable(txn: &Self::Txn) -> bool;
+
+    /// Infer the keys that the transaction is expected to read and write, before it is executed.
+    /// Used by parallel execution as hints, to make the readers of the inferred writes wait for
+    /// the first execution of the writer and to order the first executions accordingly. Hints
+    /// never affect the outputs: the actual reads and writes of an execution override them.
+    /// Returns no hints by default, which is what the AptosVM does as of now, so the hints are
+    /// only exercised by the mock executor tasks of the tests and benchmarks.
+    fn infer_accesses(
+        &self,
+        _txn: &Self::Txn,
+    ) -> Option<Accesses<<Self::Txn as Transaction>::Key>> {
+        None
+    }
 }
 
 /// Trait for execution result of a single transaction.
@@ -156,8 +231,12 @@ pub trait TransactionOutput: Send + Sync + Debug {
             .collect()
     }
 
-    /// Execution output for transactions that comes after SkipRest signal.
-    fn skip_output() -> Self;
+    /// Execution output for transactions that comes after SkipRest signal, recording the
+    /// reason for which they were skipped.
+    fn skip_output(reason: SkipReason) -> Self;
+
+    /// Return the reason for which the transaction was skipped, if this is a skip output.
+    fn skip_reason(&self) -> Option<SkipReason>;
 
     /// Will be called once per transaction when the output is ready to be committed.
     /// Ensures that any writes corresponding to materialized deltas and group updates
@@ -180,4 +259,32 @@ pub trait TransactionOutput: Send + Sync + Debug {
 
     /// Return the fee statement of the transaction.
     fn fee_statement(&self) -> FeeStatement;
+
+    /// Return the approximate size in bytes of the writes and events of the transaction,
+    /// which counts towards the per-block output size limit. The size must not depend on
+    /// whether the deltas were materialized, so that parallel and sequential execution cut
+    /// the block at the same transaction.
+    fn output_approx_size(&self) -> u64;
+
+    /// Will be called once per committed transaction if the block executor is configured to
+    /// collect read summaries, before the commit hook is invoked.
+    fn set_read_summary(
+        &self,
+        read_summary: ReadSummary<
+            <Self::Txn as Transaction>::Key,
+            <Self::Txn as Transaction>::Tag,
+            <Self::Txn as Transaction>::Identifier,
+        >,
+    );
+
+    /// Return the reads of the committed incarnation of the transaction, if collected.
+    fn read_summary(
+        &self,
+    ) -> Option<
+        &ReadSummary<
+            <Self::Txn as Transaction>::Key,
+            <Self::Txn as Transaction>::Tag,
+            <Self::Txn as Transaction>::Identifier,
+        >,
+    >;
 }
@@ -5,12 +5,22 @@ use crate::task::TransactionOutput;
 use aptos_mvhashmap::types::TxnIndex;
 
 /// An interface for listening to transaction commit events. The listener is called only once
-/// for each transaction commit.
+/// for each transaction commit, in the order of the transaction indices, as soon as the
+/// transaction is final within the block.
+///
+/// In parallel execution, the listener is called after the output of the transaction is
+/// materialized (i.e. after `incorporate_materialized_txn_output`), by one worker at a time.
+/// A slow listener only delays the worker calling it, while the others keep executing and
+/// committing transactions, but the listener is still called for all the committed transactions
+/// before the block execution returns. In sequential execution, the listener is called after
+/// each transaction.
 pub trait TransactionCommitHook: Send + Sync {
     type Output;
 
+    /// Called with the materialized output of a committed transaction.
     fn on_transaction_committed(&self, txn_idx: TxnIndex, output: &Self::Output);
 
+    /// Called for a committed transaction which aborted the execution of the block.
     fn on_execution_aborted(&self, txn_idx: TxnIndex);
 }
 
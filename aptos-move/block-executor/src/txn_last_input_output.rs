@@ -5,7 +5,7 @@ use crate::{
     captured_reads::CapturedReads,
     errors::{Error, IntentionalFallbackToSequential},
     explicit_sync_wrapper::ExplicitSyncWrapper,
-    task::{ExecutionStatus, TransactionOutput},
+    task::{ExecutionStatus, SkipReason, TransactionOutput},
 };
 use aptos_aggregator::types::PanicOr;
 use aptos_mvhashmap::types::{TxnIndex, ValueWithLayout};
@@ -128,7 +128,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         output: ExecutionStatus<O, Error<E>>,
     ) -> bool {
         let written_modules = match &output {
-            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output, _) => {
                 output.module_write_set()
             },
             ExecutionStatus::Abort(_)
@@ -171,22 +171,38 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
             .expect("[BlockSTM]: Execution output must be recorded after execution")
             .output_status
         {
-            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output, _) => {
                 Some(output.fee_statement())
             },
             _ => None,
         }
     }
 
-    /// Does a transaction at txn_idx have SkipRest or Abort status.
-    pub(crate) fn block_skips_rest_at_idx(&self, txn_idx: TxnIndex) -> bool {
-        matches!(
-            &self.outputs[txn_idx as usize]
-                .load_full()
-                .expect("[BlockSTM]: Execution output must be recorded after execution")
-                .output_status,
-            ExecutionStatus::SkipRest(_)
-        )
+    /// Returns the approximate size of the writes and events of the transaction.
+    pub(crate) fn output_approx_size(&self, txn_idx: TxnIndex) -> Option<u64> {
+        match &self.outputs[txn_idx as usize]
+            .load_full()
+            .expect("[BlockSTM]: Execution output must be recorded after execution")
+            .output_status
+        {
+            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output, _) => {
+                Some(output.output_approx_size())
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the reason for which the rest of the block is skipped after the transaction at
+    /// txn_idx, if it has SkipRest status.
+    pub(crate) fn block_skip_reason_at_idx(&self, txn_idx: TxnIndex) -> Option<SkipReason> {
+        match &self.outputs[txn_idx as usize]
+            .load_full()
+            .expect("[BlockSTM]: Execution output must be recorded after execution")
+            .output_status
+        {
+            ExecutionStatus::SkipRest(_, reason) => Some(*reason),
+            _ => None,
+        }
     }
 
     pub(crate) fn maybe_execution_error(&self, txn_idx: TxnIndex) -> Option<Error<E>> {
@@ -206,10 +222,25 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         None
     }
 
-    pub(crate) fn update_to_skip_rest(&self, txn_idx: TxnIndex) {
+    /// Records the summary of the reads of the committed incarnation in its output.
+    pub(crate) fn record_read_summary(&self, txn_idx: TxnIndex) {
+        let read_set = self
+            .read_set(txn_idx)
+            .expect("[BlockSTM]: Prior read-set must be recorded");
+        let txn_output = self.outputs[txn_idx as usize]
+            .load_full()
+            .expect("[BlockSTM]: Execution output must be recorded after execution");
+        if let ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output, _) =
+            &txn_output.output_status
+        {
+            output.set_read_summary(read_set.read_summary());
+        }
+    }
+
+    pub(crate) fn update_to_skip_rest(&self, txn_idx: TxnIndex, reason: SkipReason) {
         if let ExecutionStatus::Success(output) = self.take_output(txn_idx) {
             self.outputs[txn_idx as usize].store(Some(Arc::new(TxnOutput {
-                output_status: ExecutionStatus::SkipRest(output),
+                output_status: ExecutionStatus::SkipRest(output, reason),
             })));
         } else {
             unreachable!();
@@ -229,7 +260,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         self.outputs[txn_idx as usize]
             .load_full()
             .and_then(|txn_output| match &txn_output.output_status {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => Some(
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => Some(
                     t.resource_write_set()
                         .into_keys()
                         .chain(t.aggregator_v1_write_set().into_keys())
@@ -260,7 +291,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         self.outputs[txn_idx as usize]
             .load_full()
             .and_then(|txn_output| match &txn_output.output_status {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
                     Some(t.resource_write_set())
                 },
                 ExecutionStatus::Abort(_)
@@ -278,7 +309,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
             .load()
             .as_ref()
             .and_then(|txn_output| match &txn_output.output_status {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
                     Some(t.delayed_field_change_set().into_keys())
                 },
                 ExecutionStatus::Abort(_)
@@ -296,7 +327,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
             .load()
             .as_ref()
             .and_then(|txn_output| match &txn_output.output_status {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
                     Some(t.reads_needing_delayed_field_exchange())
                 },
                 ExecutionStatus::Abort(_)
@@ -314,7 +345,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
             .load()
             .as_ref()
             .and_then(|txn_output| match &txn_output.output_status {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
                     Some(t.group_reads_needing_delayed_field_exchange())
                 },
                 ExecutionStatus::Abort(_)
@@ -328,7 +359,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         self.outputs[txn_idx as usize].load().as_ref().map_or(
             vec![],
             |txn_output| match &txn_output.output_status {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
                     t.aggregator_v1_delta_set().into_keys().collect()
                 },
                 ExecutionStatus::Abort(_)
@@ -343,7 +374,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         self.outputs[txn_idx as usize].load().as_ref().map_or(
             vec![],
             |txn_output| match &txn_output.output_status {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
                     t.resource_group_metadata_ops()
                 },
                 ExecutionStatus::Abort(_)
@@ -361,7 +392,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         self.outputs[txn_idx as usize].load().as_ref().map_or(
             Box::new(empty::<(T::Event, Option<MoveTypeLayout>)>()),
             |txn_output| match &txn_output.output_status {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
                     let events = t.get_events();
                     Box::new(events.into_iter())
                 },
@@ -406,7 +437,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
             .expect("Output must exist")
             .output_status
         {
-            ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
+            ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t, _) => {
                 t.incorporate_materialized_txn_output(
                     delta_writes,
                     patched_resource_write_set,
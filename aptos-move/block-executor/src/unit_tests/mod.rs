@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    executor::BlockExecutor,
+    executor::{BlockExecutor, BlockExecutorConfig, BlockOutput},
     proptest_types::{
         baseline::BaselineOutput,
         types::{
@@ -14,25 +14,43 @@ use crate::{
     scheduler::{
         DependencyResult, ExecutionTaskType, Scheduler, SchedulerTask, TWaitForDependency,
     },
-    txn_commit_hook::NoOpTransactionCommitHook,
+    task::{
+        Accesses, ExecutionContext, ExecutionStatus, ExecutorTask, ReadSummary, SkipReason,
+        TransactionOutput,
+    },
+    txn_commit_hook::{NoOpTransactionCommitHook, TransactionCommitHook},
 };
 use aptos_aggregator::{
     bounded_math::SignedU128,
     delta_change_set::{delta_add, delta_sub, DeltaOp},
     delta_math::DeltaHistory,
+    types::DelayedFieldID,
 };
+use aptos_infallible::Mutex;
 use aptos_mvhashmap::types::TxnIndex;
+use aptos_state_view::{StateViewId, TStateView};
 use aptos_types::{
     contract_event::TransactionEvent,
     executable::{ExecutableTestType, ModulePath},
+    state_store::{state_storage_usage::StateStorageUsage, state_value::StateValue},
 };
-use claims::assert_matches;
+use aptos_vm_types::resolver::{TExecutorView, TResourceGroupView};
+use claims::{assert_matches, assert_ok};
+use move_core_types::value::MoveTypeLayout;
 use rand::{prelude::*, random};
 use std::{
-    cmp::min, collections::BTreeMap, fmt::Debug, hash::Hash, marker::PhantomData, sync::Arc,
+    cmp::min,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
 };
 
-// TODO: add unit test for block gas limit!
 fn run_and_assert<K, E>(transactions: Vec<MockTransaction<K, E>>)
 where
     K: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + Debug + 'static,
@@ -56,7 +74,8 @@ where
         NoOpTransactionCommitHook<MockOutput<K, E>, usize>,
         ExecutableTestType,
     >::new(num_cpus::get(), executor_thread_pool, None, None)
-    .execute_transactions_parallel((), &transactions, &data_view);
+    .execute_transactions_parallel((), &transactions, &data_view)
+    .map(BlockOutput::into_transaction_outputs);
 
     let baseline = BaselineOutput::generate(&transactions, None);
     baseline.assert_output(&output);
@@ -316,11 +335,601 @@ fn early_skips() {
             )));
         }
         // One transaction that triggers an abort
-        transactions.push(MockTransaction::SkipRest)
+        transactions.push(MockTransaction::SkipRest(0))
     }
     run_and_assert(transactions)
 }
 
+type LimitTestOutput = BlockOutput<MockOutput<KeyType<[u8; 32]>, MockEvent>>;
+
+type NoOpTestHook = NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>;
+
+/// An executor of mock txns on 32-byte keys, by default of `MockTask` on a `DeltaDataView`.
+type TestExecutor<
+    L = NoOpTestHook,
+    E = MockTask<KeyType<[u8; 32]>, MockEvent>,
+    S = DeltaDataView<KeyType<[u8; 32]>>,
+> = BlockExecutor<MockTransaction<KeyType<[u8; 32]>, MockEvent>, E, S, L, ExecutableTestType>;
+
+/// Creates an executor with the limits of the config, using all cpus of a pool of its own.
+fn test_executor<L, E, S>(
+    config: BlockExecutorConfig,
+    commit_hook: Option<L>,
+) -> TestExecutor<L, E, S>
+where
+    E: ExecutorTask<Txn = MockTransaction<KeyType<[u8; 32]>, MockEvent>>,
+    S: TStateView<Key = KeyType<[u8; 32]>> + Sync,
+    L: TransactionCommitHook<Output = E::Output>,
+{
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+    BlockExecutor::from_config(num_cpus::get(), executor_thread_pool, config, commit_hook)
+}
+
+/// A txn writing 32 bytes to a fresh key, with the given gas.
+fn write_with_gas(gas: u64) -> MockTransaction<KeyType<[u8; 32]>, MockEvent> {
+    MockTransaction::from_behavior(MockIncarnation::new(
+        vec![],
+        vec![(KeyType(random::<[u8; 32]>(), false), random_value(false))], // writes
+        vec![],
+        vec![],
+        gas,
+    ))
+}
+
+/// Executes the block in parallel and sequentially with the given limits.
+fn execute_with_limits(
+    transactions: &[MockTransaction<KeyType<[u8; 32]>, MockEvent>],
+    maybe_block_gas_limit: Option<u64>,
+    maybe_block_output_size_limit: Option<u64>,
+) -> [LimitTestOutput; 2] {
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+    let config = BlockExecutorConfig {
+        maybe_block_gas_limit,
+        maybe_block_output_size_limit,
+    };
+    let executor: TestExecutor = test_executor(config, None);
+
+    [
+        executor
+            .execute_transactions_parallel((), transactions, &data_view)
+            .unwrap(),
+        executor
+            .execute_transactions_sequential((), transactions, &data_view, true)
+            .unwrap(),
+    ]
+}
+
+/// Asserts that exactly the first `num_committed` txns have non-skip outputs.
+fn assert_committed(output: &LimitTestOutput, num_committed: usize) {
+    for (idx, txn_output) in output.transaction_outputs().iter().enumerate() {
+        assert_eq!(
+            txn_output.writes.is_empty(),
+            idx >= num_committed,
+            "txn {}",
+            idx
+        );
+    }
+}
+
+/// Asserts that the block was cut for the reason, and that exactly the txns from
+/// `first_skipped` on have skip outputs recording it.
+fn assert_skip_reason(output: &LimitTestOutput, first_skipped: usize, reason: SkipReason) {
+    assert_eq!(output.cut_reason(), Some(reason));
+    for (idx, txn_output) in output.transaction_outputs().iter().enumerate() {
+        assert_eq!(
+            txn_output.skip_reason(),
+            (idx >= first_skipped).then_some(reason),
+            "txn {}",
+            idx
+        );
+    }
+}
+
+#[test]
+fn block_output_size_limit() {
+    // Every txn writes 32 bytes to a 33 byte key, so txn 3 is the first to cross the limit of
+    // 200 bytes.
+    let transactions: Vec<_> = (0..6).map(|_| write_with_gas(1)).collect();
+    for output in execute_with_limits(&transactions, None, Some(200)) {
+        assert!(output.is_output_size_limit_reached());
+        assert_eq!(output.output_size_cut_at(), Some(3));
+        assert_eq!(output.accumulated_output_size(), 195);
+        assert_eq!(output.accumulated_gas(), 3);
+        assert_eq!(output.transaction_outputs().len(), 6);
+        // Only txns 0-2 are committed, the rest are retryable skip outputs.
+        assert_committed(&output, 3);
+        assert_skip_reason(&output, 3, SkipReason::OutputSizeLimit);
+    }
+}
+
+#[test]
+fn block_output_size_limit_with_deltas() {
+    // The deltas are counted as a 16 byte value with their 33 byte key, whether or not they
+    // are materialized, so both executions cross the limit of 200 bytes with txn 3.
+    let key = KeyType(random::<[u8; 32]>(), false);
+    let transactions: Vec<_> = [MockTransaction::from_behavior(MockIncarnation::new(
+        vec![],
+        vec![(key, random_value(false))], // writes
+        vec![],
+        vec![],
+        1, // gas
+    ))]
+    .into_iter()
+    .chain((0..5).map(|_| {
+        MockTransaction::from_behavior(MockIncarnation::new(
+            vec![key], // reads
+            vec![],
+            vec![(key, delta_add(5, u128::MAX))], // deltas
+            vec![],
+            1, // gas
+        ))
+    }))
+    .collect();
+    for output in execute_with_limits(&transactions, None, Some(200)) {
+        assert_eq!(output.output_size_cut_at(), Some(3));
+        assert_eq!(output.accumulated_output_size(), 65 + 2 * 49);
+        assert_skip_reason(&output, 3, SkipReason::OutputSizeLimit);
+    }
+}
+
+#[test]
+fn block_gas_limit() {
+    // The accumulated gas reaches the limit of 25 with txn 2, which is still committed.
+    let transactions: Vec<_> = (0..6).map(|_| write_with_gas(10)).collect();
+    for output in execute_with_limits(&transactions, Some(25), None) {
+        assert_eq!(output.cut_at(), Some(2));
+        assert_eq!(output.accumulated_gas(), 30);
+        assert!(!output.is_output_size_limit_reached());
+        assert_committed(&output, 3);
+        assert_skip_reason(&output, 3, SkipReason::BlockGasLimit);
+    }
+
+    // A first txn exceeding the limit on its own is committed.
+    let transactions: Vec<_> = (0..3).map(|_| write_with_gas(100)).collect();
+    for output in execute_with_limits(&transactions, Some(25), None) {
+        assert_eq!(output.cut_at(), Some(0));
+        assert_eq!(output.accumulated_gas(), 100);
+        assert_committed(&output, 1);
+        assert_skip_reason(&output, 1, SkipReason::BlockGasLimit);
+    }
+
+    // Reaching the limit with the last txn does not cut the block.
+    let transactions: Vec<_> = (0..3).map(|_| write_with_gas(10)).collect();
+    for output in execute_with_limits(&transactions, Some(30), None) {
+        assert_eq!(output.cut_at(), None);
+        assert_eq!(output.accumulated_gas(), 30);
+        assert_committed(&output, 3);
+        assert!(output
+            .transaction_outputs()
+            .iter()
+            .all(|txn_output| txn_output.skip_reason().is_none()));
+    }
+}
+
+#[test]
+fn skip_rest_precedes_block_gas_limit() {
+    // The SkipRest txn also reaches the gas limit, but its own reason is reported.
+    let transactions = vec![
+        write_with_gas(10),
+        MockTransaction::SkipRest(100),
+        write_with_gas(10),
+        write_with_gas(10),
+    ];
+    for output in execute_with_limits(&transactions, Some(25), None) {
+        assert_eq!(output.cut_at(), Some(1));
+        assert_eq!(output.accumulated_gas(), 110);
+        // The SkipRest txn has no writes, but txns after it have skip outputs.
+        assert_committed(&output, 1);
+        assert_eq!(output.transaction_outputs()[1].total_gas, 100);
+        assert_skip_reason(&output, 2, SkipReason::ExplicitHalt);
+    }
+}
+
+#[test]
+fn skip_reason_recorded_in_every_skipped_output() {
+    // A txn halting the block in the middle, with writes on both sides of it.
+    let mut transactions: Vec<_> = (0..5).map(|_| write_with_gas(1)).collect();
+    transactions.push(MockTransaction::SkipRest(1));
+    transactions.extend((0..10).map(|_| write_with_gas(1)));
+    for output in execute_with_limits(&transactions, Some(100), Some(1000)) {
+        assert_eq!(output.cut_at(), Some(5));
+        assert_eq!(output.transaction_outputs().len(), 16);
+        assert_committed(&output, 5);
+        assert_skip_reason(&output, 6, SkipReason::ExplicitHalt);
+    }
+
+    // A halting last txn does not cut the block, so no output records a reason.
+    let transactions = vec![write_with_gas(1), MockTransaction::SkipRest(1)];
+    for output in execute_with_limits(&transactions, None, None) {
+        assert_eq!(output.cut_at(), None);
+        assert!(output
+            .transaction_outputs()
+            .iter()
+            .all(|txn_output| txn_output.skip_reason().is_none()));
+    }
+}
+
+/// A view of mock storage which sends every key read from it, so that tests can observe the
+/// executions of txns reading keys which no txn writes.
+struct NotifyingDataView {
+    reads: Mutex<mpsc::Sender<KeyType<[u8; 32]>>>,
+}
+
+impl TStateView for NotifyingDataView {
+    type Key = KeyType<[u8; 32]>;
+
+    fn get_state_value(&self, key: &Self::Key) -> anyhow::Result<Option<StateValue>> {
+        // Nobody listens anymore once the commit hook stopped waiting, which is fine.
+        let _ = self.reads.lock().send(*key);
+        DeltaDataView::<KeyType<[u8; 32]>> {
+            phantom: PhantomData,
+        }
+        .get_state_value(key)
+    }
+
+    fn id(&self) -> StateViewId {
+        StateViewId::Miscellaneous
+    }
+
+    fn get_usage(&self) -> anyhow::Result<StateStorageUsage> {
+        unreachable!("Not used in tests");
+    }
+}
+
+/// A commit hook which records the order in which it is invoked. If given the keys read by the
+/// txns from a `NotifyingDataView` and their number, the hook for txn 0 waits until all the
+/// keys were read, i.e. all the txns were executed, which requires the other workers to make
+/// progress while it is blocked.
+struct RecordingCommitHook {
+    invocations: Arc<Mutex<Vec<TxnIndex>>>,
+    wait_for_reads: Option<(Mutex<mpsc::Receiver<KeyType<[u8; 32]>>>, usize)>,
+    waited: Arc<AtomicBool>,
+}
+
+impl RecordingCommitHook {
+    fn new(wait_for_reads: Option<(mpsc::Receiver<KeyType<[u8; 32]>>, usize)>) -> Self {
+        Self {
+            invocations: Arc::new(Mutex::new(vec![])),
+            wait_for_reads: wait_for_reads.map(|(reads, num_keys)| (Mutex::new(reads), num_keys)),
+            waited: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn record(&self, txn_idx: TxnIndex) {
+        if let (0, Some((reads, num_keys))) = (txn_idx, &self.wait_for_reads) {
+            let reads = reads.lock();
+            let mut keys = HashSet::new();
+            // The timeout only keeps the test from hanging if the other workers are blocked.
+            while keys.len() < *num_keys {
+                match reads.recv_timeout(Duration::from_secs(60)) {
+                    Ok(key) => keys.insert(key),
+                    Err(_) => break,
+                };
+            }
+            self.waited.store(keys.len() == *num_keys, Ordering::SeqCst);
+        }
+        self.invocations.lock().push(txn_idx);
+    }
+}
+
+impl TransactionCommitHook for RecordingCommitHook {
+    type Output = MockOutput<KeyType<[u8; 32]>, MockEvent>;
+
+    fn on_transaction_committed(&self, txn_idx: TxnIndex, output: &Self::Output) {
+        // The hook is invoked after the output is materialized.
+        assert!(output.materialized_delta_writes.get().is_some());
+        self.record(txn_idx);
+    }
+
+    fn on_execution_aborted(&self, txn_idx: TxnIndex) {
+        self.record(txn_idx);
+    }
+}
+
+#[test]
+fn commit_hooks_in_txn_order() {
+    // All txns read and write a few keys, which causes conflicts and re-executions.
+    let keys: Vec<_> = (0..4)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+    let transactions: Vec<_> = (0..TXN_PER_BLOCK as usize)
+        .map(|i| {
+            MockTransaction::from_behavior(MockIncarnation::new(
+                vec![keys[i % 4], keys[(i + 1) % 4]],           // reads
+                vec![(keys[(i + 2) % 4], random_value(false))], // writes
+                vec![],
+                vec![],
+                1, // gas
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+
+    for parallel in [true, false] {
+        let commit_hook = RecordingCommitHook::new(None);
+        let invocations = commit_hook.invocations.clone();
+        let executor: TestExecutor<_> =
+            test_executor(BlockExecutorConfig::default(), Some(commit_hook));
+        let output = if parallel {
+            executor.execute_transactions_parallel((), &transactions, &data_view)
+        } else {
+            executor.execute_transactions_sequential((), &transactions, &data_view, true)
+        };
+        assert_ok!(output);
+        assert_eq!(
+            *invocations.lock(),
+            (0..TXN_PER_BLOCK as TxnIndex).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn slow_commit_hook_does_not_block_workers() {
+    // Every txn reads a key of its own from storage, so each execution is observed.
+    let keys: Vec<_> = (0..TXN_PER_BLOCK)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+    let transactions: Vec<_> = keys
+        .iter()
+        .map(|key| {
+            MockTransaction::from_behavior(MockIncarnation::new(
+                vec![*key],                                                        // reads
+                vec![(KeyType(random::<[u8; 32]>(), false), random_value(false))], // writes
+                vec![],
+                vec![],
+                1, // gas
+            ))
+        })
+        .collect();
+    let (sender, receiver) = mpsc::channel();
+    let data_view = NotifyingDataView {
+        reads: Mutex::new(sender),
+    };
+
+    let commit_hook = RecordingCommitHook::new(Some((receiver, keys.len())));
+    let invocations = commit_hook.invocations.clone();
+    let waited = commit_hook.waited.clone();
+    let executor: TestExecutor<
+        RecordingCommitHook,
+        MockTask<KeyType<[u8; 32]>, MockEvent>,
+        NotifyingDataView,
+    > = test_executor(BlockExecutorConfig::default(), Some(commit_hook));
+    assert_ok!(executor.execute_transactions_parallel((), &transactions, &data_view));
+
+    // All the txns were executed while the hook for txn 0 was blocked, and the hooks were
+    // still invoked in order.
+    assert!(waited.load(Ordering::SeqCst));
+    assert_eq!(
+        *invocations.lock(),
+        (0..TXN_PER_BLOCK as TxnIndex).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn read_summaries() {
+    let key = KeyType(random::<[u8; 32]>(), false);
+    let module_key = KeyType(random::<[u8; 32]>(), true);
+    // Txn 1 reads the key written by txn 0, and a module.
+    let transactions = vec![
+        MockTransaction::from_behavior(MockIncarnation::new(
+            vec![],
+            vec![(key, random_value(false))], // writes
+            vec![],
+            vec![],
+            1, // gas
+        )),
+        MockTransaction::from_behavior(MockIncarnation::new(
+            vec![key, module_key],            // reads
+            vec![(key, random_value(false))], // writes
+            vec![],
+            vec![],
+            1, // gas
+        )),
+    ];
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+
+    for collect_read_summaries in [false, true] {
+        let executor: TestExecutor = test_executor(BlockExecutorConfig::default(), None);
+        let executor = executor.with_read_summaries(collect_read_summaries);
+
+        let outputs = [
+            executor.execute_transactions_parallel((), &transactions, &data_view),
+            executor.execute_transactions_sequential((), &transactions, &data_view, true),
+        ];
+        for output in outputs {
+            let output = output.unwrap();
+            let read_summaries: Vec<_> = output.read_summaries().collect();
+            if !collect_read_summaries {
+                assert!(read_summaries.is_empty());
+                continue;
+            }
+            assert_eq!(read_summaries.len(), 2);
+            let (txn_idx, read_summary) = read_summaries[0];
+            assert_eq!(txn_idx, 0);
+            assert_eq!(read_summary, &ReadSummary::default());
+            let (txn_idx, read_summary) = read_summaries[1];
+            assert_eq!(txn_idx, 1);
+            assert_eq!(read_summary.resources, BTreeSet::from([key]));
+            assert_eq!(read_summary.modules, BTreeSet::from([module_key]));
+            assert!(read_summary.group_resources.is_empty());
+            assert!(read_summary.delayed_fields.is_empty());
+        }
+    }
+}
+
+#[test]
+fn access_hints() {
+    let keys: Vec<_> = (0..4)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+    let unwritten_key = KeyType(random::<[u8; 32]>(), false);
+    let transfer = |from: usize, to: usize| {
+        MockIncarnation::new(
+            vec![keys[from], keys[to]], // reads
+            vec![
+                (keys[from], random_value(false)),
+                (keys[to], random_value(false)),
+            ], // writes
+            vec![],
+            vec![],
+            1, // gas
+        )
+    };
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+
+    for wrong_hints in [false, true] {
+        let transactions: Vec<_> = (0..1000)
+            .map(|i| {
+                let (from, to) = (i % 4, (i + 1) % 4);
+                // Every tenth txn behaves differently when re-executed.
+                let behaviors = if i % 10 == 0 {
+                    vec![transfer(from, to), transfer(to, (to + 1) % 4)]
+                } else {
+                    vec![transfer(from, to)]
+                };
+                let accesses = if wrong_hints {
+                    // Hints that miss the actual accesses, and include a write that never
+                    // happens, so that the estimate for it must be removed.
+                    Accesses {
+                        keys_read: vec![keys[(from + 2) % 4]],
+                        keys_written: vec![keys[(to + 2) % 4], unwritten_key],
+                    }
+                } else {
+                    behaviors[0].accesses()
+                };
+                MockTransaction::from_behaviors(behaviors).with_access_hints(accesses)
+            })
+            .chain([MockTransaction::from_behavior(MockIncarnation::new(
+                vec![unwritten_key], // reads
+                vec![],
+                vec![],
+                vec![],
+                1, // gas
+            ))])
+            .collect();
+
+        let executor: TestExecutor = test_executor(BlockExecutorConfig::default(), None);
+        let output = executor
+            .with_access_hints(true)
+            .execute_transactions_parallel((), &transactions, &data_view)
+            .map(BlockOutput::into_transaction_outputs);
+
+        let baseline = BaselineOutput::generate(&transactions, None);
+        baseline.assert_output(&output);
+    }
+}
+
+/// Records the context of each execution, and fails the first parallel execution of a given
+/// transaction with a speculative error, so that the transaction is re-executed.
+struct RecordingTask<'a> {
+    inner: MockTask<KeyType<[u8; 32]>, MockEvent>,
+    contexts: &'a Mutex<Vec<ExecutionContext>>,
+    failing_txn_idx: TxnIndex,
+}
+
+impl<'a> ExecutorTask for RecordingTask<'a> {
+    type Argument = (&'a Mutex<Vec<ExecutionContext>>, TxnIndex);
+    type Error = usize;
+    type Output = MockOutput<KeyType<[u8; 32]>, MockEvent>;
+    type Txn = MockTransaction<KeyType<[u8; 32]>, MockEvent>;
+
+    fn init((contexts, failing_txn_idx): Self::Argument) -> Self {
+        Self {
+            inner: MockTask::init(()),
+            contexts,
+            failing_txn_idx,
+        }
+    }
+
+    fn execute_transaction(
+        &self,
+        view: &(impl TExecutorView<KeyType<[u8; 32]>, u32, MoveTypeLayout, DelayedFieldID, ValueType>
+              + TResourceGroupView<
+            GroupKey = KeyType<[u8; 32]>,
+            ResourceTag = u32,
+            Layout = MoveTypeLayout,
+        >),
+        txn: &Self::Txn,
+        context: ExecutionContext,
+        materialize_deltas: bool,
+    ) -> ExecutionStatus<Self::Output, Self::Error> {
+        self.contexts.lock().push(context);
+        if context.txn_idx == self.failing_txn_idx
+            && context.incarnation == 0
+            && !context.is_sequential
+        {
+            return ExecutionStatus::SpeculativeExecutionAbortError("forced abort".to_string());
+        }
+        self.inner
+            .execute_transaction(view, txn, context, materialize_deltas)
+    }
+
+    fn is_transaction_dynamic_change_set_capable(txn: &Self::Txn) -> bool {
+        MockTask::<KeyType<[u8; 32]>, MockEvent>::is_transaction_dynamic_change_set_capable(txn)
+    }
+}
+
+#[test]
+fn execution_contexts() {
+    // Independent transactions, so that only the failing transaction is re-executed.
+    let transactions: Vec<_> = (0..3)
+        .map(|_| {
+            MockTransaction::from_behavior(MockIncarnation::new(
+                vec![],
+                vec![(KeyType(random::<[u8; 32]>(), false), random_value(false))], // writes
+                vec![],
+                vec![],
+                1, // gas
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+    let contexts = Mutex::new(vec![]);
+    let executor: TestExecutor<NoOpTestHook, RecordingTask> =
+        test_executor(BlockExecutorConfig::default(), None);
+    // Takes the contexts recorded so far.
+    let recorded = || {
+        let mut recorded: Vec<_> = std::mem::take(&mut *contexts.lock())
+            .into_iter()
+            .map(|context| (context.txn_idx, context.incarnation, context.is_sequential))
+            .collect();
+        recorded.sort();
+        recorded
+    };
+
+    assert_ok!(executor.execute_transactions_parallel((&contexts, 1), &transactions, &data_view));
+    // The failing transaction is observed again by its re-execution, as incarnation 1.
+    assert_eq!(recorded(), vec![
+        (0, 0, false),
+        (1, 0, false),
+        (1, 1, false),
+        (2, 0, false)
+    ]);
+
+    assert_ok!(executor.execute_transactions_sequential(
+        (&contexts, 1),
+        &transactions,
+        &data_view,
+        false
+    ));
+    assert_eq!(recorded(), vec![(0, 0, true), (1, 0, true), (2, 0, true)]);
+}
+
 #[test]
 fn scheduler_tasks() {
     let s = Scheduler::new(5);
@@ -513,6 +1122,33 @@ fn scheduler_dependency() {
     ));
 }
 
+#[test]
+fn scheduler_hinted_dependency() {
+    let s = Scheduler::new(4).with_hinted_dependencies(vec![None, None, Some(0), None]);
+
+    for i in [0, 1, 3] {
+        // Transaction 2 is deferred, as transaction 0 is inferred to write what it reads.
+        assert!(matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        ));
+    }
+
+    assert!(matches!(
+        s.finish_execution(0, 0, false),
+        SchedulerTask::NoTask
+    ));
+    assert!(matches!(
+        s.next_task(),
+        SchedulerTask::ValidationTask(0, 0, 0)
+    ));
+    // Once transaction 0 is executed, transaction 2 is no longer deferred.
+    assert!(matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(2, 0, ExecutionTaskType::Execution)
+    ));
+}
+
 // Will return a scheduler in a state where all transactions are scheduled for
 // for execution, validation index = num_txns, and wave = 0.
 fn incarnation_one_scheduler(num_txns: TxnIndex) -> Scheduler {
@@ -7,6 +7,7 @@ use crate::{
     },
     counters,
     scheduler::{DependencyResult, DependencyStatus, Scheduler, TWaitForDependency},
+    task::ReadSummary,
 };
 use anyhow::bail;
 use aptos_aggregator::{
@@ -748,6 +749,8 @@ pub(crate) struct SequentialState<'a, T: Transaction, X: Executable> {
     pub(crate) counter: &'a RefCell<u32>,
     pub(crate) dynamic_change_set_optimizations_enabled: bool,
     pub(crate) incorrect_use: RefCell<bool>,
+    /// Set if the reads are to be recorded, to be summarized in the output.
+    pub(crate) read_summary: Option<RefCell<ReadSummary<T::Key, T::Tag, T::Identifier>>>,
 }
 
 impl<'a, T: Transaction, X: Executable> SequentialState<'a, T, X> {
@@ -765,9 +768,16 @@ impl<'a, T: Transaction, X: Executable> SequentialState<'a, T, X> {
             counter,
             dynamic_change_set_optimizations_enabled,
             incorrect_use: RefCell::new(false),
+            read_summary: None,
         }
     }
 
+    /// Records the reads of the transaction, which can be taken from the view afterwards.
+    pub fn with_read_summary(mut self) -> Self {
+        self.read_summary = Some(RefCell::new(ReadSummary::default()));
+        self
+    }
+
     fn set_delayed_field_value(&self, id: T::Identifier, base_value: DelayedFieldValue) {
         self.unsync_map.write_delayed_field(id, base_value)
     }
@@ -964,6 +974,28 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> LatestView<
         }
     }
 
+    /// Takes the summary of the reads in sequential execution, if they are recorded. In parallel
+    /// execution, the summary is derived from the captured reads instead.
+    pub(crate) fn take_read_summary(&self) -> Option<ReadSummary<T::Key, T::Tag, T::Identifier>> {
+        match &self.latest_view {
+            ViewState::Sync(_) => None,
+            ViewState::Unsync(state) => state.read_summary.as_ref().map(RefCell::take),
+        }
+    }
+
+    fn record_sequential_read(
+        &self,
+        record: impl FnOnce(&mut ReadSummary<T::Key, T::Tag, T::Identifier>),
+    ) {
+        if let ViewState::Unsync(SequentialState {
+            read_summary: Some(read_summary),
+            ..
+        }) = &self.latest_view
+        {
+            record(&mut read_summary.borrow_mut());
+        }
+    }
+
     fn mark_incorrect_use(&self) {
         match &self.latest_view {
             ViewState::Sync(state) => state.captured_reads.borrow_mut().mark_incorrect_use(),
@@ -1313,6 +1345,9 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> LatestView<
             "Reading a module {:?} using ResourceView",
             state_key,
         );
+        self.record_sequential_read(|summary| {
+            summary.resources.insert(state_key.clone());
+        });
 
         let layout = if self.is_delayed_field_optimization_capable() {
             layout
@@ -1473,6 +1508,11 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TResourceGr
         maybe_layout: Option<&Self::Layout>,
     ) -> anyhow::Result<Option<Bytes>> {
         let maybe_layout = maybe_layout.filter(|_| self.is_delayed_field_optimization_capable());
+        self.record_sequential_read(|summary| {
+            summary
+                .group_resources
+                .insert((group_key.clone(), resource_tag.clone()));
+        });
 
         let mut group_read = self
             .latest_view
@@ -1544,6 +1584,9 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TModuleView
             "Reading a resource {:?} using ModuleView",
             state_key,
         );
+        self.record_sequential_read(|summary| {
+            summary.modules.insert(state_key.clone());
+        });
 
         match &self.latest_view {
             ViewState::Sync(state) => {
@@ -1618,6 +1661,9 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TDelayedFie
         &self,
         id: &Self::Identifier,
     ) -> Result<DelayedFieldValue, PanicOr<DelayedFieldsSpeculativeError>> {
+        self.record_sequential_read(|summary| {
+            summary.delayed_fields.insert(*id);
+        });
         match &self.latest_view {
             ViewState::Sync(state) => get_delayed_field_value_impl(&state.captured_reads, state.versioned_map.delayed_fields(), state.scheduler, id, self.txn_idx),
             ViewState::Unsync(state) => Ok(state.unsync_map.fetch_delayed_field(id).ok_or_else(|| {
@@ -1633,6 +1679,9 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TDelayedFie
         delta: &SignedU128,
         max_value: u128,
     ) -> Result<bool, PanicOr<DelayedFieldsSpeculativeError>> {
+        self.record_sequential_read(|summary| {
+            summary.delayed_fields.insert(*id);
+        });
         match &self.latest_view {
             ViewState::Sync(state) => delayed_field_try_add_delta_outcome_impl(
                 &state.captured_reads,
@@ -15,7 +15,10 @@ use crate::{
 use anyhow::Error;
 use aptos_abstract_gas_usage::CalibrationAlgebra;
 use aptos_bitvec::BitVec;
-use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
+use aptos_block_executor::{
+    executor::{BlockExecutorConfig, BlockOutput},
+    txn_commit_hook::NoOpTransactionCommitHook,
+};
 use aptos_crypto::HashValue;
 use aptos_framework::ReleaseBundle;
 use aptos_gas_algebra::DynamicExpression;
@@ -466,12 +469,26 @@ impl FakeExecutor {
         &self,
         txn_block: &[SignatureVerifiedTransaction],
     ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        self.execute_transaction_block_parallel_with_config(
+            txn_block,
+            BlockExecutorConfig::default(),
+        )
+        .map(BlockOutput::into_transaction_outputs)
+    }
+
+    /// Executes the block in parallel with the limits of the config, returning where and why
+    /// the block was cut along with the outputs.
+    pub fn execute_transaction_block_parallel_with_config(
+        &self,
+        txn_block: &[SignatureVerifiedTransaction],
+        config: BlockExecutorConfig,
+    ) -> Result<BlockOutput<TransactionOutput>, VMStatus> {
         BlockAptosVM::execute_block::<_, NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>>(
             self.executor_thread_pool.clone(),
             txn_block,
             &self.data_store,
             usize::min(4, num_cpus::get()),
-            None,
+            config,
             None,
         )
     }
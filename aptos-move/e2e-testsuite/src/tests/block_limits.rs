@@ -0,0 +1,105 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_block_executor::{
+    executor::{BlockExecutorConfig, BlockOutput},
+    task::SkipReason,
+};
+use aptos_language_e2e_tests::{common_transactions::peer_to_peer_txn, executor::FakeExecutor};
+use aptos_types::transaction::{
+    signature_verified_transaction::{into_signature_verified_block, SignatureVerifiedTransaction},
+    ExecutionStatus, Transaction, TransactionOutput, TransactionStatus,
+};
+
+/// A block of transfers between distinct accounts, so that all of them can be committed.
+fn transfer_block(executor: &mut FakeExecutor) -> Vec<SignatureVerifiedTransaction> {
+    let txns = (0..4)
+        .map(|_| {
+            let sender = executor.create_raw_account_data(1_000_000, 10);
+            let receiver = executor.create_raw_account_data(100_000, 10);
+            executor.add_account_data(&sender);
+            executor.add_account_data(&receiver);
+            Transaction::UserTransaction(peer_to_peer_txn(
+                sender.account(),
+                receiver.account(),
+                10,
+                1_000,
+                0,
+            ))
+        })
+        .collect();
+    into_signature_verified_block(txns)
+}
+
+/// Asserts that exactly the first `num_committed` transactions were kept, and the others are to
+/// be retried.
+fn assert_committed(output: &BlockOutput<TransactionOutput>, num_committed: usize) {
+    for (idx, txn_output) in output.transaction_outputs().iter().enumerate() {
+        if idx < num_committed {
+            assert_eq!(
+                txn_output.status(),
+                &TransactionStatus::Keep(ExecutionStatus::Success)
+            );
+        } else {
+            assert_eq!(txn_output.status(), &TransactionStatus::Retry);
+        }
+    }
+}
+
+#[test]
+fn block_gas_limit_cut_is_reported() {
+    let mut executor = FakeExecutor::from_head_genesis();
+    let block = transfer_block(&mut executor);
+
+    // The first transaction exceeds the limit on its own, but is still committed.
+    let output = executor
+        .execute_transaction_block_parallel_with_config(&block, BlockExecutorConfig {
+            maybe_block_gas_limit: Some(1),
+            maybe_block_output_size_limit: None,
+        })
+        .unwrap();
+    assert_eq!(output.cut_at(), Some(0));
+    assert_eq!(output.cut_reason(), Some(SkipReason::BlockGasLimit));
+    // Only the execution and io gas count towards the limit, not the storage fee.
+    assert!(output.accumulated_gas() > 1);
+    assert!(output.accumulated_gas() <= output.transaction_outputs()[0].gas_used());
+    assert_committed(&output, 1);
+}
+
+#[test]
+fn output_size_limit_cut_is_reported() {
+    let mut executor = FakeExecutor::from_head_genesis();
+    let block = transfer_block(&mut executor);
+
+    // The second transaction crosses the limit, so only the first one is committed.
+    let output = executor
+        .execute_transaction_block_parallel_with_config(&block, BlockExecutorConfig {
+            maybe_block_gas_limit: None,
+            maybe_block_output_size_limit: Some(1),
+        })
+        .unwrap();
+    assert_eq!(output.cut_at(), Some(1));
+    assert_eq!(output.cut_reason(), Some(SkipReason::OutputSizeLimit));
+    assert!(output.accumulated_output_size() > 1);
+    assert_committed(&output, 1);
+}
+
+#[test]
+fn uncut_block_reports_accumulated_gas() {
+    let mut executor = FakeExecutor::from_head_genesis();
+    let block = transfer_block(&mut executor);
+
+    let output = executor
+        .execute_transaction_block_parallel_with_config(&block, BlockExecutorConfig::default())
+        .unwrap();
+    assert_eq!(output.cut_at(), None);
+    assert_eq!(output.cut_reason(), None);
+    let total_gas_used: u64 = output
+        .transaction_outputs()
+        .iter()
+        .map(|txn_output| txn_output.gas_used())
+        .sum();
+    assert!(output.accumulated_gas() > 0);
+    assert!(output.accumulated_gas() <= total_gas_used);
+    assert_committed(&output, 4);
+}
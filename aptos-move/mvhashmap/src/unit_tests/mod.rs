@@ -273,6 +273,43 @@ fn materialize_delta_shortcut() {
     assert_eq!(vd.fetch_data(&ap, 10), Ok(Resolved(50)));
 }
 
+#[test]
+fn estimate_before_first_incarnation() {
+    use MVDataError::*;
+    use MVDataOutput::*;
+
+    let vd: VersionedData<KeyType<Vec<u8>>, TestValue> = VersionedData::new();
+    let ap1 = KeyType(b"/foo/b".to_vec());
+    let ap2 = KeyType(b"/foo/c".to_vec());
+
+    vd.add_estimate(ap1.clone(), 5);
+    vd.add_estimate(ap2.clone(), 5);
+    assert_eq!(vd.fetch_data(&ap1, 5), Err(Uninitialized));
+    assert_eq!(vd.fetch_data(&ap1, 6), Err(Dependency(5)));
+
+    // The first incarnation overwrites the estimate, and removes the one it did not write.
+    vd.write(ap1.clone(), 5, 0, (value_for(5, 0), None));
+    vd.remove(&ap2, 5);
+    assert_eq!(
+        vd.fetch_data(&ap1, 6),
+        Ok(Versioned(
+            Ok((5, 0)),
+            ValueWithLayout::Exchanged(arc_value_for(5, 0), None)
+        ))
+    );
+    assert_eq!(vd.fetch_data(&ap2, 6), Err(Uninitialized));
+
+    // An estimate does not replace an existing entry.
+    vd.add_estimate(ap1.clone(), 5);
+    assert_eq!(
+        vd.fetch_data(&ap1, 6),
+        Ok(Versioned(
+            Ok((5, 0)),
+            ValueWithLayout::Exchanged(arc_value_for(5, 0), None)
+        ))
+    );
+}
+
 #[test]
 #[should_panic]
 fn aggregator_base_mismatch() {
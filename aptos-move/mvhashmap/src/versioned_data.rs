@@ -234,6 +234,24 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
             .mark_estimate();
     }
 
+    /// Record an estimated write from transaction 'txn_idx' at access path 'key' ahead of its
+    /// first incarnation, e.g. based on inferred accesses, unless the transaction has an entry
+    /// at the access path. The estimate must be overwritten by the first incarnation, or removed.
+    pub fn add_estimate(&self, key: K, txn_idx: TxnIndex) {
+        let mut v = self.values.entry(key).or_default();
+        v.versioned_map
+            .entry(ShiftedTxnIndex::new(txn_idx))
+            .or_insert_with(|| {
+                CachePadded::new(Entry {
+                    cell: EntryCell::Write(
+                        0,
+                        ValueWithLayout::RawFromStorage(Arc::new(V::from_state_value(None))),
+                    ),
+                    flag: Flag::Estimate,
+                })
+            });
+    }
+
     /// Delete an entry from transaction 'txn_idx' at access path 'key'. Will panic
     /// if the corresponding entry does not exist.
     pub fn remove(&self, key: &K, txn_idx: TxnIndex) {
@@ -319,10 +337,11 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
             )),
         );
 
-        // Assert that the previous entry for txn_idx, if present, had lower incarnation.
+        // Assert that the previous entry for txn_idx, if present, had lower incarnation,
+        // or was an estimate added before the first incarnation.
         assert!(prev_entry.map_or(true, |entry| -> bool {
             if let EntryCell::Write(i, _) = entry.cell {
-                i < incarnation
+                i < incarnation || (i == 0 && entry.flag == Flag::Estimate)
             } else {
                 true
             }
@@ -53,6 +53,7 @@ pub fn set_aptos_vm_configurations(node_config: &NodeConfig) {
     AptosVM::set_num_proof_reading_threads_once(
         node_config.execution.num_proof_reading_threads as usize,
     );
+    AptosVM::set_block_output_size_limit_once(node_config.execution.block_output_size_limit);
 
     if node_config
         .execution
@@ -36,6 +36,9 @@ pub struct ExecutionConfig {
     pub processed_transactions_detailed_counters: bool,
     /// Enables filtering of transactions before they are sent to execution
     pub transaction_filter: Filter,
+    /// The limit on the accumulated output size of the transactions committed in a block, in
+    /// bytes. Transactions after the limit is reached are retried in later blocks.
+    pub block_output_size_limit: Option<u64>,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -66,6 +69,7 @@ impl Default for ExecutionConfig {
             paranoid_hot_potato_verification: true,
             processed_transactions_detailed_counters: false,
             transaction_filter: Filter::empty(),
+            block_output_size_limit: None,
         }
     }
 }
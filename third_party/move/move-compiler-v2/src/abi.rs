@@ -0,0 +1,254 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation of the ABIs of the target modules as JSON, written if `Options::emit_abi` is
+//! set. The ABI of a module lists its friends, the functions which can be called from outside
+//! of the module, which are the public, friend, and entry functions, and its structs.
+//!
+//! The format is the one in which the REST API exposes the ABIs of on-chain modules, so
+//! clients can use the same parsers for both. In particular, types are rendered as strings
+//! with numerical addresses, like `&0x1::string::String`, and type parameters as `T0`, `T1`,
+//! and so on. Functions and structs are listed in the order of their declaration, as in the
+//! compiled module.
+
+use crate::file_format_generator::{functions_in_source_order, structs_in_source_order};
+use move_binary_format::file_format::{Ability, AbilitySet, Visibility};
+use move_model::{
+    ast::{Address, ModuleName},
+    model::{FunctionEnv, GlobalEnv, ModuleEnv, StructEnv},
+    ty::{PrimitiveType, ReferenceKind, Type},
+};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The attribute marking view functions, which the REST API can call without a transaction.
+const VIEW_ATTRIBUTE: &str = "view";
+
+/// The ABI of a module.
+#[derive(Clone, Debug, Serialize)]
+pub struct ModuleAbi {
+    pub address: String,
+    pub name: String,
+    pub friends: Vec<String>,
+    pub exposed_functions: Vec<FunctionAbi>,
+    pub structs: Vec<StructAbi>,
+}
+
+/// The ABI of a function.
+#[derive(Clone, Debug, Serialize)]
+pub struct FunctionAbi {
+    pub name: String,
+    pub visibility: String,
+    pub is_entry: bool,
+    pub is_view: bool,
+    pub generic_type_params: Vec<TypeParamAbi>,
+    pub params: Vec<String>,
+    #[serde(rename = "return")]
+    pub return_: Vec<String>,
+}
+
+/// The layout of a struct.
+#[derive(Clone, Debug, Serialize)]
+pub struct StructAbi {
+    pub name: String,
+    pub is_native: bool,
+    pub abilities: Vec<String>,
+    pub generic_type_params: Vec<TypeParamAbi>,
+    pub fields: Vec<FieldAbi>,
+}
+
+/// The abilities a type parameter is constrained by.
+#[derive(Clone, Debug, Serialize)]
+pub struct TypeParamAbi {
+    pub constraints: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FieldAbi {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: String,
+}
+
+/// Returns the ABIs of the target modules.
+pub fn module_abis(env: &GlobalEnv) -> Vec<ModuleAbi> {
+    env.get_modules()
+        .filter(|module_env| module_env.is_target() && !module_env.is_script_module())
+        .map(|module_env| module_abi(&module_env))
+        .collect()
+}
+
+/// Writes the ABI of each target module to `<dir>/<module name>.json`, returning the paths of
+/// the written files.
+pub fn write_abis(env: &GlobalEnv, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut paths = vec![];
+    for abi in module_abis(env) {
+        let path = dir.join(format!("{}.json", abi.name));
+        fs::write(&path, serde_json::to_string_pretty(&abi)? + "\n")?;
+        paths.push(path)
+    }
+    Ok(paths)
+}
+
+fn module_abi(module_env: &ModuleEnv) -> ModuleAbi {
+    let env = module_env.env;
+    let name = module_env.get_name();
+    ModuleAbi {
+        address: address_str(name.addr()),
+        name: env.symbol_pool().string(name.name()).to_string(),
+        friends: module_env
+            .get_friend_decls()
+            .iter()
+            .map(|decl| module_name_str(env, &decl.module_name))
+            .collect(),
+        exposed_functions: functions_in_source_order(module_env)
+            .into_iter()
+            .filter(|fun_env| {
+                // Private entry functions can still be called by transactions.
+                !fun_env.is_inline()
+                    && (fun_env.is_entry() || fun_env.visibility() != Visibility::Private)
+            })
+            .map(|fun_env| function_abi(&fun_env))
+            .collect(),
+        structs: structs_in_source_order(module_env)
+            .iter()
+            .map(struct_abi)
+            .collect(),
+    }
+}
+
+fn function_abi(fun_env: &FunctionEnv) -> FunctionAbi {
+    let env = fun_env.module_env.env;
+    let visibility = match fun_env.visibility() {
+        Visibility::Private => "private",
+        Visibility::Public => "public",
+        Visibility::Friend => "friend",
+    };
+    let return_ = match fun_env.get_result_type() {
+        Type::Tuple(tys) => tys,
+        ty => vec![ty],
+    };
+    FunctionAbi {
+        name: fun_env.get_name_str(),
+        visibility: visibility.to_owned(),
+        is_entry: fun_env.is_entry(),
+        is_view: fun_env
+            .has_attribute(|attr| env.symbol_pool().string(attr.name()).as_str() == VIEW_ATTRIBUTE),
+        generic_type_params: fun_env
+            .get_type_parameters()
+            .iter()
+            .map(|param| type_param_abi(param.1.abilities))
+            .collect(),
+        params: fun_env
+            .get_parameters()
+            .iter()
+            .map(|param| type_str(env, &param.1))
+            .collect(),
+        return_: return_.iter().map(|ty| type_str(env, ty)).collect(),
+    }
+}
+
+fn struct_abi(struct_env: &StructEnv) -> StructAbi {
+    let env = struct_env.module_env.env;
+    StructAbi {
+        name: env.symbol_pool().string(struct_env.get_name()).to_string(),
+        // Structs compiled from sources are never native.
+        is_native: false,
+        abilities: ability_strs(struct_env.get_abilities()),
+        generic_type_params: struct_env
+            .get_type_parameters()
+            .iter()
+            .map(|param| type_param_abi(param.1.abilities))
+            .collect(),
+        fields: struct_env
+            .get_fields()
+            .map(|field_env| FieldAbi {
+                name: env.symbol_pool().string(field_env.get_name()).to_string(),
+                typ: type_str(env, &field_env.get_type()),
+            })
+            .collect(),
+    }
+}
+
+fn type_param_abi(abilities: AbilitySet) -> TypeParamAbi {
+    TypeParamAbi {
+        constraints: ability_strs(abilities),
+    }
+}
+
+fn ability_strs(abilities: AbilitySet) -> Vec<String> {
+    abilities
+        .into_iter()
+        .map(|ability| {
+            match ability {
+                Ability::Copy => "copy",
+                Ability::Drop => "drop",
+                Ability::Store => "store",
+                Ability::Key => "key",
+            }
+            .to_owned()
+        })
+        .collect()
+}
+
+fn address_str(addr: &Address) -> String {
+    addr.expect_numerical().to_hex_literal()
+}
+
+fn module_name_str(env: &GlobalEnv, name: &ModuleName) -> String {
+    format!(
+        "{}::{}",
+        address_str(name.addr()),
+        name.name().display(env.symbol_pool())
+    )
+}
+
+/// Renders a type as the REST API does, with fully qualified struct names.
+fn type_str(env: &GlobalEnv, ty: &Type) -> String {
+    match ty {
+        Type::Primitive(prim) => match prim {
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::U8 => "u8",
+            PrimitiveType::U16 => "u16",
+            PrimitiveType::U32 => "u32",
+            PrimitiveType::U64 => "u64",
+            PrimitiveType::U128 => "u128",
+            PrimitiveType::U256 => "u256",
+            PrimitiveType::Address => "address",
+            PrimitiveType::Signer => "signer",
+            PrimitiveType::Num | PrimitiveType::Range | PrimitiveType::EventStore => {
+                unreachable!("specification type in program")
+            },
+        }
+        .to_owned(),
+        Type::Vector(elem) => format!("vector<{}>", type_str(env, elem)),
+        Type::Reference(ReferenceKind::Immutable, inner) => format!("&{}", type_str(env, inner)),
+        Type::Reference(ReferenceKind::Mutable, inner) => format!("&mut {}", type_str(env, inner)),
+        Type::TypeParameter(idx) => format!("T{}", idx),
+        Type::Struct(mid, sid, args) => {
+            let struct_env = env.get_module(*mid).into_struct(*sid);
+            let name = format!(
+                "{}::{}",
+                module_name_str(env, struct_env.module_env.get_name()),
+                struct_env.get_name().display(env.symbol_pool())
+            );
+            if args.is_empty() {
+                name
+            } else {
+                format!(
+                    "{}<{}>",
+                    name,
+                    args.iter()
+                        .map(|arg| type_str(env, arg))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+        },
+        _ => unreachable!("unexpected type `{:?}` in ABI", ty),
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compilation for tools like language servers, which need the stackless bytecode of the
+//! functions of a package even while some of them have errors. Unlike `run_move_compiler`,
+//! which stops after the first phase with errors, the analysis generates code for all
+//! functions of the target modules without errors, and returns it together with all
+//! diagnostics. The bytecode pipeline is not run, since its checkers assume the whole program
+//! is well-formed, so the targets carry the code as generated, without annotations.
+
+use crate::{
+    bytecode_generator,
+    diagnostics::{self, CompilerDiagnostic},
+    lint, run_checker, Options,
+};
+use codespan::Span;
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use move_model::model::{FunId, GlobalEnv, Loc, QualifiedId};
+use move_stackless_bytecode::function_target_pipeline::{FunctionTargetsHolder, FunctionVariant};
+use std::collections::BTreeSet;
+
+/// The result of `run_move_compiler_for_analysis`.
+pub struct AnalysisOutput {
+    /// The global environment. Empty if the checker could not build a model, as when source
+    /// files cannot be read.
+    pub env: GlobalEnv,
+    /// The baseline variant of the stackless bytecode of the functions of the target modules
+    /// without errors.
+    pub targets: FunctionTargetsHolder,
+    /// All diagnostics of the checker and the code generator, regardless of
+    /// `Options::report_severity`.
+    pub diagnostics: Vec<CompilerDiagnostic>,
+    /// The functions of the target modules without code in `targets`, since they or the code
+    /// generated for them have errors. Results derived from their code in earlier runs are
+    /// stale.
+    pub missing: BTreeSet<QualifiedId<FunId>>,
+}
+
+impl AnalysisOutput {
+    /// Returns true if code was generated for the function.
+    pub fn has_code(&self, id: QualifiedId<FunId>) -> bool {
+        self.targets
+            .has_target(&self.env.get_function(id), &FunctionVariant::Baseline)
+    }
+}
+
+/// Runs the checker and generates code for the functions of the target modules without errors.
+/// Never fails: errors which keep the checker from building a model are returned as
+/// diagnostics, with an empty env.
+pub fn run_move_compiler_for_analysis(options: Options) -> AnalysisOutput {
+    let env = match run_checker(options) {
+        Ok(env) => env,
+        Err(err) => {
+            return AnalysisOutput {
+                env: GlobalEnv::new(),
+                targets: FunctionTargetsHolder::default(),
+                diagnostics: vec![CompilerDiagnostic::error(err.to_string())],
+                missing: BTreeSet::new(),
+            }
+        },
+    };
+    let mut targets = FunctionTargetsHolder::default();
+    let mut missing = BTreeSet::new();
+    let error_locs = error_locs(&env);
+    for module in env.get_modules().filter(|m| m.is_target()) {
+        for fun in module.get_functions() {
+            let id = fun.get_qualified_id();
+            if fun.is_native() {
+                continue;
+            }
+            // Code for functions with errors, or whose calls were not resolved, would be built
+            // from invalid expressions, so generation is not even attempted.
+            let fun_loc = fun.get_loc();
+            if fun.get_called_functions().is_none()
+                || error_locs.iter().any(|loc| fun_loc.is_enclosing(loc))
+            {
+                missing.insert(id);
+                continue;
+            }
+            let error_count = env.error_count();
+            let data = bytecode_generator::generate_bytecode(&env, id);
+            if env.error_count() > error_count {
+                missing.insert(id);
+                continue;
+            }
+            targets.insert_target_data(&id, FunctionVariant::Baseline, data);
+        }
+    }
+    let options = env.get_extension::<Options>().unwrap_or_default();
+    lint::suppress_allowed_lints(&env);
+    lint::filter_warning_categories(&env, &options);
+    env.deduplicate_diags();
+    diagnostics::sort_diags(&env);
+    let diagnostics = diagnostics::collect_diags(&env, Severity::Help);
+    AnalysisOutput {
+        env,
+        targets,
+        diagnostics,
+        missing,
+    }
+}
+
+/// Returns the locations of the primary labels of the errors in the env.
+fn error_locs(env: &GlobalEnv) -> Vec<Loc> {
+    env.get_unreported_diags(|d| d.severity >= Severity::Error)
+        .into_iter()
+        .flat_map(|d| d.labels)
+        .filter(|l| l.style == LabelStyle::Primary)
+        .map(|l| {
+            Loc::new(
+                l.file_id,
+                Span::new(l.range.start as u32, l.range.end as u32),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,169 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Writing of the compiled units into the output directory, if `Options::output_dir` is set.
+//! A module is written to `<output_dir>/<address>/<module>.mv`, with the address as a hex
+//! literal like `0x42`, and a script to `<output_dir>/scripts/<script>.mv`. The source map of
+//! each unit is written next to it, with extension `.mvsm`, and, if `Options::emit_abi` is
+//! set, the ABI of each module as `<module>.json`, in the format described in `abi`.
+//!
+//! For embedders which do not need files, `CompiledArtifacts` holds the serialized units in
+//! memory, as returned by `compile_to_bytes`.
+
+use crate::{abi, diagnostics::CompilerDiagnostic, Options};
+use anyhow::Context;
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::files::{MOVE_COMPILED_EXTENSION, SOURCE_MAP_EXTENSION};
+use move_compiler::compiled_unit::{
+    AnnotatedCompiledUnit, CompiledUnitEnum, NamedCompiledModule, NamedCompiledScript,
+};
+use move_core_types::language_storage::ModuleId;
+use move_model::model::GlobalEnv;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// The directory below the output directory into which scripts are written.
+const SCRIPTS_DIR: &str = "scripts";
+
+/// The serialized units of a compilation.
+#[derive(Clone, Debug)]
+pub struct CompiledArtifacts {
+    /// The compiled modules, by id.
+    pub modules: BTreeMap<ModuleId, SerializedUnit>,
+    /// The compiled scripts, by the name of their function.
+    pub scripts: BTreeMap<String, SerializedUnit>,
+    /// The diagnostics of the compilation, like warnings.
+    pub diagnostics: Vec<CompilerDiagnostic>,
+}
+
+/// A serialized module or script, with its source map.
+#[derive(Clone, Debug)]
+pub struct SerializedUnit {
+    pub code: Vec<u8>,
+    pub source_map: SourceMap,
+}
+
+impl CompiledArtifacts {
+    /// Serializes the units for the given bytecode version.
+    pub fn new(
+        units: &[AnnotatedCompiledUnit],
+        version: u32,
+        diagnostics: Vec<CompilerDiagnostic>,
+    ) -> anyhow::Result<Self> {
+        let mut modules = BTreeMap::new();
+        let mut scripts = BTreeMap::new();
+        for unit in units {
+            match unit {
+                CompiledUnitEnum::Module(annotated_module) => {
+                    let NamedCompiledModule {
+                        module, source_map, ..
+                    } = &annotated_module.named_module;
+                    let mut code = vec![];
+                    module.serialize_for_version(Some(version), &mut code)?;
+                    modules.insert(module.self_id(), SerializedUnit {
+                        code,
+                        source_map: source_map.clone(),
+                    });
+                },
+                CompiledUnitEnum::Script(annotated_script) => {
+                    let NamedCompiledScript {
+                        name,
+                        script,
+                        source_map,
+                        ..
+                    } = &annotated_script.named_script;
+                    let mut code = vec![];
+                    script.serialize_for_version(Some(version), &mut code)?;
+                    scripts.insert(name.to_string(), SerializedUnit {
+                        code,
+                        source_map: source_map.clone(),
+                    });
+                },
+            }
+        }
+        Ok(Self {
+            modules,
+            scripts,
+            diagnostics,
+        })
+    }
+}
+
+/// Writes the units, and their source maps and ABIs, into the directory, returning the paths
+/// of the written files. Directories are created as needed, and files are synced to disk.
+pub fn write_artifacts(
+    env: &GlobalEnv,
+    options: &Options,
+    units: &[AnnotatedCompiledUnit],
+    dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut abis = if options.emit_abi.is_some() {
+        abi::module_abis(env)
+            .into_iter()
+            .map(|abi| ((abi.address.clone(), abi.name.clone()), abi))
+            .collect()
+    } else {
+        BTreeMap::new()
+    };
+    let mut paths = vec![];
+    for unit in units {
+        let (unit_dir, name, code, source_map) = match unit {
+            CompiledUnitEnum::Module(annotated_module) => {
+                let NamedCompiledModule {
+                    address,
+                    name,
+                    module,
+                    source_map,
+                    ..
+                } = &annotated_module.named_module;
+                let mut code = vec![];
+                module.serialize_for_version(Some(module.version), &mut code)?;
+                let address = address.into_inner().to_hex_literal();
+                let unit_dir = dir.join(&address);
+                if let Some(abi) = abis.remove(&(address, name.to_string())) {
+                    let path = unit_dir.join(format!("{}.json", name));
+                    let json = serde_json::to_string_pretty(&abi)? + "\n";
+                    write_file(&path, json.as_bytes())?;
+                    paths.push(path)
+                }
+                (unit_dir, name, code, source_map)
+            },
+            CompiledUnitEnum::Script(annotated_script) => {
+                let NamedCompiledScript {
+                    name,
+                    script,
+                    source_map,
+                    ..
+                } = &annotated_script.named_script;
+                let mut code = vec![];
+                script.serialize_for_version(Some(script.version), &mut code)?;
+                (dir.join(SCRIPTS_DIR), name, code, source_map)
+            },
+        };
+        let path = unit_dir.join(format!("{}.{}", name, MOVE_COMPILED_EXTENSION));
+        write_file(&path, &code)?;
+        paths.push(path);
+        let path = unit_dir.join(format!("{}.{}", name, SOURCE_MAP_EXTENSION));
+        write_file(&path, &bcs::to_bytes(source_map)?)?;
+        paths.push(path)
+    }
+    Ok(paths)
+}
+
+/// Writes the contents to the file at the path, creating its directory if needed, and syncs
+/// the file to disk. Errors mention the path.
+fn write_file(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let write = || -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?
+        }
+        let mut file = fs::File::create(path)?;
+        file.write_all(contents)?;
+        file.sync_all()
+    };
+    write().with_context(|| format!("cannot write `{}`", path.display()))
+}
@@ -0,0 +1,236 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compile-time configuration of the build with `Options::build_config`, which sets named
+//! flags to `true` or `false`. An item annotated with `#[cfg_attr(<flag>)]` is only part of the
+//! build if the flag is true, and an item annotated with `#[cfg_attr(not(<flag>))]` only if it
+//! is false. If an attribute lists several conditions, as in `#[cfg_attr(testnet, not(fast))]`,
+//! all of them must hold. This way, variants of modules, like for testnet and mainnet, can be
+//! built from the same sources.
+//!
+//! Excluded items are removed from the parsed sources, so they are not part of the model, and
+//! the checks of visibility and calls reflect the actual build. Errors about calls of excluded
+//! functions get a note explaining the exclusion. Flags which are not set, and conditions
+//! which are neither a flag nor its negation, are reported as errors.
+
+use crate::diagnostic_codes::{DiagnosticCode, MALFORMED_CFG_ATTR, UNKNOWN_BUILD_CONFIG_FLAG};
+use move_compiler::{
+    parser::{
+        ast::{self as P, Attribute_},
+        filter::FilterContext,
+    },
+    shared::Name,
+};
+use move_ir_types::location::Loc as MoveIrLoc;
+use move_model::model::GlobalEnv;
+use move_symbol_pool::Symbol;
+use std::collections::BTreeMap;
+
+/// The name of the attribute which includes or excludes items.
+pub const CFG_ATTR: &str = "cfg_attr";
+
+/// The name of the negation of a condition.
+const NOT: &str = "not";
+
+/// Removes the parsed items whose `cfg_attr` conditions do not hold. Problems with the
+/// attributes are collected, and reported with `BuildConfigFilter::report` once the model is
+/// built.
+pub(crate) struct BuildConfigFilter<'a> {
+    config: &'a BTreeMap<String, bool>,
+    /// The module whose members are filtered.
+    module: Option<Symbol>,
+    /// The excluded functions, by module and name, with the flag which excluded them and its
+    /// value.
+    excluded_functions: BTreeMap<(Option<Symbol>, Symbol), (String, bool)>,
+    errors: Vec<(DiagnosticCode, MoveIrLoc, String)>,
+}
+
+impl<'a> BuildConfigFilter<'a> {
+    pub fn new(config: &'a BTreeMap<String, bool>) -> Self {
+        Self {
+            config,
+            module: None,
+            excluded_functions: BTreeMap::new(),
+            errors: vec![],
+        }
+    }
+
+    /// Reports the problems with the attributes, and adds notes to the errors about calls of
+    /// excluded functions.
+    pub fn report(self, env: &GlobalEnv) {
+        env.add_diag_notes(|diag| {
+            let Some(target) = diag
+                .message
+                .strip_prefix("no function named `")
+                .and_then(|rest| rest.strip_suffix("` found"))
+            else {
+                return vec![];
+            };
+            let (module, name) = match target.rsplit_once("::") {
+                Some((module, name)) => (module.rsplit("::").next(), name),
+                None => (None, target),
+            };
+            self.excluded_functions
+                .iter()
+                .filter(|((m, n), _)| {
+                    n.as_str() == name
+                        && module.map_or(true, |module| m.is_some_and(|m| m.as_str() == module))
+                })
+                .map(|(_, (flag, value))| {
+                    format!(
+                        "`{}` is excluded from the build, since flag `{}` is set to {}",
+                        name, flag, value
+                    )
+                })
+                .take(1)
+                .collect()
+        });
+        for (code, loc, msg) in self.errors {
+            code.error(env, &env.to_loc(&loc), &msg)
+        }
+    }
+
+    /// Returns the flag and its value which exclude an item with the attributes, if any.
+    fn excluding_flag(&mut self, attrs: &[P::Attributes]) -> Option<(String, bool)> {
+        let mut excluding = None;
+        for attr in attrs.iter().flat_map(|attrs| &attrs.value) {
+            if attr.value.attribute_name().value.as_str() != CFG_ATTR {
+                continue;
+            }
+            match &attr.value {
+                Attribute_::Parameterized(_, conditions) if !conditions.value.is_empty() => {
+                    for condition in &conditions.value {
+                        if let Some(failed) = self.failed_condition(condition) {
+                            excluding.get_or_insert(failed);
+                        }
+                    }
+                },
+                _ => self.errors.push((
+                    MALFORMED_CFG_ATTR,
+                    attr.loc,
+                    format!(
+                        "expected conditions, as in `#[{}(<flag>)]` or `#[{}(not(<flag>))]`",
+                        CFG_ATTR, CFG_ATTR
+                    ),
+                )),
+            }
+        }
+        excluding
+    }
+
+    /// Returns the flag and its value if the condition does not hold. Conditions with errors
+    /// hold, so the errors are not followed by errors about missing items.
+    fn failed_condition(&mut self, condition: &P::Attribute) -> Option<(String, bool)> {
+        match &condition.value {
+            Attribute_::Name(flag) => {
+                let value = self.flag_value(flag)?;
+                (!value).then(|| (flag.value.to_string(), value))
+            },
+            Attribute_::Parameterized(not, negated)
+                if not.value.as_str() == NOT && negated.value.len() == 1 =>
+            {
+                match &negated.value[0].value {
+                    Attribute_::Name(flag) => {
+                        let value = self.flag_value(flag)?;
+                        value.then(|| (flag.value.to_string(), value))
+                    },
+                    _ => {
+                        self.malformed_condition(condition.loc);
+                        None
+                    },
+                }
+            },
+            _ => {
+                self.malformed_condition(condition.loc);
+                None
+            },
+        }
+    }
+
+    /// Returns the value of the flag, reporting an error if it is not set.
+    fn flag_value(&mut self, flag: &Name) -> Option<bool> {
+        let value = self.config.get(flag.value.as_str()).copied();
+        if value.is_none() {
+            let known = if self.config.is_empty() {
+                "no flags are set".to_owned()
+            } else {
+                format!(
+                    "set flags are {}",
+                    self.config
+                        .keys()
+                        .map(|flag| format!("`{}`", flag))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            self.errors.push((
+                UNKNOWN_BUILD_CONFIG_FLAG,
+                flag.loc,
+                format!(
+                    "flag `{}` is not set in the build configuration ({})",
+                    flag.value, known
+                ),
+            ))
+        }
+        value
+    }
+
+    fn malformed_condition(&mut self, loc: MoveIrLoc) {
+        self.errors.push((
+            MALFORMED_CFG_ATTR,
+            loc,
+            "expected a flag, as in `testnet`, or its negation, as in `not(testnet)`".to_owned(),
+        ))
+    }
+}
+
+impl<'a> FilterContext for BuildConfigFilter<'a> {
+    fn should_remove_by_attributes(
+        &mut self,
+        attrs: &[P::Attributes],
+        _is_source_def: bool,
+    ) -> bool {
+        self.excluding_flag(attrs).is_some()
+    }
+
+    fn filter_map_module(
+        &mut self,
+        module_def: P::ModuleDefinition,
+        is_source_def: bool,
+    ) -> Option<P::ModuleDefinition> {
+        self.module = Some(module_def.name.0.value);
+        if self.should_remove_by_attributes(&module_def.attributes, is_source_def) {
+            None
+        } else {
+            Some(module_def)
+        }
+    }
+
+    fn filter_map_script(
+        &mut self,
+        script_def: P::Script,
+        is_source_def: bool,
+    ) -> Option<P::Script> {
+        self.module = None;
+        if self.should_remove_by_attributes(&script_def.attributes, is_source_def) {
+            None
+        } else {
+            Some(script_def)
+        }
+    }
+
+    fn filter_map_function(
+        &mut self,
+        function_def: P::Function,
+        _is_source_def: bool,
+    ) -> Option<P::Function> {
+        match self.excluding_flag(&function_def.attributes) {
+            Some(excluding) => {
+                self.excluded_functions
+                    .insert((self.module, function_def.name.0.value), excluding);
+                None
+            },
+            None => Some(function_def),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Line-based diffs of texts, used to dump only the changes which a processor of the bytecode
+//! pipeline makes to functions.
+
+use prettydiff::{basic::DiffOp, diff_lines};
+
+/// The number of unchanged lines shown before and after a change.
+const CONTEXT_LINES: usize = 2;
+
+/// Returns a line-based diff of the texts, which is empty if the texts are equal. Removed lines
+/// are prefixed by `- `, added lines by `+ `, and unchanged lines by two spaces. Of a run of
+/// unchanged lines, only the lines next to changes are shown.
+pub fn diff_text(old: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    let mut result = vec![];
+    let push_lines = |result: &mut Vec<String>, lines: &[&str], prefix: &str| {
+        for line in lines {
+            result.push(format!("{}{}", prefix, line).trim_end().to_string())
+        }
+    };
+    let diff = diff_lines(old, new);
+    let ops = diff.diff();
+    for (idx, op) in ops.iter().enumerate() {
+        match op {
+            DiffOp::Equal(lines) => {
+                let before = if idx > 0 { CONTEXT_LINES } else { 0 };
+                let after = if idx + 1 < ops.len() {
+                    CONTEXT_LINES
+                } else {
+                    0
+                };
+                if lines.len() <= before + after {
+                    push_lines(&mut result, lines, "  ")
+                } else {
+                    push_lines(&mut result, &lines[..before], "  ");
+                    result.push("  ...".to_string());
+                    push_lines(&mut result, &lines[lines.len() - after..], "  ");
+                }
+            },
+            DiffOp::Insert(lines) => push_lines(&mut result, lines, "+ "),
+            DiffOp::Remove(lines) => push_lines(&mut result, lines, "- "),
+            DiffOp::Replace(old, new) => {
+                push_lines(&mut result, old, "- ");
+                push_lines(&mut result, new, "+ ");
+            },
+        }
+    }
+    result.push(String::new());
+    result.join("\n")
+}
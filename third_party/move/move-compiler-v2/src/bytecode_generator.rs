@@ -2,13 +2,14 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::diagnostic_codes::{self, DiagnosticCode};
 use codespan_reporting::diagnostic::Severity;
 use ethnum::U256;
 use move_model::{
     ast::{Exp, ExpData, Operation, Pattern, TempIndex, Value},
     model::{
-        FieldId, FunId, FunctionEnv, GlobalEnv, NodeId, Parameter, QualifiedId, QualifiedInstId,
-        StructId,
+        FieldId, FunId, FunctionEnv, GlobalEnv, Loc, NodeId, Parameter, QualifiedId,
+        QualifiedInstId, StructId,
     },
     symbol::Symbol,
     ty::{PrimitiveType, ReferenceKind, Type},
@@ -21,7 +22,7 @@ use move_stackless_bytecode::{
     stackless_bytecode_generator::BytecodeGeneratorContext,
 };
 use num::ToPrimitive;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 // ======================================================================================
 // Entry
@@ -41,11 +42,15 @@ pub fn generate_bytecode(env: &GlobalEnv, fid: QualifiedId<FunId>) -> FunctionDa
         reference_mode_kind: ReferenceKind::Immutable,
         results: vec![],
         code: vec![],
+        declared_locals: DeclaredLocals::default(),
+        spec_blocks: SpecBlocks::default(),
     };
     let mut scope = BTreeMap::new();
-    for Parameter(name, ty) in gen.func_env.get_parameters() {
+    for (idx, Parameter(name, ty)) in gen.func_env.get_parameters().into_iter().enumerate() {
         let temp = gen.new_temp(ty);
         scope.insert(name, temp);
+        let loc = gen.func_env.get_parameter_loc(idx);
+        gen.declared_locals.vars.insert(temp, (name, loc));
     }
     for ty in gen.func_env.get_result_type().flatten() {
         let temp = gen.new_temp(ty);
@@ -70,6 +75,8 @@ pub fn generate_bytecode(env: &GlobalEnv, fid: QualifiedId<FunId>) -> FunctionDa
         reference_mode_kind: _,
         results: _,
         code,
+        declared_locals,
+        spec_blocks,
     } = gen;
     let BytecodeGeneratorContext {
         loop_unrolling,
@@ -77,7 +84,7 @@ pub fn generate_bytecode(env: &GlobalEnv, fid: QualifiedId<FunId>) -> FunctionDa
         location_table,
         ..
     } = context;
-    FunctionData::new(
+    let mut data = FunctionData::new(
         &func_env,
         code,
         temps,
@@ -87,7 +94,31 @@ pub fn generate_bytecode(env: &GlobalEnv, fid: QualifiedId<FunId>) -> FunctionDa
         vec![],
         loop_unrolling,
         loop_invariants,
-    )
+    );
+    data.annotations.set(declared_locals, true);
+    data.annotations.set(spec_blocks, true);
+    data
+}
+
+/// An annotation attached by the generator to the function data, describing the user declared
+/// variables of the function.
+#[derive(Clone, Debug, Default)]
+pub struct DeclaredLocals {
+    /// Maps the temporaries representing parameters and let-bound variables to their name
+    /// and declaration location.
+    pub vars: BTreeMap<TempIndex, (Symbol, Loc)>,
+    /// Temporaries whose value is explicitly discarded, as in `_ = x`. No code is generated
+    /// for such assignments.
+    pub discarded: BTreeSet<TempIndex>,
+}
+
+/// An annotation attached by the generator to the function data, relating the conditions of
+/// inline spec blocks to the blocks they come from.
+#[derive(Clone, Debug, Default)]
+pub struct SpecBlocks {
+    /// Maps the attribute ids of the `Prop` instructions generated for the conditions of a spec
+    /// block to the node id of the block.
+    pub conditions: BTreeMap<AttrId, NodeId>,
 }
 
 // ======================================================================================
@@ -121,6 +152,10 @@ struct Generator<'env> {
     results: Vec<TempIndex>,
     /// The bytecode, as generated so far.
     code: Vec<Bytecode>,
+    /// The user declared variables, attached as an annotation to the generated code.
+    declared_locals: DeclaredLocals,
+    /// The spec blocks of the conditions, attached as an annotation to the generated code.
+    spec_blocks: SpecBlocks,
 }
 
 type Scope = BTreeMap<Symbol, TempIndex>;
@@ -201,6 +236,7 @@ impl<'env> Generator<'env> {
         if matches!(ty, Type::Tuple(..)) {
             self.error(
                 id,
+                diagnostic_codes::TUPLE_TO_SINGLE_VARIABLE,
                 format!("cannot assign tuple type `{}` to single variable (use `(a, b, ..) = ..` instead)",
                         ty.display(&self.env().get_type_display_ctx()))
             )
@@ -257,7 +293,8 @@ impl<'env> Generator<'env> {
 
     /// Finds the temporary index assigned to the local.
     fn find_local(&self, id: NodeId, sym: Symbol) -> TempIndex {
-        for scope in &self.scopes {
+        // Search innermost scope first, so shadowing declarations take precedence.
+        for scope in self.scopes.iter().rev() {
             if let Some(idx) = scope.get(&sym) {
                 return *idx;
             }
@@ -271,20 +308,25 @@ impl<'env> Generator<'env> {
         &self.temps[temp]
     }
 
-    /// Report an error at the location associated with the node.
-    fn error(&self, id: NodeId, msg: impl AsRef<str>) {
-        self.diag(id, Severity::Error, msg)
+    /// Report an error with the given code at the location associated with the node.
+    fn error(&self, id: NodeId, code: DiagnosticCode, msg: impl AsRef<str>) {
+        self.diag(id, code, Severity::Error, msg)
     }
 
     /// Report an (internal) error at the location associated with the node.
     fn internal_error(&self, id: NodeId, msg: impl AsRef<str>) {
-        self.diag(id, Severity::Bug, msg)
+        self.diag(
+            id,
+            diagnostic_codes::BYTECODE_GEN_INTERNAL,
+            Severity::Bug,
+            msg,
+        )
     }
 
-    fn diag(&self, id: NodeId, severity: Severity, msg: impl AsRef<str>) {
+    fn diag(&self, id: NodeId, code: DiagnosticCode, severity: Severity, msg: impl AsRef<str>) {
         let env = self.env();
         let loc = env.get_node_loc(id);
-        env.diag(severity, &loc, msg.as_ref())
+        code.diag(env, severity, &loc, msg.as_ref())
     }
 }
 
@@ -325,6 +367,8 @@ impl<'env> Generator<'env> {
                     let ty = self.get_node_type(id);
                     let temp = self.new_temp_with_valid_type(id, ty);
                     scope.insert(sym, temp);
+                    let loc = self.env().get_node_loc(id);
+                    self.declared_locals.vars.insert(temp, (sym, loc));
                 }
                 // If there is a binding, assign the pattern
                 if let Some(binding) = opt_binding {
@@ -341,6 +385,7 @@ impl<'env> Generator<'env> {
                 if !self.temp_type(lhs_temp).is_mutable_reference() {
                     self.error(
                         lhs.node_id(),
+                        diagnostic_codes::EXPECTED_MUTABLE_REFERENCE,
                         format!(
                             "expected `&mut` but found `{}`",
                             self.temp_type(lhs_temp)
@@ -401,11 +446,18 @@ impl<'env> Generator<'env> {
                     };
                     self.emit_with(*id, |attr| Bytecode::Jump(attr, target))
                 } else {
-                    self.error(*id, "missing enclosing loop statement")
+                    self.error(
+                        *id,
+                        diagnostic_codes::MISSING_ENCLOSING_LOOP,
+                        "missing enclosing loop statement",
+                    )
                 }
             },
-            ExpData::SpecBlock(_, spec) => {
+            ExpData::SpecBlock(id, spec) => {
                 let (mut code, mut update_map) = self.context.generate_spec(&self.func_env, spec);
+                for bc in &code {
+                    self.spec_blocks.conditions.insert(bc.get_attr_id(), *id);
+                }
                 self.code.append(&mut code);
                 self.func_env
                     .get_mut_spec()
@@ -480,7 +532,7 @@ impl<'env> Generator<'env> {
     fn gen_local(&mut self, targets: Vec<TempIndex>, id: NodeId, name: Symbol) {
         let target = self.require_unary_target(id, targets);
         let attr = self.new_loc_attr(id);
-        for scope in &self.scopes {
+        for scope in self.scopes.iter().rev() {
             if let Some(temp) = scope.get(&name) {
                 self.emit(Bytecode::Assign(attr, target, *temp, AssignKind::Move));
                 return;
@@ -879,6 +931,7 @@ impl<'env> Generator<'env> {
             // of such anonymous (stack) locations, but cannot mutate them.
             self.error(
                 arg.node_id(),
+                diagnostic_codes::INVALID_MUTABLE_BORROW_OPERAND,
                 "operand to `&mut` must be a field selection (`&mut s.f`) or a local (`&mut name`)",
             );
         } else {
@@ -927,6 +980,7 @@ impl<'env> Generator<'env> {
                     let struct_name = self.env().get_struct(struct_id).get_full_name_str();
                     self.error(
                         oper.node_id(),
+                        diagnostic_codes::INVALID_MUTABLE_BORROW_OPERAND,
                         format!(
                             "operand to `&mut _.{}` must have type `&mut {}` or be a local of type `{}`",
                             field_name.display(self.env().symbol_pool()),
@@ -994,6 +1048,7 @@ impl<'env> Generator<'env> {
         {
             self.error(
                 oper.node_id(),
+                diagnostic_codes::EXPECTED_MUTABLE_REFERENCE,
                 format!(
                     "expected `&mut` but found `{}`",
                     self.temp_type(oper_temp)
@@ -1076,6 +1131,7 @@ impl<'env> Generator<'env> {
             },
             _ => self.error(
                 id,
+                diagnostic_codes::INVALID_TUPLE_ASSIGNMENT,
                 "assignment to tuple must be tuple itself or a function call",
             ),
         }
@@ -1090,7 +1146,8 @@ impl<'env> Generator<'env> {
     ) {
         match pat {
             Pattern::Wildcard(_) => {
-                // Nothing to do
+                // Nothing to do, except to remember the value is discarded
+                self.declared_locals.discarded.insert(arg);
             },
             Pattern::Var(var_id, sym) => {
                 let local = self.find_local_for_pattern(*var_id, *sym, next_scope);
@@ -1110,7 +1167,11 @@ impl<'env> Generator<'env> {
                     self.gen_assign_from_temp(cont_id, &cont_pat, cont_temp, next_scope)
                 }
             },
-            Pattern::Tuple(id, _) => self.error(*id, "tuple not allowed here"),
+            Pattern::Tuple(id, _) => self.error(
+                *id,
+                diagnostic_codes::INVALID_TUPLE_ASSIGNMENT,
+                "tuple not allowed here",
+            ),
             Pattern::Error(_) => self.internal_error(id, "unexpected error pattern"),
         }
     }
@@ -1131,6 +1192,7 @@ impl<'env> Generator<'env> {
                 // Wildcard pattern: we need to create a temporary to receive the value, even
                 // if its dropped afterwards.
                 let temp = self.new_temp(self.get_node_type(*id));
+                self.declared_locals.discarded.insert(temp);
                 (temp, None)
             },
             Pattern::Var(id, sym) => {
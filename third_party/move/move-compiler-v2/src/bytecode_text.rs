@@ -0,0 +1,941 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A textual format for stackless bytecode which can be parsed back into function targets.
+//!
+//! The format is the one of the bytecode dumps. A function variant is printed as a line
+//! `[variant <variant>]`, followed by the function header with the parameters and their types,
+//! one `var $t<N>: <type>` line for each other temporary, and one `<offset>: <instruction>`
+//! line for each instruction. Branch targets are either the offset of a `label` instruction, or
+//! `L<N>` for a label which is not defined in the code. Annotations and debug comments are
+//! printed as lines starting with `#`, and are ignored by the parser, as are the separator lines
+//! of dump files starting with `=`.
+//!
+//! Names of modules, structs and functions are printed without addresses, and are resolved
+//! against the global environment when parsing. If a name is ambiguous, the one in the module of
+//! the function being parsed is preferred. Names of temporaries, as in `$t0|x: u64`, are
+//! derived from the environment when printing, and only recorded in the name-to-index map of
+//! the parsed data. Attribute ids are assigned in order of the instructions, and all
+//! instructions get the location of their function. The conditions of specification properties
+//! cannot be parsed, and are resolved against the inline spec blocks of the function by their
+//! printed form. The other instructions of the prover, like memory labels, are not supported by
+//! the parser.
+
+use anyhow::{anyhow, bail};
+use ethnum::U256;
+use move_core_types::account_address::AccountAddress;
+use move_model::{
+    ast::{Address, Exp, ExpData, TempIndex},
+    model::{FunId, FunctionEnv, GlobalEnv, ModuleId, QualifiedId, StructId},
+    ty::{PrimitiveType, ReferenceKind, Type},
+};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant, VerificationFlavor},
+    stackless_bytecode::{
+        AbortAction, AssignKind, AttrId, Bytecode, Constant, HavocKind, Label, Operation, PropKind,
+    },
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Prints a function variant in the textual format.
+pub fn print_target(variant: &FunctionVariant, target: &FunctionTarget) -> String {
+    format!("[variant {}]\n{}", variant, target)
+}
+
+/// Prints all function variants of the target modules in the textual format. Functions
+/// without code are skipped, unless they are native. With `with_annotations`, the annotations
+/// relevant for tests are printed as comments.
+pub fn print_targets(
+    env: &GlobalEnv,
+    targets: &FunctionTargetsHolder,
+    with_annotations: bool,
+) -> String {
+    let funs = targets.get_funs().collect::<BTreeSet<_>>();
+    let mut text = String::new();
+    for module_env in env.get_modules().filter(|m| m.is_target()) {
+        for fun_env in module_env.get_functions() {
+            if fun_env.is_inline() || !funs.contains(&fun_env.get_qualified_id()) {
+                continue;
+            }
+            for (variant, target) in targets.get_targets(&fun_env) {
+                if target.data.code.is_empty() && !fun_env.is_native() {
+                    continue;
+                }
+                if with_annotations {
+                    target.register_annotation_formatters_for_test();
+                }
+                text.push_str(&format!("\n{}\n", print_target(&variant, &target)))
+            }
+        }
+    }
+    text
+}
+
+/// Parses function variants in the textual format, as printed by `print_targets` or found in
+/// bytecode dumps. The functions must be declared in the environment.
+pub fn parse_targets(env: &GlobalEnv, text: &str) -> anyhow::Result<FunctionTargetsHolder> {
+    let parser = Parser::new(env);
+    let mut targets = FunctionTargetsHolder::default();
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#') && !line.starts_with('='));
+    while let Some((line_no, line)) = lines.next() {
+        let at_line = |err: anyhow::Error| anyhow!("line {}: {}", line_no, err);
+        let variant = parse_variant(line).map_err(at_line)?;
+        let Some((header_no, header)) = lines.next() else {
+            bail!("line {}: expected function after variant", line_no)
+        };
+        let mut body = vec![];
+        if !header.ends_with(';') {
+            loop {
+                match lines.next() {
+                    Some((_, "}")) => break,
+                    Some(line) => body.push(line),
+                    None => bail!("line {}: function is not terminated by `}}`", header_no),
+                }
+            }
+        }
+        let (fun_env, data) = parser.parse_function(variant.clone(), (header_no, header), &body)?;
+        let id = fun_env.get_qualified_id();
+        if targets.get_data(&id, &variant).is_some() {
+            bail!(
+                "line {}: variant `{}` of function `{}` is defined twice",
+                line_no,
+                variant,
+                fun_env.get_full_name_str()
+            )
+        }
+        targets.insert_target_data(&id, variant, data);
+    }
+    Ok(targets)
+}
+
+fn parse_variant(line: &str) -> anyhow::Result<FunctionVariant> {
+    let name = line
+        .strip_prefix("[variant ")
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or_else(|| anyhow!("expected `[variant <variant>]`"))?;
+    Ok(match name {
+        "baseline" => FunctionVariant::Baseline,
+        "optimized" => FunctionVariant::Optimized,
+        "verification" => FunctionVariant::Verification(VerificationFlavor::Regular),
+        _ => bail!("unsupported variant `{}`", name),
+    })
+}
+
+/// Operations which are printed as a fixed text, directly followed by the arguments.
+const SIMPLE_OPERATIONS: &[(&str, Operation)] = &[
+    ("borrow_local", Operation::BorrowLoc),
+    ("uninit", Operation::Uninit),
+    ("destroy", Operation::Destroy),
+    ("read_ref", Operation::ReadRef),
+    ("write_ref", Operation::WriteRef),
+    ("freeze_ref", Operation::FreezeRef),
+    ("vector", Operation::Vector),
+    ("unpack_ref", Operation::UnpackRef),
+    ("pack_ref", Operation::PackRef),
+    ("pack_ref_deep", Operation::PackRefDeep),
+    ("unpack_ref_deep", Operation::UnpackRefDeep),
+    ("havoc[val]", Operation::Havoc(HavocKind::Value)),
+    ("havoc[mut]", Operation::Havoc(HavocKind::MutationValue)),
+    ("havoc[mut_all]", Operation::Havoc(HavocKind::MutationAll)),
+    ("stop", Operation::Stop),
+    ("trace_abort", Operation::TraceAbort),
+    ("emit_event", Operation::EmitEvent),
+    ("event_store_diverge", Operation::EventStoreDiverge),
+    ("(u8)", Operation::CastU8),
+    ("(u16)", Operation::CastU16),
+    ("(u32)", Operation::CastU32),
+    ("(u64)", Operation::CastU64),
+    ("(u128)", Operation::CastU128),
+    ("(u256)", Operation::CastU256),
+    ("!", Operation::Not),
+    ("+", Operation::Add),
+    ("-", Operation::Sub),
+    ("*", Operation::Mul),
+    ("/", Operation::Div),
+    ("%", Operation::Mod),
+    ("|", Operation::BitOr),
+    ("&", Operation::BitAnd),
+    ("^", Operation::Xor),
+    ("<<", Operation::Shl),
+    (">>", Operation::Shr),
+    ("<", Operation::Lt),
+    (">", Operation::Gt),
+    ("<=", Operation::Le),
+    (">=", Operation::Ge),
+    ("||", Operation::Or),
+    ("&&", Operation::And),
+    ("==", Operation::Eq),
+    ("!=", Operation::Neq),
+];
+
+/// Resolves the names of functions and structs, as printed in the textual format.
+struct Parser<'env> {
+    env: &'env GlobalEnv,
+    functions: BTreeMap<String, Vec<QualifiedId<FunId>>>,
+    structs: BTreeMap<String, Vec<QualifiedId<StructId>>>,
+}
+
+/// The state of parsing a function body.
+struct FunctionContext<'env> {
+    fun_env: FunctionEnv<'env>,
+    local_types: Vec<Type>,
+    /// The labels defined at code offsets.
+    labels: BTreeMap<usize, Label>,
+}
+
+impl<'env> Parser<'env> {
+    fn new(env: &'env GlobalEnv) -> Self {
+        let mut functions = BTreeMap::<String, Vec<_>>::new();
+        let mut structs = BTreeMap::<String, Vec<_>>::new();
+        for module_env in env.get_modules() {
+            let module_name = module_env.get_name().display(env).to_string();
+            for fun_env in module_env.get_functions() {
+                let name = format!("{}::{}", module_name, fun_env.get_name_str());
+                functions
+                    .entry(name)
+                    .or_default()
+                    .push(fun_env.get_qualified_id())
+            }
+            for struct_env in module_env.get_structs() {
+                let name = format!(
+                    "{}::{}",
+                    module_name,
+                    struct_env.get_name().display(env.symbol_pool())
+                );
+                structs
+                    .entry(name)
+                    .or_default()
+                    .push(struct_env.get_qualified_id())
+            }
+        }
+        Self {
+            env,
+            functions,
+            structs,
+        }
+    }
+
+    /// Parses a function from its header and the lines of its body, without the closing brace.
+    fn parse_function(
+        &self,
+        variant: FunctionVariant,
+        header: (usize, &str),
+        body: &[(usize, &str)],
+    ) -> anyhow::Result<(FunctionEnv<'env>, FunctionData)> {
+        let (header_no, header) = header;
+        let at_line =
+            |line_no: usize| move |err: anyhow::Error| anyhow!("line {}: {}", line_no, err);
+        let mut ctx = self.parse_header(header).map_err(at_line(header_no))?;
+        let mut name_to_index = BTreeMap::new();
+        let mut instructions = vec![];
+        for (line_no, line) in body {
+            if let Some(decl) = line.strip_prefix("var ") {
+                if !instructions.is_empty() {
+                    bail!("line {}: declaration after instructions", line_no)
+                }
+                let mut s = Scanner::new(decl);
+                let (temp, name, ty) = self
+                    .parse_decl(&mut s, &ctx)
+                    .and_then(|decl| s.expect_end().map(|_| decl))
+                    .map_err(at_line(*line_no))?;
+                if temp != ctx.local_types.len() {
+                    bail!(
+                        "line {}: expected declaration of `$t{}`",
+                        line_no,
+                        ctx.local_types.len()
+                    )
+                }
+                if let Some(name) = name {
+                    name_to_index.insert(self.env.symbol_pool().make(&name), temp);
+                }
+                ctx.local_types.push(ty)
+            } else {
+                let (offset, instr) = line
+                    .split_once(':')
+                    .and_then(|(offset, instr)| Some((offset.parse::<usize>().ok()?, instr)))
+                    .ok_or_else(|| {
+                        anyhow!("line {}: expected `<offset>: <instruction>`", line_no)
+                    })?;
+                if offset != instructions.len() {
+                    bail!("line {}: expected offset {}", line_no, instructions.len())
+                }
+                let instr = instr.trim();
+                if let Some(label) = instr.strip_prefix("label L") {
+                    let label = label
+                        .parse::<usize>()
+                        .map_err(|_| anyhow!("line {}: invalid label", line_no))?;
+                    ctx.labels.insert(offset, Label::new(label));
+                }
+                instructions.push((*line_no, instr))
+            }
+        }
+        let mut code = vec![];
+        let mut locations = BTreeMap::new();
+        for (line_no, instr) in instructions {
+            let attr = AttrId::new(code.len());
+            let mut s = Scanner::new(instr);
+            let bc = self
+                .parse_instruction(&mut s, &ctx, attr)
+                .and_then(|bc| s.expect_end().map(|_| bc))
+                .map_err(at_line(line_no))?;
+            locations.insert(attr, ctx.fun_env.get_loc());
+            code.push(bc)
+        }
+        let FunctionContext {
+            fun_env,
+            local_types,
+            ..
+        } = ctx;
+        let result_type = fun_env.get_result_type();
+        let mut data = FunctionData::new(
+            &fun_env,
+            code,
+            local_types,
+            result_type,
+            locations,
+            name_to_index,
+            vec![],
+            BTreeMap::new(),
+            Default::default(),
+        );
+        data.variant = variant;
+        Ok((fun_env, data))
+    }
+
+    /// Parses the header of a function, as in `public fun m::f<#0>($t0: u64): u64 {`, returning
+    /// a context with the types of the parameters.
+    fn parse_header(&self, header: &str) -> anyhow::Result<FunctionContext<'env>> {
+        let mut s = Scanner::new(header);
+        for modifier in ["public(friend)", "public", "native", "intrinsic"] {
+            s.eat_keyword(modifier);
+        }
+        if !s.eat_keyword("fun") {
+            bail!("expected function header")
+        }
+        s.skip_ws();
+        let id = self.resolve(&self.functions, &mut s, None, "function")?;
+        let fun_env = self.env.get_function(id);
+        let mut ctx = FunctionContext {
+            fun_env,
+            local_types: vec![],
+            labels: BTreeMap::new(),
+        };
+        if s.eat("<") {
+            let mut count = 0;
+            loop {
+                if !s.eat("#") || s.number()? != count {
+                    bail!("expected type parameter `#{}`", count)
+                }
+                count += 1;
+                if !s.eat(",") {
+                    break;
+                }
+            }
+            s.expect(">")?;
+            if count != ctx.fun_env.get_type_parameter_count() {
+                bail!("wrong number of type parameters")
+            }
+        }
+        s.expect("(")?;
+        if !s.eat(")") {
+            loop {
+                let (temp, _, ty) = self.parse_decl(&mut s, &ctx)?;
+                if temp != ctx.local_types.len() {
+                    bail!("expected declaration of `$t{}`", ctx.local_types.len())
+                }
+                ctx.local_types.push(ty);
+                if !s.eat(",") {
+                    break;
+                }
+            }
+            s.expect(")")?;
+        }
+        if ctx.local_types.len() != ctx.fun_env.get_parameter_count() {
+            bail!("wrong number of parameters")
+        }
+        let result_type = if s.eat(":") {
+            self.parse_type(&mut s, &ctx)?
+        } else {
+            Type::Tuple(vec![])
+        };
+        if result_type != ctx.fun_env.get_result_type() {
+            bail!("result type does not match the function")
+        }
+        if ctx.fun_env.is_native_or_intrinsic() {
+            s.expect(";")?
+        } else {
+            s.expect("{")?
+        }
+        s.expect_end()?;
+        Ok(ctx)
+    }
+
+    /// Parses a declaration of a temporary, as in `$t0|x: u64`, returning the index, the user
+    /// name if given, and the type.
+    fn parse_decl(
+        &self,
+        s: &mut Scanner,
+        ctx: &FunctionContext,
+    ) -> anyhow::Result<(TempIndex, Option<String>, Type)> {
+        let temp = s.temp()?;
+        let name = if s.eat("|") {
+            Some(s.take_while(|c| c != ':').trim().to_string())
+        } else {
+            None
+        };
+        s.expect(":")?;
+        let ty = self.parse_type(s, ctx)?;
+        Ok((temp, name, ty))
+    }
+
+    fn parse_type(&self, s: &mut Scanner, ctx: &FunctionContext) -> anyhow::Result<Type> {
+        s.skip_ws();
+        if s.eat("&") {
+            let kind = if s.eat_keyword("mut") {
+                ReferenceKind::Mutable
+            } else {
+                ReferenceKind::Immutable
+            };
+            return Ok(Type::Reference(kind, Box::new(self.parse_type(s, ctx)?)));
+        }
+        if s.eat("(") {
+            let tys = self.parse_type_list(s, ctx, ")")?;
+            return Ok(Type::Tuple(tys));
+        }
+        if s.eat("#") {
+            return Ok(Type::TypeParameter(s.number()? as u16));
+        }
+        if s.eat_keyword("vector") {
+            s.expect("<")?;
+            let ty = self.parse_type(s, ctx)?;
+            s.expect(">")?;
+            return Ok(Type::Vector(Box::new(ty)));
+        }
+        for (name, prim) in [
+            ("bool", PrimitiveType::Bool),
+            ("u8", PrimitiveType::U8),
+            ("u16", PrimitiveType::U16),
+            ("u32", PrimitiveType::U32),
+            ("u64", PrimitiveType::U64),
+            ("u128", PrimitiveType::U128),
+            ("u256", PrimitiveType::U256),
+            ("address", PrimitiveType::Address),
+            ("signer", PrimitiveType::Signer),
+        ] {
+            if s.eat_keyword(name) {
+                return Ok(Type::Primitive(prim));
+            }
+        }
+        let id = self.resolve(
+            &self.structs,
+            s,
+            Some(ctx.fun_env.module_env.get_id()),
+            "struct",
+        )?;
+        let targs = if s.eat("<") {
+            self.parse_type_list(s, ctx, ">")?
+        } else {
+            vec![]
+        };
+        Ok(Type::Struct(id.module_id, id.id, targs))
+    }
+
+    /// Parses a comma separated list of types, up to and including the closing text.
+    fn parse_type_list(
+        &self,
+        s: &mut Scanner,
+        ctx: &FunctionContext,
+        close: &str,
+    ) -> anyhow::Result<Vec<Type>> {
+        let mut tys = vec![];
+        if s.eat(close) {
+            return Ok(tys);
+        }
+        loop {
+            tys.push(self.parse_type(s, ctx)?);
+            if !s.eat(",") {
+                break;
+            }
+        }
+        s.expect(close)?;
+        Ok(tys)
+    }
+
+    /// Parses a struct type, returning the struct and the type arguments.
+    fn parse_struct_type(
+        &self,
+        s: &mut Scanner,
+        ctx: &FunctionContext,
+    ) -> anyhow::Result<(ModuleId, StructId, Vec<Type>)> {
+        match self.parse_type(s, ctx)? {
+            Type::Struct(mid, sid, targs) => Ok((mid, sid, targs)),
+            _ => bail!("expected struct type"),
+        }
+    }
+
+    /// Resolves the longest name in the table at the position of the scanner. Of functions or
+    /// structs with the same name, the one in the preferred module is taken.
+    fn resolve<T: Copy>(
+        &self,
+        table: &BTreeMap<String, Vec<QualifiedId<T>>>,
+        s: &mut Scanner,
+        preferred: Option<ModuleId>,
+        kind: &str,
+    ) -> anyhow::Result<QualifiedId<T>> {
+        let rest = s.rest();
+        let Some((name, ids)) = table
+            .iter()
+            .filter(|(name, _)| rest.starts_with(name.as_str()) && is_boundary(&rest[name.len()..]))
+            .max_by_key(|(name, _)| name.len())
+        else {
+            bail!("unknown {} at `{}`", kind, rest)
+        };
+        let id = match ids.as_slice() {
+            [id] => *id,
+            _ => match ids.iter().find(|id| Some(id.module_id) == preferred) {
+                Some(id) => *id,
+                None => bail!("ambiguous {} `{}`", kind, name),
+            },
+        };
+        s.advance(name.len());
+        Ok(id)
+    }
+
+    fn parse_instruction(
+        &self,
+        s: &mut Scanner,
+        ctx: &FunctionContext,
+        attr: AttrId,
+    ) -> anyhow::Result<Bytecode> {
+        if s.eat_keyword("label") {
+            s.expect("L")?;
+            return Ok(Bytecode::Label(attr, Label::new(s.number()?)));
+        }
+        if s.eat_keyword("goto") {
+            return Ok(Bytecode::Jump(attr, self.parse_label(s, ctx)?));
+        }
+        if s.eat_keyword("if") {
+            s.expect("(")?;
+            let cond = self.parse_temp(s, ctx)?;
+            s.expect(")")?;
+            s.expect_keyword("goto")?;
+            let then_label = self.parse_label(s, ctx)?;
+            s.expect_keyword("else")?;
+            s.expect_keyword("goto")?;
+            let else_label = self.parse_label(s, ctx)?;
+            return Ok(Bytecode::Branch(attr, then_label, else_label, cond));
+        }
+        if s.eat_keyword("abort") {
+            s.expect("(")?;
+            let src = self.parse_temp(s, ctx)?;
+            s.expect(")")?;
+            return Ok(Bytecode::Abort(attr, src));
+        }
+        if s.eat_keyword("nop") {
+            return Ok(Bytecode::Nop(attr));
+        }
+        if s.eat_keyword("return") {
+            return Ok(Bytecode::Ret(attr, self.parse_temps(s, ctx, false)?));
+        }
+        for (keyword, kind) in [("assert", PropKind::Assert), ("assume", PropKind::Assume)] {
+            if s.eat_keyword(keyword) {
+                s.skip_ws();
+                let text = s.rest();
+                let exp = self.resolve_condition(text, ctx)?;
+                s.advance(text.len());
+                return Ok(Bytecode::Prop(attr, kind, exp));
+            }
+        }
+        // An instruction with destinations starts with a temporary or a list of them, while an
+        // operation without destinations never starts with `$` and only a cast starts with
+        // `(`, which is not followed by `$`.
+        s.skip_ws();
+        let rest = s.rest();
+        let dests = if rest.starts_with('$') || rest.starts_with("($") || rest.starts_with("()") {
+            let dests = self.parse_temps(s, ctx, false)?;
+            s.expect(":=")?;
+            s.skip_ws();
+            if dests.len() == 1 {
+                let dest = dests[0];
+                if s.rest().starts_with('$') {
+                    let src = self.parse_temp(s, ctx)?;
+                    return Ok(Bytecode::Assign(attr, dest, src, AssignKind::Store));
+                }
+                for (name, kind) in [("copy", AssignKind::Copy), ("move", AssignKind::Move)] {
+                    if s.rest().starts_with(&format!("{}(", name)) {
+                        s.advance(name.len() + 1);
+                        let src = self.parse_temp(s, ctx)?;
+                        s.expect(")")?;
+                        return Ok(Bytecode::Assign(attr, dest, src, kind));
+                    }
+                }
+                let rest = s.rest();
+                if rest.starts_with(|c: char| c.is_ascii_digit())
+                    || rest.starts_with('[')
+                    || rest == "true"
+                    || rest == "false"
+                {
+                    let cons = parse_constant(rest, &ctx.local_types[dest])?;
+                    s.advance(rest.len());
+                    return Ok(Bytecode::Load(attr, dest, cons));
+                }
+            }
+            dests
+        } else {
+            vec![]
+        };
+        let oper = self.parse_operation(s, ctx)?;
+        let srcs = self.parse_temps(s, ctx, true)?;
+        let abort_action = if s.eat_keyword("on_abort") {
+            s.expect_keyword("goto")?;
+            let label = self.parse_label(s, ctx)?;
+            s.expect_keyword("with")?;
+            Some(AbortAction(label, self.parse_temp(s, ctx)?))
+        } else {
+            None
+        };
+        Ok(Bytecode::Call(attr, dests, oper, srcs, abort_action))
+    }
+
+    fn parse_operation(&self, s: &mut Scanner, ctx: &FunctionContext) -> anyhow::Result<Operation> {
+        s.skip_ws();
+        for (text, oper) in SIMPLE_OPERATIONS {
+            if s.rest().starts_with(&format!("{}(", text)) {
+                s.advance(text.len());
+                return Ok(oper.clone());
+            }
+        }
+        for name in ["borrow_field", "get_field"] {
+            if s.eat_keyword(name) {
+                s.expect("<")?;
+                let (mid, sid, targs) = self.parse_struct_type(s, ctx)?;
+                s.expect(">")?;
+                s.expect(".")?;
+                let field = s.take_while(|c| c != '(');
+                let struct_env = self.env.get_module(mid).into_struct(sid);
+                let Some(field_env) = struct_env
+                    .get_fields()
+                    .find(|f| f.get_name().display(self.env.symbol_pool()).to_string() == field)
+                else {
+                    bail!("unknown field `{}`", field)
+                };
+                let offset = field_env.get_offset();
+                return Ok(if name == "borrow_field" {
+                    Operation::BorrowField(mid, sid, targs, offset)
+                } else {
+                    Operation::GetField(mid, sid, targs, offset)
+                });
+            }
+        }
+        type StructOperation = fn(ModuleId, StructId, Vec<Type>) -> Operation;
+        // Pack and unpack are followed by the type, the other operations by the type in angle
+        // brackets.
+        let struct_operations: [(&str, bool, StructOperation); 7] = [
+            ("pack", false, Operation::Pack),
+            ("unpack", false, Operation::Unpack),
+            ("borrow_global", true, Operation::BorrowGlobal),
+            ("get_global", true, Operation::GetGlobal),
+            ("move_to", true, Operation::MoveTo),
+            ("move_from", true, Operation::MoveFrom),
+            ("exists", true, Operation::Exists),
+        ];
+        for (name, angle_brackets, make) in struct_operations {
+            if s.eat_keyword(name) {
+                if angle_brackets {
+                    s.expect("<")?;
+                }
+                let (mid, sid, targs) = self.parse_struct_type(s, ctx)?;
+                if angle_brackets {
+                    s.expect(">")?;
+                }
+                return Ok(make(mid, sid, targs));
+            }
+        }
+        if s.rest().starts_with("trace_return[") {
+            s.advance("trace_return[".len());
+            let idx = s.number()?;
+            s.expect("]")?;
+            return Ok(Operation::TraceReturn(idx));
+        }
+        let id = self.resolve(
+            &self.functions,
+            s,
+            Some(ctx.fun_env.module_env.get_id()),
+            "operation",
+        )?;
+        let targs = if s.rest().starts_with('<') {
+            s.advance(1);
+            self.parse_type_list(s, ctx, ">")?
+        } else {
+            vec![]
+        };
+        Ok(Operation::Function(id.module_id, id.id, targs))
+    }
+
+    /// Parses a list of temporaries. Unless `always_paren` is set, a single temporary is not
+    /// enclosed in parentheses.
+    /// Returns the condition of an inline spec block of the function which is printed as the
+    /// text.
+    fn resolve_condition(&self, text: &str, ctx: &FunctionContext) -> anyhow::Result<Exp> {
+        let mut found = None;
+        if let Some(def) = ctx.fun_env.get_def() {
+            def.visit(&mut |e| {
+                if let ExpData::SpecBlock(_, spec) = e {
+                    for cond in &spec.conditions {
+                        if found.is_none() && cond.exp.display(self.env).to_string() == text {
+                            found = Some(cond.exp.clone())
+                        }
+                    }
+                }
+            })
+        }
+        found.ok_or_else(|| anyhow!("unknown condition `{}`", text))
+    }
+
+    fn parse_temps(
+        &self,
+        s: &mut Scanner,
+        ctx: &FunctionContext,
+        always_paren: bool,
+    ) -> anyhow::Result<Vec<TempIndex>> {
+        if !always_paren && !s.eat("(") {
+            return Ok(vec![self.parse_temp(s, ctx)?]);
+        }
+        if always_paren {
+            s.expect("(")?;
+        }
+        let mut temps = vec![];
+        if s.eat(")") {
+            return Ok(temps);
+        }
+        loop {
+            temps.push(self.parse_temp(s, ctx)?);
+            if !s.eat(",") {
+                break;
+            }
+        }
+        s.expect(")")?;
+        Ok(temps)
+    }
+
+    fn parse_temp(&self, s: &mut Scanner, ctx: &FunctionContext) -> anyhow::Result<TempIndex> {
+        let temp = s.temp()?;
+        if temp >= ctx.local_types.len() {
+            bail!("undeclared temporary `$t{}`", temp)
+        }
+        Ok(temp)
+    }
+
+    /// Parses a branch target, which is either the offset of a label instruction or `L<N>`.
+    fn parse_label(&self, s: &mut Scanner, ctx: &FunctionContext) -> anyhow::Result<Label> {
+        if s.eat("L") {
+            return Ok(Label::new(s.number()?));
+        }
+        let offset = s.number()?;
+        ctx.labels
+            .get(&offset)
+            .cloned()
+            .ok_or_else(|| anyhow!("no label at offset {}", offset))
+    }
+}
+
+/// Parses a constant, as printed for a load into a temporary of the given type. Numbers are
+/// printed the same way for all integer types, so the type determines the kind of constant.
+fn parse_constant(text: &str, ty: &Type) -> anyhow::Result<Constant> {
+    let invalid = || anyhow!("invalid constant `{}` of type `{:?}`", text, ty);
+    Ok(match ty {
+        Type::Primitive(prim) => match prim {
+            PrimitiveType::Bool => Constant::Bool(text.parse().map_err(|_| invalid())?),
+            PrimitiveType::U8 => Constant::U8(text.parse().map_err(|_| invalid())?),
+            PrimitiveType::U16 => Constant::U16(text.parse().map_err(|_| invalid())?),
+            PrimitiveType::U32 => Constant::U32(text.parse().map_err(|_| invalid())?),
+            PrimitiveType::U64 => Constant::U64(text.parse().map_err(|_| invalid())?),
+            PrimitiveType::U128 => Constant::U128(text.parse().map_err(|_| invalid())?),
+            PrimitiveType::U256 => {
+                Constant::U256(U256::from_str_radix(text, 10).map_err(|_| invalid())?)
+            },
+            PrimitiveType::Address => Constant::Address(parse_address(text)?),
+            _ => return Err(invalid()),
+        },
+        Type::Vector(elem_ty) => {
+            let inner = text
+                .strip_prefix('[')
+                .and_then(|s| s.strip_suffix(']'))
+                .ok_or_else(invalid)?;
+            match elem_ty.as_ref() {
+                // Byte arrays are printed as a list of numbers, other vectors as a list of
+                // quoted elements.
+                Type::Primitive(PrimitiveType::U8) if !inner.starts_with('"') => {
+                    Constant::ByteArray(
+                        if inner.is_empty() {
+                            vec![]
+                        } else {
+                            inner
+                                .split(", ")
+                                .map(|n| n.parse().map_err(|_| invalid()))
+                                .collect::<anyhow::Result<_>>()?
+                        },
+                    )
+                },
+                Type::Primitive(PrimitiveType::Address) => Constant::AddressArray(
+                    parse_quoted_list(inner)?
+                        .iter()
+                        .map(|s| parse_address(s))
+                        .collect::<anyhow::Result<_>>()?,
+                ),
+                _ => Constant::Vector(
+                    parse_quoted_list(inner)?
+                        .iter()
+                        .map(|s| parse_constant(s, elem_ty))
+                        .collect::<anyhow::Result<_>>()?,
+                ),
+            }
+        },
+        _ => return Err(invalid()),
+    })
+}
+
+fn parse_address(text: &str) -> anyhow::Result<Address> {
+    AccountAddress::from_hex_literal(text)
+        .map(Address::Numerical)
+        .map_err(|_| anyhow!("invalid address `{}`", text))
+}
+
+/// Parses a comma separated list of strings, quoted and escaped as by `Debug`.
+fn parse_quoted_list(text: &str) -> anyhow::Result<Vec<String>> {
+    let mut result = vec![];
+    let mut chars = text.chars();
+    loop {
+        match chars.next() {
+            None => return Ok(result),
+            Some('"') => {},
+            Some(_) => bail!("expected quoted element in `[{}]`", text),
+        }
+        let mut elem = String::new();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some(c @ ('"' | '\\' | '\'')) => elem.push(c),
+                    Some('n') => elem.push('\n'),
+                    Some('t') => elem.push('\t'),
+                    _ => bail!("unsupported escape in `[{}]`", text),
+                },
+                Some(c) => elem.push(c),
+                None => bail!("unterminated element in `[{}]`", text),
+            }
+        }
+        result.push(elem);
+        match (chars.next(), chars.next()) {
+            (None, _) => return Ok(result),
+            (Some(','), Some(' ')) => {},
+            _ => bail!("expected `, ` in `[{}]`", text),
+        }
+    }
+}
+
+/// Returns true if a name ends at the start of the text.
+fn is_boundary(text: &str) -> bool {
+    !text.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+/// A cursor into the text of a line. Apart from `advance`, `rest` and `take_while`, all
+/// methods skip whitespace before.
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn advance(&mut self, len: usize) {
+        self.pos += len
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len()
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_ws();
+        let rest = self.rest();
+        if rest.starts_with(keyword) && is_boundary(&rest[keyword.len()..]) {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> anyhow::Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            bail!("expected `{}` at `{}`", token, self.rest())
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> anyhow::Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            bail!("expected `{}` at `{}`", keyword, self.rest())
+        }
+    }
+
+    fn expect_end(&mut self) -> anyhow::Result<()> {
+        self.skip_ws();
+        if self.rest().is_empty() {
+            Ok(())
+        } else {
+            bail!("unexpected `{}`", self.rest())
+        }
+    }
+
+    fn number(&mut self) -> anyhow::Result<usize> {
+        self.skip_ws();
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        digits
+            .parse()
+            .map_err(|_| anyhow!("expected number at `{}`", self.rest()))
+    }
+
+    fn temp(&mut self) -> anyhow::Result<TempIndex> {
+        self.skip_ws();
+        if !self.rest().starts_with("$t") {
+            bail!("expected temporary at `{}`", self.rest())
+        }
+        self.advance(2);
+        self.number()
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements an on-disk cache of compiled modules, which allows to skip code generation for
+//! modules whose inputs did not change since the last compilation.
+//!
+//! A module is cached under a key which is a hash over:
+//!
+//! - the hash of the file defining the module,
+//! - the hashes of the files defining the modules the module transitively depends on,
+//! - the named address mapping, with the overrides for dependencies, and
+//! - the options which affect code generation, namely the experiments, the targeted
+//!   bytecode version, and whether test code is compiled.
+//!
+//! The cache is installed as an extension of the `GlobalEnv`, where `run_bytecode_gen` and
+//! `run_file_format_gen` look it up. Code is still generated for functions of cached modules
+//! which are called from regenerated modules, as processors like acquires inference need their
+//! bytecode. Since the bytecode pipeline does not run for cached modules, warnings it reports
+//! for them are not repeated. Scripts are always regenerated, and so are modules targeting the
+//! bytecode version `VERSION_NEXT`, which the deserializer only accepts in tests.
+
+use crate::Options;
+use move_binary_format::{
+    deserializer::DeserializerConfig,
+    file_format::CompiledModule,
+    file_format_common::{IDENTIFIER_SIZE_MAX, VERSION_MAX, VERSION_NEXT},
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::{address::NumericalAddress, files::FileHash, parser::NumberFormat};
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use move_model::model::{GlobalEnv, ModuleEnv, ModuleId};
+use move_symbol_pool::Symbol;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The cache of compiled modules for a compilation.
+pub struct CompilationCache {
+    dir: PathBuf,
+    /// The keys of the target modules which are not scripts.
+    keys: BTreeMap<ModuleId, FileHash>,
+    /// The units found in the cache with a matching key.
+    cached: BTreeMap<ModuleId, CompiledUnit>,
+    /// The modules for which code has been generated, in the order of generation.
+    generated: RefCell<Vec<ModuleId>>,
+}
+
+/// The representation of a module in the cache.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: [u8; 32],
+    module: Vec<u8>,
+    source_map: SourceMap,
+}
+
+impl CompilationCache {
+    /// Creates the cache for the target modules of the environment, loading the entries from
+    /// `dir` which are still valid. Entries which cannot be read are treated as missing.
+    pub fn load(env: &GlobalEnv, dir: &Path) -> Self {
+        let options = env.get_extension::<Options>().unwrap_or_default();
+        let mut keys = BTreeMap::new();
+        let mut cached = BTreeMap::new();
+        for module_env in env.get_modules() {
+            if !module_env.is_target() || module_env.is_script_module() {
+                continue;
+            }
+            let key = module_key(&module_env, &options);
+            if let Some(module) = fs::read(entry_path(dir, &module_env))
+                .ok()
+                .and_then(|bytes| bcs::from_bytes::<CacheEntry>(&bytes).ok())
+                .filter(|entry| entry.key == key.0)
+                .and_then(|entry| {
+                    let module = CompiledModule::deserialize_with_config(
+                        &entry.module,
+                        &DeserializerConfig::new(VERSION_MAX, IDENTIFIER_SIZE_MAX),
+                    )
+                    .ok()?;
+                    Some(CompiledUnit::Module(NamedCompiledModule {
+                        package_name: None,
+                        address: NumericalAddress::new(
+                            module_env.get_name().addr().expect_numerical().into_bytes(),
+                            NumberFormat::Hex,
+                        ),
+                        name: Symbol::from(
+                            env.symbol_pool()
+                                .string(module_env.get_name().name())
+                                .as_str(),
+                        ),
+                        module,
+                        source_map: entry.source_map,
+                    }))
+                })
+            {
+                cached.insert(module_env.get_id(), module);
+            }
+            keys.insert(module_env.get_id(), key);
+        }
+        Self {
+            dir: dir.to_path_buf(),
+            keys,
+            cached,
+            generated: RefCell::new(vec![]),
+        }
+    }
+
+    /// Returns true if the module is found in the cache, so no code needs to be generated
+    /// for it.
+    pub fn is_cached(&self, id: ModuleId) -> bool {
+        self.cached.contains_key(&id)
+    }
+
+    /// Returns the cached unit of the module, if available.
+    pub fn get(&self, id: ModuleId) -> Option<&CompiledUnit> {
+        self.cached.get(&id)
+    }
+
+    /// Records that code has been generated for the module.
+    pub fn record_generated(&self, id: ModuleId) {
+        self.generated.borrow_mut().push(id)
+    }
+
+    /// Returns the modules for which code has been generated during this compilation.
+    pub fn generated_modules(&self) -> Vec<ModuleId> {
+        self.generated.borrow().clone()
+    }
+
+    /// Writes the units of the generated modules into the cache directory, which is created
+    /// if needed. Modules of version `VERSION_NEXT` are skipped, as they cannot be read back.
+    pub fn store(&self, env: &GlobalEnv, units: &[CompiledUnit]) -> anyhow::Result<()> {
+        let generated = self
+            .generated
+            .borrow()
+            .iter()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        fs::create_dir_all(&self.dir)?;
+        for unit in units {
+            let CompiledUnit::Module(named_module) = unit else {
+                continue;
+            };
+            let Some(module_env) =
+                env.find_module_by_language_storage_id(&named_module.module.self_id())
+            else {
+                continue;
+            };
+            let id = module_env.get_id();
+            let Some(key) = self.keys.get(&id).filter(|_| generated.contains(&id)) else {
+                continue;
+            };
+            if named_module.module.version >= VERSION_NEXT {
+                continue;
+            }
+            let mut module = vec![];
+            named_module
+                .module
+                .serialize_for_version(Some(named_module.module.version), &mut module)?;
+            let entry = CacheEntry {
+                key: key.0,
+                module,
+                source_map: named_module.source_map.clone(),
+            };
+            fs::write(entry_path(&self.dir, &module_env), bcs::to_bytes(&entry)?)?
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path of the cache entry for the module.
+fn entry_path(dir: &Path, module_env: &ModuleEnv) -> PathBuf {
+    let name = module_env
+        .get_full_name_str()
+        .replace("::", "_")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    dir.join(format!("{}.cache", name))
+}
+
+/// Computes the key of the module, as described in the module documentation.
+fn module_key(module_env: &ModuleEnv, options: &Options) -> FileHash {
+    let env = module_env.env;
+    let mut text = format!(
+        "compiler {}\nmodule {}\n",
+        env!("CARGO_PKG_VERSION"),
+        module_env.get_full_name_str()
+    );
+    let mut file_hash = |module_env: &ModuleEnv| {
+        let hash = env
+            .get_file_hash(module_env.get_loc().file_id())
+            .unwrap_or_else(FileHash::empty);
+        text.push_str(&format!(
+            "source {} {}\n",
+            module_env.get_full_name_str(),
+            hash
+        ))
+    };
+    file_hash(module_env);
+    for dep in transitive_dependencies(module_env) {
+        file_hash(&env.get_module(dep))
+    }
+    let mut addresses = options.named_address_mapping.clone();
+    addresses.extend(
+        options
+            .dependency_address_overrides
+            .iter()
+            .map(|(prefix, name, address)| format!("{}:{}={}", prefix, name, address)),
+    );
+    addresses.sort();
+    text.push_str(&format!(
+        "addresses {}\nflags {}\n",
+        addresses.join(","),
+        options.code_generation_flags().join(",")
+    ));
+    FileHash::new(&text)
+}
+
+/// Returns the modules the given one transitively depends on, excluding itself.
+fn transitive_dependencies(module_env: &ModuleEnv) -> BTreeSet<ModuleId> {
+    let env = module_env.env;
+    let mut result = BTreeSet::new();
+    let mut todo = vec![module_env.get_id()];
+    while let Some(id) = todo.pop() {
+        for dep in direct_dependencies(&env.get_module(id)) {
+            if dep != module_env.get_id() && result.insert(dep) {
+                todo.push(dep)
+            }
+        }
+    }
+    result
+}
+
+/// Returns the modules the given one directly depends on. Besides the modules from `use`
+/// declarations, this includes modules referred to by fully qualified names, as found in
+/// function calls and types.
+pub(crate) fn direct_dependencies(module_env: &ModuleEnv) -> BTreeSet<ModuleId> {
+    let env = module_env.env;
+    let mut usage = module_env.get_used_modules(false);
+    for struct_env in module_env.get_structs() {
+        for field_env in struct_env.get_fields() {
+            field_env.get_type().module_usage(&mut usage)
+        }
+    }
+    for fun_env in module_env.get_functions() {
+        for ty in fun_env.get_parameter_types() {
+            ty.module_usage(&mut usage)
+        }
+        fun_env.get_result_type().module_usage(&mut usage);
+        if let Some(callees) = fun_env.get_called_functions() {
+            usage.extend(callees.iter().map(|id| id.module_id))
+        }
+        if let Some(def) = fun_env.get_def() {
+            def.module_usage(&mut usage);
+            for node_id in def.node_ids() {
+                env.get_node_type(node_id).module_usage(&mut usage);
+                for ty in env.get_node_instantiation(node_id) {
+                    ty.module_usage(&mut usage)
+                }
+            }
+        }
+    }
+    usage.remove(&module_env.get_id());
+    usage
+}
@@ -0,0 +1,393 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Collects statistics of a compilation: the time spent in each phase and in each processor
+//! of the bytecode pipeline, and the size of the generated code.
+//!
+//! The statistics are installed as an extension of the `GlobalEnv` by `run_move_compiler`, from
+//! which callers can retrieve them, and are rendered as a table if `Options::print_stats` is
+//! set. Collection only takes a timestamp per phase and processor, so it is always on.
+//!
+//! If `Options::profile_compilation` is set, the time each processor spends on each function,
+//! and the time of bytecode generation for each module, are collected as well, to find the
+//! functions which dominate the compilation. The profile lists the slowest of them.
+//!
+//! The stack depth, frame size, and call depth of each generated function, estimated by
+//! `stack_depth`, are recorded as well.
+
+use crate::stack_depth::{CallDepth, FunctionFrame};
+use move_compiler::compiled_unit::CompiledUnit;
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::{
+    function_target::FunctionData,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+};
+use serde::{Serialize, Serializer};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// Names of the phases of a compilation, in the order in which they run.
+pub const CHECKING_PHASE: &str = "checking";
+pub const BYTECODE_GENERATION_PHASE: &str = "bytecode generation";
+pub const BYTECODE_PIPELINE_PHASE: &str = "bytecode pipeline";
+pub const FILE_FORMAT_GENERATION_PHASE: &str = "file format generation";
+pub const BYTECODE_VERIFICATION_PHASE: &str = "bytecode verification";
+
+/// The time spent in a phase of the compilation, or in a processor of the bytecode pipeline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timing {
+    pub name: String,
+    /// The start, relative to the start of the compilation.
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+impl Timing {
+    /// Returns the end, relative to the start of the compilation.
+    pub fn end(&self) -> Duration {
+        self.start + self.duration
+    }
+}
+
+/// The time a processor of the bytecode pipeline spent on a function, summed over the variants
+/// of the function and the iterations over recursive functions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FunctionTiming {
+    pub function: String,
+    pub processor: String,
+    #[serde(rename = "duration_us", serialize_with = "serialize_micros")]
+    pub duration: Duration,
+}
+
+/// The time of bytecode generation for the functions of a module.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ModuleTiming {
+    pub module: String,
+    #[serde(rename = "duration_us", serialize_with = "serialize_micros")]
+    pub duration: Duration,
+}
+
+/// The profile of a compilation, with the slowest entries first. This is also the JSON
+/// representation of the profile.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CompilationProfile {
+    pub functions: Vec<FunctionTiming>,
+    pub modules: Vec<ModuleTiming>,
+}
+
+fn serialize_micros<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_micros() as u64)
+}
+
+/// The statistics of a compilation, available as an extension of the `GlobalEnv` returned by
+/// `run_move_compiler`.
+#[derive(Debug)]
+pub struct CompilationStats {
+    start: Instant,
+    phases: RefCell<Vec<Timing>>,
+    processors: RefCell<Vec<Timing>>,
+    function_processors: RefCell<BTreeMap<(String, String), Duration>>,
+    module_generation: RefCell<BTreeMap<String, Duration>>,
+    modules: Cell<usize>,
+    scripts: Cell<usize>,
+    functions: Cell<usize>,
+    instructions: Cell<usize>,
+    frames: RefCell<BTreeMap<String, FunctionFrame>>,
+}
+
+impl CompilationStats {
+    /// Creates statistics for a compilation which started at the given time.
+    pub fn new(start: Instant) -> Self {
+        Self {
+            start,
+            phases: RefCell::default(),
+            processors: RefCell::default(),
+            function_processors: RefCell::default(),
+            module_generation: RefCell::default(),
+            modules: Cell::default(),
+            scripts: Cell::default(),
+            functions: Cell::default(),
+            instructions: Cell::default(),
+            frames: RefCell::default(),
+        }
+    }
+
+    /// Records a phase which started at the given time and ends now.
+    pub fn record_phase(&self, name: &str, start: Instant) {
+        self.phases.borrow_mut().push(self.timing(name, start))
+    }
+
+    /// Records a processor of the bytecode pipeline which started at the given time and ends
+    /// now.
+    pub fn record_processor(&self, name: &str, start: Instant) {
+        self.processors.borrow_mut().push(self.timing(name, start))
+    }
+
+    /// Adds the time a processor of the bytecode pipeline spent on a function, by its full name,
+    /// as in `0x42::m::f`.
+    pub fn record_function_processor(&self, function: &str, processor: &str, duration: Duration) {
+        *self
+            .function_processors
+            .borrow_mut()
+            .entry((function.to_owned(), processor.to_owned()))
+            .or_default() += duration
+    }
+
+    /// Adds the time of bytecode generation for a function of the module.
+    pub fn record_module_generation(&self, module: &str, duration: Duration) {
+        *self
+            .module_generation
+            .borrow_mut()
+            .entry(module.to_owned())
+            .or_default() += duration
+    }
+
+    fn timing(&self, name: &str, start: Instant) -> Timing {
+        Timing {
+            name: name.to_owned(),
+            start: start.saturating_duration_since(self.start),
+            duration: start.elapsed(),
+        }
+    }
+
+    /// Records the size of the generated code.
+    pub fn record_units(&self, units: &[CompiledUnit]) {
+        for unit in units {
+            match unit {
+                CompiledUnit::Module(module) => {
+                    let defs = &module.module.function_defs;
+                    self.modules.set(self.modules.get() + 1);
+                    self.functions.set(self.functions.get() + defs.len());
+                    self.instructions.set(
+                        self.instructions.get()
+                            + defs
+                                .iter()
+                                .filter_map(|def| def.code.as_ref())
+                                .map(|code| code.code.len())
+                                .sum::<usize>(),
+                    )
+                },
+                CompiledUnit::Script(script) => {
+                    self.scripts.set(self.scripts.get() + 1);
+                    self.functions.set(self.functions.get() + 1);
+                    self.instructions
+                        .set(self.instructions.get() + script.script.code.code.len())
+                },
+            }
+        }
+    }
+
+    /// Records the frames of the generated functions, by their full name.
+    pub fn record_frames(&self, frames: BTreeMap<String, FunctionFrame>) {
+        self.frames.borrow_mut().extend(frames)
+    }
+
+    /// Returns the phases which ran, in order.
+    pub fn phases(&self) -> Vec<Timing> {
+        self.phases.borrow().clone()
+    }
+
+    /// Returns the processors of the bytecode pipeline which ran, in order.
+    pub fn processors(&self) -> Vec<Timing> {
+        self.processors.borrow().clone()
+    }
+
+    /// Returns the time from the start of the compilation to the end of the last phase.
+    pub fn total(&self) -> Duration {
+        self.phases
+            .borrow()
+            .last()
+            .map(Timing::end)
+            .unwrap_or_default()
+    }
+
+    pub fn modules(&self) -> usize {
+        self.modules.get()
+    }
+
+    pub fn scripts(&self) -> usize {
+        self.scripts.get()
+    }
+
+    /// Returns the number of generated functions, where each script counts as one function.
+    pub fn functions(&self) -> usize {
+        self.functions.get()
+    }
+
+    pub fn instructions(&self) -> usize {
+        self.instructions.get()
+    }
+
+    /// Returns the frames of the generated functions, by their full name, as in `0x42::m::f`.
+    pub fn frames(&self) -> BTreeMap<String, FunctionFrame> {
+        self.frames.borrow().clone()
+    }
+
+    /// Returns the profile of the compilation, with the `top` slowest pairs of a function and a
+    /// processor, and all modules. Both are empty unless `Options::profile_compilation` is set.
+    pub fn profile(&self, top: usize) -> CompilationProfile {
+        let mut functions = self
+            .function_processors
+            .borrow()
+            .iter()
+            .map(|((function, processor), duration)| FunctionTiming {
+                function: function.clone(),
+                processor: processor.clone(),
+                duration: *duration,
+            })
+            .collect::<Vec<_>>();
+        // The sort is stable, so entries of equal time remain ordered by name.
+        functions.sort_by(|a, b| b.duration.cmp(&a.duration));
+        functions.truncate(top);
+        let mut modules = self
+            .module_generation
+            .borrow()
+            .iter()
+            .map(|(module, duration)| ModuleTiming {
+                module: module.clone(),
+                duration: *duration,
+            })
+            .collect::<Vec<_>>();
+        modules.sort_by(|a, b| b.duration.cmp(&a.duration));
+        CompilationProfile { functions, modules }
+    }
+
+    /// Renders the statistics as a table. Processors are listed below the pipeline phase.
+    pub fn render(&self) -> String {
+        let ms = |d: Duration| format!("{:.3}ms", d.as_secs_f64() * 1000.0);
+        let mut rows = vec![];
+        for phase in self.phases.borrow().iter() {
+            rows.push((phase.name.clone(), ms(phase.duration)));
+            if phase.name == BYTECODE_PIPELINE_PHASE {
+                for processor in self.processors.borrow().iter() {
+                    rows.push((format!("  {}", processor.name), ms(processor.duration)))
+                }
+            }
+        }
+        rows.push(("total".to_owned(), ms(self.total())));
+        let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        let time_width = rows.iter().map(|(_, time)| time.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for (name, time) in rows {
+            let _ = writeln!(out, "{:width$}  {:>time_width$}", name, time);
+        }
+        let _ = writeln!(
+            out,
+            "generated {} module(s), {} script(s), {} function(s), {} instruction(s)",
+            self.modules(),
+            self.scripts(),
+            self.functions(),
+            self.instructions()
+        );
+        let frames = self.frames.borrow();
+        if !frames.is_empty() {
+            let _ = writeln!(
+                out,
+                "max operand stack depth {}, max frame size {}, max call depth {}",
+                frames
+                    .values()
+                    .map(|f| f.max_stack_depth)
+                    .max()
+                    .unwrap_or(0),
+                frames.values().map(|f| f.frame_size).max().unwrap_or(0),
+                frames
+                    .values()
+                    .map(|f| f.call_depth)
+                    .max()
+                    .unwrap_or(CallDepth::Bounded(0))
+            );
+        }
+        out
+    }
+}
+
+impl CompilationProfile {
+    /// Renders the profile as two tables.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "slowest functions in the bytecode pipeline:");
+        render_rows(
+            &mut out,
+            self.functions
+                .iter()
+                .map(|t| (format!("{}  {}", t.function, t.processor), t.duration)),
+        );
+        let _ = writeln!(out, "bytecode generation per module:");
+        render_rows(
+            &mut out,
+            self.modules.iter().map(|t| (t.module.clone(), t.duration)),
+        );
+        out
+    }
+}
+
+fn render_rows(out: &mut String, rows: impl Iterator<Item = (String, Duration)>) {
+    let rows = rows
+        .map(|(name, d)| (name, format!("{:.3}ms", d.as_secs_f64() * 1000.0)))
+        .collect::<Vec<_>>();
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let time_width = rows.iter().map(|(_, time)| time.len()).max().unwrap_or(0);
+    for (name, time) in rows {
+        let _ = writeln!(out, "  {:width$}  {:>time_width$}", name, time);
+    }
+}
+
+/// A processor which records the time the wrapped processor spends on each function in the
+/// `CompilationStats` of the env. It has the name of the wrapped processor. Single-run
+/// processors are not specific to a function, and only timed as a whole.
+pub(crate) struct ProfiledProcessor {
+    processor: Box<dyn FunctionTargetProcessor>,
+}
+
+impl ProfiledProcessor {
+    pub(crate) fn new(processor: Box<dyn FunctionTargetProcessor>) -> Self {
+        Self { processor }
+    }
+}
+
+impl FunctionTargetProcessor for ProfiledProcessor {
+    fn process_and_maybe_remove(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        scc_opt: Option<&[FunctionEnv]>,
+    ) -> Option<FunctionData> {
+        let start = Instant::now();
+        let result = self
+            .processor
+            .process_and_maybe_remove(targets, fun_env, data, scc_opt);
+        if let Some(stats) = fun_env.module_env.env.get_extension::<CompilationStats>() {
+            stats.record_function_processor(
+                &fun_env.get_full_name_with_address(),
+                &self.processor.name(),
+                start.elapsed(),
+            )
+        }
+        result
+    }
+
+    fn name(&self) -> String {
+        self.processor.name()
+    }
+
+    fn initialize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.processor.initialize(env, targets)
+    }
+
+    fn finalize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.processor.finalize(env, targets)
+    }
+
+    fn is_single_run(&self) -> bool {
+        self.processor.is_single_run()
+    }
+
+    fn run(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.processor.run(env, targets)
+    }
+}
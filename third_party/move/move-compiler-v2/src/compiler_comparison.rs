@@ -0,0 +1,528 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Comparison of the units compiled by compiler v1 and this compiler from the same sources,
+//! to spot differences when migrating packages. Modules are matched by their id, and scripts
+//! by their name. A difference is reported at `Severity::Abi` if it is visible to other
+//! modules or to clients: missing units, different friends, dependencies, struct layouts, or
+//! signatures of public, friend, and entry functions. Everything else, like the signatures
+//! of private functions or, with `ComparisonLevel::Code`, the code of functions, is reported
+//! at `Severity::Code`.
+//!
+//! Code is compared in a normalized form, where indices into the pools of a unit are
+//! replaced by the entities they denote, so it does not depend on the order of the pools.
+
+use crate::{known_attributes, ComparisonLevel, Options};
+use anyhow::bail;
+use codespan_reporting::term::termcolor::WriteColor;
+use move_binary_format::{
+    access::ModuleAccess,
+    binary_views::BinaryIndexedView,
+    file_format::{
+        Bytecode, CompiledModule, CompiledScript, FieldHandleIndex, FieldInstantiationIndex,
+        FunctionHandleIndex, SignatureIndex, SignatureToken, StructDefInstantiationIndex,
+        StructDefinitionIndex, StructHandleIndex, Visibility,
+    },
+    normalized,
+};
+use move_compiler::{
+    compiled_unit::{AnnotatedCompiledUnit, CompiledUnit},
+    diagnostics,
+    shared::{Flags, PackagePaths},
+    Compiler,
+};
+use move_symbol_pool::Symbol;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// The severity of a difference between the units of the two compilers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// A difference in code which is not visible outside of the unit.
+    Code,
+    /// A difference visible to other modules or clients of the unit.
+    Abi,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Code => write!(f, "code"),
+            Severity::Abi => write!(f, "abi"),
+        }
+    }
+}
+
+/// A difference between the units of the two compilers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difference {
+    pub severity: Severity,
+    /// The name of the unit, like `0x42::m` for a module, or the name of a script.
+    pub unit: String,
+    pub message: String,
+}
+
+/// The differences between the units of the two compilers.
+#[derive(Clone, Debug, Default)]
+pub struct ComparisonReport {
+    /// The differences, ordered by unit.
+    pub differences: Vec<Difference>,
+}
+
+impl ComparisonReport {
+    /// Returns true if there is a difference at `Severity::Abi`.
+    pub fn has_abi_differences(&self) -> bool {
+        self.differences.iter().any(|d| d.severity == Severity::Abi)
+    }
+
+    /// Renders the report, one difference per line.
+    pub fn render(&self) -> String {
+        if self.differences.is_empty() {
+            return "compiler v1 and v2 generate equivalent units\n".to_owned();
+        }
+        self.differences
+            .iter()
+            .map(|d| format!("[{}] {}: {}\n", d.severity, d.unit, d.message))
+            .collect()
+    }
+
+    fn add(&mut self, severity: Severity, unit: &str, message: String) {
+        self.differences.push(Difference {
+            severity,
+            unit: unit.to_owned(),
+            message,
+        })
+    }
+}
+
+/// Compiles the sources of the options with both compilers and compares the results at the
+/// given level. Diagnostics of either compiler are written to the error writer, and the
+/// comparison fails if either compiler reports errors.
+pub fn run_compiler_comparison(
+    error_writer: &mut impl WriteColor,
+    options: Options,
+    level: ComparisonLevel,
+) -> anyhow::Result<ComparisonReport> {
+    let v1_units = compile_with_v1(error_writer, &options)?;
+    let (_, v2_units) = crate::run_move_compiler(error_writer, Options {
+        compare_with_v1: None,
+        ..options
+    })?;
+    let v2_units = v2_units
+        .into_iter()
+        .map(AnnotatedCompiledUnit::into_compiled_unit)
+        .collect::<Vec<_>>();
+    Ok(compare_units(&v1_units, &v2_units, level))
+}
+
+/// Compiles the sources of the options with compiler v1.
+pub(crate) fn compile_with_v1(
+    error_writer: &mut impl WriteColor,
+    options: &Options,
+) -> anyhow::Result<Vec<CompiledUnit>> {
+    if !options.bytecode_dependencies.is_empty() {
+        bail!("comparison with compiler v1 does not support bytecode dependencies")
+    }
+    if !options.dependency_address_overrides.is_empty() {
+        bail!("comparison with compiler v1 does not support dependency address overrides")
+    }
+    let addrs = move_model::parse_addresses_from_options(options.named_address_mapping.clone())?;
+    let mut buffers = BTreeMap::new();
+    let mut package = |sources: &[String], source_buffers: &[(String, String)]| {
+        let mut paths = sources
+            .iter()
+            .map(|path| Symbol::from(path.as_str()))
+            .collect::<Vec<_>>();
+        for (path, content) in source_buffers {
+            paths.push(Symbol::from(path.as_str()));
+            buffers.insert(Symbol::from(path.as_str()), content.clone());
+        }
+        PackagePaths {
+            name: None,
+            paths,
+            named_address_map: addrs
+                .iter()
+                .map(|(name, addr)| (Symbol::from(name.as_str()), *addr))
+                .collect(),
+        }
+    };
+    let targets = vec![package(&options.sources, &options.source_buffers)];
+    let deps = vec![package(&options.dependencies, &options.dependency_buffers)];
+    let (files, result) = Compiler::from_package_paths(
+        targets,
+        deps,
+        Flags::empty()
+            .set_skip_attribute_checks(options.skip_attribute_checks)
+            .set_keep_testing_functions(options.compile_test_code),
+        &known_attributes(options),
+    )
+    .set_source_buffers(buffers)
+    .build()?;
+    match result {
+        Ok((units, _warnings)) => Ok(units
+            .into_iter()
+            .map(AnnotatedCompiledUnit::into_compiled_unit)
+            .collect()),
+        Err(diags) => {
+            error_writer.write_all(&diagnostics::report_diagnostics_to_buffer(&files, diags))?;
+            bail!("compiler v1 reported errors")
+        },
+    }
+}
+
+/// Compares the units of compiler v1 with the ones of compiler v2.
+pub fn compare_units(
+    v1_units: &[CompiledUnit],
+    v2_units: &[CompiledUnit],
+    level: ComparisonLevel,
+) -> ComparisonReport {
+    let (v1_modules, v1_scripts) = partition_units(v1_units);
+    let (v2_modules, v2_scripts) = partition_units(v2_units);
+    let mut report = ComparisonReport::default();
+    for (name, in_v1, in_v2) in matched(&v1_modules, &v2_modules) {
+        match (in_v1, in_v2) {
+            (Some(m1), Some(m2)) => compare_modules(&mut report, &name, m1, m2, level),
+            (Some(_), None) => report.add(Severity::Abi, &name, "missing in v2".to_owned()),
+            _ => report.add(Severity::Abi, &name, "missing in v1".to_owned()),
+        }
+    }
+    for (name, in_v1, in_v2) in matched(&v1_scripts, &v2_scripts) {
+        match (in_v1, in_v2) {
+            (Some(s1), Some(s2)) => compare_scripts(&mut report, &name, s1, s2, level),
+            (Some(_), None) => report.add(Severity::Abi, &name, "missing in v2".to_owned()),
+            _ => report.add(Severity::Abi, &name, "missing in v1".to_owned()),
+        }
+    }
+    report
+}
+
+fn partition_units(
+    units: &[CompiledUnit],
+) -> (
+    BTreeMap<String, &CompiledModule>,
+    BTreeMap<String, &CompiledScript>,
+) {
+    let mut modules = BTreeMap::new();
+    let mut scripts = BTreeMap::new();
+    for unit in units {
+        match unit {
+            CompiledUnit::Module(named_module) => {
+                let module = &named_module.module;
+                modules.insert(module.self_id().short_str_lossless(), module);
+            },
+            CompiledUnit::Script(named_script) => {
+                scripts.insert(named_script.name.to_string(), &named_script.script);
+            },
+        }
+    }
+    (modules, scripts)
+}
+
+/// Pairs the entries of two maps by key.
+fn matched<'a, T>(
+    left: &'a BTreeMap<String, T>,
+    right: &'a BTreeMap<String, T>,
+) -> Vec<(String, Option<&'a T>, Option<&'a T>)> {
+    left.keys()
+        .chain(right.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|key| (key.clone(), left.get(key), right.get(key)))
+        .collect()
+}
+
+fn compare_modules(
+    report: &mut ComparisonReport,
+    name: &str,
+    m1: &CompiledModule,
+    m2: &CompiledModule,
+    level: ComparisonLevel,
+) {
+    let n1 = normalized::Module::new(m1);
+    let n2 = normalized::Module::new(m2);
+    if n1.friends != n2.friends {
+        report.add(
+            Severity::Abi,
+            name,
+            format!("friends differ: {:?} vs {:?}", n1.friends, n2.friends),
+        )
+    }
+    let deps1 = dependencies(m1);
+    let deps2 = dependencies(m2);
+    if deps1 != deps2 {
+        report.add(
+            Severity::Abi,
+            name,
+            format!("dependencies differ: {:?} vs {:?}", deps1, deps2),
+        )
+    }
+    for (struct_name, s1, s2) in matched(&by_name(n1.structs), &by_name(n2.structs)) {
+        if s1 != s2 {
+            report.add(
+                Severity::Abi,
+                name,
+                format!("struct `{}` differs: {:?} vs {:?}", struct_name, s1, s2),
+            )
+        }
+    }
+    let funs1 = functions(m1);
+    let funs2 = functions(m2);
+    for (fun_name, f1, f2) in matched(&funs1, &funs2) {
+        let exposed = |f: Option<&normalized::Function>| {
+            f.map_or(false, |f| f.visibility != Visibility::Private || f.is_entry)
+        };
+        let severity = if exposed(f1.map(|(f, _)| f)) || exposed(f2.map(|(f, _)| f)) {
+            Severity::Abi
+        } else {
+            Severity::Code
+        };
+        match (f1, f2) {
+            (Some((f1, code1)), Some((f2, code2))) => {
+                if f1 != f2 {
+                    report.add(
+                        severity,
+                        name,
+                        format!("signature of `{}` differs: {:?} vs {:?}", fun_name, f1, f2),
+                    )
+                }
+                if level == ComparisonLevel::Code && code1 != code2 {
+                    report.add(
+                        Severity::Code,
+                        name,
+                        code_difference(&fun_name, code1, code2),
+                    )
+                }
+            },
+            (Some(_), None) => report.add(
+                severity,
+                name,
+                format!("function `{}` is missing in v2", fun_name),
+            ),
+            _ => report.add(
+                severity,
+                name,
+                format!("function `{}` is missing in v1", fun_name),
+            ),
+        }
+    }
+}
+
+fn compare_scripts(
+    report: &mut ComparisonReport,
+    name: &str,
+    s1: &CompiledScript,
+    s2: &CompiledScript,
+    level: ComparisonLevel,
+) {
+    let sig1 = script_signature(s1);
+    let sig2 = script_signature(s2);
+    if sig1 != sig2 {
+        report.add(
+            Severity::Abi,
+            name,
+            format!("signature differs: {:?} vs {:?}", sig1, sig2),
+        )
+    }
+    if level == ComparisonLevel::Code {
+        let code1 = normalized_code(&BinaryIndexedView::Script(s1), &s1.code.code);
+        let code2 = normalized_code(&BinaryIndexedView::Script(s2), &s2.code.code);
+        if code1 != code2 {
+            report.add(Severity::Code, name, code_difference(name, &code1, &code2))
+        }
+    }
+}
+
+fn by_name<T>(map: BTreeMap<move_core_types::identifier::Identifier, T>) -> BTreeMap<String, T> {
+    map.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+/// Returns the ids of the modules a module refers to, other than itself.
+fn dependencies(module: &CompiledModule) -> BTreeSet<String> {
+    let self_id = module.self_id();
+    module
+        .module_handles()
+        .iter()
+        .map(|handle| module.module_id_for_handle(handle))
+        .filter(|id| *id != self_id)
+        .map(|id| id.short_str_lossless())
+        .collect()
+}
+
+/// Returns the signature and the normalized code of each function defined in the module.
+fn functions(module: &CompiledModule) -> BTreeMap<String, (normalized::Function, Vec<String>)> {
+    module
+        .function_defs()
+        .iter()
+        .map(|def| {
+            let (name, fun) = normalized::Function::new(module, def);
+            let code = def
+                .code
+                .as_ref()
+                .map(|unit| normalized_code(&BinaryIndexedView::Module(module), &unit.code))
+                .unwrap_or_default();
+            (name.to_string(), (fun, code))
+        })
+        .collect()
+}
+
+/// Returns the parameter types and the number of type parameters of a script.
+fn script_signature(script: &CompiledScript) -> (String, usize) {
+    let params = types(&BinaryIndexedView::Script(script), script.parameters);
+    (params, script.type_parameters.len())
+}
+
+/// Describes how two normalized codes differ, by the first instruction which differs.
+fn code_difference(fun_name: &str, code1: &[String], code2: &[String]) -> String {
+    let offset = code1
+        .iter()
+        .zip(code2)
+        .take_while(|(bc1, bc2)| bc1 == bc2)
+        .count();
+    let at = |code: &[String]| {
+        code.get(offset)
+            .cloned()
+            .unwrap_or_else(|| "end of code".to_owned())
+    };
+    format!(
+        "code of `{}` differs at offset {}: `{}` vs `{}`",
+        fun_name,
+        offset,
+        at(code1),
+        at(code2)
+    )
+}
+
+/// Renders a type with the names of structs in place of their handles.
+fn type_name(view: &BinaryIndexedView, token: &SignatureToken) -> String {
+    use SignatureToken::*;
+    let struct_name = |idx: StructHandleIndex| {
+        let handle = view.struct_handle_at(idx);
+        format!(
+            "{}::{}",
+            view.module_id_for_handle(view.module_handle_at(handle.module))
+                .short_str_lossless(),
+            view.identifier_at(handle.name)
+        )
+    };
+    match token {
+        Vector(elem) => format!("vector<{}>", type_name(view, elem)),
+        Struct(idx) => struct_name(*idx),
+        StructInstantiation(idx, args) => format!(
+            "{}<{}>",
+            struct_name(*idx),
+            args.iter()
+                .map(|arg| type_name(view, arg))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Reference(ty) => format!("&{}", type_name(view, ty)),
+        MutableReference(ty) => format!("&mut {}", type_name(view, ty)),
+        TypeParameter(idx) => format!("T{}", idx),
+        _ => format!("{:?}", token).to_lowercase(),
+    }
+}
+
+/// Renders the types of a signature, separated by commas.
+fn types(view: &BinaryIndexedView, idx: SignatureIndex) -> String {
+    view.signature_at(idx)
+        .0
+        .iter()
+        .map(|token| type_name(view, token))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Renders the code with the entities denoted by pool indices in place of the indices.
+fn normalized_code(view: &BinaryIndexedView, code: &[Bytecode]) -> Vec<String> {
+    let fun = |idx: FunctionHandleIndex| {
+        let handle = view.function_handle_at(idx);
+        format!(
+            "{}::{}",
+            view.module_id_for_handle(view.module_handle_at(handle.module))
+                .short_str_lossless(),
+            view.identifier_at(handle.name)
+        )
+    };
+    let struct_ = |idx: StructDefinitionIndex| {
+        view.struct_def_at(idx).map_or_else(
+            |_| format!("{:?}", idx),
+            |def| type_name(view, &SignatureToken::Struct(def.struct_handle)),
+        )
+    };
+    let struct_inst = |idx: StructDefInstantiationIndex| {
+        view.struct_instantiation_at(idx).map_or_else(
+            |_| format!("{:?}", idx),
+            |inst| {
+                format!(
+                    "{}<{}>",
+                    struct_(inst.def),
+                    types(view, inst.type_parameters)
+                )
+            },
+        )
+    };
+    let field = |idx: FieldHandleIndex| {
+        view.field_handle_at(idx).map_or_else(
+            |_| format!("{:?}", idx),
+            |handle| format!("{}.{}", struct_(handle.owner), handle.field),
+        )
+    };
+    let field_inst = |idx: FieldInstantiationIndex| {
+        view.field_instantiation_at(idx).map_or_else(
+            |_| format!("{:?}", idx),
+            |inst| {
+                format!(
+                    "{}<{}>",
+                    field(inst.handle),
+                    types(view, inst.type_parameters)
+                )
+            },
+        )
+    };
+    code.iter()
+        .map(|bc| {
+            use Bytecode::*;
+            match bc {
+                Call(idx) => format!("Call({})", fun(*idx)),
+                CallGeneric(idx) => {
+                    let inst = view.function_instantiation_at(*idx);
+                    format!(
+                        "Call({}<{}>)",
+                        fun(inst.handle),
+                        types(view, inst.type_parameters)
+                    )
+                },
+                Pack(idx) => format!("Pack({})", struct_(*idx)),
+                Unpack(idx) => format!("Unpack({})", struct_(*idx)),
+                Exists(idx) => format!("Exists({})", struct_(*idx)),
+                MoveFrom(idx) => format!("MoveFrom({})", struct_(*idx)),
+                MoveTo(idx) => format!("MoveTo({})", struct_(*idx)),
+                MutBorrowGlobal(idx) => format!("MutBorrowGlobal({})", struct_(*idx)),
+                ImmBorrowGlobal(idx) => format!("ImmBorrowGlobal({})", struct_(*idx)),
+                PackGeneric(idx) => format!("Pack({})", struct_inst(*idx)),
+                UnpackGeneric(idx) => format!("Unpack({})", struct_inst(*idx)),
+                ExistsGeneric(idx) => format!("Exists({})", struct_inst(*idx)),
+                MoveFromGeneric(idx) => format!("MoveFrom({})", struct_inst(*idx)),
+                MoveToGeneric(idx) => format!("MoveTo({})", struct_inst(*idx)),
+                MutBorrowGlobalGeneric(idx) => format!("MutBorrowGlobal({})", struct_inst(*idx)),
+                ImmBorrowGlobalGeneric(idx) => format!("ImmBorrowGlobal({})", struct_inst(*idx)),
+                MutBorrowField(idx) => format!("MutBorrowField({})", field(*idx)),
+                ImmBorrowField(idx) => format!("ImmBorrowField({})", field(*idx)),
+                MutBorrowFieldGeneric(idx) => format!("MutBorrowField({})", field_inst(*idx)),
+                ImmBorrowFieldGeneric(idx) => format!("ImmBorrowField({})", field_inst(*idx)),
+                LdConst(idx) => format!("LdConst({:?})", view.constant_at(*idx).data),
+                VecPack(idx, n) => format!("VecPack({}, {})", types(view, *idx), n),
+                VecUnpack(idx, n) => format!("VecUnpack({}, {})", types(view, *idx), n),
+                VecLen(idx) => format!("VecLen({})", types(view, *idx)),
+                VecImmBorrow(idx) => format!("VecImmBorrow({})", types(view, *idx)),
+                VecMutBorrow(idx) => format!("VecMutBorrow({})", types(view, *idx)),
+                VecPushBack(idx) => format!("VecPushBack({})", types(view, *idx)),
+                VecPopBack(idx) => format!("VecPopBack({})", types(view, *idx)),
+                VecSwap(idx) => format!("VecSwap({})", types(view, *idx)),
+                _ => format!("{:?}", bc),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,106 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation of the maps from code offsets to source locations which source coverage is
+//! computed from, written as JSON if `Options::emit_coverage_map` is set.
+//!
+//! The maps are keyed like the coverage maps which are collected from VM traces: modules by
+//! address and name, functions by name, and instructions by code offset. Unlike the source
+//! map, which only has an entry for the first of the instructions generated for a stackless
+//! instruction, a map has an entry for each instruction. Instructions which the compiler
+//! synthesizes are already attributed to the statements around them in the source map.
+//! Scripts are not included, as their coverage is not collected.
+
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{CodeOffset, FunctionDefinitionIndex},
+};
+use move_compiler::compiled_unit::{CompiledUnit, CompiledUnitEnum};
+use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+use move_ir_types::location::Loc;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The source locations of the instructions of the functions of a module.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModuleCodeMap {
+    pub module_addr: AccountAddress,
+    pub module_name: Identifier,
+    pub function_maps: BTreeMap<Identifier, FunctionCodeMap>,
+}
+
+/// The source locations of the instructions of a function.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FunctionCodeMap {
+    pub definition_location: Loc,
+    /// The location of each instruction, by code offset. Empty for native functions.
+    pub code_map: BTreeMap<u64, Loc>,
+}
+
+impl FunctionCodeMap {
+    /// Returns the locations of the instructions at the given code offsets.
+    pub fn locations<'a>(&'a self, offsets: impl IntoIterator<Item = &'a u64>) -> Vec<Loc> {
+        offsets
+            .into_iter()
+            .filter_map(|offset| self.code_map.get(offset).cloned())
+            .collect()
+    }
+}
+
+/// Returns the code maps of the modules among the units.
+pub fn module_code_maps(units: &[CompiledUnit]) -> Vec<ModuleCodeMap> {
+    units
+        .iter()
+        .filter_map(|unit| match unit {
+            CompiledUnitEnum::Module(named_module) => {
+                let module = &named_module.module;
+                let source_map = &named_module.source_map;
+                let function_maps = module
+                    .function_defs()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, def)| {
+                        let def_idx = FunctionDefinitionIndex(idx as u16);
+                        let fun_map = source_map.get_function_source_map(def_idx).ok()?;
+                        let code_len = def.code.as_ref().map_or(0, |code| code.code.len());
+                        let code_map = (0..code_len)
+                            .filter_map(|offset| {
+                                let loc = fun_map.get_code_location(offset as CodeOffset)?;
+                                Some((offset as u64, loc))
+                            })
+                            .collect();
+                        let name =
+                            module.identifier_at(module.function_handle_at(def.function).name);
+                        Some((name.to_owned(), FunctionCodeMap {
+                            definition_location: fun_map.definition_location,
+                            code_map,
+                        }))
+                    })
+                    .collect();
+                Some(ModuleCodeMap {
+                    module_addr: *module.self_id().address(),
+                    module_name: module.self_id().name().to_owned(),
+                    function_maps,
+                })
+            },
+            CompiledUnitEnum::Script(_) => None,
+        })
+        .collect()
+}
+
+/// Writes the code map of each module to `<dir>/<module name>.json`, returning the paths of
+/// the written files.
+pub fn write_code_maps(code_maps: &[ModuleCodeMap], dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut paths = vec![];
+    for code_map in code_maps {
+        let path = dir.join(format!("{}.json", code_map.module_name));
+        fs::write(&path, serde_json::to_string_pretty(code_map)? + "\n")?;
+        paths.push(path)
+    }
+    Ok(paths)
+}
@@ -0,0 +1,112 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Stripping of the private functions of target modules which can never be called, with
+//! `Options::strip_dead_private_functions`. Generated code often leaves helpers behind which
+//! nothing calls anymore, but which still occupy space on chain.
+//!
+//! The live functions of a module are the public, friend, entry, native, and inline functions,
+//! `init_module`, `#[test]`, `#[test_only]`, and `#[verify_only]` functions, those used in specs,
+//! and the functions called by live functions. Since test code is part of the model only with
+//! `Options::compile_test_code`, functions called only by test code are kept exactly if test
+//! code is compiled. The other functions are excluded from file format generation, and a note
+//! lists them for each module.
+
+use crate::{
+    diagnostic_codes::STRIPPED_DEAD_FUNCTIONS,
+    file_format_generator::functions_in_source_order,
+    unused_items::{is_used_in_specs, INIT_MODULE},
+};
+use codespan_reporting::diagnostic::Severity;
+use move_binary_format::file_format::Visibility;
+use move_model::model::{FunId, FunctionEnv, GlobalEnv, ModuleEnv, QualifiedId};
+use std::collections::BTreeSet;
+
+/// The functions excluded from file format generation, installed as an extension of the env.
+#[derive(Debug, Default)]
+pub(crate) struct StrippedFunctions(BTreeSet<QualifiedId<FunId>>);
+
+/// Determines the functions of the target modules which can never be called, reports them, and
+/// excludes them from file format generation.
+pub(crate) fn strip_dead_functions(env: &GlobalEnv) {
+    let mut stripped = BTreeSet::new();
+    for module_env in env
+        .get_modules()
+        .filter(|module_env| module_env.is_target() && !module_env.is_script_module())
+    {
+        let dead = dead_functions(&module_env);
+        if dead.is_empty() {
+            continue;
+        }
+        let names = dead
+            .iter()
+            .map(|fun_env| format!("`{}`", fun_env.get_name_str()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        STRIPPED_DEAD_FUNCTIONS.diag(
+            env,
+            Severity::Note,
+            &module_env.get_loc(),
+            &format!(
+                "stripped unreachable private functions from `{}`: {}",
+                module_env.get_full_name_str(),
+                names
+            ),
+        );
+        stripped.extend(dead.iter().map(|fun_env| fun_env.get_qualified_id()))
+    }
+    env.set_extension(StrippedFunctions(stripped))
+}
+
+/// Returns true if the function is excluded from file format generation.
+pub(crate) fn is_stripped(env: &GlobalEnv, fun_env: &FunctionEnv) -> bool {
+    env.get_extension::<StrippedFunctions>()
+        .is_some_and(|stripped| stripped.0.contains(&fun_env.get_qualified_id()))
+}
+
+/// Returns the functions of the module which are not live, in the order of their declaration.
+fn dead_functions<'env>(module_env: &ModuleEnv<'env>) -> Vec<FunctionEnv<'env>> {
+    let env = module_env.env;
+    let functions = functions_in_source_order(module_env);
+    // If the callees of a function are not known, neither are the live functions.
+    if functions
+        .iter()
+        .any(|fun_env| fun_env.get_called_functions().is_none() && !fun_env.is_native())
+    {
+        return vec![];
+    }
+    let mut live = BTreeSet::new();
+    let mut todo = functions
+        .iter()
+        .filter(|fun_env| is_root(fun_env))
+        .map(|fun_env| fun_env.get_qualified_id())
+        .collect::<Vec<_>>();
+    while let Some(id) = todo.pop() {
+        if !live.insert(id) {
+            continue;
+        }
+        let callees = env.get_function(id).get_called_functions().cloned();
+        todo.extend(
+            callees
+                .into_iter()
+                .flatten()
+                .filter(|callee| callee.module_id == module_env.get_id()),
+        )
+    }
+    functions
+        .into_iter()
+        .filter(|fun_env| !live.contains(&fun_env.get_qualified_id()))
+        .collect()
+}
+
+/// Returns true if the function is live regardless of calls from its module.
+fn is_root(fun_env: &FunctionEnv) -> bool {
+    fun_env.visibility() != Visibility::Private
+        || fun_env.is_entry()
+        || fun_env.is_native()
+        || fun_env.is_inline()
+        || fun_env.is_test_only()
+        || fun_env.is_verify_only()
+        || fun_env.get_name_str() == INIT_MODULE
+        || is_used_in_specs(fun_env)
+}
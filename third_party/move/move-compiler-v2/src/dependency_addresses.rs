@@ -0,0 +1,291 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements the address mappings of dependencies given by
+//! `Options::dependency_address_overrides`.
+//!
+//! By default, dependencies are compiled with the named address mapping of the sources. An
+//! override binds a named address to another address for the dependency files whose path
+//! starts with a given prefix, for example for a vendored package written against `@owner`
+//! which is deployed at another address than the one `owner` denotes in the sources. The
+//! dependency files are grouped by the overrides which apply to them, and each group is passed
+//! to the model builder as a package of its own, with the mapping of the sources updated by
+//! the overrides.
+//!
+//! Sources and dependencies may then disagree on the address of a name, so a reference to a
+//! module of the dependency via the name does not resolve. The errors about such references
+//! get a note pointing at the mapping under which the module is declared. Overrides which bind
+//! the same name to different addresses for the same file are reported as errors, located at
+//! the file.
+
+use crate::diagnostic_codes;
+use codespan::{FileId, Span};
+use codespan_reporting::diagnostic::{Diagnostic, LabelStyle};
+use move_command_line_common::{
+    address::NumericalAddress,
+    files::{find_move_filenames, FileHash},
+};
+use move_compiler::{
+    parser::{
+        ast::{Definition, LeadingNameAccess, LeadingNameAccess_},
+        syntax::parse_file_string,
+    },
+    shared::{CompilationEnv, Flags},
+};
+use move_core_types::account_address::AccountAddress;
+use move_model::{
+    ast::{Address, ModuleName},
+    model::{GlobalEnv, Loc},
+    PackageInfo,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+/// An override of a named address for the dependency files under a path prefix.
+struct AddressOverride {
+    prefix: String,
+    name: String,
+    address: NumericalAddress,
+}
+
+/// The address mappings of the dependencies.
+pub(crate) struct DependencyAddresses {
+    primary: BTreeMap<String, NumericalAddress>,
+    overrides: Vec<AddressOverride>,
+    /// The bindings of names which differ from the primary mapping, as pairs of the name and
+    /// address with the prefix of the override.
+    bindings: Vec<(String, AccountAddress, String)>,
+    /// The conflicting overrides, as pairs of a file and a message.
+    conflicts: Vec<(String, String)>,
+}
+
+impl DependencyAddresses {
+    /// Creates the mappings from the primary one and the overrides, given as triples of a path
+    /// prefix, a name, and an address.
+    pub fn new(
+        primary: &BTreeMap<String, NumericalAddress>,
+        overrides: &[(String, String, String)],
+    ) -> anyhow::Result<Self> {
+        let overrides = overrides
+            .iter()
+            .map(|(prefix, name, address)| {
+                Ok(AddressOverride {
+                    prefix: prefix.clone(),
+                    name: name.clone(),
+                    address: NumericalAddress::parse_str(address).map_err(anyhow::Error::msg)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            primary: primary.clone(),
+            overrides,
+            bindings: vec![],
+            conflicts: vec![],
+        })
+    }
+
+    /// Returns the packages of the dependencies, one for each set of overrides applying to
+    /// some of them. Directories of dependencies are expanded into their files only if there
+    /// are overrides.
+    pub fn packages(
+        &mut self,
+        dependencies: Vec<String>,
+        dependency_buffers: Vec<(String, String)>,
+    ) -> anyhow::Result<Vec<PackageInfo>> {
+        if self.overrides.is_empty() {
+            return Ok(vec![PackageInfo {
+                sources: dependencies,
+                source_buffers: dependency_buffers,
+                address_map: self.primary.clone(),
+            }]);
+        }
+        let mut groups: BTreeMap<Vec<(String, AccountAddress)>, PackageInfo> = BTreeMap::new();
+        for path in find_move_filenames(&dependencies, true)? {
+            let package = self.package_of(&mut groups, &path);
+            package.sources.push(path)
+        }
+        for (path, content) in dependency_buffers {
+            let package = self.package_of(&mut groups, &path);
+            package.source_buffers.push((path, content))
+        }
+        Ok(groups.into_values().collect())
+    }
+
+    /// Returns the package of the file with the given path, which is added to the groups if
+    /// needed. Records the conflicts of the overrides applying to the file.
+    fn package_of<'a>(
+        &mut self,
+        groups: &'a mut BTreeMap<Vec<(String, AccountAddress)>, PackageInfo>,
+        path: &str,
+    ) -> &'a mut PackageInfo {
+        let mut address_map = self.primary.clone();
+        let mut applied: BTreeMap<&str, &AddressOverride> = BTreeMap::new();
+        for over in &self.overrides {
+            if !Path::new(path).starts_with(&over.prefix) {
+                continue;
+            }
+            if let Some(other) = applied.get(over.name.as_str()) {
+                if other.address.into_inner() != over.address.into_inner() {
+                    self.conflicts.push((
+                        path.to_owned(),
+                        format!(
+                            "conflicting address overrides for `{}` in dependency file: {} for \
+                             `{}` and {} for `{}`",
+                            over.name,
+                            other.address.into_inner().to_hex_literal(),
+                            other.prefix,
+                            over.address.into_inner().to_hex_literal(),
+                            over.prefix
+                        ),
+                    ))
+                }
+                continue;
+            }
+            applied.insert(&over.name, over);
+            address_map.insert(over.name.clone(), over.address);
+        }
+        let key = applied
+            .values()
+            .map(|over| (over.name.clone(), over.address.into_inner()))
+            .collect::<Vec<_>>();
+        for over in applied.values() {
+            let address = over.address.into_inner();
+            let differs = self
+                .primary
+                .get(&over.name)
+                .map_or(true, |primary| primary.into_inner() != address);
+            let binding = (over.name.clone(), address, over.prefix.clone());
+            if differs && !self.bindings.contains(&binding) {
+                self.bindings.push(binding)
+            }
+        }
+        groups.entry(key).or_insert_with(|| PackageInfo {
+            sources: vec![],
+            source_buffers: vec![],
+            address_map,
+        })
+    }
+
+    /// Reports the conflicting overrides, and adds notes to the errors about references to
+    /// modules which are declared under another binding of their address name.
+    pub fn report(self, env: &GlobalEnv) {
+        if !self.bindings.is_empty() {
+            env.add_diag_notes(|diag| {
+                let Some((name, module)) = unresolved_module_reference(env, diag) else {
+                    return vec![];
+                };
+                // Dependency modules which are not used by the sources are not in the model,
+                // so the files of the dependencies are parsed instead.
+                let declared_in_dependencies = self
+                    .bindings
+                    .iter()
+                    .filter(|(n, addr, prefix)| {
+                        n == name && declares_module(env, prefix, name, *addr, module)
+                    })
+                    .map(|(_, addr, prefix)| (*addr, format!("dependencies under `{}`", prefix)));
+                let module_sym = env.symbol_pool().make(module);
+                let declared_in_sources = self
+                    .primary
+                    .get(name)
+                    .map(|addr| addr.into_inner())
+                    .filter(|addr| {
+                        let module_name = ModuleName::new(Address::Numerical(*addr), module_sym);
+                        env.find_module(&module_name).is_some()
+                    })
+                    .map(|addr| (addr, "the sources".to_owned()));
+                declared_in_dependencies
+                    .chain(declared_in_sources)
+                    .map(|(addr, origin)| {
+                        let addr = addr.to_hex_literal();
+                        format!(
+                            "module `{}` is declared at {}, since `{}` is mapped to {} for {}; \
+                             refer to it as `{}::{}`",
+                            module, addr, name, addr, origin, addr, module
+                        )
+                    })
+                    .take(1)
+                    .collect()
+            })
+        }
+        for (path, msg) in self.conflicts {
+            let loc = env
+                .get_source_file_ids()
+                .into_iter()
+                .find(|file_id| env.get_file(*file_id) == path.as_str())
+                .map(|file_id| Loc::new(file_id, Span::new(0, 0)))
+                .unwrap_or_else(|| env.unknown_loc());
+            diagnostic_codes::CONFLICTING_ADDRESS_OVERRIDES.error(env, &loc, &msg)
+        }
+    }
+}
+
+/// Returns the address name and module of an error about a call of a function of a module
+/// which does not exist at the address, or about an unbound module, if the module was referred
+/// to via an address name, like in `owner::m::f()`. As the errors only show the address, the
+/// reference is read from the source at the primary label.
+fn unresolved_module_reference<'a>(
+    env: &'a GlobalEnv,
+    diag: &Diagnostic<FileId>,
+) -> Option<(&'a str, &'a str)> {
+    let label = diag
+        .labels
+        .iter()
+        .find(|label| label.style == LabelStyle::Primary)?;
+    if !diag.message.starts_with("no function named") && !label.message.contains("Unbound module") {
+        return None;
+    }
+    let reference = env
+        .get_file_source(label.file_id)
+        .get(label.range.clone())?;
+    let mut parts = reference.split("::").map(str::trim);
+    let name = parts.next()?;
+    let module = parts.next()?;
+    let module = &module[..module
+        .find(|c: char| !is_identifier_char(c))
+        .unwrap_or(module.len())];
+    let is_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(is_identifier_char);
+    (is_name && !module.is_empty()).then_some((name, module))
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Returns whether a dependency file under the prefix declares the module at the address of
+/// the name, either via the name or via the address itself.
+fn declares_module(
+    env: &GlobalEnv,
+    prefix: &str,
+    name: &str,
+    addr: AccountAddress,
+    module: &str,
+) -> bool {
+    let is_address = |access: &LeadingNameAccess| match &access.value {
+        LeadingNameAccess_::Name(n) => n.value.as_str() == name,
+        LeadingNameAccess_::AnonymousAddress(a) => a.into_inner() == addr,
+    };
+    env.get_source_file_ids().into_iter().any(|file_id| {
+        if !Path::new(env.get_file(file_id)).starts_with(prefix) {
+            return false;
+        }
+        let content = env.get_file_source(file_id);
+        let mut compilation_env = CompilationEnv::new(Flags::empty(), BTreeSet::new());
+        let Ok((defs, _)) =
+            parse_file_string(&mut compilation_env, FileHash::new(content), content)
+        else {
+            return false;
+        };
+        defs.into_iter().any(|def| match def {
+            Definition::Module(m) => {
+                m.name.0.value.as_str() == module && m.address.as_ref().map_or(false, is_address)
+            },
+            Definition::Address(a) => {
+                is_address(&a.addr) && a.modules.iter().any(|m| m.name.0.value.as_str() == module)
+            },
+            Definition::Script(_) => false,
+        })
+    })
+}
@@ -0,0 +1,195 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements an on-disk cache of the dependencies of a compilation, which allows the checker
+//! to skip most of the work on dependencies which did not change since the last compilation.
+//!
+//! The checker only needs the declarations of dependencies, and the bodies of their inline
+//! functions, which are expanded into the code of callers. The cache therefore holds a
+//! stripped copy of each dependency file, in which the other functions are declared `native`,
+//! without their bodies, and from which the module-level spec blocks are removed. Compiling
+//! against the stripped files generates the same code for the target modules, since code only
+//! refers to the signatures of the functions of dependencies. Removed text is replaced by its
+//! line breaks, so locations in dependencies keep their line numbers.
+//!
+//! The cache has a single entry, stored under a key which is a hash over:
+//!
+//! - the version of the compiler,
+//! - the paths and hashes of the dependency files, including `Options::dependency_buffers`,
+//! - the named address mapping, with the overrides for dependencies, and
+//! - the options which affect code generation.
+//!
+//! If the key does not match, the dependencies are checked in full, and the entry is replaced
+//! unless they have errors. If the stripped files have errors, say since a stripped function
+//! was referred to by a spec block of an inline function, the entry is discarded and the
+//! dependencies are checked in full.
+
+use crate::Options;
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use move_command_line_common::files::{find_move_filenames, FileHash};
+use move_compiler::{
+    parser::{
+        ast::{Definition, FunctionBody_, ModuleMember},
+        syntax::parse_file_string,
+    },
+    shared::{CompilationEnv, Flags},
+};
+use move_model::model::GlobalEnv;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file of the entry in the cache directory.
+const ENTRY_FILE: &str = "dependencies.cache";
+
+/// The cache of the dependencies of a compilation.
+pub(crate) struct DependencyCache {
+    path: PathBuf,
+    key: FileHash,
+    /// The paths and contents of the dependency files.
+    files: Vec<(String, String)>,
+}
+
+/// The representation of the dependencies in the cache.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: [u8; 32],
+    /// The paths and stripped contents of the dependency files.
+    files: Vec<(String, String)>,
+}
+
+impl DependencyCache {
+    /// Creates the cache in `dir` for the dependencies of the options, reading the dependency
+    /// files to compute the key.
+    pub fn new(dir: &Path, options: &Options) -> anyhow::Result<Self> {
+        let mut files = vec![];
+        for path in find_move_filenames(&options.dependencies, true)? {
+            let content = fs::read_to_string(&path)?;
+            files.push((path, content))
+        }
+        files.extend(options.dependency_buffers.iter().cloned());
+        files.sort();
+        let mut text = format!("compiler {}\n", env!("CARGO_PKG_VERSION"));
+        for (path, content) in &files {
+            text.push_str(&format!("dependency {} {}\n", path, FileHash::new(content)))
+        }
+        let mut addresses = options.named_address_mapping.clone();
+        addresses.extend(
+            options
+                .dependency_address_overrides
+                .iter()
+                .map(|(prefix, name, address)| format!("{}:{}={}", prefix, name, address)),
+        );
+        addresses.sort();
+        text.push_str(&format!(
+            "addresses {}\nflags {}\n",
+            addresses.join(","),
+            options.code_generation_flags().join(",")
+        ));
+        Ok(Self {
+            path: dir.join(ENTRY_FILE),
+            key: FileHash::new(&text),
+            files,
+        })
+    }
+
+    /// Returns the stripped dependency files, if the entry of the cache has a matching key.
+    /// An entry which cannot be read is treated as missing.
+    pub fn load(&self) -> Option<Vec<(String, String)>> {
+        fs::read(&self.path)
+            .ok()
+            .and_then(|bytes| bcs::from_bytes::<CacheEntry>(&bytes).ok())
+            .filter(|entry| entry.key == self.key.0)
+            .map(|entry| entry.files)
+    }
+
+    /// Writes the stripped dependency files into the cache directory, which is created if
+    /// needed. Nothing is written if a dependency file cannot be parsed.
+    pub fn store(&self) -> anyhow::Result<()> {
+        let Some(files) = self
+            .files
+            .iter()
+            .map(|(path, content)| Some((path.clone(), strip(content)?)))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(());
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?
+        }
+        let entry = CacheEntry {
+            key: self.key.0,
+            files,
+        };
+        fs::write(&self.path, bcs::to_bytes(&entry)?)?;
+        Ok(())
+    }
+
+    /// Removes the entry of the cache, if any.
+    pub fn discard(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Returns true if the env has errors outside of the files of the target modules.
+pub(crate) fn has_dependency_errors(env: &GlobalEnv) -> bool {
+    let target_files = env
+        .get_modules()
+        .filter(|module_env| module_env.is_target())
+        .map(|module_env| module_env.get_loc().file_id())
+        .collect::<BTreeSet<_>>();
+    env.get_unreported_diags(|d| d.severity >= Severity::Error)
+        .iter()
+        .flat_map(|d| &d.labels)
+        .any(|l| l.style == LabelStyle::Primary && !target_files.contains(&l.file_id))
+}
+
+/// Returns the content of a dependency file without the bodies of functions which are not
+/// inline, which are declared `native` instead, and without module-level spec blocks. Returns
+/// `None` if the file cannot be parsed.
+fn strip(content: &str) -> Option<String> {
+    let mut compilation_env = CompilationEnv::new(Flags::empty(), BTreeSet::new());
+    let (defs, _) =
+        parse_file_string(&mut compilation_env, FileHash::new(content), content).ok()?;
+    let modules = defs.into_iter().flat_map(|def| match def {
+        Definition::Module(module) => vec![module],
+        Definition::Address(address) => address.modules,
+        Definition::Script(_) => vec![],
+    });
+    // The edits as pairs of a byte range and its replacement.
+    let mut edits = vec![];
+    for module in modules.filter(|module| !module.is_spec_module) {
+        for member in module.members {
+            match member {
+                ModuleMember::Function(fun) if !fun.inline => {
+                    if let FunctionBody_::Defined(_) = fun.body.value {
+                        let name_start = fun.name.0.loc.start() as usize;
+                        let fun_start = content[..name_start].rfind("fun")?;
+                        edits.push((fun_start..fun_start, "native "));
+                        edits.push((fun.body.loc.usize_range(), ";"))
+                    }
+                },
+                // Spec blocks with attributes are kept, as the attributes would otherwise
+                // apply to the next member.
+                ModuleMember::Spec(spec) if spec.value.attributes.is_empty() => {
+                    edits.push((spec.loc.usize_range(), ""))
+                },
+                _ => {},
+            }
+        }
+    }
+    edits.sort_by_key(|(range, _)| (range.start, range.end));
+    let mut stripped = String::with_capacity(content.len());
+    let mut pos = 0;
+    for (range, replacement) in edits {
+        stripped.push_str(&content[pos..range.start]);
+        stripped.push_str(replacement);
+        stripped.extend(content[range.clone()].chars().filter(|c| *c == '\n'));
+        pos = range.end
+    }
+    stripped.push_str(&content[pos..]);
+    Some(stripped)
+}
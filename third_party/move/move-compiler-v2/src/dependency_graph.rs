@@ -0,0 +1,209 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Graphs of the dependencies of the target modules in Graphviz DOT format, written if
+//! `Options::emit_dependency_graph` is set, for understanding the coupling of the modules of a
+//! package.
+//!
+//! The module graph has an edge from a target module to each module whose functions it calls,
+//! labeled `calls`, and to each other module it uses, by `use` declarations or in types,
+//! labeled `uses`. Like in the dependency check of the checker, a friend declaration adds an
+//! edge labeled `friend` from the friend to the declaring module, since the friend depends on
+//! the declaring module being published first. Nodes are labeled with the address and name of
+//! the module, and filled in a different color for targets and dependencies.
+//!
+//! The function graph, written if `Options::dependency_graph_functions` is also set, has an
+//! edge from each function of a target module to each function of a target module it calls.
+//!
+//! In both graphs, the edges on a cycle are drawn in red. Scripts are not included.
+
+use crate::compilation_cache;
+use move_model::model::{FunId, GlobalEnv, ModuleEnv, ModuleId, QualifiedId};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file of the module graph.
+pub const MODULE_GRAPH_FILE: &str = "modules.dot";
+/// The name of the file of the function graph.
+pub const FUNCTION_GRAPH_FILE: &str = "functions.dot";
+
+/// The kind of a dependency of a module on another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum DependencyKind {
+    Call,
+    Use,
+    Friend,
+}
+
+impl DependencyKind {
+    /// Returns the attributes of the edges of the kind.
+    fn attributes(self) -> &'static str {
+        match self {
+            DependencyKind::Call => "label=\"calls\"",
+            DependencyKind::Use => "label=\"uses\", style=dashed",
+            DependencyKind::Friend => "label=\"friend\", style=dotted",
+        }
+    }
+}
+
+/// Writes the module graph into the directory, and the function graph if requested. Returns the
+/// paths of the written files.
+pub fn write_dependency_graphs(
+    env: &GlobalEnv,
+    dir: &Path,
+    with_functions: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut written = vec![];
+    let path = dir.join(MODULE_GRAPH_FILE);
+    fs::write(&path, module_graph(env))?;
+    written.push(path);
+    if with_functions {
+        let path = dir.join(FUNCTION_GRAPH_FILE);
+        fs::write(&path, function_graph(env))?;
+        written.push(path)
+    }
+    Ok(written)
+}
+
+/// Returns the module graph of the target modules.
+pub fn module_graph(env: &GlobalEnv) -> String {
+    let mut edges = BTreeSet::new();
+    for module_env in target_modules(env) {
+        let id = module_env.get_id();
+        let called = module_env
+            .get_functions()
+            .filter_map(|fun_env| fun_env.get_called_functions().cloned())
+            .flatten()
+            .map(|callee| callee.module_id)
+            .filter(|callee| *callee != id)
+            .collect::<BTreeSet<_>>();
+        for used in compilation_cache::direct_dependencies(&module_env) {
+            if !called.contains(&used) {
+                edges.insert((id, used, DependencyKind::Use));
+            }
+        }
+        for callee in called {
+            edges.insert((id, callee, DependencyKind::Call));
+        }
+        for friend in module_env.get_friend_modules() {
+            edges.insert((friend, id, DependencyKind::Friend));
+        }
+    }
+    let nodes = edges
+        .iter()
+        .flat_map(|(from, to, _)| [*from, *to])
+        .chain(target_modules(env).map(|module_env| module_env.get_id()))
+        .collect::<BTreeSet<_>>();
+    let name = |id: ModuleId| env.get_module(id).get_full_name_str();
+    let cyclic = cyclic_edges(edges.iter().map(|(from, to, _)| (*from, *to)));
+    let mut out = String::from("digraph modules {\n    node [shape=box];\n");
+    for id in nodes {
+        let color = if env.get_module(id).is_target() {
+            "lightblue"
+        } else {
+            "lightgray"
+        };
+        out.push_str(&format!(
+            "    \"{}\" [style=filled, fillcolor={}];\n",
+            name(id),
+            color
+        ))
+    }
+    for (from, to, kind) in &edges {
+        out.push_str(&edge(
+            &name(*from),
+            &name(*to),
+            Some(kind.attributes()),
+            cyclic.contains(&(*from, *to)),
+        ))
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Returns the function graph of the target modules.
+pub fn function_graph(env: &GlobalEnv) -> String {
+    let mut nodes = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for module_env in target_modules(env) {
+        for fun_env in module_env.get_functions() {
+            let id = fun_env.get_qualified_id();
+            nodes.insert(id);
+            for callee in fun_env.get_called_functions().into_iter().flatten() {
+                if env.get_module(callee.module_id).is_target() {
+                    edges.insert((id, *callee));
+                }
+            }
+        }
+    }
+    let name = |id: QualifiedId<FunId>| env.get_function(id).get_full_name_with_address();
+    let cyclic = cyclic_edges(edges.iter().copied());
+    let mut out = String::from("digraph functions {\n    node [shape=box];\n");
+    for id in nodes {
+        out.push_str(&format!(
+            "    \"{}\" [style=filled, fillcolor=lightblue];\n",
+            name(id)
+        ))
+    }
+    for (from, to) in &edges {
+        out.push_str(&edge(
+            &name(*from),
+            &name(*to),
+            None,
+            cyclic.contains(&(*from, *to)),
+        ))
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Returns the target modules which are not scripts.
+fn target_modules(env: &GlobalEnv) -> impl Iterator<Item = ModuleEnv<'_>> {
+    env.get_modules()
+        .filter(|module_env| module_env.is_target() && !module_env.is_script_module())
+}
+
+/// Returns the line of an edge with the attributes, drawn in red if on a cycle.
+fn edge(from: &str, to: &str, attributes: Option<&str>, cyclic: bool) -> String {
+    let attributes = attributes
+        .into_iter()
+        .chain(cyclic.then_some("color=red"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if attributes.is_empty() {
+        format!("    \"{}\" -> \"{}\";\n", from, to)
+    } else {
+        format!("    \"{}\" -> \"{}\" [{}];\n", from, to, attributes)
+    }
+}
+
+/// Returns the edges which are on a cycle, that is, whose source is reachable from their
+/// target.
+fn cyclic_edges<N: Copy + Ord>(edges: impl Iterator<Item = (N, N)>) -> BTreeSet<(N, N)> {
+    let mut successors = BTreeMap::<N, Vec<N>>::new();
+    for (from, to) in edges {
+        successors.entry(from).or_default().push(to)
+    }
+    let reaches = |start: N, goal: N| {
+        let mut visited = BTreeSet::new();
+        let mut todo = vec![start];
+        while let Some(node) = todo.pop() {
+            if node == goal {
+                return true;
+            }
+            if visited.insert(node) {
+                todo.extend(successors.get(&node).into_iter().flatten().copied())
+            }
+        }
+        false
+    };
+    successors
+        .iter()
+        .flat_map(|(from, tos)| tos.iter().map(move |to| (*from, *to)))
+        .filter(|(from, to)| reaches(*to, *from))
+        .collect()
+}
@@ -0,0 +1,479 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Stable codes of the diagnostics reported by compiler v2, and a registry mapping them to
+//! explanations, as shown by `--explain <code>`.
+//!
+//! A code consists of a letter for the severity, `E` for errors and internal errors, `W` for
+//! warnings, and `N` for notes, followed by five digits. The first two digits identify the phase which reports the
+//! diagnostic:
+//!
+//! - `00`: the driver, for example validation of options
+//! - `01`: bytecode generation
+//! - `02`: the bytecode pipeline
+//! - `03`: file format generation
+//! - `04`: bytecode verification
+//! - `05`: unit tests
+//! - `06`: checks of script entry points
+//! - `07`: lints of the model built by the checker
+//!
+//! Within a phase, `000` is reserved for internal errors. Codes must never be reused for a
+//! different kind of diagnostic, since tools refer to them, for example to suppress
+//! diagnostics. Diagnostics reported by the v1 front end, which the checker reuses, do not
+//! have codes of this scheme.
+
+use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
+use move_model::model::{GlobalEnv, Loc};
+
+/// A stable code identifying a kind of diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DiagnosticCode {
+    pub code: &'static str,
+    /// A one line summary of the kind of diagnostic.
+    pub summary: &'static str,
+    /// A longer description, explaining the cause of the diagnostic and how to address it.
+    pub explanation: &'static str,
+}
+
+impl DiagnosticCode {
+    /// Reports a diagnostic with this code at the location.
+    pub fn diag(&self, env: &GlobalEnv, severity: Severity, loc: &Loc, msg: &str) {
+        env.add_diag(
+            Diagnostic::new(severity)
+                .with_code(self.code)
+                .with_message(msg)
+                .with_labels(vec![Label::primary(loc.file_id(), loc.span())]),
+        )
+    }
+
+    /// Reports an error with this code at the location.
+    pub fn error(&self, env: &GlobalEnv, loc: &Loc, msg: &str) {
+        self.diag(env, Severity::Error, loc, msg)
+    }
+
+    /// Reports an error with this code at the location, with secondary labels pointing to
+    /// related locations, and notes.
+    pub fn error_with_context(
+        &self,
+        env: &GlobalEnv,
+        loc: &Loc,
+        msg: &str,
+        labels: Vec<(Loc, String)>,
+        notes: Vec<String>,
+    ) {
+        self.diag_with_context(env, Severity::Error, loc, msg, labels, notes)
+    }
+
+    /// Reports a warning with this code at the location, with secondary labels pointing to
+    /// related locations, and notes.
+    pub fn warning_with_context(
+        &self,
+        env: &GlobalEnv,
+        loc: &Loc,
+        msg: &str,
+        labels: Vec<(Loc, String)>,
+        notes: Vec<String>,
+    ) {
+        self.diag_with_context(env, Severity::Warning, loc, msg, labels, notes)
+    }
+
+    fn diag_with_context(
+        &self,
+        env: &GlobalEnv,
+        severity: Severity,
+        loc: &Loc,
+        msg: &str,
+        labels: Vec<(Loc, String)>,
+        notes: Vec<String>,
+    ) {
+        let mut all_labels = vec![Label::primary(loc.file_id(), loc.span())];
+        all_labels.extend(
+            labels.into_iter().map(|(loc, label)| {
+                Label::secondary(loc.file_id(), loc.span()).with_message(label)
+            }),
+        );
+        env.add_diag(
+            Diagnostic::new(severity)
+                .with_code(self.code)
+                .with_message(msg)
+                .with_labels(all_labels)
+                .with_notes(notes),
+        )
+    }
+
+    /// Reports a warning with this code at the location.
+    pub fn warning(&self, env: &GlobalEnv, loc: &Loc, msg: &str) {
+        self.diag(env, Severity::Warning, loc, msg)
+    }
+
+    /// Reports an internal error with this code at the location.
+    pub fn bug(&self, env: &GlobalEnv, loc: &Loc, msg: &str) {
+        self.diag(env, Severity::Bug, loc, msg)
+    }
+
+    /// Reports a diagnostic with this code which is not related to a location in the sources.
+    pub fn diag_without_loc(&self, env: &GlobalEnv, severity: Severity, msg: &str) {
+        env.add_diag(
+            Diagnostic::new(severity)
+                .with_code(self.code)
+                .with_message(msg),
+        )
+    }
+
+    /// Reports an error with this code which is not related to a location in the sources.
+    pub fn error_without_loc(&self, env: &GlobalEnv, msg: &str) {
+        self.diag_without_loc(env, Severity::Error, msg)
+    }
+
+    /// Returns the explanation of the code, as shown by `--explain`.
+    pub fn render_explanation(&self) -> String {
+        format!("{}: {}\n\n{}\n", self.code, self.summary, self.explanation)
+    }
+}
+
+macro_rules! diagnostic_codes {
+    ($($name:ident = $code:literal, $summary:literal, $explanation:literal;)*) => {
+        $(
+            #[doc = $summary]
+            pub const $name: DiagnosticCode = DiagnosticCode {
+                code: $code,
+                summary: $summary,
+                explanation: $explanation,
+            };
+        )*
+
+        /// Returns the registry of all diagnostic codes.
+        pub fn registry() -> &'static [DiagnosticCode] {
+            &[$($name),*]
+        }
+    };
+}
+
+/// Returns the registered diagnostic code with the given name, if any.
+pub fn find(code: &str) -> Option<&'static DiagnosticCode> {
+    registry().iter().find(|c| c.code == code)
+}
+
+diagnostic_codes! {
+    // Driver
+    INVALID_EXPERIMENT = "E00001",
+        "invalid experiment setting",
+        "An experiment given with `--experiments` is not known, or its value is neither `on` \
+         nor `off`. Use `--list-experiments` to see the known experiments.";
+    UNKNOWN_PIPELINE_POSITION = "E00002",
+        "unknown position for a registered pipeline processor",
+        "A processor registered via `Options::register_pipeline_extension` is to be inserted \
+         before or after a processor which is not part of the bytecode pipeline. Processors \
+         are referred to by the name returned by `FunctionTargetProcessor::name`, and whether \
+         a processor is part of the pipeline may depend on the active experiments.";
+    UNKNOWN_LINT = "W00003",
+        "unknown lint in `lint::allow` attribute",
+        "The `lint::allow` attribute of a module, script, or function names a lint which is not \
+         known, or is not of the form `#[lint::allow(<lint>, ..)]`. Known lints are \
+         `unused_variable`, `unreachable_code`, `unused_item`, and `expensive_copy`.";
+    INVALID_WARNING_CATEGORY = "E00004",
+        "unknown warning category",
+        "A warning category given with `--Wno`, `--Werror`, or `--W` is neither the name of a \
+         lint, like `unused_variable`, nor the code of a warning, like `W02050`.";
+    DUPLICATE_SOURCE_PATH = "W00005",
+        "source or dependency given more than once",
+        "A path is given more than once as a source, or more than once as a dependency, \
+         possibly spelled differently, like `./m.move` and `m.move`. It is compiled once. \
+         Remove the duplicate entry.";
+    SOURCE_IS_DEPENDENCY = "E00006",
+        "path given both as a source and as a dependency",
+        "A path is given both as a source and as a dependency, so it is unclear whether its \
+         modules are compiled. A common cause is a package which lists its own sources among \
+         its dependencies. Remove the path from the dependencies.";
+    IDENTICAL_SOURCE_FILES = "W00007",
+        "distinct files with identical contents",
+        "Two files among the sources and dependencies have identical contents. Files are \
+         identified by the hash of their contents, so locations in one are reported as \
+         locations in the other. Usually, one of the files is a stale copy, which should be \
+         removed from the build.";
+    UNMATCHED_MODULE_FILTER = "E00008",
+        "module filter matches no target module",
+        "A pattern given with `--module-filter` matches the full name of no target module, \
+         like `0x42::m`. Patterns may use `*` to match any sequence of characters, as in \
+         `0x42::*`. Check the address, which is written as a hex literal, and the spelling of \
+         the module name.";
+    MISPLACED_ATTRIBUTE = "E00009",
+        "attribute attached to an item it is not allowed on",
+        "An attribute registered via `Options::extra_known_attributes` is attached to a \
+         module, function, or struct, but its specification does not allow it there. Move the \
+         attribute to one of the allowed items, or remove it.";
+    MALFORMED_ATTRIBUTE = "E00010",
+        "attribute with arguments of the wrong shape",
+        "An attribute registered via `Options::extra_known_attributes` has arguments which do \
+         not match its specification: either no arguments, as in `#[name]`, a list of \
+         arguments, as in `#[name(a, b)]`, or a value, as in `#[name = 1]`.";
+    REDUNDANT_UNCHECKED_ATTRIBUTES = "W00011",
+        "unchecked attributes given while all attribute checks are skipped",
+        "Attributes are given with `--unchecked-attributes`, but `--skip-attribute-checks` is \
+         also set, which skips the checks of all attributes, so typos in attribute names go \
+         unnoticed. Remove `--skip-attribute-checks` to only skip the checks of the listed \
+         attributes.";
+    UNKNOWN_BUILD_CONFIG_FLAG = "E00012",
+        "flag of a `cfg_attr` attribute not set in the build configuration",
+        "A `cfg_attr` attribute refers to a flag which is not set with `--build-config`, as in \
+         `--build-config testnet=true`. Every flag must be set to `true` or `false`, so a \
+         misspelled flag does not silently exclude items from the build.";
+    MALFORMED_CFG_ATTR = "E00013",
+        "`cfg_attr` attribute with conditions of the wrong shape",
+        "A `cfg_attr` attribute must list conditions, each a flag, as in \
+         `#[cfg_attr(testnet)]`, or the negation of a flag, as in `#[cfg_attr(not(testnet))]`. \
+         The item is only part of the build if all conditions hold.";
+    CONFLICTING_ADDRESS_OVERRIDES = "E00014",
+        "dependency address overrides disagree for a file",
+        "Two overrides given with `--dependency-address-override` apply to the same dependency \
+         file, since their path prefixes both match it, but bind the same named address to \
+         different addresses. Make the prefixes disjoint, or remove one of the overrides.";
+
+    // Bytecode generation
+    BYTECODE_GEN_INTERNAL = "E01000",
+        "internal error in bytecode generation",
+        "The bytecode generator encountered an unexpected state. This is a bug in the compiler \
+         and should be reported.";
+    TUPLE_TO_SINGLE_VARIABLE = "E01001",
+        "tuple assigned to a single variable",
+        "A value of tuple type, for example the result of a function returning multiple values, \
+         is assigned to a single variable. Tuples must be destructured, as in \
+         `let (a, b) = f();`.";
+    EXPECTED_MUTABLE_REFERENCE = "E01002",
+        "expected a mutable reference",
+        "A value is written through a reference which is not mutable. Only references of type \
+         `&mut T` can be used to modify the referenced value.";
+    MISSING_ENCLOSING_LOOP = "E01003",
+        "`break` or `continue` outside of a loop",
+        "A `break` or `continue` statement is not enclosed in a `loop` or `while` statement.";
+    INVALID_MUTABLE_BORROW_OPERAND = "E01004",
+        "invalid operand of a mutable borrow",
+        "The operand of `&mut` must be a local, as in `&mut x`, or a field selection whose \
+         operand is a mutable reference or a local, as in `&mut s.f`. Other expressions, like \
+         function calls, do not denote a location which can be borrowed mutably.";
+    INVALID_TUPLE_ASSIGNMENT = "E01005",
+        "invalid tuple assignment",
+        "A tuple pattern is used where tuples are not allowed, or the right hand side of a tuple \
+         assignment is neither a tuple nor a function call. Tuples can only be assigned from \
+         tuple expressions or calls of functions returning multiple values, and tuple patterns \
+         cannot be nested.";
+    UNKNOWN_CALLEES = "E01006",
+        "functions called are not known",
+        "Code cannot be generated for a function because the functions it calls are not known, \
+         for example because the function was not processed by the checker. This happens for \
+         functions which are only declared in dependencies.";
+    UNUSED_ITEM = "W01007",
+        "unused function, struct, or constant",
+        "A private function is never called from its module, a struct never occurs in a type, \
+         or a constant is never used. Remove the item, or allow the warning with \
+         `#[lint::allow(unused_item)]` on the item or its module.";
+    UNUSED_DEPENDENCY = "W01008",
+        "dependency contributes nothing to the build",
+        "None of the files of a dependency, given as a file or a directory, declares a module \
+         which the compiled modules use, directly or via other dependencies. A module is used \
+         if one of its functions is called, one of its structs occurs in a type, or it is \
+         declared a friend. Remove the dependency, or suppress the warning with \
+         `--Wno-W01008`.";
+    RECURSIVE_CALL_CYCLE = "W01009",
+        "recursive call cycle",
+        "A function calls itself, directly or via other functions. Move allows recursion, so \
+         this warning is only reported with `--warn-recursion`, for code bases which avoid \
+         recursion, for example because its depth, and the gas it consumes, cannot be \
+         bounded statically. The diagnostic shows the functions of the cycle and the \
+         location of each call in it.";
+
+    // Bytecode pipeline
+    PRIVATE_FUNCTION_CALL = "E02001",
+        "call of a private function",
+        "A function which is private to another module is called. Only public functions, \
+         and friend functions from friend modules, can be called from other modules. Make the \
+         function `public`, or `public(friend)` and declare the calling module a friend. \
+         Functions declared `entry` but not `public` are private, too.";
+    FRIEND_FUNCTION_CALL = "E02002",
+        "call of a friend function from a module which is not a friend",
+        "A `public(friend)` function is called from a module which is not declared as a friend \
+         of the module defining the function. Add a `friend` declaration for the calling module \
+         to the module defining the function.";
+    SCRIPT_NON_PUBLIC_CALL = "E02003",
+        "call of a non-public function from a script",
+        "Scripts can only call public functions. Friend and private functions are not visible \
+         to scripts, even if they are `entry` functions.";
+    INVALID_FRIEND_DECLARATION = "E02004",
+        "invalid friend declaration",
+        "A `friend` declaration names the declaring module itself, or a module at another \
+         address. Friends must be other modules at the same address as the declaring module.";
+    FRIEND_CYCLE = "E02005",
+        "cyclic friend declarations",
+        "Modules declare each other as friends in a cycle, for example `A` is a friend of `B` \
+         and `B` a friend of `A`. Since a friend depends on the modules declaring it a friend, \
+         cyclic friend declarations imply cyclic dependencies, which are not allowed.";
+    FIELD_MISSING_ABILITY = "E02010",
+        "field type missing an ability required by the struct",
+        "A struct declares an ability, but the type of one of its fields does not have the \
+         ability it requires: `copy`, `drop` and `store` require the same ability of the \
+         fields, and `key` requires `store`.";
+    TYPE_ARGUMENT_MISSING_ABILITY = "E02011",
+        "type argument missing an ability required by a type parameter",
+        "A type is used to instantiate a type parameter which is constrained by an ability the \
+         type does not have, for example `T: copy`.";
+    VALUE_MISSING_ABILITY = "E02012",
+        "value missing an ability required by its use",
+        "A value is used in a way which requires an ability its type does not have: copying \
+         requires `copy`, dropping, for example by overwriting or leaving it unused, requires \
+         `drop`, and storing in global storage requires `key`.";
+    UNUSED_ACQUIRES = "E02020",
+        "resource in `acquires` list is never acquired",
+        "The `acquires` list of a function names a resource which is neither accessed by \
+         `move_from`, `borrow_global` or `borrow_global_mut` in the function, nor by a function \
+         it transitively calls. Remove the resource from the list.";
+    MISSING_ACQUIRES = "E02021",
+        "acquired resource missing in `acquires` list",
+        "A function accesses a resource of its module, directly or via a call, without \
+         declaring it in its `acquires` list. Add the resource to the list.";
+    BORROW_CONFLICT = "E02030",
+        "conflicting borrows",
+        "A value is borrowed in a way which conflicts with an existing borrow: a mutable borrow \
+         requires that the value is not borrowed otherwise, and an immutable borrow, or \
+         freezing a reference, requires that the value is not mutably borrowed.";
+    USE_OF_BORROWED_VALUE = "E02031",
+        "invalid use of a borrowed value",
+        "A value is used in a way which is not allowed while it is borrowed, for example it is \
+         moved, assigned, or accessed through a mutable reference while other references to it \
+         exist. End the use of the existing references before this use.";
+    INVALID_REFERENCE_RETURN = "E02032",
+        "invalid return of a reference",
+        "A function returns a reference to a value which does not outlive the function, like a \
+         local, or returns a mutable reference while the referenced value is borrowed \
+         otherwise.";
+    CONSTANT_EXPRESSION_ABORTS = "E02040",
+        "constant expression aborts",
+        "An operation over constants is known to abort, for example because of an arithmetic \
+         overflow, a shift exceeding the bit width, or a cast of a value out of range of the \
+         target type.";
+    UNUSED_VARIABLE = "W02050",
+        "unused variable or parameter",
+        "A local variable or parameter is never used. Remove it, or prefix its name with an \
+         underscore to indicate that it is unused on purpose.";
+    RECURSIVE_INLINE_FUNCTION = "W02060",
+        "function marked for inlining is recursive",
+        "A function with the `#[inline]` attribute calls itself, directly or via other \
+         functions. Its body cannot be inlined into callers, so the attribute has no effect.";
+    UNREACHABLE_CODE = "W02070",
+        "unreachable code",
+        "Code can never be executed, for example because it follows an `abort` or a `return`, \
+         or is guarded by a constant condition. The code is removed. Remove it from the source \
+         as well, or suppress the warning with `#[lint::allow(unreachable_code)]`.";
+    EXPENSIVE_COPY = "W02080",
+        "copy of a large value which is only borrowed",
+        "A large value, like a vector or a struct with many fields, is copied by dereferencing \
+         a reference or by assigning a variable which is used again, but the copy is only \
+         borrowed afterwards. Borrow the original value instead, or suppress the warning with \
+         `#[lint::allow(expensive_copy)]`. Structs with more fields than given by \
+         `--large-struct-fields` are considered large.";
+    UNUSED_MUST_USE = "W02090",
+        "result of a must-use function is never used",
+        "The function is annotated with `#[lint::must_use]`, since its result, like a \
+         capability or a receipt, should not be dropped silently, but the result of the call is \
+         never read. Use the result, discard it deliberately with `let _ = ..`, or suppress the \
+         warning with `#[lint::allow(unused_must_use)]`.";
+
+    // File format generation
+    FILE_FORMAT_GEN_INTERNAL = "E03000",
+        "internal error in file format generation",
+        "The file format generator encountered an unexpected state. This is a bug in the \
+         compiler and should be reported.";
+    BYTECODE_VERSION_TOO_LOW = "E03001",
+        "feature requires a newer bytecode version",
+        "The code uses a feature which is only supported by a newer bytecode version than the \
+         targeted one. Target a newer version with `--bytecode-version`, or avoid the feature.";
+    TABLE_SIZE_EXCEEDED = "E03002",
+        "limit of the file format exceeded",
+        "A module or function exceeds a limit of the file format, like the maximal number of \
+         functions and structs of a module, or of locals and instructions of a function. Split \
+         the module or function.";
+    CONSTANT_TOO_LARGE = "E03003",
+        "constant exceeds the maximal size",
+        "The serialized value of a constant, like a vector literal, exceeds the maximal size of \
+         a constant in the file format, or the smaller limit set with `--max-constant-size`. \
+         Split the value into several constants, or store it outside of the module.";
+    STRIPPED_DEAD_FUNCTIONS = "N03004",
+        "unreachable private functions stripped",
+        "With `--strip-dead-private-functions`, private functions which cannot be reached from \
+         the public, friend, entry, and test functions of their module, nor are used in specs, \
+         are not included in the generated module. The note lists the stripped functions. Call \
+         a function, or make it public, to keep it.";
+    STACK_DEPTH_EXCEEDED = "W03005",
+        "operand stack depth exceeds the threshold",
+        "With `--max-stack-depth`, functions whose generated code needs a deeper operand stack \
+         than the given threshold are reported. The depth grows with the nesting of \
+         expressions and the number of arguments of calls. Assign intermediate results to \
+         local variables to reduce it.";
+    CALL_DEPTH_EXCEEDED = "W03006",
+        "call depth exceeds the threshold",
+        "With `--max-call-depth`, functions which lead to deeper chains of calls among the \
+         compiled modules than the given threshold are reported, counting the frame of the \
+         function itself. Functions which can reach recursive calls have an unbounded call \
+         depth. The VM limits the depth of calls, so such functions may abort at runtime.";
+
+    // Bytecode verification
+    BYTECODE_VERIFICATION_FAILED = "E04000",
+        "generated bytecode fails verification",
+        "The bytecode generated by the compiler was rejected by the bytecode verifier. This is \
+         a bug in the compiler and should be reported. The experiment `skip-bytecode-verifier` \
+         disables verification.";
+
+    // Unit tests
+    INVALID_TEST_ATTRIBUTE = "E05001",
+        "invalid `#[test]` attribute",
+        "A `#[test]` attribute is malformed, or used together with attributes it does not \
+         combine with. The attribute has the form `#[test]` or `#[test(<param> = <address>, ..)]`, \
+         and must assign an address to each parameter of the test function. A test function \
+         cannot also be `#[test_only]`, and only test functions can be `#[expected_failure]`.";
+    INVALID_TEST_SIGNATURE = "E05002",
+        "invalid signature of a test function",
+        "The unit test framework calls test functions with signers for the addresses given in \
+         the `#[test]` attribute. Test functions therefore cannot have type parameters, and \
+         their parameters must be of type `signer` or `&signer`.";
+    INVALID_EXPECTED_FAILURE = "E05003",
+        "invalid `#[expected_failure]` attribute",
+        "An `#[expected_failure]` attribute is malformed. The attribute is either \
+         `#[expected_failure]`, or names exactly one kind of failure: `abort_code = <code>`, \
+         `arithmetic_error`, `vector_error`, `out_of_gas`, or `major_status = <code>`, together \
+         with the module in which the failure is expected, as in `location = std::vector`. \
+         Codes are u64 values or constants, like `std::vector::EINDEX_OUT_OF_BOUNDS`.";
+    EXPECTED_FAILURE_IN_ANY_MODULE = "W05004",
+        "expected abort code without a location",
+        "An `#[expected_failure]` attribute expects an abort code given as a value, without \
+         the module in which the abort is expected, so the test also passes if another module \
+         aborts with the same code. Use a constant of the expected module as the code, or add \
+         `location = <module>`.";
+
+    // Scripts
+    SCRIPT_RETURNS_VALUE = "E06001",
+        "script function returns a value",
+        "The function of a script is the entry point of a transaction, so there is no caller \
+         to receive values it returns. Script functions must not declare a return type.";
+    MISPLACED_SIGNER_PARAMETER = "E06002",
+        "signer parameter of a script after other parameters",
+        "Parameters of type `signer` or `&signer` of a script function are provided by the VM \
+         for the senders of the transaction, and the transaction arguments for the remaining \
+         parameters. Signer parameters must therefore come before all other parameters.";
+    INVALID_SCRIPT_PARAMETER = "E06003",
+        "type of a script parameter cannot be passed as transaction argument",
+        "Parameters of a script function, except leading signers, are provided as transaction \
+         arguments, so their values must be constructible from bytes. Allowed types are \
+         `bool`, the integer types, `address`, vectors of allowed types, `0x1::string::String`, \
+         `0x1::object::Object<T>`, `0x1::option::Option<T>` of an allowed type, and \
+         `0x1::fixed_point32::FixedPoint32` and `0x1::fixed_point64::FixedPoint64`. In \
+         particular, references other than `&signer`, type parameters, and other structs are \
+         not allowed.";
+
+    // Lints of the model
+    SHADOWED_LOCAL = "W07001",
+        "local shadows another local or a parameter",
+        "A `let` or a lambda declares a local with the same name as a local or parameter which \
+         is still in scope, so later uses of the name refer to the new local. This is legal, \
+         but easily leads to mutating or reading the wrong value. Rename one of them, prefix \
+         the new name with `_`, or suppress the warning with `#[lint::allow(shadowing)]`. This \
+         lint is only checked if enabled, as in `--W shadowing`.";
+}
@@ -0,0 +1,509 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reporting of the diagnostics accumulated in the global env, in one of the formats
+//! selected by `Options::diagnostics_format`, and additionally as a SARIF log if
+//! `Options::sarif_output` is set. Diagnostics can also be collected as `CompilerDiagnostic`s
+//! instead of being reported. Only the text format limits the number of diagnostics shown, see
+//! `Options::max_diagnostics`; the other formats are meant for tools, and include everything.
+//! Diagnostics are sorted by their location before they are reported, see `sort_diags`.
+
+use crate::{
+    options::{DiagnosticsFormat, Options},
+    source_positions::SourcePositionMapper,
+};
+use codespan::{ByteIndex, FileId, Span};
+use codespan_reporting::{
+    diagnostic::{Diagnostic, Label, LabelStyle, Severity},
+    term::termcolor::WriteColor,
+};
+use move_model::model::GlobalEnv;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::BTreeSet, fs, io::Write, path::Path};
+
+/// Reports diagnostics in the env which are at least of the given severity, in the format
+/// requested by the options. In the text format, at most `limit` diagnostics are shown, see
+/// `report_diags_as_text`. Returns the number of diagnostics shown.
+pub fn report_diags<W: WriteColor>(
+    env: &GlobalEnv,
+    writer: &mut W,
+    options: &Options,
+    severity: Severity,
+    limit: Option<usize>,
+) -> usize {
+    match options.diagnostics_format {
+        DiagnosticsFormat::Text => report_diags_as_text(env, writer, severity, limit),
+        DiagnosticsFormat::Json => report_diags_as_json(env, writer, severity),
+    }
+}
+
+/// Reports diagnostics in the env as text, as rendered by `codespan_reporting`. If there are
+/// more than `limit` diagnostics, those of the highest severity, and among them the first in
+/// source order, are shown, followed by a note stating how many diagnostics of each severity
+/// are not shown. All diagnostics are marked as reported. Returns the number of diagnostics
+/// shown.
+pub fn report_diags_as_text<W: WriteColor>(
+    env: &GlobalEnv,
+    writer: &mut W,
+    severity: Severity,
+    limit: Option<usize>,
+) -> usize {
+    // Identical diagnostics are shown once, as by `GlobalEnv::report_diag`.
+    let mut seen = BTreeSet::new();
+    let mut diags = vec![];
+    env.report_diag_with_emitter(
+        |d| d.severity >= severity,
+        |d| {
+            if seen.insert(format!("{:?}", d)) {
+                diags.push(d.clone())
+            }
+        },
+    );
+    let limit = limit.unwrap_or(usize::MAX);
+    let mut hidden = vec![];
+    if diags.len() > limit {
+        let mut ranked = (0..diags.len()).collect::<Vec<_>>();
+        ranked.sort_by_key(|idx| {
+            let diag = &diags[*idx];
+            let position = diag
+                .labels
+                .iter()
+                .find(|l| l.style == LabelStyle::Primary)
+                .map(|l| (env.get_file(l.file_id).to_owned(), l.range.start));
+            (
+                std::cmp::Reverse(DiagnosticSeverity::from(diag.severity)),
+                position,
+            )
+        });
+        let shown = ranked.into_iter().take(limit).collect::<BTreeSet<_>>();
+        let mut idx = 0;
+        diags.retain(|diag| {
+            let keep = shown.contains(&idx);
+            if !keep {
+                hidden.push(diag.severity)
+            }
+            idx += 1;
+            keep
+        })
+    }
+    for diag in &diags {
+        env.emit_diag(writer, diag)
+    }
+    if !hidden.is_empty() {
+        env.emit_diag(
+            writer,
+            &Diagnostic::note().with_message(format!(
+                "{} not shown; use `--max-diagnostics` to show more",
+                hidden_counts(&hidden)
+            )),
+        )
+    }
+    diags.len()
+}
+
+/// Returns a description of the number of diagnostics of each severity, like `2 more errors
+/// and 1 more warning`.
+fn hidden_counts(severities: &[Severity]) -> String {
+    let counts = [
+        (Severity::Bug, "bug"),
+        (Severity::Error, "error"),
+        (Severity::Warning, "warning"),
+        (Severity::Note, "note"),
+        (Severity::Help, "help message"),
+    ]
+    .into_iter()
+    .filter_map(|(severity, name)| {
+        let count = severities.iter().filter(|s| **s == severity).count();
+        match count {
+            0 => None,
+            1 => Some(format!("1 more {}", name)),
+            _ => Some(format!("{} more {}s", count, name)),
+        }
+    })
+    .collect::<Vec<_>>();
+    match counts.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => counts.join(""),
+    }
+}
+
+/// Reports diagnostics in the env as JSON, one object per line. Returns the number of
+/// diagnostics reported.
+pub fn report_diags_as_json<W: Write>(
+    env: &GlobalEnv,
+    writer: &mut W,
+    severity: Severity,
+) -> usize {
+    let diags = collect_diags(env, severity);
+    for diag in &diags {
+        let json = serde_json::to_string(diag).expect("serialization must not fail");
+        writeln!(writer, "{}", json).expect("write must not fail")
+    }
+    diags.len()
+}
+
+/// Returns the diagnostics in the env which are at least of the given severity and have not
+/// been reported yet, and marks them as reported. Identical diagnostics are returned once, as
+/// they are shown once in the text format.
+pub fn collect_diags(env: &GlobalEnv, severity: Severity) -> Vec<CompilerDiagnostic> {
+    let positions = SourcePositionMapper::new(env);
+    let mut seen = BTreeSet::new();
+    let mut diags = vec![];
+    env.report_diag_with_emitter(
+        |d| d.severity >= severity,
+        |d| {
+            if seen.insert(format!("{:?}", d)) {
+                diags.push(CompilerDiagnostic::new(env, &positions, d))
+            }
+        },
+    );
+    diags
+}
+
+/// Sorts the diagnostics in the env by the path of the file and the start of their primary
+/// label, then with the higher severity first, then by code and message. Diagnostics without a
+/// primary label come first. This makes the order in which diagnostics are reported, in any
+/// format, independent of the order in which phases and processors find them.
+pub fn sort_diags(env: &GlobalEnv) {
+    env.sort_diags_by_key(|diag| {
+        let position = diag
+            .labels
+            .iter()
+            .find(|l| l.style == LabelStyle::Primary)
+            .map(|l| (env.get_file(l.file_id).to_owned(), l.range.start));
+        (
+            position,
+            std::cmp::Reverse(DiagnosticSeverity::from(diag.severity)),
+            diag.code.clone(),
+            diag.message.clone(),
+        )
+    })
+}
+
+/// A diagnostic of a compilation, extracted from the env for tools which process diagnostics
+/// instead of showing them. This is also the JSON representation of a diagnostic.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompilerDiagnostic {
+    pub severity: DiagnosticSeverity,
+    pub code: Option<String>,
+    pub message: String,
+    /// The location of the first primary label, if any.
+    pub location: Option<DiagnosticLocation>,
+    pub labels: Vec<DiagnosticLabel>,
+    pub notes: Vec<String>,
+}
+
+/// The severity of a diagnostic, serialized as its lower case name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Help,
+    Note,
+    Warning,
+    Error,
+    Bug,
+}
+
+/// A label attached to a diagnostic. Primary labels mark the locations the diagnostic is
+/// about, secondary labels related locations.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticLabel {
+    pub primary: bool,
+    pub message: String,
+    pub location: DiagnosticLocation,
+}
+
+/// A source location. Lines and columns are 1-based, byte offsets are 0-based. Columns are
+/// counted in bytes, and additionally in UTF-16 code units, as expected by some editors.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticLocation {
+    pub file: String,
+    pub start: u32,
+    pub end: u32,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub start_utf16_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub end_utf16_column: usize,
+}
+
+impl CompilerDiagnostic {
+    pub(crate) fn new(
+        env: &GlobalEnv,
+        positions: &SourcePositionMapper,
+        diag: &Diagnostic<FileId>,
+    ) -> Self {
+        let labels = diag
+            .labels
+            .iter()
+            .map(|l| DiagnosticLabel {
+                primary: l.style == LabelStyle::Primary,
+                message: l.message.clone(),
+                location: DiagnosticLocation::new(
+                    env,
+                    positions,
+                    l.file_id,
+                    Span::new(l.range.start as u32, l.range.end as u32),
+                ),
+            })
+            .collect::<Vec<_>>();
+        let location = labels
+            .iter()
+            .find(|l| l.primary)
+            .map(|l| l.location.clone());
+        Self {
+            severity: DiagnosticSeverity::from(diag.severity),
+            code: diag.code.clone(),
+            message: diag.message.clone(),
+            location,
+            labels,
+            notes: diag.notes.clone(),
+        }
+    }
+
+    /// Creates an error without a location, for failures which are not reported as
+    /// diagnostics, like i/o errors.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: DiagnosticSeverity::Error,
+            code: None,
+            message: message.into(),
+            location: None,
+            labels: vec![],
+            notes: vec![],
+        }
+    }
+
+    /// Returns the secondary labels, which point to locations related to the diagnostic.
+    pub fn secondary_labels(&self) -> impl Iterator<Item = &DiagnosticLabel> {
+        self.labels.iter().filter(|l| !l.primary)
+    }
+}
+
+impl From<Severity> for DiagnosticSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Bug => DiagnosticSeverity::Bug,
+            Severity::Error => DiagnosticSeverity::Error,
+            Severity::Warning => DiagnosticSeverity::Warning,
+            Severity::Note => DiagnosticSeverity::Note,
+            Severity::Help => DiagnosticSeverity::Help,
+        }
+    }
+}
+
+impl DiagnosticLocation {
+    fn new(env: &GlobalEnv, positions: &SourcePositionMapper, file_id: FileId, span: Span) -> Self {
+        let line_col = |offset: ByteIndex| {
+            positions
+                .byte_to_line_col(file_id, offset.0)
+                .map(|pos| (pos.line, pos.utf8_column, pos.utf16_column))
+                .unwrap_or_default()
+        };
+        let (start_line, start_column, start_utf16_column) = line_col(span.start());
+        let (end_line, end_column, end_utf16_column) = line_col(span.end());
+        Self {
+            file: env.get_file(file_id).to_string_lossy().to_string(),
+            start: span.start().0,
+            end: span.end().0,
+            start_line,
+            start_column,
+            start_utf16_column,
+            end_line,
+            end_column,
+            end_utf16_column,
+        }
+    }
+}
+
+/// The version of the SARIF format which is produced.
+pub const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The results of the SARIF log of a compilation. Diagnostics are reported in several
+/// batches, so the results are accumulated as an extension of the env, and the log is
+/// rewritten with each batch.
+#[derive(Default)]
+struct SarifResults(RefCell<Vec<SarifResult>>);
+
+/// Writes the SARIF log of the diagnostics in the env which are at least of the given severity
+/// to the given path. Diagnostics which have not been reported yet are added to the results
+/// of previous calls, and are not marked as reported, so they can still be reported by
+/// `report_diags`.
+pub fn write_diags_as_sarif(
+    env: &GlobalEnv,
+    path: &Path,
+    severity: Severity,
+) -> anyhow::Result<()> {
+    if !env.has_extension::<SarifResults>() {
+        env.set_extension(SarifResults::default())
+    }
+    let results = env.get_extension::<SarifResults>().expect("results");
+    let positions = SourcePositionMapper::new(env);
+    let mut shown = BTreeSet::new();
+    results.0.borrow_mut().extend(
+        env.get_unreported_diags(|d| d.severity >= severity)
+            .into_iter()
+            .filter(|d| shown.insert(format!("{:?}", d)))
+            .map(|d| SarifResult::new(env, &positions, &d)),
+    );
+    let log = SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![SarifRun {
+            tool: SarifTool {
+                driver: SarifDriver {
+                    name: "move-compiler-v2",
+                },
+            },
+            column_kind: "utf16CodeUnits",
+            results: results.0.borrow().clone(),
+        }],
+    };
+    fs::write(path, serde_json::to_string_pretty(&log)? + "\n")?;
+    Ok(())
+}
+
+/// The SARIF representation of the diagnostics of a compilation. Only the parts of the format
+/// which are needed to represent diagnostics are defined.
+#[derive(Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<SarifRun>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRun {
+    tool: SarifTool,
+    column_kind: &'static str,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct SarifTool {
+    driver: SarifDriver,
+}
+
+#[derive(Serialize)]
+struct SarifDriver {
+    name: &'static str,
+}
+
+/// A diagnostic. The location of the first primary label is the location of the result, and
+/// the other labels are related locations. Notes are appended to the message.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    level: &'static str,
+    message: SarifMessage,
+    locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<SarifLocation>,
+}
+
+#[derive(Clone, Serialize)]
+struct SarifMessage {
+    text: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<usize>,
+    physical_location: SarifPhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<SarifMessage>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+    region: SarifRegion,
+}
+
+#[derive(Clone, Serialize)]
+struct SarifArtifactLocation {
+    uri: String,
+}
+
+/// A region of a file, with 1-based lines and columns in UTF-16 code units, where the end column
+/// is exclusive.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl SarifResult {
+    fn new(env: &GlobalEnv, positions: &SourcePositionMapper, diag: &Diagnostic<FileId>) -> Self {
+        let primary = diag
+            .labels
+            .iter()
+            .position(|l| l.style == LabelStyle::Primary);
+        let location = |l: &Label<FileId>, id: Option<usize>| {
+            let json = DiagnosticLocation::new(
+                env,
+                positions,
+                l.file_id,
+                Span::new(l.range.start as u32, l.range.end as u32),
+            );
+            SarifLocation {
+                id,
+                physical_location: SarifPhysicalLocation {
+                    artifact_location: SarifArtifactLocation {
+                        uri: json.file.replace('\\', "/"),
+                    },
+                    region: SarifRegion {
+                        start_line: json.start_line,
+                        start_column: json.start_utf16_column,
+                        end_line: json.end_line,
+                        end_column: json.end_utf16_column,
+                    },
+                },
+                message: (!l.message.is_empty()).then(|| SarifMessage {
+                    text: l.message.clone(),
+                }),
+            }
+        };
+        let locations = primary
+            .map(|i| vec![location(&diag.labels[i], None)])
+            .unwrap_or_default();
+        let related_locations = diag
+            .labels
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != primary)
+            .enumerate()
+            .map(|(id, (_, l))| location(l, Some(id)))
+            .collect();
+        let mut text = diag.message.clone();
+        for note in &diag.notes {
+            text.push('\n');
+            text.push_str(note)
+        }
+        Self {
+            rule_id: diag
+                .code
+                .clone()
+                .unwrap_or_else(|| "unclassified".to_owned()),
+            level: match diag.severity {
+                Severity::Bug | Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Note | Severity::Help => "note",
+            },
+            message: SarifMessage { text },
+            locations,
+            related_locations,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Disassembly of the compiled units, written into `<module>.mvasm` in the dump directory if
+//! `Options::dump_disassembly` is set. Units are rendered by the Move disassembler, which
+//! names parameters and locals after the source map of the unit. Before each instruction
+//! whose location starts on another source line than the previous instruction of the
+//! function, that line is interleaved as a comment, like `// 3: x + 1`.
+
+use crate::source_positions::SourcePositionMapper;
+use move_binary_format::file_format::{CodeOffset, FunctionDefinitionIndex};
+use move_compiler::compiled_unit::CompiledUnit;
+use move_disassembler::disassembler::Disassembler;
+use move_model::model::GlobalEnv;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Returns the name and the disassembly of each unit. Modules are named by their full name,
+/// like `0x42::m`, and scripts by their name.
+pub fn disassemble_units(env: &GlobalEnv, units: &[CompiledUnit]) -> Vec<(String, String)> {
+    units
+        .iter()
+        .map(|unit| {
+            let (name, fun_defs_with_code) = match unit {
+                CompiledUnit::Module(named_module) => (
+                    format!(
+                        "{}::{}",
+                        named_module.address.into_inner().to_hex_literal(),
+                        named_module.name
+                    ),
+                    named_module
+                        .module
+                        .function_defs
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, def)| def.code.is_some())
+                        .map(|(idx, _)| FunctionDefinitionIndex(idx as u16))
+                        .collect::<Vec<_>>(),
+                ),
+                CompiledUnit::Script(named_script) => (named_script.name.to_string(), vec![
+                    FunctionDefinitionIndex(0),
+                ]),
+            };
+            let text = match Disassembler::from_unit(unit).disassemble() {
+                Ok(text) => with_source_lines(env, unit, &fun_defs_with_code, &text),
+                Err(err) => format!("// disassembly failed: {}\n", err),
+            };
+            (name, text)
+        })
+        .collect()
+}
+
+/// Writes the disassembly of each unit into the directory, named `<module>.mvasm`. Returns
+/// the paths of the written files.
+pub fn write_disassembly(
+    env: &GlobalEnv,
+    units: &[CompiledUnit],
+    dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut written = vec![];
+    for (name, text) in disassemble_units(env, units) {
+        let path = dir.join(format!("{}.mvasm", crate::portable_file_name(&name)));
+        fs::write(&path, text)?;
+        written.push(path)
+    }
+    Ok(written)
+}
+
+/// Interleaves the source lines of the instructions in the disassembly. The disassembler
+/// prints each instruction as `\t<offset>: <instruction>`, and the functions with code in
+/// the order of their definitions, so a function ends where the offsets start over at 0.
+fn with_source_lines(
+    env: &GlobalEnv,
+    unit: &CompiledUnit,
+    fun_defs_with_code: &[FunctionDefinitionIndex],
+    text: &str,
+) -> String {
+    let source_map = unit.source_map();
+    let positions = SourcePositionMapper::new(env);
+    let mut fun_defs = fun_defs_with_code.iter();
+    let mut current_def = None;
+    let mut last_line = None;
+    let mut out = String::new();
+    for line in text.lines() {
+        if let Some(offset) = instruction_offset(line) {
+            if offset == 0 {
+                current_def = fun_defs.next();
+                last_line = None
+            }
+            let source_line = current_def
+                .and_then(|def_idx| source_map.get_code_location(*def_idx, offset).ok())
+                .and_then(|ir_loc| {
+                    let loc = env.to_loc(&ir_loc);
+                    let line = positions
+                        .byte_to_line_col(loc.file_id(), loc.span().start().0)?
+                        .line;
+                    let text = env.get_file_source(loc.file_id()).lines().nth(line - 1)?;
+                    Some((line, text.trim()))
+                });
+            if let Some((line, text)) = source_line {
+                if last_line != Some(line) {
+                    out.push_str(&format!("\t// {}: {}\n", line, text));
+                    last_line = Some(line)
+                }
+            }
+        }
+        out.push_str(line);
+        out.push('\n')
+    }
+    out
+}
+
+/// Returns the code offset of an instruction line of the disassembly.
+fn instruction_offset(line: &str) -> Option<CodeOffset> {
+    let (offset, _) = line.strip_prefix('\t')?.split_once(": ")?;
+    offset.parse().ok()
+}
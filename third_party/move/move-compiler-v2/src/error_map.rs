@@ -0,0 +1,276 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation of a catalog of the abort sites of the target modules, written as JSON if
+//! `Options::emit_error_map` is set. Like the error map of compiler v1, it maps abort codes to
+//! the constants they are declared by, but it lists each `abort` and `assert!` site with its
+//! function and location.
+//!
+//! The abort codes are read from the stackless bytecode after the pipeline ran, from which
+//! code is generated. A code is known if the aborted temporary holds a constant, or the result
+//! of a function of `std::error` building a canonical code from constants, as in
+//! `abort error::invalid_argument(E_FOO)`. Since constants are inlined by the checker, the
+//! name of the constant is recovered from the constants of the module with the same value,
+//! preferring those starting with `E` if several have it. Other codes are listed as dynamic.
+
+use crate::file_format_generator::functions_in_source_order;
+use move_core_types::account_address::AccountAddress;
+use move_model::{
+    ast::Value,
+    model::{FunctionEnv, GlobalEnv, ModuleEnv},
+    ty::{PrimitiveType, Type},
+};
+use move_stackless_bytecode::{
+    function_target::FunctionData,
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant},
+    stackless_bytecode::{Bytecode, Constant, Operation},
+};
+use num::BigInt;
+use serde::Serialize;
+use std::{fs, path::Path};
+
+/// The functions of `std::error` building a canonical code from a reason, with the category
+/// of the code. `error::canonical` takes the category as argument instead.
+const CATEGORIES: &[(&str, u64)] = &[
+    ("invalid_argument", 0x1),
+    ("out_of_range", 0x2),
+    ("invalid_state", 0x3),
+    ("unauthenticated", 0x4),
+    ("permission_denied", 0x5),
+    ("not_found", 0x6),
+    ("aborted", 0x7),
+    ("already_exists", 0x8),
+    ("resource_exhausted", 0x9),
+    ("cancelled", 0xA),
+    ("internal", 0xB),
+    ("not_implemented", 0xC),
+    ("unavailable", 0xD),
+];
+
+/// The abort sites of the target modules.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct ErrorMap {
+    pub modules: Vec<ModuleErrorMap>,
+}
+
+/// The abort sites of a module, in the order of their functions and code.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct ModuleErrorMap {
+    pub address: String,
+    pub name: String,
+    pub aborts: Vec<AbortSite>,
+}
+
+/// An `abort` or `assert!` in a function. Lines and columns are 1-based.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct AbortSite {
+    pub function: String,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub code: AbortCode,
+}
+
+/// The code of an abort site.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum AbortCode {
+    /// A code known at compile time. For canonical codes, the category is the name of the
+    /// function of `std::error` building the code, and the reason its argument. Otherwise,
+    /// the reason is the code. The constant is the one declaring the reason, with its
+    /// documentation as description.
+    Constant {
+        value: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        category: Option<String>,
+        reason: u64,
+        constant: Option<String>,
+        description: Option<String>,
+    },
+    /// A code computed at runtime.
+    Dynamic,
+}
+
+/// Returns the abort sites of the target modules which have stackless bytecode, ordered by
+/// module name.
+pub fn error_map(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> ErrorMap {
+    let mut modules = env
+        .get_modules()
+        .filter(|module_env| module_env.is_target() && !module_env.is_script_module())
+        .map(|module_env| module_error_map(&module_env, targets))
+        .collect::<Vec<_>>();
+    modules.sort_by(|m1, m2| (&m1.address, &m1.name).cmp(&(&m2.address, &m2.name)));
+    ErrorMap { modules }
+}
+
+/// Writes the error map of the target modules as JSON to the file at the path.
+pub fn write_error_map(
+    env: &GlobalEnv,
+    targets: &FunctionTargetsHolder,
+    path: &Path,
+) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?
+    }
+    let map = error_map(env, targets);
+    fs::write(path, serde_json::to_string_pretty(&map)? + "\n")?;
+    Ok(())
+}
+
+fn module_error_map(module_env: &ModuleEnv, targets: &FunctionTargetsHolder) -> ModuleErrorMap {
+    let env = module_env.env;
+    let name = module_env.get_name();
+    let mut aborts = vec![];
+    for fun_env in functions_in_source_order(module_env) {
+        let id = fun_env.get_qualified_id();
+        let Some(data) = targets
+            .get_data(&id, &FunctionVariant::Optimized)
+            .or_else(|| targets.get_data(&id, &FunctionVariant::Baseline))
+        else {
+            continue;
+        };
+        for bc in &data.code {
+            let Bytecode::Abort(attr, temp) = bc else {
+                continue;
+            };
+            let loc = data
+                .locations
+                .get(attr)
+                .cloned()
+                .unwrap_or_else(|| fun_env.get_loc());
+            let (line, column) = env
+                .get_location(&loc)
+                .map(|pos| (pos.line.0 as usize + 1, pos.column.0 as usize + 1))
+                .unwrap_or_default();
+            aborts.push(AbortSite {
+                function: fun_env.get_name_str(),
+                file: env.get_file(loc.file_id()).to_string_lossy().to_string(),
+                line,
+                column,
+                code: abort_code(&fun_env, data, *temp),
+            })
+        }
+    }
+    ModuleErrorMap {
+        address: name.addr().expect_numerical().to_hex_literal(),
+        name: env.symbol_pool().string(name.name()).to_string(),
+        aborts,
+    }
+}
+
+/// Determines the code held by the temporary at the abort site.
+fn abort_code(fun_env: &FunctionEnv, data: &FunctionData, temp: usize) -> AbortCode {
+    let constant = |reason: u64, category: Option<(&str, u64)>| {
+        let (name, description) = match constant_named(&fun_env.module_env, reason) {
+            Some((name, description)) => (Some(name), Some(description).filter(|d| !d.is_empty())),
+            None => (None, None),
+        };
+        AbortCode::Constant {
+            value: category.map_or(reason, |(_, cat)| (cat << 16) + reason),
+            category: category.map(|(name, _)| name.to_owned()),
+            reason,
+            constant: name,
+            description,
+        }
+    };
+    match definition(data, temp) {
+        Some(Bytecode::Load(_, _, Constant::U64(value))) => constant(*value, None),
+        Some(Bytecode::Call(_, _, Operation::Function(mid, fid, _), args, _)) => {
+            let env = fun_env.module_env.env;
+            let callee = env.get_function(mid.qualified(*fid));
+            if !is_error_module(&callee.module_env) {
+                return AbortCode::Dynamic;
+            }
+            let args = args
+                .iter()
+                .map(|arg| match definition(data, *arg) {
+                    Some(Bytecode::Load(_, _, Constant::U64(value))) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let name = callee.get_name_str();
+            match args.as_slice() {
+                [Some(reason)] => match CATEGORIES.iter().find(|(cat, _)| *cat == name) {
+                    Some(category) => constant(*reason, Some(*category)),
+                    None => AbortCode::Dynamic,
+                },
+                [Some(category), Some(reason)] if name == "canonical" => {
+                    match CATEGORIES.iter().find(|(_, cat)| cat == category) {
+                        Some(category) => constant(*reason, Some(*category)),
+                        None => AbortCode::Dynamic,
+                    }
+                },
+                _ => AbortCode::Dynamic,
+            }
+        },
+        _ => AbortCode::Dynamic,
+    }
+}
+
+/// Returns the instruction defining the temporary, following assignments, if it is defined by
+/// a single instruction and never borrowed, so it holds the value of that instruction.
+fn definition(data: &FunctionData, mut temp: usize) -> Option<&Bytecode> {
+    // Each step follows an assignment, so there are no more steps than instructions.
+    for _ in 0..=data.code.len() {
+        let mut defs = data.code.iter().filter(|bc| match bc {
+            Bytecode::Assign(_, dest, _, _) | Bytecode::Load(_, dest, _) => *dest == temp,
+            Bytecode::Call(_, dests, _, _, _) => dests.contains(&temp),
+            _ => false,
+        });
+        let (Some(def), None) = (defs.next(), defs.next()) else {
+            return None;
+        };
+        let borrowed = data.code.iter().any(|bc| {
+            matches!(bc, Bytecode::Call(_, _, Operation::BorrowLoc, srcs, _) if srcs.contains(&temp))
+        });
+        if borrowed {
+            return None;
+        }
+        match def {
+            Bytecode::Assign(_, _, src, _) => temp = *src,
+            _ => return Some(def),
+        }
+    }
+    None
+}
+
+/// Returns true if the module is `std::error`.
+fn is_error_module(module_env: &ModuleEnv) -> bool {
+    let name = module_env.get_name();
+    name.addr().expect_numerical() == AccountAddress::ONE
+        && module_env.env.symbol_pool().string(name.name()).as_str() == "error"
+}
+
+/// Returns the name and documentation of the `u64` constant of the module with the value, if it
+/// is unique, or unique among the constants starting with `E`.
+fn constant_named(module_env: &ModuleEnv, value: u64) -> Option<(String, String)> {
+    let value = Value::Number(BigInt::from(value));
+    let candidates = module_env
+        .get_named_constants()
+        .filter(|constant| {
+            constant.get_type() == Type::Primitive(PrimitiveType::U64)
+                && constant.get_value() == value
+        })
+        .map(|constant| {
+            (
+                module_env
+                    .symbol_pool()
+                    .string(constant.get_name())
+                    .to_string(),
+                constant.get_doc().trim().to_owned(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let unique = |candidates: Vec<(String, String)>| match candidates.as_slice() {
+        [single] => Some(single.clone()),
+        _ => None,
+    };
+    unique(candidates.clone()).or_else(|| {
+        unique(
+            candidates
+                .into_iter()
+                .filter(|(name, _)| name.starts_with('E'))
+                .collect(),
+        )
+    })
+}
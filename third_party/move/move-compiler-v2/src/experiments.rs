@@ -3,7 +3,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 /// Container for experiments in the compiler. Those can be activated
-/// via the `--experiment=<name>` flag.
+/// via the `--experiments=<name>` flag, and deactivated via `--experiments=<name>=off`.
+/// Multiple experiments can be given as a comma separated list.
 ///
 /// One can activate an experiment in a test source by using a comment as such:
 /// ```
@@ -13,7 +14,8 @@
 /// the default configuration and for each of the named experiments separately (if it is a
 /// baseline test, a different baseline file will be generated each time).
 ///
-/// Each new experiment should have a description and explicit note about its retention.
+/// Each new experiment should have a description and explicit note about its retention, and
+/// must be registered in `Experiment::definitions`, otherwise it is rejected as unknown.
 ///
 /// - Permanent: the experiment is available indefinitely
 /// - Temporary: the experiment is intended to be removed after some time. Please document
@@ -25,4 +27,127 @@ impl Experiment {
     // /// Whether to exit after type checking.
     // /// Retention: permanent
     // pub const CHECK_ONLY: &'static str = "check-only";
+
+    /// Whether to simplify the control-flow graph of the stackless bytecode, threading jumps
+    /// and merging blocks.
+    /// Retention: temporary. Remove once the processor is enabled by default.
+    pub const CFG_SIMPLIFICATION: &'static str = "cfg-simplification";
+    /// Whether to fold constants in the stackless bytecode. Operations over constants which
+    /// are known to abort are reported as errors.
+    /// Retention: temporary. Remove once the processor is enabled by default.
+    pub const CONSTANT_FOLDING: &'static str = "constant-folding";
+    /// Whether to run copy propagation on the stackless bytecode.
+    /// Retention: temporary. Remove once the processor is enabled by default.
+    pub const COPY_PROPAGATION: &'static str = "copy-propagation";
+    /// Whether to run dead store elimination on the stackless bytecode.
+    /// Retention: temporary. Remove once the processor is enabled by default.
+    pub const DEAD_STORE_ELIMINATION: &'static str = "dead-store-elimination";
+    /// Whether to inline calls to small private functions, and to functions with the
+    /// `#[inline]` attribute, in the stackless bytecode.
+    /// Retention: temporary. Remove once the processor is enabled by default.
+    pub const FUNCTION_INLINING: &'static str = "function-inlining";
+    /// Whether to run the peephole optimizer over the generated file format code.
+    /// Retention: temporary. Remove once the optimizer is enabled by default.
+    pub const PEEPHOLE_OPTIMIZATION: &'static str = "peephole-optimization";
+    /// Whether to skip running the bytecode verifier on the generated code.
+    /// Retention: permanent
+    pub const SKIP_BYTECODE_VERIFIER: &'static str = "skip-bytecode-verifier";
+}
+
+/// The definition of an experiment in the registry.
+#[derive(Clone, Copy, Debug)]
+pub struct ExperimentDefinition {
+    /// The name by which the experiment is activated.
+    pub name: &'static str,
+    /// Whether the experiment is on if not set otherwise.
+    pub default: bool,
+    /// A short description, as shown by `--list-experiments`.
+    pub description: &'static str,
+}
+
+impl Experiment {
+    /// Returns the registry of known experiments.
+    pub fn definitions() -> &'static [ExperimentDefinition] {
+        &[
+            ExperimentDefinition {
+                name: Self::CFG_SIMPLIFICATION,
+                default: false,
+                description: "Thread jumps and merge blocks in the stackless bytecode",
+            },
+            ExperimentDefinition {
+                name: Self::CONSTANT_FOLDING,
+                default: false,
+                description: "Fold constants in the stackless bytecode",
+            },
+            ExperimentDefinition {
+                name: Self::COPY_PROPAGATION,
+                default: false,
+                description: "Run copy propagation on the stackless bytecode",
+            },
+            ExperimentDefinition {
+                name: Self::DEAD_STORE_ELIMINATION,
+                default: false,
+                description: "Run dead store elimination on the stackless bytecode",
+            },
+            ExperimentDefinition {
+                name: Self::FUNCTION_INLINING,
+                default: false,
+                description: "Inline calls to small private and `#[inline]` functions",
+            },
+            ExperimentDefinition {
+                name: Self::PEEPHOLE_OPTIMIZATION,
+                default: false,
+                description: "Run the peephole optimizer over the generated file format code",
+            },
+            ExperimentDefinition {
+                name: Self::SKIP_BYTECODE_VERIFIER,
+                default: false,
+                description: "Skip running the bytecode verifier on the generated code",
+            },
+        ]
+    }
+
+    /// Returns the definition of the named experiment, if it is known.
+    pub fn find(name: &str) -> Option<&'static ExperimentDefinition> {
+        Self::definitions().iter().find(|def| def.name == name)
+    }
+
+    /// Parses a setting of an experiment, given as `<name>` or `<name>=<value>`, where the
+    /// value is one of `on`, `off`, `true` or `false`. Returns the name and whether the
+    /// experiment is on. The name is not checked against the registry.
+    pub fn parse_setting(setting: &str) -> Result<(&str, bool), String> {
+        match setting.split_once('=') {
+            None => Ok((setting.trim(), true)),
+            Some((name, value)) => match value.trim() {
+                "on" | "true" => Ok((name.trim(), true)),
+                "off" | "false" => Ok((name.trim(), false)),
+                _ => Err(format!(
+                    "invalid value `{}` for experiment `{}`, expected `on` or `off`",
+                    value.trim(),
+                    name.trim()
+                )),
+            },
+        }
+    }
+
+    /// Returns a listing of the registry, one experiment per line.
+    pub fn listing() -> String {
+        let width = Self::definitions()
+            .iter()
+            .map(|def| def.name.len())
+            .max()
+            .unwrap_or_default();
+        Self::definitions()
+            .iter()
+            .map(|def| {
+                format!(
+                    "{:width$}  {:3}  {}\n",
+                    def.name,
+                    if def.default { "on" } else { "off" },
+                    def.description,
+                    width = width
+                )
+            })
+            .collect()
+    }
 }
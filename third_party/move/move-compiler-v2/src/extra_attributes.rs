@@ -0,0 +1,93 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the attributes defined by embedders via `Options::extra_known_attributes`. The
+//! front end only checks whether the name of an attribute is known, so the items an attribute
+//! is attached to, and the shape of its arguments, are checked here against its
+//! `AttributeSpec`. Each use of such an attribute in a target module which is attached to an
+//! item not among the positions of the spec, or whose arguments do not have the shape of the
+//! spec, is reported as an error.
+
+use crate::{
+    diagnostic_codes::{MALFORMED_ATTRIBUTE, MISPLACED_ATTRIBUTE},
+    options::{AttributeArguments, AttributePosition, AttributeSpec},
+};
+use move_model::{ast::Attribute, model::GlobalEnv};
+
+/// Checks the uses of the attributes of the specs in the target modules.
+pub fn check_extra_attributes(env: &GlobalEnv, specs: &[AttributeSpec]) {
+    if specs.is_empty() {
+        return;
+    }
+    for module_env in env.get_modules().filter(|m| m.is_target()) {
+        let check = |attrs: &[Attribute], position| check_attributes(env, specs, attrs, position);
+        check(module_env.get_attributes(), AttributePosition::Module);
+        for fun_env in module_env.get_functions() {
+            check(fun_env.get_attributes(), AttributePosition::Function)
+        }
+        for struct_env in module_env.get_structs() {
+            check(struct_env.get_attributes(), AttributePosition::Struct)
+        }
+    }
+}
+
+fn check_attributes(
+    env: &GlobalEnv,
+    specs: &[AttributeSpec],
+    attrs: &[Attribute],
+    position: AttributePosition,
+) {
+    for attr in attrs {
+        let name = env.symbol_pool().string(attr.name());
+        let Some(spec) = specs.iter().find(|spec| spec.name == *name) else {
+            continue;
+        };
+        let loc = env.get_node_loc(attr.node_id());
+        if !spec.positions.contains(&position) {
+            MISPLACED_ATTRIBUTE.error(
+                env,
+                &loc,
+                &format!(
+                    "attribute `{}` is not allowed on a {}, only on: {}",
+                    name,
+                    position_name(position),
+                    spec.positions
+                        .iter()
+                        .map(|p| format!("{}s", position_name(*p)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+        }
+        let well_formed = match (spec.arguments, attr) {
+            (AttributeArguments::Any, _) => true,
+            (AttributeArguments::None, Attribute::Apply(_, _, args)) => args.is_empty(),
+            (AttributeArguments::List, Attribute::Apply(_, _, args)) => !args.is_empty(),
+            (AttributeArguments::Value, Attribute::Assign(..)) => true,
+            _ => false,
+        };
+        if !well_formed {
+            let expected = match spec.arguments {
+                AttributeArguments::None => format!("no arguments, as in `#[{}]`", name),
+                AttributeArguments::List => {
+                    format!("a list of arguments, as in `#[{}(<argument>, ..)]`", name)
+                },
+                AttributeArguments::Value => format!("a value, as in `#[{} = <value>]`", name),
+                AttributeArguments::Any => unreachable!("any arguments are well-formed"),
+            };
+            MALFORMED_ATTRIBUTE.error(
+                env,
+                &loc,
+                &format!("attribute `{}` expects {}", name, expected),
+            )
+        }
+    }
+}
+
+fn position_name(position: AttributePosition) -> &'static str {
+    match position {
+        AttributePosition::Module => "module",
+        AttributePosition::Function => "function",
+        AttributePosition::Struct => "struct",
+    }
+}
@@ -2,21 +2,26 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::file_format_generator::{
-    module_generator::{ModuleContext, ModuleGenerator},
-    MAX_FUNCTION_DEF_COUNT, MAX_LOCAL_COUNT,
+use crate::{
+    bytecode_generator::{DeclaredLocals, SpecBlocks},
+    diagnostic_codes,
+    file_format_generator::{
+        module_generator::{ModuleContext, ModuleGenerator},
+        peephole_optimizer, table_index, MAX_CODE_SIZE, MAX_FUNCTION_DEF_COUNT, MAX_LOCAL_COUNT,
+    },
+    spec_info::{SpecAnchor, SpecAnchors},
+    Experiment, Options,
 };
-use move_binary_format::file_format as FF;
+use move_binary_format::{file_format as FF, file_format_common::VERSION_6};
 use move_model::{
     ast::TempIndex,
-    model::{FunId, FunctionEnv, Loc, QualifiedId, StructId, TypeParameter},
+    model::{FunId, FunctionEnv, Loc, NodeId, Parameter, QualifiedId, StructId, TypeParameter},
     ty::{PrimitiveType, Type},
 };
 use move_stackless_bytecode::{
     function_target::FunctionTarget,
-    function_target_pipeline::FunctionVariant,
     livevar_analysis::LiveVarAnnotation,
-    stackless_bytecode::{Bytecode, Constant, Label, Operation},
+    stackless_bytecode::{AttrId, Bytecode, Constant, Label, Operation},
 };
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -28,6 +33,9 @@ pub struct FunctionGenerator<'a> {
     pinned: BTreeSet<TempIndex>,
     /// A map from a temporary to information associated with it.
     temps: BTreeMap<TempIndex, TempInfo>,
+    /// For each temporary, the temporaries which are alive at the same time, and therefore
+    /// cannot share a local with it.
+    interference: BTreeMap<TempIndex, BTreeSet<TempIndex>>,
     /// The value stack, represented by the temporaries which are located on it.
     stack: Vec<TempIndex>,
     /// The locals which have been used so far. This contains the parameters of the function.
@@ -36,6 +44,11 @@ pub struct FunctionGenerator<'a> {
     label_info: BTreeMap<Label, LabelInfo>,
     /// The generated code
     code: Vec<FF::Bytecode>,
+    /// Where the inline spec blocks of the function landed in the generated code, keyed by the
+    /// node id of the block.
+    spec_anchors: BTreeMap<NodeId, SpecAnchor>,
+    /// The index of the function definition, used for the source map.
+    def_idx: FF::FunctionDefinitionIndex,
 }
 
 /// Immutable context for a function, seperated from the mutable generator state, to reduce
@@ -94,35 +107,45 @@ impl<'a> FunctionGenerator<'a> {
         let function = gen.function_index(ctx, &loc, &fun_env);
         let visibility = fun_env.visibility();
         let fun_count = gen.module.function_defs.len();
+        let def_idx =
+            FF::FunctionDefinitionIndex::new(table_index(fun_count, MAX_FUNCTION_DEF_COUNT));
+        Self::gen_function_source_map(gen, ctx, &fun_env, def_idx);
         let (gen, code) = if !fun_env.is_native() {
             let mut fun_gen = Self {
                 gen,
                 pinned: Default::default(),
                 temps: Default::default(),
+                interference: Default::default(),
                 stack: vec![],
                 locals: vec![],
                 label_info: Default::default(),
                 code: vec![],
+                spec_anchors: BTreeMap::new(),
+                def_idx,
             };
-            let target = ctx.targets.get_target(&fun_env, &FunctionVariant::Baseline);
-
-            let code = fun_gen.gen_code(&FunctionContext {
+            let target = ctx.code_target(&fun_env);
+            let fun_ctx = FunctionContext {
                 module: ctx.clone(),
                 fun: target,
                 loc: loc.clone(),
                 type_parameters: fun_env.get_type_parameters(),
-            });
+            };
+            let code = fun_gen.gen_code(&fun_ctx);
+            fun_gen.check_function_limits(&fun_ctx, &code);
+            fun_gen.gen_local_source_map(&fun_ctx);
             (fun_gen.gen, Some(code))
         } else {
             (gen, None)
         };
-        let acquires_global_resources = acquires_list
+        // Order acquired structs by their definition, not by their ids, which are symbols.
+        let mut acquires_global_resources = acquires_list
             .iter()
             .map(|id| {
                 let struct_env = fun_env.module_env.get_struct(*id);
                 gen.struct_def_index(ctx, &struct_env.get_loc(), &struct_env)
             })
-            .collect();
+            .collect::<Vec<_>>();
+        acquires_global_resources.sort();
         let def = FF::FunctionDefinition {
             function,
             visibility,
@@ -130,19 +153,121 @@ impl<'a> FunctionGenerator<'a> {
             acquires_global_resources,
             code,
         };
-        ctx.checked_bound(
+        gen.module.function_defs.push(def)
+    }
+
+    /// Reports the locals and instructions of the function which exceed the limits of the
+    /// file format. Parameters count as locals.
+    fn check_function_limits(&self, ctx: &FunctionContext, code: &FF::CodeUnit) {
+        for (count, max, kind) in [
+            (self.locals.len(), MAX_LOCAL_COUNT, "locals"),
+            (code.code.len(), MAX_CODE_SIZE, "instructions"),
+        ] {
+            if count > max {
+                ctx.module.error(
+                    &ctx.loc,
+                    diagnostic_codes::TABLE_SIZE_EXCEEDED,
+                    format!(
+                        "function `{}` has {} {}, exceeding the file format limit of {}; \
+                        consider splitting the function",
+                        ctx.fun.func_env.get_full_name_with_address(),
+                        count,
+                        kind,
+                        max
+                    ),
+                )
+            }
+        }
+    }
+
+    /// Adds the declaration of the function and its parameters to the source map.
+    fn gen_function_source_map(
+        gen: &mut ModuleGenerator,
+        ctx: &ModuleContext,
+        fun_env: &FunctionEnv,
+        def_idx: FF::FunctionDefinitionIndex,
+    ) {
+        let loc = &fun_env.get_loc();
+        ctx.check_source_map(
             loc,
-            fun_count, // gen.module.function_defs.len(),
-            MAX_FUNCTION_DEF_COUNT,
-            "defined function",
+            gen.source_map.add_top_level_function_mapping(
+                def_idx,
+                ctx.env.to_ir_loc(loc),
+                fun_env.is_native(),
+            ),
         );
-        gen.module.function_defs.push(def)
+        for TypeParameter(name, _) in fun_env.get_type_parameters() {
+            ctx.check_source_map(
+                loc,
+                gen.source_map
+                    .add_function_type_parameter_mapping(def_idx, ctx.source_name(name, loc)),
+            );
+        }
+        // Use the declaration locations of parameters if the bytecode generator provided them.
+        let declared = ctx
+            .code_target(fun_env)
+            .get_annotations()
+            .get::<DeclaredLocals>()
+            .map(|d| d.vars.clone())
+            .unwrap_or_default();
+        for (temp, Parameter(name, _)) in fun_env.get_parameters().into_iter().enumerate() {
+            let param_loc = declared.get(&temp).map(|(_, l)| l).unwrap_or(loc);
+            ctx.check_source_map(
+                loc,
+                gen.source_map
+                    .add_parameter_mapping(def_idx, ctx.source_name(name, param_loc)),
+            );
+        }
+    }
+
+    /// Adds the locals of the function, which are not parameters, to the source map. With
+    /// `Options::emit_debug_info`, locals are named after the variables they represent, or
+    /// `%<temp>` for temporaries introduced by the compiler. Otherwise they are named after
+    /// their index.
+    fn gen_local_source_map(&mut self, ctx: &FunctionContext) {
+        let emit_debug_info = ctx
+            .module
+            .env
+            .get_extension::<Options>()
+            .map_or(false, |options| options.emit_debug_info);
+        let declared = ctx
+            .fun
+            .get_annotations()
+            .get::<DeclaredLocals>()
+            .map(|d| d.vars.clone())
+            .unwrap_or_default();
+        // A local shared by several temporaries is named after the first of them which
+        // represents a variable, if any.
+        let mut local_to_temp = BTreeMap::new();
+        for (temp, info) in &self.temps {
+            let replace = match local_to_temp.get(&info.local) {
+                None => true,
+                Some(other) => !declared.contains_key(other) && declared.contains_key(temp),
+            };
+            if replace {
+                local_to_temp.insert(info.local, *temp);
+            }
+        }
+        for local in ctx.fun.get_parameter_count()..self.locals.len() {
+            let name = match local_to_temp.get(&(local as FF::LocalIndex)) {
+                Some(temp) if emit_debug_info => match declared.get(temp) {
+                    Some((name, loc)) => ctx.module.source_name(*name, loc),
+                    None => (format!("%{}", temp), ctx.module.env.to_ir_loc(&ctx.loc)),
+                },
+                _ => (format!("$l{}", local), ctx.module.env.to_ir_loc(&ctx.loc)),
+            };
+            ctx.module.check_source_map(
+                &ctx.loc,
+                self.gen.source_map.add_local_mapping(self.def_idx, name),
+            );
+        }
     }
 
     /// Generates code for a function.
     fn gen_code(&mut self, ctx: &FunctionContext<'_>) -> FF::CodeUnit {
         // Initialize the abstract virtual machine
         self.pinned = Self::referenced_temps(ctx);
+        self.interference = Self::interfering_temps(ctx);
         self.temps = (0..ctx.fun.get_parameter_count())
             .map(|temp| (temp, TempInfo::new(self.temp_to_local(ctx, temp))))
             .collect();
@@ -150,7 +275,9 @@ impl<'a> FunctionGenerator<'a> {
             .map(|temp| ctx.temp_type(temp).to_owned())
             .collect();
 
-        // Walk the bytecode
+        // Walk the bytecode, remembering the location of the instructions generated for each
+        // stackless instruction for the source map.
+        let mut code_locs = vec![];
         let bytecode = ctx.fun.get_bytecode();
         for i in 0..bytecode.len() {
             let code_offset = i as FF::CodeOffset;
@@ -158,6 +285,7 @@ impl<'a> FunctionGenerator<'a> {
                 fun_ctx: ctx,
                 code_offset,
             };
+            let start = self.code.len() as FF::CodeOffset;
             if i + 1 < bytecode.len() {
                 let bc = &bytecode[i];
                 let next_bc = &bytecode[i + 1];
@@ -170,7 +298,14 @@ impl<'a> FunctionGenerator<'a> {
             } else {
                 self.gen_bytecode(&bytecode_ctx, &bytecode[i], None)
             }
+            // Map the generated instructions to the location of the stackless bytecode.
+            if self.code.len() as FF::CodeOffset > start {
+                let loc = ctx.fun.get_bytecode_loc(bytecode[i].get_attr_id());
+                self.check_version(ctx, &loc, start);
+                code_locs.push((start, loc, i));
+            }
         }
+        let mut code_locs = Self::attribute_synthesized_instructions(ctx, code_locs);
 
         // At this point, all labels should be resolved, so link them.
         for info in self.label_info.values() {
@@ -190,6 +325,42 @@ impl<'a> FunctionGenerator<'a> {
             }
         }
 
+        // Optimize the code if requested, and map instructions to their locations.
+        let options = ctx
+            .module
+            .env
+            .get_extension::<Options>()
+            .unwrap_or_default();
+        if options.experiment_on(Experiment::PEEPHOLE_OPTIMIZATION) || options.optimize >= 2 {
+            let mapping = peephole_optimizer::optimize(&mut self.code);
+            for (start, _) in code_locs.iter_mut() {
+                *start = mapping[*start as usize]
+            }
+            // An anchor past the last instruction stays past it.
+            for anchor in self.spec_anchors.values_mut() {
+                anchor.offset = mapping
+                    .get(anchor.offset as usize)
+                    .copied()
+                    .unwrap_or(self.code.len() as FF::CodeOffset)
+            }
+        }
+        if let Some(anchors) = ctx.module.env.get_extension::<SpecAnchors>() {
+            anchors.record(
+                ctx.fun.func_env.get_qualified_id(),
+                std::mem::take(&mut self.spec_anchors),
+            )
+        }
+        for (start, loc) in code_locs {
+            ctx.module.check_source_map(
+                &loc,
+                self.gen.source_map.add_code_mapping(
+                    self.def_idx,
+                    start,
+                    ctx.module.env.to_ir_loc(&loc),
+                ),
+            );
+        }
+
         // Deliver result
         let locals = self.gen.signature(
             &ctx.module,
@@ -202,6 +373,98 @@ impl<'a> FunctionGenerator<'a> {
         }
     }
 
+    /// Attributes the instructions generated for stackless instructions which the compiler
+    /// synthesizes to the statements around them, so they do not dilute source coverage. Takes
+    /// the start offset, location and stackless offset of each group of generated instructions,
+    /// and returns the start offsets with the locations for the source map.
+    ///
+    /// Jumps are synthesized, and so are returns, branches, assignments and drops whose location
+    /// encloses the location of another instruction, like the return at the end of a function
+    /// body, or the branch of a loop. They get the location of the closest other instruction in their
+    /// block, preferring preceding ones, or if there is none, the end of their own location.
+    fn attribute_synthesized_instructions(
+        ctx: &FunctionContext,
+        code_locs: Vec<(FF::CodeOffset, Loc, usize)>,
+    ) -> Vec<(FF::CodeOffset, Loc)> {
+        let bytecode = ctx.fun.get_bytecode();
+        let mut spans = bytecode
+            .iter()
+            .map(|bc| {
+                let loc = ctx.fun.get_bytecode_loc(bc.get_attr_id());
+                (loc.file_id(), loc.span().start(), loc.span().end())
+            })
+            .collect::<Vec<_>>();
+        spans.sort();
+        spans.dedup();
+        let encloses_other = |loc: &Loc| {
+            let (file_id, start, end) = (loc.file_id(), loc.span().start(), loc.span().end());
+            let first = spans.partition_point(|span| span < &(file_id, start, start));
+            spans[first..]
+                .iter()
+                .take_while(|(f, s, _)| *f == file_id && *s < end)
+                .any(|(_, s, e)| *e <= end && (*s, *e) != (start, end))
+        };
+        // The blocks of the stackless instructions, which start at labels and after branches.
+        let mut blocks = vec![];
+        let mut block = 0;
+        for bc in bytecode {
+            if matches!(bc, Bytecode::Label(..)) {
+                block += 1
+            }
+            blocks.push(block);
+            if bc.is_branch() {
+                block += 1
+            }
+        }
+        let synthesized = code_locs
+            .iter()
+            .map(|(_, loc, offset)| match &bytecode[*offset] {
+                Bytecode::Jump(..) | Bytecode::Nop(..) => true,
+                Bytecode::Ret(..)
+                | Bytecode::Branch(..)
+                | Bytecode::Assign(..)
+                | Bytecode::Call(_, _, Operation::Destroy, _, _) => encloses_other(loc),
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+        let statement_loc = |idx: usize| {
+            let block = blocks[code_locs[idx].2];
+            let in_block = |other: &usize| blocks[code_locs[*other].2] == block;
+            (0..idx)
+                .rev()
+                .take_while(in_block)
+                .chain((idx + 1..code_locs.len()).take_while(in_block))
+                .find(|other| !synthesized[*other])
+                .map(|other| code_locs[other].1.clone())
+                .unwrap_or_else(|| code_locs[idx].1.at_end())
+        };
+        (0..code_locs.len())
+            .map(|idx| {
+                let loc = if synthesized[idx] {
+                    statement_loc(idx)
+                } else {
+                    code_locs[idx].1.clone()
+                };
+                (code_locs[idx].0, loc)
+            })
+            .collect()
+    }
+
+    /// Checks that the instructions generated from the given code offset on are supported by
+    /// the targeted bytecode version.
+    fn check_version(&mut self, ctx: &FunctionContext, loc: &Loc, start: FF::CodeOffset) {
+        for bc in &self.code[start as usize..] {
+            let feature = match bc {
+                FF::Bytecode::LdU16(_) | FF::Bytecode::CastU16 => "the `u16` type",
+                FF::Bytecode::LdU32(_) | FF::Bytecode::CastU32 => "the `u32` type",
+                FF::Bytecode::LdU256(_) | FF::Bytecode::CastU256 => "the `u256` type",
+                _ => continue,
+            };
+            self.gen
+                .require_version(&ctx.module, loc, VERSION_6, feature)
+        }
+    }
+
     /// Compute the set of temporaries which are referenced in borrow instructions.
     fn referenced_temps(ctx: &FunctionContext) -> BTreeSet<TempIndex> {
         let mut result = BTreeSet::new();
@@ -213,6 +476,43 @@ impl<'a> FunctionGenerator<'a> {
         result
     }
 
+    /// Computes, for each temporary, the temporaries which are alive at the same time. Two
+    /// temporaries interfere if they are alive before or after the same instruction, or if one
+    /// is assigned by an instruction after which the other is alive, since the assignment
+    /// overwrites the local even if its value is never used.
+    fn interfering_temps(ctx: &FunctionContext) -> BTreeMap<TempIndex, BTreeSet<TempIndex>> {
+        let mut result: BTreeMap<TempIndex, BTreeSet<TempIndex>> = BTreeMap::new();
+        let mut add_all = |temps: &BTreeSet<TempIndex>| {
+            for temp in temps {
+                result
+                    .entry(*temp)
+                    .or_default()
+                    .extend(temps.iter().filter(|t| *t != temp))
+            }
+        };
+        let annotation = ctx
+            .fun
+            .get_annotations()
+            .get::<LiveVarAnnotation>()
+            .expect("livevar analysis result");
+        for (offset, bc) in ctx.fun.get_bytecode().iter().enumerate() {
+            let Some(info) = annotation.get_live_var_info_at(offset as FF::CodeOffset) else {
+                continue;
+            };
+            let mut after = info.after.clone();
+            match bc {
+                Bytecode::Assign(_, dest, ..) | Bytecode::Load(_, dest, _) => {
+                    after.insert(*dest);
+                },
+                Bytecode::Call(_, dests, ..) => after.extend(dests.iter().cloned()),
+                _ => {},
+            }
+            add_all(&info.before);
+            add_all(&after)
+        }
+        result
+    }
+
     /// Generate file-format bytecode from a stackless bytecode and an optional next bytecode
     /// for peephole optimizations.
     fn gen_bytecode(&mut self, ctx: &BytecodeContext, bc: &Bytecode, next_bc: Option<&Bytecode>) {
@@ -231,7 +531,7 @@ impl<'a> FunctionGenerator<'a> {
             Bytecode::Call(_, dest, oper, source, None) => {
                 self.gen_operation(ctx, dest, oper, source)
             },
-            Bytecode::Load(_, dest, cons) => self.gen_load(ctx, dest, cons),
+            Bytecode::Load(attr_id, dest, cons) => self.gen_load(ctx, *attr_id, dest, cons),
             Bytecode::Label(_, label) => self.define_label(*label),
             Bytecode::Branch(_, if_true, if_false, cond) => {
                 // Ensure only `cond` is on the stack before branch.
@@ -277,10 +577,29 @@ impl<'a> FunctionGenerator<'a> {
             Bytecode::Nop(_) => {
                 // do nothing -- labels are relative
             },
+            Bytecode::Prop(attr, _, _) => {
+                // No code is generated for conditions, only their spec block is anchored at
+                // the next instruction.
+                let fun = &ctx.fun_ctx.fun;
+                if let Some(block) = fun
+                    .get_annotations()
+                    .get::<SpecBlocks>()
+                    .and_then(|blocks| blocks.conditions.get(attr))
+                {
+                    let offset = self.code.len() as FF::CodeOffset;
+                    self.spec_anchors
+                        .entry(*block)
+                        .or_insert_with(|| SpecAnchor {
+                            offset,
+                            condition_locs: vec![],
+                        })
+                        .condition_locs
+                        .push(fun.get_bytecode_loc(*attr))
+                }
+            },
             Bytecode::SaveMem(_, _, _)
             | Bytecode::Call(_, _, _, _, Some(_))
-            | Bytecode::SaveSpecVar(_, _, _)
-            | Bytecode::Prop(_, _, _) => ctx
+            | Bytecode::SaveSpecVar(_, _, _) => ctx
                 .fun_ctx
                 .internal_error("unexpected specification bytecode"),
         }
@@ -479,6 +798,7 @@ impl<'a> FunctionGenerator<'a> {
             Operation::And => self.gen_builtin(ctx, dest, FF::Bytecode::And, source),
             Operation::Eq => self.gen_builtin(ctx, dest, FF::Bytecode::Eq, source),
             Operation::Neq => self.gen_builtin(ctx, dest, FF::Bytecode::Neq, source),
+            Operation::Destroy => self.gen_builtin(ctx, dest, FF::Bytecode::Pop, source),
 
             Operation::TraceLocal(_)
             | Operation::TraceReturn(_)
@@ -492,7 +812,6 @@ impl<'a> FunctionGenerator<'a> {
             | Operation::GetField(_, _, _, _)
             | Operation::GetGlobal(_, _, _)
             | Operation::Uninit
-            | Operation::Destroy
             | Operation::Havoc(_)
             | Operation::Stop
             | Operation::IsParent(_, _)
@@ -631,7 +950,13 @@ impl<'a> FunctionGenerator<'a> {
     }
 
     /// Generate code for the load instruction.
-    fn gen_load(&mut self, ctx: &BytecodeContext, dest: &TempIndex, cons: &Constant) {
+    fn gen_load(
+        &mut self,
+        ctx: &BytecodeContext,
+        attr_id: AttrId,
+        dest: &TempIndex,
+        cons: &Constant,
+    ) {
         use Constant::*;
         match cons {
             Bool(b) => {
@@ -652,7 +977,7 @@ impl<'a> FunctionGenerator<'a> {
             _ => {
                 let cons = self.gen.constant_index(
                     &ctx.fun_ctx.module,
-                    &ctx.fun_ctx.loc,
+                    &ctx.fun_ctx.fun.get_bytecode_loc(attr_id),
                     cons,
                     ctx.fun_ctx.fun.get_local_type(*dest),
                 );
@@ -680,7 +1005,13 @@ impl<'a> FunctionGenerator<'a> {
             if end > self.stack.len() || end == 0 {
                 continue;
             }
-            if self.stack.ends_with(&temps[0..end]) {
+            // Temporaries still used after this point, or used again as a later argument,
+            // cannot be consumed from the stack, as their value would be lost.
+            if self.stack.ends_with(&temps[0..end])
+                && temps[0..end]
+                    .iter()
+                    .all(|t| !ctx.is_alive_after(*t) && !temps[end..].contains(t))
+            {
                 temps_to_push = &temps[end..temps.len()];
                 break;
             }
@@ -699,10 +1030,13 @@ impl<'a> FunctionGenerator<'a> {
         }
         self.abstract_flush_stack_before(ctx, stack_to_flush);
         // Finally, push `temps_to_push` onto the stack.
-        for temp in temps_to_push {
+        for (i, temp) in temps_to_push.iter().enumerate() {
             let local = self.temp_to_local(fun_ctx, *temp);
-            // Copy the temporary if it is copyable or still used after this code point.
-            if fun_ctx.is_copyable(*temp) && ctx.is_alive_after(*temp) {
+            // Copy the temporary if it is copyable and still used after this code point,
+            // or pushed again as a later argument.
+            if fun_ctx.is_copyable(*temp)
+                && (ctx.is_alive_after(*temp) || temps_to_push[i + 1..].contains(temp))
+            {
                 self.emit(FF::Bytecode::CopyLoc(local))
             } else {
                 self.emit(FF::Bytecode::MoveLoc(local));
@@ -771,25 +1105,48 @@ impl<'a> FunctionGenerator<'a> {
     }
 
     /// Creates a new local of type.
-    fn new_local(&mut self, ctx: &FunctionContext, ty: Type) -> FF::LocalIndex {
-        let local = ctx
-            .module
-            .checked_bound(&ctx.loc, self.locals.len(), MAX_LOCAL_COUNT, "local")
-            as FF::LocalIndex;
+    fn new_local(&mut self, ty: Type) -> FF::LocalIndex {
+        let local = table_index(self.locals.len(), MAX_LOCAL_COUNT) as FF::LocalIndex;
         self.locals.push(ty);
         local
     }
 
-    /// Allocates a local for the given temporary
+    /// Allocates a local for the given temporary. The local of another temporary of the same
+    /// type is reused if their live ranges do not overlap. Parameters and temporaries which
+    /// are borrowed keep a local of their own.
     fn temp_to_local(&mut self, ctx: &FunctionContext, temp: TempIndex) -> FF::LocalIndex {
         if let Some(TempInfo { local }) = self.temps.get(&temp) {
             *local
         } else {
-            let idx = self.new_local(ctx, ctx.temp_type(temp).to_owned());
+            let idx = self
+                .shareable_local(ctx, temp)
+                .unwrap_or_else(|| self.new_local(ctx.temp_type(temp).to_owned()));
             self.temps.insert(temp, TempInfo::new(idx));
             idx
         }
     }
+
+    /// Finds an allocated local which the temporary can share with the temporaries already
+    /// stored in it.
+    fn shareable_local(&self, ctx: &FunctionContext, temp: TempIndex) -> Option<FF::LocalIndex> {
+        if self.pinned.contains(&temp) {
+            return None;
+        }
+        let ty = ctx.temp_type(temp);
+        let interfering = self.interference.get(&temp);
+        let mut blocked = BTreeSet::new();
+        for (other, TempInfo { local }) in &self.temps {
+            if *other < ctx.fun.get_parameter_count()
+                || self.pinned.contains(other)
+                || interfering.map_or(false, |temps| temps.contains(other))
+            {
+                blocked.insert(*local);
+            }
+        }
+        (ctx.fun.get_parameter_count()..self.locals.len())
+            .map(|local| local as FF::LocalIndex)
+            .find(|local| !blocked.contains(local) && &self.locals[*local as usize] == ty)
+    }
 }
 
 impl<'env> FunctionContext<'env> {
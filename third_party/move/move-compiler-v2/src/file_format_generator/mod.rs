@@ -4,25 +4,71 @@
 
 mod function_generator;
 mod module_generator;
+mod peephole_optimizer;
 
-use crate::file_format_generator::module_generator::ModuleContext;
+use crate::{
+    compilation_cache::CompilationCache, file_format_generator::module_generator::ModuleContext,
+    module_filter, partial_compilation, progress::ProgressEvent, spec_info::SpecAnchors, Options,
+};
 use module_generator::ModuleGenerator;
-use move_binary_format::{file_format as FF, internals::ModuleIndex};
+use move_binary_format::{
+    file_format as FF,
+    file_format_common::{
+        ADDRESS_INDEX_MAX, BYTECODE_COUNT_MAX, CONSTANT_INDEX_MAX, FIELD_HANDLE_INDEX_MAX,
+        FIELD_INST_INDEX_MAX, FUNCTION_HANDLE_INDEX_MAX, FUNCTION_INST_INDEX_MAX,
+        IDENTIFIER_INDEX_MAX, LOCAL_INDEX_MAX, MODULE_HANDLE_INDEX_MAX, SIGNATURE_INDEX_MAX,
+        STRUCT_DEF_INDEX_MAX, STRUCT_DEF_INST_INDEX_MAX, STRUCT_HANDLE_INDEX_MAX, TABLE_INDEX_MAX,
+    },
+    internals::ModuleIndex,
+};
 use move_command_line_common::{address::NumericalAddress, parser::NumberFormat};
 use move_compiler::compiled_unit as CU;
-use move_model::model::GlobalEnv;
+use move_model::model::{FunctionEnv, GlobalEnv, ModuleEnv, StructEnv};
 use move_stackless_bytecode::function_target_pipeline::FunctionTargetsHolder;
 use move_symbol_pool::Symbol;
 
+/// Generates the units of the target modules and scripts selected by `Options::module_filter`,
+/// except those excluded by partial compilation, see `partial_compilation`.
+/// Modules come first, ordered by address and name, followed by scripts ordered by name, so the
+/// result does not depend on the order in which sources were given. The tables of a unit are
+/// filled in the order in which entries are first used while visiting the module, whose structs
+/// and functions are visited in the order of their declaration, so the tables are deterministic
+/// as well. Where inline spec blocks landed in the generated code is recorded in
+/// the `SpecAnchors` extension of the env.
 pub fn generate_file_format(
     env: &GlobalEnv,
     targets: &FunctionTargetsHolder,
+    cache: Option<&CompilationCache>,
 ) -> Vec<CU::CompiledUnit> {
+    env.set_extension(SpecAnchors::default());
+    let options = env.get_extension::<Options>().unwrap_or_default();
     let ctx = ModuleContext { env, targets };
     let mut result = vec![];
-    for module_env in ctx.env.get_modules() {
-        if !module_env.is_target() {
-            continue;
+    let module_envs = ctx
+        .env
+        .get_modules()
+        .filter(|module_env| {
+            module_env.is_target()
+                && module_filter::is_selected(module_env, &options.module_filter)
+                && !partial_compilation::is_failed(env, module_env)
+        })
+        .collect::<Vec<_>>();
+    let total = module_envs.len();
+    let report_done = |done| {
+        options
+            .progress
+            .report(ProgressEvent::ModuleGenerated { done, total })
+    };
+    for (count, module_env) in module_envs.into_iter().enumerate() {
+        if let Some(cache) = cache {
+            if let Some(unit) = cache.get(module_env.get_id()) {
+                result.push(unit.clone());
+                report_done(count + 1);
+                continue;
+            }
+            if !module_env.is_script_module() {
+                cache.record_generated(module_env.get_id())
+            }
         }
         let (ff_module, source_map, main_handle) = ModuleGenerator::run(&ctx, &module_env);
         if module_env.is_script_module() {
@@ -87,21 +133,74 @@ pub fn generate_file_format(
                 source_map,
             }));
         }
+        report_done(count + 1)
     }
+    result.sort_by_key(unit_order_key);
     result
 }
 
-const MAX_MODULE_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_IDENTIFIER_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_ADDRESS_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_CONST_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_STRUCT_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_SIGNATURE_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_STRUCT_DEF_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_STRUCT_DEF_INST_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_FIELD_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_FIELD_INST_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_FUNCTION_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_FUNCTION_INST_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_FUNCTION_DEF_COUNT: usize = FF::TableIndex::MAX as usize;
-const MAX_LOCAL_COUNT: usize = FF::LocalIndex::MAX as usize;
+/// The key by which generated units are ordered.
+fn unit_order_key(unit: &CU::CompiledUnit) -> (bool, Option<[u8; 32]>, Symbol) {
+    match unit {
+        CU::CompiledUnitEnum::Module(module) => {
+            (false, Some(module.address.into_bytes()), module.name)
+        },
+        CU::CompiledUnitEnum::Script(script) => (true, None, script.name),
+    }
+}
+
+/// Returns the structs of the module in the order of their declaration. Unlike the order of
+/// their ids, which are symbols, this does not depend on the order in which sources are read.
+pub(crate) fn structs_in_source_order<'env>(module_env: &ModuleEnv<'env>) -> Vec<StructEnv<'env>> {
+    let mut structs = module_env.clone().into_structs().collect::<Vec<_>>();
+    structs.sort_by_cached_key(|struct_env| {
+        (
+            struct_env.get_loc().span().start(),
+            struct_env.get_full_name_str(),
+        )
+    });
+    structs
+}
+
+/// Returns the functions of the module in the order of their declaration, like
+/// `structs_in_source_order`. Functions generated by the compiler, like lifted lambdas, are
+/// ordered by their location as well.
+pub(crate) fn functions_in_source_order<'env>(
+    module_env: &ModuleEnv<'env>,
+) -> Vec<FunctionEnv<'env>> {
+    let mut functions = module_env.clone().into_functions().collect::<Vec<_>>();
+    functions.sort_by_cached_key(|fun_env| {
+        (
+            fun_env.get_loc().span().start(),
+            fun_env.get_full_name_str(),
+        )
+    });
+    functions
+}
+
+const MAX_MODULE_COUNT: usize = MODULE_HANDLE_INDEX_MAX as usize;
+const MAX_IDENTIFIER_COUNT: usize = IDENTIFIER_INDEX_MAX as usize;
+const MAX_ADDRESS_COUNT: usize = ADDRESS_INDEX_MAX as usize;
+const MAX_CONST_COUNT: usize = CONSTANT_INDEX_MAX as usize;
+const MAX_STRUCT_COUNT: usize = STRUCT_HANDLE_INDEX_MAX as usize;
+const MAX_SIGNATURE_COUNT: usize = SIGNATURE_INDEX_MAX as usize;
+const MAX_STRUCT_DEF_COUNT: usize = STRUCT_DEF_INDEX_MAX as usize;
+const MAX_STRUCT_DEF_INST_COUNT: usize = STRUCT_DEF_INST_INDEX_MAX as usize;
+const MAX_FIELD_COUNT: usize = FIELD_HANDLE_INDEX_MAX as usize;
+const MAX_FIELD_INST_COUNT: usize = FIELD_INST_INDEX_MAX as usize;
+const MAX_FUNCTION_COUNT: usize = FUNCTION_HANDLE_INDEX_MAX as usize;
+const MAX_FUNCTION_INST_COUNT: usize = FUNCTION_INST_INDEX_MAX as usize;
+const MAX_FUNCTION_DEF_COUNT: usize = TABLE_INDEX_MAX as usize;
+const MAX_LOCAL_COUNT: usize = LOCAL_INDEX_MAX as usize;
+const MAX_CODE_SIZE: usize = BYTECODE_COUNT_MAX as usize;
+
+/// Returns the index of a new entry of a table with `value` entries, or 0 if the table
+/// already has `max` entries. Entries are still added beyond the limit, and violations are
+/// reported once per table by the limit checks after generation, which know the final count.
+fn table_index(value: usize, max: usize) -> FF::TableIndex {
+    if value >= max {
+        0
+    } else {
+        value as FF::TableIndex
+    }
+}
@@ -2,19 +2,25 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::file_format_generator::{
-    function_generator::FunctionGenerator, MAX_ADDRESS_COUNT, MAX_CONST_COUNT, MAX_FIELD_COUNT,
-    MAX_FIELD_INST_COUNT, MAX_FUNCTION_COUNT, MAX_FUNCTION_INST_COUNT, MAX_IDENTIFIER_COUNT,
-    MAX_MODULE_COUNT, MAX_SIGNATURE_COUNT, MAX_STRUCT_COUNT, MAX_STRUCT_DEF_COUNT,
-    MAX_STRUCT_DEF_INST_COUNT,
+use crate::{
+    dead_functions,
+    diagnostic_codes::{self, DiagnosticCode},
+    file_format_generator::{
+        function_generator::FunctionGenerator, functions_in_source_order, structs_in_source_order,
+        table_index, MAX_ADDRESS_COUNT, MAX_CONST_COUNT, MAX_FIELD_COUNT, MAX_FIELD_INST_COUNT,
+        MAX_FUNCTION_COUNT, MAX_FUNCTION_DEF_COUNT, MAX_FUNCTION_INST_COUNT, MAX_IDENTIFIER_COUNT,
+        MAX_MODULE_COUNT, MAX_SIGNATURE_COUNT, MAX_STRUCT_COUNT, MAX_STRUCT_DEF_COUNT,
+        MAX_STRUCT_DEF_INST_COUNT,
+    },
+    pipeline::acquires_inference::AcquiresAnnotation,
+    Options,
 };
-use codespan_reporting::diagnostic::Severity;
 use move_binary_format::{
     file_format as FF,
     file_format::{FunctionHandle, ModuleHandle, TableIndex},
-    file_format_common,
+    file_format_common::{VERSION_6, VERSION_7},
 };
-use move_bytecode_source_map::source_map::SourceMap;
+use move_bytecode_source_map::source_map::{SourceMap, SourceName};
 use move_core_types::{account_address::AccountAddress, identifier::Identifier};
 use move_ir_types::ast as IR_AST;
 use move_model::{
@@ -27,8 +33,9 @@ use move_model::{
     ty::{PrimitiveType, ReferenceKind, Type},
 };
 use move_stackless_bytecode::{
+    function_target::FunctionTarget,
     function_target_pipeline::{FunctionTargetsHolder, FunctionVariant},
-    stackless_bytecode::{Bytecode, Constant, Operation},
+    stackless_bytecode::Constant,
 };
 use move_symbol_pool::symbol as IR_SYMBOL;
 use std::collections::{BTreeMap, BTreeSet};
@@ -68,12 +75,17 @@ pub struct ModuleGenerator {
         BTreeMap<(QualifiedId<StructId>, usize, FF::SignatureIndex), FF::FieldInstantiationIndex>,
     /// A mapping from type sequences to signature indices.
     types_to_signature: BTreeMap<Vec<Type>, FF::SignatureIndex>,
-    /// A mapping from constants sequences to pool indices.
-    cons_to_idx: BTreeMap<Constant, FF::ConstantPoolIndex>,
+    /// A mapping from constants, by type and serialized value, to pool indices.
+    cons_to_idx: BTreeMap<(FF::SignatureToken, Vec<u8>), FF::ConstantPoolIndex>,
+    /// The maximal size of the serialized value of a constant.
+    max_constant_size: usize,
     /// The file-format module we are building.
     pub module: FF::CompiledModule,
     /// The source map for the module.
     pub source_map: SourceMap,
+    /// Features for which an error was reported because they are not supported by the
+    /// targeted bytecode version.
+    unsupported_features: BTreeSet<&'static str>,
 }
 
 /// Immutable context for a module code generation, separated from the mutable generator
@@ -92,8 +104,9 @@ impl ModuleGenerator {
         ctx: &ModuleContext,
         module_env: &ModuleEnv,
     ) -> (FF::CompiledModule, SourceMap, Option<FF::FunctionHandle>) {
+        let options = ctx.env.get_extension::<Options>().unwrap_or_default();
         let module = move_binary_format::CompiledModule {
-            version: file_format_common::VERSION_NEXT,
+            version: options.target_bytecode_version(),
             self_module_handle_idx: FF::ModuleHandleIndex(0),
             ..Default::default()
         };
@@ -123,13 +136,16 @@ impl ModuleGenerator {
             field_inst_to_idx: Default::default(),
             types_to_signature: Default::default(),
             cons_to_idx: Default::default(),
+            max_constant_size: options.constant_size_limit(),
             fun_inst_to_idx: Default::default(),
             main_handle: None,
             script_handle: None,
             module,
             source_map,
+            unsupported_features: Default::default(),
         };
         gen.gen_module(ctx, module_env);
+        gen.check_module_limits(ctx, module_env);
         (gen.module, gen.source_map, gen.main_handle)
     }
 
@@ -140,16 +156,94 @@ impl ModuleGenerator {
         if !module_env.is_script_module() {
             let loc = &module_env.get_loc();
             self.module_index(ctx, loc, module_env);
+            // Friend declarations refer to modules by handles which are not part of the module
+            // handle table. The friend modules need not be part of the compilation.
+            for friend_decl in module_env.get_friend_decls() {
+                let friend_name = &friend_decl.module_name;
+                let address = self.address_index(friend_name.addr().expect_numerical());
+                let name = self.name_index(ctx, loc, friend_name.name());
+                self.module
+                    .friend_decls
+                    .push(FF::ModuleHandle { address, name })
+            }
         }
 
-        for struct_env in module_env.get_structs() {
+        for struct_env in structs_in_source_order(module_env) {
             self.gen_struct(ctx, &struct_env)
         }
 
-        let acquires_map = ctx.generate_acquires_map(module_env);
-        for fun_env in module_env.get_functions() {
-            let acquires_list = &acquires_map[&fun_env.get_id()];
-            FunctionGenerator::run(self, ctx, fun_env, acquires_list);
+        for fun_env in functions_in_source_order(module_env) {
+            if dead_functions::is_stripped(ctx.env, &fun_env) {
+                continue;
+            }
+            let acquires_list = ctx
+                .targets
+                .get_target(&fun_env, &FunctionVariant::Baseline)
+                .get_annotations()
+                .get::<AcquiresAnnotation>()
+                .expect("acquires annotation")
+                .acquires
+                .clone();
+            FunctionGenerator::run(self, ctx, fun_env, &acquires_list);
+        }
+    }
+
+    /// Reports the tables of the module which exceed the limits of the file format.
+    fn check_module_limits(&self, ctx: &ModuleContext, module_env: &ModuleEnv) {
+        let m = &self.module;
+        for (count, max, kind) in [
+            (m.module_handles.len(), MAX_MODULE_COUNT, "module handles"),
+            (m.struct_handles.len(), MAX_STRUCT_COUNT, "struct handles"),
+            (
+                m.function_handles.len(),
+                MAX_FUNCTION_COUNT,
+                "function handles",
+            ),
+            (m.field_handles.len(), MAX_FIELD_COUNT, "field handles"),
+            (
+                m.struct_def_instantiations.len(),
+                MAX_STRUCT_DEF_INST_COUNT,
+                "struct instantiations",
+            ),
+            (
+                m.function_instantiations.len(),
+                MAX_FUNCTION_INST_COUNT,
+                "function instantiations",
+            ),
+            (
+                m.field_instantiations.len(),
+                MAX_FIELD_INST_COUNT,
+                "field instantiations",
+            ),
+            (m.signatures.len(), MAX_SIGNATURE_COUNT, "signatures"),
+            (m.identifiers.len(), MAX_IDENTIFIER_COUNT, "identifiers"),
+            (m.address_identifiers.len(), MAX_ADDRESS_COUNT, "addresses"),
+            (m.constant_pool.len(), MAX_CONST_COUNT, "constants"),
+            (
+                m.struct_defs.len(),
+                MAX_STRUCT_DEF_COUNT,
+                "struct definitions",
+            ),
+            (
+                m.function_defs.len(),
+                MAX_FUNCTION_DEF_COUNT,
+                "function definitions",
+            ),
+        ] {
+            if count > max {
+                ctx.error(
+                    module_env.get_loc(),
+                    diagnostic_codes::TABLE_SIZE_EXCEEDED,
+                    format!(
+                        "module `{}` has {} {}, exceeding the file format limit of {}; \
+                        consider splitting the module",
+                        module_env.get_full_name_str(),
+                        count,
+                        kind,
+                        max
+                    ),
+                )
+            }
         }
     }
 
@@ -172,13 +266,62 @@ impl ModuleGenerator {
             struct_handle,
             field_information,
         };
-        ctx.checked_bound(
-            loc,
+        let def_idx = FF::StructDefinitionIndex::new(table_index(
             self.module.struct_defs.len(),
             MAX_STRUCT_DEF_COUNT,
-            "struct",
-        );
+        ));
         self.module.struct_defs.push(def);
+        self.gen_struct_source_map(ctx, struct_env, def_idx)
+    }
+
+    /// Adds the declarations of the struct to the source map.
+    fn gen_struct_source_map(
+        &mut self,
+        ctx: &ModuleContext,
+        struct_env: &StructEnv<'_>,
+        def_idx: FF::StructDefinitionIndex,
+    ) {
+        let loc = &struct_env.get_loc();
+        ctx.check_source_map(
+            loc,
+            self.source_map
+                .add_top_level_struct_mapping(def_idx, ctx.env.to_ir_loc(loc)),
+        );
+        for TypeParameter(name, _) in struct_env.get_type_parameters() {
+            ctx.check_source_map(
+                loc,
+                self.source_map
+                    .add_struct_type_parameter_mapping(def_idx, ctx.source_name(*name, loc)),
+            );
+        }
+        for field_env in struct_env.get_fields() {
+            ctx.check_source_map(
+                loc,
+                self.source_map
+                    .add_struct_field_mapping(def_idx, ctx.env.to_ir_loc(field_env.get_loc())),
+            );
+        }
+    }
+
+    /// Reports an error if the targeted bytecode version is lower than the version required
+    /// by the feature. The error is reported only once per module and feature.
+    pub fn require_version(
+        &mut self,
+        ctx: &ModuleContext,
+        loc: &Loc,
+        required: u32,
+        feature: &'static str,
+    ) {
+        if self.module.version < required && self.unsupported_features.insert(feature) {
+            ctx.error(
+                loc,
+                diagnostic_codes::BYTECODE_VERSION_TOO_LOW,
+                format!(
+                    "use of {} requires bytecode version {} or higher, but version {} is targeted",
+                    feature, required, self.module.version
+                ),
+            )
+        }
     }
 
     /// Obtains or creates an index for a signature, a sequence of types.
@@ -195,11 +338,9 @@ impl ModuleGenerator {
             .iter()
             .map(|ty| self.signature_token(ctx, loc, ty))
             .collect::<Vec<_>>();
-        let idx = FF::SignatureIndex(ctx.checked_bound(
-            loc,
+        let idx = FF::SignatureIndex(table_index(
             self.module.signatures.len(),
             MAX_SIGNATURE_COUNT,
-            "signature",
         ));
         self.module.signatures.push(FF::Signature(tokens));
         self.types_to_signature.insert(tys, idx);
@@ -219,11 +360,20 @@ impl ModuleGenerator {
             Primitive(kind) => match kind {
                 Bool => FF::SignatureToken::Bool,
                 U8 => FF::SignatureToken::U8,
-                U16 => FF::SignatureToken::U16,
-                U32 => FF::SignatureToken::U32,
+                U16 => {
+                    self.require_version(ctx, loc, VERSION_6, "the `u16` type");
+                    FF::SignatureToken::U16
+                },
+                U32 => {
+                    self.require_version(ctx, loc, VERSION_6, "the `u32` type");
+                    FF::SignatureToken::U32
+                },
                 U64 => FF::SignatureToken::U64,
                 U128 => FF::SignatureToken::U128,
-                U256 => FF::SignatureToken::U256,
+                U256 => {
+                    self.require_version(ctx, loc, VERSION_6, "the `u256` type");
+                    FF::SignatureToken::U256
+                },
                 Address => FF::SignatureToken::Address,
                 Signer => FF::SignatureToken::Signer,
                 Num | Range | EventStore => {
@@ -290,11 +440,9 @@ impl ModuleGenerator {
                 );
                 Identifier::new("error").unwrap()
             };
-        let idx = FF::IdentifierIndex(ctx.checked_bound(
-            loc,
+        let idx = FF::IdentifierIndex(table_index(
             self.module.identifiers.len(),
             MAX_IDENTIFIER_COUNT,
-            "identifier",
         ));
         self.module.identifiers.push(ident);
         self.name_to_idx.insert(name, idx);
@@ -302,20 +450,13 @@ impl ModuleGenerator {
     }
 
     /// Obtains or generates an identifier index for the given symbol.
-    pub fn address_index(
-        &mut self,
-        ctx: &ModuleContext,
-        loc: &Loc,
-        addr: AccountAddress,
-    ) -> FF::AddressIdentifierIndex {
+    pub fn address_index(&mut self, addr: AccountAddress) -> FF::AddressIdentifierIndex {
         if let Some(idx) = self.address_to_idx.get(&addr) {
             return *idx;
         }
-        let idx = FF::AddressIdentifierIndex(ctx.checked_bound(
-            loc,
+        let idx = FF::AddressIdentifierIndex(table_index(
             self.module.address_identifiers.len(),
             MAX_ADDRESS_COUNT,
-            "address",
         ));
         self.module.address_identifiers.push(addr);
         self.address_to_idx.insert(addr, idx);
@@ -334,18 +475,16 @@ impl ModuleGenerator {
             return *idx;
         }
         let name = module_env.get_name();
-        let address = self.address_index(ctx, loc, name.addr().expect_numerical());
+        let address = self.address_index(name.addr().expect_numerical());
         let name = self.name_index(ctx, loc, name.name());
         let handle = FF::ModuleHandle { address, name };
         let idx = if module_env.is_script_module() {
             self.script_handle = Some(handle);
             FF::ModuleHandleIndex(TableIndex::MAX)
         } else {
-            let idx = FF::ModuleHandleIndex(ctx.checked_bound(
-                loc,
+            let idx = FF::ModuleHandleIndex(table_index(
                 self.module.module_handles.len(),
                 MAX_MODULE_COUNT,
-                "used module",
             ));
             self.module.module_handles.push(handle);
             idx
@@ -385,11 +524,17 @@ impl ModuleGenerator {
             loc,
             fun_env.get_result_type().flatten().into_iter().collect(),
         );
-        let access_specifiers = fun_env.get_access_specifiers().as_ref().map(|v| {
-            v.iter()
-                .map(|s| self.access_specifier(ctx, fun_env, s))
-                .collect()
-        });
+        // Plain `acquires` clauses are represented by the acquires list of the function
+        // definition, so access specifiers are only needed if other kinds are used.
+        let access_specifiers = fun_env
+            .get_access_specifiers()
+            .filter(|v| !v.iter().all(is_plain_acquires))
+            .map(|v| {
+                self.require_version(ctx, loc, VERSION_7, "access specifiers");
+                v.iter()
+                    .map(|s| self.access_specifier(ctx, fun_env, s))
+                    .collect()
+            });
         let handle = FF::FunctionHandle {
             module,
             name,
@@ -402,11 +547,9 @@ impl ModuleGenerator {
             self.main_handle = Some(handle);
             FF::FunctionHandleIndex(TableIndex::MAX)
         } else {
-            let idx = FF::FunctionHandleIndex(ctx.checked_bound(
-                loc,
+            let idx = FF::FunctionHandleIndex(table_index(
                 self.module.function_handles.len(),
                 MAX_FUNCTION_COUNT,
-                "used function",
             ));
             self.module.function_handles.push(handle);
             idx
@@ -424,7 +567,7 @@ impl ModuleGenerator {
         let resource = match &access_specifier.resource.1 {
             ResourceSpecifier::Any => FF::ResourceSpecifier::Any,
             ResourceSpecifier::DeclaredAtAddress(addr) => FF::ResourceSpecifier::DeclaredAtAddress(
-                self.address_index(ctx, &access_specifier.resource.0, addr.expect_numerical()),
+                self.address_index(addr.expect_numerical()),
             ),
             ResourceSpecifier::DeclaredInModule(module_id) => {
                 FF::ResourceSpecifier::DeclaredInModule(self.module_index(
@@ -449,35 +592,34 @@ impl ModuleGenerator {
                 }
             },
         };
-        let address =
-            match &access_specifier.address.1 {
-                AddressSpecifier::Any => FF::AddressSpecifier::Any,
-                AddressSpecifier::Address(addr) => FF::AddressSpecifier::Literal(
-                    self.address_index(ctx, &access_specifier.address.0, addr.expect_numerical()),
-                ),
-                AddressSpecifier::Parameter(name) => {
-                    let param_index = fun_env
-                        .get_parameters()
-                        .iter()
-                        .position(|Parameter(n, _)| n == name)
-                        .expect("parameter defined") as u8;
-                    FF::AddressSpecifier::Parameter(param_index, None)
-                },
-                AddressSpecifier::Call(fun, name) => {
-                    let param_index = fun_env
-                        .get_parameters()
-                        .iter()
-                        .position(|Parameter(n, _)| n == name)
-                        .expect("parameter defined") as u8;
-                    let fun_index = self.function_instantiation_index(
-                        ctx,
-                        &access_specifier.address.0,
-                        &ctx.env.get_function(fun.to_qualified_id()),
-                        fun.inst.clone(),
-                    );
-                    FF::AddressSpecifier::Parameter(param_index, Some(fun_index))
-                },
-            };
+        let address = match &access_specifier.address.1 {
+            AddressSpecifier::Any => FF::AddressSpecifier::Any,
+            AddressSpecifier::Address(addr) => {
+                FF::AddressSpecifier::Literal(self.address_index(addr.expect_numerical()))
+            },
+            AddressSpecifier::Parameter(name) => {
+                let param_index = fun_env
+                    .get_parameters()
+                    .iter()
+                    .position(|Parameter(n, _)| n == name)
+                    .expect("parameter defined") as u8;
+                FF::AddressSpecifier::Parameter(param_index, None)
+            },
+            AddressSpecifier::Call(fun, name) => {
+                let param_index = fun_env
+                    .get_parameters()
+                    .iter()
+                    .position(|Parameter(n, _)| n == name)
+                    .expect("parameter defined") as u8;
+                let fun_index = self.function_instantiation_index(
+                    ctx,
+                    &access_specifier.address.0,
+                    &ctx.env.get_function(fun.to_qualified_id()),
+                    fun.inst.clone(),
+                );
+                FF::AddressSpecifier::Parameter(param_index, Some(fun_index))
+            },
+        };
         FF::AccessSpecifier {
             kind: access_specifier.kind,
             negated: access_specifier.negated,
@@ -503,11 +645,9 @@ impl ModuleGenerator {
             handle,
             type_parameters,
         };
-        let idx = FF::FunctionInstantiationIndex(ctx.checked_bound(
-            loc,
+        let idx = FF::FunctionInstantiationIndex(table_index(
             self.module.function_instantiations.len(),
             MAX_FUNCTION_INST_COUNT,
-            "function instantiation",
         ));
         self.module.function_instantiations.push(fun_inst);
         self.fun_inst_to_idx.insert(cache_key, idx);
@@ -547,11 +687,9 @@ impl ModuleGenerator {
                 )
                 .collect(),
         };
-        let idx = FF::StructHandleIndex(ctx.checked_bound(
-            loc,
+        let idx = FF::StructHandleIndex(table_index(
             self.module.struct_handles.len(),
             MAX_STRUCT_COUNT,
-            "used structs",
         ));
         self.module.struct_handles.push(handle);
         self.struct_to_idx
@@ -594,11 +732,9 @@ impl ModuleGenerator {
             def,
             type_parameters,
         };
-        let idx = FF::StructDefInstantiationIndex(ctx.checked_bound(
-            loc,
+        let idx = FF::StructDefInstantiationIndex(table_index(
             self.module.struct_def_instantiations.len(),
             MAX_STRUCT_DEF_INST_COUNT,
-            "struct instantiation",
         ));
         self.module.struct_def_instantiations.push(struct_inst);
         self.struct_def_inst_to_idx.insert(cache_key, idx);
@@ -619,11 +755,9 @@ impl ModuleGenerator {
         if let Some(idx) = self.field_to_idx.get(&key) {
             return *idx;
         }
-        let field_idx = FF::FieldHandleIndex(ctx.checked_bound(
-            loc,
+        let field_idx = FF::FieldHandleIndex(table_index(
             self.module.field_handles.len(),
             MAX_FIELD_COUNT,
-            "field",
         ));
         let owner = self.struct_def_index(ctx, loc, &field_env.struct_env);
         self.module.field_handles.push(FF::FieldHandle {
@@ -651,11 +785,9 @@ impl ModuleGenerator {
         if let Some(idx) = self.field_inst_to_idx.get(&key) {
             return *idx;
         }
-        let field_inst_idx = FF::FieldInstantiationIndex(ctx.checked_bound(
-            loc,
+        let field_inst_idx = FF::FieldInstantiationIndex(table_index(
             self.module.field_instantiations.len(),
             MAX_FIELD_INST_COUNT,
-            "field instantiation",
         ));
         let handle = self.field_index(ctx, loc, field_env);
         self.module
@@ -668,7 +800,10 @@ impl ModuleGenerator {
         field_inst_idx
     }
 
-    /// Obtains or generates a constant index.
+    /// Obtains or generates a constant index. Constants are identified by their type and
+    /// serialized value, so equal values share an entry even if written differently, like
+    /// `b"a"` and `vector[97u8]`. A constant whose serialized value exceeds the maximal size,
+    /// see `Options::max_constant_size`, is reported at the given location.
     pub fn constant_index(
         &mut self,
         ctx: &ModuleContext,
@@ -676,56 +811,61 @@ impl ModuleGenerator {
         cons: &Constant,
         ty: &Type,
     ) -> FF::ConstantPoolIndex {
-        if let Some(idx) = self.cons_to_idx.get(cons) {
-            return *idx;
-        }
         let data = cons
             .to_move_value()
             .simple_serialize()
             .expect("serialization succeeds");
-        let ff_cons = FF::Constant {
-            type_: self.signature_token(ctx, loc, ty),
-            data,
-        };
-        let idx = FF::ConstantPoolIndex(ctx.checked_bound(
-            loc,
+        let key = (self.signature_token(ctx, loc, ty), data);
+        if let Some(idx) = self.cons_to_idx.get(&key) {
+            return *idx;
+        }
+        if key.1.len() > self.max_constant_size {
+            ctx.error(
+                loc,
+                diagnostic_codes::CONSTANT_TOO_LARGE,
+                format!(
+                    "constant of {} bytes exceeds the maximal size of {} bytes",
+                    key.1.len(),
+                    self.max_constant_size
+                ),
+            )
+        }
+        let idx = FF::ConstantPoolIndex(table_index(
             self.module.constant_pool.len(),
             MAX_CONST_COUNT,
-            "constant",
         ));
-        self.module.constant_pool.push(ff_cons);
-        self.cons_to_idx.insert(cons.clone(), idx);
+        self.module.constant_pool.push(FF::Constant {
+            type_: key.0.clone(),
+            data: key.1.clone(),
+        });
+        self.cons_to_idx.insert(key, idx);
         idx
     }
 }
 
 impl<'env> ModuleContext<'env> {
-    /// Emits an error at the location.
-    pub fn error(&self, loc: impl AsRef<Loc>, msg: impl AsRef<str>) {
-        self.env.diag(Severity::Error, loc.as_ref(), msg.as_ref())
+    /// Returns the target of the function from which code is generated: the optimized
+    /// variant if there is one, and the baseline otherwise.
+    pub fn code_target<'a>(&'a self, fun_env: &'a FunctionEnv<'a>) -> FunctionTarget<'a> {
+        if self
+            .targets
+            .has_target(fun_env, &FunctionVariant::Optimized)
+        {
+            self.targets
+                .get_target(fun_env, &FunctionVariant::Optimized)
+        } else {
+            self.targets.get_target(fun_env, &FunctionVariant::Baseline)
+        }
     }
 
-    /// Emits an internal error at the location.
-    pub fn internal_error(&self, loc: impl AsRef<Loc>, msg: impl AsRef<str>) {
-        self.env.diag(Severity::Bug, loc.as_ref(), msg.as_ref())
+    /// Emits an error with the given code at the location.
+    pub fn error(&self, loc: impl AsRef<Loc>, code: DiagnosticCode, msg: impl AsRef<str>) {
+        code.error(self.env, loc.as_ref(), msg.as_ref())
     }
 
-    /// Check for a bound table index and report an error if its out of bound. All bounds
-    /// should be handled by this generator in a graceful and giving the user detail
-    /// information of the location.
-    pub fn checked_bound(
-        &self,
-        loc: impl AsRef<Loc>,
-        value: usize,
-        max: usize,
-        msg: &str,
-    ) -> FF::TableIndex {
-        if value >= max {
-            self.error(loc, format!("exceeded maximal {} count: {}", msg, max));
-            0
-        } else {
-            value as FF::TableIndex
-        }
+    /// Emits an internal error at the location.
+    pub fn internal_error(&self, loc: impl AsRef<Loc>, msg: impl AsRef<str>) {
+        diagnostic_codes::FILE_FORMAT_GEN_INTERNAL.bug(self.env, loc.as_ref(), msg.as_ref())
     }
 
     /// Get the file format opcode for a well-known function. This applies currently to a set
@@ -765,66 +905,32 @@ impl<'env> ModuleContext<'env> {
         }
     }
 
+    /// Reports an internal error if an update of the source map failed.
+    pub fn check_source_map(&self, loc: impl AsRef<Loc>, result: anyhow::Result<()>) {
+        if let Err(err) = result {
+            self.internal_error(loc, format!("inconsistent source map: {}", err))
+        }
+    }
+
+    /// Makes a source map name from a symbol and a location.
+    pub fn source_name(&self, name: Symbol, loc: &Loc) -> SourceName {
+        (self.symbol_to_str(name), self.env.to_ir_loc(loc))
+    }
+
     /// Convert the symbol into a string.
     pub fn symbol_to_str(&self, s: Symbol) -> String {
         s.display(self.env.symbol_pool()).to_string()
     }
 }
 
-impl<'env> ModuleContext<'env> {
-    /// Acquires analysis. This is temporary until we have the full reference analysis.
-    fn generate_acquires_map(&self, module: &ModuleEnv) -> BTreeMap<FunId, BTreeSet<StructId>> {
-        // Compute map with direct usage of resources
-        let mut usage_map = module
-            .get_functions()
-            .map(|f| (f.get_id(), self.get_direct_function_acquires(&f)))
-            .collect::<BTreeMap<_, _>>();
-        // Now run a fixed-point loop: add resources used by called functions until there are no
-        // changes.
-        loop {
-            let mut changes = false;
-            for fun in module.get_functions() {
-                if let Some(callees) = fun.get_called_functions() {
-                    let mut usage = usage_map[&fun.get_id()].clone();
-                    let count = usage.len();
-                    // Extend usage by that of callees from the same module. Acquires is only
-                    // local to a module.
-                    for callee in callees {
-                        if callee.module_id == module.get_id() {
-                            usage.extend(usage_map[&callee.id].iter().cloned());
-                        }
-                    }
-                    if usage.len() > count {
-                        *usage_map.get_mut(&fun.get_id()).unwrap() = usage;
-                        changes = true;
-                    }
-                }
-            }
-            if !changes {
-                break;
-            }
-        }
-        usage_map
-    }
-
-    fn get_direct_function_acquires(&self, fun: &FunctionEnv) -> BTreeSet<StructId> {
-        let mut result = BTreeSet::new();
-        let target = self.targets.get_target(fun, &FunctionVariant::Baseline);
-        for bc in target.get_bytecode() {
-            use Bytecode::*;
-            use Operation::*;
-            match bc {
-                Call(_, _, MoveFrom(mid, sid, ..), ..)
-                | Call(_, _, BorrowGlobal(mid, sid, ..), ..)
-                // GetGlobal from spec language, but cover it anyway.
-                | Call(_, _, GetGlobal(mid, sid, ..), ..)
-                if *mid == fun.module_env.get_id() =>
-                    {
-                        result.insert(*sid);
-                    },
-                _ => {},
-            }
-        }
-        result
-    }
+/// Returns true if the access specifier is a plain `acquires R` clause, as supported by
+/// bytecode versions without access control.
+fn is_plain_acquires(access_specifier: &AccessSpecifier) -> bool {
+    access_specifier.kind == FF::AccessKind::Acquires
+        && !access_specifier.negated
+        && matches!(access_specifier.address.1, AddressSpecifier::Any)
+        && matches!(
+            &access_specifier.resource.1,
+            ResourceSpecifier::Resource(struct_id) if struct_id.inst.is_empty()
+        )
 }
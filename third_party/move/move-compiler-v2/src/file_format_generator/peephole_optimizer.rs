@@ -0,0 +1,103 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A peephole optimizer over the generated code of a function, enabled by the experiment
+//! `peephole-optimization` or optimization level 2. It rewrites pairs of adjacent instructions, and single
+//! instructions, by the following rules, until none applies anymore:
+//!
+//! - a load of a constant or a copy of a local which is popped right away is removed;
+//! - a conditional branch on a constant is replaced by an unconditional branch if taken, and
+//!   removed otherwise;
+//! - a negation followed by a negation is removed, and a negation followed by a conditional
+//!   branch is replaced by the branch with the opposite condition;
+//! - an unconditional branch to the next instruction is removed, and a conditional branch to
+//!   the next instruction is replaced by popping the condition.
+//!
+//! A pair is only rewritten if the second instruction is not the target of a branch, so the
+//! pair is always executed as a whole. Branch targets are fixed up after each round.
+
+use move_binary_format::file_format::{Bytecode, CodeOffset};
+use std::collections::BTreeSet;
+
+/// Optimizes the code. Returns, for each offset of the original code, the offset of the
+/// instruction in the optimized code which it is mapped to. An instruction which is removed
+/// is mapped to the instruction which follows it.
+pub fn optimize(code: &mut Vec<Bytecode>) -> Vec<CodeOffset> {
+    let mut mapping = (0..=code.len() as CodeOffset).collect::<Vec<_>>();
+    while let Some(round_mapping) = optimize_round(code) {
+        for offset in mapping.iter_mut() {
+            *offset = round_mapping[*offset as usize]
+        }
+    }
+    mapping.pop();
+    mapping
+}
+
+/// Runs one round of rewriting over the code. Returns the mapping from old to new offsets,
+/// which includes the offset past the end of the code, or `None` if nothing was rewritten.
+fn optimize_round(code: &mut Vec<Bytecode>) -> Option<Vec<CodeOffset>> {
+    let targets = code
+        .iter()
+        .filter_map(|bc| bc.offset().copied())
+        .collect::<BTreeSet<_>>();
+    let mut new_code = vec![];
+    let mut mapping = vec![0; code.len() + 1];
+    let mut changed = false;
+    let mut offset = 0;
+    while offset < code.len() {
+        mapping[offset] = new_code.len() as CodeOffset;
+        let next = offset + 1;
+        if next < code.len() && !targets.contains(&(next as CodeOffset)) {
+            if let Some(replacement) = rewrite_pair(&code[offset], &code[next]) {
+                mapping[next] = new_code.len() as CodeOffset;
+                new_code.extend(replacement);
+                changed = true;
+                offset += 2;
+                continue;
+            }
+        }
+        match &code[offset] {
+            Bytecode::Branch(target) if *target as usize == next => {
+                changed = true;
+            },
+            Bytecode::BrTrue(target) | Bytecode::BrFalse(target) if *target as usize == next => {
+                new_code.push(Bytecode::Pop);
+                changed = true;
+            },
+            bc => new_code.push(bc.clone()),
+        }
+        offset += 1;
+    }
+    if !changed {
+        return None;
+    }
+    mapping[code.len()] = new_code.len() as CodeOffset;
+    for bc in new_code.iter_mut() {
+        match bc {
+            Bytecode::Branch(target) | Bytecode::BrTrue(target) | Bytecode::BrFalse(target) => {
+                *target = mapping[*target as usize]
+            },
+            _ => {},
+        }
+    }
+    *code = new_code;
+    Some(mapping)
+}
+
+/// Returns the replacement of a pair of instructions, if a rule applies.
+fn rewrite_pair(first: &Bytecode, second: &Bytecode) -> Option<Vec<Bytecode>> {
+    use Bytecode::*;
+    match (first, second) {
+        (
+            LdU8(_) | LdU16(_) | LdU32(_) | LdU64(_) | LdU128(_) | LdU256(_) | LdConst(_) | LdTrue
+            | LdFalse | CopyLoc(_),
+            Pop,
+        ) => Some(vec![]),
+        (LdTrue, BrTrue(target)) | (LdFalse, BrFalse(target)) => Some(vec![Branch(*target)]),
+        (LdTrue, BrFalse(_)) | (LdFalse, BrTrue(_)) => Some(vec![]),
+        (Not, Not) => Some(vec![]),
+        (Not, BrTrue(target)) => Some(vec![BrFalse(*target)]),
+        (Not, BrFalse(target)) => Some(vec![BrTrue(*target)]),
+        _ => None,
+    }
+}
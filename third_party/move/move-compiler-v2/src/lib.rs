@@ -2,201 +2,1432 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod abi;
+pub mod analysis;
+pub mod artifacts;
+pub mod build_config;
+mod bytecode_diff;
 mod bytecode_generator;
+pub mod bytecode_text;
+pub mod compilation_cache;
+pub mod compilation_stats;
+pub mod compiler_comparison;
+pub mod coverage_map;
+mod dead_functions;
+mod dependency_addresses;
+mod dependency_cache;
+pub mod dependency_graph;
+pub mod diagnostic_codes;
+pub mod diagnostics;
+pub mod disassembly;
+pub mod error_map;
 mod experiments;
+mod extra_attributes;
 mod file_format_generator;
+pub mod lint;
+pub mod model_dump;
+mod module_filter;
 mod options;
+pub mod package_metadata;
+mod partial_compilation;
 pub mod pipeline;
+pub mod progress;
+pub mod publish_bundle;
+pub mod recheck;
+mod recursion_checker;
+pub mod script_checker;
+mod shadowing_checker;
+mod source_paths;
+pub mod source_positions;
+pub mod spec_info;
+pub mod stack_depth;
+mod trace;
+pub mod unit_test;
+mod unused_dependencies;
+mod unused_items;
 
-use crate::pipeline::{
-    livevar_analysis_processor::LiveVarAnalysisProcessor, visibility_checker::VisibilityChecker,
+use crate::{
+    artifacts::CompiledArtifacts,
+    build_config::BuildConfigFilter,
+    compilation_cache::CompilationCache,
+    compilation_stats::{CompilationStats, ProfiledProcessor},
+    coverage_map::ModuleCodeMap,
+    dependency_addresses::DependencyAddresses,
+    dependency_cache::DependencyCache,
+    diagnostics::{CompilerDiagnostic, DiagnosticSeverity},
+    pipeline::{
+        ability_checker::AbilityChecker, acquires_inference::AcquiresInference,
+        annotation_formatters, cfg_simplifier::CfgSimplifier, constant_folding::ConstantFolder,
+        copy_propagation::CopyPropagation, dead_store_elimination::DeadStoreElimination,
+        expensive_copy_checker::ExpensiveCopyChecker, explicit_drop::ExplicitDrop,
+        function_inlining::FunctionInliner, livevar_analysis_processor::LiveVarAnalysisProcessor,
+        must_use_checker::MustUseChecker, reference_safety_processor::ReferenceSafetyProcessor,
+        unreachable_code_remover::UnreachableCodeRemover, unused_vars_checker::UnusedVarsChecker,
+        visibility_checker::VisibilityChecker, VariantProcessor,
+    },
+    progress::{Phase, ProgressEvent, ReportedProcessor},
+    source_paths::SourcePaths,
+    trace::{TracedPhase, TracedProcessor},
+};
+use anyhow::{anyhow, bail};
+use codespan_reporting::{
+    diagnostic::Severity,
+    term::termcolor::{Ansi, ColorChoice, NoColor, StandardStream, WriteColor},
 };
-use anyhow::bail;
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream, WriteColor};
 pub use experiments::*;
+use log::info;
+use move_binary_format::{
+    access::ModuleAccess,
+    deserializer::DeserializerConfig,
+    file_format::{CompiledModule, FunctionDefinitionIndex},
+    file_format_common::{IDENTIFIER_SIZE_MAX, VERSION_MAX, VERSION_MIN},
+    IndexKind,
+};
+use move_command_line_common::{
+    address::NumericalAddress,
+    files::{extension_equals, find_filenames, MOVE_COMPILED_EXTENSION},
+    parser::NumberFormat,
+};
 use move_compiler::{
     compiled_unit::{
         AnnotatedCompiledModule, AnnotatedCompiledScript, AnnotatedCompiledUnit, CompiledUnit,
         FunctionInfo,
     },
     diagnostics::FilesSourceText,
-    shared::{known_attributes::KnownAttribute, unique_map::UniqueMap},
+    interface_generator,
+    parser::ast::FunctionName,
+    shared::{
+        known_attributes::{AttributeKind, KnownAttribute, LintAttribute, TestingAttribute},
+        unique_map::UniqueMap,
+    },
+};
+use move_core_types::language_storage::ModuleId;
+use move_ir_types::location::Spanned;
+use move_model::{
+    ast::{FriendDecl, ModuleName},
+    model::{FunId, FunctionEnv, GlobalEnv, ModuleEnv, QualifiedId},
+    PackageInfo,
 };
-use move_model::{model::GlobalEnv, PackageInfo};
 use move_stackless_bytecode::function_target_pipeline::{
-    FunctionTargetPipeline, FunctionTargetsHolder, FunctionVariant,
+    FunctionTargetPipeline, FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant,
 };
 use move_symbol_pool::Symbol;
 pub use options::*;
-use std::{collections::BTreeSet, path::Path};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Instant,
+};
 
-/// Run Move compiler and print errors to stderr.
+/// Run Move compiler and print errors to stderr, colored as set by `Options::color`.
 pub fn run_move_compiler_to_stderr(
     options: Options,
 ) -> anyhow::Result<(GlobalEnv, Vec<AnnotatedCompiledUnit>)> {
-    let mut error_writer = StandardStream::stderr(ColorChoice::Auto);
+    let mut error_writer = StandardStream::stderr(options.color_choice(io::stderr().is_terminal()));
     run_move_compiler(&mut error_writer, options)
 }
 
-/// Run move compiler and print errors to given writer.
+/// Run Move compiler and print errors to the given writer, which is not a terminal, colored
+/// with ANSI escape codes if `Options::color` is `always`.
+pub fn run_move_compiler_to_writer(
+    writer: impl io::Write,
+    options: Options,
+) -> anyhow::Result<(GlobalEnv, Vec<AnnotatedCompiledUnit>)> {
+    match options.color_choice(false) {
+        ColorChoice::Never => run_move_compiler(&mut NoColor::new(writer), options),
+        _ => run_move_compiler(&mut Ansi::new(writer), options),
+    }
+}
+
+/// Run move compiler and print errors to given writer. What is compiled, and what is produced,
+/// is controlled by the `Options`. Use `run_move_compiler_with_targets` to also obtain the
+/// stackless bytecode of the compiled functions.
 pub fn run_move_compiler(
     error_writer: &mut impl WriteColor,
     options: Options,
 ) -> anyhow::Result<(GlobalEnv, Vec<AnnotatedCompiledUnit>)> {
+    let CompilerOutput {
+        env,
+        annotated_units,
+        ..
+    } = run_move_compiler_with_targets(error_writer, options)?;
+    Ok((env, annotated_units))
+}
+
+/// The result of running the compiler.
+pub struct CompilerOutput {
+    /// The global environment, including the diagnostics of the compilation.
+    pub env: GlobalEnv,
+    /// The stackless bytecode of the functions, with the annotations of the processors, in the
+    /// state after the bytecode pipeline ran, from which the units were generated.
+    pub targets: FunctionTargetsHolder,
+    /// The compiled modules and scripts.
+    pub annotated_units: Vec<AnnotatedCompiledUnit>,
+    /// The maps from code offsets to source locations of the compiled modules, for computing
+    /// source coverage.
+    pub coverage_maps: Vec<ModuleCodeMap>,
+    /// The diagnostics of the compilation, like warnings, if collected by
+    /// `run_move_compiler_collecting`. Empty if diagnostics are written to a writer.
+    pub diagnostics: Vec<CompilerDiagnostic>,
+}
+
+impl CompilerOutput {
+    /// Creates an output without code, for runs which do not generate code.
+    fn without_code(env: GlobalEnv) -> Self {
+        Self {
+            env,
+            targets: FunctionTargetsHolder::default(),
+            annotated_units: vec![],
+            coverage_maps: vec![],
+            diagnostics: vec![],
+        }
+    }
+}
+
+/// Like `run_move_compiler`, but also returns the stackless bytecode of the functions, for
+/// tools which analyze it. The targets are empty if no code is generated.
+pub fn run_move_compiler_with_targets(
+    error_writer: &mut impl WriteColor,
+    options: Options,
+) -> anyhow::Result<CompilerOutput> {
+    run_compiler(&mut Reporter::writing(error_writer), options)
+}
+
+/// Like `run_move_compiler_with_targets`, but collects the diagnostics of the compilation
+/// instead of rendering them to a writer, for tools which process diagnostics, like language
+/// servers. On success, the output carries the diagnostics, like warnings. On failure, the
+/// diagnostics are returned, including one without location for a failure which is not
+/// reported as a diagnostic, like an i/o error. Output which is not a diagnostic, like the
+/// listing of experiments or the statistics of the compilation, is dropped.
+pub fn run_move_compiler_collecting(
+    options: Options,
+) -> Result<CompilerOutput, Vec<CompilerDiagnostic>> {
+    let mut output = NoColor::new(io::sink());
+    let mut reporter = Reporter {
+        output: &mut output,
+        collected: Some(vec![]),
+        shown: 0,
+        tolerated_errors: 0,
+    };
+    let result = run_compiler(&mut reporter, options);
+    let mut diags = reporter.collected.unwrap_or_default();
+    match result {
+        Ok(output) => Ok(CompilerOutput {
+            diagnostics: diags,
+            ..output
+        }),
+        Err(err) => {
+            if !diags
+                .iter()
+                .any(|d| d.severity >= DiagnosticSeverity::Error)
+            {
+                diags.push(CompilerDiagnostic::error(err.to_string()))
+            }
+            Err(diags)
+        },
+    }
+}
+
+/// Compiles the sources like `run_move_compiler`, and returns the compiled modules and scripts
+/// serialized for the targeted bytecode version, with their source maps, and the diagnostics
+/// of the compilation, like warnings. On failure, the error carries the diagnostics rendered
+/// as text, so callers without a terminal can still show them.
+pub fn compile_to_bytes(options: Options) -> anyhow::Result<CompiledArtifacts> {
+    let version = options.target_bytecode_version();
+    let mut output = NoColor::new(vec![]);
+    let mut reporter = Reporter {
+        output: &mut output,
+        collected: Some(vec![]),
+        shown: 0,
+        tolerated_errors: 0,
+    };
+    let result = run_compiler(&mut reporter, options);
+    let diagnostics = reporter.collected.take().unwrap_or_default();
+    match result {
+        Ok(compiled) => CompiledArtifacts::new(&compiled.annotated_units, version, diagnostics),
+        Err(err) => {
+            let rendered = String::from_utf8_lossy(output.get_ref()).to_string();
+            bail!("{}\n{}", err, rendered.trim_end())
+        },
+    }
+}
+
+/// Where the diagnostics and the other output of a compilation, like listings and reports, go.
+/// Diagnostics are either collected, and then also rendered to the output as text, or rendered
+/// to the output, in the format selected by the options. Rendered diagnostics are counted, so
+/// `Options::max_diagnostics` limits the number of diagnostics shown over all phases of the
+/// compilation.
+struct Reporter<'w> {
+    output: &'w mut dyn WriteColor,
+    collected: Option<Vec<CompilerDiagnostic>>,
+    shown: usize,
+    /// The number of errors reported which do not fail the compilation, namely the errors of
+    /// modules excluded by partial compilation.
+    tolerated_errors: usize,
+}
+
+impl<'w> Reporter<'w> {
+    fn writing(output: &'w mut dyn WriteColor) -> Self {
+        Self {
+            output,
+            collected: None,
+            shown: 0,
+            tolerated_errors: 0,
+        }
+    }
+
+    /// Reports the diagnostics in the env which are at least of the given severity and have
+    /// not been reported yet. Only errors are rendered at `Options::verbosity` 0.
+    fn report_diags(&mut self, env: &GlobalEnv, options: &Options, severity: Severity) {
+        match &mut self.collected {
+            Some(diags) => {
+                // Collected diagnostics are also rendered as text, so they can be shown if the
+                // compilation fails, see `compile_to_bytes`.
+                for diag in env.get_unreported_diags(|d| d.severity >= severity) {
+                    env.emit_diag(&mut self.output, &diag)
+                }
+                diags.extend(diagnostics::collect_diags(env, severity))
+            },
+            None => {
+                let severity = if options.verbosity == 0 && severity < Severity::Error {
+                    Severity::Error
+                } else {
+                    severity
+                };
+                let limit = options
+                    .max_diagnostics()
+                    .map(|max| max.saturating_sub(self.shown));
+                self.shown +=
+                    diagnostics::report_diags(env, &mut self.output, options, severity, limit)
+            },
+        }
+    }
+
+    /// Writes a line of the progress of the compilation if `Options::verbosity` is at least
+    /// 2. The line is also logged.
+    fn progress(&mut self, options: &Options, line: &str) -> io::Result<()> {
+        info!("{}", line);
+        if options.verbosity >= 2 {
+            writeln!(self.output, "{}", line)?
+        }
+        Ok(())
+    }
+
+    /// Writes output requested by the options, like statistics, unless `Options::verbosity`
+    /// is 0.
+    fn requested(&mut self, options: &Options, text: &str) -> io::Result<()> {
+        if options.verbosity > 0 {
+            self.output.write_all(text.as_bytes())?
+        }
+        Ok(())
+    }
+}
+
+/// Runs the compiler, reporting diagnostics and other output to the reporter. If
+/// `Options::trace_filter` is set, the compilation is traced to the subscriber in effect, see
+/// `trace`.
+fn run_compiler(reporter: &mut Reporter, options: Options) -> anyhow::Result<CompilerOutput> {
+    let trace_filter = options.trace_filter.clone();
+    trace::with_trace_filter(trace_filter.as_deref(), || {
+        run_traced_compiler(reporter, options)
+    })
+}
+
+fn run_traced_compiler(
+    reporter: &mut Reporter,
+    options: Options,
+) -> anyhow::Result<CompilerOutput> {
+    if options.list_experiments {
+        reporter
+            .output
+            .write_all(Experiment::listing().as_bytes())?;
+        return Ok(CompilerOutput::without_code(GlobalEnv::new()));
+    }
+    if let Some(code) = &options.explain {
+        let Some(code) = diagnostic_codes::find(code) else {
+            bail!("unknown diagnostic code `{}`", code)
+        };
+        reporter
+            .output
+            .write_all(code.render_explanation().as_bytes())?;
+        return Ok(CompilerOutput::without_code(GlobalEnv::new()));
+    }
+    if options.check_only {
+        let env = run_checker_and_report(reporter, options)?;
+        return Ok(CompilerOutput::without_code(env));
+    }
     // Run context check.
+    let start = Instant::now();
+    let phase = TracedPhase::enter(compilation_stats::CHECKING_PHASE, None);
+    reporter.progress(&options, &phase_line(compilation_stats::CHECKING_PHASE))?;
     let env = run_checker(options.clone())?;
-    check_errors(&env, error_writer, "checking errors")?;
+    phase.exit(&env);
+    let stats = install_stats(&env, start);
+    report_and_check_errors(&env, reporter, "checking errors")?;
+    reporter.progress(&options, &module_counts(&env))?;
+    dump_model(&env, reporter, &options)?;
+    emit_dependency_graph(&env, reporter, &options)?;
+    // The error map is computed from the stackless bytecode, which is not cached.
+    if let Some(dir) = options
+        .compilation_cache_dir
+        .as_ref()
+        .filter(|_| options.emit_error_map.is_none())
+    {
+        env.set_extension(CompilationCache::load(&env, dir))
+    }
     // Run code generator
+    let start = Instant::now();
+    let phase = TracedPhase::enter(compilation_stats::BYTECODE_GENERATION_PHASE, Some(&env));
+    reporter.progress(
+        &options,
+        &phase_line(compilation_stats::BYTECODE_GENERATION_PHASE),
+    )?;
+    options
+        .progress
+        .report(ProgressEvent::PhaseStarted(Phase::BytecodeGeneration));
     let mut targets = run_bytecode_gen(&env);
-    check_errors(&env, error_writer, "code generation errors")?;
+    unused_items::check_unused_items(&env);
+    unused_dependencies::check_unused_dependencies(&env, &options);
+    phase.exit(&env);
+    stats.record_phase(compilation_stats::BYTECODE_GENERATION_PHASE, start);
+    if options.partial_compilation
+        && env.has_errors()
+        && partial_compilation::exclude_failed_modules(&env, &targets)
+    {
+        // The modules with errors are not assembled, so their errors do not fail the
+        // compilation of the other modules.
+        reporter.tolerated_errors = env.error_count();
+    }
+    report_and_check_errors(&env, reporter, "code generation errors")?;
     // Run transformation pipeline
+    let start = Instant::now();
+    let phase = TracedPhase::enter(compilation_stats::BYTECODE_PIPELINE_PHASE, Some(&env));
+    reporter.progress(
+        &options,
+        &phase_line(compilation_stats::BYTECODE_PIPELINE_PHASE),
+    )?;
+    options
+        .progress
+        .report(ProgressEvent::PhaseStarted(Phase::BytecodePipeline));
     let pipeline = bytecode_pipeline(&env);
-    if options.dump_bytecode {
-        // Dump bytecode to files, using a basename for the individual sources derived
-        // from the first input file.
-        let dump_base_name = options
-            .sources
-            .get(0)
-            .and_then(|f| {
-                Path::new(f)
-                    .file_name()
-                    .map(|f| f.to_string_lossy().as_ref().to_owned())
-            })
-            .unwrap_or_else(|| "dump".to_owned());
-        pipeline.run_with_dump(&env, &mut targets, &dump_base_name, false)
+    if options.dump_bytecode
+        || options.dump_bytecode_dir.is_some()
+        || options.dump_bytecode_diff
+        || options.dump_bytecode_diff_annotations
+    {
+        let dump_dir = options
+            .dump_bytecode_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        for path in run_pipeline_with_dump(&env, &pipeline, &mut targets, &dump_dir)? {
+            reporter.progress(
+                &options,
+                &format!("dumped bytecode to `{}`", path.display()),
+            )?
+        }
     } else {
-        pipeline.run(&env, &mut targets)
+        let processor_start = Cell::new(start);
+        pipeline.run_with_hook(
+            &env,
+            &mut targets,
+            |_| processor_start.set(Instant::now()),
+            |_, processor, _| {
+                stats.record_processor(&processor.name(), processor_start.get());
+                processor_start.set(Instant::now())
+            },
+        )
     }
-    check_errors(&env, error_writer, "stackless-bytecode analysis errors")?;
+    phase.exit(&env);
+    stats.record_phase(compilation_stats::BYTECODE_PIPELINE_PHASE, start);
+    report_and_check_errors(&env, reporter, "stackless-bytecode analysis errors")?;
+    let start = Instant::now();
+    let phase = TracedPhase::enter(compilation_stats::FILE_FORMAT_GENERATION_PHASE, Some(&env));
+    reporter.progress(
+        &options,
+        &phase_line(compilation_stats::FILE_FORMAT_GENERATION_PHASE),
+    )?;
+    options
+        .progress
+        .report(ProgressEvent::PhaseStarted(Phase::FileFormatGeneration));
     let modules_and_scripts = run_file_format_gen(&env, &targets);
-    check_errors(&env, error_writer, "assembling errors")?;
-    let annotated = annotate_units(&env, modules_and_scripts);
-    Ok((env, annotated))
+    stack_depth::check_frames(&env, &modules_and_scripts);
+    phase.exit(&env);
+    stats.record_phase(compilation_stats::FILE_FORMAT_GENERATION_PHASE, start);
+    report_and_check_errors(&env, reporter, "assembling errors")?;
+    reporter.progress(
+        &options,
+        &format!("generated {} unit(s)", modules_and_scripts.len()),
+    )?;
+    if !options.experiment_on(Experiment::SKIP_BYTECODE_VERIFIER) {
+        let start = Instant::now();
+        let phase = TracedPhase::enter(compilation_stats::BYTECODE_VERIFICATION_PHASE, Some(&env));
+        reporter.progress(
+            &options,
+            &phase_line(compilation_stats::BYTECODE_VERIFICATION_PHASE),
+        )?;
+        options
+            .progress
+            .report(ProgressEvent::PhaseStarted(Phase::BytecodeVerification));
+        run_bytecode_verifier(&env, &modules_and_scripts);
+        phase.exit(&env);
+        stats.record_phase(compilation_stats::BYTECODE_VERIFICATION_PHASE, start);
+        report_and_check_errors(&env, reporter, "bytecode verification errors")?;
+    }
+    stats.record_units(&modules_and_scripts);
+    if options.dump_disassembly {
+        let dump_dir = options
+            .dump_bytecode_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        for path in disassembly::write_disassembly(&env, &modules_and_scripts, &dump_dir)? {
+            reporter.progress(
+                &options,
+                &format!("dumped disassembly to `{}`", path.display()),
+            )?
+        }
+    }
+    if let Some(dir) = &options.emit_abi {
+        for path in abi::write_abis(&env, dir)? {
+            reporter.progress(&options, &format!("wrote ABI to `{}`", path.display()))?
+        }
+    }
+    if let Some(path) = &options.emit_error_map {
+        error_map::write_error_map(&env, &targets, path)?;
+        reporter.progress(
+            &options,
+            &format!("wrote error map to `{}`", path.display()),
+        )?
+    }
+    let coverage_maps = coverage_map::module_code_maps(&modules_and_scripts);
+    if let Some(dir) = &options.emit_coverage_map {
+        for path in coverage_map::write_code_maps(&coverage_maps, dir)? {
+            reporter.progress(
+                &options,
+                &format!("wrote coverage map to `{}`", path.display()),
+            )?
+        }
+    }
+    if let Some(cache) = env.get_extension::<CompilationCache>() {
+        cache.store(&env, &modules_and_scripts)?
+    }
+    if let Some(level) = options.compare_with_v1 {
+        let v1_units = compiler_comparison::compile_with_v1(&mut reporter.output, &options)?;
+        let report = compiler_comparison::compare_units(&v1_units, &modules_and_scripts, level);
+        reporter.requested(&options, &report.render())?;
+        if report.has_abi_differences() {
+            bail!("compiler v1 and v2 generate units with different ABIs")
+        }
+    }
+    if options.print_stats {
+        reporter.requested(&options, &stats.render())?
+    }
+    if options.profile_compilation {
+        let profile = stats.profile(options.profile_top);
+        let text = match options.diagnostics_format {
+            DiagnosticsFormat::Text => profile.render(),
+            DiagnosticsFormat::Json => serde_json::to_string(&profile)? + "\n",
+        };
+        reporter.requested(&options, &text)?
+    }
+    let annotated_units = annotate_units(&env, modules_and_scripts);
+    if let Some(dir) = &options.output_dir {
+        for path in artifacts::write_artifacts(&env, &options, &annotated_units, dir)? {
+            reporter.progress(&options, &format!("wrote `{}`", path.display()))?
+        }
+    }
+    if let Some(path) = &options.emit_publish_bundle {
+        publish_bundle::write_publish_bundle(&env, &annotated_units, &options, path)?;
+        reporter.progress(
+            &options,
+            &format!("wrote publish bundle to `{}`", path.display()),
+        )?
+    }
+    Ok(CompilerOutput {
+        env,
+        targets,
+        annotated_units,
+        coverage_maps,
+        diagnostics: vec![],
+    })
+}
+
+/// Run the checker of the Move compiler, without generating code, and print diagnostics,
+/// including warnings, to the given writer. Returns the global env if there are no errors.
+pub fn run_move_compiler_check_only(
+    error_writer: &mut impl WriteColor,
+    options: Options,
+) -> anyhow::Result<GlobalEnv> {
+    run_checker_and_report(&mut Reporter::writing(error_writer), options)
+}
+
+fn run_checker_and_report(reporter: &mut Reporter, options: Options) -> anyhow::Result<GlobalEnv> {
+    let start = Instant::now();
+    let phase = TracedPhase::enter(compilation_stats::CHECKING_PHASE, None);
+    reporter.progress(&options, &phase_line(compilation_stats::CHECKING_PHASE))?;
+    let env = run_checker(options.clone())?;
+    phase.exit(&env);
+    let stats = install_stats(&env, start);
+    report_and_check_errors(&env, reporter, "checking errors")?;
+    reporter.progress(&options, &module_counts(&env))?;
+    dump_model(&env, reporter, &options)?;
+    emit_dependency_graph(&env, reporter, &options)?;
+    if env
+        .get_extension::<Options>()
+        .map_or(false, |o| o.print_stats)
+    {
+        reporter.requested(&options, &stats.render())?
+    }
+    Ok(env)
+}
+
+/// Dumps the model of the target modules into the dump directory if `Options::dump_model` is
+/// set.
+fn dump_model(env: &GlobalEnv, reporter: &mut Reporter, options: &Options) -> anyhow::Result<()> {
+    if options.dump_model {
+        let dump_dir = options
+            .dump_bytecode_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        for path in model_dump::write_model_dumps(env, &dump_dir)? {
+            reporter.progress(options, &format!("dumped model to `{}`", path.display()))?
+        }
+    }
+    Ok(())
+}
+
+/// Writes the dependency graphs of the target modules into the directory of
+/// `Options::emit_dependency_graph`, if set.
+fn emit_dependency_graph(
+    env: &GlobalEnv,
+    reporter: &mut Reporter,
+    options: &Options,
+) -> anyhow::Result<()> {
+    if let Some(dir) = &options.emit_dependency_graph {
+        let with_functions = options.dependency_graph_functions;
+        for path in dependency_graph::write_dependency_graphs(env, dir, with_functions)? {
+            reporter.progress(
+                options,
+                &format!("wrote dependency graph to `{}`", path.display()),
+            )?
+        }
+    }
+    Ok(())
+}
+
+/// Returns the progress line of a phase which starts, given by its name.
+fn phase_line(phase: &str) -> String {
+    format!("phase: {}", phase)
+}
+
+/// Returns the progress line stating the numbers of target and dependency modules.
+fn module_counts(env: &GlobalEnv) -> String {
+    let (targets, dependencies): (Vec<_>, Vec<_>) = env
+        .get_modules()
+        .partition(|module_env| module_env.is_target());
+    format!(
+        "checked {} target and {} dependency module(s)",
+        targets.len(),
+        dependencies.len()
+    )
+}
+
+/// Installs the statistics of a compilation which started at the given time in the env, with
+/// the checking phase ending now, and returns them.
+fn install_stats(env: &GlobalEnv, start: Instant) -> Rc<CompilationStats> {
+    let stats = CompilationStats::new(start);
+    stats.record_phase(compilation_stats::CHECKING_PHASE, start);
+    env.set_extension(stats);
+    env.get_extension::<CompilationStats>()
+        .expect("statistics installed")
 }
 
 /// Run the type checker and return the global env (with errors if encountered). The result
-/// fails not on context checking errors, but possibly on i/o errors.
-pub fn run_checker(options: Options) -> anyhow::Result<GlobalEnv> {
+/// fails not on context checking errors, but possibly on i/o errors. Problems with the paths of
+/// sources and dependencies, like duplicates, are reported as described in `source_paths`.
+pub fn run_checker(mut options: Options) -> anyhow::Result<GlobalEnv> {
+    options
+        .progress
+        .report(ProgressEvent::PhaseStarted(Phase::Checking));
+    if let Some(version) = options.bytecode_version {
+        if !(VERSION_MIN..=VERSION_MAX).contains(&version) {
+            bail!(
+                "bytecode version {} is not supported, supported versions are {} to {}",
+                version,
+                VERSION_MIN,
+                VERSION_MAX
+            )
+        }
+    }
+    // Compiled dependencies enter the model via source interfaces, which declare their public
+    // and friend functions as native.
+    let (interfaces, bytecode_dependencies) =
+        load_bytecode_dependencies(&options.bytecode_dependencies)?;
+    // Duplicate paths are removed, so files are not compiled twice.
+    let source_paths = SourcePaths::new(&options);
+    options.sources = source_paths.sources.clone();
+    options.dependencies = source_paths.dependencies.clone();
     // Run the model builder, which performs context checking.
     let addrs = move_model::parse_addresses_from_options(options.named_address_mapping.clone())?;
-    let mut env = move_model::run_model_builder_in_compiler_mode(
-        PackageInfo {
-            sources: options.sources.clone(),
-            address_map: addrs.clone(),
-        },
-        vec![PackageInfo {
-            sources: options.dependencies.clone(),
-            address_map: addrs.clone(),
-        }],
-        options.skip_attribute_checks,
-        if !options.skip_attribute_checks && options.known_attributes.is_empty() {
-            KnownAttribute::get_all_attribute_names()
-        } else {
-            &options.known_attributes
+    let build_model = |dependencies: Vec<String>,
+                       dependency_buffers: Vec<(String, String)>|
+     -> anyhow::Result<GlobalEnv> {
+        let mut build_config_filter = BuildConfigFilter::new(&options.build_config);
+        // Dependencies are grouped into packages by the overrides of their address mapping.
+        let mut dependency_addresses =
+            DependencyAddresses::new(&addrs, &options.dependency_address_overrides)?;
+        let dependency_packages = dependency_addresses.packages(
+            dependencies,
+            dependency_buffers
+                .into_iter()
+                .chain(interfaces.iter().cloned())
+                .collect(),
+        )?;
+        let env = move_model::run_model_builder_in_compiler_mode(
+            PackageInfo {
+                sources: options.sources.clone(),
+                source_buffers: options.source_buffers.clone(),
+                address_map: addrs.clone(),
+            },
+            dependency_packages,
+            options.skip_attribute_checks,
+            &known_attributes(&options),
+            options.compile_test_code,
+            Some(&mut build_config_filter),
+        )?;
+        build_config_filter.report(&env);
+        dependency_addresses.report(&env);
+        Ok(env)
+    };
+    // With a dependency cache, the stripped dependencies of a valid entry are checked instead
+    // of the dependencies, falling back to the dependencies if the stripped ones have errors.
+    let dependency_cache = options
+        .dependency_cache_dir
+        .as_deref()
+        .map(|dir| DependencyCache::new(dir, &options))
+        .transpose()?;
+    let mut cached_env = None;
+    let mut cache_hit = false;
+    if let Some(cache) = &dependency_cache {
+        if let Some(files) = cache.load() {
+            cache_hit = true;
+            let env = build_model(vec![], files)?;
+            if dependency_cache::has_dependency_errors(&env) {
+                cache.discard()
+            } else {
+                cached_env = Some(env)
+            }
+        }
+    }
+    let mut env = match cached_env {
+        Some(env) => env,
+        None => {
+            let env = build_model(
+                options.dependencies.clone(),
+                options.dependency_buffers.clone(),
+            )?;
+            if let Some(cache) = dependency_cache.as_ref().filter(|_| !cache_hit) {
+                if !dependency_cache::has_dependency_errors(&env) {
+                    cache.store()?
+                }
+            }
+            env
         },
-    )?;
+    };
     // Store address aliases
     let map = addrs
         .into_iter()
         .map(|(s, a)| (env.symbol_pool().make(&s), a.into_inner()))
         .collect();
     env.set_address_alias_map(map);
+    add_bytecode_dependency_friends(&mut env, &bytecode_dependencies);
+    source_paths.report(&env);
+    // Report invalid experiment settings, which would otherwise be silently ignored.
+    for error in options.experiment_errors() {
+        diagnostic_codes::INVALID_EXPERIMENT.error_without_loc(&env, &error)
+    }
+    for error in options.warning_category_errors() {
+        diagnostic_codes::INVALID_WARNING_CATEGORY.error_without_loc(&env, &error)
+    }
+    if options.skip_attribute_checks {
+        if !options.unchecked_attributes.is_empty() {
+            diagnostic_codes::REDUNDANT_UNCHECKED_ATTRIBUTES.diag_without_loc(
+                &env,
+                Severity::Warning,
+                "`--unchecked-attributes` has no effect since `--skip-attribute-checks` is set",
+            )
+        }
+    } else {
+        let is_checked = |name: &str| !options.unchecked_attributes.iter().any(|a| a == name);
+        if is_checked(LintAttribute::ALLOW) {
+            lint::check_lint_attributes(&env)
+        }
+        let specs = options
+            .extra_known_attributes
+            .iter()
+            .filter(|spec| is_checked(&spec.name))
+            .cloned()
+            .collect::<Vec<_>>();
+        extra_attributes::check_extra_attributes(&env, &specs)
+    }
+    script_checker::check_scripts(&env);
+    if lint::is_lint_enabled(&options, "shadowing") {
+        shadowing_checker::check_shadowing(&env)
+    }
+    let compile_test_code = options.compile_test_code;
     // Store options in env, for later access
     env.set_extension(options);
+    if compile_test_code && !env.has_errors() {
+        let plans = unit_test::construct_test_plans(&env);
+        env.set_extension(unit_test::ModuleTestPlans(plans))
+    }
     Ok(env)
 }
 
+/// Returns the attributes known to the checker. Unless attribute checks are skipped, these
+/// default to all attributes known to the v1 compiler, and include the testing attributes if
+/// test code is compiled. The attributes defined by embedders, the unchecked attributes, and
+/// `cfg_attr` of the build configuration are always included.
+pub(crate) fn known_attributes(options: &Options) -> BTreeSet<String> {
+    let mut known_attributes =
+        if !options.skip_attribute_checks && options.known_attributes.is_empty() {
+            KnownAttribute::get_all_attribute_names().clone()
+        } else {
+            options.known_attributes.clone()
+        };
+    if options.compile_test_code {
+        TestingAttribute::add_attribute_names(&mut known_attributes)
+    }
+    known_attributes.extend(
+        options
+            .extra_known_attributes
+            .iter()
+            .map(|spec| spec.name.clone())
+            .chain(options.unchecked_attributes.iter().cloned())
+            .chain([build_config::CFG_ATTR.to_owned()]),
+    );
+    known_attributes
+}
+
+/// Loads the compiled modules in the given `.mv` files, or in the `.mv` files found in the given
+/// directories. Returns their source interfaces, paired with the path of the module file, and
+/// the modules. The friend declarations of the modules are not part of the interfaces, as the
+/// friend modules need not be part of the compilation.
+fn load_bytecode_dependencies(
+    paths: &[String],
+) -> anyhow::Result<(Vec<(String, String)>, Vec<CompiledModule>)> {
+    let config = DeserializerConfig::new(VERSION_MAX, IDENTIFIER_SIZE_MAX);
+    let mut interfaces = vec![];
+    let mut modules = vec![];
+    for path in find_filenames(paths, |p| extension_equals(p, MOVE_COMPILED_EXTENSION))? {
+        let module = CompiledModule::deserialize_with_config(&fs::read(&path)?, &config)
+            .map_err(|err| anyhow!("cannot deserialize module `{}`: {}", path, err))?;
+        // Addresses are written numerically, since the named address of a compiled module is
+        // not known.
+        let (_, interface) = interface_generator::write_module_to_string(
+            &BTreeMap::<ModuleId, String>::new(),
+            &CompiledModule {
+                friend_decls: vec![],
+                ..module.clone()
+            },
+        )?;
+        interfaces.push((path, interface));
+        modules.push(module)
+    }
+    Ok((interfaces, modules))
+}
+
+/// Adds the friend declarations of compiled dependencies to their modules in the env.
+fn add_bytecode_dependency_friends(env: &mut GlobalEnv, modules: &[CompiledModule]) {
+    for module in modules {
+        let Some(module_env) = env.find_module_by_language_storage_id(&module.self_id()) else {
+            continue;
+        };
+        let module_id = module_env.get_id();
+        let loc = module_env.get_loc();
+        let decls = module
+            .friend_decls()
+            .iter()
+            .map(|handle| {
+                let friend = module.module_id_for_handle(handle);
+                let module_name = ModuleName::from_address_bytes_and_name(
+                    NumericalAddress::new(friend.address().into_bytes(), NumberFormat::Hex),
+                    env.symbol_pool().make(friend.name().as_str()),
+                );
+                FriendDecl {
+                    loc: loc.clone(),
+                    module_id: env.find_module(&module_name).map(|m| m.get_id()),
+                    module_name,
+                }
+            })
+            .collect::<Vec<_>>();
+        for decl in decls {
+            env.add_friend_decl(module_id, decl)
+        }
+    }
+}
+
 // Run the (stackless) bytecode generator. For each function which is target of the
 // compilation, create an entry in the functions target holder which encapsulate info
 // like the generated bytecode.
+//
+// Code is generated for all functions even if errors are reported for some of them, so all
+// errors are reported at once. Functions with errors get no entry, as the pipeline assumes
+// well-formed code.
 pub fn run_bytecode_gen(env: &GlobalEnv) -> FunctionTargetsHolder {
-    let mut targets = FunctionTargetsHolder::default();
+    let cache = env.get_extension::<CompilationCache>();
+    let options = env.get_extension::<Options>().unwrap_or_default();
+    let optimize = options.optimize > 0;
+    module_filter::check_module_filter(env, &options.module_filter);
+    // Compute the functions for which code is generated: the functions of the selected target
+    // modules and their transitive callees.
     let mut todo = BTreeSet::new();
-    let mut done = BTreeSet::new();
     for module in env.get_modules() {
-        if module.is_target() {
+        if module.is_target()
+            && module_filter::is_selected(&module, &options.module_filter)
+            && !cache.as_ref().is_some_and(|c| c.is_cached(module.get_id()))
+        {
             for fun in module.get_functions() {
                 let id = fun.get_qualified_id();
                 todo.insert(id);
             }
         }
     }
+    let (done, skipped) =
+        functions_to_generate(env, todo, |fun_env| fun_env.get_called_functions().cloned());
+    if options.warn_recursion {
+        recursion_checker::check_recursion(env, done.difference(&skipped))
+    }
+    // Generate code in the order of function ids, so targets and diagnostics are
+    // deterministic. Generation is sequential because the generator allocates expression
+    // nodes and symbols in the `GlobalEnv`, which cannot be shared between threads.
+    let stats = env
+        .get_extension::<CompilationStats>()
+        .filter(|_| options.profile_compilation);
+    let mut targets = FunctionTargetsHolder::default();
+    let total = done.len() - skipped.len();
+    for (count, id) in done.difference(&skipped).enumerate() {
+        let error_count = env.error_count();
+        let start = Instant::now();
+        let data = bytecode_generator::generate_bytecode(env, *id);
+        options.progress.report(ProgressEvent::FunctionCompiled {
+            done: count + 1,
+            total,
+        });
+        if let Some(stats) = &stats {
+            let module_env = env.get_module(id.module_id);
+            stats.record_module_generation(&module_env.get_full_name_str(), start.elapsed())
+        }
+        if env.error_count() > error_count {
+            continue;
+        }
+        if optimize && env.get_function(*id).module_env.is_target() {
+            targets.insert_target_data(
+                id,
+                FunctionVariant::Optimized,
+                data.fork(FunctionVariant::Optimized),
+            );
+        }
+        targets.insert_target_data(id, FunctionVariant::Baseline, data);
+    }
+    targets
+}
+
+/// Returns the functions for which code is generated, namely the roots and their transitive
+/// callees as given by `callees_of`, and among them the functions for which no code can be
+/// generated because their callees are not known. An error is reported for each of the latter.
+fn functions_to_generate(
+    env: &GlobalEnv,
+    mut todo: BTreeSet<QualifiedId<FunId>>,
+    callees_of: impl Fn(&FunctionEnv) -> Option<BTreeSet<QualifiedId<FunId>>>,
+) -> (BTreeSet<QualifiedId<FunId>>, BTreeSet<QualifiedId<FunId>>) {
+    let mut done = BTreeSet::new();
+    let mut skipped = BTreeSet::new();
     while let Some(id) = todo.pop_first() {
         done.insert(id);
-        let data = bytecode_generator::generate_bytecode(env, id);
-        targets.insert_target_data(&id, FunctionVariant::Baseline, data);
-        for callee in env
-            .get_function(id)
-            .get_called_functions()
-            .expect("called functions available")
-        {
-            if !done.contains(callee) {
-                todo.insert(*callee);
+        let fun_env = env.get_function(id);
+        // Native functions, and functions of dependencies without a definition, have no
+        // callees for which code is needed.
+        if fun_env.is_native() || (!fun_env.module_env.is_target() && fun_env.get_def().is_none()) {
+            continue;
+        }
+        let Some(callees) = callees_of(&fun_env) else {
+            // The model builder did not fully process the function, so no code can be
+            // generated for it.
+            diagnostic_codes::UNKNOWN_CALLEES.error(
+                env,
+                &fun_env.get_loc(),
+                &format!(
+                    "cannot generate code for `{}`: the functions it calls are not known",
+                    fun_env.get_full_name_str()
+                ),
+            );
+            skipped.insert(id);
+            continue;
+        };
+        for callee in callees {
+            if !done.contains(&callee) {
+                todo.insert(callee);
             }
         }
     }
-    targets
+    (done, skipped)
 }
 
+/// Generate the file format for the target modules and scripts. The units of modules found in
+/// the `CompilationCache`, if one is installed in the env, are reused. Private functions which
+/// can never be called are left out if `Options::strip_dead_private_functions` is set.
 pub fn run_file_format_gen(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> Vec<CompiledUnit> {
-    file_format_generator::generate_file_format(env, targets)
+    let cache = env.get_extension::<CompilationCache>();
+    if env
+        .get_extension::<Options>()
+        .is_some_and(|options| options.strip_dead_private_functions)
+    {
+        dead_functions::strip_dead_functions(env)
+    }
+    file_format_generator::generate_file_format(env, targets, cache.as_deref())
+}
+
+/// Runs the bytecode verifier on the compiled units. Since the compiler should never produce
+/// code which does not verify, failures are reported as internal errors, pointing to the
+/// function and source location at fault if known.
+pub fn run_bytecode_verifier(env: &GlobalEnv, units: &[CompiledUnit]) {
+    for unit in units {
+        let (result, source_map, unit_name, fun_names) = match unit {
+            CompiledUnit::Module(named_module) => {
+                let module = &named_module.module;
+                let fun_names = module
+                    .function_defs
+                    .iter()
+                    .map(|def| {
+                        let handle = module.function_handle_at(def.function);
+                        module.identifier_at(handle.name).to_string()
+                    })
+                    .collect::<Vec<_>>();
+                (
+                    move_bytecode_verifier::verify_module(module),
+                    &named_module.source_map,
+                    format!("module `{}`", module.self_id().short_str_lossless()),
+                    fun_names,
+                )
+            },
+            CompiledUnit::Script(named_script) => (
+                move_bytecode_verifier::verify_script(&named_script.script),
+                &named_script.source_map,
+                format!("script `{}`", named_script.name),
+                vec![named_script.name.to_string()],
+            ),
+        };
+        let Err(err) = result else {
+            continue;
+        };
+        // Determine the function and location of the failure, if the verifier reports them.
+        let fun_idx = err.offsets().first().map(|(idx, _)| *idx).or_else(|| {
+            err.indices().iter().find_map(|(kind, idx)| {
+                (*kind == IndexKind::FunctionDefinition).then_some(FunctionDefinitionIndex(*idx))
+            })
+        });
+        let fun_name = fun_idx
+            .and_then(|idx| fun_names.get(idx.0 as usize))
+            .map(|name| format!(", function `{}`", name))
+            .unwrap_or_default();
+        let ir_loc = err
+            .offsets()
+            .first()
+            .and_then(|(idx, offset)| source_map.get_code_location(*idx, *offset).ok())
+            .or_else(|| {
+                fun_idx.and_then(|idx| {
+                    source_map
+                        .get_function_source_map(idx)
+                        .ok()
+                        .map(|fun_map| fun_map.definition_location)
+                })
+            })
+            .unwrap_or(source_map.definition_location);
+        diagnostic_codes::BYTECODE_VERIFICATION_FAILED.bug(
+            env,
+            &env.to_loc(&ir_loc),
+            &format!(
+                "bytecode verification failed for {}{}: {}",
+                unit_name, fun_name, err
+            ),
+        )
+    }
 }
 
-/// Returns the bytecode processing pipeline.
-pub fn bytecode_pipeline(_env: &GlobalEnv) -> FunctionTargetPipeline {
+/// Returns the bytecode processing pipeline, including the processors registered via
+/// `Options::register_pipeline_extension`. An error is reported if a registered processor
+/// refers to an unknown processor for its position. Each processor runs in a tracing span per
+/// function, see `trace`.
+///
+/// If `Options::optimize` is set, the checkers only run on the baseline variant, so their
+/// diagnostics are not duplicated, and the optimizations only run on the optimized variant,
+/// unless enabled for all variants by their experiments.
+pub fn bytecode_pipeline(env: &GlobalEnv) -> FunctionTargetPipeline {
+    let options = env.get_extension::<Options>().unwrap_or_default();
+    let optimize = options.optimize > 0;
+    let baseline_only = |processor: Box<dyn FunctionTargetProcessor>| {
+        if optimize {
+            Box::new(VariantProcessor::new(FunctionVariant::Baseline, processor))
+        } else {
+            processor
+        }
+    };
+    let mut processors: Vec<Box<dyn FunctionTargetProcessor>> = vec![];
+    processors.push(baseline_only(Box::new(UnusedVarsChecker())));
+    if options.experiment_on(Experiment::FUNCTION_INLINING) {
+        processors.push(Box::new(FunctionInliner()));
+    } else if optimize {
+        processors.push(Box::new(VariantProcessor::new(
+            FunctionVariant::Optimized,
+            Box::new(FunctionInliner()),
+        )));
+    }
+    if options.experiment_on(Experiment::CONSTANT_FOLDING) {
+        processors.push(Box::new(ConstantFolder()));
+    }
+    processors.push(Box::new(UnreachableCodeRemover()));
+    processors.push(Box::new(LiveVarAnalysisProcessor()));
+    processors.push(baseline_only(Box::new(ExpensiveCopyChecker())));
+    processors.push(baseline_only(Box::new(MustUseChecker())));
+    if options.experiment_on(Experiment::COPY_PROPAGATION) {
+        processors.push(Box::new(CopyPropagation()));
+    } else if optimize {
+        processors.push(Box::new(VariantProcessor::new(
+            FunctionVariant::Optimized,
+            Box::new(CopyPropagation()),
+        )));
+    }
+    if options.experiment_on(Experiment::DEAD_STORE_ELIMINATION) {
+        processors.push(Box::new(DeadStoreElimination()));
+    } else if optimize {
+        processors.push(Box::new(VariantProcessor::new(
+            FunctionVariant::Optimized,
+            Box::new(DeadStoreElimination()),
+        )));
+    }
+    if options.experiment_on(Experiment::CFG_SIMPLIFICATION) {
+        processors.push(Box::new(CfgSimplifier()));
+    } else if optimize {
+        processors.push(Box::new(VariantProcessor::new(
+            FunctionVariant::Optimized,
+            Box::new(CfgSimplifier()),
+        )));
+    }
+    processors.push(Box::new(ExplicitDrop()));
+    processors.push(Box::new(AcquiresInference()));
+    processors.push(baseline_only(Box::new(AbilityChecker())));
+    processors.push(baseline_only(Box::new(ReferenceSafetyProcessor())));
+    processors.push(baseline_only(Box::new(VisibilityChecker())));
+    for extension in &options.pipeline_extensions {
+        let processor = (extension.processor)();
+        let position_of = |name: &str| processors.iter().position(|p| p.name() == name);
+        let index = match &extension.position {
+            ProcessorPosition::Before(name) => position_of(name).ok_or(name),
+            ProcessorPosition::After(name) => position_of(name).map(|i| i + 1).ok_or(name),
+            ProcessorPosition::End => Ok(processors.len()),
+        };
+        match index {
+            Ok(index) => processors.insert(index, processor),
+            Err(name) => diagnostic_codes::UNKNOWN_PIPELINE_POSITION.error_without_loc(
+                env,
+                &format!(
+                    "cannot insert processor `{}` into the bytecode pipeline: no processor \
+                     named `{}`",
+                    processor.name(),
+                    name
+                ),
+            ),
+        }
+    }
     let mut pipeline = FunctionTargetPipeline::default();
-    pipeline.add_processor(Box::new(LiveVarAnalysisProcessor()));
-    pipeline.add_processor(Box::new(VisibilityChecker()));
+    for processor in processors {
+        let processor: Box<dyn FunctionTargetProcessor> = if options.profile_compilation {
+            Box::new(ProfiledProcessor::new(processor))
+        } else {
+            processor
+        };
+        let processor: Box<dyn FunctionTargetProcessor> = if options.progress.is_enabled() {
+            Box::new(ReportedProcessor::new(processor, options.progress.clone()))
+        } else {
+            processor
+        };
+        pipeline.add_processor(Box::new(TracedProcessor::new(processor)))
+    }
     pipeline
 }
 
+/// Returns the name with `::`, and other characters which are not portable in file names, like
+/// those in module names of scripts, replaced by `_`.
+fn portable_file_name(name: &str) -> String {
+    name.replace("::", "_")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Runs the bytecode pipeline, dumping the bytecode of each target module before the
+/// pipeline and after each processor into `dump_dir`, which is created if needed. Files are
+/// named `<module>_<stage>_<processor>.bytecode`, where the stage is 0 for the initial bytecode.
+/// If `Options::dump_bytecode_diff` is set, the files after a processor are named
+/// `<module>_<stage>_<processor>.diff`, and only contain the changes of functions relative to
+/// the previous stage. If `Options::dump_annotations` is set, the annotations of the analyses
+/// are included as comments. Returns the paths of the written files.
+pub fn run_pipeline_with_dump(
+    env: &GlobalEnv,
+    pipeline: &FunctionTargetPipeline,
+    targets: &mut FunctionTargetsHolder,
+    dump_dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let options = env.get_extension::<Options>().unwrap_or_default();
+    let diff = options.dump_bytecode_diff || options.dump_bytecode_diff_annotations;
+    let with_annotations = options.dump_annotations || options.dump_bytecode_diff_annotations;
+    fs::create_dir_all(dump_dir)?;
+    // The hooks of the pipeline cannot fail, so remember written files and the first error.
+    let written = RefCell::new(vec![]);
+    let error = RefCell::new(None);
+    // The text of each function at the previous stage, for computing diffs.
+    let previous = RefCell::new(BTreeMap::new());
+    let dump = |stage: usize, name: &str, targets: &FunctionTargetsHolder| {
+        if error.borrow().is_some() {
+            return;
+        }
+        let mut previous = previous.borrow_mut();
+        for module_env in env.get_modules().filter(|m| m.is_target()) {
+            let mut text = String::new();
+            for fun_env in module_env.get_functions() {
+                for (variant, target) in targets.get_targets(&fun_env) {
+                    if target.data.code.is_empty() && !fun_env.is_native() {
+                        continue;
+                    }
+                    if with_annotations {
+                        annotation_formatters::register_annotation_formatters(&target, &options)
+                    }
+                    let fun_text = target.to_string();
+                    let old_text = previous.insert(
+                        (fun_env.get_qualified_id(), variant.clone()),
+                        fun_text.clone(),
+                    );
+                    if diff && stage > 0 {
+                        let fun_diff =
+                            bytecode_diff::diff_text(&old_text.unwrap_or_default(), &fun_text);
+                        if !fun_diff.is_empty() {
+                            text.push_str(&format!(
+                                "[variant {}] fun {} changed by `{}`:\n{}\n",
+                                variant,
+                                fun_env.get_full_name_str(),
+                                name,
+                                fun_diff
+                            ))
+                        }
+                    } else {
+                        text.push_str(&format!(
+                            "\n{}\n",
+                            bytecode_text::print_target(&variant, &target)
+                        ))
+                    }
+                }
+            }
+            let (text, ext) = if diff && stage > 0 {
+                (text, "diff")
+            } else {
+                (
+                    format!(
+                        "============ after processor `{}` ================\n{}",
+                        name, text
+                    ),
+                    "bytecode",
+                )
+            };
+            let path = dump_dir.join(format!(
+                "{}_{}_{}.{}",
+                portable_file_name(&module_env.get_full_name_str()),
+                stage,
+                portable_file_name(name),
+                ext
+            ));
+            match fs::write(&path, text) {
+                Ok(()) => written.borrow_mut().push(path),
+                Err(err) => {
+                    *error.borrow_mut() = Some(err.into());
+                    return;
+                },
+            }
+        }
+    };
+    // If statistics are collected, the time of a processor excludes the time of dumping.
+    let stats = env.get_extension::<CompilationStats>();
+    let processor_start = Cell::new(Instant::now());
+    pipeline.run_with_hook(
+        env,
+        targets,
+        |targets| {
+            dump(0, "stackless", targets);
+            processor_start.set(Instant::now())
+        },
+        |stage, processor, targets| {
+            if let Some(stats) = &stats {
+                stats.record_processor(&processor.name(), processor_start.get())
+            }
+            dump(stage, &processor.name(), targets);
+            processor_start.set(Instant::now())
+        },
+    );
+    match error.into_inner() {
+        Some(err) => Err(err),
+        None => Ok(written.into_inner()),
+    }
+}
+
 /// Report any diags in the env to the writer and fail if there are errors.
 pub fn check_errors<W: WriteColor>(
     env: &GlobalEnv,
     error_writer: &mut W,
     msg: &'static str,
+) -> anyhow::Result<()> {
+    report_and_check_errors(env, &mut Reporter::writing(error_writer), msg)
+}
+
+fn report_and_check_errors(
+    env: &GlobalEnv,
+    reporter: &mut Reporter,
+    msg: &'static str,
 ) -> anyhow::Result<()> {
     let options = env.get_extension::<Options>().unwrap_or_default();
-    env.report_diag(error_writer, options.report_severity());
-    if env.has_errors() {
+    lint::suppress_allowed_lints(env);
+    lint::filter_warning_categories(env, &options);
+    env.deduplicate_diags();
+    diagnostics::sort_diags(env);
+    // Warnings are promoted before diagnostics are filtered by severity, so promoted warnings
+    // are reported even if only errors are.
+    if options.warnings_are_errors {
+        env.promote_diag_severity(Severity::Warning, Severity::Error)
+    }
+    if let Some(path) = &options.sarif_output {
+        diagnostics::write_diags_as_sarif(env, path, options.report_severity())?
+    }
+    reporter.report_diags(env, &options, options.report_severity());
+    if env.error_count() > reporter.tolerated_errors {
         bail!("exiting with {}", msg);
     } else {
         Ok(())
     }
 }
 
-/// Annotate the given compiled units.
-/// TODO: this currently only fills in defaults. The annotations are only used in
-/// the prover, and compiler v2 is not yet connected to the prover.
+/// Annotate the given compiled units with the locations of modules, scripts, and functions,
+/// and with where the inline spec blocks of functions landed in the code, see `spec_info`.
 pub fn annotate_units(env: &GlobalEnv, units: Vec<CompiledUnit>) -> Vec<AnnotatedCompiledUnit> {
-    let loc = env.unknown_move_ir_loc();
+    let index = UnitModuleIndex::new(env);
     units
         .into_iter()
         .map(|u| match u {
             CompiledUnit::Module(named_module) => {
-                AnnotatedCompiledUnit::Module(AnnotatedCompiledModule {
+                let module_env = index
+                    .find_module(&named_module.module.self_id())
+                    .expect("module of compiled unit");
+                let loc = env.to_ir_loc(&module_env.get_loc());
+                let mut annotated_module = AnnotatedCompiledModule {
                     loc,
                     module_name_loc: loc,
                     address_name: None,
                     named_module,
                     function_infos: UniqueMap::new(),
-                })
+                };
+                let module_ident = annotated_module.module_ident().value;
+                let module = &annotated_module.named_module.module;
+                for def in &module.function_defs {
+                    let handle = module.function_handle_at(def.function);
+                    let name = module.identifier_at(handle.name).as_str();
+                    let fun_env = module_env
+                        .find_function(env.symbol_pool().make(name))
+                        .expect("function of compiled module");
+                    let fun_name = FunctionName(Spanned::new(
+                        env.to_ir_loc(&fun_env.get_loc()),
+                        Symbol::from(name),
+                    ));
+                    annotated_module
+                        .function_infos
+                        .add(
+                            fun_name,
+                            spec_info::function_info(&fun_env, Some(module_ident)),
+                        )
+                        .expect("unique function names");
+                }
+                AnnotatedCompiledUnit::Module(annotated_module)
             },
             CompiledUnit::Script(named_script) => {
+                let module_env = index.find_script(named_script.name.as_str());
+                let loc = module_env
+                    .as_ref()
+                    .map(|module_env| env.to_ir_loc(&module_env.get_loc()))
+                    .unwrap_or_else(|| env.unknown_move_ir_loc());
+                let function_info = module_env
+                    .as_ref()
+                    .and_then(|module_env| module_env.get_functions().next())
+                    .map(|fun_env| spec_info::function_info(&fun_env, None))
+                    .unwrap_or_else(|| FunctionInfo {
+                        spec_info: Default::default(),
+                    });
                 AnnotatedCompiledUnit::Script(AnnotatedCompiledScript {
                     loc,
                     named_script,
-                    function_info: FunctionInfo {
-                        spec_info: Default::default(),
-                    },
+                    function_info,
                 })
             },
         })
         .collect()
 }
 
+/// An index of the modules of the environment by the names of the units compiled from them,
+/// built once to look up the module of each unit without scanning all modules. Modules are
+/// keyed by their address and name, so modules of the same name at different addresses are
+/// told apart, and script modules by the name of their function.
+pub(crate) struct UnitModuleIndex<'env> {
+    env: &'env GlobalEnv,
+    modules: BTreeMap<ModuleName, ModuleEnv<'env>>,
+    scripts: BTreeMap<move_model::symbol::Symbol, ModuleEnv<'env>>,
+}
+
+impl<'env> UnitModuleIndex<'env> {
+    pub fn new(env: &'env GlobalEnv) -> Self {
+        let mut modules = BTreeMap::new();
+        let mut scripts = BTreeMap::new();
+        for module_env in env.get_modules() {
+            if module_env.is_script_module() {
+                for fun_env in module_env.get_functions() {
+                    scripts.insert(fun_env.get_name(), module_env.clone());
+                }
+            } else {
+                modules.insert(module_env.get_name().clone(), module_env);
+            }
+        }
+        Self {
+            env,
+            modules,
+            scripts,
+        }
+    }
+
+    /// Returns the module of the compiled module with the given id.
+    pub fn find_module(&self, id: &ModuleId) -> Option<ModuleEnv<'env>> {
+        self.modules.get(&self.env.to_module_name(id)).cloned()
+    }
+
+    /// Returns the script module of the compiled script with the given name.
+    pub fn find_script(&self, name: &str) -> Option<ModuleEnv<'env>> {
+        self.scripts
+            .get(&self.env.symbol_pool().make(name))
+            .cloned()
+    }
+}
+
 /// Computes the `FilesSourceText` from the global environment, which maps IR loc file hashes
-/// into files and sources. This value is used for the package system only.
+/// into files and sources. This value is used for the package system only. Files with identical
+/// contents have the same hash, so only one of them is included; the checker warns about such
+/// files.
 pub fn make_files_source_text(env: &GlobalEnv) -> FilesSourceText {
     let mut result = FilesSourceText::new();
     for fid in env.get_source_file_ids() {
@@ -208,3 +1439,50 @@ pub fn make_files_source_text(env: &GlobalEnv) -> FilesSourceText {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codespan_reporting::term::termcolor::Buffer;
+
+    #[test]
+    fn functions_with_unknown_callees_are_reported_and_skipped() {
+        let options = Options {
+            source_buffers: vec![(
+                "m.move".to_owned(),
+                "module 0x42::m { fun f() { g() } fun g() {} fun h() { g() } }".to_owned(),
+            )],
+            ..Options::default()
+        };
+        let env = run_checker(options).expect("checks");
+        let module = env.get_target_modules().pop().expect("module");
+        let fun_id = |name: &str| {
+            module
+                .find_function(env.symbol_pool().make(name))
+                .expect("function")
+                .get_qualified_id()
+        };
+        let (f, g, h) = (fun_id("f"), fun_id("g"), fun_id("h"));
+        // The callees of `f` are not known, as if the model builder did not fully process it.
+        let (done, skipped) = functions_to_generate(&env, [f, h].into(), |fun_env| {
+            if fun_env.get_qualified_id() == f {
+                None
+            } else {
+                fun_env.get_called_functions().cloned()
+            }
+        });
+        assert_eq!(done, [f, g, h].into());
+        assert_eq!(skipped, [f].into());
+        let mut error_writer = Buffer::no_color();
+        env.report_diag(&mut error_writer, Severity::Error);
+        let diags = String::from_utf8(error_writer.into_inner()).expect("utf8");
+        assert!(
+            diags.contains("E01006")
+                && diags.contains(
+                    "cannot generate code for `m::f`: the functions it calls are not known"
+                ),
+            "{}",
+            diags
+        );
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Suppression of lint warnings via the `#[lint::allow(<lint>, ..)]` attribute. If the primary
+//! location of a warning lies within a module, script, function, or struct carrying the
+//! attribute with the lint of the warning, the warning is dropped before diagnostics are
+//! reported.
+//!
+//! In addition, warnings can be suppressed or turned into errors for the whole compilation via
+//! `Options::suppressed_warnings` and `Options::elevated_warnings`, by category: either the name
+//! of a lint, or the code of a warning. Lints in `OPT_IN_LINTS` are only checked if enabled via
+//! `Options::enabled_warnings`, or turned into errors.
+
+use crate::{
+    diagnostic_codes::{self, DiagnosticCode},
+    Options,
+};
+use codespan::Span;
+use codespan_reporting::diagnostic::Severity;
+use move_compiler::shared::known_attributes::LintAttribute;
+use move_model::{
+    ast::Attribute,
+    model::{GlobalEnv, Loc},
+};
+use std::collections::BTreeSet;
+
+/// Returns the known lints, with the code of the warnings they suppress.
+pub fn lints() -> &'static [(&'static str, DiagnosticCode)] {
+    &[
+        ("unused_variable", diagnostic_codes::UNUSED_VARIABLE),
+        ("unreachable_code", diagnostic_codes::UNREACHABLE_CODE),
+        ("unused_item", diagnostic_codes::UNUSED_ITEM),
+        ("expensive_copy", diagnostic_codes::EXPENSIVE_COPY),
+        ("shadowing", diagnostic_codes::SHADOWED_LOCAL),
+        ("unused_must_use", diagnostic_codes::UNUSED_MUST_USE),
+    ]
+}
+
+/// The lints which are off by default, since they report code which is often intended.
+const OPT_IN_LINTS: &[&str] = &["shadowing"];
+
+/// Returns whether the lint is checked: unless it is suppressed, and for lints in
+/// `OPT_IN_LINTS` only if it is enabled or turned into errors.
+pub fn is_lint_enabled(options: &Options, lint: &str) -> bool {
+    let Some((_, code)) = lints().iter().find(|(name, _)| *name == lint) else {
+        return false;
+    };
+    let in_any = |categories: &[String]| {
+        categories
+            .iter()
+            .any(|category| in_warning_category(code.code, category))
+    };
+    !in_any(&options.suppressed_warnings)
+        && (!OPT_IN_LINTS.contains(&lint)
+            || in_any(&options.enabled_warnings)
+            || in_any(&options.elevated_warnings))
+}
+
+/// Reports a warning for each argument of a `lint::allow` attribute in a target module which
+/// is not a known lint.
+pub fn check_lint_attributes(env: &GlobalEnv) {
+    for module_env in env.get_modules().filter(|m| m.is_target()) {
+        check_attributes(env, module_env.get_attributes());
+        for fun_env in module_env.get_functions() {
+            check_attributes(env, fun_env.get_attributes())
+        }
+        for struct_env in module_env.get_structs() {
+            check_attributes(env, struct_env.get_attributes())
+        }
+    }
+}
+
+fn check_attributes(env: &GlobalEnv, attributes: &[Attribute]) {
+    for attr in attributes {
+        if env.symbol_pool().string(attr.name()).as_str() != LintAttribute::ALLOW {
+            continue;
+        }
+        let Attribute::Apply(id, _, args) = attr else {
+            diagnostic_codes::UNKNOWN_LINT.warning(
+                env,
+                &env.get_node_loc(attr.node_id()),
+                "expected a list of lints, as in `#[lint::allow(<lint>, ..)]`",
+            );
+            continue;
+        };
+        if args.is_empty() {
+            diagnostic_codes::UNKNOWN_LINT.warning(
+                env,
+                &env.get_node_loc(*id),
+                "expected at least one lint to allow",
+            )
+        }
+        for arg in args {
+            let loc = env.get_node_loc(arg.node_id());
+            match lint_name(env, arg) {
+                Some(name) if !lints().iter().any(|(lint, _)| *lint == name) => {
+                    diagnostic_codes::UNKNOWN_LINT.warning(
+                        env,
+                        &loc,
+                        &format!(
+                            "unknown lint `{}`, known lints are: {}",
+                            name,
+                            lints()
+                                .iter()
+                                .map(|(lint, _)| format!("`{}`", lint))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    )
+                },
+                Some(_) => {},
+                None => diagnostic_codes::UNKNOWN_LINT.warning(
+                    env,
+                    &loc,
+                    "expected the name of a lint, as in `#[lint::allow(<lint>, ..)]`",
+                ),
+            }
+        }
+    }
+}
+
+/// Returns the name of the lint an argument of `lint::allow` refers to, if it is a plain name.
+fn lint_name(env: &GlobalEnv, arg: &Attribute) -> Option<String> {
+    match arg {
+        Attribute::Apply(_, name, args) if args.is_empty() => {
+            Some(env.symbol_pool().string(*name).to_string())
+        },
+        _ => None,
+    }
+}
+
+/// Removes the warnings of lints which are allowed at their primary location.
+pub fn suppress_allowed_lints(env: &GlobalEnv) {
+    let lint_of = |code: &Option<String>| {
+        lints()
+            .iter()
+            .find(|(_, lint_code)| code.as_deref() == Some(lint_code.code))
+            .map(|(lint, _)| *lint)
+    };
+    if env
+        .get_unreported_diags(|d| lint_of(&d.code).is_some())
+        .is_empty()
+    {
+        return;
+    }
+    // The locations of items which allow lints, with the allowed lints.
+    let mut scopes: Vec<(Loc, BTreeSet<String>)> = vec![];
+    for module_env in env.get_modules() {
+        scopes.push((
+            module_env.get_loc(),
+            allowed_lints(env, module_env.get_attributes()),
+        ));
+        for fun_env in module_env.get_functions() {
+            scopes.push((
+                fun_env.get_loc(),
+                allowed_lints(env, fun_env.get_attributes()),
+            ))
+        }
+        for struct_env in module_env.get_structs() {
+            scopes.push((
+                struct_env.get_loc(),
+                allowed_lints(env, struct_env.get_attributes()),
+            ))
+        }
+    }
+    scopes.retain(|(_, lints)| !lints.is_empty());
+    env.retain_diags(|diag| {
+        let (Some(lint), Some(label)) = (lint_of(&diag.code), diag.labels.first()) else {
+            return true;
+        };
+        let loc = Loc::new(
+            label.file_id,
+            Span::new(label.range.start as u32, label.range.end as u32),
+        );
+        !scopes
+            .iter()
+            .any(|(scope, lints)| scope.is_enclosing(&loc) && lints.contains(lint))
+    })
+}
+
+/// Returns the lints allowed by the `lint::allow` attributes.
+fn allowed_lints(env: &GlobalEnv, attributes: &[Attribute]) -> BTreeSet<String> {
+    attributes
+        .iter()
+        .filter_map(|attr| match attr {
+            Attribute::Apply(_, name, args)
+                if env.symbol_pool().string(*name).as_str() == LintAttribute::ALLOW =>
+            {
+                Some(args)
+            },
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|arg| lint_name(env, arg))
+        .collect()
+}
+
+/// Returns whether the warning category, the name of a lint or the code of a warning, is known.
+pub fn is_warning_category(category: &str) -> bool {
+    lints().iter().any(|(lint, _)| *lint == category)
+        || diagnostic_codes::find(category).map_or(false, |code| code.code.starts_with('W'))
+}
+
+/// Returns whether a diagnostic with the code belongs to the warning category.
+fn in_warning_category(code: &str, category: &str) -> bool {
+    code == category
+        || lints()
+            .iter()
+            .any(|(lint, lint_code)| *lint == category && lint_code.code == code)
+}
+
+/// Removes the warnings in `Options::suppressed_warnings`, and turns the warnings in
+/// `Options::elevated_warnings` into errors.
+pub fn filter_warning_categories(env: &GlobalEnv, options: &Options) {
+    let in_any = |categories: &[String], code: &Option<String>| {
+        code.as_deref().map_or(false, |code| {
+            categories
+                .iter()
+                .any(|category| in_warning_category(code, category))
+        })
+    };
+    if !options.suppressed_warnings.is_empty() {
+        env.retain_diags(|diag| {
+            diag.severity != Severity::Warning || !in_any(&options.suppressed_warnings, &diag.code)
+        })
+    }
+    if !options.elevated_warnings.is_empty() {
+        env.promote_diag_severity_with_filter(Severity::Warning, Severity::Error, |diag| {
+            in_any(&options.elevated_warnings, &diag.code)
+        })
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Dumps of the model of the target modules as built by the checker, for debugging type
+//! inference and other issues of the front end. A dump lists the friends and attributes of a
+//! module, its structs with type parameters, abilities, and field types, and its functions
+//! with their signatures, access specifiers like `acquires`, and their bodies. Members are
+//! listed in the order of the model, so dumps are deterministic and can be used as expected
+//! output of tests.
+
+use crate::pipeline::ability_checker::ability_name;
+use move_binary_format::file_format::{AbilitySet, AccessKind, Visibility};
+use move_model::{
+    ast::{AccessSpecifier, AddressSpecifier, Attribute, AttributeValue, ResourceSpecifier},
+    model::{FunctionEnv, GlobalEnv, ModuleEnv, StructEnv, TypeParameter},
+    ty::TypeDisplayContext,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Writes the dump of each target module into the directory, named `<module>.model`. Returns
+/// the paths of the written files.
+pub fn write_model_dumps(env: &GlobalEnv, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut written = vec![];
+    for module_env in env.get_modules().filter(|m| m.is_target()) {
+        let path = dir.join(format!(
+            "{}.model",
+            crate::portable_file_name(&module_env.get_full_name_str())
+        ));
+        fs::write(&path, dump_module(&module_env))?;
+        written.push(path)
+    }
+    Ok(written)
+}
+
+/// Returns the dump of the module.
+pub fn dump_module(module_env: &ModuleEnv) -> String {
+    let env = module_env.env;
+    let mut out = String::new();
+    dump_attributes(env, module_env.get_attributes(), "", &mut out);
+    if module_env.is_script_module() {
+        out.push_str("script\n")
+    } else {
+        out.push_str(&format!("module {}\n", module_env.get_full_name_str()))
+    }
+    for decl in module_env.get_friend_decls() {
+        out.push_str(&format!(
+            "    friend {}\n",
+            decl.module_name.display_full(env)
+        ))
+    }
+    for struct_env in module_env.get_structs() {
+        out.push('\n');
+        dump_struct(&struct_env, &mut out)
+    }
+    for fun_env in module_env.get_functions() {
+        out.push('\n');
+        dump_function(&fun_env, &mut out)
+    }
+    out
+}
+
+fn dump_struct(struct_env: &StructEnv, out: &mut String) {
+    let env = struct_env.module_env.env;
+    let tctx = TypeDisplayContext::new_with_params(
+        env,
+        struct_env
+            .get_type_parameters()
+            .iter()
+            .map(|param| param.0)
+            .collect(),
+    );
+    dump_attributes(env, struct_env.get_attributes(), "    ", out);
+    out.push_str(&format!(
+        "    struct {}{}{}\n",
+        struct_env.get_name().display(env.symbol_pool()),
+        type_params_str(env, struct_env.get_type_parameters()),
+        abilities_str(" has ", struct_env.get_abilities(), ", ")
+    ));
+    for field_env in struct_env.get_fields() {
+        out.push_str(&format!(
+            "        {}: {}\n",
+            field_env.get_name().display(env.symbol_pool()),
+            field_env.get_type().display(&tctx)
+        ))
+    }
+}
+
+fn dump_function(fun_env: &FunctionEnv, out: &mut String) {
+    let env = fun_env.module_env.env;
+    let tctx = fun_env.get_type_display_ctx();
+    dump_attributes(env, fun_env.get_attributes(), "    ", out);
+    let visibility = match fun_env.visibility() {
+        Visibility::Private => "",
+        Visibility::Public => "public ",
+        Visibility::Friend => "public(friend) ",
+    };
+    let kind = if fun_env.is_entry() {
+        "entry "
+    } else if fun_env.is_inline() {
+        "inline "
+    } else {
+        ""
+    };
+    let native = if fun_env.is_native() { "native " } else { "" };
+    let params = fun_env
+        .get_parameters()
+        .iter()
+        .map(|param| {
+            format!(
+                "{}: {}",
+                param.0.display(env.symbol_pool()),
+                param.1.display(&tctx)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let result_type = fun_env.get_result_type();
+    let result = if result_type.is_unit() {
+        String::new()
+    } else {
+        format!(": {}", result_type.display(&tctx))
+    };
+    out.push_str(&format!(
+        "    {}{}{}fun {}{}({}){}\n",
+        visibility,
+        kind,
+        native,
+        fun_env.get_name_str(),
+        type_params_str(env, &fun_env.get_type_parameters()),
+        params,
+        result
+    ));
+    for spec in fun_env.get_access_specifiers().unwrap_or_default() {
+        out.push_str(&format!("        {}\n", access_specifier_str(env, spec)))
+    }
+    if let Some(def) = fun_env.get_def() {
+        out.push_str("    {\n");
+        for line in def.display_for_fun(fun_env.clone()).to_string().lines() {
+            out.push_str(format!("        {}", line).trim_end());
+            out.push('\n')
+        }
+        out.push_str("    }\n")
+    }
+}
+
+/// Writes the attributes, one per line, each indented by the prefix.
+fn dump_attributes(env: &GlobalEnv, attrs: &[Attribute], indent: &str, out: &mut String) {
+    for attr in attrs {
+        out.push_str(&format!("{}#[{}]\n", indent, attribute_str(env, attr)))
+    }
+}
+
+fn attribute_str(env: &GlobalEnv, attr: &Attribute) -> String {
+    let name = attr.name().display(env.symbol_pool()).to_string();
+    match attr {
+        Attribute::Apply(_, _, args) if args.is_empty() => name,
+        Attribute::Apply(_, _, args) => format!(
+            "{}({})",
+            name,
+            args.iter()
+                .map(|arg| attribute_str(env, arg))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Attribute::Assign(_, _, AttributeValue::Value(_, value)) => {
+            format!("{} = {}", name, env.display(value))
+        },
+        Attribute::Assign(_, _, AttributeValue::Name(_, module_name, member)) => format!(
+            "{} = {}{}",
+            name,
+            module_name
+                .as_ref()
+                .map(|m| format!("{}::", m.display_full(env)))
+                .unwrap_or_default(),
+            member.display(env.symbol_pool())
+        ),
+    }
+}
+
+fn type_params_str(env: &GlobalEnv, params: &[TypeParameter]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+    let params = params
+        .iter()
+        .map(|TypeParameter(name, kind)| {
+            format!(
+                "{}{}{}",
+                if kind.is_phantom { "phantom " } else { "" },
+                name.display(env.symbol_pool()),
+                abilities_str(": ", kind.abilities, " + ")
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("<{}>", params.join(", "))
+}
+
+/// Returns the abilities after the prefix, separated by the separator, or nothing if there
+/// are none.
+fn abilities_str(prefix: &str, abilities: AbilitySet, separator: &str) -> String {
+    if abilities == AbilitySet::EMPTY {
+        return String::new();
+    }
+    let abilities = abilities.into_iter().map(ability_name).collect::<Vec<_>>();
+    format!("{}{}", prefix, abilities.join(separator))
+}
+
+fn access_specifier_str(env: &GlobalEnv, spec: &AccessSpecifier) -> String {
+    let resource = match &spec.resource.1 {
+        ResourceSpecifier::Any => "*".to_owned(),
+        ResourceSpecifier::DeclaredAtAddress(addr) => format!("{}::*", env.display(addr)),
+        ResourceSpecifier::DeclaredInModule(mid) => {
+            format!("{}::*", env.get_module(*mid).get_full_name_str())
+        },
+        ResourceSpecifier::Resource(sid) => sid
+            .to_type()
+            .display(&env.get_type_display_ctx())
+            .to_string(),
+    };
+    let address = match &spec.address.1 {
+        AddressSpecifier::Any => "*".to_owned(),
+        AddressSpecifier::Address(addr) => env.display(addr).to_string(),
+        AddressSpecifier::Parameter(sym) => sym.display(env.symbol_pool()).to_string(),
+        AddressSpecifier::Call(fun, sym) => format!(
+            "{}({})",
+            env.get_function(fun.to_qualified_id()).get_full_name_str(),
+            sym.display(env.symbol_pool())
+        ),
+    };
+    let kind = match spec.kind {
+        AccessKind::Reads => "reads",
+        AccessKind::Writes => "writes",
+        AccessKind::Acquires => "acquires",
+    };
+    format!(
+        "{}{} {}({})",
+        if spec.negated { "!" } else { "" },
+        kind,
+        resource,
+        address
+    )
+}
@@ -0,0 +1,64 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Selection of the target modules for which code is generated, by `Options::module_filter`.
+//! A pattern is matched against the full name of a module, like `0x42::m`, where `*` matches
+//! any sequence of characters, so `0x42::*` selects all modules at `0x42`. If no pattern is
+//! given, all target modules are selected.
+//!
+//! Only the functions of selected modules seed the code generation. Functions of other target
+//! modules which they call are still generated, like those of dependencies, but no unit is
+//! produced for their modules.
+
+use crate::diagnostic_codes::UNMATCHED_MODULE_FILTER;
+use move_model::model::{GlobalEnv, ModuleEnv};
+
+/// Returns true if the module is selected by the patterns.
+pub fn is_selected(module_env: &ModuleEnv, patterns: &[String]) -> bool {
+    if patterns.is_empty() {
+        return true;
+    }
+    let name = module_env.get_full_name_str();
+    patterns.iter().any(|pattern| matches(pattern, &name))
+}
+
+/// Reports an error for each pattern which matches no target module.
+pub fn check_module_filter(env: &GlobalEnv, patterns: &[String]) {
+    let names = env
+        .get_modules()
+        .filter(|module_env| module_env.is_target())
+        .map(|module_env| module_env.get_full_name_str())
+        .collect::<Vec<_>>();
+    for pattern in patterns {
+        if !names.iter().any(|name| matches(pattern, name)) {
+            UNMATCHED_MODULE_FILTER.error_without_loc(
+                env,
+                &format!("module filter `{}` matches no target module", pattern),
+            )
+        }
+    }
+}
+
+/// Returns true if the name matches the pattern, in which `*` matches any sequence of
+/// characters.
+fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // There is always a first part, which must be a prefix of the name.
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    // Without a `*`, the pattern must match the whole name. Otherwise, the last part must be a
+    // suffix, and the parts in between are matched leftmost in the remainder.
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
@@ -2,13 +2,37 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use clap::Parser;
-use codespan_reporting::diagnostic::Severity;
-use std::collections::BTreeSet;
+use crate::{
+    experiments::Experiment,
+    lint,
+    progress::{Progress, ProgressSink},
+};
+use anyhow::Context;
+use clap::{error::ErrorKind, Parser, ValueEnum};
+use codespan_reporting::{diagnostic::Severity, term::termcolor::ColorChoice};
+use move_binary_format::{
+    file_format::CodeOffset,
+    file_format_common::{CONSTANT_SIZE_MAX, VERSION_MAX},
+};
+use move_command_line_common::address::NumericalAddress;
+use move_stackless_bytecode::{
+    function_target::FunctionTarget, function_target_pipeline::FunctionTargetProcessor,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+    rc::Rc,
+};
 
-/// Defines options for a run of the compiler.
-#[derive(Parser, Clone, Debug)]
+/// Defines options for a run of the compiler. Besides parsing them from the command line,
+/// options can be built with `Options::builder`, or loaded from TOML or JSON, where fields
+/// which are not given have their defaults from the command line. Options registered by
+/// embedders, like pipeline extensions, are not serialized.
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
 #[clap(author, version, about)]
+#[serde(default, deny_unknown_fields)]
 pub struct Options {
     /// Directories where to lookup dependencies.
     #[clap(
@@ -16,41 +40,474 @@ pub struct Options {
         num_args = 0..
     )]
     pub dependencies: Vec<String>,
+    /// Compiled modules to use as dependencies, given as `.mv` files or directories
+    /// containing them.
+    #[clap(
+        long = "bytecode-dependency",
+        num_args = 0..
+    )]
+    pub bytecode_dependencies: Vec<String>,
     /// Named address mapping.
     #[clap(
         short,
         num_args = 0..
     )]
     pub named_address_mapping: Vec<String>,
-    /// Output directory.
-    #[clap(short, long, default_value = "")]
-    pub output_dir: String,
+    /// Overrides of the named address mapping for the dependency files under a path prefix,
+    /// as triples of the prefix, the name, and the address. See `dependency_addresses`. On the
+    /// command line, given as `<prefix>:<name>=<address>`.
+    #[clap(
+        long = "dependency-address-override",
+        value_name = "PREFIX:NAME=ADDRESS",
+        num_args = 0..,
+        value_parser = parse_dependency_address_override
+    )]
+    pub dependency_address_overrides: Vec<(String, String, String)>,
+    /// Directory into which the compiled units are written, with their source maps, and ABIs
+    /// if `--emit-abi` is set. See `artifacts` for the layout. Nothing is written if not set.
+    #[clap(short, long)]
+    pub output_dir: Option<PathBuf>,
+    /// A prefix which is stripped from the paths of source files embedded into the output,
+    /// like the package metadata, so the output does not depend on where the package is
+    /// located. Paths which do not start with the prefix are kept as they are.
+    #[clap(long = "path-prefix-strip")]
+    pub path_prefix_strip: Option<PathBuf>,
     /// Whether to dump intermediate bytecode for debugging.
     #[clap(long = "dump-bytecode")]
     pub dump_bytecode: bool,
-    /// Do not complain about unknown attributes in Move code.
+    /// Directory into which intermediate bytecode, and the model and disassembly if
+    /// `--dump-model` and `--dump-disassembly` are set, is dumped. Setting this implies
+    /// `--dump-bytecode`. Defaults to the current working directory.
+    #[clap(long = "dump-bytecode-dir")]
+    pub dump_bytecode_dir: Option<PathBuf>,
+    /// Whether to dump the model of each target module, as built by the checker, into
+    /// `<module>.model` in the dump directory: the attributes and friends of the module, and
+    /// its structs and functions with their resolved types, abilities, access specifiers like
+    /// `acquires`, and the bodies of functions.
+    #[clap(long = "dump-model")]
+    pub dump_model: bool,
+    /// Whether to dump the disassembly of each compiled unit into `<module>.mvasm` in the dump
+    /// directory, with the source lines of the instructions interleaved as comments.
+    #[clap(long = "dump-disassembly")]
+    pub dump_disassembly: bool,
+    /// Whether to dump, after each processor of the bytecode pipeline, only the changes of
+    /// functions relative to the previous stage, instead of the full bytecode. Setting this
+    /// implies `--dump-bytecode`.
+    #[clap(long = "dump-bytecode-diff")]
+    pub dump_bytecode_diff: bool,
+    /// Like `--dump-bytecode-diff`, but also includes changes of annotations, such as live
+    /// variables, in the diffs.
+    #[clap(long = "dump-bytecode-diff-annotations")]
+    pub dump_bytecode_diff_annotations: bool,
+    /// Whether dumped bytecode includes the annotations of the analyses of the pipeline, like
+    /// the live variables before and after each instruction, as comments. See
+    /// `pipeline::annotation_formatters`.
+    #[clap(long = "dump-annotations")]
+    pub dump_annotations: bool,
+    /// Directory of a cache of compiled modules. If set, code is only generated for modules
+    /// whose sources, dependencies, or relevant options changed since the last compilation
+    /// with the same cache. Modules targeting an unreleased bytecode version are not cached.
+    #[clap(long = "compilation-cache-dir")]
+    pub compilation_cache_dir: Option<PathBuf>,
+    /// Directory of a cache of the dependencies, see `dependency_cache`. If set, the checker
+    /// skips the bodies and specs of the dependencies if they did not change since the last
+    /// compilation with the same cache.
+    #[clap(long = "dependency-cache-dir")]
+    pub dependency_cache_dir: Option<PathBuf>,
+    /// Do not complain about unknown attributes in Move code. Prefer `--unchecked-attributes`,
+    /// which only skips the checks of the given attributes.
     #[clap(long, default_value = "false")]
     pub skip_attribute_checks: bool,
+    /// Names of attributes which are accepted without checks, possibly comma separated, like
+    /// experimental attributes. All other attributes are still checked. Has no effect if
+    /// `--skip-attribute-checks` is set.
+    #[clap(long = "unchecked-attributes", value_delimiter = ',', num_args = 0..)]
+    pub unchecked_attributes: Vec<String>,
     /// Known attributes for this dialect of move; if empty, assumes third-party Move.
     /// Only used if skip_attribute_checks is false.
     #[clap(skip)]
     pub known_attributes: BTreeSet<String>,
+    /// Attributes defined by embedders, which are known in addition to `known_attributes`.
+    /// Unlike those, their positions and arguments are checked, see `extra_attributes`.
+    #[clap(skip)]
+    pub extra_known_attributes: Vec<AttributeSpec>,
     /// Whether we generate code for tests. This specifically guarantees stable output
     /// for baseline testing.
     #[clap(long)]
     pub testing: bool,
-    /// Active experiments. Experiments alter default behavior of the compiler.
-    /// See `Experiment` struct.
+    /// Whether to only check the sources, without generating code. No units are returned then;
+    /// `run_move_compiler_check_only` makes this explicit in the result type.
+    #[clap(long = "check")]
+    pub check_only: bool,
+    /// Whether to compile as much as possible: if code cannot be generated for some functions,
+    /// only their modules are not assembled, and the units of the other modules are returned
+    /// along with the errors. Errors found by the checker, or after code generation, still fail
+    /// the compilation. See `partial_compilation`.
+    #[clap(long = "partial-compilation")]
+    pub partial_compilation: bool,
+    /// Whether to leave the private functions of target modules which can never be called out
+    /// of the generated modules, with a note listing them. See `dead_functions`.
+    #[clap(long = "strip-dead-private-functions")]
+    pub strip_dead_private_functions: bool,
+    /// Whether to compile the functions and modules annotated with `#[test]` or `#[test_only]`,
+    /// and to build the test plan for the unit test framework from the `#[test]` functions.
+    #[clap(long = "compile-test-code")]
+    pub compile_test_code: bool,
+    /// The flags of the build configuration, which include or exclude the items annotated
+    /// with `#[cfg_attr(<flag>)]`, see `build_config`. On the command line, given as
+    /// `<flag>=true` or `<flag>=false`, comma separated.
+    #[clap(
+        long = "build-config",
+        value_name = "FLAG=BOOL",
+        default_value = "",
+        value_parser = parse_build_config
+    )]
+    pub build_config: BTreeMap<String, bool>,
+    /// Patterns of the full names of the target modules for which code is generated, like
+    /// `0x42::m` or `0x42::*`, possibly comma separated. Functions of other target modules are
+    /// only generated if called from selected ones, and no units are produced for them. All
+    /// target modules are selected if empty. See `module_filter`.
+    #[clap(long = "module-filter", value_delimiter = ',', num_args = 0..)]
+    pub module_filter: Vec<String>,
+    /// The most verbose level of the tracing spans and events of the compilation, `info` for
+    /// the phases or `debug` for the processors and diagnostics as well. Nothing is traced if
+    /// not set. The spans go to the subscriber installed by the embedder. Defaults to the
+    /// `MOVE_COMPILER_LOG` environment variable.
+    #[clap(long = "trace-filter", env = "MOVE_COMPILER_LOG")]
+    pub trace_filter: Option<String>,
+    /// Whether to print statistics of the compilation, like the time spent in each phase, to
+    /// the error writer.
+    #[clap(long = "print-stats")]
+    pub print_stats: bool,
+    /// Whether to profile the compilation, recording the time each processor of the bytecode
+    /// pipeline spends on each function, and the time of bytecode generation for each module.
+    /// The slowest are reported to the error writer, in `Options::diagnostics_format`.
+    #[clap(long = "profile-compilation")]
+    pub profile_compilation: bool,
+    /// The number of pairs of a function and a processor listed in the profile of the
+    /// compilation, starting with the slowest.
+    #[clap(long = "profile-top", default_value_t = 20)]
+    pub profile_top: usize,
+    /// How much is written to the error writer besides errors. At 0, nothing else is written,
+    /// like warnings, statistics, or profiles. At 1, the output requested by other options is
+    /// written. At 2 and above, also the progress of the compilation, like the phases which
+    /// start, the numbers of modules, and the files which are written. On the command line,
+    /// `-q` stands for `--verbosity 0`, and `-v` and `-vv` for `--verbosity 2` and
+    /// `--verbosity 3`.
+    #[clap(long = "verbosity", value_name = "LEVEL", default_value_t = 1)]
+    pub verbosity: u8,
+    /// Whether to warn about cycles of calls among the functions of the target modules. Move
+    /// allows recursion, so this is off by default.
+    #[clap(long = "warn-recursion")]
+    pub warn_recursion: bool,
+    /// Whether warnings should be treated as errors.
+    #[clap(long)]
+    pub warnings_are_errors: bool,
+    /// Categories of warnings which are not reported, given as lint names, like
+    /// `unused_variable`, or as warning codes, like `W02050`. On the command line, categories
+    /// can also be given as `--Wno-<category>`.
+    #[clap(long = "Wno", value_name = "CATEGORY")]
+    pub suppressed_warnings: Vec<String>,
+    /// Categories of warnings which are reported as errors, given like
+    /// `Options::suppressed_warnings`. Suppression takes precedence.
+    #[clap(long = "Werror", value_name = "CATEGORY")]
+    pub elevated_warnings: Vec<String>,
+    /// Categories of warnings of lints which are off by default, like `shadowing`, which are
+    /// checked, given like `Options::suppressed_warnings`. Suppression takes precedence.
+    #[clap(long = "W", value_name = "CATEGORY")]
+    pub enabled_warnings: Vec<String>,
+    /// The least severity of diagnostics which are reported: `error`, `warning`, `note`, or
+    /// `help`. Errors are always reported. With `--warnings-are-errors`, warnings are promoted
+    /// to errors before diagnostics are filtered by severity. On the command line, `--quiet`
+    /// stands for `--report-severity error`, and `--verbose-diags` for
+    /// `--report-severity help`.
+    #[clap(
+        long = "report-severity",
+        value_name = "SEVERITY",
+        default_value = "warning",
+        value_parser = parse_report_severity
+    )]
+    #[serde(with = "severity_name")]
+    pub report_severity: Severity,
+    /// The greatest number of diagnostics which are shown in the text format. If there are
+    /// more, errors are preferred over warnings, and a note states how many are not shown.
+    /// Whether compilation fails does not depend on which diagnostics are shown. The other
+    /// formats, and SARIF output, always include all diagnostics. No limit applies if not set
+    /// or set to 0.
+    #[clap(long = "max-diagnostics", value_name = "N", default_value = "50")]
+    pub max_diagnostics: Option<usize>,
+    /// When to color diagnostics and other output written to the error writer. Defaults to
+    /// the `MOVE_COMPILER_COLOR` environment variable, and to `auto` if that is not set. See
+    /// `Options::color_choice`.
+    #[clap(
+        long,
+        value_enum,
+        env = "MOVE_COMPILER_COLOR",
+        default_value_t = ColorMode::Auto
+    )]
+    pub color: ColorMode,
+    /// The format in which diagnostics are reported.
+    #[clap(long, value_enum, default_value_t = DiagnosticsFormat::Text)]
+    pub diagnostics_format: DiagnosticsFormat,
+    /// Path of a file into which diagnostics are written as a SARIF log, in addition to
+    /// reporting them in `Options::diagnostics_format`.
+    #[clap(long = "sarif-output")]
+    pub sarif_output: Option<PathBuf>,
+    /// Directory into which the ABI of each target module is written as JSON, in the format
+    /// in which the REST API exposes the ABIs of on-chain modules.
+    #[clap(long = "emit-abi")]
+    pub emit_abi: Option<PathBuf>,
+    /// Path of a file into which the compiled units and the package metadata are written as a
+    /// JSON bundle, from which the package can be published via the REST API, see
+    /// `publish_bundle`.
+    #[clap(long = "emit-publish-bundle")]
+    pub emit_publish_bundle: Option<PathBuf>,
+    /// Directory into which the map from code offsets to source locations of each target
+    /// module is written as JSON, for computing source coverage from VM traces.
+    #[clap(long = "emit-coverage-map")]
+    pub emit_coverage_map: Option<PathBuf>,
+    /// Path of a file into which a catalog of the abort sites of the target modules is written
+    /// as JSON, with their abort codes and the constants declaring them, see `error_map`.
+    /// Modules are not taken from the compilation cache if this is set.
+    #[clap(long = "emit-error-map")]
+    pub emit_error_map: Option<PathBuf>,
+    /// Directory into which the graph of the dependencies among the target modules and their
+    /// dependencies is written as `modules.dot` in Graphviz DOT format, see
+    /// `dependency_graph`.
+    #[clap(long = "emit-dependency-graph")]
+    pub emit_dependency_graph: Option<PathBuf>,
+    /// Whether to also write the call graph of the functions of the target modules as
+    /// `functions.dot` if `emit_dependency_graph` is set.
+    #[clap(long = "dependency-graph-functions")]
+    pub dependency_graph_functions: bool,
+    /// The bytecode version of the generated modules and scripts. Defaults to the latest
+    /// version.
+    #[clap(long = "bytecode-version")]
+    pub bytecode_version: Option<u32>,
+    /// Whether to name the locals of functions in the source map after the variables they
+    /// hold, with temporaries introduced by the compiler named `%<index>`. Otherwise, locals
+    /// are named after their index only.
+    #[clap(long = "emit-debug-info")]
+    pub emit_debug_info: bool,
+    /// Whether to also compile the sources with compiler v1, and to report the differences
+    /// between the units generated by the two compilers at the given level. Compilation fails
+    /// if the units differ in their ABI.
+    #[clap(long = "compare-with-v1", value_enum)]
+    pub compare_with_v1: Option<ComparisonLevel>,
+    /// The optimization level. At level 0, code is generated from the baseline variant of
+    /// the bytecode. At level 1 and above, an optimized variant of each function of the target
+    /// modules is derived from the baseline and code is generated from it instead, so the two
+    /// variants can be compared in dumps. Level 2 additionally runs the peephole optimizer over
+    /// the generated code.
+    #[clap(
+        short = 'O',
+        long = "optimize",
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u8).range(0..=2)
+    )]
+    pub optimize: u8,
+    /// The maximal size in bytes of the serialized value of a constant, like a vector
+    /// literal. Larger constants are reported as errors. The size is always limited by the
+    /// file format, to 65535 bytes.
+    #[clap(long = "max-constant-size")]
+    pub max_constant_size: Option<usize>,
+    /// The maximal depth of the operand stack of a generated function. Functions needing a
+    /// deeper stack are reported as warnings.
+    #[clap(long = "max-stack-depth")]
+    pub max_stack_depth: Option<usize>,
+    /// The maximal depth of the calls a generated function leads to, counting its own frame.
+    /// Functions leading to deeper calls, or to recursive calls, are reported as warnings.
+    #[clap(long = "max-call-depth")]
+    pub max_call_depth: Option<usize>,
+    /// The number of fields above which a struct is considered large by the `expensive_copy`
+    /// lint. Vectors, and structs containing vectors, are always considered large.
+    #[clap(long = "large-struct-fields", default_value_t = 8)]
+    pub large_struct_fields: usize,
+    /// Experiment settings, given as `<name>` or `<name>=on|off`, possibly comma separated.
+    /// Experiments alter default behavior of the compiler. If an experiment is set multiple
+    /// times, the last setting counts. See `Experiment` struct.
     #[clap(short)]
     #[clap(
-        long = "experiment",
+        long = "experiments",
+        visible_alias = "experiment",
+        value_delimiter = ',',
         num_args = 0..
     )]
     pub experiments: Vec<String>,
+    /// Whether to print the known experiments, with their default and description, instead
+    /// of compiling. An empty environment is returned then.
+    #[clap(long = "list-experiments")]
+    pub list_experiments: bool,
+    /// A diagnostic code, like `E02001`, whose explanation to print instead of compiling. An
+    /// empty environment is returned then.
+    #[clap(long)]
+    pub explain: Option<String>,
+    /// Sources to compile which are not read from the file system, as pairs of a virtual
+    /// path and the source text. The virtual path is used as the file name, for example in
+    /// diagnostics.
+    #[clap(skip)]
+    pub source_buffers: Vec<(String, String)>,
+    /// Dependencies which are not read from the file system, as pairs of a virtual path and
+    /// the source text.
+    #[clap(skip)]
+    pub dependency_buffers: Vec<(String, String)>,
+    /// Processors registered by embedders, which are inserted into the bytecode pipeline in
+    /// the given order. See `Options::register_pipeline_extension`.
+    #[clap(skip)]
+    #[serde(skip)]
+    pub pipeline_extensions: Vec<PipelineExtension>,
+    /// Formatters of annotations registered by embedders, which are shown in bytecode dumps
+    /// with `--dump-annotations`. See `Options::register_annotation_formatter`.
+    #[clap(skip)]
+    #[serde(skip)]
+    pub annotation_formatters: Vec<AnnotationFormatterExtension>,
+    /// The sink receiving the progress of the compilation, see `progress`. Set with
+    /// `Options::set_progress_sink`; without a sink, progress is not reported.
+    #[clap(skip)]
+    #[serde(skip)]
+    pub progress: Progress,
     /// Sources to compile (positional arg, therefore last)
     pub sources: Vec<String>,
 }
 
+/// The format in which diagnostics are reported.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticsFormat {
+    /// Human readable text, as rendered by `codespan_reporting`.
+    #[default]
+    Text,
+    /// One JSON object per line and diagnostic.
+    Json,
+}
+
+/// When to color output.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Color if writing to a terminal, and the `NO_COLOR` environment variable is not set.
+    #[default]
+    Auto,
+    /// Always color.
+    Always,
+    /// Never color.
+    Never,
+}
+
+/// What to compare of the units generated by compiler v1 and v2, see `compiler_comparison`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparisonLevel {
+    /// Compare the units and the signatures of their functions and structs.
+    Abi,
+    /// Additionally compare the code of functions.
+    Code,
+}
+
+/// The position at which a processor registered by an embedder is inserted into the bytecode
+/// pipeline. Processors are referred to by `FunctionTargetProcessor::name`, which includes
+/// previously inserted processors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProcessorPosition {
+    /// Before the named processor.
+    Before(String),
+    /// After the named processor.
+    After(String),
+    /// After all other processors.
+    End,
+}
+
+/// A processor registered by an embedder. Since a pipeline owns its processors, and a new
+/// pipeline is created for each compilation, the processor is given by a function creating it.
+#[derive(Clone)]
+pub struct PipelineExtension {
+    pub position: ProcessorPosition,
+    pub processor: Rc<dyn Fn() -> Box<dyn FunctionTargetProcessor>>,
+}
+
+/// A formatter of an annotation registered by an embedder, which renders the annotation at a
+/// code offset of a function, if the function has it.
+#[derive(Clone)]
+pub struct AnnotationFormatterExtension {
+    pub formatter: Rc<dyn Fn(&FunctionTarget, CodeOffset) -> Option<String>>,
+}
+
+/// An attribute defined by an embedder, like `#[event]` for a framework, with the items it may
+/// be attached to and the shape of its arguments.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeSpec {
+    pub name: String,
+    pub positions: BTreeSet<AttributePosition>,
+    pub arguments: AttributeArguments,
+}
+
+/// An item an attribute can be attached to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributePosition {
+    /// A module or a script.
+    Module,
+    Function,
+    Struct,
+}
+
+/// The shape of the arguments of an attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeArguments {
+    /// No arguments, as in `#[name]`.
+    None,
+    /// A non-empty list of names or nested attributes, as in `#[name(a, b = 1)]`.
+    List,
+    /// A value, as in `#[name = 1]` or `#[name = 0x1::m::S]`.
+    Value,
+    /// Any of the above.
+    Any,
+}
+
+impl fmt::Debug for PipelineExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipelineExtension")
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for AnnotationFormatterExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnnotationFormatterExtension")
+            .finish_non_exhaustive()
+    }
+}
+
+/// An invalid combination or value of options, found when options are built, loaded, or
+/// parsed from the command line. Options are referred to by their command line names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionsError {
+    /// Two options were set which cannot be combined.
+    Conflict(&'static str, &'static str),
+    /// An option has a value out of its range.
+    InvalidValue {
+        option: &'static str,
+        message: String,
+    },
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::Conflict(first, second) => {
+                write!(f, "`{}` cannot be combined with `{}`", first, second)
+            },
+            OptionsError::InvalidValue { option, message } => {
+                write!(f, "invalid value of `{}`: {}", option, message)
+            },
+        }
+    }
+}
+
+impl std::error::Error for OptionsError {}
+
 impl Default for Options {
     fn default() -> Self {
         Parser::parse_from(std::iter::empty::<String>())
@@ -58,14 +515,454 @@ impl Default for Options {
 }
 
 impl Options {
-    /// Returns the least severity of diagnosis which shall be reported.
-    /// This is currently hardwired.
+    /// Parses options from command line arguments, like `Options::try_parse_from`, but also
+    /// accepts warning categories given as `--Wno-<category>`, which clap cannot express, and
+    /// the shorthands `--quiet` and `--verbose-diags` for `--report-severity`, and `-q`, `-v`,
+    /// and `-vv` for `--verbosity`.
+    pub fn try_parse_command_line<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let options = Self::try_parse_from(args.into_iter().map(|arg| {
+            let arg = arg.into();
+            match arg.strip_prefix("--Wno-") {
+                Some(category) => format!("--Wno={}", category),
+                None if arg == "--quiet" => "--report-severity=error".to_owned(),
+                None if arg == "--verbose-diags" => "--report-severity=help".to_owned(),
+                None if arg == "-q" => "--verbosity=0".to_owned(),
+                None if arg == "-v" => "--verbosity=2".to_owned(),
+                None if arg == "-vv" => "--verbosity=3".to_owned(),
+                None => arg,
+            }
+        }))?;
+        // Validated like built options, so the command line accepts the same combinations.
+        options.validate().map_err(|err| {
+            let kind = match err {
+                OptionsError::Conflict(..) => ErrorKind::ArgumentConflict,
+                OptionsError::InvalidValue { .. } => ErrorKind::ValueValidation,
+            };
+            clap::Error::raw(kind, format!("{}\n", err))
+        })?;
+        Ok(options)
+    }
+
+    /// Returns a builder of options, starting from the defaults.
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder::default()
+    }
+
+    /// Loads options from a TOML file. Options not given in the file have their defaults.
+    pub fn from_toml_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("cannot read options from `{}`", path.display()))?;
+        Self::from_toml_str(&content)
+            .with_context(|| format!("cannot read options from `{}`", path.display()))
+    }
+
+    /// Parses options from TOML. Options not given have their defaults.
+    pub fn from_toml_str(s: &str) -> anyhow::Result<Self> {
+        let options: Options = toml::from_str(s)?;
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Parses options from JSON. Options not given have their defaults.
+    pub fn from_json_str(s: &str) -> anyhow::Result<Self> {
+        let options: Options = serde_json::from_str(s)?;
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Returns the options as TOML, which `Options::from_toml_str` reads back. Options
+    /// registered by embedders are not included.
+    pub fn to_toml_string(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Checks that the options can be combined, and that their values are in range.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        if self.check_only {
+            let outputs = [
+                ("--output-dir", self.output_dir.is_some()),
+                ("--emit-abi", self.emit_abi.is_some()),
+                ("--emit-publish-bundle", self.emit_publish_bundle.is_some()),
+                ("--emit-coverage-map", self.emit_coverage_map.is_some()),
+                ("--emit-error-map", self.emit_error_map.is_some()),
+                ("--compare-with-v1", self.compare_with_v1.is_some()),
+                ("--dump-disassembly", self.dump_disassembly),
+            ];
+            if let Some((option, _)) = outputs.into_iter().find(|(_, set)| *set) {
+                return Err(OptionsError::Conflict("--check", option));
+            }
+        }
+        if self.optimize > 2 {
+            return Err(OptionsError::InvalidValue {
+                option: "--optimize",
+                message: format!("expected a level from 0 to 2, found {}", self.optimize),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the least severity of diagnosis which shall be reported. This is
+    /// `Options::report_severity`, but at most `Severity::Error`, so errors are always
+    /// reported.
     pub fn report_severity(&self) -> Severity {
-        Severity::Warning
+        if self.report_severity > Severity::Error {
+            Severity::Error
+        } else {
+            self.report_severity
+        }
+    }
+
+    /// Returns the greatest number of diagnostics which are shown, if limited. This is
+    /// `Options::max_diagnostics`, where 0 stands for no limit.
+    pub fn max_diagnostics(&self) -> Option<usize> {
+        self.max_diagnostics.filter(|max| *max > 0)
+    }
+
+    /// Returns whether output written to a writer, which is a terminal if `is_terminal` is
+    /// set, is colored. This is `Options::color`, where `auto` colors only if writing to a
+    /// terminal and the `NO_COLOR` environment variable is not set to a non-empty value.
+    pub fn color_choice(&self, is_terminal: bool) -> ColorChoice {
+        let no_color = std::env::var_os("NO_COLOR").map_or(false, |v| !v.is_empty());
+        match self.color {
+            ColorMode::Always => ColorChoice::Always,
+            ColorMode::Auto if is_terminal && !no_color => ColorChoice::Always,
+            ColorMode::Auto | ColorMode::Never => ColorChoice::Never,
+        }
+    }
+
+    /// Returns the bytecode version of the generated code.
+    pub fn target_bytecode_version(&self) -> u32 {
+        self.bytecode_version.unwrap_or(VERSION_MAX)
+    }
+
+    /// Returns the maximal size in bytes of the serialized value of a constant: the size set
+    /// with `max_constant_size`, but at most the limit of the file format.
+    pub fn constant_size_limit(&self) -> usize {
+        self.max_constant_size
+            .unwrap_or(usize::MAX)
+            .min(CONSTANT_SIZE_MAX as usize)
+    }
+
+    /// Returns the settings which affect the generated code, as `<name>=<value>` strings: the
+    /// state of each known experiment, the targeted bytecode version, the optimization level,
+    /// whether test code is compiled, whether debug info is emitted, whether dead private
+    /// functions are stripped, the maximal constant size if set, and the flags of the build
+    /// configuration if any.
+    pub fn code_generation_flags(&self) -> Vec<String> {
+        let mut flags = Experiment::definitions()
+            .iter()
+            .map(|def| {
+                let state = if self.experiment_on(def.name) {
+                    "on"
+                } else {
+                    "off"
+                };
+                format!("{}={}", def.name, state)
+            })
+            .collect::<Vec<_>>();
+        flags.push(format!(
+            "bytecode-version={}",
+            self.target_bytecode_version()
+        ));
+        flags.push(format!("optimize={}", self.optimize));
+        flags.push(format!("compile-test-code={}", self.compile_test_code));
+        flags.push(format!("emit-debug-info={}", self.emit_debug_info));
+        flags.push(format!(
+            "strip-dead-private-functions={}",
+            self.strip_dead_private_functions
+        ));
+        if let Some(size) = self.max_constant_size {
+            flags.push(format!("max-constant-size={}", size))
+        }
+        if !self.build_config.is_empty() {
+            let config = self
+                .build_config
+                .iter()
+                .map(|(flag, value)| format!("{}={}", flag, value))
+                .collect::<Vec<_>>()
+                .join(",");
+            flags.push(format!("build-config={}", config))
+        }
+        flags
+    }
+
+    /// Returns the path of a source file as embedded into the output, relative to
+    /// `path_prefix_strip` if it is set, and with `/` as separator on all platforms.
+    pub fn embedded_path(&self, path: &str) -> String {
+        let path = Path::new(path);
+        let path = self
+            .path_prefix_strip
+            .as_ref()
+            .and_then(|prefix| path.strip_prefix(prefix).ok())
+            .unwrap_or(path);
+        path.to_string_lossy().replace(MAIN_SEPARATOR, "/")
     }
 
-    /// Returns true if an experiment is on.
+    /// Registers a processor to be inserted into the bytecode pipeline at the given position.
+    pub fn register_pipeline_extension(
+        &mut self,
+        position: ProcessorPosition,
+        processor: impl Fn() -> Box<dyn FunctionTargetProcessor> + 'static,
+    ) {
+        self.pipeline_extensions.push(PipelineExtension {
+            position,
+            processor: Rc::new(processor),
+        })
+    }
+
+    /// Registers a formatter of an annotation, like one attached by a processor registered with
+    /// `Options::register_pipeline_extension`, which is shown in bytecode dumps.
+    pub fn register_annotation_formatter(
+        &mut self,
+        formatter: impl Fn(&FunctionTarget, CodeOffset) -> Option<String> + 'static,
+    ) {
+        self.annotation_formatters
+            .push(AnnotationFormatterExtension {
+                formatter: Rc::new(formatter),
+            })
+    }
+
+    /// Sets the sink receiving the progress of the compilation.
+    pub fn set_progress_sink(&mut self, sink: impl ProgressSink + 'static) {
+        self.progress = Progress::new(sink)
+    }
+
+    /// Returns true if an experiment is on, either by its last setting in
+    /// `Options::experiments`, or by its default in the registry.
     pub fn experiment_on(&self, name: &str) -> bool {
-        self.experiments.iter().any(|s| s == name)
+        self.experiments
+            .iter()
+            .rev()
+            .filter_map(|setting| Experiment::parse_setting(setting).ok())
+            .find(|(n, _)| *n == name)
+            .map(|(_, on)| on)
+            .unwrap_or_else(|| Experiment::find(name).map_or(false, |def| def.default))
+    }
+
+    /// Returns the errors in the experiment settings, namely unknown experiment names and
+    /// invalid values.
+    pub fn experiment_errors(&self) -> Vec<String> {
+        self.experiments
+            .iter()
+            .filter(|setting| !setting.trim().is_empty())
+            .filter_map(|setting| match Experiment::parse_setting(setting) {
+                Ok((name, _)) if Experiment::find(name).is_none() => Some(format!(
+                    "unknown experiment `{}`, see `--list-experiments` for known ones",
+                    name
+                )),
+                Ok(_) => None,
+                Err(err) => Some(err),
+            })
+            .collect()
+    }
+
+    /// Returns an error message for each warning category in `Options::suppressed_warnings`,
+    /// `Options::elevated_warnings`, and `Options::enabled_warnings` which is not known.
+    pub fn warning_category_errors(&self) -> Vec<String> {
+        self.suppressed_warnings
+            .iter()
+            .chain(&self.elevated_warnings)
+            .chain(&self.enabled_warnings)
+            .filter(|category| !lint::is_warning_category(category))
+            .map(|category| {
+                format!(
+                    "unknown warning category `{}`, expected a lint name like `unused_variable` \
+                     or a warning code like `W02050`",
+                    category
+                )
+            })
+            .collect()
+    }
+}
+
+/// A builder of options, created with `Options::builder`. Invalid combinations of options are
+/// reported when the options are built, see `Options::validate`.
+#[derive(Debug, Default)]
+pub struct OptionsBuilder {
+    options: Options,
+}
+
+impl OptionsBuilder {
+    /// Adds source files or directories to compile.
+    pub fn sources(mut self, sources: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.options
+            .sources
+            .extend(sources.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds a source file or directory to compile.
+    pub fn source(self, source: impl Into<String>) -> Self {
+        self.sources([source])
+    }
+
+    /// Adds a source to compile which is not read from the file system.
+    pub fn source_buffer(mut self, path: impl Into<String>, text: impl Into<String>) -> Self {
+        self.options.source_buffers.push((path.into(), text.into()));
+        self
+    }
+
+    /// Adds a directory where to lookup dependencies.
+    pub fn dependency(self, dependency: impl Into<String>) -> Self {
+        self.dependencies([dependency])
+    }
+
+    /// Adds directories where to lookup dependencies.
+    pub fn dependencies(
+        mut self,
+        dependencies: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.options
+            .dependencies
+            .extend(dependencies.into_iter().map(Into::into));
+        self
+    }
+
+    /// Maps a named address, like `std` to `0x1`.
+    pub fn named_address(mut self, name: &str, address: &str) -> Self {
+        self.options
+            .named_address_mapping
+            .push(format!("{}={}", name, address));
+        self
+    }
+
+    /// Maps a named address to another address for the dependency files under a path prefix.
+    pub fn dependency_address_override(mut self, prefix: &str, name: &str, address: &str) -> Self {
+        self.options.dependency_address_overrides.push((
+            prefix.to_owned(),
+            name.to_owned(),
+            address.to_owned(),
+        ));
+        self
+    }
+
+    /// Adds an experiment setting, given as `<name>` or `<name>=on|off`.
+    pub fn experiment(mut self, setting: impl Into<String>) -> Self {
+        self.options.experiments.push(setting.into());
+        self
+    }
+
+    /// Sets the directory into which the compiled units are written.
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.options.output_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets whether to only check the sources.
+    pub fn check_only(mut self, check_only: bool) -> Self {
+        self.options.check_only = check_only;
+        self
+    }
+
+    /// Sets whether to compile test code.
+    pub fn compile_test_code(mut self, compile_test_code: bool) -> Self {
+        self.options.compile_test_code = compile_test_code;
+        self
+    }
+
+    /// Sets the optimization level.
+    pub fn optimize(mut self, level: u8) -> Self {
+        self.options.optimize = level;
+        self
+    }
+
+    /// Sets the bytecode version of the generated code.
+    pub fn bytecode_version(mut self, version: u32) -> Self {
+        self.options.bytecode_version = Some(version);
+        self
+    }
+
+    /// Sets whether warnings are treated as errors.
+    pub fn warnings_are_errors(mut self, warnings_are_errors: bool) -> Self {
+        self.options.warnings_are_errors = warnings_are_errors;
+        self
+    }
+
+    /// Modifies options which have no method of their own.
+    pub fn with(mut self, f: impl FnOnce(&mut Options)) -> Self {
+        f(&mut self.options);
+        self
+    }
+
+    /// Returns the options, or the first invalid combination of them.
+    pub fn build(self) -> Result<Options, OptionsError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+/// Parses the flags of `Options::build_config`, given as `<flag>=<bool>`, comma separated.
+fn parse_build_config(s: &str) -> Result<BTreeMap<String, bool>, String> {
+    s.split(',')
+        .filter(|setting| !setting.is_empty())
+        .map(|setting| {
+            let parsed = setting
+                .split_once('=')
+                .and_then(|(flag, value)| Some((flag, value.parse::<bool>().ok()?)));
+            match parsed {
+                Some((flag, value)) if !flag.is_empty() => Ok((flag.to_owned(), value)),
+                _ => Err(format!(
+                    "expected `<flag>=true` or `<flag>=false`, found `{}`",
+                    setting
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Parses an override of `Options::dependency_address_overrides`, given as
+/// `<prefix>:<name>=<address>`.
+fn parse_dependency_address_override(s: &str) -> Result<(String, String, String), String> {
+    let parsed = s.split_once('=').and_then(|(target, address)| {
+        let (prefix, name) = target.rsplit_once(':')?;
+        Some((prefix, name, address))
+    });
+    match parsed {
+        Some((prefix, name, address)) if !prefix.is_empty() && !name.is_empty() => {
+            NumericalAddress::parse_str(address)?;
+            Ok((prefix.to_owned(), name.to_owned(), address.to_owned()))
+        },
+        _ => Err(format!(
+            "expected `<prefix>:<name>=<address>`, found `{}`",
+            s
+        )),
+    }
+}
+
+/// Parses a severity of `Options::report_severity`. Bugs are not accepted, since they are
+/// more severe than errors, which are always reported.
+fn parse_report_severity(s: &str) -> Result<Severity, String> {
+    match s {
+        "error" => Ok(Severity::Error),
+        "warning" => Ok(Severity::Warning),
+        "note" => Ok(Severity::Note),
+        "help" => Ok(Severity::Help),
+        _ => Err("expected `error`, `warning`, `note`, or `help`".to_owned()),
+    }
+}
+
+/// The representation of `Options::report_severity` in serialized options, by the names of the
+/// command line.
+mod severity_name {
+    use codespan_reporting::diagnostic::Severity;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(severity: &Severity, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match severity {
+            Severity::Bug | Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+            Severity::Help => "help",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Severity, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        super::parse_report_severity(&name).map_err(D::Error::custom)
     }
 }
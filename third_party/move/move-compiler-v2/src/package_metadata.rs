@@ -0,0 +1,154 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metadata of the compiled units of a package, which allows to verify that published code
+//! was compiled from given sources. The metadata records the compiler and the options which
+//! affect code generation, the named address assignment, and for each compiled module and
+//! script the digest of its source file, as computed for `make_files_source_text`. The digest
+//! of a module does not depend on its dependencies, so changes of dependencies only change
+//! the list of dependencies.
+//!
+//! The digest of the package is computed over the digests of the compiled units, in the
+//! order of their names, so it does not depend on the order in which units are compiled.
+
+use crate::{make_files_source_text, Options, UnitModuleIndex};
+use anyhow::{bail, ensure};
+use move_command_line_common::files::FileHash;
+use move_compiler::compiled_unit::AnnotatedCompiledUnit;
+use move_model::model::GlobalEnv;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The metadata of a compiled package.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageMetadata {
+    /// The name and version of the compiler.
+    pub compiler_version: String,
+    /// The options which affect code generation, see `Options::code_generation_flags`.
+    pub flags: Vec<String>,
+    /// The digest over the source digests of all units.
+    pub source_digest: String,
+    /// The compiled modules and scripts, ordered by name.
+    pub units: Vec<UnitMetadata>,
+    /// The modules the package was compiled against, which are not part of it, by name.
+    pub dependencies: Vec<String>,
+    /// The assignment of named addresses.
+    pub named_addresses: BTreeMap<String, String>,
+}
+
+/// The metadata of a compiled module or script.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnitMetadata {
+    /// The name of the unit, which is the full name for modules, like `0x42::m`, and the
+    /// name of the function for scripts.
+    pub name: String,
+    /// The path of the source file, relative to `Options::path_prefix_strip` if set.
+    pub source_path: String,
+    /// The digest of the source file.
+    pub source_digest: String,
+}
+
+/// Builds the metadata of the given units, which are the result of compiling the target
+/// modules and scripts of the environment with the options.
+pub fn build_package_metadata(
+    env: &GlobalEnv,
+    units: &[AnnotatedCompiledUnit],
+    opts: &Options,
+) -> PackageMetadata {
+    let files = make_files_source_text(env);
+    let index = UnitModuleIndex::new(env);
+    let mut unit_metadata = units
+        .iter()
+        .filter_map(|unit| {
+            let (name, loc) = match unit {
+                AnnotatedCompiledUnit::Module(module) => {
+                    let module_env = index.find_module(&module.named_module.module.self_id())?;
+                    (module_env.get_full_name_str(), module_env.get_loc())
+                },
+                AnnotatedCompiledUnit::Script(script) => {
+                    let name = script.named_script.name;
+                    let module_env = index.find_script(name.as_str())?;
+                    (name.to_string(), module_env.get_loc())
+                },
+            };
+            let hash = env.get_file_hash(loc.file_id())?;
+            let (path, _) = files.get(&hash)?;
+            Some(UnitMetadata {
+                name,
+                source_path: opts.embedded_path(path.as_str()),
+                source_digest: hash.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    unit_metadata.sort_by(|u1, u2| u1.name.cmp(&u2.name));
+    let dependencies = env
+        .get_modules()
+        .filter(|module_env| !module_env.is_target())
+        .map(|module_env| module_env.get_full_name_str())
+        .collect();
+    let named_addresses = opts
+        .named_address_mapping
+        .iter()
+        .filter_map(|mapping| mapping.split_once('='))
+        .map(|(name, addr)| (name.trim().to_owned(), addr.trim().to_owned()))
+        .collect();
+    PackageMetadata {
+        compiler_version: compiler_version(),
+        flags: opts.code_generation_flags(),
+        source_digest: package_digest(&unit_metadata),
+        units: unit_metadata,
+        dependencies,
+        named_addresses,
+    }
+}
+
+/// Verifies that the metadata describes units compiled from the given sources, which are
+/// pairs of the path and the content of a source file, as in `Options::source_buffers`, with
+/// paths relative to the `Options::path_prefix_strip` the metadata was built with, if any.
+/// Fails if the source of a unit is missing or has a different digest, or if the digest of
+/// the package does not match the units.
+pub fn verify_metadata(
+    metadata: &PackageMetadata,
+    sources: &[(String, String)],
+) -> anyhow::Result<()> {
+    let digests = sources
+        .iter()
+        .map(|(path, content)| (path.as_str(), FileHash::new(content).to_string()))
+        .collect::<BTreeMap<_, _>>();
+    for unit in &metadata.units {
+        let Some(digest) = digests.get(unit.source_path.as_str()) else {
+            bail!(
+                "source `{}` of `{}` is missing",
+                unit.source_path,
+                unit.name
+            )
+        };
+        ensure!(
+            *digest == unit.source_digest,
+            "source `{}` of `{}` has digest {}, but the metadata records {}",
+            unit.source_path,
+            unit.name,
+            digest,
+            unit.source_digest
+        );
+    }
+    ensure!(
+        package_digest(&metadata.units) == metadata.source_digest,
+        "package digest {} does not match the digests of the units",
+        metadata.source_digest
+    );
+    Ok(())
+}
+
+fn compiler_version() -> String {
+    format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
+
+/// Computes the digest of a package from the digests of its units, which are ordered by name.
+fn package_digest(units: &[UnitMetadata]) -> String {
+    let text = units
+        .iter()
+        .map(|unit| format!("{} {}\n", unit.name, unit.source_digest))
+        .collect::<String>();
+    FileHash::new(&text).to_string()
+}
@@ -0,0 +1,54 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Best-effort compilation, with `Options::partial_compilation`. If code cannot be generated
+//! for some functions of a target module, only that module is excluded from file format
+//! generation, and the other modules are still compiled. The functions of an excluded module
+//! for which code was generated still run through the bytecode pipeline, so analyses of their
+//! callers, like acquires inference, see them.
+//!
+//! Errors found after code generation, like those of the bytecode pipeline, still fail the
+//! compilation, since they cannot always be attributed to a single module.
+
+use crate::{compilation_cache::CompilationCache, module_filter, Options};
+use move_model::model::{GlobalEnv, ModuleEnv, ModuleId};
+use move_stackless_bytecode::function_target_pipeline::{FunctionTargetsHolder, FunctionVariant};
+use std::collections::BTreeSet;
+
+/// The target modules excluded from file format generation, installed as an extension of the
+/// env.
+#[derive(Debug, Default)]
+pub(crate) struct FailedModules(BTreeSet<ModuleId>);
+
+/// Excludes the target modules with functions for which no code was generated from file format
+/// generation. Returns false if there are no such modules, so the errors of code generation
+/// are not attributed to any module and the compilation fails as usual.
+pub(crate) fn exclude_failed_modules(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> bool {
+    let options = env.get_extension::<Options>().unwrap_or_default();
+    let cache = env.get_extension::<CompilationCache>();
+    let failed = env
+        .get_modules()
+        .filter(|module_env| {
+            module_env.is_target()
+                && module_filter::is_selected(module_env, &options.module_filter)
+                && !cache
+                    .as_ref()
+                    .is_some_and(|c| c.is_cached(module_env.get_id()))
+                && module_env
+                    .get_functions()
+                    .any(|fun_env| !targets.has_target(&fun_env, &FunctionVariant::Baseline))
+        })
+        .map(|module_env| module_env.get_id())
+        .collect::<BTreeSet<_>>();
+    if failed.is_empty() {
+        return false;
+    }
+    env.set_extension(FailedModules(failed));
+    true
+}
+
+/// Returns true if the module was excluded from file format generation.
+pub(crate) fn is_failed(env: &GlobalEnv, module_env: &ModuleEnv) -> bool {
+    env.get_extension::<FailedModules>()
+        .is_some_and(|failed| failed.0.contains(&module_env.get_id()))
+}
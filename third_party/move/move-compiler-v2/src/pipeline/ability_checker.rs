@@ -0,0 +1,438 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements an ability checker, which ensures that the code passed on to the file format
+//! generator respects the `copy`, `drop`, `store`, and `key` abilities of types. The checker
+//! mirrors how the file format generator treats temporaries:
+//!
+//! - a temporary which is read and still alive afterwards, or which is read twice by the same
+//!   instruction, is copied, and therefore requires `copy`;
+//! - a temporary which is defined but not alive afterwards is popped, and a temporary which
+//!   dies on an edge of the control flow graph stays in its local, so both require `drop` (in
+//!   the second case only if the function may return before the local is overwritten);
+//! - reading and writing through references require `copy` and `drop` of the referenced type,
+//!   as does comparing values for equality;
+//! - global storage operations require `key`, and instantiations of structs and functions
+//!   must satisfy the constraints of the type parameters.
+//!
+//! In addition, struct declarations of target modules are checked for field types which do
+//! not have the abilities the struct is declared with.
+//!
+//! The checker must run after `LiveVarAnalysisProcessor`, and after any processor which changes
+//! the code, so it sees the same liveness information as the file format generator.
+
+use crate::{bytecode_generator::DeclaredLocals, diagnostic_codes};
+use move_binary_format::file_format::{Ability, AbilitySet, CodeOffset};
+use move_model::{
+    ast::TempIndex,
+    model::{FunctionEnv, GlobalEnv, Loc, StructEnv, TypeParameter, TypeParameterKind},
+    ty::{Type, TypeDisplayContext},
+};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis::LiveVarAnnotation,
+    stackless_bytecode::{Bytecode, Label, Operation},
+};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+pub struct AbilityChecker();
+
+impl FunctionTargetProcessor for AbilityChecker {
+    fn initialize(&self, env: &GlobalEnv, _targets: &mut FunctionTargetsHolder) {
+        for module_env in env.get_modules() {
+            if module_env.is_target() {
+                for struct_env in module_env.get_structs() {
+                    check_struct(&struct_env)
+                }
+            }
+        }
+    }
+
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() || !fun_env.module_env.is_target() {
+            return data;
+        }
+        let target = FunctionTarget::new(fun_env, &data);
+        let live_vars = target
+            .get_annotations()
+            .get::<LiveVarAnnotation>()
+            .expect("live variable annotation");
+        let checker = FunctionChecker {
+            target: &target,
+            type_params: fun_env.get_type_parameters(),
+            type_display_ctx: fun_env.get_type_display_ctx(),
+            live_vars,
+            declared: target.get_annotations().get::<DeclaredLocals>(),
+            label_offsets: Bytecode::label_offsets(target.get_bytecode()),
+        };
+        checker.check();
+        data
+    }
+
+    fn name(&self) -> String {
+        "AbilityChecker".to_owned()
+    }
+}
+
+/// Checks that the fields of a struct have the abilities required by the abilities the struct
+/// is declared with, and that the field types satisfy type parameter constraints.
+fn check_struct(struct_env: &StructEnv) {
+    let env = struct_env.module_env.env;
+    let type_params = struct_env.get_type_parameters();
+    // The abilities of a struct are conditional on the abilities of its type arguments, so
+    // type parameters are assumed to have all abilities when checking fields.
+    let unconstrained_params = type_params
+        .iter()
+        .map(|TypeParameter(name, kind)| {
+            TypeParameter(*name, TypeParameterKind {
+                abilities: AbilitySet::ALL,
+                is_phantom: kind.is_phantom,
+            })
+        })
+        .collect::<Vec<_>>();
+    let type_display_ctx = TypeDisplayContext::new_with_params(
+        env,
+        type_params
+            .iter()
+            .map(|TypeParameter(name, _)| *name)
+            .collect(),
+    );
+    for field_env in struct_env.get_fields() {
+        let field_ty = field_env.get_type();
+        let loc = field_env.get_loc();
+        check_instantiations(env, loc, &field_ty, type_params, &type_display_ctx);
+        let field_abilities = env.type_abilities(&field_ty, &unconstrained_params);
+        for ability in struct_env.get_abilities() {
+            let required = ability.requires();
+            if !field_abilities.has_ability(required) {
+                diagnostic_codes::FIELD_MISSING_ABILITY.error(
+                    env,
+                    loc,
+                    &format!(
+                        "field `{}` of type `{}` does not have the `{}` ability required by \
+                         struct `{}`",
+                        field_env.get_name().display(env.symbol_pool()),
+                        field_ty.display(&type_display_ctx),
+                        ability_name(required),
+                        struct_env.get_full_name_str(),
+                    ),
+                )
+            }
+        }
+    }
+}
+
+/// Checks that all struct instantiations in the given type satisfy the constraints of the
+/// struct type parameters.
+fn check_instantiations(
+    env: &GlobalEnv,
+    loc: &Loc,
+    ty: &Type,
+    type_params: &[TypeParameter],
+    type_display_ctx: &TypeDisplayContext,
+) {
+    match ty {
+        Type::Struct(mid, sid, inst) => {
+            let struct_env = env.get_struct(mid.qualified(*sid));
+            check_type_args(
+                env,
+                loc,
+                &format!("struct `{}`", struct_env.get_full_name_str()),
+                struct_env.get_type_parameters(),
+                inst,
+                type_params,
+                type_display_ctx,
+            )
+        },
+        Type::Vector(elem_ty) | Type::Reference(_, elem_ty) => {
+            check_instantiations(env, loc, elem_ty, type_params, type_display_ctx)
+        },
+        Type::Tuple(tys) => {
+            for ty in tys {
+                check_instantiations(env, loc, ty, type_params, type_display_ctx)
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Checks that the type arguments satisfy the constraints of the declared type parameters
+/// of a struct or function, and that they are themselves well-formed.
+fn check_type_args(
+    env: &GlobalEnv,
+    loc: &Loc,
+    owner: &str,
+    declared_params: &[TypeParameter],
+    type_args: &[Type],
+    type_params: &[TypeParameter],
+    type_display_ctx: &TypeDisplayContext,
+) {
+    for (TypeParameter(name, kind), ty) in declared_params.iter().zip(type_args) {
+        check_instantiations(env, loc, ty, type_params, type_display_ctx);
+        if !is_checkable(ty) {
+            continue;
+        }
+        let abilities = env.type_abilities(ty, type_params);
+        for ability in kind.abilities {
+            if !abilities.has_ability(ability) {
+                diagnostic_codes::TYPE_ARGUMENT_MISSING_ABILITY.error(
+                    env,
+                    loc,
+                    &format!(
+                        "type `{}` is missing required ability `{}` (required by type \
+                         parameter `{}` of {})",
+                        ty.display(type_display_ctx),
+                        ability_name(ability),
+                        name.display(env.symbol_pool()),
+                        owner
+                    ),
+                )
+            }
+        }
+    }
+}
+
+/// Returns true if the type is fully known, so its abilities can be checked. Types which
+/// contain errors or unresolved type variables have been reported by the type checker.
+pub(crate) fn is_checkable(ty: &Type) -> bool {
+    let mut checkable = true;
+    ty.visit(&mut |t| checkable = checkable && !matches!(t, Type::Var(_) | Type::Error));
+    checkable
+}
+
+pub(crate) fn ability_name(ability: Ability) -> &'static str {
+    match ability {
+        Ability::Copy => "copy",
+        Ability::Drop => "drop",
+        Ability::Store => "store",
+        Ability::Key => "key",
+    }
+}
+
+struct FunctionChecker<'a> {
+    target: &'a FunctionTarget<'a>,
+    type_params: Vec<TypeParameter>,
+    type_display_ctx: TypeDisplayContext<'a>,
+    live_vars: &'a LiveVarAnnotation,
+    declared: Option<&'a DeclaredLocals>,
+    label_offsets: BTreeMap<Label, CodeOffset>,
+}
+
+impl<'a> FunctionChecker<'a> {
+    fn env(&self) -> &GlobalEnv {
+        self.target.global_env()
+    }
+
+    /// Checks the code of the function.
+    fn check(&self) {
+        let code = self.target.get_bytecode();
+        // The types of declared variables, including parameters, must be well-formed.
+        if let Some(declared) = self.declared {
+            for (temp, (_, loc)) in &declared.vars {
+                self.check_instantiations(loc, self.target.get_local_type(*temp))
+            }
+        }
+        self.check_instantiations(
+            &self.target.func_env.get_loc(),
+            &self.target.func_env.get_result_type(),
+        );
+        // Parameters which are not used are dropped at function entry.
+        if let Some(info) = self.live_vars.get_live_var_info_at(0) {
+            for param in 0..self.target.get_parameter_count() {
+                if !info.before.contains(&param) && self.may_be_released(0, param) {
+                    self.check_temp(&self.temp_loc(param, None), param, Ability::Drop)
+                }
+            }
+        }
+        for (offset, bc) in code.iter().enumerate() {
+            let offset = offset as CodeOffset;
+            // Code which is not reachable has no live variable information.
+            let Some(info) = self.live_vars.get_live_var_info_at(offset) else {
+                continue;
+            };
+            let loc = self.target.get_bytecode_loc(bc.get_attr_id());
+            self.check_operation(&loc, bc);
+            // Temporaries which are still alive after this instruction, or are read again
+            // by it, are copied.
+            let srcs = Self::consumed_temps(bc);
+            let mut copied = BTreeSet::new();
+            for (i, temp) in srcs.iter().enumerate() {
+                if (info.after.contains(temp) || srcs[i + 1..].contains(temp))
+                    && copied.insert(*temp)
+                {
+                    self.check_temp(&loc, *temp, Ability::Copy)
+                }
+            }
+            // Temporaries which are defined but not used afterwards are dropped.
+            for temp in Self::defined_temps(bc) {
+                if !info.after.contains(&temp) {
+                    self.check_temp(&loc, temp, Ability::Drop)
+                }
+            }
+            // Temporaries which are alive on some but not all outgoing edges are dropped on
+            // the others.
+            if bc.is_branch() {
+                for succ in Bytecode::get_successors(offset, code, &self.label_offsets) {
+                    let Some(succ_info) = self.live_vars.get_live_var_info_at(succ) else {
+                        continue;
+                    };
+                    for temp in info.after.difference(&succ_info.before) {
+                        if self.may_be_released(succ, *temp) {
+                            self.check_temp(&self.temp_loc(*temp, Some(&loc)), *temp, Ability::Drop)
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks the abilities required by the operation of an instruction.
+    fn check_operation(&self, loc: &Loc, bc: &Bytecode) {
+        use Operation::*;
+        let env = self.env();
+        let Bytecode::Call(_, _, op, srcs, _) = bc else {
+            return;
+        };
+        match op {
+            Function(mid, fid, inst) => {
+                let fun_env = env.get_function(mid.qualified(*fid));
+                check_type_args(
+                    env,
+                    loc,
+                    &format!("function `{}`", fun_env.get_full_name_str()),
+                    &fun_env.get_type_parameters(),
+                    inst,
+                    &self.type_params,
+                    &self.type_display_ctx,
+                )
+            },
+            Pack(mid, sid, inst) | Unpack(mid, sid, inst) | BorrowField(mid, sid, inst, _) => {
+                let ty = Type::Struct(*mid, *sid, inst.clone());
+                self.check_instantiations(loc, &ty)
+            },
+            MoveTo(mid, sid, inst)
+            | MoveFrom(mid, sid, inst)
+            | Exists(mid, sid, inst)
+            | BorrowGlobal(mid, sid, inst) => {
+                let ty = Type::Struct(*mid, *sid, inst.clone());
+                self.check_instantiations(loc, &ty);
+                self.check_type(loc, "global value", &ty, Ability::Key)
+            },
+            ReadRef => {
+                if let Type::Reference(_, ty) = self.target.get_local_type(srcs[0]) {
+                    self.check_type(loc, "value", ty, Ability::Copy)
+                }
+            },
+            WriteRef => {
+                if let Type::Reference(_, ty) = self.target.get_local_type(srcs[0]) {
+                    self.check_type(loc, "value", ty, Ability::Drop)
+                }
+            },
+            Eq | Neq => self.check_temp(loc, srcs[0], Ability::Drop),
+            _ => {},
+        }
+    }
+
+    /// Checks that the struct instantiations in the type satisfy their constraints.
+    fn check_instantiations(&self, loc: &Loc, ty: &Type) {
+        check_instantiations(
+            self.env(),
+            loc,
+            ty,
+            &self.type_params,
+            &self.type_display_ctx,
+        )
+    }
+
+    /// Checks that the type of the temporary has the ability.
+    fn check_temp(&self, loc: &Loc, temp: TempIndex, ability: Ability) {
+        let what = match self.declared.and_then(|d| d.vars.get(&temp)) {
+            Some((name, _)) => format!("local `{}`", name.display(self.env().symbol_pool())),
+            None => "value".to_owned(),
+        };
+        self.check_type(loc, &what, self.target.get_local_type(temp), ability)
+    }
+
+    /// Checks that the type has the ability. `what` describes the entity of the type.
+    fn check_type(&self, loc: &Loc, what: &str, ty: &Type, ability: Ability) {
+        if is_checkable(ty)
+            && !self
+                .env()
+                .type_abilities(ty, &self.type_params)
+                .has_ability(ability)
+        {
+            diagnostic_codes::VALUE_MISSING_ABILITY.error(
+                self.env(),
+                loc,
+                &format!(
+                    "{} of type `{}` does not have the `{}` ability",
+                    what,
+                    ty.display(&self.type_display_ctx),
+                    ability_name(ability)
+                ),
+            )
+        }
+    }
+
+    /// Returns the location where the temporary is declared, if it is a user declared variable,
+    /// otherwise the given default location, or the location of the function.
+    fn temp_loc(&self, temp: TempIndex, default: Option<&Loc>) -> Loc {
+        match self.declared.and_then(|d| d.vars.get(&temp)) {
+            Some((_, loc)) => loc.clone(),
+            None => default
+                .cloned()
+                .unwrap_or_else(|| self.target.func_env.get_loc()),
+        }
+    }
+
+    /// Determines whether a dead value of the temporary, left in its local at the given code
+    /// offset, may need to be released: either because the function may return, or because
+    /// the temporary may be overwritten.
+    fn may_be_released(&self, start: CodeOffset, temp: TempIndex) -> bool {
+        let code = self.target.get_bytecode();
+        let mut visited = BTreeSet::new();
+        let mut todo = VecDeque::from([start]);
+        while let Some(offset) = todo.pop_front() {
+            if !visited.insert(offset) {
+                continue;
+            }
+            let bc = &code[offset as usize];
+            if matches!(bc, Bytecode::Ret(..)) || Self::defined_temps(bc).contains(&temp) {
+                return true;
+            }
+            if !matches!(bc, Bytecode::Abort(..)) {
+                todo.extend(Bytecode::get_successors(offset, code, &self.label_offsets))
+            }
+        }
+        false
+    }
+
+    /// Returns the temporaries whose values are consumed by the instruction, in order.
+    fn consumed_temps(bc: &Bytecode) -> Vec<TempIndex> {
+        match bc {
+            Bytecode::Assign(_, _, src, _) => vec![*src],
+            // Borrowing a local does not consume its value.
+            Bytecode::Call(_, _, Operation::BorrowLoc, _, _) => vec![],
+            Bytecode::Call(_, _, _, srcs, _) | Bytecode::Ret(_, srcs) => srcs.clone(),
+            Bytecode::Branch(_, _, _, cond) => vec![*cond],
+            Bytecode::Abort(_, code) => vec![*code],
+            _ => vec![],
+        }
+    }
+
+    /// Returns the temporaries defined by the instruction.
+    fn defined_temps(bc: &Bytecode) -> Vec<TempIndex> {
+        match bc {
+            Bytecode::Assign(_, dest, _, _) | Bytecode::Load(_, dest, _) => vec![*dest],
+            Bytecode::Call(_, dests, _, _, _) => dests.clone(),
+            _ => vec![],
+        }
+    }
+}
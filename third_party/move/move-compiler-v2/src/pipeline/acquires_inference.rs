@@ -0,0 +1,237 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements inference of the resources a function acquires. A function acquires a resource
+//! if it uses `move_from`, `borrow_global`, or `borrow_global_mut` on it, or if it calls a
+//! function of the same module which acquires it. The result is attached to each function as
+//! an `AcquiresAnnotation`, which is used by the file format generator to populate the
+//! `acquires` list of function definitions.
+//!
+//! For functions of target modules, the inferred resources are compared with the `acquires`
+//! clauses declared in the source. As in compiler v1, it is an error if a resource is
+//! acquired but not declared, or declared but never acquired.
+//!
+//! The processor must run after any processor which changes the code, since the bytecode
+//! verifier rejects `acquires` lists which do not exactly match the code.
+
+use crate::diagnostic_codes;
+use move_binary_format::file_format::AccessKind;
+use move_model::{
+    ast::ResourceSpecifier,
+    model::{FunId, FunctionEnv, GlobalEnv, Loc, QualifiedId, StructId},
+};
+use move_stackless_bytecode::{
+    function_target::FunctionTarget,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    stackless_bytecode::{Bytecode, Operation},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct AcquiresInference();
+
+/// The resources acquired by a function, directly or through calls.
+#[derive(Clone, Debug, Default)]
+pub struct AcquiresAnnotation {
+    pub acquires: BTreeSet<StructId>,
+}
+
+/// Describes how a resource is acquired by a function, for error reporting.
+#[derive(Clone)]
+enum AcquiredBy {
+    /// By a global storage operation, given by its name.
+    Operation(&'static str),
+    /// By a call to another function.
+    Call(QualifiedId<FunId>),
+    /// By declaration, for native functions.
+    Declaration,
+}
+
+/// A map from acquired resources to the location and the way they are acquired.
+type Usage = BTreeMap<StructId, (Loc, AcquiredBy)>;
+
+impl FunctionTargetProcessor for AcquiresInference {
+    fn is_single_run(&self) -> bool {
+        true
+    }
+
+    fn run(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        // Compute the direct usage of resources, and the calls to functions of the same module.
+        let mut usage_map: BTreeMap<QualifiedId<FunId>, Usage> = BTreeMap::new();
+        let mut call_map = BTreeMap::new();
+        for fun_id in targets.get_funs().collect::<Vec<_>>() {
+            let fun_env = env.get_function(fun_id);
+            if fun_env.is_native() {
+                // Native functions have no code, so their declaration has to be trusted.
+                let usage = declared_acquires(&fun_env)
+                    .into_iter()
+                    .map(|(sid, loc)| (sid, (loc, AcquiredBy::Declaration)))
+                    .collect();
+                usage_map.insert(fun_id, usage);
+            } else {
+                let data = targets
+                    .get_data(&fun_id, &FunctionVariant::Baseline)
+                    .expect("function data");
+                let (usage, calls) = direct_usage(&FunctionTarget::new(&fun_env, data));
+                usage_map.insert(fun_id, usage);
+                call_map.insert(fun_id, calls);
+            }
+        }
+        // Run a fixed-point loop: add resources acquired by called functions until there are no
+        // changes.
+        loop {
+            let mut changes = false;
+            for (fun_id, calls) in &call_map {
+                for (callee, loc) in calls {
+                    let Some(callee_usage) = usage_map.get(callee) else {
+                        continue;
+                    };
+                    let added = callee_usage
+                        .keys()
+                        .filter(|sid| !usage_map[fun_id].contains_key(sid))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    let usage = usage_map.get_mut(fun_id).expect("usage");
+                    for sid in added {
+                        usage.insert(sid, (loc.clone(), AcquiredBy::Call(*callee)));
+                        changes = true
+                    }
+                }
+            }
+            if !changes {
+                break;
+            }
+        }
+        for (fun_id, usage) in usage_map {
+            let fun_env = env.get_function(fun_id);
+            if fun_env.module_env.is_target() && !fun_env.is_native() {
+                check_declared_acquires(&fun_env, &usage)
+            }
+            let data = targets
+                .get_data_mut(&fun_id, &FunctionVariant::Baseline)
+                .expect("function data");
+            data.annotations.set(
+                AcquiresAnnotation {
+                    acquires: usage.into_keys().collect(),
+                },
+                true,
+            )
+        }
+    }
+
+    fn name(&self) -> String {
+        "AcquiresInference".to_owned()
+    }
+}
+
+/// Computes the resources acquired directly by the code of a function, together with the
+/// calls to functions of the same module.
+fn direct_usage(target: &FunctionTarget) -> (Usage, Vec<(QualifiedId<FunId>, Loc)>) {
+    use Bytecode::*;
+    use Operation::*;
+    let module_id = target.func_env.module_env.get_id();
+    let mut usage = Usage::new();
+    let mut calls = vec![];
+    for bc in target.get_bytecode() {
+        let Call(attr_id, dests, op, _, _) = bc else {
+            continue;
+        };
+        let loc = target.get_bytecode_loc(*attr_id);
+        match op {
+            MoveFrom(mid, sid, _) if *mid == module_id => {
+                usage
+                    .entry(*sid)
+                    .or_insert((loc, AcquiredBy::Operation("move_from")));
+            },
+            BorrowGlobal(mid, sid, _) if *mid == module_id => {
+                let name = if target.get_local_type(dests[0]).is_mutable_reference() {
+                    "borrow_global_mut"
+                } else {
+                    "borrow_global"
+                };
+                usage
+                    .entry(*sid)
+                    .or_insert((loc, AcquiredBy::Operation(name)));
+            },
+            Function(mid, fid, _) if *mid == module_id => calls.push((mid.qualified(*fid), loc)),
+            _ => {},
+        }
+    }
+    (usage, calls)
+}
+
+/// Returns the resources named in `acquires` clauses of the function, with their locations.
+fn declared_acquires(fun_env: &FunctionEnv) -> BTreeMap<StructId, Loc> {
+    let module_id = fun_env.module_env.get_id();
+    fun_env
+        .get_access_specifiers()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|spec| match &spec.resource.1 {
+            ResourceSpecifier::Resource(inst_id)
+                if spec.kind == AccessKind::Acquires
+                    && !spec.negated
+                    && inst_id.module_id == module_id =>
+            {
+                Some((inst_id.id, spec.loc.clone()))
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks the inferred resources against the `acquires` clauses of the function.
+fn check_declared_acquires(fun_env: &FunctionEnv, usage: &Usage) {
+    let env = fun_env.module_env.env;
+    // Access specifiers other than plain `acquires` of a resource, as wildcards, are not
+    // checked against the code, and can cover any resource.
+    let has_other_specifiers = fun_env
+        .get_access_specifiers()
+        .unwrap_or_default()
+        .iter()
+        .any(|spec| {
+            spec.kind != AccessKind::Acquires
+                || spec.negated
+                || !matches!(spec.resource.1, ResourceSpecifier::Resource(_))
+        });
+    let declared = declared_acquires(fun_env);
+    for (sid, loc) in &declared {
+        if !usage.contains_key(sid) {
+            diagnostic_codes::UNUSED_ACQUIRES.error(
+                env,
+                loc,
+                &format!(
+                    "invalid `acquires` list: the resource `{}` is never acquired by \
+                     `move_from`, `borrow_global`, `borrow_global_mut`, or a transitive call",
+                    fun_env.module_env.get_struct(*sid).get_full_name_str()
+                ),
+            )
+        }
+    }
+    if has_other_specifiers {
+        return;
+    }
+    for (sid, (loc, by)) in usage {
+        if declared.contains_key(sid) {
+            continue;
+        }
+        let how = match by {
+            AcquiredBy::Operation(name) => format!("`{}`", name),
+            AcquiredBy::Call(callee) => format!(
+                "the call to `{}`",
+                env.get_function(*callee).get_full_name_str()
+            ),
+            AcquiredBy::Declaration => "its declaration".to_owned(),
+        };
+        diagnostic_codes::MISSING_ACQUIRES.error(
+            env,
+            loc,
+            &format!(
+                "missing `acquires` annotation: the resource `{}` is acquired by {}, but \
+                 is not in the `acquires` list of function `{}`",
+                fun_env.module_env.get_struct(*sid).get_full_name_str(),
+                how,
+                fun_env.get_full_name_str()
+            ),
+        )
+    }
+}
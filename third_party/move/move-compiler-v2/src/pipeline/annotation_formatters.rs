@@ -0,0 +1,78 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Formatting of the annotations of the bytecode pipeline as comments in bytecode dumps, if
+//! `Options::dump_annotations` is set. Each formatter renders one kind of annotation at a code
+//! offset, if the function has it. The formatters of the annotations of the processors of the
+//! compiler are listed in `FORMATTERS`; embedders which add processors with annotations of
+//! their own register formatters with `Options::register_annotation_formatter`.
+//!
+//! Sets are rendered in the order of their elements, like temporaries by index, so dumps are
+//! stable and can be diffed.
+
+use crate::{pipeline::acquires_inference::AcquiresAnnotation, Options};
+use itertools::Itertools;
+use move_binary_format::file_format::CodeOffset;
+use move_model::ast::TempIndex;
+use move_stackless_bytecode::{
+    function_target::FunctionTarget, livevar_analysis::LiveVarAnnotation,
+};
+use std::collections::BTreeSet;
+
+/// The formatters of the annotations of the processors of the compiler.
+const FORMATTERS: &[fn(&FunctionTarget, CodeOffset) -> Option<String>] =
+    &[format_live_vars, format_acquires];
+
+/// Registers the formatters of all known annotations with the target, so printing it shows
+/// the annotations as comments.
+pub fn register_annotation_formatters(target: &FunctionTarget, options: &Options) {
+    for formatter in FORMATTERS {
+        target.register_annotation_formatter(Box::new(*formatter))
+    }
+    for extension in &options.annotation_formatters {
+        let formatter = extension.formatter.clone();
+        target.register_annotation_formatter(Box::new(
+            move |target: &FunctionTarget, offset: CodeOffset| formatter(target, offset),
+        ))
+    }
+}
+
+/// Formats the variables live before and after the instruction.
+fn format_live_vars(target: &FunctionTarget, offset: CodeOffset) -> Option<String> {
+    let info = target
+        .get_annotations()
+        .get::<LiveVarAnnotation>()?
+        .get_live_var_info_at(offset)?;
+    let names = |temps: &BTreeSet<TempIndex>| {
+        temps
+            .iter()
+            .map(|temp| {
+                target
+                    .get_local_name(*temp)
+                    .display(target.symbol_pool())
+                    .to_string()
+            })
+            .join(", ")
+    };
+    Some(format!(
+        "live vars: before {{{}}}, after {{{}}}",
+        names(&info.before),
+        names(&info.after)
+    ))
+}
+
+/// Formats the resources acquired by the function, at its first instruction.
+fn format_acquires(target: &FunctionTarget, offset: CodeOffset) -> Option<String> {
+    if offset != 0 {
+        return None;
+    }
+    let annotation = target.get_annotations().get::<AcquiresAnnotation>()?;
+    let module_env = &target.func_env.module_env;
+    let names = annotation
+        .acquires
+        .iter()
+        .map(|id| module_env.get_struct(*id).get_full_name_str())
+        .sorted()
+        .join(", ");
+    Some(format!("acquires: {{{}}}", names))
+}
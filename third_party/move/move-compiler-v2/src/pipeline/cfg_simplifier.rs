@@ -0,0 +1,238 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a processor which simplifies the control-flow graph of a function. The following
+//! rewrites are applied until none of them changes the code anymore:
+//!
+//! - Jump threading: a branch to a label which is directly followed by another label, or by a
+//!   jump, is redirected to the final target of that chain. A conditional branch whose targets
+//!   become the same is replaced by a jump.
+//! - A jump to a label which directly follows it is removed.
+//! - Code which cannot be reached from the entry of the function is removed, which includes
+//!   blocks only reached by jumps which have been threaded.
+//! - Labels which are no longer the target of any branch are removed, merging a block with
+//!   the one falling through into it.
+//! - A block which is only reached by a single jump, and which ends in a jump, branch, return,
+//!   or abort, is moved in place of that jump, merging it with its only predecessor.
+//!
+//! Since branches refer to labels instead of code offsets, moving blocks keeps loop back edges
+//! intact. A chain of jumps forming a cycle, as for an empty infinite loop, is left in place.
+//!
+//! The processor should run after the other optimizations of the stackless bytecode, which
+//! leave behind empty blocks. It recomputes the live-variable annotation, if present, when it
+//! changes the code.
+
+use crate::pipeline::unreachable_code_remover::UnreachableCodeRemover;
+use move_binary_format::file_format::CodeOffset;
+use move_model::model::FunctionEnv;
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis::{self, LiveVarAnnotation},
+    stackless_bytecode::{AbortAction, Bytecode, Label},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct CfgSimplifier();
+
+impl FunctionTargetProcessor for CfgSimplifier {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        mut data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() {
+            return data;
+        }
+        let mut changed = false;
+        loop {
+            let round_changed = Self::thread_jumps(&mut data.code)
+                | Self::remove_jumps_to_next(&mut data.code)
+                | Self::remove_unreachable(&mut data.code)
+                | Self::remove_unused_labels(&mut data.code)
+                | Self::merge_blocks(&mut data.code);
+            if !round_changed {
+                break;
+            }
+            changed = true
+        }
+        if changed && data.annotations.get::<LiveVarAnnotation>().is_some() {
+            // Recompute the live-var annotation for the transformed code.
+            let target = FunctionTarget::new(fun_env, &data);
+            let offset_to_live_refs = LiveVarAnnotation::from_map(
+                livevar_analysis::run_livevar_analysis(&target, &data.code),
+            );
+            data.annotations.set(offset_to_live_refs, true);
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "CfgSimplifier".to_owned()
+    }
+}
+
+impl CfgSimplifier {
+    /// Redirects branches to the final target of the chain of labels and jumps they lead to.
+    /// Returns true if any branch has been changed.
+    fn thread_jumps(code: &mut [Bytecode]) -> bool {
+        // The next label of each label which is directly followed by a label or a jump.
+        let mut next_hops = BTreeMap::new();
+        for (offset, bc) in code.iter().enumerate() {
+            if let Bytecode::Label(_, label) = bc {
+                match code.get(offset + 1) {
+                    Some(Bytecode::Label(_, next)) | Some(Bytecode::Jump(_, next)) => {
+                        next_hops.insert(*label, *next);
+                    },
+                    _ => {},
+                }
+            }
+        }
+        let resolve = |mut label: Label| {
+            let mut visited = BTreeSet::new();
+            while let Some(next) = next_hops.get(&label) {
+                if !visited.insert(label) {
+                    break;
+                }
+                label = *next
+            }
+            label
+        };
+        let mut changed = false;
+        for bc in code.iter_mut() {
+            match bc {
+                Bytecode::Jump(_, label)
+                | Bytecode::Call(_, _, _, _, Some(AbortAction(label, _))) => {
+                    let target = resolve(*label);
+                    if target != *label {
+                        *label = target;
+                        changed = true
+                    }
+                },
+                Bytecode::Branch(attr, if_label, else_label, cond) => {
+                    let (if_target, else_target) = (resolve(*if_label), resolve(*else_label));
+                    if if_target == else_target {
+                        *bc = Bytecode::Jump(*attr, if_target);
+                        changed = true
+                    } else if (if_target, else_target) != (*if_label, *else_label) {
+                        *bc = Bytecode::Branch(*attr, if_target, else_target, *cond);
+                        changed = true
+                    }
+                },
+                _ => {},
+            }
+        }
+        changed
+    }
+
+    /// Removes jumps to a label which directly follows them, possibly among other labels.
+    /// Returns true if any jump has been removed.
+    fn remove_jumps_to_next(code: &mut Vec<Bytecode>) -> bool {
+        let len = code.len();
+        let redundant = (0..len)
+            .filter(|offset| match &code[*offset] {
+                Bytecode::Jump(_, target) => code[offset + 1..]
+                    .iter()
+                    .map_while(|bc| match bc {
+                        Bytecode::Label(_, label) => Some(label),
+                        _ => None,
+                    })
+                    .any(|label| label == target),
+                _ => false,
+            })
+            .collect::<BTreeSet<_>>();
+        Self::remove_offsets(code, |offset| redundant.contains(&offset));
+        code.len() != len
+    }
+
+    /// Removes the code which is not reachable from the entry of the function. Returns true
+    /// if any code has been removed.
+    fn remove_unreachable(code: &mut Vec<Bytecode>) -> bool {
+        let reachable = UnreachableCodeRemover::reachable_offsets(code);
+        if reachable.len() == code.len() {
+            return false;
+        }
+        Self::remove_offsets(code, |offset| !reachable.contains(&(offset as CodeOffset)));
+        true
+    }
+
+    /// Removes labels which are not the target of any branch. Returns true if any label has
+    /// been removed.
+    fn remove_unused_labels(code: &mut Vec<Bytecode>) -> bool {
+        let targets = code
+            .iter()
+            .flat_map(|bc| bc.branch_dests())
+            .collect::<BTreeSet<_>>();
+        let len = code.len();
+        code.retain(|bc| !matches!(bc, Bytecode::Label(_, label) if !targets.contains(label)));
+        code.len() != len
+    }
+
+    /// Moves blocks which are only reached by a single jump in place of that jump. Only blocks
+    /// which are not fallen into and which do not fall through are moved, so the control flow
+    /// around both the old and the new position of the block is preserved. Returns true if
+    /// any block has been moved.
+    fn merge_blocks(code: &mut Vec<Bytecode>) -> bool {
+        let mut changed = false;
+        while let Some((jump, start, end)) = Self::find_mergeable_block(code) {
+            // The label of the block is dropped, as the jump was its only use.
+            let block = code.drain(start..=end).skip(1).collect::<Vec<_>>();
+            let jump = if jump > end {
+                jump - (end + 1 - start)
+            } else {
+                jump
+            };
+            code.splice(jump..=jump, block);
+            changed = true
+        }
+        changed
+    }
+
+    /// Returns the offset of a jump, and the start and end offsets of the block it leads to,
+    /// which can be moved in place of the jump.
+    fn find_mergeable_block(code: &[Bytecode]) -> Option<(usize, usize, usize)> {
+        let mut uses: BTreeMap<Label, usize> = BTreeMap::new();
+        for label in code.iter().flat_map(|bc| bc.branch_dests()) {
+            *uses.entry(label).or_default() += 1
+        }
+        let label_offsets = Bytecode::label_offsets(code);
+        code.iter().enumerate().find_map(|(jump, bc)| {
+            let Bytecode::Jump(_, label) = bc else {
+                return None;
+            };
+            if uses.get(label) != Some(&1) {
+                return None;
+            }
+            let start = *label_offsets.get(label)? as usize;
+            if start == 0 || Self::falls_through(&code[start - 1]) {
+                return None;
+            }
+            let end = start
+                + 1
+                + code[start + 1..]
+                    .iter()
+                    .position(|bc| !Self::falls_through(bc) || matches!(bc, Bytecode::Label(..)))?;
+            if matches!(code[end], Bytecode::Label(..)) || (start..=end).contains(&jump) {
+                return None;
+            }
+            Some((jump, start, end))
+        })
+    }
+
+    /// Returns true if execution may continue with the next instruction after the given one.
+    fn falls_through(bc: &Bytecode) -> bool {
+        !bc.is_unconditional_branch() && !matches!(bc, Bytecode::Branch(..))
+    }
+
+    /// Removes the instructions at the offsets for which the predicate holds.
+    fn remove_offsets(code: &mut Vec<Bytecode>, remove: impl Fn(usize) -> bool) {
+        *code = std::mem::take(code)
+            .into_iter()
+            .enumerate()
+            .filter(|(offset, _)| !remove(*offset))
+            .map(|(_, bc)| bc)
+            .collect()
+    }
+}
@@ -0,0 +1,406 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a constant folding processor. A forward data-flow analysis determines which
+//! temporaries hold a known scalar constant at each program point. Based on this:
+//!
+//! - operations over constant arguments (arithmetics, bit operations, comparisons, boolean
+//!   negation, and casts) are replaced by loads of their result;
+//! - vectors of constants, including nested vectors and byte strings, are replaced by loads
+//!   of a constant from the constant pool, unless they contain values which cannot be
+//!   constants, like structs, or their serialized value exceeds the size limit of constants,
+//!   see `Options::constant_size_limit`;
+//! - assignments of a constant are replaced by loads of the constant;
+//! - branches on a constant condition are replaced by jumps.
+//!
+//! If an operation over constants is known to abort at runtime, e.g. because of an
+//! arithmetic overflow or a division by zero, an error is reported instead.
+//!
+//! The processor does not rely on other analyses. It should run before the
+//! `LiveVarAnalysisProcessor`, since it changes the code.
+
+use crate::{diagnostic_codes, Options};
+use ethnum::U256;
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::TempIndex,
+    model::FunctionEnv,
+    ty::{PrimitiveType, Type},
+};
+use move_stackless_bytecode::{
+    dataflow_analysis::{DataflowAnalysis, TransferFunctions},
+    dataflow_domains::{AbstractDomain, JoinResult},
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    stackless_bytecode::{AttrId, Bytecode, Constant, Operation},
+    stackless_control_flow_graph::StacklessControlFlowGraph,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct ConstantFolder();
+
+impl FunctionTargetProcessor for ConstantFolder {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        mut data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() {
+            return data;
+        }
+        // Folding branches makes code unreachable, which may allow to fold more constants,
+        // so iterate until a fixpoint is reached.
+        let mut errors = BTreeMap::new();
+        loop {
+            let target = FunctionTarget::new(fun_env, &data);
+            let new_code = Self::fold_code(&target, &mut errors);
+            if new_code == data.code {
+                break;
+            }
+            data.code = new_code;
+        }
+        let target = FunctionTarget::new(fun_env, &data);
+        for (attr, msg) in errors {
+            diagnostic_codes::CONSTANT_EXPRESSION_ABORTS.error(
+                fun_env.module_env.env,
+                &target.get_bytecode_loc(attr),
+                &msg,
+            )
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "ConstantFolder".to_owned()
+    }
+}
+
+impl ConstantFolder {
+    /// Runs the analysis and folds the code based on its results. Operations which are known
+    /// to abort are collected in `errors`.
+    fn fold_code(target: &FunctionTarget, errors: &mut BTreeMap<AttrId, String>) -> Vec<Bytecode> {
+        let code = target.get_bytecode();
+        let cfg = StacklessControlFlowGraph::new_forward(code);
+        let options = target
+            .global_env()
+            .get_extension::<Options>()
+            .unwrap_or_default();
+        let analysis = ConstantAnalysis {
+            target,
+            max_vector_size: options.constant_size_limit(),
+            borrowed: code
+                .iter()
+                .filter_map(|bc| match bc {
+                    Bytecode::Call(_, _, Operation::BorrowLoc, srcs, _) => Some(srcs[0]),
+                    _ => None,
+                })
+                .collect(),
+        };
+        let state_map = analysis.analyze_function(ConstantState::default(), code, &cfg);
+        let per_offset =
+            analysis.state_per_instruction(state_map, code, &cfg, |before, _| before.clone());
+        code.iter()
+            .enumerate()
+            .map(|(offset, bc)| {
+                // Code not reachable from the entry has no state; leave it as is.
+                match per_offset.get(&(offset as CodeOffset)) {
+                    Some(state) => analysis.fold(state, bc, errors),
+                    None => bc.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The state of the analysis: temporaries which are known to hold a constant.
+#[derive(Clone, Default)]
+struct ConstantState(BTreeMap<TempIndex, Constant>);
+
+impl AbstractDomain for ConstantState {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        let count = self.0.len();
+        self.0.retain(|temp, cons| other.0.get(temp) == Some(cons));
+        if self.0.len() == count {
+            JoinResult::Unchanged
+        } else {
+            JoinResult::Changed
+        }
+    }
+}
+
+struct ConstantAnalysis<'a> {
+    target: &'a FunctionTarget<'a>,
+    /// Temporaries which are borrowed, and therefore can be modified via references.
+    borrowed: BTreeSet<TempIndex>,
+    /// The maximal size of the serialized value of a tracked vector.
+    max_vector_size: usize,
+}
+
+impl<'a> TransferFunctions for ConstantAnalysis<'a> {
+    type State = ConstantState;
+
+    const BACKWARD: bool = false;
+
+    fn execute(&self, state: &mut ConstantState, instr: &Bytecode, _offset: CodeOffset) {
+        let known = match instr {
+            Bytecode::Load(_, dest, cons) if self.is_tracked(cons) => Some((*dest, cons.clone())),
+            Bytecode::Assign(_, dest, src, _) => state.0.get(src).map(|c| (*dest, c.clone())),
+            Bytecode::Call(_, dests, op, srcs, None) if dests.len() == 1 => self
+                .constant_args(state, srcs)
+                .and_then(|args| self.fold_call(op, dests[0], &args).ok().flatten())
+                .map(|c| (dests[0], c)),
+            _ => None,
+        };
+        let (modified_vals, modified_refs) = instr.modifies(self.target);
+        for temp in modified_vals
+            .into_iter()
+            .chain(modified_refs.into_iter().map(|(temp, _)| temp))
+        {
+            state.0.remove(&temp);
+        }
+        if let Some((dest, cons)) = known {
+            if !self.borrowed.contains(&dest) {
+                state.0.insert(dest, cons);
+            }
+        }
+    }
+}
+
+impl<'a> DataflowAnalysis for ConstantAnalysis<'a> {}
+
+impl<'a> ConstantAnalysis<'a> {
+    /// Returns the constant values of the given temporaries, if all of them are known.
+    fn constant_args(&self, state: &ConstantState, temps: &[TempIndex]) -> Option<Vec<Constant>> {
+        temps.iter().map(|t| state.0.get(t).cloned()).collect()
+    }
+
+    /// Returns true if this is a constant which is tracked by the analysis. Vectors are only
+    /// tracked if they fit into the constant pool, since folding a larger vector would turn
+    /// its construction at runtime into an error.
+    fn is_tracked(&self, cons: &Constant) -> bool {
+        is_scalar(cons)
+            || cons
+                .to_move_value()
+                .simple_serialize()
+                .is_some_and(|data| data.len() <= self.max_vector_size)
+    }
+
+    /// Folds a call with a single result over constant arguments into a constant, see
+    /// `fold_operation`. A vector of constants is folded into a constant vector, represented
+    /// as a byte array if its elements are bytes.
+    fn fold_call(
+        &self,
+        op: &Operation,
+        dest: TempIndex,
+        args: &[Constant],
+    ) -> Result<Option<Constant>, String> {
+        if !matches!(op, Operation::Vector) {
+            return fold_operation(op, args);
+        }
+        let Type::Vector(elem_ty) = self.target.get_local_type(dest) else {
+            return Ok(None);
+        };
+        if !is_constant_type(elem_ty) {
+            return Ok(None);
+        }
+        let cons = if matches!(elem_ty.as_ref(), Type::Primitive(PrimitiveType::U8)) {
+            let bytes = args
+                .iter()
+                .map(|arg| match arg {
+                    Constant::U8(byte) => Some(*byte),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            match bytes {
+                Some(bytes) => Constant::ByteArray(bytes),
+                None => return Ok(None),
+            }
+        } else {
+            Constant::Vector(args.to_vec())
+        };
+        Ok(Some(cons).filter(|cons| self.is_tracked(cons)))
+    }
+
+    /// Folds the instruction, given the state before it, collecting operations which are
+    /// known to abort.
+    fn fold(
+        &self,
+        state: &ConstantState,
+        bc: &Bytecode,
+        errors: &mut BTreeMap<AttrId, String>,
+    ) -> Bytecode {
+        match bc {
+            Bytecode::Assign(attr, dest, src, _) => match state.0.get(src) {
+                Some(cons) => Bytecode::Load(*attr, *dest, cons.clone()),
+                None => bc.clone(),
+            },
+            Bytecode::Call(attr, dests, op, srcs, None) if dests.len() == 1 => {
+                match self.constant_args(state, srcs) {
+                    Some(args) => match self.fold_call(op, dests[0], &args) {
+                        Ok(Some(cons)) => Bytecode::Load(*attr, dests[0], cons),
+                        Ok(None) => bc.clone(),
+                        Err(msg) => {
+                            errors.insert(*attr, msg);
+                            bc.clone()
+                        },
+                    },
+                    None => bc.clone(),
+                }
+            },
+            Bytecode::Branch(attr, if_true, if_false, cond) => match state.0.get(cond) {
+                Some(Constant::Bool(true)) => Bytecode::Jump(*attr, *if_true),
+                Some(Constant::Bool(false)) => Bytecode::Jump(*attr, *if_false),
+                _ => bc.clone(),
+            },
+            _ => bc.clone(),
+        }
+    }
+}
+
+/// Returns true if this is a constant which is not a vector.
+fn is_scalar(cons: &Constant) -> bool {
+    !matches!(
+        cons,
+        Constant::ByteArray(_) | Constant::AddressArray(_) | Constant::Vector(_)
+    )
+}
+
+/// Returns true if values of the type can be constants: primitive values other than signers,
+/// and vectors of them.
+fn is_constant_type(ty: &Type) -> bool {
+    match ty {
+        Type::Primitive(PrimitiveType::Signer) => false,
+        Type::Primitive(_) => true,
+        Type::Vector(elem_ty) => is_constant_type(elem_ty),
+        _ => false,
+    }
+}
+
+/// Folds an operation over constant arguments. Returns `Ok(None)` if the operation cannot
+/// be folded, and an error message if the operation is known to abort.
+fn fold_operation(op: &Operation, args: &[Constant]) -> Result<Option<Constant>, String> {
+    use Operation::*;
+    let result = match (op, args) {
+        (Add | Sub | Mul | Div | Mod | BitOr | BitAnd | Xor, [x, y]) => {
+            let (Some((x, bits)), Some((y, _))) = (to_int(x), to_int(y)) else {
+                return Ok(None);
+            };
+            let result = match op {
+                Add => x.checked_add(y),
+                Sub => x.checked_sub(y),
+                Mul => x.checked_mul(y),
+                Div | Mod if y == U256::ZERO => {
+                    return Err("division by zero in constant expression".to_owned())
+                },
+                Div => Some(x / y),
+                Mod => Some(x % y),
+                BitOr => Some(x | y),
+                BitAnd => Some(x & y),
+                _ => Some(x ^ y),
+            };
+            match result {
+                Some(r) if r <= max_value(bits) => from_int(r, bits),
+                _ => {
+                    return Err(format!(
+                        "arithmetic {} in constant expression of type `u{}`",
+                        if matches!(op, Sub) {
+                            "underflow"
+                        } else {
+                            "overflow"
+                        },
+                        bits
+                    ))
+                },
+            }
+        },
+        (Shl | Shr, [x, Constant::U8(n)]) => {
+            let Some((x, bits)) = to_int(x) else {
+                return Ok(None);
+            };
+            if *n as usize >= bits {
+                return Err(format!(
+                    "shift by {} exceeds the bit width of `u{}` in constant expression",
+                    n, bits
+                ));
+            }
+            if matches!(op, Shl) {
+                from_int((x << *n as u32) & max_value(bits), bits)
+            } else {
+                from_int(x >> *n as u32, bits)
+            }
+        },
+        (Lt | Gt | Le | Ge, [x, y]) => {
+            let (Some((x, _)), Some((y, _))) = (to_int(x), to_int(y)) else {
+                return Ok(None);
+            };
+            Constant::Bool(match op {
+                Lt => x < y,
+                Gt => x > y,
+                Le => x <= y,
+                _ => x >= y,
+            })
+        },
+        // Vectors are compared by value, since byte strings have several representations.
+        (Eq, [x, y]) => Constant::Bool(x.to_move_value() == y.to_move_value()),
+        (Neq, [x, y]) => Constant::Bool(x.to_move_value() != y.to_move_value()),
+        (Not, [Constant::Bool(b)]) => Constant::Bool(!b),
+        (CastU8 | CastU16 | CastU32 | CastU64 | CastU128 | CastU256, [x]) => {
+            let Some((x, _)) = to_int(x) else {
+                return Ok(None);
+            };
+            let bits = match op {
+                CastU8 => 8,
+                CastU16 => 16,
+                CastU32 => 32,
+                CastU64 => 64,
+                CastU128 => 128,
+                _ => 256,
+            };
+            if x > max_value(bits) {
+                return Err(format!(
+                    "constant value `{}` out of range for cast to `u{}`",
+                    x, bits
+                ));
+            }
+            from_int(x, bits)
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+/// Returns the value and bit width of an integer constant.
+fn to_int(cons: &Constant) -> Option<(U256, usize)> {
+    match cons {
+        Constant::U8(x) => Some((U256::from(*x), 8)),
+        Constant::U16(x) => Some((U256::from(*x), 16)),
+        Constant::U32(x) => Some((U256::from(*x), 32)),
+        Constant::U64(x) => Some((U256::from(*x), 64)),
+        Constant::U128(x) => Some((U256::from(*x), 128)),
+        Constant::U256(x) => Some((*x, 256)),
+        _ => None,
+    }
+}
+
+/// Creates an integer constant of the given bit width. The value must be in range.
+fn from_int(value: U256, bits: usize) -> Constant {
+    match bits {
+        8 => Constant::U8(value.as_u8()),
+        16 => Constant::U16(value.as_u16()),
+        32 => Constant::U32(value.as_u32()),
+        64 => Constant::U64(value.as_u64()),
+        128 => Constant::U128(value.as_u128()),
+        _ => Constant::U256(value),
+    }
+}
+
+/// Returns the maximal value of an integer of the given bit width.
+fn max_value(bits: usize) -> U256 {
+    if bits == 256 {
+        U256::MAX
+    } else {
+        (U256::ONE << bits as u32) - U256::ONE
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a copy propagation processor. For an assignment `t2 := t1`, subsequent uses
+//! of `t2` in the same basic block are replaced by `t1`, as long as neither `t1` nor `t2`
+//! is modified in between. Assignments which become dead by this are removed, based on
+//! a fresh live-variable analysis, so the file format generator needs fewer locals.
+//!
+//! To preserve semantics, propagation is restricted to temporaries which:
+//!
+//! - have the `copy` ability, so that using `t1` instead of `t2` does not consume a value
+//!   still needed elsewhere;
+//! - are not mutable references, since those can be updated in place;
+//! - are never borrowed in the function, since writes through a reference would not be
+//!   visible in the copy.
+//!
+//! The processor must run after `LiveVarAnalysisProcessor`, and recomputes the live-variable
+//! annotation if it changes the code.
+
+use move_binary_format::file_format::{Ability, CodeOffset};
+use move_model::{ast::TempIndex, model::FunctionEnv};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis,
+    stackless_bytecode::{Bytecode, Operation},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct CopyPropagation();
+
+impl FunctionTargetProcessor for CopyPropagation {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        mut data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() {
+            return data;
+        }
+        let target = FunctionTarget::new(fun_env, &data);
+        let (code, copies) = Self::propagate(&target);
+        if copies.is_empty() {
+            return data;
+        }
+        data.code = code;
+        // Remove the propagated copies which are no longer used.
+        let target = FunctionTarget::new(fun_env, &data);
+        let live_vars = livevar_analysis::run_livevar_analysis(&target, &data.code);
+        data.code = std::mem::take(&mut data.code)
+            .into_iter()
+            .enumerate()
+            .filter(|(offset, bc)| match bc {
+                Bytecode::Assign(_, dest, _, _) if copies.contains(offset) => live_vars
+                    .get(&(*offset as CodeOffset))
+                    .map(|info| info.after.contains(dest))
+                    .unwrap_or(true),
+                _ => true,
+            })
+            .map(|(_, bc)| bc)
+            .collect();
+        // Recompute the live-var annotation for the transformed code.
+        let target = FunctionTarget::new(fun_env, &data);
+        let offset_to_live_refs = livevar_analysis::LiveVarAnnotation::from_map(
+            livevar_analysis::run_livevar_analysis(&target, &data.code),
+        );
+        data.annotations.set(offset_to_live_refs, true);
+        data
+    }
+
+    fn name(&self) -> String {
+        "CopyPropagation".to_owned()
+    }
+}
+
+impl CopyPropagation {
+    /// Propagates copies within basic blocks. Returns the new code together with the offsets
+    /// of the copies which have been propagated.
+    fn propagate(target: &FunctionTarget) -> (Vec<Bytecode>, BTreeSet<usize>) {
+        let env = target.global_env();
+        let type_params = target.func_env.get_type_parameters();
+        let code = target.get_bytecode();
+        let borrowed = code
+            .iter()
+            .filter_map(|bc| match bc {
+                Bytecode::Call(_, _, Operation::BorrowLoc, srcs, _) => Some(srcs[0]),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+        let is_admissible = |temp: TempIndex| {
+            let ty = target.get_local_type(temp);
+            !borrowed.contains(&temp)
+                && !ty.is_mutable_reference()
+                && env
+                    .type_abilities(ty, &type_params)
+                    .has_ability(Ability::Copy)
+        };
+        // Maps a temporary to the temporary it is a copy of, and the offset of the copy.
+        let mut copy_of: BTreeMap<TempIndex, (TempIndex, usize)> = BTreeMap::new();
+        let mut copies = BTreeSet::new();
+        let mut new_code = vec![];
+        for (offset, bc) in code.iter().enumerate() {
+            if matches!(bc, Bytecode::Label(..)) {
+                // Start of a new basic block.
+                copy_of.clear()
+            }
+            let bc = bc.clone().remap_src_vars(target, &mut |temp| {
+                if let Some((orig, copy_offset)) = copy_of.get(&temp) {
+                    copies.insert(*copy_offset);
+                    *orig
+                } else {
+                    temp
+                }
+            });
+            // Invalidate copies involving modified temporaries.
+            let (modified_vals, modified_refs) = bc.modifies(target);
+            for temp in modified_vals
+                .into_iter()
+                .chain(modified_refs.into_iter().map(|(temp, _)| temp))
+            {
+                copy_of.retain(|copy, (orig, _)| *copy != temp && *orig != temp)
+            }
+            if let Bytecode::Assign(_, dest, src, _) = &bc {
+                if dest != src && is_admissible(*dest) && is_admissible(*src) {
+                    copy_of.insert(*dest, (*src, offset));
+                }
+            }
+            if bc.is_branch() {
+                copy_of.clear()
+            }
+            new_code.push(bc)
+        }
+        (new_code, copies)
+    }
+}
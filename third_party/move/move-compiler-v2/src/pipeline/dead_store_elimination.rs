@@ -0,0 +1,150 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a dead store elimination processor, based on the results of live-variable
+//! analysis. The processor performs two transformations until a fixpoint is reached:
+//!
+//! - Assignments `t := s` and loads `t := c` where `t` is not alive afterwards are removed.
+//!   Those instructions have no side effects, so dropping them does not change semantics.
+//! - Chains of the form `t := op(..); u := t`, as frequently introduced by the bytecode
+//!   generator, are collapsed into `u := op(..)` if `t` is not alive after the second
+//!   instruction.
+//!
+//! The processor must run after `LiveVarAnalysisProcessor`. If it changes the code, the
+//! live-variable annotation is recomputed, so processors running afterwards see up-to-date
+//! information.
+
+use move_binary_format::file_format::CodeOffset;
+use move_model::{ast::TempIndex, model::FunctionEnv};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis::{self, LiveVarInfoAtCodeOffset},
+    stackless_bytecode::Bytecode,
+};
+use std::collections::BTreeMap;
+
+pub struct DeadStoreElimination();
+
+impl FunctionTargetProcessor for DeadStoreElimination {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        mut data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() {
+            return data;
+        }
+        let mut changed = false;
+        loop {
+            let target = FunctionTarget::new(fun_env, &data);
+            let live_vars = livevar_analysis::run_livevar_analysis(&target, &data.code);
+            let new_code = Self::transform(&target, &live_vars);
+            if new_code.len() == data.code.len() {
+                break;
+            }
+            data.code = new_code;
+            changed = true;
+        }
+        if changed {
+            // Recompute the live-var annotation for the transformed code.
+            let target = FunctionTarget::new(fun_env, &data);
+            let offset_to_live_refs = livevar_analysis::LiveVarAnnotation::from_map(
+                livevar_analysis::run_livevar_analysis(&target, &data.code),
+            );
+            data.annotations.set(offset_to_live_refs, true);
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "DeadStoreElimination".to_owned()
+    }
+}
+
+impl DeadStoreElimination {
+    /// Performs one round of the transformation. Every instruction removed shortens the
+    /// code, so the caller can detect changes by comparing the length.
+    fn transform(
+        target: &FunctionTarget,
+        live_vars: &BTreeMap<CodeOffset, LiveVarInfoAtCodeOffset>,
+    ) -> Vec<Bytecode> {
+        let is_alive_after = |offset: usize, temp: TempIndex| {
+            live_vars
+                .get(&(offset as CodeOffset))
+                .map(|info| info.after.contains(&temp))
+                .unwrap_or(true)
+        };
+        let code = target.get_bytecode();
+        let mut new_code = vec![];
+        let mut offset = 0;
+        while offset < code.len() {
+            let bc = &code[offset];
+            match bc {
+                Bytecode::Assign(_, dest, src, _)
+                    if dest == src || !is_alive_after(offset, *dest) =>
+                {
+                    // Remove the store.
+                },
+                Bytecode::Load(_, dest, _) if !is_alive_after(offset, *dest) => {
+                    // Remove the store.
+                },
+                _ => {
+                    if let Some(Bytecode::Assign(_, next_dest, next_src, _)) = code.get(offset + 1)
+                    {
+                        if let Some(collapsed) =
+                            Self::collapse_chain(target, bc, *next_dest, *next_src)
+                        {
+                            if !is_alive_after(offset + 1, *next_src) {
+                                new_code.push(collapsed);
+                                offset += 2;
+                                continue;
+                            }
+                        }
+                    }
+                    new_code.push(bc.clone())
+                },
+            }
+            offset += 1;
+        }
+        new_code
+    }
+
+    /// Given an instruction `bc` defining `src`, followed by `dest := src`, returns the
+    /// instruction with `src` replaced by `dest` as destination, if this is admissible.
+    fn collapse_chain(
+        target: &FunctionTarget,
+        bc: &Bytecode,
+        dest: TempIndex,
+        src: TempIndex,
+    ) -> Option<Bytecode> {
+        if dest == src || target.get_local_type(dest) != target.get_local_type(src) {
+            return None;
+        }
+        let retarget = |dests: &[TempIndex]| -> Option<Vec<TempIndex>> {
+            if dests.iter().filter(|t| **t == src).count() == 1 && !dests.contains(&dest) {
+                Some(
+                    dests
+                        .iter()
+                        .map(|t| if *t == src { dest } else { *t })
+                        .collect(),
+                )
+            } else {
+                None
+            }
+        };
+        match bc {
+            Bytecode::Assign(attr, t, s, kind) if *t == src => {
+                Some(Bytecode::Assign(*attr, dest, *s, *kind))
+            },
+            Bytecode::Load(attr, t, cons) if *t == src => {
+                Some(Bytecode::Load(*attr, dest, cons.clone()))
+            },
+            Bytecode::Call(attr, dests, op, srcs, None) => retarget(dests)
+                .map(|dests| Bytecode::Call(*attr, dests, op.clone(), srcs.clone(), None)),
+            _ => None,
+        }
+    }
+}
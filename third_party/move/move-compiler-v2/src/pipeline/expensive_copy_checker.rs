@@ -0,0 +1,165 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a checker which warns about copies of large values which are only borrowed
+//! afterwards, so borrowing the original would avoid the copy. A value is large if its type
+//! is a vector, or a struct which has more than `Options::large_struct_fields` fields or
+//! contains a large value.
+//!
+//! Values are copied by dereferencing a reference, as in `*r` or `r.f`, and by assigning a
+//! variable which is used again later, as in `let w = v;`, which the live-variable analysis
+//! tells. Reading a variable which is not used again moves it instead. A copy is necessary, and
+//! not reported, if the copy is used other than by immutable borrows, for example if it is
+//! mutated, passed to a function, or returned, which requires a value of its own. It is also
+//! necessary if the original may change while the copy is in use: if the original variable is
+//! assigned or mutably borrowed, or the value is dereferenced through a mutable reference. Uses
+//! are followed through assignments, irrespective of control flow.
+//!
+//! The checker relies on the `LiveVarAnnotation`, so it must run after the
+//! `LiveVarAnalysisProcessor` and before any processor which changes the code. The warning can
+//! be suppressed with the lint `expensive_copy`.
+
+use crate::{diagnostic_codes, Options};
+use move_model::{
+    ast::TempIndex,
+    model::{FunctionEnv, GlobalEnv},
+    ty::{ReferenceKind, Type},
+};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis::LiveVarAnnotation,
+    stackless_bytecode::{Bytecode, Operation},
+};
+use std::collections::BTreeSet;
+
+pub struct ExpensiveCopyChecker();
+
+impl FunctionTargetProcessor for ExpensiveCopyChecker {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() || !fun_env.module_env.is_target() {
+            return data;
+        }
+        let Some(live_vars) = data.annotations.get::<LiveVarAnnotation>() else {
+            return data;
+        };
+        let env = fun_env.module_env.env;
+        let max_fields = env
+            .get_extension::<Options>()
+            .unwrap_or_default()
+            .large_struct_fields;
+        let target = FunctionTarget::new(fun_env, &data);
+        for (offset, bc) in data.code.iter().enumerate() {
+            let (attr_id, copy, kind) = match bc {
+                Bytecode::Call(attr_id, dests, Operation::ReadRef, srcs, _)
+                    if matches!(
+                        target.get_local_type(srcs[0]),
+                        Type::Reference(ReferenceKind::Immutable, _)
+                    ) =>
+                {
+                    (*attr_id, dests[0], "dereference")
+                },
+                Bytecode::Assign(attr_id, dest, src, _)
+                    if live_vars
+                        .get_live_var_info_at(offset as u16)
+                        .map_or(false, |info| info.after.contains(src))
+                        && !is_mutated(&target, &data.code, *src) =>
+                {
+                    (*attr_id, *dest, "assignment")
+                },
+                _ => continue,
+            };
+            let ty = target.get_local_type(copy);
+            if is_large(env, ty, max_fields) && is_only_borrowed(&target, &data.code, copy) {
+                diagnostic_codes::EXPENSIVE_COPY.warning(
+                    env,
+                    &target.get_bytecode_loc(attr_id),
+                    &format!(
+                        "{} copies a large value of type `{}` which is only borrowed \
+                         afterwards; consider borrowing instead",
+                        kind,
+                        ty.display(&fun_env.get_type_display_ctx())
+                    ),
+                )
+            }
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "ExpensiveCopyChecker".to_owned()
+    }
+}
+
+/// Returns whether values of the type are large.
+fn is_large(env: &GlobalEnv, ty: &Type, max_fields: usize) -> bool {
+    match ty {
+        Type::Vector(_) => true,
+        Type::Struct(mid, sid, inst) => {
+            let struct_env = env.get_struct(mid.qualified(*sid));
+            struct_env.get_field_count() > max_fields
+                || struct_env
+                    .get_fields()
+                    .any(|f| is_large(env, &f.get_type().instantiate(inst), max_fields))
+        },
+        _ => false,
+    }
+}
+
+/// Returns whether the temporary is assigned, or borrowed mutably.
+fn is_mutated(target: &FunctionTarget, code: &[Bytecode], temp: TempIndex) -> bool {
+    code.iter().any(|bc| match bc {
+        Bytecode::Assign(_, dest, ..) | Bytecode::Load(_, dest, _) => *dest == temp,
+        Bytecode::Call(_, dests, Operation::BorrowLoc, srcs, _) if srcs[0] == temp => {
+            matches!(
+                target.get_local_type(dests[0]),
+                Type::Reference(ReferenceKind::Mutable, _)
+            )
+        },
+        Bytecode::Call(_, dests, ..) => dests.contains(&temp),
+        _ => false,
+    })
+}
+
+/// Returns whether the temporary, and the temporaries it is assigned to, are only borrowed
+/// immutably.
+fn is_only_borrowed(target: &FunctionTarget, code: &[Bytecode], temp: TempIndex) -> bool {
+    let mut copies = BTreeSet::from([temp]);
+    let mut todo = vec![temp];
+    while let Some(temp) = todo.pop() {
+        for bc in code {
+            match bc {
+                Bytecode::Assign(_, dest, src, _) if *src == temp => {
+                    if copies.insert(*dest) {
+                        todo.push(*dest)
+                    }
+                },
+                Bytecode::Call(_, dests, Operation::BorrowLoc, srcs, _) if srcs[0] == temp => {
+                    if !matches!(
+                        target.get_local_type(dests[0]),
+                        Type::Reference(ReferenceKind::Immutable, _)
+                    ) {
+                        return false;
+                    }
+                },
+                _ => {
+                    let mut used = false;
+                    bc.clone().remap_src_vars(target, &mut |src| {
+                        used |= src == temp;
+                        src
+                    });
+                    if used {
+                        return false;
+                    }
+                },
+            }
+        }
+    }
+    true
+}
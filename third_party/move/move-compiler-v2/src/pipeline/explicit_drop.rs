@@ -0,0 +1,259 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a processor which makes the release of values explicit, so the file format
+//! generator does not need to reason about values which are left behind:
+//!
+//! - A temporary which is defined but not used afterwards is destroyed right after its
+//!   definition. This is the case for the results of calls which are ignored, as in `f();`.
+//!   The type of the temporary must have the `drop` ability, otherwise an error is reported.
+//! - A reference which is alive at a branch but not at one of its targets is destroyed on the
+//!   edge to this target, by splitting the edge. Otherwise the reference would stay in its
+//!   local and keep the borrowed value borrowed, which the bytecode verifier rejects if the
+//!   value is borrowed again. Other values which die on an edge can stay in their local.
+//! - If a function has multiple `return` instructions, they are replaced by jumps to a single
+//!   exit block, so there is only one point where the stack must be balanced for the return.
+//!
+//! The processor must run after `LiveVarAnalysisProcessor`, and recomputes the live-variable
+//! annotation for the transformed code.
+
+use crate::{
+    bytecode_generator::DeclaredLocals, diagnostic_codes, pipeline::ability_checker::is_checkable,
+};
+use move_binary_format::file_format::{Ability, CodeOffset};
+use move_model::{
+    ast::TempIndex,
+    exp_generator::ExpGenerator,
+    model::{FunctionEnv, Loc},
+};
+use move_stackless_bytecode::{
+    function_data_builder::FunctionDataBuilder,
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis::{self, LiveVarAnnotation},
+    stackless_bytecode::{AssignKind, AttrId, Bytecode, Label, Operation},
+};
+use std::collections::BTreeMap;
+
+pub struct ExplicitDrop();
+
+impl FunctionTargetProcessor for ExplicitDrop {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() {
+            return data;
+        }
+        let live_vars = data
+            .annotations
+            .get::<LiveVarAnnotation>()
+            .expect("live variable annotation")
+            .clone();
+        let code = data.code.clone();
+        let mut transformer = DropTransformer {
+            builder: FunctionDataBuilder::new(fun_env, data),
+            live_vars,
+            label_offsets: Bytecode::label_offsets(&code),
+            edge_blocks: vec![],
+        };
+        transformer.builder.data.code = vec![];
+        transformer.transform(code);
+        let mut data = transformer.builder.data;
+        let target = FunctionTarget::new(fun_env, &data);
+        let live_vars = LiveVarAnnotation::from_map(livevar_analysis::run_livevar_analysis(
+            &target, &data.code,
+        ));
+        data.annotations.set(live_vars, true);
+        data
+    }
+
+    fn name(&self) -> String {
+        "ExplicitDrop".to_owned()
+    }
+}
+
+struct DropTransformer<'env> {
+    builder: FunctionDataBuilder<'env>,
+    live_vars: LiveVarAnnotation,
+    label_offsets: BTreeMap<Label, CodeOffset>,
+    /// Blocks which destroy references on edges, appended to the end of the code.
+    edge_blocks: Vec<Bytecode>,
+}
+
+impl<'env> DropTransformer<'env> {
+    fn transform(&mut self, code: Vec<Bytecode>) {
+        // If there are multiple returns, create temporaries for the results and a label for
+        // the exit block.
+        let exit = if code
+            .iter()
+            .filter(|bc| matches!(bc, Bytecode::Ret(..)))
+            .count()
+            > 1
+        {
+            let result_temps = self
+                .builder
+                .data
+                .result_type
+                .clone()
+                .flatten()
+                .into_iter()
+                .map(|ty| self.builder.new_temp(ty))
+                .collect::<Vec<_>>();
+            Some((self.builder.new_label(), result_temps))
+        } else {
+            None
+        };
+        let mut exit_attr = None;
+        for (offset, bc) in code.into_iter().enumerate() {
+            let offset = offset as CodeOffset;
+            // Code which is not reachable has no live variable information.
+            let Some(info) = self.live_vars.get_live_var_info_at(offset) else {
+                self.builder.emit(bc);
+                continue;
+            };
+            let after = info.after.clone();
+            match bc {
+                Bytecode::Ret(attr_id, srcs) if exit.is_some() => {
+                    let (exit_label, result_temps) = exit.as_ref().expect("exit");
+                    for (temp, src) in result_temps.iter().zip(srcs) {
+                        let attr_id = self.builder.new_attr_with_cloned_info(attr_id);
+                        self.builder
+                            .emit(Bytecode::Assign(attr_id, *temp, src, AssignKind::Move))
+                    }
+                    let jump_attr = self.builder.new_attr_with_cloned_info(attr_id);
+                    self.builder.emit(Bytecode::Jump(jump_attr, *exit_label));
+                    exit_attr.get_or_insert(attr_id);
+                },
+                Bytecode::Branch(attr_id, then_label, else_label, cond) => {
+                    let then_label = self.split_edge(attr_id, offset, then_label);
+                    let else_label = self.split_edge(attr_id, offset, else_label);
+                    self.builder
+                        .emit(Bytecode::Branch(attr_id, then_label, else_label, cond))
+                },
+                Bytecode::Assign(attr_id, dest, _, _) | Bytecode::Load(attr_id, dest, _) => {
+                    self.builder.emit(bc);
+                    if !after.contains(&dest) {
+                        self.destroy(attr_id, dest)
+                    }
+                },
+                Bytecode::Call(attr_id, ref dests, ..) => {
+                    let dests = dests.clone();
+                    self.builder.emit(bc);
+                    for dest in dests {
+                        if !after.contains(&dest) {
+                            self.destroy(attr_id, dest)
+                        }
+                    }
+                },
+                _ => self.builder.emit(bc),
+            }
+        }
+        if let (Some((exit_label, result_temps)), Some(attr_id)) = (exit, exit_attr) {
+            let label_attr = self.builder.new_attr_with_cloned_info(attr_id);
+            self.builder.emit(Bytecode::Label(label_attr, exit_label));
+            let ret_attr = self.builder.new_attr_with_cloned_info(attr_id);
+            self.builder.emit(Bytecode::Ret(ret_attr, result_temps));
+        }
+        let edge_blocks = std::mem::take(&mut self.edge_blocks);
+        self.builder.emit_vec(edge_blocks);
+    }
+
+    /// Emits an instruction destroying the temporary, after checking that it can be dropped.
+    fn destroy(&mut self, attr_id: AttrId, temp: TempIndex) {
+        let loc = self.builder.get_loc(attr_id);
+        self.check_drop(&loc, temp);
+        let attr_id = self.builder.new_attr_with_cloned_info(attr_id);
+        self.builder.emit(Bytecode::Call(
+            attr_id,
+            vec![],
+            Operation::Destroy,
+            vec![temp],
+            None,
+        ))
+    }
+
+    /// If references die on the edge from the branch at the offset to the label, creates a
+    /// block which destroys them and continues at the label. Returns the label to branch to.
+    fn split_edge(&mut self, attr_id: AttrId, offset: CodeOffset, label: Label) -> Label {
+        let after = &self
+            .live_vars
+            .get_live_var_info_at(offset)
+            .expect("live vars")
+            .after;
+        let before_target = self
+            .live_vars
+            .get_live_var_info_at(self.label_offsets[&label])
+            .map(|info| info.before.clone())
+            .unwrap_or_default();
+        let dead_refs = after
+            .iter()
+            .filter(|temp| {
+                !before_target.contains(temp) && self.builder.get_local_type(**temp).is_reference()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if dead_refs.is_empty() {
+            return label;
+        }
+        let block_label = self.builder.new_label();
+        let mut block = vec![Bytecode::Label(
+            self.builder.new_attr_with_cloned_info(attr_id),
+            block_label,
+        )];
+        for temp in dead_refs {
+            block.push(Bytecode::Call(
+                self.builder.new_attr_with_cloned_info(attr_id),
+                vec![],
+                Operation::Destroy,
+                vec![temp],
+                None,
+            ))
+        }
+        block.push(Bytecode::Jump(
+            self.builder.new_attr_with_cloned_info(attr_id),
+            label,
+        ));
+        self.edge_blocks.extend(block);
+        block_label
+    }
+
+    /// Checks that the type of the temporary has the `drop` ability.
+    fn check_drop(&self, loc: &Loc, temp: TempIndex) {
+        let fun_env = self.builder.fun_env;
+        if !fun_env.module_env.is_target() {
+            return;
+        }
+        let env = fun_env.module_env.env;
+        let ty = self.builder.get_local_type(temp);
+        if !is_checkable(&ty)
+            || env
+                .type_abilities(&ty, &fun_env.get_type_parameters())
+                .has_ability(Ability::Drop)
+        {
+            return;
+        }
+        let what = match self
+            .builder
+            .data
+            .annotations
+            .get::<DeclaredLocals>()
+            .and_then(|d| d.vars.get(&temp))
+        {
+            Some((name, _)) => format!("local `{}`", name.display(env.symbol_pool())),
+            None => "value".to_owned(),
+        };
+        diagnostic_codes::VALUE_MISSING_ABILITY.error(
+            env,
+            loc,
+            &format!(
+                "{} of type `{}` does not have the `drop` ability",
+                what,
+                ty.display(&fun_env.get_type_display_ctx())
+            ),
+        )
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a function inlining processor. A call is replaced by the body of the called
+//! function if the callee is defined in the same module as the caller, is not recursive, and
+//! either has the `#[inline]` attribute or is private and has at most
+//! `MAX_INLINED_INSTRUCTIONS` instructions. Calls into other modules are never inlined, since
+//! the body of the callee may use operations which are only allowed in its own module.
+//!
+//! The body of the callee is instantiated with the type arguments of the call. Its temporaries
+//! are appended to the ones of the caller, with the parameters initialized from the arguments
+//! of the call, and its labels are renamed to fresh ones. A return assigns the results of the
+//! call and jumps behind the inlined body. Inlined instructions keep the locations they have
+//! in the callee, so diagnostics about them point to its source.
+//!
+//! Functions are processed bottom-up in the call graph, so callees have already been
+//! processed, and inlining is transitive. If a function with the `#[inline]` attribute is
+//! recursive, a warning is reported. The processor does not rely on other analyses. It should
+//! run before the `LiveVarAnalysisProcessor`, since it changes the code.
+
+use crate::diagnostic_codes;
+use move_binary_format::file_format::Visibility;
+use move_compiler::shared::known_attributes::OptimizationAttribute;
+use move_model::{
+    model::{FunctionEnv, GlobalEnv, Loc},
+    ty::Type,
+};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    stackless_bytecode::{AbortAction, AssignKind, AttrId, Bytecode, Label, Operation},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The maximal number of instructions of a private function without the `#[inline]`
+/// attribute for calls to it to be inlined.
+pub const MAX_INLINED_INSTRUCTIONS: usize = 10;
+
+pub struct FunctionInliner();
+
+impl FunctionTargetProcessor for FunctionInliner {
+    fn initialize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        for fun_id in targets.get_funs() {
+            let fun_env = env.get_function(fun_id);
+            if fun_env.module_env.is_target()
+                && has_inline_attribute(&fun_env)
+                && is_recursive(&fun_env)
+            {
+                diagnostic_codes::RECURSIVE_INLINE_FUNCTION.warning(
+                    env,
+                    &fun_env.get_loc(),
+                    &format!(
+                        "function `{}` has the `#[inline]` attribute, but is recursive, so \
+                         calls to it are not inlined",
+                        fun_env.get_full_name_str()
+                    ),
+                )
+            }
+        }
+    }
+
+    fn process(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        mut data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() {
+            return data;
+        }
+        let env = fun_env.module_env.env;
+        let next_attr = data.next_free_attr_index();
+        let next_label = data.next_free_label_index();
+        let code = std::mem::take(&mut data.code);
+        let mut inliner = Inliner {
+            env,
+            next_attr,
+            next_label,
+            data: &mut data,
+            code: vec![],
+        };
+        for bc in code {
+            match bc {
+                Bytecode::Call(attr, dests, Operation::Function(mid, fid, inst), srcs, None) => {
+                    let callee_env = env.get_function(mid.qualified(fid));
+                    match targets.get_data(&callee_env.get_qualified_id(), &inliner.data.variant) {
+                        Some(callee_data) if should_inline(fun_env, &callee_env, callee_data) => {
+                            let callee = FunctionTarget::new(&callee_env, callee_data);
+                            inliner.inline_call(&callee, attr, &dests, &inst, &srcs)
+                        },
+                        _ => inliner.code.push(Bytecode::Call(
+                            attr,
+                            dests,
+                            Operation::Function(mid, fid, inst),
+                            srcs,
+                            None,
+                        )),
+                    }
+                },
+                _ => inliner.code.push(bc),
+            }
+        }
+        data.code = inliner.code;
+        data
+    }
+
+    fn name(&self) -> String {
+        "FunctionInliner".to_string()
+    }
+}
+
+/// Returns true if calls from the caller to the callee are inlined.
+fn should_inline(caller: &FunctionEnv, callee: &FunctionEnv, callee_data: &FunctionData) -> bool {
+    callee.module_env.get_id() == caller.module_env.get_id()
+        && !callee.is_native()
+        && !is_recursive(callee)
+        && (has_inline_attribute(callee)
+            || (callee.visibility() == Visibility::Private
+                && callee_data.code.len() <= MAX_INLINED_INSTRUCTIONS))
+}
+
+fn has_inline_attribute(fun_env: &FunctionEnv) -> bool {
+    let pool = fun_env.module_env.env.symbol_pool();
+    fun_env.has_attribute(|attr| pool.string(attr.name()).as_str() == OptimizationAttribute::INLINE)
+}
+
+/// Returns true if the function calls itself, directly or via other functions.
+fn is_recursive(fun_env: &FunctionEnv) -> bool {
+    let env = fun_env.module_env.env;
+    let id = fun_env.get_qualified_id();
+    let mut visited = BTreeSet::new();
+    let mut todo = vec![id];
+    while let Some(caller) = todo.pop() {
+        let callees = env
+            .get_function(caller)
+            .get_called_functions()
+            .cloned()
+            .unwrap_or_default();
+        for callee in callees {
+            if callee == id {
+                return true;
+            }
+            if visited.insert(callee) {
+                todo.push(callee)
+            }
+        }
+    }
+    false
+}
+
+/// The state of inlining calls into the code of a function.
+struct Inliner<'a> {
+    env: &'a GlobalEnv,
+    /// The data of the function, whose code is built in `code`.
+    data: &'a mut FunctionData,
+    code: Vec<Bytecode>,
+    next_attr: usize,
+    next_label: usize,
+}
+
+impl<'a> Inliner<'a> {
+    /// Creates a fresh attribute id for an instruction at the location.
+    fn new_attr(&mut self, loc: Loc) -> AttrId {
+        let attr = AttrId::new(self.next_attr);
+        self.next_attr += 1;
+        self.data.locations.insert(attr, loc);
+        attr
+    }
+
+    fn new_label(&mut self) -> Label {
+        let label = Label::new(self.next_label);
+        self.next_label += 1;
+        label
+    }
+
+    /// Emits the body of the callee in place of the call `dests := callee<inst>(srcs)`.
+    fn inline_call(
+        &mut self,
+        callee: &FunctionTarget,
+        call_attr: AttrId,
+        dests: &[usize],
+        inst: &[Type],
+        srcs: &[usize],
+    ) {
+        let call_loc = self
+            .data
+            .locations
+            .get(&call_attr)
+            .cloned()
+            .unwrap_or_else(|| self.env.internal_loc());
+        let body = callee
+            .data
+            .fork_with_instantiation(self.env, inst, callee.data.variant.clone());
+        let body_target = FunctionTarget::new(callee.func_env, &body);
+        let offset = self.data.local_types.len();
+        self.data
+            .local_types
+            .extend(body.local_types.iter().cloned());
+        for (param, src) in srcs.iter().enumerate() {
+            let attr = self.new_attr(call_loc.clone());
+            self.code.push(Bytecode::Assign(
+                attr,
+                offset + param,
+                *src,
+                AssignKind::Move,
+            ))
+        }
+        let mut labels = BTreeMap::new();
+        for bc in &body.code {
+            if let Bytecode::Label(_, label) = bc {
+                let new_label = self.new_label();
+                labels.insert(*label, new_label);
+            }
+        }
+        let end_label = self.new_label();
+        let last = body.code.len().saturating_sub(1);
+        for (offset_in_body, bc) in body.code.iter().enumerate() {
+            let loc = body_target.get_bytecode_loc(bc.get_attr_id());
+            let attr = self.new_attr(loc.clone());
+            let bc = bc
+                .clone()
+                .remap_all_vars(&body_target, &mut |temp| offset + temp);
+            let new_bc = match bc {
+                Bytecode::Ret(_, rets) => {
+                    for (dest, ret) in dests.iter().zip(rets) {
+                        let attr = self.new_attr(loc.clone());
+                        self.code
+                            .push(Bytecode::Assign(attr, *dest, ret, AssignKind::Move))
+                    }
+                    // A return at the end of the body falls through to the end label.
+                    if offset_in_body == last {
+                        continue;
+                    }
+                    Bytecode::Jump(attr, end_label)
+                },
+                Bytecode::Label(_, label) => Bytecode::Label(attr, labels[&label]),
+                Bytecode::Jump(_, label) => Bytecode::Jump(attr, labels[&label]),
+                Bytecode::Branch(_, if_label, else_label, cond) => {
+                    Bytecode::Branch(attr, labels[&if_label], labels[&else_label], cond)
+                },
+                Bytecode::Call(_, dests, op, srcs, abort_action) => Bytecode::Call(
+                    attr,
+                    dests,
+                    op,
+                    srcs,
+                    abort_action.map(|AbortAction(label, code)| AbortAction(labels[&label], code)),
+                ),
+                Bytecode::Assign(_, dest, src, kind) => Bytecode::Assign(attr, dest, src, kind),
+                Bytecode::Load(_, dest, cons) => Bytecode::Load(attr, dest, cons),
+                Bytecode::Abort(_, code) => Bytecode::Abort(attr, code),
+                Bytecode::Nop(_) => Bytecode::Nop(attr),
+                Bytecode::SaveMem(_, label, mem) => Bytecode::SaveMem(attr, label, mem),
+                Bytecode::SaveSpecVar(_, label, var) => Bytecode::SaveSpecVar(attr, label, var),
+                Bytecode::Prop(_, kind, exp) => Bytecode::Prop(attr, kind, exp),
+            };
+            self.code.push(new_bc)
+        }
+        let attr = self.new_attr(call_loc);
+        self.code.push(Bytecode::Label(attr, end_label))
+    }
+}
@@ -1,5 +1,75 @@
 // Copyright © Aptos Foundation
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
+pub mod ability_checker;
+pub mod acquires_inference;
+pub mod annotation_formatters;
+pub mod cfg_simplifier;
+pub mod constant_folding;
+pub mod copy_propagation;
+pub mod dead_store_elimination;
+pub mod expensive_copy_checker;
+pub mod explicit_drop;
+pub mod function_inlining;
 pub mod livevar_analysis_processor;
+pub mod must_use_checker;
+pub mod reference_safety_processor;
+pub mod unreachable_code_remover;
+pub mod unused_vars_checker;
 pub mod visibility_checker;
+
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::{
+    function_target::FunctionData,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+};
+
+/// A processor which runs another processor only on one function variant, passing the data
+/// of other variants through unchanged. It has the name of the wrapped processor, so pipeline
+/// extensions can be positioned relative to it.
+pub struct VariantProcessor {
+    variant: FunctionVariant,
+    processor: Box<dyn FunctionTargetProcessor>,
+}
+
+impl VariantProcessor {
+    /// Restricts the processor to the variant. Single-run processors see all variants and
+    /// cannot be restricted.
+    pub fn new(variant: FunctionVariant, processor: Box<dyn FunctionTargetProcessor>) -> Self {
+        assert!(
+            !processor.is_single_run(),
+            "single-run processor `{}` cannot be restricted to a variant",
+            processor.name()
+        );
+        Self { variant, processor }
+    }
+}
+
+impl FunctionTargetProcessor for VariantProcessor {
+    fn process_and_maybe_remove(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        scc_opt: Option<&[FunctionEnv]>,
+    ) -> Option<FunctionData> {
+        if data.variant == self.variant {
+            self.processor
+                .process_and_maybe_remove(targets, fun_env, data, scc_opt)
+        } else {
+            Some(data)
+        }
+    }
+
+    fn name(&self) -> String {
+        self.processor.name()
+    }
+
+    fn initialize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.processor.initialize(env, targets)
+    }
+
+    fn finalize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.processor.finalize(env, targets)
+    }
+}
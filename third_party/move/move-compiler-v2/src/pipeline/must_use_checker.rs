@@ -0,0 +1,118 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a checker which warns about calls of functions annotated with
+//! `#[lint::must_use]` whose results are never read. Such functions return values like
+//! capabilities or receipts, which should not be dropped silently. The attribute can carry a
+//! note on what to do with the result, as in `#[lint::must_use(note = b"redeem the receipt")]`,
+//! which is added to the warning.
+//!
+//! A result is read if its temporary is alive after the call, which the live-variable analysis
+//! tells. Results which are deliberately discarded, as in `let _ = f();` or `_ = f();`, are not
+//! reported, nor are calls where some result of a tuple is read.
+//!
+//! The checker relies on the `LiveVarAnnotation`, so it must run after the
+//! `LiveVarAnalysisProcessor` and before any processor which changes the code, and on the
+//! `DeclaredLocals` annotation attached by the bytecode generator. The warning can be
+//! suppressed with the lint `unused_must_use`.
+
+use crate::{bytecode_generator::DeclaredLocals, diagnostic_codes};
+use move_compiler::shared::known_attributes::LintAttribute;
+use move_model::{
+    ast::{Attribute, AttributeValue, Value},
+    model::{FunctionEnv, GlobalEnv},
+};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis::LiveVarAnnotation,
+    stackless_bytecode::{Bytecode, Operation},
+};
+
+/// The name of the argument of `lint::must_use` with a note on the result.
+const NOTE: &str = "note";
+
+pub struct MustUseChecker();
+
+impl FunctionTargetProcessor for MustUseChecker {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() || !fun_env.module_env.is_target() {
+            return data;
+        }
+        let Some(live_vars) = data.annotations.get::<LiveVarAnnotation>() else {
+            return data;
+        };
+        let discarded = data
+            .annotations
+            .get::<DeclaredLocals>()
+            .map(|declared| declared.discarded.clone())
+            .unwrap_or_default();
+        let env = fun_env.module_env.env;
+        let target = FunctionTarget::new(fun_env, &data);
+        for (offset, bc) in data.code.iter().enumerate() {
+            let Bytecode::Call(attr_id, dests, Operation::Function(mid, fid, _), _, _) = bc else {
+                continue;
+            };
+            if dests.is_empty() || dests.iter().any(|dest| discarded.contains(dest)) {
+                continue;
+            }
+            let read = live_vars
+                .get_live_var_info_at(offset as u16)
+                .map_or(true, |info| {
+                    dests.iter().any(|dest| info.after.contains(dest))
+                });
+            if read {
+                continue;
+            }
+            let callee = env.get_function(mid.qualified(*fid));
+            let Some(note) = must_use(env, callee.get_attributes()) else {
+                continue;
+            };
+            diagnostic_codes::UNUSED_MUST_USE.warning_with_context(
+                env,
+                &target.get_bytecode_loc(*attr_id),
+                &format!(
+                    "the result of `{}` is never used; discard it deliberately with \
+                     `let _ = ..`",
+                    callee.get_full_name_str()
+                ),
+                vec![(
+                    callee.get_loc(),
+                    format!("annotated with `#[{}]`", LintAttribute::MUST_USE),
+                )],
+                note.into_iter().collect(),
+            )
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "MustUseChecker".to_owned()
+    }
+}
+
+/// Returns whether the attributes include `lint::must_use`, with its note, if any.
+fn must_use(env: &GlobalEnv, attributes: &[Attribute]) -> Option<Option<String>> {
+    let args = attributes.iter().find_map(|attr| match attr {
+        Attribute::Apply(_, name, args)
+            if env.symbol_pool().string(*name).as_str() == LintAttribute::MUST_USE =>
+        {
+            Some(args)
+        },
+        _ => None,
+    })?;
+    Some(args.iter().find_map(|arg| match arg {
+        Attribute::Assign(_, name, AttributeValue::Value(_, Value::ByteArray(bytes)))
+            if env.symbol_pool().string(*name).as_str() == NOTE =>
+        {
+            Some(String::from_utf8_lossy(bytes).to_string())
+        },
+        _ => None,
+    }))
+}
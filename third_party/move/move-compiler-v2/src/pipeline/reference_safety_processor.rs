@@ -0,0 +1,623 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a reference safety checker, based on a borrow graph computed by a forward
+//! data-flow analysis. The nodes of the graph are locals, global resources, and temporaries
+//! holding references. An edge from a node to a reference is labelled with a path, which
+//! describes which part of the parent is borrowed: a sequence of field selections, possibly
+//! ending in an unknown selection, which stands for borrows through function calls, for
+//! instance of vector elements.
+//!
+//! References are released as soon as they are not alive anymore. When a reference is
+//! released, the references it borrows are re-attached to its parents. The checker reports:
+//!
+//! - mutable borrows of something already borrowed, and immutable borrows of something
+//!   mutably borrowed;
+//! - moves of, or assignments to, borrowed locals, and copies of mutably borrowed locals;
+//! - reads and freezes through references which are mutably borrowed, and writes through
+//!   references which are borrowed;
+//! - mutable references passed to functions while they are borrowed, and calls of functions
+//!   which acquire a borrowed resource;
+//! - returns of references derived from locals or global resources.
+//!
+//! The processor must run after `LiveVarAnalysisProcessor` and `AcquiresInference`, and after
+//! any processor which changes the code.
+
+use crate::{
+    bytecode_generator::DeclaredLocals,
+    diagnostic_codes::{self, DiagnosticCode},
+    pipeline::acquires_inference::AcquiresAnnotation,
+};
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::TempIndex,
+    model::{FunId, FunctionEnv, QualifiedId, StructId},
+};
+use move_stackless_bytecode::{
+    dataflow_analysis::{DataflowAnalysis, TransferFunctions},
+    dataflow_domains::{AbstractDomain, JoinResult},
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    livevar_analysis::LiveVarAnnotation,
+    stackless_bytecode::{Bytecode, Operation},
+    stackless_control_flow_graph::StacklessControlFlowGraph,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct ReferenceSafetyProcessor();
+
+impl FunctionTargetProcessor for ReferenceSafetyProcessor {
+    fn process(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() || !fun_env.module_env.is_target() {
+            return data;
+        }
+        // Collect the resources acquired by called functions. The data of the function
+        // itself is not in the holder while it is processed.
+        let mut callee_acquires = BTreeMap::new();
+        for bc in &data.code {
+            if let Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) = bc {
+                let callee = mid.qualified(*fid);
+                let callee_data = if callee == fun_env.get_qualified_id() {
+                    Some(&data)
+                } else {
+                    targets.get_data(&callee, &FunctionVariant::Baseline)
+                };
+                let acquires = callee_data
+                    .and_then(|d| d.annotations.get::<AcquiresAnnotation>())
+                    .map(|a| a.acquires.clone())
+                    .unwrap_or_default();
+                callee_acquires.insert(callee, acquires);
+            }
+        }
+        let target = FunctionTarget::new(fun_env, &data);
+        let analysis = BorrowAnalysis {
+            callee_acquires,
+            target: &target,
+            live_vars: target
+                .get_annotations()
+                .get::<LiveVarAnnotation>()
+                .expect("live variable annotation"),
+            declared: target.get_annotations().get::<DeclaredLocals>(),
+        };
+        let code = target.get_bytecode();
+        let cfg = StacklessControlFlowGraph::new_forward(code);
+        let state_map = analysis.analyze_function(BorrowState::default(), code, &cfg);
+        let per_offset =
+            analysis.state_per_instruction(state_map, code, &cfg, |before, _| before.clone());
+        // Replay each instruction on the state before it, now collecting errors.
+        for (offset, before) in per_offset {
+            let mut state = before;
+            let mut errors = BTreeSet::new();
+            let bc = &code[offset as usize];
+            analysis.step(&mut state, bc, offset, &mut errors);
+            let loc = target.get_bytecode_loc(bc.get_attr_id());
+            for (msg, code) in errors {
+                code.error(target.global_env(), &loc, &msg)
+            }
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "ReferenceSafetyProcessor".to_owned()
+    }
+}
+
+/// A node in the borrow graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    /// A local holding a value.
+    Local(TempIndex),
+    /// A global resource.
+    Global(QualifiedId<StructId>),
+    /// A temporary holding a reference.
+    Ref(TempIndex),
+}
+
+/// A selector in a borrow path.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Selector {
+    /// A field, identified by its offset in the struct.
+    Field(usize),
+    /// An unknown part of the value, as for references returned by functions.
+    Unknown,
+}
+
+type Path = Vec<Selector>;
+
+/// Concatenates two paths. Since an unknown selector may cover any part of a value,
+/// paths are truncated after the first unknown selector, which keeps the domain finite.
+fn concat(prefix: &[Selector], suffix: &[Selector]) -> Path {
+    let mut path = vec![];
+    for sel in prefix.iter().chain(suffix) {
+        path.push(sel.clone());
+        if *sel == Selector::Unknown {
+            break;
+        }
+    }
+    path
+}
+
+/// Determines whether two paths may denote overlapping parts of a value.
+fn overlaps(p1: &[Selector], p2: &[Selector]) -> bool {
+    for (s1, s2) in p1.iter().zip(p2) {
+        if s1 != s2 {
+            return *s1 == Selector::Unknown || *s2 == Selector::Unknown;
+        }
+    }
+    true
+}
+
+/// The borrow graph, mapping each node to the references borrowing from it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct BorrowState {
+    edges: BTreeMap<Node, BTreeSet<(TempIndex, Path)>>,
+}
+
+impl AbstractDomain for BorrowState {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        let mut result = JoinResult::Unchanged;
+        for (node, children) in &other.edges {
+            let own = self.edges.entry(*node).or_default();
+            for child in children {
+                if own.insert(child.clone()) {
+                    result = JoinResult::Changed
+                }
+            }
+        }
+        result
+    }
+}
+
+impl BorrowState {
+    /// Returns the references borrowing from the node, with their paths.
+    fn children(&self, node: Node) -> impl Iterator<Item = &(TempIndex, Path)> {
+        self.edges.get(&node).into_iter().flatten()
+    }
+
+    /// Returns the nodes the reference borrows from, with the paths.
+    fn parents(&self, temp: TempIndex) -> Vec<(Node, Path)> {
+        self.edges
+            .iter()
+            .flat_map(|(node, children)| {
+                children
+                    .iter()
+                    .filter(move |(child, _)| *child == temp)
+                    .map(move |(_, path)| (*node, path.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns all references in the graph.
+    fn refs(&self) -> BTreeSet<TempIndex> {
+        self.edges
+            .iter()
+            .flat_map(|(node, children)| {
+                let own = match node {
+                    Node::Ref(temp) => Some(*temp),
+                    _ => None,
+                };
+                own.into_iter()
+                    .chain(children.iter().map(|(child, _)| *child))
+            })
+            .collect()
+    }
+
+    /// Moves a reference into another temporary, which takes over its edges.
+    fn rename(&mut self, from: TempIndex, to: TempIndex) {
+        if let Some(children) = self.edges.remove(&Node::Ref(from)) {
+            self.edges.insert(Node::Ref(to), children);
+        }
+        for children in self.edges.values_mut() {
+            *children = std::mem::take(children)
+                .into_iter()
+                .map(|(child, path)| (if child == from { to } else { child }, path))
+                .collect()
+        }
+    }
+
+    fn add_edge(&mut self, parent: Node, child: TempIndex, path: Path) {
+        self.edges.entry(parent).or_default().insert((child, path));
+    }
+
+    /// Releases a reference, attaching the references borrowing from it to its parents.
+    fn release(&mut self, temp: TempIndex) {
+        let parents = self.parents(temp);
+        let children = self.edges.remove(&Node::Ref(temp)).unwrap_or_default();
+        for edges in self.edges.values_mut() {
+            edges.retain(|(child, _)| *child != temp)
+        }
+        for (parent, parent_path) in parents {
+            for (child, child_path) in &children {
+                self.add_edge(parent, *child, concat(&parent_path, child_path))
+            }
+        }
+        self.edges.retain(|_, children| !children.is_empty())
+    }
+
+    /// Determines whether the node is borrowed at a part overlapping with the path. If
+    /// `mutably` is set, only mutable borrows are considered.
+    fn is_borrowed_at(
+        &self,
+        target: &FunctionTarget,
+        node: Node,
+        path: &[Selector],
+        mutably: bool,
+    ) -> bool {
+        self.children(node).any(|(child, child_path)| {
+            overlaps(path, child_path)
+                && (!mutably || target.get_local_type(*child).is_mutable_reference())
+        })
+    }
+
+    /// Returns the roots of the nodes the reference is derived from.
+    fn roots(&self, temp: TempIndex) -> BTreeSet<Node> {
+        let mut roots = BTreeSet::new();
+        let mut todo = vec![temp];
+        let mut visited = BTreeSet::new();
+        while let Some(temp) = todo.pop() {
+            if !visited.insert(temp) {
+                continue;
+            }
+            for (parent, _) in self.parents(temp) {
+                match parent {
+                    Node::Ref(parent_temp) => todo.push(parent_temp),
+                    _ => {
+                        roots.insert(parent);
+                    },
+                }
+            }
+        }
+        roots
+    }
+}
+
+struct BorrowAnalysis<'a> {
+    target: &'a FunctionTarget<'a>,
+    /// The resources acquired by the functions called, as inferred by `AcquiresInference`.
+    callee_acquires: BTreeMap<QualifiedId<FunId>, BTreeSet<StructId>>,
+    live_vars: &'a LiveVarAnnotation,
+    declared: Option<&'a DeclaredLocals>,
+}
+
+impl<'a> TransferFunctions for BorrowAnalysis<'a> {
+    type State = BorrowState;
+
+    const BACKWARD: bool = false;
+
+    fn execute(&self, state: &mut BorrowState, instr: &Bytecode, offset: CodeOffset) {
+        self.step(state, instr, offset, &mut BTreeSet::new())
+    }
+}
+
+impl<'a> DataflowAnalysis for BorrowAnalysis<'a> {}
+
+impl<'a> BorrowAnalysis<'a> {
+    fn is_ref(&self, temp: TempIndex) -> bool {
+        self.target.get_local_type(temp).is_reference()
+    }
+
+    fn is_mut_ref(&self, temp: TempIndex) -> bool {
+        self.target.get_local_type(temp).is_mutable_reference()
+    }
+
+    fn is_alive_after(&self, temp: TempIndex, offset: CodeOffset) -> bool {
+        self.live_vars
+            .get_live_var_info_at(offset)
+            .map(|info| info.after.contains(&temp))
+            .unwrap_or(true)
+    }
+
+    /// Describes a node for error messages.
+    fn describe(&self, node: Node) -> String {
+        let env = self.target.global_env();
+        match node {
+            Node::Local(temp) | Node::Ref(temp) => {
+                match self.declared.and_then(|d| d.vars.get(&temp)) {
+                    Some((name, _)) => format!("local `{}`", name.display(env.symbol_pool())),
+                    None => "value".to_owned(),
+                }
+            },
+            Node::Global(qid) => format!("global `{}`", env.get_struct(qid).get_full_name_str()),
+        }
+    }
+
+    /// Checks and creates a new borrow of the node at the path. Returns the edge to add.
+    fn borrow(
+        &self,
+        state: &BorrowState,
+        parent: Node,
+        path: Path,
+        dest: TempIndex,
+        what: String,
+        errors: &mut BTreeSet<(String, DiagnosticCode)>,
+    ) -> (Node, TempIndex, Path) {
+        if self.is_mut_ref(dest) {
+            if state.is_borrowed_at(self.target, parent, &path, false) {
+                errors.insert((
+                    format!(
+                        "cannot mutably borrow {} since it is already borrowed",
+                        what
+                    ),
+                    diagnostic_codes::BORROW_CONFLICT,
+                ));
+            }
+        } else if state.is_borrowed_at(self.target, parent, &path, true) {
+            errors.insert((
+                format!(
+                    "cannot immutably borrow {} since it is mutably borrowed",
+                    what
+                ),
+                diagnostic_codes::BORROW_CONFLICT,
+            ));
+        }
+        (parent, dest, path)
+    }
+
+    /// Executes the instruction on the borrow graph, collecting errors.
+    fn step(
+        &self,
+        state: &mut BorrowState,
+        bc: &Bytecode,
+        offset: CodeOffset,
+        errors: &mut BTreeSet<(String, DiagnosticCode)>,
+    ) {
+        use Bytecode::*;
+        use Operation::*;
+        let env = self.target.global_env();
+        // The edges created by this instruction.
+        let mut new_edges = vec![];
+        // A reference moved into another temporary.
+        let mut moved_ref = None;
+        match bc {
+            Assign(_, dest, src, _) if self.is_ref(*src) => {
+                // Assigning a mutable to an immutable reference freezes it.
+                if self.is_mut_ref(*src)
+                    && !self.is_mut_ref(*dest)
+                    && state.is_borrowed_at(self.target, Node::Ref(*src), &[], true)
+                {
+                    errors.insert((
+                        "cannot freeze reference since it is mutably borrowed".to_owned(),
+                        diagnostic_codes::BORROW_CONFLICT,
+                    ));
+                }
+                if self.is_alive_after(*src, offset) || src == dest {
+                    new_edges.push((Node::Ref(*src), *dest, vec![]))
+                } else {
+                    moved_ref = Some((*src, *dest))
+                }
+            },
+            Call(_, dests, op, srcs, _) => match op {
+                BorrowLoc => new_edges.push(self.borrow(
+                    state,
+                    Node::Local(srcs[0]),
+                    vec![],
+                    dests[0],
+                    self.describe(Node::Local(srcs[0])),
+                    errors,
+                )),
+                BorrowField(mid, sid, _, field_offset) => {
+                    let struct_env = env.get_struct(mid.qualified(*sid));
+                    let field_name = struct_env
+                        .get_field_by_offset(*field_offset)
+                        .get_name()
+                        .display(env.symbol_pool())
+                        .to_string();
+                    new_edges.push(self.borrow(
+                        state,
+                        Node::Ref(srcs[0]),
+                        vec![Selector::Field(*field_offset)],
+                        dests[0],
+                        format!("field `{}`", field_name),
+                        errors,
+                    ))
+                },
+                BorrowGlobal(mid, sid, _) => {
+                    let node = Node::Global(mid.qualified(*sid));
+                    new_edges.push(self.borrow(
+                        state,
+                        node,
+                        vec![],
+                        dests[0],
+                        self.describe(node),
+                        errors,
+                    ))
+                },
+                MoveFrom(mid, sid, _) => {
+                    let node = Node::Global(mid.qualified(*sid));
+                    if state.children(node).next().is_some() {
+                        errors.insert((
+                            format!(
+                                "cannot move {} from storage since it is borrowed",
+                                self.describe(node)
+                            ),
+                            diagnostic_codes::USE_OF_BORROWED_VALUE,
+                        ));
+                    }
+                },
+                ReadRef => {
+                    if state.is_borrowed_at(self.target, Node::Ref(srcs[0]), &[], true) {
+                        errors.insert((
+                            "cannot read through reference since it is mutably borrowed".to_owned(),
+                            diagnostic_codes::USE_OF_BORROWED_VALUE,
+                        ));
+                    }
+                },
+                WriteRef => {
+                    if state.children(Node::Ref(srcs[0])).next().is_some() {
+                        errors.insert((
+                            "cannot write through reference since it is borrowed".to_owned(),
+                            diagnostic_codes::USE_OF_BORROWED_VALUE,
+                        ));
+                    }
+                },
+                FreezeRef => {
+                    if state.is_borrowed_at(self.target, Node::Ref(srcs[0]), &[], true) {
+                        errors.insert((
+                            "cannot freeze reference since it is mutably borrowed".to_owned(),
+                            diagnostic_codes::BORROW_CONFLICT,
+                        ));
+                    }
+                    new_edges.push((Node::Ref(srcs[0]), dests[0], vec![]))
+                },
+                Eq | Neq => {
+                    for src in srcs {
+                        if self.is_ref(*src)
+                            && state.is_borrowed_at(self.target, Node::Ref(*src), &[], true)
+                        {
+                            errors.insert((
+                                "cannot compare through reference since it is mutably borrowed"
+                                    .to_owned(),
+                                diagnostic_codes::USE_OF_BORROWED_VALUE,
+                            ));
+                        }
+                    }
+                },
+                Function(mid, fid, _) => {
+                    let fun_env = env.get_function(mid.qualified(*fid));
+                    for sid in &self.callee_acquires[&fun_env.get_qualified_id()] {
+                        let node = Node::Global(mid.qualified(*sid));
+                        if state.children(node).next().is_some() {
+                            errors.insert((
+                                format!(
+                                    "cannot call function `{}` which acquires {} since it is \
+                                 borrowed",
+                                    fun_env.get_full_name_str(),
+                                    self.describe(node)
+                                ),
+                                diagnostic_codes::USE_OF_BORROWED_VALUE,
+                            ));
+                        }
+                    }
+                    let ref_args = srcs
+                        .iter()
+                        .filter(|src| self.is_ref(**src))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    for arg in &ref_args {
+                        if self.is_mut_ref(*arg) && state.children(Node::Ref(*arg)).next().is_some()
+                        {
+                            errors.insert((
+                                "cannot pass mutable reference to function since it is borrowed"
+                                    .to_owned(),
+                                diagnostic_codes::USE_OF_BORROWED_VALUE,
+                            ));
+                        }
+                    }
+                    // Returned mutable references borrow from mutable reference arguments,
+                    // immutable ones from all reference arguments.
+                    for dest in dests.iter().filter(|dest| self.is_ref(**dest)) {
+                        for arg in &ref_args {
+                            if !self.is_mut_ref(*dest) || self.is_mut_ref(*arg) {
+                                new_edges.push((Node::Ref(*arg), *dest, vec![Selector::Unknown]))
+                            }
+                        }
+                    }
+                },
+                _ => {},
+            },
+            Ret(_, srcs) => {
+                for src in srcs.iter().filter(|src| self.is_ref(**src)) {
+                    for root in state.roots(*src) {
+                        if !matches!(root, Node::Ref(_)) {
+                            errors.insert((
+                                format!(
+                                    "cannot return a reference derived from {}",
+                                    self.describe(root)
+                                ),
+                                diagnostic_codes::INVALID_REFERENCE_RETURN,
+                            ));
+                        }
+                    }
+                    if self.is_mut_ref(*src) && state.children(Node::Ref(*src)).next().is_some() {
+                        errors.insert((
+                            "cannot return mutable reference since it is borrowed".to_owned(),
+                            diagnostic_codes::INVALID_REFERENCE_RETURN,
+                        ));
+                    }
+                }
+            },
+            _ => {},
+        }
+        // Check moves and copies of locals. Borrowing a local does not consume it.
+        let consumed = match bc {
+            Assign(_, _, src, _) => vec![*src],
+            Call(_, _, BorrowLoc, _, _) => vec![],
+            Call(_, _, _, srcs, _) | Ret(_, srcs) => srcs.clone(),
+            _ => vec![],
+        };
+        for src in consumed {
+            let node = Node::Local(src);
+            if self.is_ref(src) {
+                continue;
+            }
+            if self.is_alive_after(src, offset) {
+                if state.is_borrowed_at(self.target, node, &[], true) {
+                    errors.insert((
+                        format!(
+                            "cannot copy {} since it is mutably borrowed",
+                            self.describe(node)
+                        ),
+                        diagnostic_codes::USE_OF_BORROWED_VALUE,
+                    ));
+                }
+            } else if state.children(node).next().is_some() {
+                errors.insert((
+                    format!("cannot move {} since it is borrowed", self.describe(node)),
+                    diagnostic_codes::USE_OF_BORROWED_VALUE,
+                ));
+            }
+        }
+        // Check and perform assignments.
+        let (defined, _) = bc.modifies(self.target);
+        for dest in defined {
+            if self.is_ref(dest) {
+                // Edges from the old value of the reference are moved to its parents
+                // before it is released.
+                if state.refs().contains(&dest) {
+                    let parents = state.parents(dest);
+                    new_edges = new_edges
+                        .into_iter()
+                        .flat_map(|(parent, child, path)| {
+                            if parent == Node::Ref(dest) {
+                                parents
+                                    .iter()
+                                    .map(|(p, p_path)| (*p, child, concat(p_path, &path)))
+                                    .collect::<Vec<_>>()
+                            } else {
+                                vec![(parent, child, path)]
+                            }
+                        })
+                        .collect();
+                    state.release(dest)
+                }
+            } else if state.children(Node::Local(dest)).next().is_some() {
+                errors.insert((
+                    format!(
+                        "cannot assign to {} since it is borrowed",
+                        self.describe(Node::Local(dest))
+                    ),
+                    diagnostic_codes::USE_OF_BORROWED_VALUE,
+                ));
+            }
+        }
+        if let Some((src, dest)) = moved_ref {
+            state.rename(src, dest)
+        }
+        for (parent, child, path) in new_edges {
+            state.add_edge(parent, child, path)
+        }
+        // Release references which are not used anymore.
+        for temp in state.refs() {
+            if !self.is_alive_after(temp, offset) {
+                state.release(temp)
+            }
+        }
+    }
+}
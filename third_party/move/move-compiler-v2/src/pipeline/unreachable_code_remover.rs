@@ -0,0 +1,173 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a processor which removes unreachable code. Branches on a condition which is
+//! loaded as a constant in the same block, as for `if (true)` or `while (false)`, are first
+//! replaced by jumps. Then all blocks which cannot be reached from the entry of the function
+//! are removed. Since a removed block is not reachable, no remaining branch refers to a label
+//! in it, so the code stays consistent.
+//!
+//! For each contiguous region of unreachable code in the baseline variant of a function, a
+//! warning is reported at the first source statement of the region, like code after an
+//! `abort` or a `return`. Labels, jumps, and returns which the bytecode generator adds
+//! around control flow constructs are not reported, since they have no counterpart in the
+//! source. The warning can be suppressed with the lint `unreachable_code`.
+//!
+//! The processor does not rely on other analyses. It should run before the
+//! `LiveVarAnalysisProcessor`, since it changes the code, and after the `ConstantFolder`, if
+//! present, which folds more conditions to constants.
+
+use crate::diagnostic_codes;
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::TempIndex,
+    model::{FunctionEnv, Loc},
+};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    stackless_bytecode::{Bytecode, Constant},
+    stackless_control_flow_graph::StacklessControlFlowGraph,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct UnreachableCodeRemover();
+
+impl FunctionTargetProcessor for UnreachableCodeRemover {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        mut data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() {
+            return data;
+        }
+        data.code = Self::fold_constant_branches(std::mem::take(&mut data.code));
+        let reachable = Self::reachable_offsets(&data.code);
+        if reachable.len() == data.code.len() {
+            return data;
+        }
+        // Diagnostics are only reported for the baseline, as other variants are derived
+        // from the same source.
+        if data.variant == FunctionVariant::Baseline && fun_env.module_env.is_target() {
+            let target = FunctionTarget::new(fun_env, &data);
+            Self::report_unreachable(&target, &reachable)
+        }
+        data.code = std::mem::take(&mut data.code)
+            .into_iter()
+            .enumerate()
+            .filter(|(offset, _)| reachable.contains(&(*offset as CodeOffset)))
+            .map(|(_, bc)| bc)
+            .collect();
+        data
+    }
+
+    fn name(&self) -> String {
+        "UnreachableCodeRemover".to_string()
+    }
+}
+
+impl UnreachableCodeRemover {
+    /// Replaces branches on a temporary which holds a constant, loaded in the same block, by
+    /// jumps to the branch taken.
+    fn fold_constant_branches(code: Vec<Bytecode>) -> Vec<Bytecode> {
+        let mut constants: BTreeMap<TempIndex, bool> = BTreeMap::new();
+        code.into_iter()
+            .map(|bc| {
+                let folded = match &bc {
+                    Bytecode::Branch(attr, if_label, else_label, cond) => {
+                        constants.get(cond).map(|value| {
+                            Bytecode::Jump(*attr, if *value { *if_label } else { *else_label })
+                        })
+                    },
+                    _ => None,
+                };
+                match &bc {
+                    Bytecode::Label(..) => constants.clear(),
+                    Bytecode::Load(_, dest, Constant::Bool(value)) => {
+                        constants.insert(*dest, *value);
+                    },
+                    Bytecode::Load(_, dest, _) | Bytecode::Assign(_, dest, ..) => {
+                        constants.remove(dest);
+                    },
+                    Bytecode::Call(_, dests, ..) => {
+                        for dest in dests {
+                            constants.remove(dest);
+                        }
+                    },
+                    _ => {},
+                }
+                folded.unwrap_or(bc)
+            })
+            .collect()
+    }
+
+    /// Returns the offsets of the instructions which are reachable from the entry.
+    pub(crate) fn reachable_offsets(code: &[Bytecode]) -> BTreeSet<CodeOffset> {
+        let cfg = StacklessControlFlowGraph::new_forward(code);
+        let mut visited = BTreeSet::new();
+        let mut todo = vec![cfg.entry_block()];
+        let mut reachable = BTreeSet::new();
+        while let Some(block) = todo.pop() {
+            if !visited.insert(block) {
+                continue;
+            }
+            if let Some(offsets) = cfg.instr_indexes(block) {
+                reachable.extend(offsets)
+            }
+            todo.extend(cfg.successors(block).iter().cloned())
+        }
+        reachable
+    }
+
+    /// Reports a warning for each contiguous region of unreachable code which contains
+    /// instructions stemming from the source, at the first source statement of the region.
+    fn report_unreachable(target: &FunctionTarget, reachable: &BTreeSet<CodeOffset>) {
+        let fun_loc = target.func_env.get_loc();
+        let mut regions: Vec<Vec<Loc>> = vec![];
+        let mut in_region = false;
+        for (offset, bc) in target.get_bytecode().iter().enumerate() {
+            if reachable.contains(&(offset as CodeOffset)) {
+                in_region = false;
+                continue;
+            }
+            if !in_region {
+                regions.push(vec![]);
+                in_region = true;
+            }
+            // Instructions inlined from other functions have locations outside of the function.
+            let loc = target.get_bytecode_loc(bc.get_attr_id());
+            if !Self::is_generated(bc) && fun_loc.is_enclosing(&loc) {
+                regions.last_mut().expect("region").push(loc)
+            }
+        }
+        for locs in regions {
+            // The first instruction of a statement is the one of its first sub-expression, so
+            // the statement is the largest location enclosing it.
+            let Some(first) = locs.first() else {
+                continue;
+            };
+            let statement = locs
+                .iter()
+                .filter(|loc| loc.is_enclosing(first))
+                .max_by_key(|loc| loc.span().end())
+                .unwrap_or(first);
+            diagnostic_codes::UNREACHABLE_CODE.warning(
+                target.global_env(),
+                statement,
+                "unreachable code; it is never executed and will be removed",
+            )
+        }
+    }
+
+    /// Returns true if the instruction is only added by the bytecode generator to structure
+    /// control flow.
+    fn is_generated(bc: &Bytecode) -> bool {
+        matches!(
+            bc,
+            Bytecode::Label(..) | Bytecode::Jump(..) | Bytecode::Nop(..) | Bytecode::Ret(..)
+        )
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a checker which warns about parameters and local variables which are never
+//! used. A variable is considered unused if its temporary is not alive at any program point,
+//! as determined by a live-variable analysis, and it is not explicitly read by any instruction.
+//! The latter covers reads whose result is discarded, as in `x;` or `_ = x`, which live-variable
+//! analysis does not count as uses. Variables whose name starts with an underscore are exempt.
+//!
+//! The checker relies on the `DeclaredLocals` annotation attached by the bytecode generator,
+//! and computes liveness itself, so it can run before any processor which changes the code.
+
+use crate::{bytecode_generator::DeclaredLocals, diagnostic_codes};
+use move_model::model::FunctionEnv;
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis,
+};
+use std::collections::BTreeSet;
+
+pub struct UnusedVarsChecker();
+
+impl FunctionTargetProcessor for UnusedVarsChecker {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if fun_env.is_native() || !fun_env.module_env.is_target() {
+            // Parameters of native functions are used by the implementation. Functions from
+            // dependencies are only compiled because they are called, so don't report on them.
+            return data;
+        }
+        let Some(declared) = data.annotations.get::<DeclaredLocals>() else {
+            return data;
+        };
+        let target = FunctionTarget::new(fun_env, &data);
+        let mut used = livevar_analysis::run_livevar_analysis(&target, &data.code)
+            .into_values()
+            .flat_map(|info| info.before.into_iter())
+            .chain(declared.discarded.iter().cloned())
+            .collect::<BTreeSet<_>>();
+        for bc in &data.code {
+            bc.clone().remap_src_vars(&target, &mut |temp| {
+                used.insert(temp);
+                temp
+            });
+        }
+        let env = fun_env.module_env.env;
+        let param_count = fun_env.get_parameter_count();
+        for (temp, (name, loc)) in &declared.vars {
+            if used.contains(temp) {
+                continue;
+            }
+            let name = name.display(env.symbol_pool()).to_string();
+            if name.starts_with('_') {
+                continue;
+            }
+            diagnostic_codes::UNUSED_VARIABLE.warning(
+                env,
+                loc,
+                &format!(
+                    "unused {} `{}`. Consider removing or prefixing with an underscore: `_{}`",
+                    if *temp < param_count {
+                        "parameter"
+                    } else {
+                        "local variable"
+                    },
+                    name,
+                    name
+                ),
+            )
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "UnusedVarsChecker".to_owned()
+    }
+}
@@ -1,18 +1,33 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-//! Implements a visibility checker, checking for visibility violations at function callsites.
+//! Implements a visibility checker, checking for visibility violations at function callsites,
+//! and the validity of the friend declarations of the target modules: friends must be other
+//! modules at the same address, and must not form a cycle.
 
+use crate::diagnostic_codes;
 use move_binary_format::file_format::Visibility;
-use move_model::model::FunctionEnv;
+use move_model::model::{FunctionEnv, GlobalEnv, Loc, ModuleEnv, ModuleId};
 use move_stackless_bytecode::{
     function_target::{FunctionData, FunctionTarget},
     function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
     stackless_bytecode::{Bytecode, Operation},
 };
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
 pub struct VisibilityChecker();
 
 impl FunctionTargetProcessor for VisibilityChecker {
+    fn initialize(&self, env: &GlobalEnv, _targets: &mut FunctionTargetsHolder) {
+        let mut reported_cycles = BTreeSet::new();
+        for module_env in env.get_modules() {
+            if module_env.is_target() && !module_env.is_script_module() {
+                check_friend_decls(&module_env);
+                check_friend_cycle(&module_env, &mut reported_cycles)
+            }
+        }
+    }
+
     fn process(
         &self,
         _targets: &mut FunctionTargetsHolder,
@@ -42,6 +57,24 @@ impl FunctionTargetProcessor for VisibilityChecker {
                     continue;
                 }
                 let callee_env = global_env.get_function(callee_mod_id.qualified(*callee_fun_id));
+                let callee_name = callee_env.get_full_name_with_address();
+                let call_loc = func_target.get_bytecode_loc(*attr_id);
+                let declared = vec![(
+                    callee_env.get_loc(),
+                    format!("`{}` is declared here", callee_name),
+                )];
+                // Declaring a function `entry` makes it callable as a transaction entry point,
+                // but not from other modules or scripts.
+                let entry_note = |callers: &str| {
+                    if callee_env.is_entry() {
+                        vec![format!(
+                            "`entry` functions can be called from {} only if they are `public`",
+                            callers
+                        )]
+                    } else {
+                        vec![]
+                    }
+                };
                 match callee_env.visibility() {
                     Visibility::Public => {
                         // Public functions are visible from any caller.
@@ -49,39 +82,66 @@ impl FunctionTargetProcessor for VisibilityChecker {
                     },
                     _ if func_target.module_env().is_script_module() => {
                         // Only public functions are visible from scripts.
-                        global_env.error(
-                            &func_target.get_bytecode_loc(*attr_id),
+                        diagnostic_codes::SCRIPT_NON_PUBLIC_CALL.error_with_context(
+                            global_env,
+                            &call_loc,
                             &format!(
-                                "function `{}` cannot be called from a script, because it is not public",
-                                callee_env.get_full_name_with_address()
+                                "function `{}` cannot be called from a script, because it is not \
+                                 public",
+                                callee_name
                             ),
+                            declared,
+                            entry_note("scripts"),
                         );
                     },
                     Visibility::Friend => {
-                        // Friend functions are visible from a caller whose module is a friend of the callee's module.
-                        // For the purposes of this check, we assume friend declarations are valid.
-                        // Validity of friend declarations should be checked elsewhere.
-                        if !callee_env.module_env.has_friend(&caller_mod_id) {
-                            global_env.error(
-                                &func_target.get_bytecode_loc(*attr_id),
+                        // Friend functions are visible from a caller whose module is a friend of
+                        // the callee's module. Friend declarations are checked in `initialize`.
+                        let callee_module = &callee_env.module_env;
+                        if !callee_module.has_friend(&caller_mod_id) {
+                            let note = if caller_mod_name.addr() == callee_module.get_name().addr()
+                            {
+                                format!(
+                                    "add `friend {};` to module `{}` to allow the call",
+                                    caller_mod_name.display_full(global_env),
+                                    callee_module.get_full_name_str()
+                                )
+                            } else {
+                                format!(
+                                    "only modules at the address of `{}` can be its friends",
+                                    callee_module.get_full_name_str()
+                                )
+                            };
+                            diagnostic_codes::FRIEND_FUNCTION_CALL.error_with_context(
+                                global_env,
+                                &call_loc,
                                 &format!(
-                                    "friend function `{}` cannot be called here because `{}` is not a friend of `{}`",
-                                    callee_env.get_full_name_with_address(),
+                                    "friend function `{}` cannot be called from `{}` because \
+                                     `{}` is not a friend of `{}`",
+                                    callee_name,
+                                    fun_env.get_full_name_with_address(),
                                     caller_mod_name.display_full(global_env),
-                                    callee_env.module_env.get_full_name_str()
+                                    callee_module.get_full_name_str()
                                 ),
+                                declared,
+                                vec![note],
                             );
                         }
                     },
                     Visibility::Private => {
                         // Private functions are not visible outside of the callee's module.
-                        global_env.error(
-                            &func_target.get_bytecode_loc(*attr_id),
+                        diagnostic_codes::PRIVATE_FUNCTION_CALL.error_with_context(
+                            global_env,
+                            &call_loc,
                             &format!(
-                                "function `{}` cannot be called here because it is private to module `{}`",
-                                callee_env.get_full_name_with_address(),
+                                "function `{}` cannot be called from `{}` because it is private \
+                                 to module `{}`",
+                                callee_name,
+                                fun_env.get_full_name_with_address(),
                                 callee_env.module_env.get_full_name_str()
                             ),
+                            declared,
+                            entry_note("other modules"),
                         );
                     },
                 }
@@ -94,3 +154,107 @@ impl FunctionTargetProcessor for VisibilityChecker {
         "VisibilityChecker".to_owned()
     }
 }
+
+/// Checks that the friends declared by the module are other modules at the same address.
+/// Friends need not be part of the compilation.
+fn check_friend_decls(module_env: &ModuleEnv) {
+    let env = module_env.env;
+    let module_name = module_env.get_name();
+    for decl in module_env.get_friend_decls() {
+        let problem = if &decl.module_name == module_name {
+            "a module cannot be a friend of itself"
+        } else if decl.module_name.addr() != module_name.addr() {
+            "friends must be modules at the same address"
+        } else {
+            continue;
+        };
+        diagnostic_codes::INVALID_FRIEND_DECLARATION.error_with_context(
+            env,
+            &decl.loc,
+            &format!(
+                "invalid friend declaration of `{}` in `{}`: {}",
+                decl.module_name.display_full(env),
+                module_env.get_full_name_str(),
+                problem
+            ),
+            vec![],
+            vec![],
+        )
+    }
+}
+
+/// Reports the shortest cycle of friend declarations through the module, if there is one and
+/// it has not been reported for another module of the cycle already. A module declaring
+/// itself a friend is reported by `check_friend_decls`.
+fn check_friend_cycle(module_env: &ModuleEnv, reported: &mut BTreeSet<BTreeSet<ModuleId>>) {
+    let env = module_env.env;
+    let start = module_env.get_id();
+    // Breadth-first search for the module, starting at its friends, remembering from which
+    // module each module was reached.
+    let mut reached_from: BTreeMap<ModuleId, ModuleId> = BTreeMap::new();
+    let mut todo = VecDeque::from([start]);
+    while let Some(id) = todo.pop_front() {
+        for friend in env.get_module(id).get_friend_modules() {
+            if friend == id || reached_from.contains_key(&friend) {
+                continue;
+            }
+            reached_from.insert(friend, id);
+            if friend == start {
+                todo.clear();
+                break;
+            }
+            todo.push_back(friend)
+        }
+    }
+    if !reached_from.contains_key(&start) {
+        return;
+    }
+    // The cycle, as the sequence of modules each of which declares the next one a friend.
+    let mut cycle = vec![];
+    let mut id = start;
+    loop {
+        id = reached_from[&id];
+        cycle.push(id);
+        if id == start {
+            break;
+        }
+    }
+    cycle.reverse();
+    if !reported.insert(cycle.iter().cloned().collect()) {
+        return;
+    }
+    let friend_decl_loc = |module: ModuleId, friend: ModuleId| -> Loc {
+        let module_env = env.get_module(module);
+        module_env
+            .get_friend_decls()
+            .iter()
+            .find(|decl| decl.module_id == Some(friend))
+            .map(|decl| decl.loc.clone())
+            .unwrap_or_else(|| module_env.get_loc())
+    };
+    let name = |id: ModuleId| env.get_module(id).get_full_name_str();
+    let next = |idx: usize| cycle[(idx + 1) % cycle.len()];
+    let labels = (1..cycle.len())
+        .map(|idx| {
+            (
+                friend_decl_loc(cycle[idx], next(idx)),
+                format!(
+                    "`{}` is a friend of `{}`",
+                    name(next(idx)),
+                    name(cycle[idx])
+                ),
+            )
+        })
+        .collect();
+    diagnostic_codes::FRIEND_CYCLE.error_with_context(
+        env,
+        &friend_decl_loc(start, next(0)),
+        &format!(
+            "friend declaration of `{}` in `{}` creates a cycle of friend declarations",
+            name(next(0)),
+            module_env.get_full_name_str()
+        ),
+        labels,
+        vec![],
+    )
+}
@@ -0,0 +1,133 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reporting of the progress of a compilation, for tools which show it to users, like a
+//! spinner in a CLI or a progress bar in an IDE. A sink registered with
+//! `Options::set_progress_sink` receives events when a phase starts, after the code of each
+//! function is generated, when a processor of the bytecode pipeline starts, and after each
+//! module is assembled. Without a sink, reporting does nothing.
+//!
+//! Events are reported on the thread running the compiler, so a sink which renders them
+//! should not block for long.
+
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::{
+    function_target::FunctionData,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+};
+use std::{fmt, rc::Rc};
+
+/// The phases of a compilation, in the order in which they run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Checking,
+    BytecodeGeneration,
+    BytecodePipeline,
+    FileFormatGeneration,
+    BytecodeVerification,
+}
+
+/// An event of the progress of a compilation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// A phase started. Phases which are not needed, like code generation if checking fails,
+    /// are not started.
+    PhaseStarted(Phase),
+    /// The code of another function was generated, of `total` functions for which code is
+    /// generated.
+    FunctionCompiled { done: usize, total: usize },
+    /// A processor of the bytecode pipeline started, given by its name.
+    ProcessorStarted(String),
+    /// Another module or script was assembled, of `total` units.
+    ModuleGenerated { done: usize, total: usize },
+}
+
+/// A receiver of progress events.
+pub trait ProgressSink {
+    fn report(&self, event: &ProgressEvent);
+}
+
+/// The progress sink of a compilation, if any.
+#[derive(Clone, Default)]
+pub struct Progress(Option<Rc<dyn ProgressSink>>);
+
+impl Progress {
+    pub fn new(sink: impl ProgressSink + 'static) -> Self {
+        Self(Some(Rc::new(sink)))
+    }
+
+    /// Returns true if a sink receives the events.
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Reports the event to the sink, if any.
+    pub fn report(&self, event: ProgressEvent) {
+        if let Some(sink) = &self.0 {
+            sink.report(&event)
+        }
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Progress")
+            .field(&if self.is_enabled() { "sink" } else { "none" })
+            .finish()
+    }
+}
+
+/// A processor which reports when the processor it wraps starts.
+pub(crate) struct ReportedProcessor {
+    processor: Box<dyn FunctionTargetProcessor>,
+    progress: Progress,
+}
+
+impl ReportedProcessor {
+    pub(crate) fn new(processor: Box<dyn FunctionTargetProcessor>, progress: Progress) -> Self {
+        Self {
+            processor,
+            progress,
+        }
+    }
+
+    fn report_start(&self) {
+        self.progress
+            .report(ProgressEvent::ProcessorStarted(self.processor.name()))
+    }
+}
+
+impl FunctionTargetProcessor for ReportedProcessor {
+    fn process_and_maybe_remove(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        scc_opt: Option<&[FunctionEnv]>,
+    ) -> Option<FunctionData> {
+        self.processor
+            .process_and_maybe_remove(targets, fun_env, data, scc_opt)
+    }
+
+    fn name(&self) -> String {
+        self.processor.name()
+    }
+
+    fn initialize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.report_start();
+        self.processor.initialize(env, targets)
+    }
+
+    fn finalize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.processor.finalize(env, targets)
+    }
+
+    fn is_single_run(&self) -> bool {
+        self.processor.is_single_run()
+    }
+
+    fn run(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.report_start();
+        self.processor.run(env, targets)
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation of a JSON bundle of the compiled units, written if `Options::emit_publish_bundle`
+//! is set, from which a package can be published via the REST API without further processing.
+//! The bundle carries the BCS-serialized package metadata, see `package_metadata`, and for
+//! each module its id, its code, the digest of its source file, and its ABI, in the format
+//! described in `abi`. Scripts, which are not published, are listed separately.
+//!
+//! Code and metadata are hex literals, like `0xa11ceb0b..`, as expected for byte arguments by
+//! `aptos move publish --json`. Modules are ordered by id and scripts by name, so the bundle
+//! does not depend on the order in which units are compiled.
+
+use crate::{
+    abi::{self, ModuleAbi},
+    package_metadata, Options, UnitModuleIndex,
+};
+use anyhow::Context;
+use move_compiler::compiled_unit::AnnotatedCompiledUnit;
+use move_model::model::{GlobalEnv, ModuleEnv};
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// A bundle of the compiled units of a package.
+#[derive(Clone, Debug, Serialize)]
+pub struct PublishBundle {
+    /// The BCS-serialized `PackageMetadata` of the package.
+    pub metadata_serialized: String,
+    /// The compiled modules, ordered by id.
+    pub modules: Vec<BundledModule>,
+    /// The compiled scripts, ordered by name.
+    pub scripts: Vec<BundledScript>,
+}
+
+/// A compiled module of a bundle.
+#[derive(Clone, Debug, Serialize)]
+pub struct BundledModule {
+    /// The id of the module, with a numerical address, like `0x42::m`.
+    pub module_id: String,
+    /// The serialized module.
+    pub bytecode: String,
+    /// The digest of the source file of the module.
+    pub source_digest: String,
+    /// The ABI of the module, if it is a target module.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abi: Option<ModuleAbi>,
+}
+
+/// A compiled script of a bundle.
+#[derive(Clone, Debug, Serialize)]
+pub struct BundledScript {
+    /// The name of the script, which is the name of its function.
+    pub name: String,
+    /// The serialized script.
+    pub bytecode: String,
+    /// The digest of the source file of the script.
+    pub source_digest: String,
+}
+
+/// Builds the bundle of the given units, which are the result of compiling the target modules
+/// and scripts of the environment with the options.
+pub fn build_publish_bundle(
+    env: &GlobalEnv,
+    units: &[AnnotatedCompiledUnit],
+    options: &Options,
+) -> anyhow::Result<PublishBundle> {
+    let metadata = package_metadata::build_package_metadata(env, units, options);
+    let mut abis = abi::module_abis(env)
+        .into_iter()
+        .map(|abi| (format!("{}::{}", abi.address, abi.name), abi))
+        .collect::<BTreeMap<_, _>>();
+    let index = UnitModuleIndex::new(env);
+    let mut modules = vec![];
+    let mut scripts = vec![];
+    for unit in units {
+        match unit {
+            AnnotatedCompiledUnit::Module(annotated_module) => {
+                let module = &annotated_module.named_module.module;
+                let mut code = vec![];
+                module.serialize_for_version(Some(module.version), &mut code)?;
+                let self_id = module.self_id();
+                let module_id =
+                    format!("{}::{}", self_id.address().to_hex_literal(), self_id.name());
+                modules.push(BundledModule {
+                    abi: abis.remove(&module_id),
+                    module_id,
+                    bytecode: to_hex(&code),
+                    source_digest: source_digest(env, index.find_module(&self_id)),
+                })
+            },
+            AnnotatedCompiledUnit::Script(annotated_script) => {
+                let named_script = &annotated_script.named_script;
+                let script = &named_script.script;
+                let mut code = vec![];
+                script.serialize_for_version(Some(script.version), &mut code)?;
+                let name = named_script.name.to_string();
+                scripts.push(BundledScript {
+                    bytecode: to_hex(&code),
+                    source_digest: source_digest(env, index.find_script(&name)),
+                    name,
+                })
+            },
+        }
+    }
+    modules.sort_by(|m1, m2| m1.module_id.cmp(&m2.module_id));
+    scripts.sort_by(|s1, s2| s1.name.cmp(&s2.name));
+    Ok(PublishBundle {
+        metadata_serialized: to_hex(&bcs::to_bytes(&metadata)?),
+        modules,
+        scripts,
+    })
+}
+
+/// Writes the bundle of the given units as JSON to the file at the path.
+pub fn write_publish_bundle(
+    env: &GlobalEnv,
+    units: &[AnnotatedCompiledUnit],
+    options: &Options,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let bundle = build_publish_bundle(env, units, options)?;
+    let json = serde_json::to_string_pretty(&bundle)? + "\n";
+    fs::write(path, json).with_context(|| format!("cannot write `{}`", path.display()))
+}
+
+/// Returns the digest of the source file of the module, or an empty string if it is not
+/// known.
+fn source_digest(env: &GlobalEnv, module_env: Option<ModuleEnv>) -> String {
+    module_env
+        .and_then(|module_env| env.get_file_hash(module_env.get_loc().file_id()))
+        .map(|hash| hash.to_string())
+        .unwrap_or_default()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
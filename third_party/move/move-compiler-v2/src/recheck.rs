@@ -0,0 +1,106 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Re-checking of a single edited function, for tools like language servers which check a
+//! function while it is being edited. Only the body of the edited function is checked again,
+//! against the declarations of the existing env, and only its diagnostics are returned. An edit
+//! which changes the signature of the function may affect other functions, so it is not
+//! checked, and the package must be compiled again instead.
+
+use crate::{
+    build_config::BuildConfigFilter,
+    diagnostics::{self, CompilerDiagnostic},
+    known_attributes, lint, Options,
+};
+use anyhow::bail;
+use codespan::Span;
+use codespan_reporting::diagnostic::LabelStyle;
+use move_model::{
+    model::{FunId, GlobalEnv, Loc, QualifiedId},
+    run_function_checker, FunctionCheckOutcome,
+};
+
+/// The result of re-checking an edited function.
+#[derive(Debug)]
+pub enum RecheckResult {
+    /// The diagnostics of the edited function, at locations in the edited source.
+    Diagnostics(Vec<CompilerDiagnostic>),
+    /// The edit changes the signature of the function, or its name, so the package must be
+    /// compiled again.
+    FullRecompileRequired,
+}
+
+/// Re-checks the function of the env with the given id after replacing its source, from its
+/// signature to the closing brace of its body, with `new_source`. The env must be the result
+/// of `run_checker`, and the function must be part of a target module. Only the file of the
+/// function is parsed and expanded again, see `run_function_checker`. The edited source is kept
+/// in the env, in one file per edited file, for the locations of the diagnostics. If the new
+/// function has the same signature as the old one, the diagnostics of the old function are removed from the env,
+/// since they are stale, and the diagnostics of the new function are returned. Otherwise, the
+/// diagnostics of the env are left unchanged.
+pub fn recheck_function(
+    env: &mut GlobalEnv,
+    fun_id: QualifiedId<FunId>,
+    new_source: &str,
+) -> anyhow::Result<RecheckResult> {
+    let fun_env = env.get_function(fun_id);
+    if !fun_env.module_env.is_target() {
+        bail!(
+            "function `{}` is not part of a target module",
+            fun_env.get_full_name_str()
+        )
+    }
+    let Some(options) = env.get_extension::<Options>() else {
+        bail!("environment was not created by the checker")
+    };
+    let old_loc = fun_env.get_loc();
+    let path = env
+        .get_file(old_loc.file_id())
+        .to_string_lossy()
+        .to_string();
+    // The diagnostics of the re-check are processed on their own, and those of the env are put
+    // back afterwards.
+    let old_diags = env.replace_diags(vec![]);
+    let outcome = run_function_checker(
+        env,
+        fun_id,
+        new_source,
+        options.skip_attribute_checks,
+        &known_attributes(&options),
+        options.compile_test_code,
+        Some(&mut BuildConfigFilter::new(&options.build_config)),
+    );
+    lint::filter_warning_categories(env, &options);
+    env.deduplicate_diags();
+    diagnostics::sort_diags(env);
+    let mut new_diags = diagnostics::collect_diags(env, options.report_severity());
+    env.replace_diags(old_diags);
+    let start = old_loc.span().start().0;
+    let new_end = start + new_source.len() as u32;
+    match outcome? {
+        FunctionCheckOutcome::Checked => {
+            new_diags.retain(|d| {
+                d.location.as_ref().map_or(false, |l| {
+                    l.file == path && l.start >= start && l.end <= new_end
+                })
+            });
+            env.retain_diags(|d| {
+                !d.labels.iter().any(|l| {
+                    l.style == LabelStyle::Primary
+                        && old_loc.is_enclosing(&Loc::new(
+                            l.file_id,
+                            Span::new(l.range.start as u32, l.range.end as u32),
+                        ))
+                })
+            });
+            Ok(RecheckResult::Diagnostics(new_diags))
+        },
+        FunctionCheckOutcome::SourceErrors => {
+            // Errors which keep the sources from being expanded, like parse errors, are
+            // returned as they are, wherever they are located in the edited file.
+            new_diags.retain(|d| d.location.as_ref().map_or(false, |l| l.file == path));
+            Ok(RecheckResult::Diagnostics(new_diags))
+        },
+        FunctionCheckOutcome::SignatureChanged => Ok(RecheckResult::FullRecompileRequired),
+    }
+}
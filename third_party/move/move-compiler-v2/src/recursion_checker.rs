@@ -0,0 +1,124 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Check for cycles of calls among the functions of the target modules, run by
+//! `run_bytecode_gen` if `Options::warn_recursion` is set. Move allows recursion, so the
+//! cycles are reported as warnings. Each cycle is reported once, as a chain of functions
+//! like `m::a -> m::b -> m::a`, starting at the function with the least name, and with the
+//! location of each call in the chain. Cycles which span modules cannot occur, as they imply
+//! cyclic module dependencies, which the checker rejects.
+
+use crate::diagnostic_codes::RECURSIVE_CALL_CYCLE;
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use move_model::{
+    ast::{ExpData, Operation},
+    model::{FunId, GlobalEnv, Loc, QualifiedId},
+};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Reports a warning for each cycle of calls among the given functions which are part of
+/// target modules, and have a definition.
+pub fn check_recursion<'a>(
+    env: &GlobalEnv,
+    functions: impl Iterator<Item = &'a QualifiedId<FunId>>,
+) {
+    let mut graph = BTreeMap::new();
+    for id in functions {
+        let fun_env = env.get_function(*id);
+        if fun_env.module_env.is_target() && fun_env.get_def().is_some() {
+            graph.insert(
+                *id,
+                fun_env.get_called_functions().cloned().unwrap_or_default(),
+            );
+        }
+    }
+    let mut starts = graph.keys().cloned().collect::<Vec<_>>();
+    starts.sort_by_key(|id| env.get_function(*id).get_full_name_str());
+    let mut reported = BTreeSet::new();
+    for start in starts {
+        let Some(cycle) = shortest_cycle(&graph, start) else {
+            continue;
+        };
+        // The same cycle is found from each of its functions.
+        if !reported.insert(cycle.iter().cloned().collect::<BTreeSet<_>>()) {
+            continue;
+        }
+        report_cycle(env, &cycle)
+    }
+}
+
+/// Returns the shortest cycle of calls from the function back to it, as the list of the
+/// functions of the cycle, starting with the function.
+fn shortest_cycle(
+    graph: &BTreeMap<QualifiedId<FunId>, BTreeSet<QualifiedId<FunId>>>,
+    start: QualifiedId<FunId>,
+) -> Option<Vec<QualifiedId<FunId>>> {
+    // Breadth-first search, remembering the caller through which each function is reached.
+    let mut reached_from = BTreeMap::new();
+    let mut todo = VecDeque::from([start]);
+    while let Some(caller) = todo.pop_front() {
+        for callee in graph.get(&caller).into_iter().flatten() {
+            if *callee == start {
+                let mut cycle = vec![caller];
+                while let Some(prev) = reached_from.get(cycle.last().expect("non-empty")) {
+                    cycle.push(*prev)
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if graph.contains_key(callee) && !reached_from.contains_key(callee) {
+                reached_from.insert(*callee, caller);
+                todo.push_back(*callee)
+            }
+        }
+    }
+    None
+}
+
+/// Reports the cycle, with a label at the first call of each function of the cycle to the
+/// next one. The label of the call in the first function is the primary one.
+fn report_cycle(env: &GlobalEnv, cycle: &[QualifiedId<FunId>]) {
+    let name = |id: &QualifiedId<FunId>| env.get_function(*id).get_full_name_str();
+    let chain = cycle
+        .iter()
+        .chain(cycle.first())
+        .map(name)
+        .collect::<Vec<_>>()
+        .join(" -> ");
+    let mut labels = vec![];
+    for (idx, caller) in cycle.iter().enumerate() {
+        let callee = &cycle[(idx + 1) % cycle.len()];
+        let loc = first_call_loc(env, *caller, *callee)
+            .unwrap_or_else(|| env.get_function(*caller).get_loc());
+        let label = if idx == 0 {
+            Label::primary(loc.file_id(), loc.span())
+        } else {
+            Label::secondary(loc.file_id(), loc.span())
+        };
+        labels.push(label.with_message(format!("`{}` calls `{}`", name(caller), name(callee))))
+    }
+    env.add_diag(
+        Diagnostic::warning()
+            .with_code(RECURSIVE_CALL_CYCLE.code)
+            .with_message(format!("recursive call cycle: `{}`", chain))
+            .with_labels(labels),
+    )
+}
+
+/// Returns the location of the first call of the callee in the body of the caller.
+fn first_call_loc(
+    env: &GlobalEnv,
+    caller: QualifiedId<FunId>,
+    callee: QualifiedId<FunId>,
+) -> Option<Loc> {
+    let fun_env = env.get_function(caller);
+    let mut locs = vec![];
+    fun_env.get_def()?.visit(&mut |exp| {
+        if let ExpData::Call(id, Operation::MoveFunction(mid, fid), _) = exp {
+            if mid.qualified(*fid) == callee {
+                locs.push(env.get_node_loc(*id))
+            }
+        }
+    });
+    locs.into_iter().min_by_key(|loc| loc.span().start())
+}
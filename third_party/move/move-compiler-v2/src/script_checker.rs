@@ -0,0 +1,131 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks the signatures of the functions of target scripts, which are the entry points of
+//! transactions. The v1 compiler leaves these checks to the VM, so scripts which cannot be
+//! called are only detected on execution; here they are reported at compile time:
+//!
+//! - a script function cannot return values, since there is no caller to receive them;
+//! - parameters of type `signer` or `&signer` are provided by the VM for the senders of the
+//!   transaction, and must come before all other parameters;
+//! - all other parameters are provided as transaction arguments, so their types must be
+//!   constructible from bytes: primitive types, vectors of such types, and the structs the VM
+//!   allows as arguments, like `0x1::string::String`.
+
+use crate::diagnostic_codes::{
+    INVALID_SCRIPT_PARAMETER, MISPLACED_SIGNER_PARAMETER, SCRIPT_RETURNS_VALUE,
+};
+use move_model::{
+    model::{FunctionEnv, GlobalEnv, Parameter},
+    ty::{PrimitiveType, ReferenceKind, Type},
+};
+
+/// The structs allowed as types of script parameters, together with whether their type
+/// arguments must be allowed as well.
+const ALLOWED_STRUCTS: &[(&str, bool)] = &[
+    ("0x1::string::String", true),
+    ("0x1::option::Option", true),
+    ("0x1::object::Object", false),
+    ("0x1::fixed_point32::FixedPoint32", true),
+    ("0x1::fixed_point64::FixedPoint64", true),
+];
+
+/// Checks the signatures of the functions of target scripts.
+pub fn check_scripts(env: &GlobalEnv) {
+    for module_env in env.get_modules() {
+        if module_env.is_target() && module_env.is_script_module() {
+            for fun_env in module_env.get_functions() {
+                check_script_signature(&fun_env)
+            }
+        }
+    }
+}
+
+fn check_script_signature(fun_env: &FunctionEnv) {
+    let env = fun_env.module_env.env;
+    let name = fun_env.get_name_str();
+    let ctx = fun_env.get_type_display_ctx();
+    let result_type = fun_env.get_result_type();
+    if !result_type.is_unit() {
+        SCRIPT_RETURNS_VALUE.error(
+            env,
+            &fun_env.get_loc(),
+            &format!(
+                "script function `{}` cannot return values, but returns `{}`",
+                name,
+                result_type.display(&ctx)
+            ),
+        )
+    }
+    let mut seen_non_signer = None;
+    for (idx, Parameter(param, ty)) in fun_env.get_parameters().into_iter().enumerate() {
+        let param = env.symbol_pool().string(param);
+        if is_signer_param(&ty) {
+            if let Some(other) = &seen_non_signer {
+                MISPLACED_SIGNER_PARAMETER.error(
+                    env,
+                    &fun_env.get_parameter_loc(idx),
+                    &format!(
+                        "parameter `{}` of script function `{}` is a signer, but follows \
+                         parameter `{}` which is not; signer parameters must come first",
+                        param, name, other
+                    ),
+                )
+            }
+            continue;
+        }
+        if !is_allowed_arg_type(env, &ty) {
+            INVALID_SCRIPT_PARAMETER.error(
+                env,
+                &fun_env.get_parameter_loc(idx),
+                &format!(
+                    "parameter `{}` of script function `{}` has type `{}`, which cannot be \
+                     passed as a transaction argument",
+                    param,
+                    name,
+                    ty.display(&ctx)
+                ),
+            )
+        }
+        seen_non_signer.get_or_insert(param);
+    }
+}
+
+/// Returns true if the type is `signer` or `&signer`.
+fn is_signer_param(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(ReferenceKind::Immutable, inner) => inner.is_signer(),
+        _ => ty.is_signer(),
+    }
+}
+
+/// Returns true if values of the type can be passed as transaction arguments.
+fn is_allowed_arg_type(env: &GlobalEnv, ty: &Type) -> bool {
+    match ty {
+        Type::Primitive(prim) => matches!(
+            prim,
+            PrimitiveType::Bool
+                | PrimitiveType::U8
+                | PrimitiveType::U16
+                | PrimitiveType::U32
+                | PrimitiveType::U64
+                | PrimitiveType::U128
+                | PrimitiveType::U256
+                | PrimitiveType::Address
+        ),
+        Type::Vector(elem) => is_allowed_arg_type(env, elem),
+        Type::Struct(..) => {
+            let (struct_env, args) = ty.get_struct(env).expect("struct type");
+            let name = struct_env.get_full_name_with_address();
+            ALLOWED_STRUCTS
+                .iter()
+                .find(|(allowed, _)| *allowed == name)
+                .is_some_and(|(_, check_args)| {
+                    !check_args || args.iter().all(|arg| is_allowed_arg_type(env, arg))
+                })
+        },
+        // Errors in the type have already been reported by the checker.
+        Type::Error => true,
+        _ => false,
+    }
+}
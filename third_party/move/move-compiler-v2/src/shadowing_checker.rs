@@ -0,0 +1,142 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Check for locals which shadow other locals or parameters of the same function, run by
+//! `run_checker` if the `shadowing` lint is enabled, see `lint::is_lint_enabled`. Shadowing is
+//! legal in Move, but a later use of the name may be meant for the shadowed local.
+//!
+//! Locals are declared by the patterns of `let`, including destructuring patterns like
+//! `let S { f } = s;`, and by the parameters of lambdas. A local declared in a loop body is in
+//! scope for the rest of that body only, so declaring it again in the next iteration does not
+//! shadow it. Names starting with `_` are exempt, and specifications are not checked.
+
+use crate::diagnostic_codes::SHADOWED_LOCAL;
+use move_model::{
+    ast::{ExpData, Pattern},
+    model::{FunctionEnv, GlobalEnv, Loc},
+    symbol::Symbol,
+};
+
+/// Reports a warning for each local of a function of a target module which shadows another
+/// local or a parameter, with the locations of both.
+pub fn check_shadowing(env: &GlobalEnv) {
+    for module_env in env.get_modules().filter(|m| m.is_target()) {
+        for fun_env in module_env.get_functions() {
+            let Some(def) = fun_env.get_def() else {
+                continue;
+            };
+            let mut checker = ShadowingChecker::new(env, &fun_env);
+            checker.check(def)
+        }
+    }
+}
+
+/// A local in scope, with the location of its declaration, and whether it is a parameter.
+struct Declaration {
+    name: Symbol,
+    loc: Loc,
+    is_parameter: bool,
+}
+
+struct ShadowingChecker<'env> {
+    env: &'env GlobalEnv,
+    /// The locals in scope, innermost last.
+    scope: Vec<Declaration>,
+}
+
+impl<'env> ShadowingChecker<'env> {
+    fn new(env: &'env GlobalEnv, fun_env: &FunctionEnv) -> Self {
+        let scope = fun_env
+            .get_parameters()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, param)| Declaration {
+                name: param.0,
+                loc: fun_env.get_parameter_loc(idx),
+                is_parameter: true,
+            })
+            .collect();
+        Self { env, scope }
+    }
+
+    fn check(&mut self, exp: &ExpData) {
+        use ExpData::*;
+        match exp {
+            Block(_, pattern, binding, body) => {
+                // The binding is checked in the scope outside of the block.
+                if let Some(binding) = binding {
+                    self.check(binding)
+                }
+                self.check_in_scope_of(pattern, body)
+            },
+            Lambda(_, pattern, body) => self.check_in_scope_of(pattern, body),
+            Call(_, _, args) => {
+                for arg in args {
+                    self.check(arg)
+                }
+            },
+            Invoke(_, target, args) => {
+                self.check(target);
+                for arg in args {
+                    self.check(arg)
+                }
+            },
+            IfElse(_, cond, then, else_) => {
+                self.check(cond);
+                self.check(then);
+                self.check(else_)
+            },
+            Loop(_, body) | Return(_, body) | Assign(_, _, body) => self.check(body),
+            Sequence(_, exps) => {
+                for exp in exps {
+                    self.check(exp)
+                }
+            },
+            Mutate(_, lhs, rhs) => {
+                self.check(lhs);
+                self.check(rhs)
+            },
+            // Specifications are not checked.
+            Quant(..) | SpecBlock(..) => {},
+            LoopCont(..) | Value(..) | LocalVar(..) | Temporary(..) | Invalid(..) => {},
+        }
+    }
+
+    /// Declares the locals of the pattern, reporting those which shadow others, and checks the
+    /// expression in their scope.
+    fn check_in_scope_of(&mut self, pattern: &Pattern, exp: &ExpData) {
+        let outer_len = self.scope.len();
+        for (id, name) in pattern.vars() {
+            let loc = self.env.get_node_loc(id);
+            if !self.env.symbol_pool().string(name).starts_with('_') {
+                self.report_if_shadowing(name, &loc)
+            }
+            self.scope.push(Declaration {
+                name,
+                loc,
+                is_parameter: false,
+            })
+        }
+        self.check(exp);
+        self.scope.truncate(outer_len)
+    }
+
+    fn report_if_shadowing(&self, name: Symbol, loc: &Loc) {
+        let Some(shadowed) = self.scope.iter().rev().find(|decl| decl.name == name) else {
+            return;
+        };
+        let name = self.env.symbol_pool().string(name);
+        let (kind, label) = if shadowed.is_parameter {
+            ("parameter", format!("parameter `{}` declared here", name))
+        } else {
+            ("local", format!("local `{}` declared here", name))
+        };
+        SHADOWED_LOCAL.warning_with_context(
+            self.env,
+            loc,
+            &format!("local `{}` shadows a {} of the same name", name, kind),
+            vec![(shadowed.loc.clone(), label)],
+            vec![],
+        )
+    }
+}
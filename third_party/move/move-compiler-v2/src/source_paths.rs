@@ -0,0 +1,205 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the paths of sources and dependencies, run before the model builder. Paths are
+//! compared by the canonical forms of the Move files they denote, so a file is recognized when
+//! given via a symbolic link, or both by itself and via its directory. A file given more than
+//! once as a source, or more than once as a dependency, is compiled once, with a warning. A
+//! file given both as a source and as a dependency is an error, as it is unclear whether its
+//! modules are targets; it is kept as a source only. A directory which contains such files is
+//! replaced by its remaining files. Moreover, distinct files with identical contents are
+//! reported, since files are identified by the hash of their contents, so locations in one
+//! would be reported as locations in the other.
+//!
+//! The problems are reported once the env exists, see `SourcePaths::report`.
+
+use crate::{
+    diagnostic_codes::{
+        DiagnosticCode, DUPLICATE_SOURCE_PATH, IDENTICAL_SOURCE_FILES, SOURCE_IS_DEPENDENCY,
+    },
+    Options,
+};
+use codespan_reporting::diagnostic::Severity;
+use move_command_line_common::files::{find_move_filenames, FileHash};
+use move_model::model::GlobalEnv;
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+
+/// The paths of sources and dependencies, without duplicates, and the problems found with them.
+pub struct SourcePaths {
+    pub sources: Vec<String>,
+    pub dependencies: Vec<String>,
+    problems: Vec<(DiagnosticCode, Severity, String)>,
+}
+
+impl SourcePaths {
+    /// Checks the paths of sources and dependencies of the options.
+    pub fn new(options: &Options) -> Self {
+        let mut problems = vec![];
+        let mut source_files = BTreeSet::new();
+        let mut sources = vec![];
+        for path in &options.sources {
+            let (repeated, fresh): (Vec<_>, Vec<_>) = files_of(path)
+                .into_iter()
+                .partition(|(_, canonical)| source_files.contains(canonical));
+            if !repeated.is_empty() {
+                problems.push(repeated_files("source", path, &repeated, fresh.is_empty()))
+            }
+            source_files.extend(fresh.iter().map(|(_, canonical)| canonical.clone()));
+            keep(&mut sources, path, fresh, repeated.is_empty())
+        }
+        let mut dependency_files = BTreeSet::new();
+        let mut dependencies = vec![];
+        for path in &options.dependencies {
+            let (in_sources, files): (Vec<_>, Vec<_>) = files_of(path)
+                .into_iter()
+                .partition(|(_, canonical)| source_files.contains(canonical));
+            let (repeated, fresh): (Vec<_>, Vec<_>) = files
+                .into_iter()
+                .partition(|(_, canonical)| dependency_files.contains(canonical));
+            if !in_sources.is_empty() {
+                let msg = if fresh.is_empty() && repeated.is_empty() {
+                    format!("`{}` is given both as a source and as a dependency", path)
+                } else {
+                    format!(
+                        "dependency `{}` contains files given as sources: {}",
+                        path,
+                        file_list(&in_sources)
+                    )
+                };
+                problems.push((SOURCE_IS_DEPENDENCY, Severity::Error, msg))
+            }
+            if !repeated.is_empty() {
+                let whole = fresh.is_empty() && in_sources.is_empty();
+                problems.push(repeated_files("dependency", path, &repeated, whole))
+            }
+            dependency_files.extend(fresh.iter().map(|(_, canonical)| canonical.clone()));
+            keep(
+                &mut dependencies,
+                path,
+                fresh,
+                in_sources.is_empty() && repeated.is_empty(),
+            )
+        }
+        problems.extend(identical_files(options, &sources, &dependencies));
+        Self {
+            sources,
+            dependencies,
+            problems,
+        }
+    }
+
+    /// Reports the problems found with the paths.
+    pub fn report(&self, env: &GlobalEnv) {
+        for (code, severity, msg) in &self.problems {
+            code.diag_without_loc(env, *severity, msg)
+        }
+    }
+}
+
+/// Returns the canonical form of the path, or the path itself if it does not exist, which the
+/// checker reports.
+fn canonical(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+/// Returns the Move files denoted by the path, with their canonical forms. A path which cannot
+/// be read, which the checker reports, denotes itself.
+fn files_of(path: &str) -> Vec<(String, PathBuf)> {
+    find_move_filenames(&[path], true)
+        .ok()
+        .filter(|files| !files.is_empty())
+        .unwrap_or_else(|| vec![path.to_owned()])
+        .into_iter()
+        .map(|file| {
+            let canonical = canonical(&file);
+            (file, canonical)
+        })
+        .collect()
+}
+
+/// Adds the path to the paths if all its files are kept, and otherwise the kept files.
+fn keep(paths: &mut Vec<String>, path: &str, kept: Vec<(String, PathBuf)>, all_kept: bool) {
+    if all_kept {
+        paths.push(path.to_owned())
+    } else {
+        paths.extend(kept.into_iter().map(|(file, _)| file))
+    }
+}
+
+/// Returns the problem of a source or dependency path with files given before, which are
+/// all of its files if `whole` is set.
+fn repeated_files(
+    kind: &str,
+    path: &str,
+    repeated: &[(String, PathBuf)],
+    whole: bool,
+) -> (DiagnosticCode, Severity, String) {
+    let msg = if whole {
+        format!("{} `{}` is given more than once", kind, path)
+    } else {
+        format!(
+            "{} `{}` contains files given more than once: {}",
+            kind,
+            path,
+            file_list(repeated)
+        )
+    };
+    (DUPLICATE_SOURCE_PATH, Severity::Warning, msg)
+}
+
+/// Returns the list of the files, for messages.
+fn file_list(files: &[(String, PathBuf)]) -> String {
+    files
+        .iter()
+        .map(|(file, _)| format!("`{}`", file))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns a problem for each file of the sources, dependencies, and buffers, whose contents
+/// are identical to those of a file before it.
+fn identical_files(
+    options: &Options,
+    sources: &[String],
+    dependencies: &[String],
+) -> Vec<(DiagnosticCode, Severity, String)> {
+    let files = find_move_filenames(&[sources, dependencies].concat(), true)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| Some((fs::read_to_string(&path).ok()?, path)))
+        .chain(
+            options
+                .source_buffers
+                .iter()
+                .chain(&options.dependency_buffers)
+                .map(|(path, text)| (text.clone(), path.clone())),
+        );
+    let mut first_paths = BTreeMap::new();
+    let mut seen = BTreeSet::new();
+    let mut problems = vec![];
+    for (text, path) in files {
+        // A file may be found via several paths, like a file and its directory.
+        if !seen.insert(canonical(&path)) {
+            continue;
+        }
+        match first_paths.entry(FileHash::new(&text)) {
+            Entry::Vacant(entry) => {
+                entry.insert(path);
+            },
+            Entry::Occupied(entry) => problems.push((
+                IDENTICAL_SOURCE_FILES,
+                Severity::Warning,
+                format!(
+                    "files `{}` and `{}` have identical contents",
+                    entry.get(),
+                    path
+                ),
+            )),
+        }
+    }
+    problems
+}
@@ -0,0 +1,74 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Mapping of byte offsets in source files to 1-based lines and columns, for rendering
+//! locations outside of the terminal output of `codespan_reporting`, like in JSON and SARIF
+//! diagnostics or in dumps. Editors count columns in different units, so columns are
+//! provided both in UTF-8 bytes and in UTF-16 code units.
+//!
+//! Lines are separated by `\n`, as by `codespan`, so with CRLF line endings the `\r` is the
+//! last character of a line. Lines agree with `GlobalEnv::get_location`.
+
+use codespan::FileId;
+use move_model::model::GlobalEnv;
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+/// A position in a source file. All numbers are 1-based.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourcePosition {
+    pub line: usize,
+    /// The column, counted in bytes of the UTF-8 encoding of the line.
+    pub utf8_column: usize,
+    /// The column, counted in code units of the UTF-16 encoding of the line.
+    pub utf16_column: usize,
+}
+
+/// Maps byte offsets to positions in the source files of an env. The offsets at which lines
+/// start are computed once per file.
+pub struct SourcePositionMapper<'env> {
+    env: &'env GlobalEnv,
+    line_starts: RefCell<BTreeMap<FileId, Rc<Vec<usize>>>>,
+}
+
+impl<'env> SourcePositionMapper<'env> {
+    pub fn new(env: &'env GlobalEnv) -> Self {
+        Self {
+            env,
+            line_starts: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the position of the byte offset in the file. The end of the file is a valid
+    /// position. Returns `None` if the offset is beyond the end of the file or not at the
+    /// start of a character, like `codespan` does.
+    pub fn byte_to_line_col(&self, file_id: FileId, offset: u32) -> Option<SourcePosition> {
+        let source = self.env.get_file_source(file_id);
+        let offset = offset as usize;
+        if !source.is_char_boundary(offset) {
+            return None;
+        }
+        let line_starts = self.line_starts(file_id, source);
+        // The line is the last one starting at or before the offset.
+        let line = line_starts.partition_point(|start| *start <= offset) - 1;
+        let prefix = &source[line_starts[line]..offset];
+        Some(SourcePosition {
+            line: line + 1,
+            utf8_column: prefix.len() + 1,
+            utf16_column: prefix.encode_utf16().count() + 1,
+        })
+    }
+
+    fn line_starts(&self, file_id: FileId, source: &str) -> Rc<Vec<usize>> {
+        self.line_starts
+            .borrow_mut()
+            .entry(file_id)
+            .or_insert_with(|| {
+                Rc::new(
+                    std::iter::once(0)
+                        .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+                        .collect(),
+                )
+            })
+            .clone()
+    }
+}
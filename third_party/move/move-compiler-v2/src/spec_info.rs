@@ -0,0 +1,98 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Specification information of the compiled units, as needed by the prover. While generating
+//! the code of a function, the file format generator records at which code offset each inline
+//! spec block of the function landed, together with the locations of its conditions, in the
+//! `SpecAnchors` extension of the env. From these anchors, `annotate_units` derives the
+//! `FunctionInfo` of each function, which maps the spec ids of the blocks to code offsets.
+//!
+//! Spec ids number the spec blocks of a function in the order in which they appear in its
+//! body, as the front end does. A spec block from which no code was generated, for example
+//! because it is unreachable, has no anchor, and neither have the blocks of modules taken
+//! from the compilation cache. Conditions which were inlined from other functions are
+//! anchored in those functions only.
+
+use move_binary_format::file_format::CodeOffset;
+use move_compiler::{
+    compiled_unit::{FunctionInfo, SpecInfo},
+    expansion::ast::{ModuleIdent_, SpecId},
+    shared::unique_map::UniqueMap,
+    typing::ast::SpecIdent,
+};
+use move_model::{
+    ast::ExpData,
+    model::{FunId, FunctionEnv, Loc, NodeId, QualifiedId},
+};
+use move_symbol_pool::Symbol;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+};
+
+/// Where an inline spec block landed in the generated code of a function.
+#[derive(Clone, Debug)]
+pub struct SpecAnchor {
+    /// The offset of the instruction before which the conditions of the block hold.
+    pub offset: CodeOffset,
+    /// The locations of the conditions of the block.
+    pub condition_locs: Vec<Loc>,
+}
+
+/// The spec anchors of the generated functions, keyed by the node id of their spec block.
+/// This is an extension of the env, installed by the file format generator.
+#[derive(Debug, Default)]
+pub struct SpecAnchors {
+    anchors: RefCell<BTreeMap<QualifiedId<FunId>, BTreeMap<NodeId, SpecAnchor>>>,
+}
+
+impl SpecAnchors {
+    /// Records the anchors of the generated code of the function.
+    pub fn record(&self, fun: QualifiedId<FunId>, anchors: BTreeMap<NodeId, SpecAnchor>) {
+        self.anchors.borrow_mut().insert(fun, anchors);
+    }
+
+    /// Returns the anchors of the function, which are empty if no code was generated for it.
+    pub fn anchors(&self, fun: QualifiedId<FunId>) -> BTreeMap<NodeId, SpecAnchor> {
+        self.anchors.borrow().get(&fun).cloned().unwrap_or_default()
+    }
+}
+
+/// Returns the function info of the function, with the spec blocks which have an anchor. The
+/// module is the one in which the function is declared, which is `None` for scripts.
+pub fn function_info(fun_env: &FunctionEnv, module: Option<ModuleIdent_>) -> FunctionInfo {
+    let anchors = fun_env
+        .module_env
+        .env
+        .get_extension::<SpecAnchors>()
+        .map(|anchors| anchors.anchors(fun_env.get_qualified_id()))
+        .unwrap_or_default();
+    let mut blocks = BTreeSet::new();
+    if let Some(def) = fun_env.get_def() {
+        def.visit(&mut |exp| {
+            if let ExpData::SpecBlock(id, _) = exp {
+                blocks.insert(*id);
+            }
+        })
+    }
+    let function = Symbol::from(fun_env.get_name_str());
+    let spec_info = blocks
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, block)| {
+            let anchor = anchors.get(&block)?;
+            let id = SpecId::new(index);
+            Some((id, SpecInfo {
+                offset: anchor.offset,
+                origin: SpecIdent {
+                    module,
+                    function,
+                    id,
+                },
+                used_locals: UniqueMap::new(),
+                used_lambda_funs: BTreeMap::new(),
+            }))
+        })
+        .collect();
+    FunctionInfo { spec_info }
+}
@@ -0,0 +1,349 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Static estimation of the resources a call of each generated function needs at runtime: the
+//! maximal depth of the operand stack within the function, the size of its frame, and the
+//! worst-case depth of the calls it leads to. The VM limits the size of the operand stack and
+//! the depth of calls, and exceeding a limit only shows as an aborted transaction. With
+//! `Options::max_stack_depth` and `Options::max_call_depth`, functions exceeding the given
+//! thresholds are reported as warnings at compile time instead. The numbers are recorded in
+//! the `CompilationStats`.
+//!
+//! The operand stack is empty at the boundaries of the basic blocks of verified code, so the
+//! maximal depth is found by a linear pass over the instructions. The call depth counts the
+//! frame of the function itself and follows the calls among the compiled units. A call of a
+//! function outside of them counts as one frame, since its code is not known. Functions which
+//! can reach a cycle of calls have an unbounded call depth.
+
+use crate::{
+    compilation_stats::CompilationStats,
+    diagnostic_codes::{CALL_DEPTH_EXCEEDED, STACK_DEPTH_EXCEEDED},
+    Options, UnitModuleIndex,
+};
+use move_binary_format::{
+    access::ModuleAccess,
+    binary_views::BinaryIndexedView,
+    file_format::{
+        Bytecode, CodeUnit, StructDefInstantiationIndex, StructDefinitionIndex,
+        StructFieldInformation,
+    },
+};
+use move_compiler::compiled_unit::CompiledUnit;
+use move_core_types::language_storage::ModuleId;
+use move_model::model::GlobalEnv;
+use std::{collections::BTreeMap, fmt};
+
+/// The depth of the calls a function leads to, counting its own frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CallDepth {
+    Bounded(usize),
+    /// The function can reach a cycle of calls.
+    Unbounded,
+}
+
+impl fmt::Display for CallDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallDepth::Bounded(depth) => write!(f, "{}", depth),
+            CallDepth::Unbounded => write!(f, "unbounded"),
+        }
+    }
+}
+
+/// The resources a call of a generated function needs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionFrame {
+    /// The maximal depth of the operand stack within the function.
+    pub max_stack_depth: usize,
+    /// The number of parameters and locals of the function.
+    pub frame_size: usize,
+    pub call_depth: CallDepth,
+}
+
+/// A generated function. Native functions have no code.
+struct Function<'a> {
+    /// The module of the function, or none for a script.
+    module: Option<ModuleId>,
+    name: String,
+    view: BinaryIndexedView<'a>,
+    code: Option<&'a CodeUnit>,
+    parameters: usize,
+    returns: usize,
+}
+
+/// Computes the frames of the functions of the units, records them in the `CompilationStats`
+/// of the env, and reports the functions which exceed the thresholds of the options.
+pub(crate) fn check_frames(env: &GlobalEnv, units: &[CompiledUnit]) {
+    let functions = functions(units);
+    let mut call_depths = BTreeMap::new();
+    for name in functions.keys() {
+        call_depth(&functions, name, &mut call_depths);
+    }
+    let frames = functions
+        .iter()
+        .map(|(name, function)| {
+            let frame = FunctionFrame {
+                max_stack_depth: function
+                    .code
+                    .map(|code| max_stack_depth(&function.view, code, function.returns))
+                    .unwrap_or_default(),
+                frame_size: function.parameters
+                    + function
+                        .code
+                        .map(|code| function.view.signature_at(code.locals).len())
+                        .unwrap_or_default(),
+                call_depth: match call_depths.get(name.as_str()) {
+                    Some(Visit::Done(depth)) => *depth,
+                    _ => CallDepth::Unbounded,
+                },
+            };
+            (name.clone(), frame)
+        })
+        .collect::<BTreeMap<_, _>>();
+    if let Some(options) = env.get_extension::<Options>() {
+        report_exceeded(env, &options, &functions, &frames)
+    }
+    if let Some(stats) = env.get_extension::<CompilationStats>() {
+        stats.record_frames(frames)
+    }
+}
+
+/// Returns the functions of the units by their full name, as in `0x42::m::f`, and scripts by
+/// their name.
+fn functions(units: &[CompiledUnit]) -> BTreeMap<String, Function<'_>> {
+    let mut functions = BTreeMap::new();
+    for unit in units {
+        match unit {
+            CompiledUnit::Module(named_module) => {
+                let module = &named_module.module;
+                let view = BinaryIndexedView::Module(module);
+                let module_id = module.self_id();
+                for def in &module.function_defs {
+                    let handle = module.function_handle_at(def.function);
+                    let name = module.identifier_at(handle.name).to_string();
+                    functions.insert(
+                        format!("{}::{}", module_id.short_str_lossless(), name),
+                        Function {
+                            module: Some(module_id.clone()),
+                            name,
+                            view,
+                            code: def.code.as_ref(),
+                            parameters: view.signature_at(handle.parameters).len(),
+                            returns: view.signature_at(handle.return_).len(),
+                        },
+                    );
+                }
+            },
+            CompiledUnit::Script(named_script) => {
+                let script = &named_script.script;
+                let view = BinaryIndexedView::Script(script);
+                functions.insert(named_script.name.to_string(), Function {
+                    module: None,
+                    name: named_script.name.to_string(),
+                    view,
+                    code: Some(&script.code),
+                    parameters: view.signature_at(script.parameters).len(),
+                    returns: 0,
+                });
+            },
+        }
+    }
+    functions
+}
+
+/// Returns the maximal depth of the operand stack within the code.
+fn max_stack_depth(view: &BinaryIndexedView, code: &CodeUnit, returns: usize) -> usize {
+    let mut depth = 0usize;
+    let mut max_depth = 0;
+    for instr in &code.code {
+        let (pops, pushes) = instruction_effect(view, instr, returns);
+        depth = depth.saturating_sub(pops).saturating_add(pushes);
+        max_depth = max_depth.max(depth)
+    }
+    max_depth
+}
+
+/// Returns the number of values the instruction pops from and pushes onto the operand stack,
+/// in a function with the given number of return values. This follows the stack usage
+/// verifier, and counts the fields of malformed structs as zero.
+fn instruction_effect(
+    view: &BinaryIndexedView,
+    instr: &Bytecode,
+    returns: usize,
+) -> (usize, usize) {
+    use Bytecode::*;
+    match instr {
+        Pop | BrTrue(_) | BrFalse(_) | StLoc(_) | Abort => (1, 0),
+        LdU8(_) | LdU16(_) | LdU32(_) | LdU64(_) | LdU128(_) | LdU256(_) | LdTrue | LdFalse
+        | LdConst(_) | CopyLoc(_) | MoveLoc(_) | MutBorrowLoc(_) | ImmBorrowLoc(_) => (0, 1),
+        Not
+        | FreezeRef
+        | ReadRef
+        | Exists(_)
+        | ExistsGeneric(_)
+        | MutBorrowGlobal(_)
+        | MutBorrowGlobalGeneric(_)
+        | ImmBorrowGlobal(_)
+        | ImmBorrowGlobalGeneric(_)
+        | MutBorrowField(_)
+        | MutBorrowFieldGeneric(_)
+        | ImmBorrowField(_)
+        | ImmBorrowFieldGeneric(_)
+        | MoveFrom(_)
+        | MoveFromGeneric(_)
+        | CastU8
+        | CastU16
+        | CastU32
+        | CastU64
+        | CastU128
+        | CastU256
+        | VecLen(_)
+        | VecPopBack(_) => (1, 1),
+        Add | Sub | Mul | Mod | Div | BitOr | BitAnd | Xor | Shl | Shr | Or | And | Eq | Neq
+        | Lt | Gt | Le | Ge | VecImmBorrow(_) | VecMutBorrow(_) => (2, 1),
+        VecPack(_, num) => (*num as usize, 1),
+        VecUnpack(_, num) => (1, *num as usize),
+        MoveTo(_) | MoveToGeneric(_) | WriteRef | VecPushBack(_) => (2, 0),
+        VecSwap(_) => (3, 0),
+        Branch(_) | Nop => (0, 0),
+        Ret => (returns, 0),
+        Call(idx) => {
+            let handle = view.function_handle_at(*idx);
+            (
+                view.signature_at(handle.parameters).len(),
+                view.signature_at(handle.return_).len(),
+            )
+        },
+        CallGeneric(idx) => {
+            let handle = view.function_handle_at(view.function_instantiation_at(*idx).handle);
+            (
+                view.signature_at(handle.parameters).len(),
+                view.signature_at(handle.return_).len(),
+            )
+        },
+        Pack(idx) => (field_count(view, *idx), 1),
+        Unpack(idx) => (1, field_count(view, *idx)),
+        PackGeneric(idx) => (generic_field_count(view, *idx), 1),
+        UnpackGeneric(idx) => (1, generic_field_count(view, *idx)),
+    }
+}
+
+fn field_count(view: &BinaryIndexedView, idx: StructDefinitionIndex) -> usize {
+    match view.struct_def_at(idx).map(|def| &def.field_information) {
+        Ok(StructFieldInformation::Declared(fields)) => fields.len(),
+        _ => 0,
+    }
+}
+
+fn generic_field_count(view: &BinaryIndexedView, idx: StructDefInstantiationIndex) -> usize {
+    view.struct_instantiation_at(idx)
+        .map_or(0, |inst| field_count(view, inst.def))
+}
+
+/// The state of a function in the search for call depths.
+enum Visit {
+    InProgress,
+    Done(CallDepth),
+}
+
+/// Returns the call depth of the function, memoizing it and the depths of its callees. A call
+/// of a function which is still in progress closes a cycle.
+fn call_depth<'a>(
+    functions: &'a BTreeMap<String, Function<'a>>,
+    name: &'a str,
+    visits: &mut BTreeMap<&'a str, Visit>,
+) -> CallDepth {
+    match visits.get(name) {
+        Some(Visit::InProgress) => return CallDepth::Unbounded,
+        Some(Visit::Done(depth)) => return *depth,
+        None => {},
+    }
+    let Some(function) = functions.get(name) else {
+        // Functions outside of the units count as one frame.
+        return CallDepth::Bounded(1);
+    };
+    visits.insert(name, Visit::InProgress);
+    let view = &function.view;
+    let mut deepest_callee = CallDepth::Bounded(0);
+    for instr in function.code.iter().flat_map(|code| &code.code) {
+        let handle = match instr {
+            Bytecode::Call(idx) => view.function_handle_at(*idx),
+            Bytecode::CallGeneric(idx) => {
+                view.function_handle_at(view.function_instantiation_at(*idx).handle)
+            },
+            _ => continue,
+        };
+        let module_id = view.module_id_for_handle(view.module_handle_at(handle.module));
+        let callee = format!(
+            "{}::{}",
+            module_id.short_str_lossless(),
+            view.identifier_at(handle.name)
+        );
+        let depth = match functions.get_key_value(&callee) {
+            Some((callee, _)) => call_depth(functions, callee, visits),
+            None => CallDepth::Bounded(1),
+        };
+        deepest_callee = deepest_callee.max(depth)
+    }
+    let depth = match deepest_callee {
+        CallDepth::Bounded(depth) => CallDepth::Bounded(depth + 1),
+        CallDepth::Unbounded => CallDepth::Unbounded,
+    };
+    visits.insert(name, Visit::Done(depth));
+    depth
+}
+
+/// Reports the functions whose frames exceed the thresholds of the options.
+fn report_exceeded(
+    env: &GlobalEnv,
+    options: &Options,
+    functions: &BTreeMap<String, Function>,
+    frames: &BTreeMap<String, FunctionFrame>,
+) {
+    if options.max_stack_depth.is_none() && options.max_call_depth.is_none() {
+        return;
+    }
+    let index = UnitModuleIndex::new(env);
+    for (name, frame) in frames {
+        let function = &functions[name];
+        let module_env = match &function.module {
+            Some(module_id) => index.find_module(module_id),
+            None => index.find_script(&function.name),
+        };
+        let loc = module_env
+            .map(|module_env| {
+                module_env
+                    .find_function(env.symbol_pool().make(&function.name))
+                    .map_or_else(|| module_env.get_loc(), |fun_env| fun_env.get_loc())
+            })
+            .unwrap_or_else(|| env.unknown_loc());
+        if let Some(max) = options.max_stack_depth {
+            if frame.max_stack_depth > max {
+                STACK_DEPTH_EXCEEDED.warning(
+                    env,
+                    &loc,
+                    &format!(
+                        "function `{}` needs an operand stack of depth {}, exceeding the \
+                         maximum of {}",
+                        name, frame.max_stack_depth, max
+                    ),
+                )
+            }
+        }
+        if let Some(max) = options.max_call_depth {
+            let msg = match frame.call_depth {
+                CallDepth::Bounded(depth) if depth > max => format!(
+                    "function `{}` leads to calls of depth {}, exceeding the maximum of {}",
+                    name, depth, max
+                ),
+                CallDepth::Bounded(_) => continue,
+                CallDepth::Unbounded => format!(
+                    "function `{}` leads to calls of unbounded depth, since it can reach a \
+                     cycle of calls, exceeding the maximum of {}",
+                    name, max
+                ),
+            };
+            CALL_DEPTH_EXCEEDED.warning(env, &loc, &msg)
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured tracing of a compilation with the `tracing` crate. Each phase of the compiler
+//! runs in an info span named `phase`, with the name of the phase as field, and each run of a
+//! processor of the bytecode pipeline on a function in a debug span named `processor`, with
+//! the name of the processor and the qualified name of the function as fields. The
+//! diagnostics added during a span are emitted as debug events within the innermost span.
+//!
+//! Spans and events are only emitted up to the level given by `Options::trace_filter`, and go
+//! to the subscriber in effect, which the embedder installs, for example with
+//! `tracing_subscriber`. Fields are only computed if a span is enabled, so tracing costs next
+//! to nothing if it is off or no subscriber is interested.
+
+use crate::{diagnostics::CompilerDiagnostic, source_positions::SourcePositionMapper};
+use anyhow::anyhow;
+use codespan_reporting::diagnostic::Severity;
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::{
+    function_target::FunctionData,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+};
+use std::{
+    cell::Cell,
+    fmt::{self, Formatter},
+    str::FromStr,
+};
+use tracing::{debug, debug_span, info_span, span::EnteredSpan, Level, Span};
+
+thread_local! {
+    /// The number of diagnostics of the env of the current phase which have been emitted, so
+    /// diagnostics emitted within the span of a processor are not emitted again when the
+    /// phase is exited.
+    static EMITTED_DIAGS: Cell<usize> = Cell::new(0);
+
+    /// The most verbose level traced by the compilation on this thread, if any.
+    static MAX_LEVEL: Cell<Option<Level>> = Cell::new(None);
+}
+
+/// Runs the function with the spans and events up to the level of the filter, like `debug`,
+/// emitted. Nothing is traced if no filter is given.
+pub(crate) fn with_trace_filter<T>(
+    filter: Option<&str>,
+    f: impl FnOnce() -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let level = filter
+        .map(|filter| {
+            Level::from_str(filter).map_err(|_| {
+                anyhow!(
+                    "invalid trace filter `{}`, expected a level like `info` or `debug`",
+                    filter
+                )
+            })
+        })
+        .transpose()?;
+    let outer = MAX_LEVEL.with(|max| max.replace(level));
+    let result = f();
+    MAX_LEVEL.with(|max| max.set(outer));
+    result
+}
+
+/// Returns whether spans and events of the level are traced by the compilation.
+fn is_traced(level: Level) -> bool {
+    MAX_LEVEL.with(|max| max.get().map_or(false, |max| level <= max))
+}
+
+/// A phase of the compiler in which the current thread is, from entering the span of the phase
+/// until `TracedPhase::exit` is called or the phase is dropped.
+pub(crate) struct TracedPhase {
+    span: EnteredSpan,
+    first_diag: usize,
+}
+
+impl TracedPhase {
+    /// Enters the span of the phase. The env, if there is one yet, is used to determine the
+    /// diagnostics added during the phase.
+    pub(crate) fn enter(name: &'static str, env: Option<&GlobalEnv>) -> Self {
+        let first_diag = env.map_or(0, diag_count);
+        EMITTED_DIAGS.with(|emitted| emitted.set(first_diag));
+        let span = if is_traced(Level::INFO) {
+            info_span!("phase", phase = name)
+        } else {
+            Span::none()
+        };
+        Self {
+            span: span.entered(),
+            first_diag,
+        }
+    }
+
+    /// Emits the diagnostics added during the phase and exits the span.
+    pub(crate) fn exit(self, env: &GlobalEnv) {
+        if !self.span.is_disabled() {
+            trace_diags_from(env, self.first_diag)
+        }
+    }
+}
+
+/// A processor which runs another processor in a span for each function, or, for single-run
+/// processors, in one span. It has the name of the wrapped processor, so pipeline extensions
+/// can be positioned relative to it.
+pub(crate) struct TracedProcessor {
+    processor: Box<dyn FunctionTargetProcessor>,
+}
+
+impl TracedProcessor {
+    pub(crate) fn new(processor: Box<dyn FunctionTargetProcessor>) -> Self {
+        Self { processor }
+    }
+
+    /// Runs the function in the span, emitting the diagnostics which it adds to the env.
+    fn in_span<T>(&self, env: &GlobalEnv, span: Span, f: impl FnOnce() -> T) -> T {
+        if span.is_disabled() {
+            return f();
+        }
+        let _entered = span.enter();
+        let first_diag = diag_count(env);
+        let result = f();
+        trace_diags_from(env, first_diag);
+        result
+    }
+}
+
+impl FunctionTargetProcessor for TracedProcessor {
+    fn process_and_maybe_remove(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        scc_opt: Option<&[FunctionEnv]>,
+    ) -> Option<FunctionData> {
+        let span = if is_traced(Level::DEBUG) {
+            debug_span!(
+                "processor",
+                name = %self.processor.name(),
+                function = %fun_env.get_full_name_str(),
+                variant = %data.variant,
+            )
+        } else {
+            Span::none()
+        };
+        self.in_span(fun_env.module_env.env, span, || {
+            self.processor
+                .process_and_maybe_remove(targets, fun_env, data, scc_opt)
+        })
+    }
+
+    fn name(&self) -> String {
+        self.processor.name()
+    }
+
+    fn initialize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.processor.initialize(env, targets)
+    }
+
+    fn finalize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        self.processor.finalize(env, targets)
+    }
+
+    fn is_single_run(&self) -> bool {
+        self.processor.is_single_run()
+    }
+
+    fn run(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        let span = if is_traced(Level::DEBUG) {
+            debug_span!("processor", name = %self.processor.name())
+        } else {
+            Span::none()
+        };
+        self.in_span(env, span, || self.processor.run(env, targets))
+    }
+
+    fn dump_result(
+        &self,
+        f: &mut Formatter<'_>,
+        env: &GlobalEnv,
+        targets: &FunctionTargetsHolder,
+    ) -> fmt::Result {
+        self.processor.dump_result(f, env, targets)
+    }
+}
+
+/// Returns the number of all diagnostics in the env.
+fn diag_count(env: &GlobalEnv) -> usize {
+    env.diag_count(Severity::Help)
+}
+
+/// Emits a debug event for each diagnostic of the env from the given position on which has not
+/// been emitted yet.
+fn trace_diags_from(env: &GlobalEnv, start: usize) {
+    if !is_traced(Level::DEBUG) || !tracing::enabled!(Level::DEBUG) {
+        return;
+    }
+    let start = start.max(EMITTED_DIAGS.with(|emitted| emitted.replace(diag_count(env))));
+    let positions = SourcePositionMapper::new(env);
+    for diag in env.get_diags_from(start) {
+        let diag = CompilerDiagnostic::new(env, &positions, &diag);
+        let location = diag
+            .location
+            .as_ref()
+            .map(|l| format!("{}:{}:{}", l.file, l.start_line, l.start_column))
+            .unwrap_or_default();
+        debug!(
+            severity = ?diag.severity,
+            code = diag.code.as_deref().unwrap_or(""),
+            location = %location,
+            "{}",
+            diag.message
+        )
+    }
+}
@@ -0,0 +1,510 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Builds the test plan consumed by the Move unit test framework, from the `#[test]` and
+//! `#[expected_failure]` attributes of the functions in the target modules, validating the
+//! attributes and the signatures of test functions on the way. This is the counterpart of the
+//! plan builder of the v1 compiler, working on the attributes of the model.
+//!
+//! The plan is only built if `Options::compile_test_code` is set, which also keeps the items
+//! annotated with `#[test]` or `#[test_only]` in the program. `run_checker` installs the plan
+//! of each module with tests as an extension of the `GlobalEnv`, and `build_test_plan` combines
+//! it with the compiled units into the plan the unit test framework runs, as used by the package
+//! system to run the tests of packages built with compiler v2.
+//!
+//! Named addresses in attributes, like in `#[test(account = @std)]`, are resolved with the
+//! address aliases of the env.
+
+use crate::diagnostic_codes::{
+    DiagnosticCode, EXPECTED_FAILURE_IN_ANY_MODULE, INVALID_EXPECTED_FAILURE,
+    INVALID_TEST_ATTRIBUTE, INVALID_TEST_SIGNATURE,
+};
+use move_compiler::{
+    compiled_unit::AnnotatedCompiledUnit,
+    shared::known_attributes::TestingAttribute,
+    unit_test::{ExpectedFailure, ExpectedMoveError, ModuleTestPlan, TestCase, TestPlan},
+};
+use move_core_types::{
+    account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+    value::MoveValue, vm_status::StatusCode,
+};
+use move_model::{
+    ast::{Address, Attribute, AttributeValue, ModuleName, Value},
+    model::{FunctionEnv, GlobalEnv, Loc, ModuleEnv},
+};
+use num::ToPrimitive;
+use std::collections::{BTreeMap, HashMap};
+
+/// The test plans of the target modules which have tests.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleTestPlans(pub Vec<ModuleTestPlan>);
+
+/// Returns the test plan of each target module which has tests, reporting diagnostics for
+/// invalid test attributes and test functions.
+pub fn construct_test_plans(env: &GlobalEnv) -> Vec<ModuleTestPlan> {
+    env.get_modules()
+        .filter(|module_env| module_env.is_target() && !module_env.is_script_module())
+        .filter_map(|module_env| {
+            let tests = module_env
+                .get_functions()
+                .filter_map(|fun_env| build_test_case(&fun_env))
+                .map(|test_case| (test_case.test_name.clone(), test_case))
+                .collect::<BTreeMap<_, _>>();
+            (!tests.is_empty()).then(|| ModuleTestPlan {
+                module_id: language_storage_id(&module_env),
+                tests,
+            })
+        })
+        .collect()
+}
+
+/// Returns the plan of the unit test framework for the tests of the target modules. The plan
+/// contains the compiled units, which must include all modules the tests depend on. The plans of
+/// the modules are taken from the env if `run_checker` installed them, and constructed from the
+/// model otherwise.
+pub fn build_test_plan(env: &GlobalEnv, units: &[AnnotatedCompiledUnit]) -> TestPlan {
+    let files = env
+        .get_source_file_ids()
+        .into_iter()
+        .filter_map(|file_id| {
+            let name = env.get_file(file_id).to_string_lossy().to_string();
+            let source = env.get_file_source(file_id).to_owned();
+            Some((env.get_file_hash(file_id)?, (name.into(), source)))
+        })
+        .collect::<HashMap<_, _>>();
+    let plans = match env.get_extension::<ModuleTestPlans>() {
+        Some(plans) => plans.0.clone(),
+        None => construct_test_plans(env),
+    };
+    TestPlan::new(plans, files, units.to_vec())
+}
+
+/// Like `build_test_plan`, taking ownership of the units.
+pub fn test_plan(env: &GlobalEnv, units: Vec<AnnotatedCompiledUnit>) -> TestPlan {
+    build_test_plan(env, &units)
+}
+
+/// Returns the id in storage of a target module.
+fn language_storage_id(module_env: &ModuleEnv) -> ModuleId {
+    let name = module_env.get_name();
+    ModuleId::new(
+        name.addr().expect_numerical(),
+        Identifier::new(module_env.symbol_pool().string(name.name()).as_str())
+            .expect("valid module name"),
+    )
+}
+
+/// Returns the test case for the function if it is a test, validating its attributes.
+fn build_test_case(fun_env: &FunctionEnv) -> Option<TestCase> {
+    let env = fun_env.module_env.env;
+    let find_attr = |name: &str| {
+        fun_env
+            .get_attributes()
+            .iter()
+            .find(|attr| env.symbol_pool().string(attr.name()).as_str() == name)
+    };
+    let attr_loc = |attr: &Attribute| env.get_node_loc(attr.node_id());
+    let Some(test_attr) = find_attr(TestingAttribute::TEST) else {
+        if let Some(failure_attr) = find_attr(TestingAttribute::EXPECTED_FAILURE) {
+            INVALID_TEST_ATTRIBUTE.error(
+                env,
+                &attr_loc(failure_attr),
+                "only functions annotated with `#[test]` can have an `#[expected_failure]` \
+                 attribute",
+            )
+        }
+        return None;
+    };
+    if let Some(test_only_attr) = find_attr(TestingAttribute::TEST_ONLY) {
+        INVALID_TEST_ATTRIBUTE.error(
+            env,
+            &attr_loc(test_only_attr),
+            "a function cannot be annotated with both `#[test]` and `#[test_only]`",
+        )
+    }
+    check_test_signature(fun_env);
+    let mut assigned = parse_test_arguments(env, test_attr);
+    let mut arguments = vec![];
+    for param in fun_env.get_parameters() {
+        match assigned.remove(&env.symbol_pool().string(param.0).to_string()) {
+            Some(value) => arguments.push(value),
+            None => INVALID_TEST_ATTRIBUTE.error(
+                env,
+                &attr_loc(test_attr),
+                &format!(
+                    "missing value for parameter `{}` of test `{}`",
+                    env.symbol_pool().string(param.0),
+                    fun_env.get_full_name_str()
+                ),
+            ),
+        }
+    }
+    for name in assigned.keys() {
+        INVALID_TEST_ATTRIBUTE.error(
+            env,
+            &attr_loc(test_attr),
+            &format!(
+                "`{}` is not a parameter of test `{}`",
+                name,
+                fun_env.get_full_name_str()
+            ),
+        )
+    }
+    let expected_failure = find_attr(TestingAttribute::EXPECTED_FAILURE)
+        .and_then(|failure_attr| parse_expected_failure(&fun_env.module_env, failure_attr));
+    Some(TestCase {
+        test_name: env.symbol_pool().string(fun_env.get_name()).to_string(),
+        arguments,
+        expected_failure,
+    })
+}
+
+/// Checks that the unit test framework can call the test function: it must not have type
+/// parameters, and its parameters must be signers.
+fn check_test_signature(fun_env: &FunctionEnv) {
+    let env = fun_env.module_env.env;
+    if fun_env.get_type_parameter_count() > 0 {
+        INVALID_TEST_SIGNATURE.error(
+            env,
+            &fun_env.get_loc(),
+            &format!(
+                "test `{}` cannot have type parameters",
+                fun_env.get_full_name_str()
+            ),
+        )
+    }
+    for param in fun_env.get_parameters() {
+        if !param.1.skip_reference().is_signer() {
+            INVALID_TEST_SIGNATURE.error(
+                env,
+                &fun_env.get_loc(),
+                &format!(
+                    "parameter `{}` of test `{}` has type `{}`, but test parameters must be \
+                     of type `signer` or `&signer`",
+                    env.symbol_pool().string(param.0),
+                    fun_env.get_full_name_str(),
+                    param.1.display(&fun_env.get_type_display_ctx())
+                ),
+            )
+        }
+    }
+}
+
+/// Returns the addresses assigned to parameters by a `#[test(<param> = <address>, ..)]`
+/// attribute.
+fn parse_test_arguments(env: &GlobalEnv, test_attr: &Attribute) -> BTreeMap<String, MoveValue> {
+    let mut assigned = BTreeMap::new();
+    let Attribute::Apply(_, _, args) = test_attr else {
+        INVALID_TEST_ATTRIBUTE.error(
+            env,
+            &env.get_node_loc(test_attr.node_id()),
+            "expected `#[test]` or `#[test(<param> = <address>, ..)]`",
+        );
+        return assigned;
+    };
+    for arg in args {
+        match arg {
+            Attribute::Assign(_, name, AttributeValue::Value(id, Value::Address(addr))) => {
+                if let Some(addr) =
+                    numerical_address(env, &INVALID_TEST_ATTRIBUTE, &env.get_node_loc(*id), addr)
+                {
+                    assigned.insert(
+                        env.symbol_pool().string(*name).to_string(),
+                        MoveValue::Address(addr),
+                    );
+                }
+            },
+            Attribute::Assign(_, _, value) => INVALID_TEST_ATTRIBUTE.error(
+                env,
+                &env.get_node_loc(value.node_id()),
+                "unsupported value, expected an address like `@0x1`",
+            ),
+            Attribute::Apply(id, _, _) => INVALID_TEST_ATTRIBUTE.error(
+                env,
+                &env.get_node_loc(*id),
+                "unexpected nested attribute, expected an assignment like `<param> = @0x1`",
+            ),
+        }
+    }
+    assigned
+}
+
+/// Parses an `#[expected_failure]` attribute. Constants and modules are resolved in the
+/// model.
+fn parse_expected_failure(
+    module_env: &ModuleEnv,
+    failure_attr: &Attribute,
+) -> Option<ExpectedFailure> {
+    let env = module_env.env;
+    let attr_loc = env.get_node_loc(failure_attr.node_id());
+    let args = match failure_attr {
+        Attribute::Apply(_, _, args) if args.is_empty() => return Some(ExpectedFailure::Expected),
+        Attribute::Apply(_, _, args) => args,
+        Attribute::Assign(..) => {
+            INVALID_EXPECTED_FAILURE.error(
+                env,
+                &attr_loc,
+                "expected `#[expected_failure]` or `#[expected_failure(<failure kind>, ..)]`",
+            );
+            return None;
+        },
+    };
+    let mut args = args
+        .iter()
+        .map(|arg| (env.symbol_pool().string(arg.name()).to_string(), arg))
+        .collect::<BTreeMap<_, _>>();
+    let mut kinds = TestingAttribute::expected_failure_cases()
+        .iter()
+        .filter_map(|kind| args.remove(*kind).map(|arg| (*kind, arg)))
+        .collect::<Vec<_>>();
+    if kinds.len() != 1 {
+        INVALID_EXPECTED_FAILURE.error(
+            env,
+            &attr_loc,
+            &format!(
+                "expected exactly one failure kind, but found {}; failure kinds are: {}",
+                kinds.len(),
+                TestingAttribute::expected_failure_cases().join(", ")
+            ),
+        );
+        return None;
+    }
+    let (kind, kind_attr) = kinds.pop().expect("one failure kind");
+    let location_attr = args.remove(TestingAttribute::ERROR_LOCATION);
+    let minor_status_attr = args.remove(TestingAttribute::MINOR_STATUS_NAME);
+    for (name, arg) in &args {
+        INVALID_EXPECTED_FAILURE.error(
+            env,
+            &env.get_node_loc(arg.node_id()),
+            &format!("unexpected argument `{}` of `#[expected_failure]`", name),
+        )
+    }
+    if minor_status_attr.is_some()
+        && kind != TestingAttribute::VECTOR_ERROR_NAME
+        && kind != TestingAttribute::MAJOR_STATUS_NAME
+    {
+        INVALID_EXPECTED_FAILURE.error(
+            env,
+            &attr_loc,
+            &format!(
+                "`{}` can only be given for `{}` or `{}`",
+                TestingAttribute::MINOR_STATUS_NAME,
+                TestingAttribute::VECTOR_ERROR_NAME,
+                TestingAttribute::MAJOR_STATUS_NAME
+            ),
+        );
+        return None;
+    }
+    let (status, sub_status, const_module) = match kind {
+        TestingAttribute::ABORT_CODE_NAME => {
+            let (code, const_module) = parse_code(module_env, kind_attr)?;
+            (StatusCode::ABORTED, Some(code), const_module)
+        },
+        TestingAttribute::MAJOR_STATUS_NAME => {
+            let (code, _) = parse_code(module_env, kind_attr)?;
+            let Ok(status) = StatusCode::try_from(code) else {
+                INVALID_EXPECTED_FAILURE.error(
+                    env,
+                    &env.get_node_loc(kind_attr.node_id()),
+                    &format!("no status code is associated with value `{}`", code),
+                );
+                return None;
+            };
+            (
+                status,
+                parse_minor_status(module_env, minor_status_attr)?,
+                None,
+            )
+        },
+        _ => {
+            if !matches!(kind_attr, Attribute::Apply(_, _, args) if args.is_empty()) {
+                INVALID_EXPECTED_FAILURE.error(
+                    env,
+                    &env.get_node_loc(kind_attr.node_id()),
+                    &format!("expected `{}` without a value", kind),
+                );
+                return None;
+            }
+            let status = match kind {
+                TestingAttribute::ARITHMETIC_ERROR_NAME => StatusCode::ARITHMETIC_ERROR,
+                TestingAttribute::VECTOR_ERROR_NAME => StatusCode::VECTOR_OPERATION_ERROR,
+                _ => StatusCode::OUT_OF_GAS,
+            };
+            (
+                status,
+                parse_minor_status(module_env, minor_status_attr)?,
+                None,
+            )
+        },
+    };
+    let location = match (location_attr, const_module) {
+        (Some(location_attr), _) => parse_location(env, location_attr)?,
+        (None, Some(module)) => module,
+        (None, None) if kind == TestingAttribute::ABORT_CODE_NAME => {
+            EXPECTED_FAILURE_IN_ANY_MODULE.warning(
+                env,
+                &env.get_node_loc(kind_attr.node_id()),
+                "the test passes for an abort with this code from any module; use a constant of \
+                 the expected module as the code, or add `location = <module>`",
+            );
+            return Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(
+                sub_status.expect("abort code"),
+            ));
+        },
+        (None, None) => {
+            INVALID_EXPECTED_FAILURE.error(
+                env,
+                &attr_loc,
+                &format!(
+                    "expected `{} = <module>` for `{}`",
+                    TestingAttribute::ERROR_LOCATION,
+                    kind
+                ),
+            );
+            return None;
+        },
+    };
+    Some(ExpectedFailure::ExpectedWithError(ExpectedMoveError(
+        status,
+        sub_status,
+        move_binary_format::errors::Location::Module(location),
+    )))
+}
+
+/// Parses the value of an optional `minor_status = <code>` argument.
+fn parse_minor_status(module_env: &ModuleEnv, attr: Option<&Attribute>) -> Option<Option<u64>> {
+    match attr {
+        Some(attr) => parse_code(module_env, attr).map(|(code, _)| Some(code)),
+        None => Some(None),
+    }
+}
+
+/// Parses a `<name> = <code>` argument, where the code is a u64 value or a u64 constant.
+/// Returns the code, and the module of the constant, if any.
+fn parse_code(module_env: &ModuleEnv, attr: &Attribute) -> Option<(u64, Option<ModuleId>)> {
+    let env = module_env.env;
+    let Attribute::Assign(_, name, value) = attr else {
+        INVALID_EXPECTED_FAILURE.error(
+            env,
+            &env.get_node_loc(attr.node_id()),
+            &format!(
+                "expected a value, as in `{} = <code>`",
+                env.symbol_pool().string(attr.name())
+            ),
+        );
+        return None;
+    };
+    let value_loc = env.get_node_loc(value.node_id());
+    let (value, const_module) = match value {
+        AttributeValue::Value(_, value) => (value.clone(), None),
+        AttributeValue::Name(_, Some(module_name), constant) => {
+            let Some(constant_value) = env.find_module(module_name).and_then(|module_env| {
+                module_env
+                    .find_named_constant(*constant)
+                    .map(|constant_env| constant_env.get_value())
+            }) else {
+                INVALID_EXPECTED_FAILURE.error(
+                    env,
+                    &value_loc,
+                    &format!(
+                        "unknown constant `{}::{}`",
+                        module_name.display_full(env),
+                        env.symbol_pool().string(*constant)
+                    ),
+                );
+                return None;
+            };
+            (
+                constant_value,
+                Some(module_id(env, &value_loc, module_name)?),
+            )
+        },
+        AttributeValue::Name(_, None, _) => {
+            INVALID_EXPECTED_FAILURE.error(
+                env,
+                &value_loc,
+                "expected a u64 value, or a qualified constant like \
+                 `std::vector::EINDEX_OUT_OF_BOUNDS`",
+            );
+            return None;
+        },
+    };
+    match value {
+        Value::Number(n) if n.to_u64().is_some() => Some((n.to_u64().expect("u64"), const_module)),
+        _ => {
+            INVALID_EXPECTED_FAILURE.error(
+                env,
+                &value_loc,
+                &format!(
+                    "the value of `{}` must be a u64",
+                    env.symbol_pool().string(*name)
+                ),
+            );
+            None
+        },
+    }
+}
+
+/// Parses a `location = <module>` argument.
+fn parse_location(env: &GlobalEnv, attr: &Attribute) -> Option<ModuleId> {
+    match attr {
+        Attribute::Assign(_, _, AttributeValue::Name(id, Some(module_name), member))
+            if env.symbol_pool().string(*member).is_empty() =>
+        {
+            if env.find_module(module_name).is_none() {
+                INVALID_EXPECTED_FAILURE.error(
+                    env,
+                    &env.get_node_loc(*id),
+                    &format!("unknown module `{}`", module_name.display_full(env)),
+                );
+                return None;
+            }
+            module_id(env, &env.get_node_loc(*id), module_name)
+        },
+        _ => {
+            INVALID_EXPECTED_FAILURE.error(
+                env,
+                &env.get_node_loc(attr.node_id()),
+                &format!(
+                    "expected a module, as in `{} = std::vector`",
+                    TestingAttribute::ERROR_LOCATION
+                ),
+            );
+            None
+        },
+    }
+}
+
+/// Returns the id of the module in storage, reporting an error if its address is not known.
+fn module_id(env: &GlobalEnv, loc: &Loc, module_name: &ModuleName) -> Option<ModuleId> {
+    let addr = numerical_address(env, &INVALID_EXPECTED_FAILURE, loc, module_name.addr())?;
+    let name = Identifier::new(env.symbol_pool().string(module_name.name()).as_str()).ok()?;
+    Some(ModuleId::new(addr, name))
+}
+
+/// Returns the numerical value of the address, resolving named addresses with the address
+/// aliases of the env, and reporting an error with the code if a named address is not assigned.
+fn numerical_address(
+    env: &GlobalEnv,
+    code: &DiagnosticCode,
+    loc: &Loc,
+    addr: &Address,
+) -> Option<AccountAddress> {
+    match addr {
+        Address::Numerical(addr) => Some(*addr),
+        Address::Symbolic(name) => {
+            let resolved = env.resolve_address_alias(*name);
+            if resolved.is_none() {
+                code.error(
+                    env,
+                    loc,
+                    &format!(
+                        "named address `{}` is not assigned",
+                        env.symbol_pool().string(*name)
+                    ),
+                )
+            }
+            resolved
+        },
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Check for dependencies which contribute nothing to the build, run after bytecode
+//! generation. A module is used if a target module uses it, transitively: by calling one of
+//! its functions, by using one of its structs in a type, including in specs, or by declaring it
+//! a friend. Each entry of `Options::dependencies`, a file or a directory, and each of
+//! `Options::dependency_buffers`, is a root of dependency files; a warning is reported for a
+//! root if none of its files declares a used module. Test-only code is part of the env only
+//! if test code is compiled, so its usage only counts then.
+
+use crate::{diagnostic_codes::UNUSED_DEPENDENCY, Options};
+use codespan_reporting::diagnostic::Diagnostic;
+use move_command_line_common::files::find_move_filenames;
+use move_model::model::{GlobalEnv, ModuleId};
+use std::collections::BTreeSet;
+
+/// Reports a warning for each root of dependency files of which no file declares a module used
+/// by the target modules.
+pub fn check_unused_dependencies(env: &GlobalEnv, options: &Options) {
+    let used_files = used_modules(env)
+        .into_iter()
+        .map(|mid| {
+            let module_env = env.get_module(mid);
+            env.get_file(module_env.get_loc().file_id())
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect::<BTreeSet<_>>();
+    let roots = options
+        .dependencies
+        .iter()
+        .chain(options.dependency_buffers.iter().map(|(path, _)| path));
+    for root in roots {
+        // Buffers are not in the file system, and count as files.
+        let files = if options.dependency_buffers.iter().any(|(p, _)| p == root) {
+            vec![root.clone()]
+        } else {
+            find_move_filenames(&[root.clone()], true).unwrap_or_default()
+        };
+        // A dependency which is also a source is compiled in any case.
+        let files = files
+            .into_iter()
+            .filter(|f| !options.sources.contains(f))
+            .collect::<Vec<_>>();
+        if files.is_empty() || files.iter().any(|f| used_files.contains(f)) {
+            continue;
+        }
+        let mut diag = Diagnostic::warning()
+            .with_code(UNUSED_DEPENDENCY.code)
+            .with_message(format!(
+                "dependency `{}` is not used by the compiled modules",
+                root
+            ));
+        if files.len() > 1 || files[0] != *root {
+            diag = diag.with_notes(vec![format!(
+                "unused dependency files: {}",
+                files
+                    .iter()
+                    .map(|f| format!("`{}`", f))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )])
+        }
+        env.add_diag(diag)
+    }
+}
+
+/// Returns the modules which are not targets and which the target modules transitively use.
+fn used_modules(env: &GlobalEnv) -> BTreeSet<ModuleId> {
+    let mut visited = BTreeSet::new();
+    let mut todo = env
+        .get_modules()
+        .filter(|m| m.is_target())
+        .map(|m| m.get_id())
+        .collect::<Vec<_>>();
+    while let Some(mid) = todo.pop() {
+        if !visited.insert(mid) {
+            continue;
+        }
+        let module_env = env.get_module(mid);
+        todo.extend(module_env.get_used_modules(true));
+        todo.extend(module_env.get_friend_modules());
+    }
+    visited.retain(|mid| !env.get_module(*mid).is_target());
+    visited
+}
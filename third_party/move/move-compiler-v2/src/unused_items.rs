@@ -0,0 +1,219 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks for items of target modules which are never used, run after bytecode generation:
+//!
+//! - private functions which are not called from another function of their module, nor used
+//!   in specs. Entry functions, tests, and `init_module`, which the VM calls when the module is
+//!   published, are called from outside of Move code and not checked;
+//! - structs whose type does not occur in a signature, a field, or an expression of any of
+//!   the target modules, including specs;
+//! - constants which are neither used in an expression, including specs, nor in an attribute,
+//!   like the abort code of `#[expected_failure]`.
+//!
+//! `#[test_only]` and `#[verify_only]` items are not checked. The warnings are lints, which can
+//! be allowed with `#[lint::allow(unused_item)]`.
+
+use crate::diagnostic_codes::UNUSED_ITEM;
+use move_binary_format::file_format::Visibility;
+use move_model::{
+    ast::{Attribute, AttributeValue, ExpData, ModuleName, Spec},
+    model::{FunctionEnv, GlobalEnv, ModuleEnv, QualifiedId, StructId},
+    symbol::Symbol,
+    ty::Type,
+};
+use std::collections::BTreeSet;
+
+/// The name of the function which the VM calls when the module is published.
+pub(crate) const INIT_MODULE: &str = "init_module";
+
+/// Reports a warning for each unused private function, struct, and constant of the target
+/// modules.
+pub fn check_unused_items(env: &GlobalEnv) {
+    let used_structs = used_structs(env);
+    let used_in_attributes = constants_used_in_attributes(env);
+    for module_env in env.get_modules() {
+        if !module_env.is_target() || module_env.is_script_module() {
+            continue;
+        }
+        for fun_env in module_env.get_functions() {
+            if is_checked_function(&fun_env) && !is_function_used(&fun_env) {
+                UNUSED_ITEM.warning(
+                    env,
+                    &fun_env.get_loc(),
+                    &format!("function `{}` is never called", fun_env.get_full_name_str()),
+                )
+            }
+        }
+        for struct_env in module_env.get_structs() {
+            if struct_env.is_test_only()
+                || struct_env.is_verify_only()
+                || struct_env.is_ghost_memory()
+                || used_structs.contains(&struct_env.get_qualified_id())
+            {
+                continue;
+            }
+            UNUSED_ITEM.warning(
+                env,
+                &struct_env.get_loc(),
+                &format!("struct `{}` is never used", struct_env.get_full_name_str()),
+            )
+        }
+        for const_env in module_env.get_named_constants() {
+            if const_env.is_used()
+                || used_in_attributes
+                    .contains(&(module_env.get_name().clone(), const_env.get_name()))
+            {
+                continue;
+            }
+            UNUSED_ITEM.warning(
+                env,
+                &const_env.get_loc(),
+                &format!(
+                    "constant `{}::{}` is never used",
+                    module_env.get_name().display(env),
+                    const_env.get_name().display(env.symbol_pool())
+                ),
+            )
+        }
+    }
+}
+
+/// Returns true if the function is private and called only from Move code.
+fn is_checked_function(fun_env: &FunctionEnv) -> bool {
+    fun_env.visibility() == Visibility::Private
+        && !fun_env.is_entry()
+        && !fun_env.is_test_only()
+        && !fun_env.is_verify_only()
+        && fun_env.get_name_str() != INIT_MODULE
+}
+
+/// Returns true if the function is called by another function of its module, or used in
+/// specs.
+fn is_function_used(fun_env: &FunctionEnv) -> bool {
+    let id = fun_env.get_qualified_id();
+    let module_env = &fun_env.module_env;
+    let called = module_env.get_functions().any(|caller| {
+        caller.get_qualified_id() != id
+            && caller
+                .get_called_functions()
+                .is_some_and(|callees| callees.contains(&id))
+    });
+    called || is_used_in_specs(fun_env)
+}
+
+/// Returns true if the function is used in specs, by way of the spec function derived from it.
+pub(crate) fn is_used_in_specs(fun_env: &FunctionEnv) -> bool {
+    let module_env = &fun_env.module_env;
+    let spec_name = module_env
+        .symbol_pool()
+        .make(&format!("${}", fun_env.get_name_str()));
+    module_env
+        .get_spec_funs_of_name(spec_name)
+        .any(|(spec_fun_id, _)| module_env.spec_fun_is_used(*spec_fun_id))
+}
+
+/// Returns the structs whose types occur in the target modules, except in their own
+/// declaration.
+fn used_structs(env: &GlobalEnv) -> BTreeSet<QualifiedId<StructId>> {
+    let mut used = BTreeSet::new();
+    for module_env in env.get_modules().filter(|m| m.is_target()) {
+        add_spec_structs(env, module_env.get_spec(), &mut used);
+        for (_, decl) in module_env.get_spec_funs() {
+            // The spec functions derived from Move functions mirror their bodies, which are
+            // visited below.
+            if decl.is_move_fun {
+                continue;
+            }
+            if let Some(body) = &decl.body {
+                add_exp_structs(env, body, &mut used)
+            }
+        }
+        for struct_env in module_env.get_structs() {
+            let mut field_structs = BTreeSet::new();
+            for field_env in struct_env.get_fields() {
+                add_type_structs(&field_env.get_type(), &mut field_structs)
+            }
+            field_structs.remove(&struct_env.get_qualified_id());
+            used.extend(field_structs);
+            add_spec_structs(env, struct_env.get_spec(), &mut used);
+        }
+        for fun_env in module_env.get_functions() {
+            for ty in fun_env.get_parameter_types() {
+                add_type_structs(&ty, &mut used)
+            }
+            add_type_structs(&fun_env.get_result_type(), &mut used);
+            if let Some(def) = fun_env.get_def() {
+                add_exp_structs(env, def, &mut used);
+                def.visit(&mut |exp| {
+                    if let ExpData::SpecBlock(_, spec) = exp {
+                        add_spec_structs(env, spec, &mut used)
+                    }
+                })
+            }
+            add_spec_structs(env, &fun_env.get_spec(), &mut used);
+        }
+    }
+    used
+}
+
+fn add_type_structs(ty: &Type, used: &mut BTreeSet<QualifiedId<StructId>>) {
+    ty.visit(&mut |ty| {
+        if let Type::Struct(mid, sid, _) = ty {
+            used.insert(mid.qualified(*sid));
+        }
+    })
+}
+
+/// Adds the structs in the types and type instantiations of the nodes of the expression.
+fn add_exp_structs(env: &GlobalEnv, exp: &ExpData, used: &mut BTreeSet<QualifiedId<StructId>>) {
+    for id in exp.node_ids() {
+        add_type_structs(&env.get_node_type(id), used);
+        for ty in env.get_node_instantiation_opt(id).unwrap_or_default() {
+            add_type_structs(&ty, used)
+        }
+    }
+}
+
+/// Adds the structs in the expressions of the conditions of the spec, including those of the
+/// specs of code points.
+fn add_spec_structs(env: &GlobalEnv, spec: &Spec, used: &mut BTreeSet<QualifiedId<StructId>>) {
+    for cond in spec.conditions.iter().chain(spec.update_map.values()) {
+        for exp in cond.all_exps() {
+            add_exp_structs(env, exp, used)
+        }
+    }
+    for spec in spec.on_impl.values() {
+        add_spec_structs(env, spec, used)
+    }
+}
+
+/// Returns the constants, by module and name, which are used in attributes of target modules
+/// or their members, like `#[expected_failure(abort_code = m::E)]`.
+fn constants_used_in_attributes(env: &GlobalEnv) -> BTreeSet<(ModuleName, Symbol)> {
+    fn add(module_env: &ModuleEnv, attrs: &[Attribute], used: &mut BTreeSet<(ModuleName, Symbol)>) {
+        for attr in attrs {
+            match attr {
+                Attribute::Apply(_, _, args) => add(module_env, args, used),
+                Attribute::Assign(_, _, AttributeValue::Name(_, module_name, name)) => {
+                    let module_name = module_name
+                        .clone()
+                        .unwrap_or_else(|| module_env.get_name().clone());
+                    used.insert((module_name, *name));
+                },
+                Attribute::Assign(_, _, AttributeValue::Value(..)) => {},
+            }
+        }
+    }
+    let mut used = BTreeSet::new();
+    for module_env in env.get_modules().filter(|m| m.is_target()) {
+        add(&module_env, module_env.get_attributes(), &mut used);
+        for fun_env in module_env.get_functions() {
+            add(&module_env, fun_env.get_attributes(), &mut used)
+        }
+        for struct_env in module_env.get_structs() {
+            add(&module_env, struct_env.get_attributes(), &mut used)
+        }
+    }
+    used
+}
@@ -0,0 +1,40 @@
+module 0x42::drop_on_branch {
+    struct R { v: u64 }
+    struct S has drop {}
+
+    fun consume(r: R): u64 {
+        let R { v } = r;
+        v
+    }
+
+    // `r` is only consumed on one branch, so it is dropped on the other.
+    fun consume_on_one_branch(r: R, c: bool): u64 {
+        if (c) consume(r) else 0
+    }
+
+    // Both values are dropped when the function returns.
+    fun unused_params(r: R, s: S): u64 {
+        1
+    }
+
+    fun ignore_result(): u64 {
+        R { v: 1 };
+        2
+    }
+
+    fun write_ref(r: &mut R) {
+        *r = R { v: 2 }
+    }
+
+    fun read_ref(r: &R): R {
+        *r
+    }
+
+    fun compare(r1: R, r2: R): bool {
+        r1 == r2
+    }
+
+    fun copy_vector(v: vector<R>): (vector<R>, vector<R>) {
+        (v, v)
+    }
+}
@@ -0,0 +1,40 @@
+module 0x42::valid {
+    struct R has key { v: u64 }
+    struct Box<T> has copy, drop, store { t: T }
+    struct Phantom<phantom T> has copy, drop {}
+
+    fun consume(r: R): u64 {
+        let R { v } = r;
+        v
+    }
+
+    // Values which are not dropped on aborting paths do not need `drop`.
+    fun consume_or_abort(r: R, c: bool): u64 {
+        if (c) consume(r) else abort 0
+    }
+
+    fun unused_on_abort(_r: R): u64 {
+        abort 1
+    }
+
+    // Copies of copyable types are fine, also conditionally on type arguments.
+    fun copy_box<T: copy + drop>(b: Box<T>): (Box<T>, Box<T>) {
+        (b, b)
+    }
+
+    // Phantom type arguments do not restrict abilities.
+    fun copy_phantom(p: Phantom<R>): (Phantom<R>, Phantom<R>) {
+        (p, p)
+    }
+
+    fun globals(s: &signer, r: R): bool acquires R {
+        move_to(s, r);
+        let R { v: _ } = move_from<R>(@0x42);
+        exists<R>(@0x42)
+    }
+
+    fun vectors(v: vector<R>): vector<R> {
+        let w = v;
+        w
+    }
+}
@@ -0,0 +1,30 @@
+module 0x42::missing {
+    struct R has key { f: u64 }
+
+    struct T has key { g: u64 }
+
+    fun direct_borrow(a: address): u64 {
+        borrow_global<R>(a).f
+    }
+
+    fun direct_borrow_mut(a: address) {
+        borrow_global_mut<R>(a).f = 1;
+    }
+
+    fun direct_move_from(a: address): u64 acquires R {
+        let T { g } = move_from<T>(a);
+        borrow_global<R>(a).f + g
+    }
+
+    fun helper(a: address): u64 acquires R {
+        borrow_global<R>(a).f
+    }
+
+    fun through_call(a: address): u64 {
+        helper(a)
+    }
+
+    fun transitive(a: address): u64 acquires T {
+        through_call(a) + borrow_global<T>(a).g
+    }
+}
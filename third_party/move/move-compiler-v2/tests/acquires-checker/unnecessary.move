@@ -0,0 +1,13 @@
+module 0x42::unnecessary {
+    struct R has key { f: u64 }
+
+    struct T has key { g: u64 }
+
+    fun never_acquired(a: address): bool acquires R {
+        exists<R>(a)
+    }
+
+    fun partially_acquired(a: address): u64 acquires R, T {
+        borrow_global<R>(a).f
+    }
+}
@@ -0,0 +1,31 @@
+module 0x42::valid {
+    struct R has key { f: u64 }
+
+    struct T has key { g: u64 }
+
+    fun read(a: address): u64 acquires R {
+        borrow_global<R>(a).f
+    }
+
+    fun write(a: address, x: u64) acquires R {
+        borrow_global_mut<R>(a).f = x;
+    }
+
+    fun transitive(a: address): u64 acquires R {
+        write(a, 1);
+        read(a)
+    }
+
+    fun recursive(a: address, n: u64): u64 acquires R, T {
+        if (n == 0) {
+            let T { g } = move_from<T>(a);
+            g
+        } else {
+            recursive(a, n - 1) + transitive(a)
+        }
+    }
+
+    fun no_global_access(a: address): bool {
+        exists<R>(a) && exists<T>(a)
+    }
+}
@@ -0,0 +1,10 @@
+// inject-error: broken
+module 0x42::m {
+    fun ok(x: u64): u64 {
+        x + 1
+    }
+
+    fun broken(x: u64): u64 {
+        x + 2
+    }
+}
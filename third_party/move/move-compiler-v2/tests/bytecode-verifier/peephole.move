@@ -0,0 +1,34 @@
+// experiment: peephole-optimization
+module 0x42::m {
+    fun while_true(x: u64): u64 {
+        while (true) {
+            if (x > 10) break;
+            x = x + 1
+        };
+        x
+    }
+
+    fun negation(c: bool): u64 {
+        if (!c) 1 else 2
+    }
+
+    fun double_negation(c: bool): bool {
+        !!c
+    }
+
+    fun unused(x: u64) {
+        let _ = x;
+        let _ = 1;
+    }
+
+    fun loop_with_continue(n: u64): u64 {
+        let i = 0;
+        let sum = 0;
+        while (i < n) {
+            i = i + 1;
+            if (i % 2 == 0) continue;
+            sum = sum + i
+        };
+        sum
+    }
+}
@@ -0,0 +1,15 @@
+module 0x42::m {
+    struct R has key { x: u64 }
+
+    fun get(addr: address): u64 acquires R {
+        borrow_global<R>(addr).x
+    }
+
+    fun branch(c: bool, r: &mut R): u64 {
+        let x = &mut r.x;
+        if (c) {
+            *x = 1
+        };
+        r.x
+    }
+}
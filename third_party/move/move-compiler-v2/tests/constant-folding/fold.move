@@ -0,0 +1,35 @@
+module 0x42::fold {
+    fun arith(): u64 {
+        1 + 2 * 3 - 4 / 2 % 3
+    }
+
+    fun bits(): u8 {
+        (0xf0 | 0x0f) & (1 << 7) ^ (255 >> 4)
+    }
+
+    fun compare(): bool {
+        1 < 2 && 3 >= 3 || !(4 == 4)
+    }
+
+    fun and_false(x: bool): bool {
+        x && false
+    }
+
+    fun cond(): u64 {
+        let x = 1;
+        if (x + 1 == 2) 3 else 4
+    }
+
+    fun casts(): u128 {
+        ((255u8 as u64) as u128) + (1u256 as u128)
+    }
+
+    fun wide(): u256 {
+        115792089237316195423570985008687907853269984665640564039457584007913129639935 - 1
+    }
+
+    fun not_constant(x: u64): u64 {
+        let y = 2;
+        x + y * 3
+    }
+}
@@ -0,0 +1,26 @@
+module 0x42::overflow {
+    fun add(): u8 {
+        255 + 1
+    }
+
+    fun sub(): u64 {
+        let x = 1;
+        x - 2
+    }
+
+    fun mul(): u128 {
+        340282366920938463463374607431768211455 * 2
+    }
+
+    fun div(): u32 {
+        1 / 0
+    }
+
+    fun shift(): u16 {
+        1 << 16
+    }
+
+    fun cast(): u8 {
+        (256u64 as u8)
+    }
+}
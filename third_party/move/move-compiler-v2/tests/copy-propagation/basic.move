@@ -0,0 +1,27 @@
+module 0x42::basic {
+    struct R { f: u64 }
+
+    fun straight(x: u64): u64 {
+        let y = x;
+        let z = y;
+        z + y
+    }
+
+    fun reassigned(x: u64): u64 {
+        let y = x;
+        x = x + 1;
+        y + x
+    }
+
+    fun across_blocks(c: bool, x: u64): u64 {
+        let y = x;
+        if (c) { y = y + 1 };
+        y
+    }
+
+    fun not_copyable(r: R): u64 {
+        let s = r;
+        let R { f } = s;
+        f
+    }
+}
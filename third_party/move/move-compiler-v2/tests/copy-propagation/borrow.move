@@ -0,0 +1,19 @@
+module 0x42::borrow {
+    fun borrowed(x: u64): u64 {
+        let y = x;
+        let r = &mut x;
+        *r = 2;
+        x + y
+    }
+
+    fun mut_ref(r: &mut u64): u64 {
+        let s = r;
+        *s = 1;
+        *r
+    }
+
+    fun imm_ref(r: &u64): u64 {
+        let s = r;
+        *s + *r
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the coverage maps of compiled modules. A unit test is run with the VM tracing the
+//! executed instructions, as for collecting coverage, and the traced code offsets are mapped
+//! to source lines. This is the only test in this file, since tracing is enabled for the
+//! whole process.
+
+use codespan_reporting::term::termcolor::Buffer;
+use move_compiler_v2::{
+    coverage_map::ModuleCodeMap, run_move_compiler_with_targets, unit_test, Options,
+};
+use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+use move_unit_test::UnitTestingConfig;
+use std::{collections::BTreeMap, fs};
+
+const SOURCE: &str = "module 0x42::m {
+    public fun classify(x: u64): u64 {
+        if (x > 100) {
+            let big = x * 2;
+            big + 1
+        } else {
+            x + 1
+        }
+    }
+
+    #[test]
+    fun small() {
+        assert!(classify(3) == 4, 0)
+    }
+}
+";
+
+/// Returns how often the instructions of the function `m::classify` at each code offset were
+/// executed, according to the trace.
+fn executed_offsets(trace: &str) -> BTreeMap<u64, usize> {
+    let mut counts = BTreeMap::new();
+    for line in trace.lines() {
+        let (context, pc) = line.split_once(',').expect("trace entry");
+        let segments = context.split("::").collect::<Vec<_>>();
+        if let [addr, "m", "classify"] = segments.as_slice() {
+            assert_eq!(
+                AccountAddress::from_hex_literal(addr).expect("address"),
+                AccountAddress::from_hex_literal("0x42").unwrap()
+            );
+            *counts.entry(pc.parse().expect("offset")).or_default() += 1
+        }
+    }
+    counts
+}
+
+/// Returns for each line of the source how often instructions located there were executed.
+fn line_coverage(code_map: &ModuleCodeMap, executed: &BTreeMap<u64, usize>) -> Vec<usize> {
+    let fun_map = &code_map.function_maps[&Identifier::new("classify").unwrap()];
+    let mut line_starts = vec![0];
+    line_starts.extend(SOURCE.match_indices('\n').map(|(idx, _)| idx + 1));
+    let mut counts = vec![0; line_starts.len()];
+    for (offset, count) in executed {
+        let loc = fun_map.code_map[offset];
+        for (line, start) in line_starts.iter().enumerate() {
+            let end = line_starts.get(line + 1).cloned().unwrap_or(SOURCE.len());
+            if (loc.start() as usize) < end && (loc.end() as usize) > *start {
+                counts[line] += count
+            }
+        }
+    }
+    counts
+}
+
+#[test]
+fn untaken_branch_is_not_covered() {
+    let trace_dir = tempfile::tempdir().expect("temp dir");
+    let trace_file = trace_dir.path().join("m.trace");
+    std::env::set_var("MOVE_VM_TRACE", &trace_file);
+    std::env::set_var("MOVE_VM_TRACE_FLUSH", "1");
+    let coverage_dir = trace_dir.path().join("coverage");
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        compile_test_code: true,
+        emit_coverage_map: Some(coverage_dir.clone()),
+        ..Options::default()
+    };
+    let mut error_writer = Buffer::no_color();
+    let output = run_move_compiler_with_targets(&mut error_writer, options).unwrap_or_else(|e| {
+        panic!(
+            "compilation failed: {}\n{}",
+            e,
+            String::from_utf8_lossy(&error_writer.into_inner())
+        )
+    });
+    // The map written to disk is the one returned.
+    let written: ModuleCodeMap = serde_json::from_str(
+        &fs::read_to_string(coverage_dir.join("m.json")).expect("coverage map written"),
+    )
+    .expect("coverage map");
+    assert_eq!(output.coverage_maps, vec![written]);
+
+    let plan = unit_test::test_plan(&output.env, output.annotated_units);
+    let (report, all_passed) = UnitTestingConfig::default_with_bound(None)
+        .run_and_report_unit_tests(plan, None, None, vec![])
+        .expect("tests run");
+    assert!(all_passed, "{}", String::from_utf8_lossy(&report));
+
+    let executed = executed_offsets(&fs::read_to_string(&trace_file).expect("trace"));
+    assert!(!executed.is_empty());
+    let lines = line_coverage(&output.coverage_maps[0], &executed);
+    let line_of = |text: &str| {
+        SOURCE
+            .lines()
+            .position(|line| line.contains(text))
+            .expect("line")
+    };
+    assert!(lines[line_of("if (x > 100)")] > 0, "{:?}", lines);
+    assert!(lines[line_of("x + 1")] > 0, "{:?}", lines);
+    assert_eq!(lines[line_of("let big = x * 2")], 0, "{:?}", lines);
+    assert_eq!(lines[line_of("big + 1")], 0, "{:?}", lines);
+}
@@ -0,0 +1,25 @@
+module 0x42::assign {
+    struct S has drop { f: u64 }
+
+    fun chain(x: u64): u64 {
+        let y = x;
+        let z = y;
+        z
+    }
+
+    fun unused_store(x: u64): u64 {
+        let _y = 1;
+        x + 1
+    }
+
+    fun overwritten(x: u64): u64 {
+        let y = x;
+        y = 2;
+        y
+    }
+
+    fun pack_chain(x: u64): S {
+        let s = S { f: x };
+        s
+    }
+}
@@ -0,0 +1,32 @@
+module 0x42::loops {
+    fun count(n: u64): u64 {
+        let i = 0;
+        let sum = 0;
+        while (i < n) {
+            let j = i;
+            sum = sum + j;
+            i = i + 1;
+        };
+        sum
+    }
+
+    fun cond(c: bool, x: u64): u64 {
+        let r = if (c) x else x + 1;
+        r
+    }
+
+    fun sum_of_odds(): u64 {
+        let x = 0;
+        let y = 0;
+        loop {
+            if (x < 10) {
+                x = x + 1;
+                if (x % 2 == 0) continue;
+                y = y + x
+            } else {
+                break
+            }
+        };
+        y
+    }
+}
@@ -0,0 +1,20 @@
+module 0x42::m {
+    struct S has drop { x: u64 }
+
+    public fun make(x: u64): S {
+        S { x }
+    }
+
+    public fun get(s: &S): u64 {
+        s.x
+    }
+}
+
+script {
+    use 0x42::m;
+
+    fun main() {
+        let s = m::make(1);
+        assert!(m::get(&s) == 1, 0);
+    }
+}
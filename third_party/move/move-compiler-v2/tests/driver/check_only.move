@@ -0,0 +1,9 @@
+// flags: --check
+module 0x42::M {
+    use 0x42::M;
+
+    struct S {}
+
+    // Reported when generating code, but not when only checking.
+    fun consume(_s: S) {}
+}
@@ -0,0 +1,29 @@
+// flags: --compare-with-v1 code
+module 0x42::m {
+    use 0x42::n;
+
+    struct S has drop { x: u64, y: bool }
+
+    public fun make(x: u64): S {
+        S { x, y: x > 0 }
+    }
+
+    public fun sum(v: &vector<u64>): u64 {
+        let i = 0;
+        let s = 0;
+        while (i < std::vector::length(v)) {
+            s = s + *std::vector::borrow(v, i);
+            i = i + 1
+        };
+        s
+    }
+
+    public fun helper(x: u64): u64 {
+        let y = x;
+        y + n::one()
+    }
+}
+
+module 0x42::n {
+    public fun one(): u64 { 1 }
+}
@@ -0,0 +1,10 @@
+// flags: --compare-with-v1 abi
+module 0x42::m {
+    public inline fun twice(x: u64): u64 {
+        x + x
+    }
+
+    public fun four(): u64 {
+        twice(2)
+    }
+}
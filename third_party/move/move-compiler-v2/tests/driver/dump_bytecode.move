@@ -0,0 +1,18 @@
+// flags: --dump-bytecode
+module 0x42::m {
+    public fun f(x: u64): u64 {
+        x + 1
+    }
+}
+
+module 0x42::n {
+    public fun g(): u64 {
+        0x42::m::f(1)
+    }
+}
+
+script {
+    fun main() {
+        assert!(0x42::n::g() == 2, 0);
+    }
+}
@@ -0,0 +1,10 @@
+// flags: --dump-bytecode-diff
+module 0x42::m {
+    fun id(x: u64): u64 {
+        x
+    }
+
+    public fun ignore(x: u64) {
+        id(x);
+    }
+}
@@ -0,0 +1,9 @@
+// flags: --dump-bytecode-diff --optimize 1
+module 0x42::m {
+    public fun copies(x: u64): u64 {
+        let y = x;
+        let z = y;
+        let unused = z;
+        z + y
+    }
+}
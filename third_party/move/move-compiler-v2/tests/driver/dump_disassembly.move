@@ -0,0 +1,4 @@
+// flags: --dump-disassembly
+module 0x42::m {
+    public fun inc(x: u64): u64 { x + 1 }
+}
@@ -0,0 +1,43 @@
+// flags: --check --dump-model --skip-attribute-checks
+module 0x42::m {
+    friend 0x42::n;
+
+    struct Coin<phantom T> has store, drop {
+        value: u64
+    }
+
+    #[resource_group_member(group = 0x42::m::Group)]
+    struct Pair<K: copy + drop, V> has key {
+        key: K,
+        values: vector<V>,
+    }
+
+    struct Group {}
+
+    public fun mint<T>(value: u64): Coin<T> {
+        Coin { value }
+    }
+
+    public(friend) fun first<K: copy + drop, V: store>(addr: address): K acquires Pair {
+        let pair = borrow_global<Pair<K, V>>(addr);
+        let k = pair.key;
+        k
+    }
+
+    #[view]
+    public fun total<T>(coins: &vector<Coin<T>>): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < std::vector::length(coins)) {
+            sum = sum + std::vector::borrow(coins, i).value;
+            i = i + 1
+        };
+        sum
+    }
+
+    native fun hash<T>(x: &T): vector<u8>;
+}
+
+module 0x42::n {
+    public entry fun run() {}
+}
@@ -0,0 +1,6 @@
+// flags: --Werror=W02050 --Wno-unused_variable
+module 0x42::m {
+    public fun f(x: u64) {}
+
+    public fun g(y: u64) {}
+}
@@ -0,0 +1,26 @@
+// flags: --emit-error-map error_map.json
+module 0x42::m {
+    use std::error;
+
+    /// The amount is zero.
+    const E_ZERO_AMOUNT: u64 = 1;
+    /// The caller is not the owner.
+    const E_NOT_OWNER: u64 = 2;
+    const MAX_AMOUNT: u64 = 100;
+
+    public fun check_amount(amount: u64) {
+        assert!(amount > 0, error::invalid_argument(E_ZERO_AMOUNT));
+    }
+
+    public fun check_owner(owner: address, caller: address) {
+        if (owner != caller) abort error::permission_denied(E_NOT_OWNER)
+    }
+
+    public fun check_limit(amount: u64) {
+        assert!(amount <= MAX_AMOUNT, E_NOT_OWNER);
+    }
+
+    public fun fail(code: u64) {
+        abort code
+    }
+}
@@ -0,0 +1,7 @@
+// flags: --dump-bytecode-diff --experiments copy-propagation,dead-store-elimination --experiments dead-store-elimination=off
+module 0x42::m {
+    public fun f(x: u64): u64 {
+        let y = x;
+        y
+    }
+}
@@ -0,0 +1,4 @@
+// flags: --explain E02001
+module 0x42::m {
+    fun f() {}
+}
@@ -0,0 +1,26 @@
+#[lint::allow(unused_variable)]
+module 0x42::allowed_in_module {
+    public fun f(x: u64) {}
+
+    public fun g() {
+        let y = 1;
+    }
+}
+
+module 0x42::allowed_in_function {
+    #[lint::allow(unused_variable)]
+    public fun f(x: u64) {}
+
+    public fun g(x: u64) {}
+}
+
+module 0x42::unknown_lints {
+    #[lint::allow(unused_variable, dead_code)]
+    public fun f(x: u64) {}
+
+    #[lint::allow(unused_variable(x))]
+    public fun g() {}
+
+    #[lint::allow]
+    public fun h() {}
+}
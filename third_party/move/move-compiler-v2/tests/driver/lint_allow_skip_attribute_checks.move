@@ -0,0 +1,7 @@
+// flags: --skip-attribute-checks
+module 0x42::m {
+    #[lint::allow(unused_variable, dead_code)]
+    public fun f(x: u64) {}
+
+    public fun g(x: u64) {}
+}
@@ -0,0 +1,5 @@
+// flags: --warnings-are-errors
+module 0x42::m {
+    #[lint::allow(unused_variable)]
+    public fun f(x: u64) {}
+}
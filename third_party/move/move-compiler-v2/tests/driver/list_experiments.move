@@ -0,0 +1,4 @@
+// flags: --list-experiments
+module 0x42::m {
+    fun f() {}
+}
@@ -0,0 +1,43 @@
+// flags: --warn-recursion
+module 0x42::direct {
+    public fun fact(n: u64): u64 {
+        if (n == 0) 1 else n * fact(n - 1)
+    }
+
+    // Not recursive, although it calls a recursive function.
+    public fun caller(): u64 {
+        fact(3)
+    }
+}
+
+module 0x42::three {
+    // The cycle is reported starting with the function with the least name.
+    public fun c(n: u64): u64 {
+        a(n) + 1
+    }
+
+    public fun a(n: u64): u64 {
+        if (n == 0) 0 else b(n - 1)
+    }
+
+    public fun b(n: u64): u64 {
+        c(n)
+    }
+}
+
+module 0x42::generic {
+    public fun depth<T>(v: &vector<T>, n: u64): u64 {
+        if (n == 0) 0 else count<T>(v, n - 1)
+    }
+
+    public fun count<U>(v: &vector<U>, n: u64): u64 {
+        depth<U>(v, n) + 1
+    }
+}
+
+module 0x42::user {
+    // Calls into cycles of other modules, which are reported once, with those modules.
+    public fun g(): u64 {
+        0x42::three::a(3) + 0x42::direct::caller()
+    }
+}
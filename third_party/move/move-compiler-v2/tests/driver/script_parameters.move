@@ -0,0 +1,22 @@
+module 0x42::m {
+    struct S has drop { x: u64 }
+}
+
+script {
+    use std::option::Option;
+    use std::string::String;
+    use 0x42::m::S;
+
+    fun main<T: drop>(
+        _s1: &signer,
+        _x: u64,
+        _s2: signer,
+        _r: &u64,
+        _t: T,
+        _v: vector<S>,
+        _o: Option<vector<u8>>,
+        _str: String,
+        _bad: Option<S>,
+    ) {
+    }
+}
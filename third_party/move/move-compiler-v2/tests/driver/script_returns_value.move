@@ -0,0 +1,13 @@
+module 0x42::m {
+    public fun value(): u64 {
+        42
+    }
+}
+
+script {
+    use 0x42::m;
+
+    fun main(): u64 {
+        m::value()
+    }
+}
@@ -0,0 +1,6 @@
+// flags: --Wno-unused_variable
+module 0x42::m {
+    use 0x42::m;
+
+    public fun f(x: u64) {}
+}
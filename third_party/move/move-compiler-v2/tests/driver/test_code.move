@@ -0,0 +1,16 @@
+module 0x42::m {
+    public fun f() {}
+
+    #[test_only]
+    fun helper() {}
+
+    #[test]
+    fun t() {
+        helper()
+    }
+}
+
+#[test_only]
+module 0x42::test_helpers {
+    public fun h() {}
+}
@@ -0,0 +1,17 @@
+// flags: --compile-test-code
+module 0x42::m {
+    public fun f() {}
+
+    #[test_only]
+    fun helper() {}
+
+    #[test]
+    fun t() {
+        helper()
+    }
+}
+
+#[test_only]
+module 0x42::test_helpers {
+    public fun h() {}
+}
@@ -0,0 +1,13 @@
+address 0x2 {
+
+module X {}
+
+module M {
+    use 0x2::X;
+    fun foo(): u64 {
+        let x = 0x2::X;
+        true
+    }
+}
+
+}
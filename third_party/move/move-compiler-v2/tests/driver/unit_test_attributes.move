@@ -0,0 +1,55 @@
+// flags: --compile-test-code
+module 0x42::m {
+    const E: u64 = 1;
+    const NOT_U64: bool = true;
+
+    #[expected_failure]
+    fun not_a_test() {}
+
+    #[test]
+    #[test_only]
+    fun test_and_test_only() {}
+
+    #[test]
+    fun missing_argument(s: signer) {}
+
+    #[test(s = @0x1, t = @0x2)]
+    fun unknown_argument(s: signer) {}
+
+    #[test(s = true)]
+    fun not_an_address(s: signer) {}
+
+    #[test(x = @0x1)]
+    fun not_a_signer(x: u64) {}
+
+    #[test]
+    fun generic<T>() {}
+
+    #[test]
+    #[expected_failure(abort_code = 1, arithmetic_error)]
+    fun two_failure_kinds() {}
+
+    #[test]
+    #[expected_failure(abort_code = 1)]
+    fun any_module() {}
+
+    #[test]
+    #[expected_failure(abort_code = 0x42::m::NOT_U64)]
+    fun non_u64_constant() {}
+
+    #[test]
+    #[expected_failure(abort_code = 0x42::m::UNKNOWN)]
+    fun unknown_constant() {}
+
+    #[test]
+    #[expected_failure(out_of_gas)]
+    fun missing_location() {}
+
+    #[test]
+    #[expected_failure(vector_error = 1, location = Self)]
+    fun assigned_kind() {}
+
+    #[test]
+    #[expected_failure(abort_code = 0x42::m::E, location = Self, extra = 1)]
+    fun extra_argument() {}
+}
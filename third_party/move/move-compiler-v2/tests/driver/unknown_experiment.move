@@ -0,0 +1,4 @@
+// flags: --experiments copy-propagaton,dead-store-elimination=maybe
+module 0x42::m {
+    fun f() {}
+}
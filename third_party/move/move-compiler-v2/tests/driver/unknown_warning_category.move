@@ -0,0 +1,4 @@
+// flags: --Wno-dead_code --Werror=E02001
+module 0x42::m {
+    fun f() {}
+}
@@ -0,0 +1,12 @@
+module 0x42::m {
+    public fun warns(x: u64): u64 {
+        abort 0;
+        x
+    }
+
+    #[lint::allow(unreachable_code)]
+    public fun allowed(x: u64): u64 {
+        abort 0;
+        x
+    }
+}
@@ -0,0 +1,4 @@
+// flags: --bytecode-version 99
+module 0x42::m {
+    fun f() {}
+}
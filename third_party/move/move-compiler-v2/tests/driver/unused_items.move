@@ -0,0 +1,65 @@
+// flags: --compile-test-code
+module 0x42::m {
+    friend 0x42::n;
+
+    struct Used has drop { x: u64 }
+    struct UsedInField has drop { u: Used }
+    struct UsedInSpec has drop { y: u64 }
+    struct Unused { z: u64 }
+
+    const USED: u64 = 1;
+    const USED_IN_SPEC: u64 = 2;
+    const USED_IN_ATTRIBUTE: u64 = 3;
+    const UNUSED: u64 = 4;
+
+    public(friend) fun called_by_friend(): u64 {
+        helper() + USED
+    }
+
+    fun helper(): u64 {
+        let u = UsedInField { u: Used { x: 1 } };
+        u.u.x
+    }
+
+    fun used_in_spec(x: u64): u64 {
+        x
+    }
+    spec called_by_friend {
+        ensures result == used_in_spec(result);
+        ensures USED_IN_SPEC > 0;
+        ensures exists<UsedInSpec>(@0x42) ==> true;
+    }
+
+    fun never_called(): u64 {
+        calls_itself(1)
+    }
+
+    fun calls_itself(x: u64): u64 {
+        if (x == 0) 0 else calls_itself(x - 1)
+    }
+
+    entry fun entry_point() {}
+
+    fun init_module(_account: &signer) {}
+
+    #[test_only]
+    fun test_helper() {}
+
+    #[test]
+    #[expected_failure(abort_code = USED_IN_ATTRIBUTE)]
+    fun test_abort() {
+        abort 3
+    }
+}
+
+#[lint::allow(unused_item)]
+module 0x42::n {
+    struct Unused {}
+    const UNUSED: u64 = 0;
+
+    public fun call(): u64 {
+        0x42::m::called_by_friend()
+    }
+
+    fun unused() {}
+}
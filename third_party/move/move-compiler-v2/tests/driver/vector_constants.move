@@ -0,0 +1,6 @@
+// flags: --dump-disassembly --experiments constant-folding,dead-store-elimination
+module 0x42::m {
+    public fun numbers(): vector<u64> { vector[1, 2, 3] }
+
+    public fun nested(): vector<vector<u8>> { vector[b"ab", vector[1, 2]] }
+}
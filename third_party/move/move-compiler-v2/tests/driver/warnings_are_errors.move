@@ -0,0 +1,8 @@
+// flags: --warnings-are-errors
+module 0x42::M {
+    use 0x42::M;
+
+    fun foo(): u64 {
+        1
+    }
+}
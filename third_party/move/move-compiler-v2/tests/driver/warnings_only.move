@@ -0,0 +1,7 @@
+module 0x42::M {
+    use 0x42::M;
+
+    public fun foo(): u64 {
+        1
+    }
+}
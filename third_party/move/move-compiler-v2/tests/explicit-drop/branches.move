@@ -0,0 +1,30 @@
+module 0x42::branches {
+    struct S has drop { f: u64, g: u64 }
+
+    fun ref_used_in_one_branch(s: &mut S, c: bool): u64 {
+        let r = &mut s.f;
+        if (c) {
+            *r = 1;
+        };
+        s.f
+    }
+
+    fun multiple_returns(x: u64): u64 {
+        if (x == 0) {
+            return 1
+        };
+        if (x == 1) {
+            return 2
+        };
+        x + 1
+    }
+
+    fun value_used_in_one_branch(c: bool): u64 {
+        let x = 1;
+        if (c) {
+            x
+        } else {
+            2
+        }
+    }
+}
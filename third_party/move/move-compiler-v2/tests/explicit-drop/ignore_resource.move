@@ -0,0 +1,13 @@
+module 0x42::ignore_resource {
+    struct R { f: u64 }
+
+    fun make_r(): R { R { f: 1 } }
+
+    fun ignore() {
+        make_r();
+    }
+
+    fun ignore_wildcard() {
+        let _ = make_r();
+    }
+}
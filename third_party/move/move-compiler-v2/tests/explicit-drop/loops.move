@@ -0,0 +1,21 @@
+module 0x42::loops {
+    fun step(i: u64): u64 { i + 1 }
+
+    fun discard_each_iteration(n: u64) {
+        let i = 0;
+        while (i < n) {
+            step(i);
+            i = i + 1;
+        }
+    }
+
+    fun borrow_each_iteration(n: u64): u64 {
+        let i = 0;
+        while (i < n) {
+            let r = &mut i;
+            if (*r > 10) break;
+            *r = *r + 1;
+        };
+        i
+    }
+}
@@ -0,0 +1,19 @@
+module 0x42::side_effects {
+    fun value(): u64 { 42 }
+
+    fun pair(): (u64, bool) { (1, true) }
+
+    fun ignore_result() {
+        value();
+    }
+
+    fun ignore_tuple() {
+        let (_, _) = pair();
+    }
+
+    fun ignore_in_sequence(): u64 {
+        value();
+        let _ = value();
+        value()
+    }
+}
@@ -0,0 +1,24 @@
+module 0x42::resources {
+    struct R has key { f: u64 }
+
+    struct T has key { g: u64 }
+
+    fun get_mut(a: address): &mut R acquires R {
+        borrow_global_mut<R>(a)
+    }
+
+    fun update(a: address, x: u64) acquires R {
+        let r = get_mut(a);
+        r.f = x;
+    }
+
+    fun update_both(a: address, x: u64): u64 acquires R, T {
+        update(a, x);
+        let T { g } = move_from<T>(a);
+        g
+    }
+
+    fun no_acquires(a: address): bool {
+        exists<R>(a) && exists<T>(a)
+    }
+}
@@ -0,0 +1,17 @@
+// flags: --bytecode-version 5
+module 0x42::versioned {
+    struct R has key { x: u64 }
+
+    fun small(x: u64): u16 {
+        (x as u16)
+    }
+
+    fun large(): u64 {
+        let y = 1u256;
+        (y as u64)
+    }
+
+    fun read(addr: address): u64 reads R {
+        borrow_global<R>(addr).x
+    }
+}
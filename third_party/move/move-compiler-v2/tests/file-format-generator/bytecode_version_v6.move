@@ -0,0 +1,12 @@
+// flags: --bytecode-version 6
+module 0x42::versioned {
+    struct R has key { x: u64 }
+
+    fun read(addr: address): u64 acquires R {
+        borrow_global<R>(addr).x
+    }
+
+    fun narrow(x: u64): u8 {
+        (x as u8)
+    }
+}
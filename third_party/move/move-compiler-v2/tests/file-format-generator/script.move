@@ -0,0 +1,24 @@
+module 0x42::m {
+    struct Counter has key { value: u64 }
+
+    public fun register(account: &signer, value: u64) {
+        move_to(account, Counter { value })
+    }
+
+    public fun is_empty(values: vector<u64>): bool {
+        values == vector[]
+    }
+
+    public fun ignore<T>(_x: u64) {}
+}
+
+script {
+    use 0x42::m;
+
+    fun main<T>(account: signer, flag: bool, values: vector<u64>) {
+        if (flag && !m::is_empty(values)) {
+            m::register(&account, 1)
+        };
+        m::ignore<T>(1);
+    }
+}
@@ -0,0 +1,39 @@
+module 0x42::m {
+    struct S has drop {
+        x: u64
+    }
+
+    fun add(x: u64, y: u64): u64 {
+        x + y
+    }
+
+    fun get(s: &S): u64 {
+        s.x
+    }
+
+    fun id<T>(x: T): T {
+        x
+    }
+
+    fun max(x: u64, y: u64): u64 {
+        if (x > y) return x;
+        y
+    }
+
+    #[inline]
+    public fun sum(n: u64): u64 {
+        let i = 0;
+        let s = 0;
+        while (i < n) {
+            s = add(s, i);
+            i = i + 1
+        };
+        s
+    }
+
+    public fun calls(s: S, n: u64): u64 {
+        let a = add(get(&s), id(n));
+        let b = id<S>(s);
+        max(a, sum(b.x))
+    }
+}
@@ -0,0 +1,19 @@
+module 0x42::m {
+    #[inline]
+    fun fact(n: u64): u64 {
+        if (n == 0) 1 else n * fact(n - 1)
+    }
+
+    #[inline]
+    fun even(n: u64): bool {
+        if (n == 0) true else odd(n - 1)
+    }
+
+    fun odd(n: u64): bool {
+        if (n == 0) false else even(n - 1)
+    }
+
+    public fun calls(n: u64): bool {
+        fact(n) > 0 && even(n)
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for emitting the ABIs of modules as JSON. The emitted ABI is compared with a golden
+//! file, which can be updated by running the test with `UPBL=1`.
+
+use crate::common;
+use move_compiler_v2::Options;
+use move_prover_test_utils::baseline_test::verify_or_update_baseline;
+use std::{fs, path::Path};
+
+/// A module with generic functions and structs, references, a view function, and a member of
+/// a resource group.
+const SOURCE: &str = "module 0x42::registry {
+    use std::string::String;
+
+    friend 0x42::admin;
+
+    #[resource_group(scope = global)]
+    struct Group {}
+
+    #[resource_group_member(group = 0x42::registry::Group)]
+    struct Entry<phantom K: store, V: store> has key {
+        name: String,
+        values: vector<V>,
+    }
+
+    struct Ref has copy, drop, store {
+        owner: address,
+    }
+
+    public fun create<K: store, V: store>(owner: &signer, name: String, values: vector<V>) {
+        move_to(owner, Entry<K, V> { name, values })
+    }
+
+    public fun values_mut<K: store, V: store>(entry: &mut Entry<K, V>): &mut vector<V> {
+        &mut entry.values
+    }
+
+    public(friend) fun swap(r: Ref, owner: address): (Ref, address) {
+        (Ref { owner }, r.owner)
+    }
+
+    entry fun register(owner: &signer, name: String) {
+        create<u64, u8>(owner, name, vector[])
+    }
+
+    #[view]
+    public fun exists_at<K: store, V: store>(owner: address): bool {
+        exists<Entry<K, V>>(owner)
+    }
+
+    fun helper(): u64 {
+        1
+    }
+}
+";
+
+#[test]
+fn module_abi() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let options = Options {
+        source_buffers: vec![("registry.move".to_owned(), SOURCE.to_owned())],
+        dependencies: vec!["../move-stdlib/sources".to_owned()],
+        named_address_mapping: vec!["std=0x1".to_owned()],
+        known_attributes: ["resource_group", "resource_group_member", "view"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect(),
+        emit_abi: Some(dir.path().to_path_buf()),
+        ..Options::default()
+    };
+    common::compile(options);
+    let abi = fs::read_to_string(dir.path().join("registry.json")).expect("ABI written");
+    verify_or_update_baseline(Path::new("tests/abi/registry.json"), &abi)
+        .expect("ABI matches golden file");
+}
@@ -0,0 +1,97 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for `run_move_compiler_for_analysis`, which generates code for the functions without
+//! errors even if other functions have errors.
+
+use move_compiler_v2::{
+    analysis::{run_move_compiler_for_analysis, AnalysisOutput},
+    diagnostics::DiagnosticSeverity,
+    Options,
+};
+
+const SOURCE: &str = "module 0x42::m {
+    public fun valid(x: u64): u64 {
+        helper(x) + 1
+    }
+
+    fun helper(x: u64): u64 {
+        x * 2
+    }
+
+    public fun mistyped(): u64 {
+        true
+    }
+
+    public fun unbound(): u64 {
+        y
+    }
+}
+";
+
+fn analyze(source: &str) -> AnalysisOutput {
+    run_move_compiler_for_analysis(Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        ..Options::default()
+    })
+}
+
+/// Returns the names of the functions of the output, with whether code was generated for them.
+fn functions(output: &AnalysisOutput) -> Vec<(String, bool)> {
+    let module = output
+        .env
+        .get_modules()
+        .find(|m| m.is_target())
+        .expect("target module");
+    module
+        .get_functions()
+        .map(|f| {
+            let id = f.get_qualified_id();
+            assert_eq!(output.has_code(id), !output.missing.contains(&id));
+            (f.get_name_str(), output.has_code(id))
+        })
+        .collect()
+}
+
+#[test]
+fn functions_without_errors_get_code() {
+    let output = analyze(SOURCE);
+    let mut functions = functions(&output);
+    functions.sort();
+    assert_eq!(functions, vec![
+        ("helper".to_owned(), true),
+        ("mistyped".to_owned(), false),
+        ("unbound".to_owned(), false),
+        ("valid".to_owned(), true),
+    ]);
+}
+
+#[test]
+fn errors_of_all_functions_are_returned() {
+    let output = analyze(SOURCE);
+    let errors = output
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == DiagnosticSeverity::Error)
+        .map(|d| (d.location.as_ref().map(|l| l.start_line), d.message.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors
+        .iter()
+        .any(|(line, msg)| *line == Some(11) && msg.contains("expected `u64` but found `bool`")));
+    assert!(errors
+        .iter()
+        .any(|(line, msg)| *line == Some(15) && msg.contains("undeclared")));
+}
+
+#[test]
+fn checker_failures_are_diagnostics() {
+    let output = run_move_compiler_for_analysis(Options {
+        sources: vec!["does/not/exist.move".to_owned()],
+        ..Options::default()
+    });
+    assert!(output
+        .diagnostics
+        .iter()
+        .any(|d| d.severity == DiagnosticSeverity::Error));
+}
@@ -0,0 +1,98 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for writing the compiled units into the output directory.
+
+use crate::common;
+use codespan_reporting::term::termcolor::Buffer;
+use move_binary_format::{
+    file_format::{CompiledModule, CompiledScript},
+    file_format_common::VERSION_DEFAULT,
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_compiler_v2::{run_move_compiler, Options};
+use std::{fs, path::Path};
+
+const SOURCES: [(&str, &str); 2] = [
+    (
+        "counter.move",
+        "module 0x42::counter {
+    struct Counter has key { value: u64 }
+
+    public fun increment(c: &mut Counter) {
+        c.value = c.value + 1
+    }
+}
+",
+    ),
+    (
+        "main.move",
+        "script {
+    fun main(x: u64) {
+        assert!(x > 0, 1)
+    }
+}
+",
+    ),
+];
+
+fn compile(dir: &Path, emit_abi: bool) {
+    let options = Options {
+        source_buffers: common::source_buffers(&SOURCES),
+        output_dir: Some(dir.to_path_buf()),
+        emit_abi: emit_abi.then(|| dir.join("abi")),
+        bytecode_version: Some(VERSION_DEFAULT),
+        ..Options::default()
+    };
+    common::compile(options);
+}
+
+#[test]
+fn units_and_source_maps_are_written() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    compile(dir.path(), false);
+    let module_dir = dir.path().join("0x42");
+    let module = CompiledModule::deserialize_with_config(
+        &fs::read(module_dir.join("counter.mv")).expect("module written"),
+        &common::deserializer_config(),
+    )
+    .expect("module deserializes");
+    assert_eq!(module.self_id().name().as_str(), "counter");
+    let source_map: SourceMap =
+        bcs::from_bytes(&fs::read(module_dir.join("counter.mvsm")).expect("source map written"))
+            .expect("source map deserializes");
+    assert_eq!(
+        source_map.module_name_opt.map(|(_, name)| name.to_string()),
+        Some("counter".to_owned())
+    );
+    assert!(!module_dir.join("counter.json").exists());
+    let script_dir = dir.path().join("scripts");
+    CompiledScript::deserialize_with_config(
+        &fs::read(script_dir.join("main.mv")).expect("script written"),
+        &common::deserializer_config(),
+    )
+    .expect("script deserializes");
+    assert!(script_dir.join("main.mvsm").exists());
+}
+
+#[test]
+fn abis_are_written_next_to_modules() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    compile(dir.path(), true);
+    let abi = fs::read_to_string(dir.path().join("0x42").join("counter.json")).expect("ABI");
+    assert!(abi.contains("\"increment\""), "{}", abi);
+}
+
+#[test]
+fn write_failures_mention_the_path() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    // A file where the directory of the module should be created.
+    fs::write(dir.path().join("0x42"), "").unwrap();
+    let options = Options {
+        source_buffers: vec![(SOURCES[0].0.to_owned(), SOURCES[0].1.to_owned())],
+        output_dir: Some(dir.path().to_path_buf()),
+        ..Options::default()
+    };
+    let err = run_move_compiler(&mut Buffer::no_color(), options).expect_err("compilation fails");
+    assert!(err.to_string().contains("counter.mv"), "{}", err);
+}
@@ -0,0 +1,143 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for including and excluding items with `cfg_attr` attributes, depending on the flags
+//! of the build configuration.
+
+use crate::common;
+use move_compiler_v2::{
+    diagnostics::CompilerDiagnostic, run_move_compiler_collecting, CompilerOutput, Options,
+};
+use std::collections::BTreeMap;
+
+const CONFIG: &str = "module 0x42::config {
+    #[cfg_attr(testnet)]
+    friend 0x42::user;
+
+    #[cfg_attr(testnet)]
+    public fun faucet(): u64 { 1000 }
+
+    #[cfg_attr(not(testnet))]
+    public fun fee(): u64 { 10 }
+
+    public fun always(): u64 { 1 }
+
+    public(friend) fun mint(): u64 { 2 }
+}
+";
+
+fn compile(
+    sources: &[&str],
+    config: &[(&str, bool)],
+) -> Result<CompilerOutput, Vec<CompilerDiagnostic>> {
+    let options = Options {
+        source_buffers: sources
+            .iter()
+            .enumerate()
+            .map(|(idx, source)| (format!("m{}.move", idx), source.to_string()))
+            .collect(),
+        build_config: config
+            .iter()
+            .map(|(flag, value)| (flag.to_string(), *value))
+            .collect(),
+        ..Options::default()
+    };
+    run_move_compiler_collecting(options)
+}
+
+/// Returns the names of the functions defined by the first compiled module.
+fn defined_functions(output: &CompilerOutput) -> Vec<String> {
+    common::defined_functions(&common::compiled_module(&output.annotated_units[0]))
+}
+
+#[test]
+fn functions_are_included_by_flags() {
+    let user = "module 0x42::user {}\n";
+    let output = compile(&[CONFIG, user], &[("testnet", true)]).expect("compiles");
+    assert_eq!(defined_functions(&output), vec!["faucet", "always", "mint"]);
+    let output = compile(&[CONFIG, user], &[("testnet", false)]).expect("compiles");
+    assert_eq!(defined_functions(&output), vec!["fee", "always", "mint"]);
+}
+
+#[test]
+fn excluded_friend_declarations_do_not_grant_access() {
+    let user = "module 0x42::user {
+    public fun f(): u64 { 0x42::config::mint() }
+}
+";
+    assert!(compile(&[CONFIG, user], &[("testnet", true)]).is_ok());
+    let errors = compile(&[CONFIG, user], &[("testnet", false)])
+        .err()
+        .expect("compilation fails");
+    assert!(
+        errors.iter().any(|d| d.message.contains("mint")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn calling_an_excluded_function_is_explained() {
+    let user = "module 0x42::user {
+    public fun f(): u64 { 0x42::config::faucet() }
+}
+";
+    let errors = compile(&[CONFIG, user], &[("testnet", false)])
+        .err()
+        .expect("compilation fails");
+    let error = errors
+        .iter()
+        .find(|d| d.message.starts_with("no function named"))
+        .expect("error about the call");
+    assert_eq!(error.notes, vec![
+        "`faucet` is excluded from the build, since flag `testnet` is set to false".to_owned()
+    ]);
+}
+
+#[test]
+fn unknown_flags_and_malformed_conditions_are_errors() {
+    let source = "module 0x42::m {
+    #[cfg_attr(tesnet)]
+    public fun f(): u64 { 1 }
+
+    #[cfg_attr(testnet = true)]
+    public fun g(): u64 { 2 }
+}
+";
+    let errors = compile(&[source], &[("testnet", true)])
+        .err()
+        .expect("compilation fails");
+    let codes = errors
+        .iter()
+        .map(|d| (d.code.clone().unwrap_or_default(), d.message.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(codes, vec![
+        (
+            "E00012".to_owned(),
+            "flag `tesnet` is not set in the build configuration (set flags are `testnet`)"
+                .to_owned()
+        ),
+        (
+            "E00013".to_owned(),
+            "expected a flag, as in `testnet`, or its negation, as in `not(testnet)`".to_owned()
+        ),
+    ]);
+}
+
+#[test]
+fn build_config_is_parsed_from_the_command_line() {
+    let options = Options::try_parse_command_line([
+        "move-compiler-v2",
+        "--build-config",
+        "testnet=true,fast=false",
+    ])
+    .expect("options");
+    assert_eq!(
+        options.build_config,
+        BTreeMap::from([("fast".to_owned(), false), ("testnet".to_owned(), true)])
+    );
+    assert!(Options::default().build_config.is_empty());
+    assert!(
+        Options::try_parse_command_line(["move-compiler-v2", "--build-config", "testnet"]).is_err()
+    );
+}
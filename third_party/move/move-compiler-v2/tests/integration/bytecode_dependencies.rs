@@ -0,0 +1,138 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for compiling against dependencies which are only available as bytecode.
+
+use crate::common;
+use move_binary_format::{access::ModuleAccess, file_format::CompiledModule};
+use move_compiler::compiled_unit::CompiledUnit;
+use move_compiler_v2::Options;
+use std::fs;
+use tempfile::TempDir;
+
+const DEP: &str = "module 0x42::dep {
+    friend 0x42::friendly;
+    struct Box<T> has copy, drop { value: T }
+    public fun make<T: copy + drop>(value: T): Box<T> { Box { value } }
+    public(friend) fun secret(): u64 { 42 }
+}
+";
+
+/// Compiles the dependency and writes its bytecode into a temporary directory.
+fn compile_dep() -> TempDir {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let options = Options {
+        source_buffers: vec![("dep.move".to_owned(), DEP.to_owned())],
+        bytecode_version: Some(6),
+        ..Options::default()
+    };
+    let (_, units) = common::compile(options);
+    for bytes in common::serialize_units(units) {
+        fs::write(dir.path().join("dep.mv"), bytes).expect("write bytecode");
+    }
+    dir
+}
+
+/// Compiles the source against the bytecode in `dir`, returning the compiled module, or the
+/// diagnostics if compilation fails.
+fn compile(dir: &TempDir, source: &str) -> Result<CompiledModule, String> {
+    let options = Options {
+        source_buffers: vec![("main.move".to_owned(), source.to_owned())],
+        bytecode_dependencies: vec![dir.path().to_string_lossy().to_string()],
+        bytecode_version: Some(6),
+        ..Options::default()
+    };
+    let (_, mut units) = common::try_compile(options)?;
+    match units.pop().map(|unit| unit.into_compiled_unit()) {
+        Some(CompiledUnit::Module(module)) => Ok(module.module),
+        _ => panic!("expected a module"),
+    }
+}
+
+#[test]
+fn call_generic_function() {
+    let dir = compile_dep();
+    let module = compile(
+        &dir,
+        "module 0x42::user {
+            use 0x42::dep;
+            public fun f(): dep::Box<u64> { dep::make(1) }
+        }",
+    )
+    .expect("compiles");
+    let handle = module
+        .function_handles()
+        .iter()
+        .find(|h| module.identifier_at(h.name).as_str() == "make")
+        .expect("handle of `make`");
+    assert_eq!(
+        module
+            .module_id_for_handle(module.module_handle_at(handle.module))
+            .short_str_lossless(),
+        "0x42::dep"
+    );
+    assert_eq!(handle.type_parameters.len(), 1);
+    assert_eq!(module.function_instantiations().len(), 1);
+}
+
+#[test]
+fn call_friend_function() {
+    let dir = compile_dep();
+    compile(
+        &dir,
+        "module 0x42::friendly {
+            use 0x42::dep;
+            public fun f(): u64 { dep::secret() }
+        }",
+    )
+    .expect("compiles");
+    let diags = compile(
+        &dir,
+        "module 0x42::stranger {
+            use 0x42::dep;
+            public fun f(): u64 { dep::secret() }
+        }",
+    )
+    .expect_err("friend function is not visible");
+    assert!(
+        diags.contains("secret"),
+        "unexpected diagnostics: {}",
+        diags
+    );
+}
+
+#[test]
+fn type_errors_in_calls_are_reported() {
+    let dir = compile_dep();
+    let diags = compile(
+        &dir,
+        "module 0x42::user {
+            use 0x42::dep;
+            public fun f(): dep::Box<u64> { dep::make(true) }
+        }",
+    )
+    .expect_err("call does not type check");
+    assert!(
+        diags.contains("main.move"),
+        "unexpected diagnostics: {}",
+        diags
+    );
+}
+
+#[test]
+fn friend_cycle_through_dependency_is_reported() {
+    let dir = compile_dep();
+    let diags = compile(
+        &dir,
+        "module 0x42::friendly {
+            friend 0x42::dep;
+            public fun f(): u64 { 1 }
+        }",
+    )
+    .expect_err("friend declarations are cyclic");
+    assert!(
+        diags.contains("E02005") && diags.contains("friend 0x42::dep;"),
+        "unexpected diagnostics: {}",
+        diags
+    );
+}
@@ -0,0 +1,155 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the textual format of stackless bytecode. Printing the bytecode of the sources in
+//! the test corpus, parsing it back and printing again must reproduce the text.
+
+use crate::common;
+use codespan_reporting::term::termcolor::Buffer;
+use move_compiler_v2::{
+    bytecode_text::{parse_targets, print_targets},
+    pipeline::{
+        dead_store_elimination::DeadStoreElimination,
+        livevar_analysis_processor::LiveVarAnalysisProcessor,
+    },
+    run_move_compiler_with_targets, Options,
+};
+use move_stackless_bytecode::function_target_pipeline::FunctionTargetPipeline;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+fn collect_sources(dir: &Path, sources: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).expect("directory") {
+        let path = entry.expect("entry").path();
+        if path.is_dir() {
+            collect_sources(&path, sources)
+        } else if path.extension().is_some_and(|ext| ext == "move") {
+            sources.push(path)
+        }
+    }
+}
+
+#[test]
+fn corpus_round_trips() {
+    let mut sources = vec![];
+    collect_sources(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests"),
+        &mut sources,
+    );
+    sources.sort();
+    let stdlib = Path::new(env!("CARGO_MANIFEST_DIR")).join("../move-stdlib/sources");
+    let mut compiled = 0;
+    for path in sources {
+        let source = fs::read_to_string(&path).expect("source");
+        // Only a few sources use the standard library, so only those pay for compiling it.
+        let dependencies = if source.contains("std::") {
+            vec![stdlib.to_string_lossy().to_string()]
+        } else {
+            vec![]
+        };
+        let options = Options {
+            sources: vec![path.to_string_lossy().to_string()],
+            dependencies,
+            named_address_mapping: vec!["std=0x1".to_string()],
+            ..Options::default()
+        };
+        // Sources of tests for errors are not part of the corpus.
+        let Ok(output) = run_move_compiler_with_targets(&mut Buffer::no_color(), options) else {
+            continue;
+        };
+        compiled += 1;
+        let text = print_targets(&output.env, &output.targets, false);
+        let parsed = parse_targets(&output.env, &text)
+            .unwrap_or_else(|e| panic!("{}: {}\n{}", path.display(), e, text));
+        assert_eq!(
+            print_targets(&output.env, &parsed, false),
+            text,
+            "{}",
+            path.display()
+        );
+        // Annotations are comments, which are ignored by the parser.
+        let annotated = print_targets(&output.env, &output.targets, true);
+        let parsed = parse_targets(&output.env, &annotated)
+            .unwrap_or_else(|e| panic!("{}: {}\n{}", path.display(), e, annotated));
+        assert_eq!(
+            print_targets(&output.env, &parsed, false),
+            text,
+            "{}",
+            path.display()
+        );
+    }
+    assert!(compiled > 100, "only {} sources compiled", compiled);
+}
+
+#[test]
+fn processors_run_on_parsed_bytecode() {
+    let output = common::compile_with_targets(Options {
+        source_buffers: vec![(
+            "m.move".to_owned(),
+            "module 0x42::m { fun f(x: u64): u64 { x } }".to_owned(),
+        )],
+        check_only: true,
+        ..Options::default()
+    });
+    let mut targets = parse_targets(
+        &output.env,
+        "
+[variant baseline]
+fun m::f($t0: u64): u64 {
+     var $t1: u64
+     var $t2: u64
+  0: $t1 := 1
+  1: $t2 := +($t0, $t0)
+  2: return $t2
+}
+",
+    )
+    .expect("parses");
+    let mut pipeline = FunctionTargetPipeline::default();
+    pipeline.add_processor(Box::new(LiveVarAnalysisProcessor()));
+    pipeline.add_processor(Box::new(DeadStoreElimination()));
+    pipeline.run(&output.env, &mut targets);
+    assert_eq!(
+        print_targets(&output.env, &targets, false),
+        "
+[variant baseline]
+fun m::f($t0: u64): u64 {
+     var $t1: u64
+     var $t2: u64
+  0: $t2 := +($t0, $t0)
+  1: return $t2
+}
+
+"
+    );
+}
+
+#[test]
+fn errors_name_the_line() {
+    let output = common::compile_with_targets(Options {
+        source_buffers: vec![(
+            "m.move".to_owned(),
+            "module 0x42::m { fun f(x: u64): u64 { x } }".to_owned(),
+        )],
+        check_only: true,
+        ..Options::default()
+    });
+    let error = |text: &str| match parse_targets(&output.env, text) {
+        Ok(_) => panic!("expected error for:\n{}", text),
+        Err(e) => e.to_string(),
+    };
+    assert_eq!(
+        error("[variant baseline]\nfun m::g($t0: u64): u64 {\n}"),
+        "line 2: unknown function at `m::g($t0: u64): u64 {`"
+    );
+    assert_eq!(
+        error("[variant baseline]\nfun m::f($t0: u64): u64 {\n  0: return $t1\n}"),
+        "line 3: undeclared temporary `$t1`"
+    );
+    assert_eq!(
+        error("[variant baseline]\nfun m::f($t0: u64): u64 {\n  0: goto 1\n}"),
+        "line 3: no label at offset 1"
+    );
+}
@@ -0,0 +1,117 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the simplification of the control-flow graph, running loops whose blocks are
+//! threaded, merged, and moved, and comparing the size of the generated code.
+
+use crate::common;
+use move_binary_format::access::ModuleAccess;
+use move_compiler::compiled_unit::AnnotatedCompiledUnit;
+use move_compiler_v2::{Experiment, Options};
+use move_model::model::GlobalEnv;
+
+const SOURCE: &str = "module 0x42::loops {
+    fun count(n: u64): u64 {
+        let i = 0;
+        let sum = 0;
+        while (i < n) {
+            sum = sum + i;
+            i = i + 1;
+        };
+        sum
+    }
+
+    fun find_limit(x: u64): u64 {
+        loop {
+            if (x > 10) {
+                x = x - 10;
+                break
+            };
+            x = x + 1;
+        };
+        x
+    }
+
+    fun sum_of_odds(n: u64): u64 {
+        let x = 0;
+        let y = 0;
+        loop {
+            if (x < n) {
+                x = x + 1;
+                if (x % 2 == 0) continue;
+                y = y + x
+            } else {
+                break
+            }
+        };
+        y
+    }
+
+    fun nested(n: u64): u64 {
+        let i = 0;
+        let pairs = 0;
+        while (i < n) {
+            let j = 0;
+            while (j < i) {
+                if ((i + j) % 3 == 0) {
+                    j = j + 1;
+                    continue
+                };
+                pairs = pairs + 1;
+                j = j + 1;
+            };
+            i = i + 1;
+        };
+        pairs
+    }
+
+    #[test]
+    fun loops_compute_the_same() {
+        assert!(count(0) == 0, 0);
+        assert!(count(5) == 10, 1);
+        assert!(find_limit(0) == 1, 2);
+        assert!(find_limit(15) == 5, 3);
+        assert!(sum_of_odds(10) == 25, 4);
+        assert!(sum_of_odds(0) == 0, 5);
+        assert!(nested(5) == 7, 6);
+    }
+}
+";
+
+fn compile(experiments: Vec<String>) -> (GlobalEnv, Vec<AnnotatedCompiledUnit>) {
+    let options = Options {
+        source_buffers: vec![("loops.move".to_owned(), SOURCE.to_owned())],
+        compile_test_code: true,
+        experiments,
+        ..Options::default()
+    };
+    common::compile(options)
+}
+
+/// Returns the total number of instructions of the non-test functions of the units.
+fn instruction_count(units: &[AnnotatedCompiledUnit]) -> usize {
+    let module = common::compiled_module(&units[0]);
+    module
+        .function_defs()
+        .iter()
+        .filter(|def| {
+            let handle = module.function_handle_at(def.function);
+            module.identifier_at(handle.name).as_str() != "loops_compute_the_same"
+        })
+        .map(|def| def.code.as_ref().expect("code").code.len())
+        .sum()
+}
+
+#[test]
+fn simplified_loops_compute_the_same() {
+    let (env, units) = compile(vec![Experiment::CFG_SIMPLIFICATION.to_owned()]);
+    let (output, all_passed) = common::run_unit_tests(&env, units);
+    assert!(all_passed, "{}", output);
+}
+
+#[test]
+fn simplification_shortens_code() {
+    let (_, before) = compile(vec![]);
+    let (_, after) = compile(vec![Experiment::CFG_SIMPLIFICATION.to_owned()]);
+    assert!(instruction_count(&after) < instruction_count(&before));
+}
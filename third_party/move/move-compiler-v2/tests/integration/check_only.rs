@@ -0,0 +1,88 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests that check-only compilation does not generate bytecode. The framework and the
+//! standard library use inline functions, for which compiler v2 cannot generate code yet, so a
+//! synthetic package is used instead.
+
+use crate::common;
+use codespan_reporting::term::termcolor::Buffer;
+use move_compiler_v2::{
+    compilation_stats::{CompilationStats, CHECKING_PHASE},
+    run_move_compiler_check_only, Options,
+};
+use move_model::model::GlobalEnv;
+
+const MODULES: usize = 10;
+const FUNCTIONS: usize = 10;
+
+/// Returns a package of modules, where each module calls functions of its predecessor.
+fn package_options() -> Options {
+    let source_buffers = (0..MODULES)
+        .map(|m| {
+            let mut source = format!("module 0x42::m{} {{\n", m);
+            source += "    struct S has copy, drop { x: u64, y: u64 }\n";
+            for f in 0..FUNCTIONS {
+                let call = if m > 0 {
+                    format!("0x42::m{}::f{}(x)", m - 1, f)
+                } else {
+                    "x".to_owned()
+                };
+                source += &format!(
+                    "    public fun f{}(x: u64): u64 {{
+        let s = S {{ x, y: {} }};
+        let i = 0;
+        while (i < s.y) {{
+            if (i % 2 == 0) s.x = s.x + i else s.x = s.x - 1;
+            i = i + 1;
+        }};
+        s.x + {}
+    }}\n",
+                    f, f, call
+                );
+            }
+            source += "}\n";
+            (format!("m{}.move", m), source)
+        })
+        .collect();
+    Options {
+        source_buffers,
+        ..Options::default()
+    }
+}
+
+/// Returns the names of the phases recorded in the statistics of the env.
+fn phases(env: &GlobalEnv) -> Vec<String> {
+    let stats = env
+        .get_extension::<CompilationStats>()
+        .expect("statistics installed");
+    stats.phases().into_iter().map(|t| t.name).collect()
+}
+
+#[test]
+fn check_only_generates_no_bytecode() {
+    let mut error_writer = Buffer::no_color();
+    let env =
+        run_move_compiler_check_only(&mut error_writer, package_options()).unwrap_or_else(|e| {
+            panic!(
+                "checking failed: {}\n{}",
+                e,
+                String::from_utf8_lossy(&error_writer.into_inner())
+            )
+        });
+    assert_eq!(env.get_target_modules().len(), MODULES);
+    assert_eq!(phases(&env), vec![CHECKING_PHASE]);
+    let stats = env.get_extension::<CompilationStats>().unwrap();
+    assert_eq!((stats.modules(), stats.functions()), (0, 0));
+    // The full compilation of the same package goes on with the later phases.
+    let (env, units) = common::compile(package_options());
+    assert_eq!(units.len(), MODULES);
+    assert!(phases(&env).len() > 1);
+    // The check-only mode also applies when requested via the options of the full driver.
+    let (env, units) = common::compile(Options {
+        check_only: true,
+        ..package_options()
+    });
+    assert!(units.is_empty());
+    assert_eq!(phases(&env), vec![CHECKING_PHASE]);
+}
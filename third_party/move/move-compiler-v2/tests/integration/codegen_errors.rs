@@ -0,0 +1,64 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests that code generation reports the errors of all functions in one run.
+
+use codespan_reporting::term::termcolor::Buffer;
+use move_compiler_v2::{run_bytecode_gen, run_checker, run_move_compiler, Options};
+
+const SOURCE: &str = "module 0x42::m {
+    struct S { f: u64 }
+
+    fun misplaced_break(x: u64): u64 {
+        break;
+        x
+    }
+
+    fun mut_expr(x: u64): u64 {
+        let r = &mut (x + 1);
+        *r
+    }
+
+    fun mut_field(s: &S): u64 {
+        let r = &mut s.f;
+        *r
+    }
+
+    fun valid(x: u64): u64 {
+        mut_expr(x) + 1
+    }
+}
+";
+
+fn options() -> Options {
+    Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..Options::default()
+    }
+}
+
+#[test]
+fn errors_of_all_functions_are_reported() {
+    let mut error_writer = Buffer::no_color();
+    let result = run_move_compiler(&mut error_writer, options());
+    let message = result.expect_err("compilation fails").to_string();
+    assert_eq!(message, "exiting with code generation errors");
+    let diags = String::from_utf8(error_writer.into_inner()).unwrap();
+    assert_eq!(diags.matches("error[").count(), 3, "{}", diags);
+    for text in ["break;", "&mut (x + 1)", "&mut s.f"] {
+        assert!(diags.contains(text), "{}", diags)
+    }
+}
+
+#[test]
+fn functions_with_errors_have_no_targets() {
+    let env = run_checker(options()).expect("checks");
+    assert!(!env.has_errors());
+    let targets = run_bytecode_gen(&env);
+    assert_eq!(env.error_count(), 3);
+    let names = targets
+        .get_funs()
+        .map(|id| env.get_function(id).get_name_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["valid"]);
+}
@@ -0,0 +1,135 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the diagnostics collected by `run_move_compiler_collecting`.
+
+use crate::common;
+use move_compiler_v2::{
+    diagnostics::{CompilerDiagnostic, DiagnosticLocation, DiagnosticSeverity},
+    run_move_compiler_collecting, DiagnosticsFormat, Options,
+};
+
+const SOURCE: &str = "module 0x42::m {
+    public fun f(x: u64): u64 {
+        let y = 1;
+        x
+    }
+
+    public fun g(): u64 {
+        0x42::n::h()
+    }
+}
+
+module 0x42::n {
+    fun h(): u64 { 1 }
+}
+";
+
+fn options(source: &str) -> Options {
+    Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        ..Options::default()
+    }
+}
+
+/// Returns the location of the first occurrence of the text after the given prefix in the
+/// source.
+fn location_of(prefix: &str, text: &str) -> DiagnosticLocation {
+    let start = SOURCE.find(prefix).expect("prefix") + prefix.len();
+    assert!(SOURCE[start..].starts_with(text));
+    let line_col = |offset: usize| {
+        let line_start = SOURCE[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        (
+            SOURCE[..offset].matches('\n').count() + 1,
+            offset - line_start + 1,
+        )
+    };
+    let (start_line, start_column) = line_col(start);
+    let (end_line, end_column) = line_col(start + text.len());
+    DiagnosticLocation {
+        file: "m.move".to_owned(),
+        start: start as u32,
+        end: (start + text.len()) as u32,
+        start_line,
+        start_column,
+        start_utf16_column: start_column,
+        end_line,
+        end_column,
+        end_utf16_column: end_column,
+    }
+}
+
+#[test]
+fn errors_are_collected_with_locations() {
+    let diags = run_move_compiler_collecting(options(SOURCE))
+        .err()
+        .expect("compilation fails");
+    let error = diags
+        .iter()
+        .find(|d| d.severity == DiagnosticSeverity::Error)
+        .expect("error");
+    assert_eq!(error.code.as_deref(), Some("E02001"));
+    assert_eq!(
+        error.location,
+        Some(location_of("g(): u64 {\n        ", "0x42::n::h()"))
+    );
+    let declared = error.secondary_labels().collect::<Vec<_>>();
+    assert_eq!(declared.len(), 1);
+    assert_eq!(
+        declared[0].location,
+        location_of("module 0x42::n {\n    ", "fun h(): u64 { 1 }")
+    );
+    assert_eq!(declared[0].message, "`0x42::n::h` is declared here");
+}
+
+#[test]
+fn warnings_of_successful_compilations_are_collected() {
+    let source = SOURCE.replace("0x42::n::h()", "f(1)");
+    let output = run_move_compiler_collecting(options(&source)).expect("compiles");
+    assert_eq!(output.annotated_units.len(), 2);
+    let messages = output
+        .diagnostics
+        .iter()
+        .map(|d| (d.severity, d.message.as_str()))
+        .collect::<Vec<_>>();
+    assert!(
+        messages.contains(&(
+            DiagnosticSeverity::Warning,
+            "function `n::h` is never called"
+        )),
+        "{:?}",
+        messages
+    );
+    assert!(messages
+        .iter()
+        .all(|(severity, _)| *severity == DiagnosticSeverity::Warning));
+}
+
+#[test]
+fn failures_without_diagnostics_are_collected() {
+    let diags = run_move_compiler_collecting(Options {
+        sources: vec!["does/not/exist.move".to_owned()],
+        ..Options::default()
+    })
+    .err()
+    .expect("compilation fails");
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].severity, DiagnosticSeverity::Error);
+    assert_eq!(diags[0].location, None);
+}
+
+#[test]
+fn collected_diagnostics_match_json_output() {
+    let written = common::try_compile(Options {
+        diagnostics_format: DiagnosticsFormat::Json,
+        ..options(SOURCE)
+    })
+    .expect_err("compilation fails")
+    .lines()
+    .map(|line| serde_json::from_str::<CompilerDiagnostic>(line).expect("diagnostic"))
+    .collect::<Vec<_>>();
+    let collected = run_move_compiler_collecting(options(SOURCE))
+        .err()
+        .expect("compilation fails");
+    assert_eq!(written, collected);
+}
@@ -0,0 +1,79 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the configuration of colored output.
+
+use crate::common;
+use codespan_reporting::term::termcolor::ColorChoice;
+use move_compiler_v2::{run_move_compiler_to_writer, ColorMode, Options};
+
+/// A source with an unused variable, which is a warning.
+const SOURCE: &str = "module 0x42::m {
+    public fun f(x: u64): u64 { let y = 1; x }
+}
+";
+
+/// Compiles the source with the given command line arguments, returning the output written to
+/// the error writer.
+fn compile(args: &[&str]) -> String {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..common::parse_options(args)
+    };
+    let mut output = vec![];
+    run_move_compiler_to_writer(&mut output, options).expect("compiles");
+    String::from_utf8(output).unwrap()
+}
+
+fn has_escape_codes(output: &str) -> bool {
+    output.contains("\u{1b}[")
+}
+
+#[test]
+fn always_colors_output() {
+    let output = compile(&["--color", "always", "--print-stats"]);
+    assert!(output.contains("unused"), "{}", output);
+    assert!(has_escape_codes(&output), "{}", output);
+}
+
+#[test]
+fn never_and_auto_do_not_color_output_which_is_not_a_terminal() {
+    for mode in ["never", "auto"] {
+        let output = compile(&["--color", mode, "--print-stats"]);
+        assert!(output.contains("unused"), "{}", output);
+        assert!(output.contains("total"), "{}", output);
+        assert!(!has_escape_codes(&output), "{}", output);
+    }
+}
+
+#[test]
+fn auto_respects_no_color() {
+    let options = |color| Options {
+        color,
+        ..Options::default()
+    };
+    std::env::remove_var("NO_COLOR");
+    assert_eq!(
+        options(ColorMode::Auto).color_choice(true),
+        ColorChoice::Always
+    );
+    std::env::set_var("NO_COLOR", "1");
+    assert_eq!(
+        options(ColorMode::Auto).color_choice(true),
+        ColorChoice::Never
+    );
+    assert_eq!(
+        options(ColorMode::Always).color_choice(true),
+        ColorChoice::Always
+    );
+    std::env::set_var("NO_COLOR", "");
+    assert_eq!(
+        options(ColorMode::Auto).color_choice(true),
+        ColorChoice::Always
+    );
+    std::env::remove_var("NO_COLOR");
+    assert_eq!(
+        options(ColorMode::Never).color_choice(true),
+        ColorChoice::Never
+    );
+}
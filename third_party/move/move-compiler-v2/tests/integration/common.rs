@@ -0,0 +1,170 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers shared by the integration tests.
+
+use codespan_reporting::term::termcolor::Buffer;
+use move_binary_format::{
+    access::ModuleAccess,
+    deserializer::DeserializerConfig,
+    file_format::{Bytecode, CompiledModule},
+    file_format_common::{IDENTIFIER_SIZE_MAX, VERSION_DEFAULT},
+};
+use move_compiler::compiled_unit::{AnnotatedCompiledUnit, CompiledUnitEnum};
+use move_compiler_v2::{
+    diagnostics::CompilerDiagnostic, run_move_compiler, run_move_compiler_collecting,
+    run_move_compiler_with_targets, unit_test, CompilerOutput, Options,
+};
+use move_model::model::GlobalEnv;
+use move_unit_test::UnitTestingConfig;
+use std::fs;
+use tempfile::TempDir;
+
+/// Returns owned copies of the named sources, as expected by `Options::source_buffers`.
+pub fn source_buffers(sources: &[(&str, &str)]) -> Vec<(String, String)> {
+    sources
+        .iter()
+        .map(|(name, source)| (name.to_string(), source.to_string()))
+        .collect()
+}
+
+/// Writes the files into a temporary directory, returning it and the paths of the files.
+pub fn write_files(files: &[(&str, &str)]) -> (TempDir, Vec<String>) {
+    let dir = tempfile::tempdir().expect("temporary directory");
+    let paths = files
+        .iter()
+        .map(|(name, content)| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).expect("file written");
+            path.to_string_lossy().to_string()
+        })
+        .collect();
+    (dir, paths)
+}
+
+/// Parses the command line arguments, which do not include the program name, into options.
+pub fn parse_options(args: &[&str]) -> Options {
+    Options::try_parse_command_line(std::iter::once("move-compiler-v2").chain(args.iter().cloned()))
+        .expect("options")
+}
+
+/// Compiles with the given options, returning whether compilation succeeded and the reported
+/// diagnostics.
+pub fn compile_reporting(options: Options) -> (bool, String) {
+    let mut error_writer = Buffer::no_color();
+    let result = run_move_compiler(&mut error_writer, options);
+    (
+        result.is_ok(),
+        String::from_utf8(error_writer.into_inner()).expect("utf8"),
+    )
+}
+
+/// Compiles with the given options, returning the reported diagnostics if compilation fails.
+pub fn try_compile(options: Options) -> Result<(GlobalEnv, Vec<AnnotatedCompiledUnit>), String> {
+    let mut error_writer = Buffer::no_color();
+    run_move_compiler(&mut error_writer, options)
+        .map_err(|_| String::from_utf8(error_writer.into_inner()).expect("utf8"))
+}
+
+/// Compiles with the given options, panicking with the diagnostics if compilation fails.
+pub fn compile(options: Options) -> (GlobalEnv, Vec<AnnotatedCompiledUnit>) {
+    try_compile(options).unwrap_or_else(|diags| panic!("compilation failed:\n{}", diags))
+}
+
+/// Compiles with the given options, returning the collected diagnostics, whether compilation
+/// succeeded or not.
+pub fn collected_diagnostics(options: Options) -> Vec<CompilerDiagnostic> {
+    match run_move_compiler_collecting(options) {
+        Ok(output) => output.diagnostics,
+        Err(diags) => diags,
+    }
+}
+
+/// Compiles with the given options, returning the output with the collected diagnostics, and
+/// panicking with them if compilation fails.
+pub fn compile_collecting(options: Options) -> CompilerOutput {
+    run_move_compiler_collecting(options)
+        .unwrap_or_else(|diags| panic!("compilation fails: {:?}", diags))
+}
+
+/// Compiles with the given options, returning the function targets next to the units, and
+/// panicking with the diagnostics if compilation fails.
+pub fn compile_with_targets(options: Options) -> CompilerOutput {
+    let mut error_writer = Buffer::no_color();
+    run_move_compiler_with_targets(&mut error_writer, options).unwrap_or_else(|_| {
+        panic!(
+            "compilation failed:\n{}",
+            String::from_utf8_lossy(&error_writer.into_inner())
+        )
+    })
+}
+
+/// Returns the names of the modules and scripts of the units.
+pub fn unit_names(units: &[AnnotatedCompiledUnit]) -> Vec<String> {
+    units
+        .iter()
+        .map(|unit| match unit.clone().into_compiled_unit() {
+            CompiledUnitEnum::Module(module) => module.name.to_string(),
+            CompiledUnitEnum::Script(script) => script.name.to_string(),
+        })
+        .collect()
+}
+
+/// The config deserializing units of the released bytecode version the tests compile to, as
+/// the unreleased one is only accepted by the deserializer in its own tests.
+pub fn deserializer_config() -> DeserializerConfig {
+    DeserializerConfig::new(VERSION_DEFAULT, IDENTIFIER_SIZE_MAX)
+}
+
+/// Returns the module of the unit, panicking if it is a script.
+pub fn compiled_module(unit: &AnnotatedCompiledUnit) -> CompiledModule {
+    match unit.clone().into_compiled_unit() {
+        CompiledUnitEnum::Module(module) => module.module,
+        CompiledUnitEnum::Script(_) => panic!("expected a module"),
+    }
+}
+
+/// Returns the names of the functions defined by the module.
+pub fn defined_functions(module: &CompiledModule) -> Vec<String> {
+    module
+        .function_defs
+        .iter()
+        .map(|def| {
+            let handle = module.function_handle_at(def.function);
+            module.identifier_at(handle.name).to_string()
+        })
+        .collect()
+}
+
+/// Returns the code of the function with the given name.
+pub fn function_code(module: &CompiledModule, name: &str) -> Vec<Bytecode> {
+    let def = module
+        .function_defs()
+        .iter()
+        .find(|def| {
+            module
+                .identifier_at(module.function_handle_at(def.function).name)
+                .as_str()
+                == name
+        })
+        .expect("function");
+    def.code.as_ref().expect("code").code.clone()
+}
+
+/// Runs the unit tests of the compiled units, returning the report and whether all tests
+/// passed.
+pub fn run_unit_tests(env: &GlobalEnv, units: Vec<AnnotatedCompiledUnit>) -> (String, bool) {
+    let plan = unit_test::test_plan(env, units);
+    let (output, all_passed) = UnitTestingConfig::default_with_bound(None)
+        .run_and_report_unit_tests(plan, None, None, vec![])
+        .expect("tests run");
+    (String::from_utf8_lossy(&output).to_string(), all_passed)
+}
+
+/// Returns the serialized bytecode of the units.
+pub fn serialize_units(units: Vec<AnnotatedCompiledUnit>) -> Vec<Vec<u8>> {
+    units
+        .into_iter()
+        .map(|unit| unit.into_compiled_unit().serialize(None))
+        .collect()
+}
@@ -0,0 +1,122 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the incremental compilation cache.
+
+use crate::common;
+use move_compiler_v2::{compilation_cache::CompilationCache, Options};
+use std::{fs, path::Path};
+use tempfile::TempDir;
+
+/// A package of three modules, where `b` depends on `a`, and `c` is independent.
+const SOURCES: &[(&str, &str)] = &[
+    ("a.move", "module 0x42::a { public fun f(): u64 { 1 } }"),
+    (
+        "b.move",
+        "module 0x42::b { use 0x42::a; public fun g(): u64 { a::f() + 1 } }",
+    ),
+    ("c.move", "module 0x42::c { public fun h(): u64 { 3 } }"),
+];
+
+fn setup() -> (TempDir, Options) {
+    let dir = tempfile::tempdir().expect("temp dir");
+    for (name, source) in SOURCES {
+        fs::write(dir.path().join(name), source).expect("write source");
+    }
+    let options = Options {
+        sources: SOURCES
+            .iter()
+            .map(|(name, _)| dir.path().join(name).to_string_lossy().to_string())
+            .collect(),
+        compilation_cache_dir: Some(dir.path().join("cache")),
+        bytecode_version: Some(6),
+        ..Options::default()
+    };
+    (dir, options)
+}
+
+/// Compiles the package, returning the sorted names of the regenerated modules and the
+/// serialized units.
+fn compile(options: &Options) -> (Vec<String>, Vec<Vec<u8>>) {
+    let (env, units) = common::compile(options.clone());
+    let cache = env
+        .get_extension::<CompilationCache>()
+        .expect("cache installed");
+    let mut generated = cache
+        .generated_modules()
+        .into_iter()
+        .map(|id| env.get_module(id).get_full_name_str())
+        .collect::<Vec<_>>();
+    generated.sort();
+    (generated, common::serialize_units(units))
+}
+
+fn modify(dir: &Path, name: &str, from: &str, to: &str) {
+    let path = dir.join(name);
+    let source = fs::read_to_string(&path).expect("read source");
+    fs::write(&path, source.replace(from, to)).expect("write source");
+}
+
+#[test]
+fn unchanged_package_is_not_regenerated() {
+    let (_dir, options) = setup();
+    let (generated, units) = compile(&options);
+    assert_eq!(generated, vec!["0x42::a", "0x42::b", "0x42::c"]);
+    let (generated, cached_units) = compile(&options);
+    assert!(generated.is_empty());
+    assert_eq!(units, cached_units);
+}
+
+#[test]
+fn changed_module_is_regenerated() {
+    let (dir, options) = setup();
+    compile(&options);
+    modify(dir.path(), "c.move", "3", "4");
+    let (generated, _) = compile(&options);
+    assert_eq!(generated, vec!["0x42::c"]);
+}
+
+#[test]
+fn dependents_of_changed_module_are_regenerated() {
+    let (dir, options) = setup();
+    compile(&options);
+    modify(dir.path(), "a.move", "1", "2");
+    let (generated, _) = compile(&options);
+    assert_eq!(generated, vec!["0x42::a", "0x42::b"]);
+}
+
+#[test]
+fn changed_options_invalidate_cache() {
+    let (_dir, options) = setup();
+    compile(&options);
+    let (generated, _) = compile(&Options {
+        bytecode_version: Some(5),
+        ..options.clone()
+    });
+    assert_eq!(generated, vec!["0x42::a", "0x42::b", "0x42::c"]);
+    let (generated, _) = compile(&Options {
+        bytecode_version: Some(5),
+        experiments: vec!["copy-propagation".to_owned()],
+        ..options.clone()
+    });
+    assert_eq!(generated, vec!["0x42::a", "0x42::b", "0x42::c"]);
+    let (generated, _) = compile(&Options {
+        bytecode_version: Some(5),
+        experiments: vec!["copy-propagation".to_owned()],
+        named_address_mapping: vec!["std=0x1".to_owned()],
+        ..options
+    });
+    assert_eq!(generated, vec!["0x42::a", "0x42::b", "0x42::c"]);
+}
+
+#[test]
+fn unreleased_bytecode_version_is_not_cached() {
+    let (_dir, options) = setup();
+    let options = Options {
+        bytecode_version: Some(7),
+        ..options
+    };
+    compile(&options);
+    let (generated, _) = compile(&options);
+    assert_eq!(generated, vec!["0x42::a", "0x42::b", "0x42::c"]);
+}
@@ -0,0 +1,171 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the statistics collected during compilation.
+
+use crate::common;
+use codespan_reporting::term::termcolor::Buffer;
+use move_compiler_v2::{
+    compilation_stats::{
+        CompilationStats, BYTECODE_GENERATION_PHASE, BYTECODE_PIPELINE_PHASE,
+        BYTECODE_VERIFICATION_PHASE, CHECKING_PHASE, FILE_FORMAT_GENERATION_PHASE,
+    },
+    run_move_compiler, DiagnosticsFormat, Options,
+};
+
+const SOURCES: &[(&str, &str)] = &[
+    (
+        "m.move",
+        "module 0x42::m {
+    public fun f(x: u64): u64 { x + 1 }
+    public fun g(): u64 { f(1) }
+}",
+    ),
+    ("s.move", "script { fun main() { 0x42::m::f(1); } }"),
+];
+
+fn options() -> Options {
+    Options {
+        source_buffers: common::source_buffers(SOURCES),
+        ..Options::default()
+    }
+}
+
+#[test]
+fn phases_are_populated_and_monotonic() {
+    let (env, units) = common::compile(options());
+    assert_eq!(units.len(), 2);
+    let stats = env
+        .get_extension::<CompilationStats>()
+        .expect("statistics installed");
+    let phases = stats.phases();
+    assert_eq!(
+        phases.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+        vec![
+            CHECKING_PHASE,
+            BYTECODE_GENERATION_PHASE,
+            BYTECODE_PIPELINE_PHASE,
+            FILE_FORMAT_GENERATION_PHASE,
+            BYTECODE_VERIFICATION_PHASE
+        ]
+    );
+    for window in phases.windows(2) {
+        assert!(window[0].end() <= window[1].start, "{:?}", window);
+    }
+    assert!(phases[0].duration > Default::default());
+    assert_eq!(stats.total(), phases.last().unwrap().end());
+    // The processors run within the pipeline phase.
+    let processors = stats.processors();
+    assert!(processors
+        .iter()
+        .any(|p| p.name == "LiveVarAnalysisProcessor"));
+    let pipeline = &phases[2];
+    for processor in &processors {
+        assert!(pipeline.start <= processor.start && processor.end() <= pipeline.end());
+    }
+    for window in processors.windows(2) {
+        assert!(window[0].end() <= window[1].start, "{:?}", window);
+    }
+    assert_eq!(stats.modules(), 1);
+    assert_eq!(stats.scripts(), 1);
+    assert_eq!(stats.functions(), 3);
+    assert!(stats.instructions() >= 3);
+}
+
+#[test]
+fn stats_are_printed_on_request() {
+    let mut error_writer = Buffer::no_color();
+    run_move_compiler(&mut error_writer, options()).expect("compiles");
+    assert!(error_writer.is_empty());
+    run_move_compiler(&mut error_writer, Options {
+        print_stats: true,
+        ..options()
+    })
+    .expect("compiles");
+    let output = String::from_utf8(error_writer.into_inner()).expect("utf8");
+    for phase in [
+        CHECKING_PHASE,
+        BYTECODE_PIPELINE_PHASE,
+        "  ExplicitDrop",
+        "total",
+    ] {
+        assert!(
+            output.lines().any(|line| line.starts_with(phase)),
+            "missing `{}` in:\n{}",
+            phase,
+            output
+        );
+    }
+    assert!(output.contains("generated 1 module(s), 1 script(s), 3 function(s)"));
+}
+
+#[test]
+fn profile_covers_every_target_function() {
+    let mut error_writer = Buffer::no_color();
+    let (env, _) = run_move_compiler(&mut error_writer, Options {
+        profile_compilation: true,
+        profile_top: usize::MAX,
+        ..options()
+    })
+    .expect("compiles");
+    let stats = env
+        .get_extension::<CompilationStats>()
+        .expect("statistics installed");
+    let profile = stats.profile(usize::MAX);
+    for function in ["0x42::m::f", "0x42::m::g"] {
+        assert!(
+            profile
+                .functions
+                .iter()
+                .any(|t| t.function == function && t.processor == "LiveVarAnalysisProcessor"),
+            "missing `{}` in {:?}",
+            function,
+            profile.functions
+        );
+    }
+    assert!(profile.modules.iter().any(|t| t.module == "0x42::m"));
+    for window in profile.functions.windows(2) {
+        assert!(window[0].duration >= window[1].duration, "{:?}", window);
+    }
+    assert_eq!(stats.profile(2).functions, profile.functions[..2]);
+    let output = String::from_utf8(error_writer.into_inner()).expect("utf8");
+    assert!(output.starts_with("slowest functions in the bytecode pipeline:\n"));
+    assert!(output.contains("\nbytecode generation per module:\n"));
+    for function in ["0x42::m::f  ", "0x42::m::g  ", "  0x42::m  "] {
+        assert!(output.contains(function), "{}", output)
+    }
+}
+
+#[test]
+fn profile_is_reported_as_json() {
+    let mut error_writer = Buffer::no_color();
+    run_move_compiler(&mut error_writer, Options {
+        profile_compilation: true,
+        profile_top: 1,
+        diagnostics_format: DiagnosticsFormat::Json,
+        ..options()
+    })
+    .expect("compiles");
+    let output = String::from_utf8(error_writer.into_inner()).expect("utf8");
+    let profile: serde_json::Value = serde_json::from_str(&output).expect("JSON");
+    let functions = profile["functions"].as_array().expect("functions");
+    assert_eq!(functions.len(), 1);
+    assert!(functions[0]["duration_us"].is_u64());
+    assert!(profile["modules"]
+        .as_array()
+        .expect("modules")
+        .iter()
+        .any(|m| m["module"] == "0x42::m"));
+}
+
+#[test]
+fn functions_are_not_profiled_by_default() {
+    let mut error_writer = Buffer::no_color();
+    let (env, _) = run_move_compiler(&mut error_writer, options()).expect("compiles");
+    let stats = env
+        .get_extension::<CompilationStats>()
+        .expect("statistics installed");
+    let profile = stats.profile(usize::MAX);
+    assert!(profile.functions.is_empty() && profile.modules.is_empty());
+    assert!(error_writer.is_empty());
+}
@@ -0,0 +1,74 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for `compile_to_bytes`, which returns the serialized units of a compilation.
+
+use move_binary_format::file_format::{CompiledModule, CompiledScript};
+use move_compiler_v2::{compile_to_bytes, diagnostics::DiagnosticSeverity, Options};
+
+const MODULES: &str = "module 0x42::a {
+    public fun f(): u64 {
+        let unused = 1;
+        2
+    }
+}
+
+module 0x42::b {
+    public fun g(): u64 { 0x42::a::f() }
+}
+";
+
+const SCRIPT: &str = "script {
+    fun main() {
+        assert!(0x42::b::g() == 2, 0);
+    }
+}
+";
+
+fn options(sources: &[&str]) -> Options {
+    Options {
+        source_buffers: sources
+            .iter()
+            .enumerate()
+            .map(|(idx, source)| (format!("s{}.move", idx), source.to_string()))
+            .collect(),
+        bytecode_version: Some(6),
+        ..Options::default()
+    }
+}
+
+#[test]
+fn units_are_serialized_for_the_configured_version() {
+    let artifacts = compile_to_bytes(options(&[MODULES, SCRIPT])).expect("compiles");
+    let names = artifacts
+        .modules
+        .keys()
+        .map(|id| id.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["a", "b"]);
+    for (id, unit) in &artifacts.modules {
+        let module = CompiledModule::deserialize(&unit.code).expect("deserializable module");
+        assert_eq!(&module.self_id(), id);
+        assert_eq!(module.version, 6);
+    }
+    let script = CompiledScript::deserialize(&artifacts.scripts["main"].code)
+        .expect("deserializable script");
+    assert_eq!(script.version, 6);
+    assert!(artifacts
+        .diagnostics
+        .iter()
+        .any(|d| d.severity == DiagnosticSeverity::Warning && d.message.contains("unused")));
+}
+
+#[test]
+fn errors_carry_rendered_diagnostics() {
+    let source = "module 0x42::m {
+    public fun f(): u64 { true }
+}
+";
+    let err = compile_to_bytes(options(&[source]))
+        .expect_err("compilation fails")
+        .to_string();
+    assert!(err.contains("expected `u64` but found `bool`"), "{}", err);
+    assert!(err.contains("s0.move:2:"), "{}", err);
+}
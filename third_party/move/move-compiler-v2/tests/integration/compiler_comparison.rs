@@ -0,0 +1,69 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for comparing the units generated by compiler v1 and v2.
+
+use codespan_reporting::term::termcolor::Buffer;
+use move_compiler_v2::{
+    compiler_comparison::{run_compiler_comparison, ComparisonReport, Severity},
+    ComparisonLevel, Options,
+};
+
+fn compare(source: &str, level: ComparisonLevel) -> ComparisonReport {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        ..Options::default()
+    };
+    let mut error_writer = Buffer::no_color();
+    run_compiler_comparison(&mut error_writer, options, level).unwrap_or_else(|e| {
+        panic!(
+            "comparison failed: {}\n{}",
+            e,
+            String::from_utf8_lossy(&error_writer.into_inner())
+        )
+    })
+}
+
+#[test]
+fn same_abi_is_not_reported() {
+    let report = compare(
+        "module 0x42::m {
+            struct S has drop { x: u64 }
+            public fun make(x: u64): S { S { x } }
+        }",
+        ComparisonLevel::Abi,
+    );
+    assert!(report.differences.is_empty(), "{}", report.render());
+}
+
+#[test]
+fn inline_functions_differ_in_abi() {
+    // Compiler v1 does not generate code for inline functions, compiler v2 does.
+    let report = compare(
+        "module 0x42::m {
+            public inline fun twice(x: u64): u64 { x + x }
+            public fun four(): u64 { twice(2) }
+        }",
+        ComparisonLevel::Abi,
+    );
+    assert!(report.has_abi_differences(), "{}", report.render());
+    assert!(report
+        .render()
+        .contains("function `twice` is missing in v1"));
+}
+
+#[test]
+fn code_differences_are_not_abi_differences() {
+    let source = "module 0x42::m {
+        public fun f(x: u64): u64 { g(x) }
+        fun g(x: u64): u64 { let y = x; let z = y; z + y }
+    }";
+    assert!(compare(source, ComparisonLevel::Abi).differences.is_empty());
+    let report = compare(source, ComparisonLevel::Code);
+    assert!(!report.differences.is_empty());
+    assert!(!report.has_abi_differences(), "{}", report.render());
+    assert!(report
+        .differences
+        .iter()
+        .all(|d| d.severity == Severity::Code && d.unit == "0x42::m"));
+}
@@ -0,0 +1,71 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the stackless bytecode returned by `run_move_compiler_with_targets`.
+
+use crate::common;
+use move_compiler_v2::Options;
+use move_stackless_bytecode::{
+    function_target_pipeline::FunctionVariant, livevar_analysis::LiveVarAnnotation,
+    stackless_bytecode::Bytecode,
+};
+
+const SOURCE: &str = "module 0x42::m {
+    public fun f(x: u64, y: u64): u64 {
+        let z = x + y;
+        z * x
+    }
+}
+";
+
+#[test]
+fn targets_carry_livevar_annotations() {
+    let output = common::compile_with_targets(Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..Options::default()
+    });
+    assert_eq!(output.annotated_units.len(), 1);
+    let module_env = output
+        .env
+        .get_modules()
+        .find(|m| m.is_target())
+        .expect("module");
+    let fun_env = module_env
+        .find_function(output.env.symbol_pool().make("f"))
+        .expect("function");
+    let target = output
+        .targets
+        .get_target(&fun_env, &FunctionVariant::Baseline);
+    let annotation = target
+        .get_annotations()
+        .get::<LiveVarAnnotation>()
+        .expect("livevar annotation");
+    // Only the returned temporaries are alive at a return, and the parameter `x` is alive at
+    // the entry.
+    let code = target.get_bytecode();
+    for (offset, bc) in code.iter().enumerate() {
+        let info = annotation
+            .get_live_var_info_at(offset as u16)
+            .expect("info at offset");
+        if let Bytecode::Ret(_, rets) = bc {
+            assert_eq!(info.before, rets.iter().cloned().collect());
+            assert!(info.after.is_empty());
+        }
+    }
+    assert!(annotation
+        .get_live_var_info_at(0)
+        .expect("info at entry")
+        .before
+        .contains(&0));
+}
+
+#[test]
+fn targets_are_empty_without_code() {
+    let output = common::compile_with_targets(Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        check_only: true,
+        ..Options::default()
+    });
+    assert!(output.annotated_units.is_empty());
+    assert_eq!(output.targets.get_funs().count(), 0);
+}
@@ -0,0 +1,91 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the constant pool of generated modules: deduplication of constants and limits of
+//! their size.
+
+use crate::common;
+use move_compiler_v2::{run_move_compiler_collecting, CompilerOutput, Options};
+
+/// A hex literal of 1024 bytes.
+fn blob() -> String {
+    format!("x\"{}\"", "ab".repeat(1024))
+}
+
+fn compile(
+    source: String,
+    max_constant_size: Option<usize>,
+) -> Result<CompilerOutput, Vec<String>> {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), source)],
+        max_constant_size,
+        ..Options::default()
+    };
+    run_move_compiler_collecting(options).map_err(|diags| {
+        diags
+            .into_iter()
+            .map(|d| {
+                let line = d.location.map(|l| l.start_line).unwrap_or_default();
+                format!("{}:{}: {}", d.code.unwrap_or_default(), line, d.message)
+            })
+            .collect()
+    })
+}
+
+/// Returns the sizes of the values in the constant pool of the only unit.
+fn constant_sizes(output: &CompilerOutput) -> Vec<usize> {
+    common::compiled_module(&output.annotated_units[0])
+        .constant_pool
+        .iter()
+        .map(|c| c.data.len())
+        .collect()
+}
+
+#[test]
+fn equal_constants_share_an_entry() {
+    let source = format!(
+        "module 0x42::m {{
+    public fun f(): vector<u8> {{
+        {}
+    }}
+
+    public fun g(): vector<u8> {{
+        {}
+    }}
+
+    public fun h(): vector<u8> {{
+        b\"ab\"
+    }}
+
+    public fun i(): vector<u8> {{
+        vector[97u8, 98u8]
+    }}
+}}
+",
+        blob(),
+        blob()
+    );
+    let output = compile(source, None).expect("compiles");
+    // The length of the serialized vectors is prefixed.
+    assert_eq!(constant_sizes(&output), vec![1026, 3]);
+}
+
+#[test]
+fn oversized_constant_is_reported_at_its_location() {
+    let source = format!(
+        "module 0x42::m {{
+    public fun f(): vector<u8> {{
+        {}
+    }}
+}}
+",
+        blob()
+    );
+    let errors = compile(source.clone(), Some(1000))
+        .err()
+        .expect("compilation fails");
+    assert_eq!(errors, vec![
+        "E03003:3: constant of 1026 bytes exceeds the maximal size of 1000 bytes".to_owned()
+    ]);
+    assert!(compile(source, Some(1026)).is_ok());
+}
@@ -0,0 +1,106 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for stripping private functions which can never be called from generated modules.
+
+use crate::common;
+use codespan_reporting::diagnostic::Severity;
+use move_compiler_v2::{run_move_compiler_collecting, CompilerOutput, Options};
+
+const SOURCE: &str = "module 0x42::m {
+    public fun api(x: u64): u64 { used_helper(x) }
+
+    fun used_helper(x: u64): u64 { deep_helper(x) + 1 }
+
+    fun deep_helper(x: u64): u64 { x * 2 }
+
+    fun dead_helper(x: u64): u64 { dead_callee(x) }
+
+    fun dead_callee(x: u64): u64 { x }
+
+    fun spec_helper(): u64 { 1 }
+
+    spec api {
+        ensures result >= spec_helper();
+    }
+
+    fun only_for_tests(): u64 { 3 }
+
+    #[test]
+    fun test_api() {
+        assert!(api(only_for_tests()) == 7, 0)
+    }
+}
+";
+
+fn compile(compile_test_code: bool) -> CompilerOutput {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        strip_dead_private_functions: true,
+        compile_test_code,
+        report_severity: Severity::Note,
+        ..Options::default()
+    };
+    common::compile_collecting(options)
+}
+
+/// Returns the names of the functions defined by the compiled module.
+fn defined_functions(output: &CompilerOutput) -> Vec<String> {
+    let [unit] = output.annotated_units.as_slice() else {
+        panic!("one unit expected")
+    };
+    common::defined_functions(&common::compiled_module(unit))
+}
+
+/// Returns the messages of the notes listing stripped functions.
+fn notes(output: &CompilerOutput) -> Vec<String> {
+    output
+        .diagnostics
+        .iter()
+        .filter(|diag| diag.code.as_deref() == Some("N03004"))
+        .map(|diag| diag.message.clone())
+        .collect()
+}
+
+#[test]
+fn dead_helpers_are_stripped() {
+    let output = compile(false);
+    assert_eq!(defined_functions(&output), vec![
+        "api",
+        "used_helper",
+        "deep_helper",
+        "spec_helper",
+    ]);
+    assert_eq!(notes(&output), vec![
+        "stripped unreachable private functions from `0x42::m`: `dead_helper`, `dead_callee`, \
+         `only_for_tests`"
+            .to_owned()
+    ]);
+}
+
+#[test]
+fn functions_used_by_tests_are_kept_with_test_code() {
+    let output = compile(true);
+    assert_eq!(defined_functions(&output), vec![
+        "api",
+        "used_helper",
+        "deep_helper",
+        "spec_helper",
+        "only_for_tests",
+        "test_api",
+    ]);
+    assert_eq!(notes(&output), vec![
+        "stripped unreachable private functions from `0x42::m`: `dead_helper`, `dead_callee`"
+            .to_owned()
+    ]);
+}
+
+#[test]
+fn nothing_is_stripped_by_default() {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..Options::default()
+    };
+    let output = run_move_compiler_collecting(options).expect("compilation");
+    assert_eq!(defined_functions(&output).len(), 7);
+}
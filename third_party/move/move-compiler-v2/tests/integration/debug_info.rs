@@ -0,0 +1,75 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests that the names of variables are only emitted into the source map with the
+//! `emit_debug_info` option.
+
+use crate::common;
+use move_binary_format::file_format::{Bytecode, FunctionDefinitionIndex};
+use move_compiler::compiled_unit::AnnotatedCompiledUnit;
+use move_compiler_v2::Options;
+
+const SOURCE: &str = "module 0x42::m {
+    public fun count(n: u64): u64 {
+        let my_counter = 0;
+        while (my_counter < n) {
+            my_counter = my_counter + 1
+        };
+        my_counter * 2
+    }
+}
+";
+
+/// Compiles the source, returning the index of the local initialized with `0`, which is
+/// `my_counter`, and the names of all locals in the source map.
+fn compile(emit_debug_info: bool) -> (usize, Vec<String>) {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        emit_debug_info,
+        ..Options::default()
+    };
+    let (_, units) = common::compile(options);
+    let AnnotatedCompiledUnit::Module(annotated) = &units[0] else {
+        panic!("expected a module")
+    };
+    let source_map = annotated.named_module.source_map.clone();
+    let module = common::compiled_module(&units[0]);
+    let code = &module.function_defs[0].code.as_ref().expect("code").code;
+    let counter = code
+        .windows(2)
+        .find_map(|pair| match pair {
+            [Bytecode::LdU64(0), Bytecode::StLoc(local)] => Some(*local as usize),
+            _ => None,
+        })
+        .expect("initialization of the counter");
+    let fun_map = source_map
+        .get_function_source_map(FunctionDefinitionIndex(0))
+        .expect("function source map");
+    let names = (0..fun_map.parameters.len() + fun_map.locals.len())
+        .map(|idx| {
+            fun_map
+                .get_parameter_or_local_name(idx as u64)
+                .expect("local name")
+                .0
+        })
+        .collect();
+    (counter, names)
+}
+
+#[test]
+fn variable_names_with_debug_info() {
+    let (counter, names) = compile(true);
+    assert_eq!(names[counter], "my_counter");
+    assert_eq!(names.iter().filter(|n| *n == "my_counter").count(), 1);
+    // The remaining locals are temporaries introduced by the compiler.
+    for (idx, name) in names.iter().enumerate().skip(1) {
+        assert!(idx == counter || name.starts_with('%'), "{}", name);
+    }
+}
+
+#[test]
+fn no_variable_names_without_debug_info() {
+    let (counter, names) = compile(false);
+    assert_eq!(names[counter], format!("$l{}", counter));
+    assert!(!names.iter().any(|n| n == "my_counter"), "{:?}", names);
+}
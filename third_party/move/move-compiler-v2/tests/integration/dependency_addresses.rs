@@ -0,0 +1,118 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for `Options::dependency_address_overrides`, which compile dependencies with their
+//! own address mappings.
+
+use crate::common;
+use move_binary_format::access::ModuleAccess;
+use move_compiler_v2::{compile_to_bytes, Options};
+
+/// A vendored dependency, written against `owner`, which is deployed at 0xb.
+const DEPENDENCY: &str = "module owner::dep {
+    public fun g(): address { @owner }
+}
+";
+
+fn options(source: &str) -> Options {
+    Options {
+        source_buffers: vec![("sources/m.move".to_owned(), source.to_owned())],
+        dependency_buffers: vec![("vendor/dep.move".to_owned(), DEPENDENCY.to_owned())],
+        named_address_mapping: vec!["owner=0xa".to_owned()],
+        dependency_address_overrides: vec![(
+            "vendor".to_owned(),
+            "owner".to_owned(),
+            "0xb".to_owned(),
+        )],
+        ..Options::default()
+    }
+}
+
+#[test]
+fn dependency_is_compiled_with_its_own_mapping() {
+    let source = "module owner::m {
+    public fun f(): address { 0xb::dep::g() }
+
+    public fun h(): address { @owner }
+}
+";
+    let (env, units) = common::compile(options(source));
+    assert!(env
+        .get_modules()
+        .any(|module_env| module_env.get_full_name_str() == "0xb::dep"));
+    assert_eq!(units.len(), 1);
+    let module = common::compiled_module(&units[0]);
+    assert_eq!(module.self_id().address().to_hex_literal(), "0xa");
+    let dependencies = module
+        .immediate_dependencies()
+        .iter()
+        .map(|id| id.address().to_hex_literal())
+        .collect::<Vec<_>>();
+    assert_eq!(dependencies, vec!["0xb".to_owned()]);
+}
+
+#[test]
+fn reference_via_the_primary_mapping_gets_a_note() {
+    let source = "module owner::m {
+    public fun f(): address { owner::dep::g() }
+}
+";
+    let err = compile_to_bytes(options(source))
+        .expect_err("compilation fails")
+        .to_string();
+    assert!(err.contains("no function named `dep::g` found"), "{}", err);
+    assert!(err.contains("sources/m.move:2:"), "{}", err);
+    assert!(
+        err.contains(
+            "module `dep` is declared at 0xb, since `owner` is mapped to 0xb for dependencies \
+             under `vendor`; refer to it as `0xb::dep`"
+        ),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn conflicting_overrides_are_errors() {
+    let source = "module owner::m {}
+";
+    let mut options = options(source);
+    options.dependency_address_overrides.push((
+        "vendor/dep.move".to_owned(),
+        "owner".to_owned(),
+        "0xc".to_owned(),
+    ));
+    let err = compile_to_bytes(options)
+        .expect_err("compilation fails")
+        .to_string();
+    assert!(err.contains("E00014"), "{}", err);
+    assert!(
+        err.contains("0xb for `vendor` and 0xc for `vendor/dep.move`"),
+        "{}",
+        err
+    );
+    assert!(err.contains("vendor/dep.move:1:1"), "{}", err);
+}
+
+#[test]
+fn overrides_are_parsed_from_the_command_line() {
+    let options = Options::try_parse_command_line([
+        "move-compiler-v2",
+        "--dependency-address-override",
+        "deps/vendor:owner=0xb",
+    ])
+    .expect("options");
+    assert_eq!(options.dependency_address_overrides, vec![(
+        "deps/vendor".to_owned(),
+        "owner".to_owned(),
+        "0xb".to_owned()
+    )]);
+    for invalid in ["owner=0xb", "vendor:owner", "vendor:owner=zz"] {
+        assert!(Options::try_parse_command_line([
+            "move-compiler-v2",
+            "--dependency-address-override",
+            invalid,
+        ])
+        .is_err());
+    }
+}
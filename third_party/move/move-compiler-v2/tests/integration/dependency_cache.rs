@@ -0,0 +1,105 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the cache of dependencies, which lets the checker skip the bodies of functions of
+//! unchanged dependencies.
+
+use crate::common;
+use move_compiler_v2::Options;
+use move_model::model::GlobalEnv;
+use std::path::Path;
+
+const SOURCE: &str = "module 0x42::m {
+    public fun f(x: u64): u64 {
+        0x43::dep::g() + x
+    }
+}
+";
+
+const DEPENDENCY: &str = "module 0x43::dep {
+    public fun g(): u64 {
+        1
+    }
+
+    spec g {
+        ensures result == 1;
+    }
+
+    public inline fun twice(x: u64): u64 {
+        x * 2
+    }
+}
+";
+
+/// The dependency with a changed body of `g`.
+const CHANGED_DEPENDENCY: &str = "module 0x43::dep {
+    public fun g(): u64 {
+        2
+    }
+
+    spec g {
+        ensures result == 2;
+    }
+
+    public inline fun twice(x: u64): u64 {
+        x * 2
+    }
+}
+";
+
+fn options(cache_dir: &Path, dependency: &str) -> Options {
+    Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        dependency_buffers: vec![("dep.move".to_owned(), dependency.to_owned())],
+        dependency_cache_dir: Some(cache_dir.to_path_buf()),
+        ..Options::default()
+    }
+}
+
+/// Compiles with the options, returning the env and the serialized units.
+fn compile(options: Options) -> (GlobalEnv, Vec<Vec<u8>>) {
+    let (env, units) = common::compile(options);
+    (env, common::serialize_units(units))
+}
+
+/// Returns whether the function with the given module and name is native in the env.
+fn is_native(env: &GlobalEnv, module: &str, fun: &str) -> bool {
+    env.get_modules()
+        .find(|m| m.get_full_name_str() == module)
+        .and_then(|m| m.find_function(env.symbol_pool().make(fun)))
+        .expect("function")
+        .is_native()
+}
+
+#[test]
+fn cached_dependencies_give_identical_code() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let (env, units) = compile(options(dir.path(), DEPENDENCY));
+    assert!(!is_native(&env, "0x43::dep", "g"));
+    assert!(dir.path().join("dependencies.cache").exists());
+    let (cached_env, cached_units) = compile(options(dir.path(), DEPENDENCY));
+    // The bodies of functions of dependencies are skipped, except those of inline functions.
+    assert!(is_native(&cached_env, "0x43::dep", "g"));
+    assert!(!is_native(&cached_env, "0x43::dep", "twice"));
+    assert_eq!(units, cached_units);
+}
+
+#[test]
+fn changed_dependencies_are_checked_in_full() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    compile(options(dir.path(), DEPENDENCY));
+    let (env, _) = compile(options(dir.path(), CHANGED_DEPENDENCY));
+    assert!(!is_native(&env, "0x43::dep", "g"));
+    // The entry is replaced, so the next compilation uses the changed dependency.
+    let (env, _) = compile(options(dir.path(), CHANGED_DEPENDENCY));
+    assert!(is_native(&env, "0x43::dep", "g"));
+}
+
+#[test]
+fn cache_dir_is_parsed_from_the_command_line() {
+    let options =
+        Options::try_parse_command_line(["move-compiler-v2", "--dependency-cache-dir", "cache"])
+            .expect("options");
+    assert_eq!(options.dependency_cache_dir, Some("cache".into()));
+    assert_eq!(Options::default().dependency_cache_dir, None);
+}
@@ -0,0 +1,164 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the dependency graphs of modules and functions written in Graphviz DOT format.
+
+use crate::common;
+use move_compiler_v2::{
+    dependency_graph::{FUNCTION_GRAPH_FILE, MODULE_GRAPH_FILE},
+    Options,
+};
+use std::{collections::BTreeSet, fs, path::Path};
+
+const SOURCES: [(&str, &str); 3] = [
+    (
+        "a.move",
+        "module 0x42::a {
+    use 0x42::b::{Self, Wallet};
+    use 0x42::c::Coin;
+
+    public fun make(): Coin { 0x42::c::mint(1) }
+
+    public fun size(w: &Wallet): u64 { b::size(w) }
+
+    public fun even(n: u64): bool { if (n == 0) true else odd(n - 1) }
+
+    fun odd(n: u64): bool { if (n == 0) false else even(n - 1) }
+}
+",
+    ),
+    (
+        "b.move",
+        "module 0x42::b {
+    struct Wallet has drop { coins: vector<0x42::c::Coin> }
+
+    public fun size(w: &Wallet): u64 { std::vector::length(&w.coins) }
+}
+",
+    ),
+    (
+        "c.move",
+        "module 0x42::c {
+    friend 0x42::a;
+
+    struct Coin has store, drop { value: u64 }
+
+    public(friend) fun mint(value: u64): Coin { Coin { value } }
+}
+",
+    ),
+];
+
+/// Compiles the sources, writing the dependency graphs into the directory.
+fn compile(dir: &Path, with_functions: bool) {
+    let options = Options {
+        source_buffers: common::source_buffers(&SOURCES),
+        dependencies: vec!["../move-stdlib/sources".to_owned()],
+        named_address_mapping: vec!["std=0x1".to_owned()],
+        emit_dependency_graph: Some(dir.to_path_buf()),
+        dependency_graph_functions: with_functions,
+        ..Options::default()
+    };
+    common::compile_collecting(options);
+}
+
+/// A node with its attributes.
+type Node = (String, String);
+/// An edge with its source, target, and attributes.
+type Edge = (String, String, String);
+
+/// Parses the nodes and edges of the graph.
+fn parse(dot: &str) -> (BTreeSet<Node>, BTreeSet<Edge>) {
+    let unquote = |s: &str| s.trim().trim_matches('"').to_owned();
+    let mut nodes = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for line in dot.lines().map(str::trim) {
+        let Some(stmt) = line.strip_suffix(';').filter(|s| s.starts_with('"')) else {
+            continue;
+        };
+        let (stmt, attributes) = match stmt.split_once(" [") {
+            Some((stmt, attributes)) => (stmt, attributes.trim_end_matches(']').to_owned()),
+            None => (stmt, String::new()),
+        };
+        match stmt.split_once(" -> ") {
+            Some((from, to)) => edges.insert((unquote(from), unquote(to), attributes)),
+            None => nodes.insert((unquote(stmt), attributes)),
+        };
+    }
+    (nodes, edges)
+}
+
+fn edge(from: &str, to: &str, attributes: &str) -> Edge {
+    (from.to_owned(), to.to_owned(), attributes.to_owned())
+}
+
+#[test]
+fn module_graph() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    compile(dir.path(), false);
+    let dot = fs::read_to_string(dir.path().join(MODULE_GRAPH_FILE)).expect("module graph");
+    assert!(dot.starts_with("digraph modules {"));
+    let (nodes, edges) = parse(&dot);
+    assert_eq!(
+        nodes,
+        BTreeSet::from([
+            (
+                "0x1::vector".to_owned(),
+                "style=filled, fillcolor=lightgray".to_owned()
+            ),
+            (
+                "0x42::a".to_owned(),
+                "style=filled, fillcolor=lightblue".to_owned()
+            ),
+            (
+                "0x42::b".to_owned(),
+                "style=filled, fillcolor=lightblue".to_owned()
+            ),
+            (
+                "0x42::c".to_owned(),
+                "style=filled, fillcolor=lightblue".to_owned()
+            ),
+        ])
+    );
+    assert_eq!(
+        edges,
+        BTreeSet::from([
+            edge("0x42::a", "0x42::b", "label=\"calls\""),
+            edge("0x42::a", "0x42::c", "label=\"calls\""),
+            edge("0x42::a", "0x42::c", "label=\"friend\", style=dotted"),
+            edge("0x42::b", "0x42::c", "label=\"uses\", style=dashed"),
+            edge("0x42::b", "0x1::vector", "label=\"calls\""),
+        ])
+    );
+    assert!(!dir.path().join(FUNCTION_GRAPH_FILE).exists());
+}
+
+#[test]
+fn function_graph() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    compile(dir.path(), true);
+    let dot = fs::read_to_string(dir.path().join(FUNCTION_GRAPH_FILE)).expect("function graph");
+    assert!(dot.starts_with("digraph functions {"));
+    let (nodes, edges) = parse(&dot);
+    assert_eq!(
+        nodes.into_iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        vec![
+            "0x42::a::even",
+            "0x42::a::make",
+            "0x42::a::odd",
+            "0x42::a::size",
+            "0x42::b::size",
+            "0x42::c::mint",
+        ]
+    );
+    // Calls into dependencies are not included, and the calls of the recursion are on a cycle.
+    assert_eq!(
+        edges,
+        BTreeSet::from([
+            edge("0x42::a::even", "0x42::a::odd", "color=red"),
+            edge("0x42::a::make", "0x42::c::mint", ""),
+            edge("0x42::a::odd", "0x42::a::even", "color=red"),
+            edge("0x42::a::size", "0x42::b::size", ""),
+        ])
+    );
+}
@@ -0,0 +1,84 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests that compiling the same package produces the same units, byte for byte, regardless
+//! of the order in which sources are given.
+
+use crate::common;
+use move_compiler::compiled_unit::CompiledUnitEnum;
+use move_compiler_v2::Options;
+
+const SOURCES: &[(&str, &str)] = &[
+    (
+        "z.move",
+        "module 0x42::z {
+    struct R has key { x: u64, v: vector<u8> }
+    const LIMIT: u64 = 10;
+    public fun make(x: u64): R { R { x, v: b\"z\" } }
+    public fun limit(): u64 { LIMIT }
+}
+",
+    ),
+    (
+        "b.move",
+        "module 0x2::b {
+    public fun g(x: u128): u128 { x * 3 }
+}
+",
+    ),
+    (
+        "a.move",
+        "module 0x42::a {
+    use 0x42::z;
+    use 0x2::b;
+    public fun f(x: u64): u64 { x + z::limit() + (b::g((x as u128)) as u64) }
+    public fun make(): z::R { z::make(f(1)) }
+}
+",
+    ),
+    (
+        "s.move",
+        "script {
+    fun main() { assert!(0x42::a::f(1) > 0, 1) }
+}
+",
+    ),
+];
+
+/// Compiles the sources in the given order, returning the names and serialized bytes of the
+/// units.
+fn compile(
+    sources: impl IntoIterator<Item = (&'static str, &'static str)>,
+) -> Vec<(String, Vec<u8>)> {
+    let options = Options {
+        source_buffers: sources
+            .into_iter()
+            .map(|(name, content)| (name.to_owned(), content.to_owned()))
+            .collect(),
+        ..Options::default()
+    };
+    let (_, units) = common::compile(options);
+    units
+        .into_iter()
+        .map(|unit| {
+            let unit = unit.into_compiled_unit();
+            let name = match &unit {
+                CompiledUnitEnum::Module(module) => module.module.self_id().short_str_lossless(),
+                CompiledUnitEnum::Script(script) => script.name.to_string(),
+            };
+            (name, unit.serialize(None))
+        })
+        .collect()
+}
+
+#[test]
+fn units_are_identical_across_compilations() {
+    let first = compile(SOURCES.iter().cloned());
+    let names = first
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["0x2::b", "0x42::a", "0x42::z", "main"]);
+    assert_eq!(compile(SOURCES.iter().cloned()), first);
+    assert_eq!(compile(SOURCES.iter().rev().cloned()), first);
+}
@@ -0,0 +1,67 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the registry of diagnostic codes, and the codes of the diagnostics in the
+//! baselines of the test corpus.
+
+use move_command_line_common::files::find_filenames;
+use move_compiler_v2::diagnostic_codes;
+use std::{collections::BTreeSet, fs};
+
+#[test]
+fn registry_codes_are_unique_and_well_formed() {
+    let mut seen = BTreeSet::new();
+    for code in diagnostic_codes::registry() {
+        assert!(seen.insert(code.code), "duplicate code {}", code.code);
+        let (kind, digits) = code.code.split_at(1);
+        assert!(
+            matches!(kind, "E" | "W" | "N")
+                && digits.len() == 5
+                && digits.chars().all(|c| c.is_ascii_digit()),
+            "malformed code {}",
+            code.code
+        );
+        assert!(!code.summary.is_empty() && !code.explanation.is_empty());
+        assert_eq!(diagnostic_codes::find(code.code), Some(code));
+    }
+    // Codes are distinct also when ignoring the severity letter.
+    let numbers = diagnostic_codes::registry()
+        .iter()
+        .map(|code| &code.code[1..])
+        .collect::<BTreeSet<_>>();
+    assert_eq!(numbers.len(), seen.len());
+}
+
+#[test]
+fn emitted_codes_are_registered() {
+    let baselines = find_filenames(&["tests"], |path| {
+        path.extension().map_or(false, |ext| ext == "exp")
+    })
+    .expect("baselines");
+    let mut emitted = BTreeSet::new();
+    for path in baselines {
+        let content = fs::read_to_string(&path).expect("read baseline");
+        for line in content.lines() {
+            for severity in ["bug[", "error[", "warning[", "note["] {
+                if let Some(rest) = line.strip_prefix(severity) {
+                    let code = rest.split(']').next().unwrap_or_default();
+                    assert!(
+                        diagnostic_codes::find(code).is_some(),
+                        "unregistered code `{}` in {}",
+                        code,
+                        path
+                    );
+                    emitted.insert(code.to_owned());
+                }
+            }
+        }
+    }
+    // The corpus covers diagnostics of each phase which can be triggered from sources.
+    for prefix in ["E01", "E02", "W02", "E03"] {
+        assert!(
+            emitted.iter().any(|code| code.starts_with(prefix)),
+            "no code with prefix {} emitted in the test corpus",
+            prefix
+        );
+    }
+}
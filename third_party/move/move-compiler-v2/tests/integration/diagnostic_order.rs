@@ -0,0 +1,93 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests that diagnostics are reported in the same order, whatever the order in which the
+//! processors of the pipeline report them.
+
+use crate::common;
+use codespan_reporting::diagnostic::Severity;
+use move_compiler_v2::{diagnostics::CompilerDiagnostic, Options, ProcessorPosition};
+use move_model::model::FunctionEnv;
+use move_stackless_bytecode::{
+    function_target::FunctionData,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+};
+
+const SOURCE: &str = "module 0x42::m {
+    fun f(): u64 {
+        1
+    }
+
+    public fun g(): u64 {
+        f()
+    }
+}
+";
+
+/// A processor which warns about each function, with secondary labels at the other functions
+/// of its module.
+struct Warner(&'static str);
+
+impl FunctionTargetProcessor for Warner {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        let labels = fun_env
+            .module_env
+            .get_functions()
+            .filter(|other| other.get_id() != fun_env.get_id())
+            .map(|other| (other.get_loc(), format!("{} also sees this", self.0)))
+            .collect();
+        fun_env.module_env.env.diag_with_labels(
+            Severity::Warning,
+            &fun_env.get_loc(),
+            &format!("{} warns about `{}`", self.0, fun_env.get_name_str()),
+            labels,
+        );
+        data
+    }
+
+    fn name(&self) -> String {
+        self.0.to_owned()
+    }
+}
+
+fn options(first: &'static str, second: &'static str) -> Options {
+    let mut options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..Options::default()
+    };
+    for name in [first, second] {
+        options.register_pipeline_extension(ProcessorPosition::End, move || Box::new(Warner(name)))
+    }
+    options
+}
+
+fn rendered(options: Options) -> String {
+    let (ok, diags) = common::compile_reporting(options);
+    assert!(ok, "{}", diags);
+    diags
+}
+
+fn collected(options: Options) -> Vec<CompilerDiagnostic> {
+    common::compile_collecting(options).diagnostics
+}
+
+#[test]
+fn order_of_processors_does_not_change_output() {
+    let text = rendered(options("first", "second"));
+    assert_eq!(text, rendered(options("second", "first")));
+    let diags = collected(options("first", "second"));
+    assert_eq!(diags, collected(options("second", "first")));
+    let messages = diags.iter().map(|d| d.message.as_str()).collect::<Vec<_>>();
+    assert_eq!(messages, vec![
+        "first warns about `f`",
+        "second warns about `f`",
+        "first warns about `g`",
+        "second warns about `g`",
+    ]);
+}
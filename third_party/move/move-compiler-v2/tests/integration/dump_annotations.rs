@@ -0,0 +1,73 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the annotations of the analyses of the pipeline in bytecode dumps.
+
+use crate::common;
+use move_compiler_v2::Options;
+use std::fs;
+
+const SOURCE: &str = "module 0x42::m {
+    public fun f(x: u64): u64 {
+        x + 1
+    }
+}
+";
+
+/// Compiles the source, dumping the bytecode, and returns the comment lines of the dump after
+/// the live variable analysis.
+fn live_var_dump_comments(options: Options) -> Vec<String> {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        dump_bytecode_dir: Some(dir.path().to_owned()),
+        ..options
+    };
+    common::compile(options);
+    let path = dir
+        .path()
+        .join("0x42_m_3_LiveVarAnalysisProcessor.bytecode");
+    let dump = fs::read_to_string(path).expect("dump");
+    dump.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn live_vars_are_dumped_on_demand() {
+    assert_eq!(
+        live_var_dump_comments(Options {
+            dump_annotations: true,
+            ..Options::default()
+        }),
+        vec![
+            "# live vars: before {$t0}, after {$t0, $t2}",
+            "# live vars: before {$t0, $t2}, after {$t1}",
+            "# live vars: before {$t1}, after {}",
+        ]
+    );
+}
+
+#[test]
+fn annotations_are_not_dumped_by_default() {
+    assert!(live_var_dump_comments(Options::default()).is_empty());
+}
+
+#[test]
+fn registered_formatters_are_dumped() {
+    let mut options = Options {
+        dump_annotations: true,
+        ..Options::default()
+    };
+    options.register_annotation_formatter(|target, offset| {
+        Some(format!("{} at {}", target.func_env.get_name_str(), offset))
+    });
+    let comments = live_var_dump_comments(options);
+    assert!(
+        comments.contains(&"# f at 2".to_owned()),
+        "unexpected comments: {:?}",
+        comments
+    );
+}
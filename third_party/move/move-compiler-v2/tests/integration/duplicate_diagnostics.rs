@@ -0,0 +1,146 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests that identical diagnostics are reported once, with the number of occurrences.
+
+use crate::common;
+use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
+use move_command_line_common::files::FileHash;
+use move_compiler_v2::{diagnostics, DiagnosticsFormat, Options, ProcessorPosition};
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::{
+    function_target::FunctionData,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+};
+use std::{collections::BTreeMap, rc::Rc};
+
+const SOURCE: &str = "module 0x42::m {
+    fun f(x: u64): u64 {
+        x + 1
+    }
+}
+";
+
+/// A processor which reports the same problem at the location of each function a number of
+/// times, as happens when a problem is found once per call site of a generic function.
+struct RepeatedReporter {
+    times: usize,
+    /// Whether each report carries a secondary label of its own.
+    distinct_context: bool,
+}
+
+impl FunctionTargetProcessor for RepeatedReporter {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        let env = fun_env.module_env.env;
+        let loc = fun_env.get_loc();
+        for i in 0..self.times {
+            let mut labels = vec![Label::primary(loc.file_id(), loc.span())];
+            if self.distinct_context {
+                labels.push(
+                    Label::secondary(loc.file_id(), loc.span())
+                        .with_message(format!("instantiation {}", i)),
+                )
+            }
+            env.add_diag(
+                Diagnostic::error()
+                    .with_message("repeated problem")
+                    .with_labels(labels),
+            )
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "RepeatedReporter".to_owned()
+    }
+}
+
+/// Compiles the source with the reporter, returning the diagnostics in the given format.
+fn diagnostics_in(
+    times: usize,
+    distinct_context: bool,
+    diagnostics_format: DiagnosticsFormat,
+) -> String {
+    let mut options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        diagnostics_format,
+        ..Options::default()
+    };
+    options.register_pipeline_extension(ProcessorPosition::End, move || {
+        Box::new(RepeatedReporter {
+            times,
+            distinct_context,
+        })
+    });
+    common::try_compile(options).expect_err("compilation fails")
+}
+
+/// Compiles the source with the reporter, returning the diagnostics as text.
+fn diagnostics(times: usize, distinct_context: bool) -> String {
+    diagnostics_in(times, distinct_context, DiagnosticsFormat::Text)
+}
+
+#[test]
+fn identical_diagnostics_are_collapsed() {
+    let diags = diagnostics(10, false);
+    assert_eq!(diags.matches("repeated problem").count(), 1, "{}", diags);
+    assert!(diags.contains("reported 10 times"), "{}", diags);
+}
+
+#[test]
+fn single_diagnostic_has_no_count() {
+    let diags = diagnostics(1, false);
+    assert_eq!(diags.matches("repeated problem").count(), 1, "{}", diags);
+    assert!(!diags.contains("reported"), "{}", diags);
+}
+
+#[test]
+fn diagnostics_with_distinct_secondary_labels_are_kept() {
+    let diags = diagnostics(3, true);
+    assert_eq!(diags.matches("repeated problem").count(), 3, "{}", diags);
+    assert!(!diags.contains("reported"), "{}", diags);
+}
+
+#[test]
+fn identical_diagnostics_are_collapsed_in_json() {
+    let diags = diagnostics_in(10, false, DiagnosticsFormat::Json);
+    let lines = diags
+        .lines()
+        .filter(|line| line.contains("repeated problem"))
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{}", diags);
+    assert!(lines[0].contains("reported 10 times"), "{}", diags);
+}
+
+#[test]
+fn identical_diagnostics_are_reported_once_as_json() {
+    // Diagnostics which were not collapsed before are still reported once, as in the text
+    // format.
+    let mut env = GlobalEnv::new();
+    let file_id = env.add_source(
+        FileHash::new(SOURCE),
+        Rc::new(BTreeMap::new()),
+        "m.move",
+        SOURCE,
+        false,
+    );
+    for _ in 0..3 {
+        env.add_diag(
+            Diagnostic::error()
+                .with_message("repeated problem")
+                .with_labels(vec![Label::primary(file_id, 0..6)]),
+        )
+    }
+    env.add_diag(Diagnostic::error().with_message("other problem"));
+    let mut output = vec![];
+    let count = diagnostics::report_diags_as_json(&env, &mut output, Severity::Warning);
+    let output = String::from_utf8(output).expect("utf8");
+    assert_eq!(count, 2, "{}", output);
+    assert_eq!(output.matches("repeated problem").count(), 1, "{}", output);
+}
@@ -0,0 +1,131 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the `expensive_copy` lint, which warns about copies of large values which are
+//! only borrowed afterwards.
+
+use move_compiler_v2::{run_move_compiler_collecting, Options};
+
+const SOURCE: &str = "module 0x42::m {
+    use std::vector;
+
+    struct Small has copy, drop { a: u64, b: u64 }
+    struct Wide has copy, drop { a: u64, b: u64, c: u64, d: u64 }
+    struct Holder has copy, drop { items: vector<u64> }
+
+    fun deref_borrowed(r: &vector<u64>): u64 {
+        let v = *r;
+        vector::length(&v)
+    }
+
+    fun field_borrowed(h: &Holder): u64 {
+        let items = h.items;
+        vector::length(&items)
+    }
+
+    fun assign_borrowed(v: vector<u64>): (u64, vector<u64>) {
+        let w = v;
+        let n = vector::length(&w);
+        (n, v)
+    }
+
+    fun wide_borrowed(r: &Wide): u64 {
+        let w = *r;
+        let x = &w;
+        x.a
+    }
+
+    fun small_borrowed(r: &Small): u64 {
+        let s = *r;
+        let x = &s;
+        x.a
+    }
+
+    fun deref_mutated(r: &vector<u64>): vector<u64> {
+        let v = *r;
+        vector::push_back(&mut v, 1);
+        v
+    }
+
+    fun assign_moved(v: vector<u64>): vector<u64> {
+        let w = v;
+        vector::push_back(&mut w, 1);
+        w
+    }
+
+    fun original_mutated(v: vector<u64>): u64 {
+        let old = v;
+        vector::push_back(&mut v, 1);
+        vector::length(&old) + vector::length(&v)
+    }
+
+    fun deref_through_mut(r: &mut vector<u64>): u64 {
+        let v = *r;
+        vector::length(&v)
+    }
+
+    #[lint::allow(expensive_copy)]
+    fun allowed(r: &vector<u64>): u64 {
+        let v = *r;
+        vector::length(&v)
+    }
+}
+";
+
+/// Compiles the source, returning the line and message of each warning of the lint.
+fn warnings(options: Options) -> Vec<(usize, String)> {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        dependencies: vec!["../move-stdlib/sources".to_owned()],
+        named_address_mapping: vec!["std=0x1".to_owned()],
+        ..options
+    };
+    run_move_compiler_collecting(options)
+        .expect("compiles")
+        .diagnostics
+        .into_iter()
+        .filter(|d| d.code.as_deref() == Some("W02080"))
+        .map(|d| {
+            (
+                d.location.map(|l| l.start_line).unwrap_or_default(),
+                d.message,
+            )
+        })
+        .collect()
+}
+
+fn warning(line: usize, kind: &str, ty: &str) -> (usize, String) {
+    (
+        line,
+        format!(
+            "{} copies a large value of type `{}` which is only borrowed afterwards; consider \
+             borrowing instead",
+            kind, ty
+        ),
+    )
+}
+
+#[test]
+fn copies_which_are_only_borrowed_are_reported() {
+    assert_eq!(warnings(Options::default()), vec![
+        warning(9, "dereference", "vector<u64>"),
+        warning(14, "dereference", "vector<u64>"),
+        warning(18, "assignment", "vector<u64>"),
+    ]);
+}
+
+#[test]
+fn large_struct_fields_is_configurable() {
+    assert_eq!(
+        warnings(Options {
+            large_struct_fields: 2,
+            ..Options::default()
+        }),
+        vec![
+            warning(9, "dereference", "vector<u64>"),
+            warning(14, "dereference", "vector<u64>"),
+            warning(18, "assignment", "vector<u64>"),
+            warning(25, "dereference", "m::Wide"),
+        ]
+    );
+}
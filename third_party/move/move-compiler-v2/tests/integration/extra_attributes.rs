@@ -0,0 +1,95 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for attributes defined by embedders via `Options::extra_known_attributes`.
+
+use crate::common;
+use move_compiler_v2::{
+    diagnostics::CompilerDiagnostic, AttributeArguments, AttributePosition, AttributeSpec, Options,
+};
+use std::collections::BTreeSet;
+
+fn marker_spec() -> AttributeSpec {
+    AttributeSpec {
+        name: "marker".to_owned(),
+        positions: BTreeSet::from([AttributePosition::Struct]),
+        arguments: AttributeArguments::None,
+    }
+}
+
+/// Compiles the source with the specs, returning the diagnostics, whether compilation
+/// succeeded or not.
+fn diags(source: &str, specs: Vec<AttributeSpec>) -> Vec<CompilerDiagnostic> {
+    common::collected_diagnostics(Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        extra_known_attributes: specs,
+        ..Options::default()
+    })
+}
+
+#[test]
+fn attribute_accepted_on_struct() {
+    let source = "module 0x42::m {
+    #[marker]
+    struct S has drop { x: u64 }
+
+    public fun make(): S {
+        S { x: 0 }
+    }
+}
+";
+    assert_eq!(diags(source, vec![marker_spec()]), vec![]);
+    // Without the spec, the attribute is unknown, as the label of the warning says.
+    assert!(diags(source, vec![]).iter().any(|d| d
+        .labels
+        .iter()
+        .any(|l| l.message.contains("'marker' is unknown"))));
+}
+
+#[test]
+fn attribute_rejected_on_function() {
+    let source = "module 0x42::m {
+    #[marker]
+    public fun f() {}
+}
+";
+    let errors = diags(source, vec![marker_spec()])
+        .into_iter()
+        .map(|d| (d.code.unwrap_or_default(), d.message))
+        .collect::<Vec<_>>();
+    assert_eq!(errors, vec![(
+        "E00009".to_owned(),
+        "attribute `marker` is not allowed on a function, only on: structs".to_owned()
+    )]);
+}
+
+#[test]
+fn attribute_with_malformed_arguments() {
+    let source = "module 0x42::m {
+    #[marker(x)]
+    struct S has drop { x: u64 }
+
+    #[tag]
+    struct T has drop { x: u64 }
+}
+";
+    let tag_spec = AttributeSpec {
+        name: "tag".to_owned(),
+        positions: BTreeSet::from([AttributePosition::Struct]),
+        arguments: AttributeArguments::Value,
+    };
+    let errors = diags(source, vec![marker_spec(), tag_spec])
+        .into_iter()
+        .map(|d| (d.code.unwrap_or_default(), d.message))
+        .collect::<Vec<_>>();
+    assert_eq!(errors, vec![
+        (
+            "E00010".to_owned(),
+            "attribute `marker` expects no arguments, as in `#[marker]`".to_owned()
+        ),
+        (
+            "E00010".to_owned(),
+            "attribute `tag` expects a value, as in `#[tag = <value>]`".to_owned()
+        ),
+    ]);
+}
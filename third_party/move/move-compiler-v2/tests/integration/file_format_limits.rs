@@ -0,0 +1,96 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests that violations of the limits of the file format are reported with the offending
+//! count, rather than producing a module which does not verify.
+
+use move_compiler_v2::{run_move_compiler_collecting, Options};
+
+/// Compiles the source, expecting a single diagnostic of a violated limit among others like
+/// unused items, and returns the count it reports after checking the rest of its message.
+fn violation_count(source: String, subject: &str, kind: &str, limit: usize, hint: &str) -> usize {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), source)],
+        ..Options::default()
+    };
+    let diags = run_move_compiler_collecting(options)
+        .err()
+        .expect("compilation fails")
+        .into_iter()
+        .filter(|d| d.code.as_deref() == Some("E03002"))
+        .collect::<Vec<_>>();
+    assert_eq!(diags.len(), 1, "{:?}", diags);
+    let diag = &diags[0];
+    let prefix = format!("{} has ", subject);
+    let suffix = format!(
+        " {}, exceeding the file format limit of {}; consider splitting the {}",
+        kind, limit, hint
+    );
+    let count = diag
+        .message
+        .strip_prefix(&prefix)
+        .and_then(|rest| rest.strip_suffix(&suffix))
+        .unwrap_or_else(|| panic!("unexpected message: {}", diag.message));
+    let count = count.parse().expect("count");
+    assert!(count > limit, "{}", diag.message);
+    count
+}
+
+#[test]
+fn too_many_locals() {
+    // The locals are collected in a vector literal rather than summed up, as the checker
+    // recurses over nested expressions.
+    let lets = (0..300)
+        .map(|i| format!("        let x{} = p + {};\n", i, i))
+        .collect::<String>();
+    let elems = (0..300)
+        .map(|i| format!("x{}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let source = format!(
+        "module 0x42::m {{\n    public fun f(p: u64): vector<u64> {{\n{}        vector[{}]\n    \
+         }}\n}}\n",
+        lets, elems
+    );
+    violation_count(source, "function `0x42::m::f`", "locals", 255, "function");
+}
+
+#[test]
+fn too_many_instructions() {
+    // Few statements with flat vector literals, each copying the parameter 2000 times, as the
+    // checker recurses over sequences and nested expressions.
+    let elems = vec!["x"; 2_000].join(", ");
+    let stmts = format!("        v = vector[{}];\n", elems).repeat(40);
+    let source = format!(
+        "module 0x42::m {{\n    public fun f(x: u64): vector<u64> {{\n        let v;\n{}        \
+         v\n    }}\n}}\n",
+        stmts
+    );
+    violation_count(
+        source,
+        "function `0x42::m::f`",
+        "instructions",
+        65535,
+        "function",
+    );
+}
+
+#[test]
+fn too_many_identifiers() {
+    // Field names are identifiers of the module, but there are at most 255 fields per struct.
+    let structs = (0..300)
+        .map(|i| {
+            let fields = (0..220)
+                .map(|j| format!("f{}_{}: u64", i, j))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("    struct S{} {{ {} }}\n", i, fields)
+        })
+        .collect::<String>();
+    let source = format!("module 0x42::m {{\n{}}}\n", structs);
+    // The module name, the struct names, and the field names.
+    assert_eq!(
+        violation_count(source, "module `0x42::m`", "identifiers", 65535, "module"),
+        1 + 300 + 300 * 220
+    );
+}
@@ -0,0 +1,101 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for function inlining, checking that inlined functions compute the same results as
+//! called ones by running the unit tests of a module with and without inlining. Compilation
+//! runs the bytecode verifier, so the inlined code is also checked to be valid.
+
+use crate::common;
+use move_binary_format::file_format::Bytecode;
+use move_compiler::compiled_unit::AnnotatedCompiledUnit;
+use move_compiler_v2::{Experiment, Options};
+use move_model::model::GlobalEnv;
+
+const SOURCE: &str = "module 0x42::m {
+    struct Pair<T> has copy, drop {
+        first: T,
+        second: T
+    }
+
+    fun first<T: copy>(p: &Pair<T>): T {
+        p.first
+    }
+
+    fun swap<T: copy + drop>(p: &mut Pair<T>) {
+        let first = p.first;
+        p.first = p.second;
+        p.second = first
+    }
+
+    fun check(x: u64) {
+        if (x > 100) abort 1
+    }
+
+    #[inline]
+    public fun clamp(x: u64, low: u64, high: u64): u64 {
+        if (x < low) return low;
+        if (x > high) return high;
+        x
+    }
+
+    public fun run(x: u64): u64 {
+        check(x);
+        let p = Pair { first: x, second: clamp(x, 10, 20) };
+        swap(&mut p);
+        first(&p) + clamp(first(&p), 0, 15)
+    }
+
+    #[test]
+    fun run_works() {
+        assert!(run(5) == 20, 0);
+        assert!(run(12) == 24, 1);
+        assert!(run(30) == 35, 2);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 1, location = Self)]
+    fun run_aborts() {
+        run(101);
+    }
+}
+";
+
+fn compile(
+    experiments: Vec<String>,
+    compile_test_code: bool,
+) -> (GlobalEnv, Vec<AnnotatedCompiledUnit>) {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        experiments,
+        compile_test_code,
+        ..Options::default()
+    };
+    common::compile(options)
+}
+
+/// Returns the number of calls in the code generated for `run`.
+fn calls_in_run(experiments: Vec<String>) -> usize {
+    let (_, units) = compile(experiments, false);
+    common::function_code(&common::compiled_module(&units[0]), "run")
+        .iter()
+        .filter(|bc| matches!(bc, Bytecode::Call(_) | Bytecode::CallGeneric(_)))
+        .count()
+}
+
+#[test]
+fn inlining_removes_calls() {
+    assert_eq!(calls_in_run(vec![]), 6);
+    assert_eq!(
+        calls_in_run(vec![Experiment::FUNCTION_INLINING.to_owned()]),
+        0
+    );
+}
+
+#[test]
+fn inlined_code_passes_tests() {
+    for experiments in [vec![], vec![Experiment::FUNCTION_INLINING.to_owned()]] {
+        let (env, units) = compile(experiments.clone(), true);
+        let (output, all_passed) = common::run_unit_tests(&env, units);
+        assert!(all_passed, "{:?}: {}", experiments, output);
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for sharing locals between temporaries whose live ranges do not overlap. The source
+//! is generated with more variables than the file format allows locals in a function.
+//! Compilation runs the bytecode verifier, and the unit tests of the module check that the
+//! code still computes the same results.
+
+use crate::common;
+use move_binary_format::access::ModuleAccess;
+use move_compiler::compiled_unit::AnnotatedCompiledUnit;
+use move_compiler_v2::Options;
+use move_model::model::GlobalEnv;
+
+/// The number of variables in the generated function, more than the 255 locals allowed.
+const VARIABLE_COUNT: usize = 300;
+
+/// Generates a module with a function which defines a chain of variables, each used twice by
+/// the next one, so none of them can be kept on the stack only. The chain doubles its input
+/// modulo 1000, which a unit test checks.
+fn generate_source() -> String {
+    let mut body = String::from("        let x0 = p;\n");
+    for i in 1..VARIABLE_COUNT {
+        body.push_str(&format!(
+            "        let x{} = (x{} + x{}) % 1000;\n",
+            i,
+            i - 1,
+            i - 1
+        ));
+    }
+    body.push_str(&format!("        x{}\n", VARIABLE_COUNT - 1));
+    let expected = (1..VARIABLE_COUNT).fold(3u64, |x, _| (x + x) % 1000);
+    format!(
+        "module 0x42::m {{
+    public fun chain(p: u64): u64 {{
+{}    }}
+
+    public fun borrowed(p: u64): u64 {{
+        let x = p + 1;
+        let r = &mut x;
+        *r = *r + 1;
+        let y = p + 2;
+        x + y
+    }}
+
+    #[test]
+    fun chain_works() {{
+        assert!(chain(3) == {}, 0)
+    }}
+
+    #[test]
+    fun borrowed_works() {{
+        assert!(borrowed(1) == 6, 0)
+    }}
+}}
+",
+        body, expected
+    )
+}
+
+fn compile(compile_test_code: bool) -> (GlobalEnv, Vec<AnnotatedCompiledUnit>) {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), generate_source())],
+        compile_test_code,
+        ..Options::default()
+    };
+    common::compile(options)
+}
+
+#[test]
+fn locals_are_shared() {
+    let (_, units) = compile(false);
+    let module = common::compiled_module(&units[0]);
+    for def in module.function_defs() {
+        let name = module.identifier_at(module.function_handle_at(def.function).name);
+        let locals = module.signature_at(def.code.as_ref().expect("code").locals);
+        // The chain needs a few locals only, since each variable dies with the next one.
+        assert!(locals.len() < 10, "{}: {} locals", name, locals.len());
+    }
+}
+
+#[test]
+fn shared_locals_pass_tests() {
+    let (env, units) = compile(true);
+    let (output, all_passed) = common::run_unit_tests(&env, units);
+    assert!(all_passed, "{}", output);
+}
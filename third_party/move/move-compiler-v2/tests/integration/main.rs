@@ -0,0 +1,67 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests which drive the compiler through its API, with a module per feature. They are built
+//! into a single test binary, next to the baseline tests of `testsuite.rs`. The test of
+//! `coverage_map.rs` has a binary of its own, since it enables VM tracing for its process.
+
+mod abi;
+mod analysis;
+mod artifacts;
+mod build_config;
+mod bytecode_dependencies;
+mod bytecode_text;
+mod cfg_simplification;
+mod check_only;
+mod codegen_errors;
+mod collected_diagnostics;
+mod color;
+mod common;
+mod compilation_cache;
+mod compilation_stats;
+mod compile_to_bytes;
+mod compiler_comparison;
+mod compiler_output;
+mod constant_pool;
+mod dead_functions;
+mod debug_info;
+mod dependency_addresses;
+mod dependency_cache;
+mod dependency_graph;
+mod deterministic_output;
+mod diagnostic_codes;
+mod diagnostic_order;
+mod dump_annotations;
+mod duplicate_diagnostics;
+mod expensive_copy;
+mod extra_attributes;
+mod file_format_limits;
+mod function_inlining;
+mod local_coalescing;
+mod max_diagnostics;
+mod module_filter;
+mod must_use;
+mod optimization_levels;
+mod options_loading;
+mod package_metadata;
+mod partial_compilation;
+mod peephole_optimization;
+mod pipeline_extensions;
+mod progress;
+mod publish_bundle;
+mod recheck;
+mod report_severity;
+mod reproducible_builds;
+mod sarif;
+mod shadowing;
+mod source_buffers;
+mod source_paths;
+mod source_positions;
+mod spec_info;
+mod stack_depth;
+mod tracing_spans;
+mod unchecked_attributes;
+mod unit_tests;
+mod unused_dependencies;
+mod vector_constants;
+mod verbosity;
@@ -0,0 +1,109 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the limit of the number of diagnostics which are shown.
+
+use crate::common;
+use move_compiler_v2::Options;
+
+/// Returns a module with the given number of functions which each have an unused variable.
+fn warnings_source(count: usize) -> String {
+    let funs = (0..count)
+        .map(|i| format!("    public fun f{}(x: u64): u64 {{ let y = 1; x }}\n", i))
+        .collect::<String>();
+    format!("module 0x42::m {{\n{}}}\n", funs)
+}
+
+/// A source with unused aliases, which are warnings, before type errors.
+const MIXED_SOURCE: &str = "module 0x42::n {
+    public fun a() {}
+    public fun b() {}
+    public fun c() {}
+}
+
+module 0x42::m {
+    use 0x42::n::a;
+    use 0x42::n::b;
+    use 0x42::n::c;
+
+    public fun f1(): u64 { true }
+    public fun f2(): u64 { false }
+    public fun f3(): u64 { @0x1 }
+}
+";
+
+/// Compiles the source with the given command line arguments, returning whether compilation
+/// succeeded and the diagnostics.
+fn compile(source: &str, args: &[&str]) -> (bool, String) {
+    common::compile_reporting(Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        ..common::parse_options(args)
+    })
+}
+
+fn count(diags: &str, prefix: &str) -> usize {
+    diags.lines().filter(|l| l.starts_with(prefix)).count()
+}
+
+#[test]
+fn diagnostics_beyond_limit_are_summarized() {
+    let (ok, diags) = compile(&warnings_source(8), &["--max-diagnostics", "3"]);
+    assert!(ok);
+    assert_eq!(count(&diags, "warning["), 3, "{}", diags);
+    assert!(
+        diags.contains("note: 5 more warnings not shown; use `--max-diagnostics` to show more"),
+        "{}",
+        diags
+    );
+    // The first warnings in source order are shown.
+    for line in 2..=4 {
+        assert!(diags.contains(&format!("m.move:{}:", line)), "{}", diags);
+    }
+    assert!(!diags.contains("m.move:5:"), "{}", diags);
+}
+
+#[test]
+fn diagnostics_within_limit_are_all_shown() {
+    let source = warnings_source(8);
+    for args in [&["--max-diagnostics", "8"][..], &["--max-diagnostics", "0"]] {
+        let (ok, diags) = compile(&source, args);
+        assert!(ok);
+        assert_eq!(count(&diags, "warning["), 8, "{}", diags);
+        assert!(!diags.contains("not shown"), "{}", diags);
+    }
+    assert_eq!(Options::default().max_diagnostics(), Some(50));
+    let (_, diags) = compile(&warnings_source(60), &[]);
+    assert_eq!(count(&diags, "warning["), 50);
+    assert!(
+        diags.contains("note: 10 more warnings not shown"),
+        "{}",
+        diags
+    );
+}
+
+#[test]
+fn errors_are_preferred_over_warnings() {
+    let (ok, diags) = compile(MIXED_SOURCE, &["--max-diagnostics", "2"]);
+    // Compilation fails because of all errors, not only the shown ones.
+    assert!(!ok);
+    assert_eq!(count(&diags, "error"), 2, "{}", diags);
+    assert_eq!(count(&diags, "warning"), 0, "{}", diags);
+    assert!(
+        diags.contains("note: 1 more error and 3 more warnings not shown"),
+        "{}",
+        diags
+    );
+}
+
+#[test]
+fn json_diagnostics_are_not_limited() {
+    let (ok, diags) = compile(MIXED_SOURCE, &[
+        "--max-diagnostics",
+        "2",
+        "--diagnostics-format",
+        "json",
+    ]);
+    assert!(!ok);
+    assert_eq!(diags.lines().count(), 6, "{}", diags);
+    assert!(!diags.contains("not shown"), "{}", diags);
+}
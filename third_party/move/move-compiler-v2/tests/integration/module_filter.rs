@@ -0,0 +1,80 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the selection of the target modules for which code is generated.
+
+use crate::common;
+use move_compiler_v2::{run_move_compiler_collecting, CompilerOutput, Options};
+use std::collections::BTreeSet;
+
+const SOURCES: [(&str, &str); 3] = [
+    (
+        "a.move",
+        "module 0x42::a {
+    public fun f(): u64 { 0x42::b::g() }
+}
+",
+    ),
+    (
+        "b.move",
+        "module 0x42::b {
+    public fun g(): u64 { 1 }
+    public fun unused(): u64 { 2 }
+}
+",
+    ),
+    (
+        "c.move",
+        "module 0x42::c {
+    public fun h(): u64 { 3 }
+}
+",
+    ),
+];
+
+fn compile(module_filter: &[&str]) -> Result<CompilerOutput, Vec<String>> {
+    let options = Options {
+        source_buffers: common::source_buffers(&SOURCES),
+        module_filter: module_filter.iter().map(|p| p.to_string()).collect(),
+        ..Options::default()
+    };
+    run_move_compiler_collecting(options)
+        .map_err(|diags| diags.into_iter().map(|d| d.message).collect())
+}
+
+#[test]
+fn filter_selects_module_and_pulls_in_callees() {
+    let output = compile(&["0x42::a"]).expect("compiles");
+    assert_eq!(common::unit_names(&output.annotated_units), vec!["a"]);
+    let generated = output
+        .targets
+        .get_funs()
+        .map(|id| output.env.get_function(id).get_full_name_str())
+        .collect::<BTreeSet<_>>();
+    assert_eq!(
+        generated,
+        BTreeSet::from(["a::f".to_owned(), "b::g".to_owned()])
+    );
+}
+
+#[test]
+fn filter_patterns_with_glob() {
+    let output = compile(&["0x42::*"]).expect("compiles");
+    assert_eq!(common::unit_names(&output.annotated_units), vec![
+        "a", "b", "c"
+    ]);
+    let output = compile(&["*::b", "0x42::c"]).expect("compiles");
+    assert_eq!(common::unit_names(&output.annotated_units), vec!["b", "c"]);
+}
+
+#[test]
+fn unmatched_filter_is_an_error() {
+    let Err(errors) = compile(&["0x42::a", "0x43::*"]) else {
+        panic!("compilation succeeded")
+    };
+    assert!(
+        errors.contains(&"module filter `0x43::*` matches no target module".to_owned()),
+        "{:?}",
+        errors
+    );
+}
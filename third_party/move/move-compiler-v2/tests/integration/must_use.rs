@@ -0,0 +1,95 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the `unused_must_use` lint, which warns about discarded results of functions
+//! annotated with `#[lint::must_use]`.
+
+use crate::common;
+use move_compiler_v2::Options;
+
+const SOURCE: &str = "module 0x42::m {
+    struct Receipt has drop { amount: u64 }
+
+    #[lint::must_use(note = b\"redeem the receipt with `redeem`\")]
+    public fun borrow(amount: u64): Receipt {
+        Receipt { amount }
+    }
+
+    #[lint::must_use]
+    public fun pair(): (u64, u64) {
+        (1, 2)
+    }
+
+    public fun plain(): u64 {
+        1
+    }
+
+    public fun redeem(receipt: Receipt): u64 {
+        let Receipt { amount } = receipt;
+        amount
+    }
+
+    fun discarded() {
+        borrow(1);
+        pair();
+    }
+
+    fun silenced() {
+        let _ = borrow(1);
+        _ = borrow(2);
+        let (_, _) = pair();
+    }
+
+    fun used(): u64 {
+        let (a, _) = pair();
+        redeem(borrow(1)) + a
+    }
+
+    fun not_annotated() {
+        plain();
+    }
+
+    #[lint::allow(unused_must_use)]
+    fun allowed() {
+        borrow(1);
+    }
+}
+";
+
+/// Compiles the source, returning the line, message, and notes of each warning of the lint.
+fn warnings() -> Vec<(usize, String, Vec<String>)> {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..Options::default()
+    };
+    common::compile_collecting(options)
+        .diagnostics
+        .into_iter()
+        .filter(|d| d.code.as_deref() == Some("W02090"))
+        .map(|d| {
+            (
+                d.location.map(|l| l.start_line).unwrap_or_default(),
+                d.message,
+                d.notes,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn discarded_results_are_reported() {
+    assert_eq!(warnings(), vec![
+        (
+            24,
+            "the result of `m::borrow` is never used; discard it deliberately with `let _ = ..`"
+                .to_owned(),
+            vec!["redeem the receipt with `redeem`".to_owned()]
+        ),
+        (
+            25,
+            "the result of `m::pair` is never used; discard it deliberately with `let _ = ..`"
+                .to_owned(),
+            vec![]
+        ),
+    ]);
+}
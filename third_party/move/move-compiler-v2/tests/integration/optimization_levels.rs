@@ -0,0 +1,91 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the optimization levels, comparing the code generated for a function with
+//! redundant copies at each level. Compilation runs the bytecode verifier, so the optimized
+//! code is checked to be valid, and the unit tests of the module check that it still computes
+//! the same results.
+
+use crate::common;
+use move_binary_format::file_format::Bytecode;
+use move_compiler::compiled_unit::AnnotatedCompiledUnit;
+use move_compiler_v2::Options;
+use move_model::model::GlobalEnv;
+
+const SOURCE: &str = "module 0x42::m {
+    public fun copies(x: u64): u64 {
+        let y = x;
+        let z = y;
+        let w = z;
+        w + y + z
+    }
+
+    public fun swap(a: u64, b: u64): (u64, u64) {
+        let t = a;
+        let a = b;
+        let b = t;
+        (a, b)
+    }
+
+    #[test]
+    fun copies_works() {
+        assert!(copies(2) == 6, 0)
+    }
+
+    #[test]
+    fun swap_works() {
+        let (a, b) = swap(1, 2);
+        assert!(a == 2 && b == 1, 0)
+    }
+}
+";
+
+fn compile(optimize: u8, compile_test_code: bool) -> (GlobalEnv, Vec<AnnotatedCompiledUnit>) {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        optimize,
+        compile_test_code,
+        ..Options::default()
+    };
+    common::compile(options)
+}
+
+/// Returns the code generated for the function at the optimization level.
+fn code_of(optimize: u8, name: &str) -> Vec<Bytecode> {
+    let (_, units) = compile(optimize, false);
+    common::function_code(&common::compiled_module(&units[0]), name)
+}
+
+#[test]
+fn optimization_removes_copies() {
+    for name in ["copies", "swap"] {
+        let baseline = code_of(0, name);
+        let optimized = code_of(1, name);
+        assert_ne!(baseline, optimized, "{}", name);
+        assert!(
+            optimized.len() < baseline.len(),
+            "{}: {:?} is not shorter than {:?}",
+            name,
+            optimized,
+            baseline
+        );
+    }
+}
+
+#[test]
+fn optimization_level_option() {
+    assert_eq!(Options::default().optimize, 0);
+    assert!(Options::try_parse_command_line(["move-compiler-v2", "--optimize", "3"]).is_err());
+    let options =
+        Options::try_parse_command_line(["move-compiler-v2", "-O", "2"]).expect("options");
+    assert_eq!(options.optimize, 2);
+}
+
+#[test]
+fn optimized_code_passes_tests() {
+    for optimize in 0..=2 {
+        let (env, units) = compile(optimize, true);
+        let (output, all_passed) = common::run_unit_tests(&env, units);
+        assert!(all_passed, "level {}: {}", optimize, output);
+    }
+}
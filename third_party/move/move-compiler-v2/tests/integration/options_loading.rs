@@ -0,0 +1,121 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for loading options from TOML and JSON, and for building them.
+
+use move_compiler_v2::{ColorMode, ComparisonLevel, Options, OptionsError};
+use std::{fs, path::PathBuf};
+
+#[test]
+fn options_round_trip_through_toml() {
+    let options = Options::builder()
+        .source("sources")
+        .dependency("deps")
+        .named_address("std", "0x1")
+        .experiment("acquires-check=off")
+        .output_dir("out")
+        .optimize(2)
+        .with(|options| {
+            options.color = ColorMode::Never;
+            options.compare_with_v1 = Some(ComparisonLevel::Code);
+            options.suppressed_warnings = vec!["unused_variable".to_owned()];
+            options.report_severity = codespan_reporting::diagnostic::Severity::Note;
+        })
+        .build()
+        .expect("valid options");
+    let toml = options.to_toml_string().expect("serializes");
+    let loaded = Options::from_toml_str(&toml).expect("deserializes");
+    assert_eq!(format!("{:?}", loaded), format!("{:?}", options));
+}
+
+#[test]
+fn options_not_given_have_defaults() {
+    let options = Options::from_toml_str(
+        r#"
+        sources = ["m.move"]
+        check_only = true
+        "#,
+    )
+    .expect("deserializes");
+    assert_eq!(options.sources, vec!["m.move"]);
+    assert!(options.check_only);
+    assert_eq!(options.profile_top, 20);
+    assert_eq!(options.max_diagnostics, Some(50));
+    assert_eq!(options.large_struct_fields, 8);
+}
+
+#[test]
+fn unknown_options_are_rejected() {
+    assert!(Options::from_toml_str("optimise = 1").is_err());
+}
+
+#[test]
+fn options_are_loaded_from_json() {
+    let options =
+        Options::from_json_str(r#"{"dependencies": ["deps"], "report_severity": "error"}"#)
+            .expect("deserializes");
+    assert_eq!(options.dependencies, vec!["deps"]);
+    assert_eq!(
+        options.report_severity(),
+        codespan_reporting::diagnostic::Severity::Error
+    );
+}
+
+#[test]
+fn options_are_loaded_from_toml_files() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("options.toml");
+    fs::write(
+        &path,
+        "output_dir = \"build\"\nwarnings_are_errors = true\n",
+    )
+    .expect("write");
+    let options = Options::from_toml_file(&path).expect("loads");
+    assert_eq!(options.output_dir, Some(PathBuf::from("build")));
+    assert!(options.warnings_are_errors);
+    let err = Options::from_toml_file(dir.path().join("missing.toml")).unwrap_err();
+    assert!(err.to_string().contains("missing.toml"), "{}", err);
+}
+
+#[test]
+fn loaded_options_are_validated() {
+    let err = Options::from_toml_str("check_only = true\nemit_abi = \"abi\"").unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<OptionsError>(),
+        Some(&OptionsError::Conflict("--check", "--emit-abi"))
+    );
+}
+
+#[test]
+fn builder_rejects_conflicts() {
+    let err = Options::builder()
+        .check_only(true)
+        .output_dir("out")
+        .build()
+        .unwrap_err();
+    assert_eq!(err, OptionsError::Conflict("--check", "--output-dir"));
+}
+
+#[test]
+fn builder_rejects_invalid_values() {
+    let err = Options::builder().optimize(3).build().unwrap_err();
+    assert!(
+        matches!(
+            err,
+            OptionsError::InvalidValue {
+                option: "--optimize",
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn command_line_rejects_conflicts() {
+    let err =
+        Options::try_parse_command_line(["move-compiler-v2", "--check", "--output-dir", "out"])
+            .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
@@ -0,0 +1,145 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for building and verifying the metadata of compiled packages.
+
+use crate::common;
+use move_compiler_v2::{
+    package_metadata::{build_package_metadata, verify_metadata, PackageMetadata},
+    Options,
+};
+
+const MODULE: &str = "module 0x42::m {
+    use 0x43::dep;
+
+    public fun f(): u64 {
+        dep::g() + 1
+    }
+}
+";
+
+const SCRIPT: &str = "script {
+    fun main() {
+        assert!(0x42::m::f() == 2, 0);
+    }
+}
+";
+
+const DEPENDENCY: &str = "module 0x43::dep {
+    public fun g(): u64 {
+        1
+    }
+}
+";
+
+fn sources(module: &str) -> Vec<(String, String)> {
+    vec![
+        ("sources/m.move".to_owned(), module.to_owned()),
+        ("scripts/main.move".to_owned(), SCRIPT.to_owned()),
+    ]
+}
+
+fn metadata(module: &str, dependency: &str) -> PackageMetadata {
+    let options = Options {
+        source_buffers: sources(module),
+        dependency_buffers: vec![("deps/dep.move".to_owned(), dependency.to_owned())],
+        named_address_mapping: vec!["pkg=0x42".to_owned()],
+        ..Options::default()
+    };
+    let (env, units) = common::compile(options.clone());
+    build_package_metadata(&env, &units, &options)
+}
+
+#[test]
+fn metadata_describes_units() {
+    let metadata = metadata(MODULE, DEPENDENCY);
+    assert!(metadata.compiler_version.starts_with("move-compiler-v2 "));
+    assert!(metadata
+        .flags
+        .iter()
+        .any(|flag| flag.starts_with("bytecode-version=")));
+    let units = metadata
+        .units
+        .iter()
+        .map(|unit| (unit.name.as_str(), unit.source_path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(units, vec![
+        ("0x42::m", "sources/m.move"),
+        ("main", "scripts/main.move")
+    ]);
+    assert_eq!(metadata.dependencies, vec!["0x43::dep".to_owned()]);
+    assert_eq!(metadata.named_addresses["pkg"], "0x42");
+}
+
+#[test]
+fn metadata_roundtrips_through_bcs() {
+    let metadata = metadata(MODULE, DEPENDENCY);
+    let bytes = bcs::to_bytes(&metadata).expect("serializes");
+    assert_eq!(
+        bcs::from_bytes::<PackageMetadata>(&bytes).expect("deserializes"),
+        metadata
+    );
+}
+
+#[test]
+fn metadata_verifies_against_sources() {
+    let metadata = metadata(MODULE, DEPENDENCY);
+    verify_metadata(&metadata, &sources(MODULE)).expect("sources match");
+    let edited = MODULE.replace("+ 1", "+ 2");
+    let err = verify_metadata(&metadata, &sources(&edited)).expect_err("edited source");
+    assert!(err.to_string().contains("sources/m.move"), "{}", err);
+    let err = verify_metadata(&metadata, &sources(MODULE)[1..]).expect_err("missing source");
+    assert!(err.to_string().contains("missing"), "{}", err);
+}
+
+#[test]
+fn editing_a_source_changes_the_digest() {
+    let original = metadata(MODULE, DEPENDENCY);
+    let edited = metadata(&MODULE.replace("+ 1", "+ 2"), DEPENDENCY);
+    assert_ne!(
+        original.units[0].source_digest,
+        edited.units[0].source_digest
+    );
+    assert_eq!(original.units[1], edited.units[1]);
+    assert_ne!(original.source_digest, edited.source_digest);
+}
+
+#[test]
+fn editing_a_dependency_keeps_the_digests() {
+    let original = metadata(MODULE, DEPENDENCY);
+    let edited = metadata(MODULE, &DEPENDENCY.replace("1\n", "2\n"));
+    assert_eq!(original.units, edited.units);
+    assert_eq!(original.source_digest, edited.source_digest);
+}
+
+#[test]
+fn same_named_modules_are_told_apart() {
+    let coin = |addr: &str| {
+        format!(
+            "module {}::coin {{\n    public fun f(): u64 {{ 1 }}\n}}\n",
+            addr
+        )
+    };
+    let options = Options {
+        source_buffers: vec![
+            ("sources/b.move".to_owned(), coin("0x2")),
+            ("sources/a.move".to_owned(), coin("0x1")),
+        ],
+        ..Options::default()
+    };
+    let (env, units) = common::compile(options.clone());
+    let metadata = build_package_metadata(&env, &units, &options);
+    let units = metadata
+        .units
+        .iter()
+        .map(|unit| (unit.name.as_str(), unit.source_path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(units, vec![
+        ("0x1::coin", "sources/a.move"),
+        ("0x2::coin", "sources/b.move")
+    ]);
+    assert_ne!(
+        metadata.units[0].source_digest,
+        metadata.units[1].source_digest
+    );
+}
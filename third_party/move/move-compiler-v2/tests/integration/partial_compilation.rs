@@ -0,0 +1,74 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for best-effort compilation of the modules without code generation errors.
+
+use crate::common;
+use move_compiler_v2::{diagnostics::DiagnosticSeverity, run_move_compiler_collecting, Options};
+
+const SOURCES: [(&str, &str); 2] = [
+    (
+        "broken.move",
+        "module 0x42::broken {
+    public fun valid(x: u64): u64 { x + 1 }
+
+    fun misplaced_break(x: u64): u64 {
+        break;
+        x
+    }
+}
+",
+    ),
+    (
+        "good.move",
+        "module 0x42::good {
+    public fun f(x: u64): u64 { 0x42::broken::valid(x) * 2 }
+}
+",
+    ),
+];
+
+fn options(partial_compilation: bool) -> Options {
+    Options {
+        source_buffers: common::source_buffers(&SOURCES),
+        partial_compilation,
+        ..Options::default()
+    }
+}
+
+#[test]
+fn good_modules_are_assembled() {
+    let output = run_move_compiler_collecting(options(true)).expect("partial compilation");
+    assert_eq!(common::unit_names(&output.annotated_units), vec!["good"]);
+    assert!(output.env.has_errors());
+    let errors = output
+        .diagnostics
+        .iter()
+        .filter(|diag| diag.severity >= DiagnosticSeverity::Error)
+        .map(|diag| diag.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(errors, vec!["missing enclosing loop statement"]);
+}
+
+#[test]
+fn compilation_fails_by_default() {
+    let diags = run_move_compiler_collecting(options(false))
+        .err()
+        .expect("errors");
+    assert!(diags
+        .iter()
+        .any(|diag| diag.message == "missing enclosing loop statement"));
+}
+
+#[test]
+fn checking_errors_still_fail() {
+    let options = Options {
+        source_buffers: vec![(
+            "m.move".to_owned(),
+            "module 0x42::m { fun f(): u64 { true } }".to_owned(),
+        )],
+        partial_compilation: true,
+        ..Options::default()
+    };
+    assert!(run_move_compiler_collecting(options).is_err());
+}
@@ -0,0 +1,80 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the peephole optimizer, comparing the number of instructions generated for a
+//! few functions with and without the optimizer. Compilation runs the bytecode verifier, so
+//! the optimized code is also checked to be valid.
+
+use crate::common;
+use move_binary_format::access::ModuleAccess;
+use move_compiler_v2::{Experiment, Options};
+use std::collections::BTreeMap;
+
+const SOURCE: &str = "module 0x42::m {
+    fun while_true(x: u64): u64 {
+        while (true) {
+            if (x > 10) break;
+            x = x + 1
+        };
+        x
+    }
+
+    fun negation(c: bool): u64 {
+        if (!c) 1 else 2
+    }
+
+    fun double_negation(c: bool): bool {
+        !!c
+    }
+
+    fun unused(x: u64) {
+        let _ = x;
+        let _ = 1;
+    }
+}
+";
+
+/// Compiles the source, returning the number of instructions of each function.
+fn instruction_counts(experiments: Vec<String>) -> BTreeMap<String, usize> {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        experiments,
+        ..Options::default()
+    };
+    let (_, units) = common::compile(options);
+    let module = common::compiled_module(&units[0]);
+    module
+        .function_defs()
+        .iter()
+        .map(|def| {
+            let handle = module.function_handle_at(def.function);
+            (
+                module.identifier_at(handle.name).to_string(),
+                def.code.as_ref().expect("code").code.len(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn optimizer_reduces_instructions() {
+    let before = instruction_counts(vec![]);
+    let after = instruction_counts(vec![Experiment::PEEPHOLE_OPTIMIZATION.to_owned()]);
+    let counts = |name: &str| (before[name], after[name]);
+    // Branches to the next instruction. The constant loop condition is already removed
+    // with the unreachable code.
+    assert_eq!(counts("while_true"), (20, 18));
+    // `Not; BrFalse` becomes `BrTrue`.
+    assert_eq!(counts("negation"), (10, 9));
+    assert_eq!(counts("double_negation"), (4, 2));
+    // `LdU64; Pop`.
+    assert_eq!(counts("unused"), (3, 1));
+}
+
+#[test]
+fn optimizer_is_off_by_default() {
+    assert_eq!(
+        instruction_counts(vec![]),
+        instruction_counts(vec![format!("{}=off", Experiment::PEEPHOLE_OPTIMIZATION)])
+    );
+}
@@ -0,0 +1,128 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for processors which are registered by embedders of the compiler.
+
+use crate::common;
+use move_compiler_v2::{Options, ProcessorPosition};
+use move_model::model::FunctionEnv;
+use move_stackless_bytecode::{
+    function_target::FunctionData,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    livevar_analysis::LiveVarAnnotation,
+};
+use std::{cell::RefCell, collections::BTreeMap, fs, rc::Rc};
+
+const SOURCE: &str = "module 0x42::m {
+    fun f(x: u64): u64 {
+        let y = x + 1;
+        y
+    }
+}
+";
+
+/// Maps function names to their number of instructions, and whether live variables have
+/// been annotated.
+type Observations = Rc<RefCell<BTreeMap<String, (usize, bool)>>>;
+
+/// A processor which counts the instructions of each function.
+struct InstructionCounter {
+    name: String,
+    observations: Observations,
+}
+
+impl FunctionTargetProcessor for InstructionCounter {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        fun_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        self.observations.borrow_mut().insert(
+            fun_env.get_full_name_str(),
+            (
+                data.code.len(),
+                data.annotations.get::<LiveVarAnnotation>().is_some(),
+            ),
+        );
+        data
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+fn options_with_counter(name: &str, position: ProcessorPosition) -> (Options, Observations) {
+    let observations = Observations::default();
+    let mut options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..Options::default()
+    };
+    let (name, shared) = (name.to_owned(), observations.clone());
+    options.register_pipeline_extension(position, move || {
+        Box::new(InstructionCounter {
+            name: name.clone(),
+            observations: shared.clone(),
+        })
+    });
+    (options, observations)
+}
+
+#[test]
+fn processor_observes_live_variables() {
+    let (options, observations) = options_with_counter(
+        "InstructionCounter",
+        ProcessorPosition::After("LiveVarAnalysisProcessor".to_owned()),
+    );
+    common::compile(options);
+    let (count, live_vars) = observations.borrow()["m::f"];
+    assert!(count > 0);
+    assert!(live_vars, "live variables are annotated");
+}
+
+#[test]
+fn processor_before_live_variables() {
+    let (options, observations) = options_with_counter(
+        "InstructionCounter",
+        ProcessorPosition::Before("LiveVarAnalysisProcessor".to_owned()),
+    );
+    common::compile(options);
+    let (_, live_vars) = observations.borrow()["m::f"];
+    assert!(!live_vars, "live variables are not yet annotated");
+}
+
+#[test]
+fn unknown_position_is_reported() {
+    let (options, observations) = options_with_counter(
+        "InstructionCounter",
+        ProcessorPosition::After("NoSuchProcessor".to_owned()),
+    );
+    let diags = common::try_compile(options).expect_err("compilation fails");
+    assert!(
+        diags.contains("no processor named `NoSuchProcessor`"),
+        "unexpected diagnostics: {}",
+        diags
+    );
+    assert!(observations.borrow().is_empty());
+}
+
+#[test]
+fn dumps_are_labeled_with_processor_name() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let (options, _) = options_with_counter("custom::counter", ProcessorPosition::End);
+    common::compile(Options {
+        dump_bytecode_dir: Some(dir.path().to_owned()),
+        ..options
+    });
+    let mut files = fs::read_dir(dir.path())
+        .expect("dump dir")
+        .map(|e| e.expect("entry").file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    files.sort_by_key(|f| f.split('_').nth(2).and_then(|s| s.parse::<usize>().ok()));
+    let last = files.last().expect("dumped files");
+    assert_eq!(last, "0x42_m_11_custom_counter.bytecode");
+    let content = fs::read_to_string(dir.path().join(last)).expect("read dump");
+    assert!(content.starts_with("============ after processor `custom::counter`"));
+}
@@ -0,0 +1,110 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the reporting of the progress of a compilation.
+
+use move_compiler_v2::{
+    progress::{Phase, ProgressEvent, ProgressSink},
+    run_move_compiler_collecting, Options,
+};
+use std::{cell::RefCell, rc::Rc};
+
+const SOURCE: &str = "module 0x42::m {
+    public fun f(): u64 { g() + 1 }
+    fun g(): u64 { 1 }
+}
+module 0x42::n {
+    public fun h(): u64 { 0x42::m::f() }
+}
+";
+
+#[derive(Clone, Default)]
+struct RecordingSink(Rc<RefCell<Vec<ProgressEvent>>>);
+
+impl ProgressSink for RecordingSink {
+    fn report(&self, event: &ProgressEvent) {
+        self.0.borrow_mut().push(event.clone())
+    }
+}
+
+/// Compiles the source, returning the reported events.
+fn events(source: &str) -> Vec<ProgressEvent> {
+    let sink = RecordingSink::default();
+    let mut options = Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        ..Options::default()
+    };
+    options.set_progress_sink(sink.clone());
+    let _ = run_move_compiler_collecting(options);
+    sink.0.take()
+}
+
+#[test]
+fn events_are_reported_in_order() {
+    let events = events(SOURCE);
+    let phases = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::PhaseStarted(phase) => Some(*phase),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(phases, vec![
+        Phase::Checking,
+        Phase::BytecodeGeneration,
+        Phase::BytecodePipeline,
+        Phase::FileFormatGeneration,
+        Phase::BytecodeVerification,
+    ]);
+    // Each kind of event is reported within its phase.
+    let phase_of = |index: usize| {
+        events[..index]
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                ProgressEvent::PhaseStarted(phase) => Some(*phase),
+                _ => None,
+            })
+            .expect("phase started")
+    };
+    for (index, event) in events.iter().enumerate() {
+        let expected = match event {
+            ProgressEvent::PhaseStarted(_) => continue,
+            ProgressEvent::FunctionCompiled { .. } => Phase::BytecodeGeneration,
+            ProgressEvent::ProcessorStarted(_) => Phase::BytecodePipeline,
+            ProgressEvent::ModuleGenerated { .. } => Phase::FileFormatGeneration,
+        };
+        assert_eq!(phase_of(index), expected, "{:?}", event);
+    }
+    let functions = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::FunctionCompiled { done, total } => Some((*done, *total)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(functions, vec![(1, 3), (2, 3), (3, 3)]);
+    let modules = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::ModuleGenerated { done, total } => Some((*done, *total)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(modules, vec![(1, 2), (2, 2)]);
+    let processors = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::ProcessorStarted(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(processors.first(), Some(&"UnusedVarsChecker"));
+    assert_eq!(processors.last(), Some(&"VisibilityChecker"));
+}
+
+#[test]
+fn only_checking_is_reported_on_errors() {
+    let events = events("module 0x42::m { fun f(): u64 { true } }");
+    assert_eq!(events, vec![ProgressEvent::PhaseStarted(Phase::Checking)]);
+}
@@ -0,0 +1,108 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the JSON bundle of compiled units written with `Options::emit_publish_bundle`.
+
+use crate::common;
+use move_binary_format::{
+    file_format::{CompiledModule, CompiledScript},
+    file_format_common::VERSION_DEFAULT,
+};
+use move_compiler_v2::{package_metadata::PackageMetadata, Options};
+use serde_json::Value;
+use std::fs;
+
+const SOURCES: [(&str, &str); 3] = [
+    (
+        "sources/b.move",
+        "module 0x42::b {
+    public fun f(): u64 { 0x42::a::g() + 1 }
+}
+",
+    ),
+    (
+        "sources/a.move",
+        "module 0x42::a {
+    public fun g(): u64 { 1 }
+}
+",
+    ),
+    (
+        "scripts/main.move",
+        "script {
+    fun main() {
+        assert!(0x42::b::f() == 2, 0);
+    }
+}
+",
+    ),
+];
+
+fn bundle() -> Value {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("bundle.json");
+    let options = Options {
+        source_buffers: common::source_buffers(&SOURCES),
+        emit_publish_bundle: Some(path.clone()),
+        bytecode_version: Some(VERSION_DEFAULT),
+        ..Options::default()
+    };
+    common::compile(options);
+    serde_json::from_str(&fs::read_to_string(path).expect("bundle written")).expect("JSON")
+}
+
+fn decode(value: &Value) -> Vec<u8> {
+    let literal = value.as_str().expect("hex literal");
+    hex::decode(literal.strip_prefix("0x").expect("0x prefix")).expect("hex")
+}
+
+#[test]
+fn modules_are_ordered_by_id_and_decode() {
+    let bundle = bundle();
+    let modules = bundle["modules"].as_array().expect("modules");
+    let ids = modules
+        .iter()
+        .map(|m| m["module_id"].as_str().expect("id").to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["0x42::a".to_owned(), "0x42::b".to_owned()]);
+    for module in modules {
+        let compiled = CompiledModule::deserialize_with_config(
+            &decode(&module["bytecode"]),
+            &common::deserializer_config(),
+        )
+        .expect("deserializable module");
+        let self_id = compiled.self_id();
+        assert_eq!(
+            format!("{}::{}", self_id.address().to_hex_literal(), self_id.name()),
+            module["module_id"].as_str().expect("id")
+        );
+        assert_eq!(module["abi"]["name"], Value::from(self_id.name().as_str()));
+        assert!(!module["source_digest"].as_str().expect("digest").is_empty());
+    }
+}
+
+#[test]
+fn scripts_and_metadata_are_included() {
+    let bundle = bundle();
+    let scripts = bundle["scripts"].as_array().expect("scripts");
+    assert_eq!(scripts.len(), 1);
+    assert_eq!(scripts[0]["name"], Value::from("main"));
+    CompiledScript::deserialize_with_config(
+        &decode(&scripts[0]["bytecode"]),
+        &common::deserializer_config(),
+    )
+    .expect("deserializable script");
+    let metadata: PackageMetadata =
+        bcs::from_bytes(&decode(&bundle["metadata_serialized"])).expect("metadata");
+    let names = metadata
+        .units
+        .iter()
+        .map(|unit| unit.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["0x42::a", "0x42::b", "main"]);
+}
+
+#[test]
+fn bundles_are_deterministic() {
+    assert_eq!(bundle(), bundle());
+}
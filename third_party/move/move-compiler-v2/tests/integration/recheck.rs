@@ -0,0 +1,227 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for re-checking a single edited function with `recheck::recheck_function`.
+
+use codespan_reporting::diagnostic::Severity;
+use move_compiler_v2::{
+    diagnostics::DiagnosticSeverity,
+    recheck::{recheck_function, RecheckResult},
+    run_checker, Options,
+};
+use move_model::model::{FunId, GlobalEnv, QualifiedId};
+
+const SOURCE: &str = "module 0x42::m {
+    struct S has drop { x: u64 }
+
+    public fun f(s: &S): u64 {
+        s.x + 1
+    }
+
+    public fun g(): u64 {
+        f(&S { x: 1 })
+    }
+}
+";
+
+fn check(source: &str) -> GlobalEnv {
+    run_checker(Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        ..Options::default()
+    })
+    .expect("checker runs")
+}
+
+fn fun_id(env: &GlobalEnv, name: &str) -> QualifiedId<FunId> {
+    env.get_modules()
+        .find(|m| m.is_target())
+        .and_then(|m| m.find_function(env.symbol_pool().make(name)))
+        .expect("function")
+        .get_qualified_id()
+}
+
+#[test]
+fn type_error_in_edit_is_reported() {
+    let mut env = check(SOURCE);
+    assert!(!env.has_errors());
+    let new_source = "public fun f(s: &S): u64 {
+        s.x + true
+    }";
+    let RecheckResult::Diagnostics(diags) = {
+        let fun_id = fun_id(&env, "f");
+        recheck_function(&mut env, fun_id, new_source)
+    }
+    .expect("recheck") else {
+        panic!("signature is unchanged")
+    };
+    assert_eq!(diags.len(), 1, "{:?}", diags);
+    assert_eq!(diags[0].severity, DiagnosticSeverity::Error);
+    // The error is located in the new source of the function, which starts where the old one
+    // starts.
+    let location = diags[0].location.as_ref().expect("location");
+    let offset = SOURCE.find("public fun f").unwrap() + new_source.find("true").unwrap();
+    assert_eq!(location.file, "m.move");
+    assert!(
+        location.start as usize <= offset && offset < location.end as usize,
+        "{:?}",
+        location
+    );
+    assert_eq!(location.start_line, 5);
+    // The env is left as it was.
+    assert!(!env.has_errors());
+}
+
+#[test]
+fn fixing_edit_clears_diagnostics_of_function() {
+    let mut env = check(&SOURCE.replace("s.x + 1", "s.x + false"));
+    assert!(env.has_errors());
+    let fun_id = fun_id(&env, "f");
+    let diags = recheck_function(
+        &mut env,
+        fun_id,
+        "public fun f(s: &S): u64 {
+        s.x + 1
+    }",
+    )
+    .expect("recheck");
+    assert!(
+        matches!(&diags, RecheckResult::Diagnostics(diags) if diags.is_empty()),
+        "{:?}",
+        diags
+    );
+    // The stale error of the old function is removed from the env.
+    assert_eq!(env.diag_count(Severity::Warning), 0);
+}
+
+#[test]
+fn signature_change_requires_full_recompile() {
+    let mut env = check(SOURCE);
+    let fun_id = fun_id(&env, "f");
+    let result = recheck_function(
+        &mut env,
+        fun_id,
+        "public fun f(s: &S): u8 {
+        (s.x as u8)
+    }",
+    )
+    .expect("recheck");
+    assert!(
+        matches!(result, RecheckResult::FullRecompileRequired),
+        "{:?}",
+        result
+    );
+    let result = recheck_function(
+        &mut env,
+        fun_id,
+        "public fun h(s: &S): u64 {
+        s.x
+    }",
+    )
+    .expect("recheck");
+    assert!(
+        matches!(result, RecheckResult::FullRecompileRequired),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn parse_error_in_edit_is_reported() {
+    let mut env = check(SOURCE);
+    let fun_id = fun_id(&env, "g");
+    let result = recheck_function(
+        &mut env,
+        fun_id,
+        "public fun g(): u64 {
+        f(&S { x: 1 }
+    }",
+    )
+    .expect("recheck");
+    let RecheckResult::Diagnostics(diags) = result else {
+        panic!("parse errors are reported")
+    };
+    assert!(!diags.is_empty());
+    assert!(diags
+        .iter()
+        .all(|d| d.severity == DiagnosticSeverity::Error));
+}
+
+#[test]
+fn rechecks_of_same_file_reuse_edited_file() {
+    let mut env = check(SOURCE);
+    let original_files = env.get_source_file_ids().len();
+    let fun_id = fun_id(&env, "f");
+    let errors_at = |env: &mut GlobalEnv, new_source: &str| {
+        let RecheckResult::Diagnostics(diags) =
+            recheck_function(env, fun_id, new_source).expect("recheck")
+        else {
+            panic!("signature is unchanged")
+        };
+        diags
+            .iter()
+            .map(|d| d.location.as_ref().expect("location").start_line)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        errors_at(
+            &mut env,
+            "public fun f(s: &S): u64 {
+        s.x + true
+    }"
+        ),
+        vec![5]
+    );
+    let files = env.get_source_file_ids().len();
+    assert_eq!(files, original_files + 1);
+    // The second edit of the file replaces the source of the first one.
+    assert_eq!(
+        errors_at(
+            &mut env,
+            "public fun f(s: &S): u64 {
+        let y = s.x;
+        y + false
+    }"
+        ),
+        vec![6]
+    );
+    assert_eq!(env.get_source_file_ids().len(), files);
+}
+
+#[test]
+fn declarations_of_other_files_are_visible() {
+    let mut env = run_checker(Options {
+        source_buffers: vec![
+            ("m.move".to_owned(), SOURCE.to_owned()),
+            (
+                "n.move".to_owned(),
+                "module 0x42::n { public fun k(): u64 { 1 } }".to_owned(),
+            ),
+        ],
+        ..Options::default()
+    })
+    .expect("checker runs");
+    let fun_id = fun_id(&env, "g");
+    let RecheckResult::Diagnostics(diags) = recheck_function(
+        &mut env,
+        fun_id,
+        "public fun g(): u64 {
+        0x42::n::k() + f(&S { x: 1 })
+    }",
+    )
+    .expect("recheck") else {
+        panic!("signature is unchanged")
+    };
+    assert!(diags.is_empty(), "{:?}", diags);
+    let RecheckResult::Diagnostics(diags) = recheck_function(
+        &mut env,
+        fun_id,
+        "public fun g(): u64 {
+        0x42::n::k() + true
+    }",
+    )
+    .expect("recheck") else {
+        panic!("signature is unchanged")
+    };
+    assert_eq!(diags.len(), 1, "{:?}", diags);
+    assert_eq!(diags[0].severity, DiagnosticSeverity::Error);
+}
@@ -0,0 +1,66 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the least severity of the diagnostics which are reported.
+
+use crate::common;
+use codespan_reporting::diagnostic::Severity;
+use move_compiler_v2::Options;
+
+const SOURCE: &str = "module 0x42::m {
+    public fun f(x: u64): u64 {
+        let y = 1;
+        x
+    }
+}
+";
+
+/// Compiles the source with the given command line arguments, returning whether compilation
+/// succeeded and the diagnostics.
+fn compile(args: &[&str]) -> (bool, String) {
+    common::compile_reporting(Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..common::parse_options(args)
+    })
+}
+
+#[test]
+fn warning_is_reported_at_warning_severity() {
+    let (ok, diags) = compile(&[]);
+    assert!(ok);
+    assert!(diags.contains("warning[W02050]"), "{}", diags);
+    assert_eq!(compile(&["--report-severity", "warning"]), (ok, diags));
+}
+
+#[test]
+fn warning_is_not_reported_at_error_severity() {
+    assert_eq!(compile(&["--quiet"]), (true, String::new()));
+    assert_eq!(
+        compile(&["--report-severity", "error"]),
+        (true, String::new())
+    );
+}
+
+#[test]
+fn promoted_warning_is_reported_at_error_severity() {
+    let (ok, diags) = compile(&["--quiet", "--warnings-are-errors"]);
+    assert!(!ok);
+    assert!(diags.contains("error[W02050]"), "{}", diags);
+}
+
+#[test]
+fn errors_cannot_be_filtered_out() {
+    assert!(
+        Options::try_parse_command_line(["move-compiler-v2", "--report-severity", "bug"]).is_err()
+    );
+    let bug_severity = Options {
+        report_severity: Severity::Bug,
+        ..Options::default()
+    };
+    assert_eq!(bug_severity.report_severity(), Severity::Error);
+    assert_eq!(
+        common::parse_options(&["--verbose-diags"]).report_severity(),
+        Severity::Help
+    );
+    assert_eq!(Options::default().report_severity(), Severity::Warning);
+}
@@ -0,0 +1,169 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests that the output of the compiler only depends on the sources, and not on the order in
+//! which they are given or where they are located.
+
+use crate::common;
+use move_compiler_v2::{
+    package_metadata::{build_package_metadata, PackageMetadata},
+    Options,
+};
+use std::{fs, path::Path};
+
+/// Modules which depend on each other, so the order in which they are given matters to the
+/// checker.
+const SOURCES: [(&str, &str); 4] = [
+    (
+        "a.move",
+        "module 0x42::a {
+    const LIMIT: u64 = 100;
+
+    struct Counter has key, store, drop { value: u64 }
+
+    public fun new(): Counter {
+        Counter { value: 0 }
+    }
+
+    public fun increment(c: &mut Counter) {
+        assert!(c.value < LIMIT, 1);
+        c.value = c.value + 1
+    }
+
+    public fun value(c: &Counter): u64 {
+        c.value
+    }
+}
+",
+    ),
+    (
+        "b.move",
+        "module 0x42::b {
+    use 0x42::a::{Self, Counter};
+
+    struct Pair has drop { first: Counter, second: Counter }
+
+    public fun pair(): Pair {
+        Pair { first: a::new(), second: a::new() }
+    }
+
+    public fun total(p: &mut Pair): u64 {
+        a::increment(&mut p.first);
+        a::value(&p.first) + a::value(&p.second)
+    }
+}
+",
+    ),
+    (
+        "c.move",
+        "module 0x42::c {
+    use 0x42::b;
+
+    public fun run(): u64 {
+        let p = b::pair();
+        let n = 0;
+        while (n < 3) {
+            n = n + b::total(&mut p);
+        };
+        n
+    }
+}
+",
+    ),
+    (
+        "main.move",
+        "script {
+    fun main() {
+        assert!(0x42::c::run() > 0, 0);
+    }
+}
+",
+    ),
+];
+
+/// Compiles the sources, returning the serialized units with their source maps, and the
+/// metadata of the package.
+fn compile(options: Options) -> (Vec<Vec<u8>>, PackageMetadata) {
+    let (env, units) = common::compile(options.clone());
+    let metadata = build_package_metadata(&env, &units, &options);
+    let serialized = units
+        .into_iter()
+        .flat_map(|unit| {
+            let unit = unit.into_compiled_unit();
+            [unit.serialize(None), unit.serialize_source_map()]
+        })
+        .collect();
+    (serialized, metadata)
+}
+
+/// Returns the sources reversed, with the first half moved to the end, so neighbours are
+/// separated.
+fn shuffle(sources: &[String]) -> Vec<String> {
+    let mut shuffled = sources.to_vec();
+    shuffled.reverse();
+    shuffled.rotate_left(sources.len() / 2);
+    assert_ne!(sources, shuffled);
+    shuffled
+}
+
+/// Compiles the sources in their given and in shuffled order, and checks that the serialized
+/// output is the same.
+fn assert_output_does_not_depend_on_order(sources: Vec<String>, options: Options) {
+    let shuffled = shuffle(&sources);
+    let (expected, _) = compile(Options {
+        sources,
+        ..options.clone()
+    });
+    let (actual, _) = compile(Options {
+        sources: shuffled,
+        ..options
+    });
+    assert!(!expected.is_empty());
+    assert!(expected == actual, "serialized output differs");
+}
+
+#[test]
+fn output_does_not_depend_on_source_order() {
+    let (_dir, sources) = common::write_files(&SOURCES);
+    assert_output_does_not_depend_on_order(sources, Options::default());
+}
+
+#[test]
+fn stdlib_output_does_not_depend_on_source_order() {
+    let stdlib = Path::new(env!("CARGO_MANIFEST_DIR")).join("../move-stdlib/sources");
+    // Modules whose loop invariants call functions, or with inline functions taking lambdas,
+    // are not supported yet by this compiler, so they are only dependencies.
+    let unsupported = ["ascii.move", "option.move", "type_name.move", "vector.move"];
+    let (dependencies, mut sources): (Vec<_>, Vec<_>) = fs::read_dir(stdlib)
+        .expect("stdlib sources")
+        .map(|entry| entry.expect("directory entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "move"))
+        .map(|path| path.to_string_lossy().to_string())
+        .partition(|path| unsupported.iter().any(|name| path.ends_with(name)));
+    sources.sort();
+    assert_output_does_not_depend_on_order(sources, Options {
+        dependencies,
+        named_address_mapping: vec!["std=0x1".to_owned()],
+        ..Options::default()
+    });
+}
+
+#[test]
+fn metadata_does_not_depend_on_location() {
+    let compile_in_new_dir = || {
+        let (dir, sources) = common::write_files(&SOURCES);
+        compile(Options {
+            sources,
+            path_prefix_strip: Some(dir.path().to_path_buf()),
+            ..Options::default()
+        })
+    };
+    let (units1, metadata1) = compile_in_new_dir();
+    let (units2, metadata2) = compile_in_new_dir();
+    assert!(units1 == units2, "serialized output differs");
+    assert_eq!(metadata1, metadata2);
+    assert!(metadata1
+        .units
+        .iter()
+        .any(|unit| unit.source_path == "a.move"));
+}
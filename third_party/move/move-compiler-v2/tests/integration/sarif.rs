@@ -0,0 +1,49 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for writing diagnostics as a SARIF log. The produced log is compared with a golden
+//! file, which can be updated by running the test with `UPBL=1`.
+
+use crate::common;
+use move_compiler_v2::Options;
+use move_prover_test_utils::baseline_test::verify_or_update_baseline;
+use std::{fs, path::Path};
+
+/// Two modules with errors, one of which has a diagnostic with multiple labels.
+const SOURCES: &[(&str, &str)] = &[
+    (
+        "sources/a.move",
+        "module 0x42::a {
+    fun f(): u64 {
+        let x = 0x42::b;
+        1
+    }
+}
+",
+    ),
+    (
+        "sources/b.move",
+        "module 0x42::b {
+    fun g() {}
+    fun g() {}
+}
+",
+    ),
+];
+
+#[test]
+fn two_errors_in_different_files() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let sarif_path = dir.path().join("diagnostics.sarif");
+    let options = Options {
+        source_buffers: common::source_buffers(SOURCES),
+        sarif_output: Some(sarif_path.clone()),
+        ..Options::default()
+    };
+    // Diagnostics are still reported to the error writer.
+    let text = common::try_compile(options).expect_err("compilation fails");
+    assert!(text.contains("sources/a.move") && text.contains("sources/b.move"));
+    let sarif = fs::read_to_string(sarif_path).expect("SARIF log written");
+    verify_or_update_baseline(Path::new("tests/sarif/two_errors.sarif"), &sarif)
+        .expect("SARIF log matches golden file");
+}
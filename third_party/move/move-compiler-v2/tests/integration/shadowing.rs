@@ -0,0 +1,131 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the `shadowing` lint, which warns about locals shadowing other locals or
+//! parameters of the same function.
+
+use move_compiler_v2::{run_move_compiler_collecting, Options};
+
+const SOURCE: &str = "module 0x42::m {
+    struct S has drop { x: u64 }
+
+    fun shadows_parameter(x: u64): u64 {
+        let x = x + 1;
+        x
+    }
+
+    fun shadows_in_nested_block(y: u64): u64 {
+        let a = y;
+        {
+            let a = a + 1;
+            y = a;
+        };
+        a + y
+    }
+
+    fun shadows_by_destructuring(s: S, x: u64): u64 {
+        let S { x } = s;
+        x
+    }
+
+    fun declares_in_loop(n: u64): u64 {
+        let sum = 0;
+        while (n > 0) {
+            let step = n;
+            sum = sum + step;
+            n = n - 1;
+        };
+        sum
+    }
+
+    fun underscore_is_exempt(_z: u64): u64 {
+        let _z = 1;
+        _z
+    }
+
+    #[lint::allow(shadowing)]
+    fun allowed(x: u64): u64 {
+        let x = x * 2;
+        x
+    }
+}
+";
+
+/// Compiles the source, returning the lines of the shadowing and the shadowed declaration, and
+/// the message, of each warning of the lint.
+fn warnings(options: Options) -> Vec<(usize, usize, String)> {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        ..options
+    };
+    run_move_compiler_collecting(options)
+        .expect("compiles")
+        .diagnostics
+        .into_iter()
+        .filter(|d| d.code.as_deref() == Some("W07001"))
+        .map(|d| {
+            let shadowed = d
+                .labels
+                .iter()
+                .find(|label| !label.primary)
+                .map(|label| label.location.start_line)
+                .unwrap_or_default();
+            (
+                d.location.map(|l| l.start_line).unwrap_or_default(),
+                shadowed,
+                d.message,
+            )
+        })
+        .collect()
+}
+
+fn enabled() -> Options {
+    Options {
+        enabled_warnings: vec!["shadowing".to_owned()],
+        ..Options::default()
+    }
+}
+
+#[test]
+fn shadowing_is_reported_if_enabled() {
+    assert_eq!(warnings(enabled()), vec![
+        (
+            5,
+            4,
+            "local `x` shadows a parameter of the same name".to_owned()
+        ),
+        (
+            12,
+            10,
+            "local `a` shadows a local of the same name".to_owned()
+        ),
+        (
+            19,
+            18,
+            "local `x` shadows a parameter of the same name".to_owned()
+        ),
+    ]);
+}
+
+#[test]
+fn shadowing_is_not_reported_by_default() {
+    assert!(warnings(Options::default()).is_empty());
+}
+
+#[test]
+fn shadowing_can_be_enabled_by_code() {
+    let options = Options {
+        enabled_warnings: vec!["W07001".to_owned()],
+        ..Options::default()
+    };
+    assert_eq!(warnings(options).len(), 3);
+}
+
+#[test]
+fn suppression_takes_precedence() {
+    let options = Options {
+        suppressed_warnings: vec!["shadowing".to_owned()],
+        ..enabled()
+    };
+    assert!(warnings(options).is_empty());
+}
@@ -0,0 +1,54 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for compiling sources which are provided in memory instead of as files.
+
+use crate::common;
+use move_compiler_v2::Options;
+
+const MODULE_A: &str = "module 0x42::a {\n    public fun f(): u64 { 1 }\n}\n";
+const MODULE_B: &str =
+    "module 0x42::b {\n    use 0x42::a;\n    public fun g(): u64 { a::f() + 1 }\n}\n";
+
+#[test]
+fn compile_from_buffers() {
+    let options = Options {
+        source_buffers: vec![("virtual/b.move".to_owned(), MODULE_B.to_owned())],
+        dependency_buffers: vec![("virtual/a.move".to_owned(), MODULE_A.to_owned())],
+        ..Options::default()
+    };
+    let (env, units) = common::compile(options);
+    assert_eq!(common::unit_names(&units), vec!["b"]);
+    let mut sources = env
+        .get_source_file_ids()
+        .into_iter()
+        .map(|fid| {
+            (
+                env.get_file(fid).to_string_lossy().to_string(),
+                env.get_file_source(fid).to_owned(),
+            )
+        })
+        .collect::<Vec<_>>();
+    sources.sort();
+    assert_eq!(sources, vec![
+        ("virtual/a.move".to_owned(), MODULE_A.to_owned()),
+        ("virtual/b.move".to_owned(), MODULE_B.to_owned()),
+    ]);
+}
+
+#[test]
+fn diagnostics_refer_to_virtual_path() {
+    let options = Options {
+        source_buffers: vec![(
+            "virtual/c.move".to_owned(),
+            "module 0x42::c {\n    fun h(): u64 { true }\n}\n".to_owned(),
+        )],
+        ..Options::default()
+    };
+    let output = common::try_compile(options).expect_err("does not type check");
+    assert!(
+        output.contains("┌─ virtual/c.move:2:20"),
+        "unexpected diagnostics: {}",
+        output
+    );
+}
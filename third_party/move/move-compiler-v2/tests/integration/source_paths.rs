@@ -0,0 +1,164 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the checks of duplicate sources and dependencies.
+
+use crate::common;
+use move_compiler_v2::{
+    diagnostics::{CompilerDiagnostic, DiagnosticSeverity},
+    run_move_compiler_collecting, Options,
+};
+
+const M: &str = "module 0x42::m {
+    public fun f(): u64 { 0x42::n::g() }
+}
+";
+
+const N: &str = "module 0x42::n {
+    public fun g(): u64 { 1 }
+}
+";
+
+/// Compiles the sources with the dependencies, returning the diagnostics, whether compilation
+/// succeeded or not.
+fn diags(sources: Vec<String>, dependencies: Vec<String>) -> Vec<CompilerDiagnostic> {
+    common::collected_diagnostics(Options {
+        sources,
+        dependencies,
+        ..Options::default()
+    })
+}
+
+/// Compiles the sources with the dependencies, returning the diagnostics with the code,
+/// whether compilation succeeded or not.
+fn diags_with_code(sources: Vec<String>, dependencies: Vec<String>, code: &str) -> Vec<String> {
+    diags(sources, dependencies)
+        .into_iter()
+        .filter(|d| d.code.as_deref() == Some(code))
+        .map(|d| d.message)
+        .collect()
+}
+
+/// Returns the messages of the errors among the diagnostics.
+fn errors(diags: &[CompilerDiagnostic]) -> Vec<String> {
+    diags
+        .iter()
+        .filter(|d| d.severity == DiagnosticSeverity::Error)
+        .map(|d| d.message.clone())
+        .collect()
+}
+
+#[test]
+fn source_given_twice_is_compiled_once() {
+    let (_dir, paths) = common::write_files(&[("m.move", M), ("n.move", N)]);
+    let options = Options {
+        sources: vec![paths[0].clone(), paths[0].clone()],
+        dependencies: vec![paths[1].clone()],
+        ..Options::default()
+    };
+    let output = run_move_compiler_collecting(options).expect("compiles");
+    assert_eq!(output.annotated_units.len(), 1);
+    let warnings = output
+        .diagnostics
+        .iter()
+        .filter(|d| d.code.as_deref() == Some("W00005"))
+        .map(|d| d.message.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(warnings, vec![format!(
+        "source `{}` is given more than once",
+        paths[0]
+    )]);
+}
+
+#[test]
+fn source_given_as_dependency_is_an_error() {
+    let (_dir, paths) = common::write_files(&[("m.move", M), ("n.move", N)]);
+    let errors = diags_with_code(paths.clone(), vec![paths[1].clone()], "E00006");
+    assert_eq!(errors, vec![format!(
+        "`{}` is given both as a source and as a dependency",
+        paths[1]
+    )]);
+}
+
+#[test]
+fn identical_files_are_reported() {
+    let (_dir, paths) = common::write_files(&[("m.move", M), ("n.move", N), ("n_copy.move", N)]);
+    let warnings = diags_with_code(
+        vec![paths[0].clone()],
+        vec![paths[1].clone(), paths[2].clone()],
+        "W00007",
+    );
+    assert_eq!(warnings, vec![format!(
+        "files `{}` and `{}` have identical contents",
+        paths[1], paths[2]
+    )]);
+}
+
+#[test]
+fn file_of_source_directory_given_as_dependency_is_an_error() {
+    let (dir, paths) = common::write_files(&[("m.move", M), ("n.move", N)]);
+    let dir = dir.path().to_string_lossy().to_string();
+    let diags = diags(vec![dir], vec![paths[1].clone()]);
+    // The file is only compiled as a source, so its module is not defined twice.
+    assert_eq!(errors(&diags), vec![format!(
+        "`{}` is given both as a source and as a dependency",
+        paths[1]
+    )]);
+}
+
+#[test]
+fn dependency_directory_containing_sources_keeps_other_files() {
+    let (dir, paths) = common::write_files(&[("m.move", M), ("n.move", N)]);
+    let dir = dir.path().to_string_lossy().to_string();
+    let diags = diags(vec![paths[0].clone()], vec![dir.clone()]);
+    // The module `0x42::n` is still found among the dependencies.
+    assert_eq!(errors(&diags), vec![format!(
+        "dependency `{}` contains files given as sources: `{}`",
+        dir, paths[0]
+    )]);
+}
+
+#[test]
+fn source_directory_repeating_a_source_is_compiled_once() {
+    let (dir, paths) = common::write_files(&[("m.move", M), ("n.move", N)]);
+    let dir = dir.path().to_string_lossy().to_string();
+    let options = Options {
+        sources: vec![paths[0].clone(), dir.clone()],
+        ..Options::default()
+    };
+    let output = run_move_compiler_collecting(options).expect("compiles");
+    assert_eq!(output.annotated_units.len(), 2);
+    let warnings = output
+        .diagnostics
+        .iter()
+        .filter(|d| d.code.as_deref() == Some("W00005"))
+        .map(|d| d.message.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(warnings, vec![format!(
+        "source `{}` contains files given more than once: `{}`",
+        dir, paths[0]
+    )]);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_files_are_recognized() {
+    let (dir, paths) = common::write_files(&[("m.move", M), ("n.move", N)]);
+    let link = dir.path().join("link.move");
+    std::os::unix::fs::symlink(&paths[1], &link).expect("symlink created");
+    let link = link.to_string_lossy().to_string();
+    let warnings = diags_with_code(
+        vec![paths[0].clone()],
+        vec![paths[1].clone(), link.clone()],
+        "W00005",
+    );
+    assert_eq!(warnings, vec![format!(
+        "dependency `{}` is given more than once",
+        link
+    )]);
+    let errors = diags_with_code(paths.clone(), vec![link.clone()], "E00006");
+    assert_eq!(errors, vec![format!(
+        "`{}` is given both as a source and as a dependency",
+        link
+    )]);
+}
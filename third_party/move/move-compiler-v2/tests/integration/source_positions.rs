@@ -0,0 +1,132 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the mapping of byte offsets to lines and columns, which must agree with
+//! `codespan` on lines and on the characters before an offset.
+
+use codespan::Span;
+use move_command_line_common::files::FileHash;
+use move_compiler_v2::source_positions::{SourcePosition, SourcePositionMapper};
+use move_model::model::{GlobalEnv, Loc};
+use proptest::prelude::*;
+use std::{collections::BTreeMap, rc::Rc};
+
+/// Returns an env with the source as its only file, and the id of that file.
+fn env_with_source(source: &str) -> (GlobalEnv, codespan::FileId) {
+    let mut env = GlobalEnv::new();
+    let file_id = env.add_source(
+        FileHash::new(source),
+        Rc::new(BTreeMap::new()),
+        "test.move",
+        source,
+        false,
+    );
+    (env, file_id)
+}
+
+/// Sources made of arbitrary characters, with line breaks in both styles mixed in.
+fn source() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            4 => any::<char>().prop_map(String::from),
+            1 => Just("\n".to_owned()),
+            1 => Just("\r\n".to_owned()),
+        ],
+        0..64,
+    )
+    .prop_map(|parts| parts.concat())
+}
+
+proptest! {
+    #[test]
+    fn positions_match_codespan(source in source()) {
+        let (env, file_id) = env_with_source(&source);
+        let positions = SourcePositionMapper::new(&env);
+        for offset in 0..=source.len() {
+            let expected =
+                env.get_location(&Loc::new(file_id, Span::new(offset as u32, offset as u32)));
+            let actual = positions.byte_to_line_col(file_id, offset as u32);
+            match (expected, actual) {
+                (None, None) => {},
+                (Some(expected), Some(actual)) => {
+                    prop_assert_eq!(actual.line, expected.line.0 as usize + 1);
+                    let prefix = &source[offset + 1 - actual.utf8_column..offset];
+                    prop_assert!(!prefix.contains('\n'));
+                    prop_assert_eq!(prefix.chars().count(), expected.column.0 as usize);
+                    prop_assert_eq!(prefix.encode_utf16().count() + 1, actual.utf16_column);
+                },
+                (expected, actual) => prop_assert!(
+                    false,
+                    "offset {}: codespan {:?}, mapper {:?}",
+                    offset,
+                    expected,
+                    actual
+                ),
+            }
+        }
+        let beyond_end = (source.len() + 1) as u32;
+        prop_assert_eq!(positions.byte_to_line_col(file_id, beyond_end), None);
+    }
+}
+
+#[test]
+fn empty_source() {
+    let (env, file_id) = env_with_source("");
+    let positions = SourcePositionMapper::new(&env);
+    assert_eq!(
+        positions.byte_to_line_col(file_id, 0),
+        Some(SourcePosition {
+            line: 1,
+            utf8_column: 1,
+            utf16_column: 1,
+        })
+    );
+    assert_eq!(positions.byte_to_line_col(file_id, 1), None);
+}
+
+#[test]
+fn multi_byte_characters_and_crlf() {
+    let source = "a\u{e9}\u{1f600}b\r\nc";
+    let (env, file_id) = env_with_source(source);
+    let positions = SourcePositionMapper::new(&env);
+    let position = |offset| positions.byte_to_line_col(file_id, offset);
+    // `b` follows a 2-byte character with one UTF-16 code unit, and a 4-byte character with
+    // two UTF-16 code units.
+    assert_eq!(
+        position(7),
+        Some(SourcePosition {
+            line: 1,
+            utf8_column: 8,
+            utf16_column: 5,
+        })
+    );
+    // The `\r` belongs to the first line.
+    assert_eq!(
+        position(8),
+        Some(SourcePosition {
+            line: 1,
+            utf8_column: 9,
+            utf16_column: 6,
+        })
+    );
+    assert_eq!(
+        position(10),
+        Some(SourcePosition {
+            line: 2,
+            utf8_column: 1,
+            utf16_column: 1,
+        })
+    );
+    // Inside the 4-byte character.
+    assert_eq!(position(4), None);
+    // The end of the file is a position, but nothing beyond.
+    assert_eq!(
+        position(11),
+        Some(SourcePosition {
+            line: 2,
+            utf8_column: 2,
+            utf16_column: 2,
+        })
+    );
+    assert_eq!(position(12), None);
+}
@@ -0,0 +1,76 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the specification information of the compiled units, which the prover consumes.
+
+use crate::common;
+use move_compiler::{compiled_unit::AnnotatedCompiledUnit, expansion::ast::SpecId};
+use move_compiler_v2::{spec_info::SpecAnchors, Options};
+
+const SOURCE: &str = "module 0x42::specs {
+    fun succ(x: u64): u64 {
+        x + 1
+    }
+    spec succ {
+        ensures result == x + 1;
+    }
+
+    fun inline_specs(): u64 {
+        let x = 0;
+        spec { assert x == 0; };
+        x = succ(x);
+        spec {
+            assert x == 1;
+            assert x > 0;
+        };
+        x
+    }
+}
+";
+
+#[test]
+fn inline_spec_blocks_are_anchored_in_code() {
+    let options = Options {
+        source_buffers: vec![("specs.move".to_owned(), SOURCE.to_owned())],
+        ..Options::default()
+    };
+    let (env, units) = common::compile(options);
+    let [AnnotatedCompiledUnit::Module(module)] = units.as_slice() else {
+        panic!("expected a single module")
+    };
+    let info = |name: &str| {
+        module
+            .function_infos
+            .key_cloned_iter()
+            .find(|(fun_name, _)| fun_name.0.value.as_str() == name)
+            .map(|(_, info)| info.clone())
+            .expect("function info")
+    };
+    assert!(info("succ").spec_info.is_empty());
+
+    let spec_info = info("inline_specs").spec_info;
+    let ids = spec_info.keys().copied().collect::<Vec<_>>();
+    assert_eq!(ids, vec![SpecId::new(0), SpecId::new(1)]);
+    let (first, second) = (&spec_info[&ids[0]], &spec_info[&ids[1]]);
+    assert!(first.offset < second.offset);
+    let code = common::function_code(&module.named_module.module, "inline_specs");
+    assert!((second.offset as usize) < code.len());
+    assert_eq!(first.origin.function.as_str(), "inline_specs");
+    assert_eq!(first.origin.id, SpecId::new(0));
+    assert!(first.origin.module.is_some());
+
+    let fun_env = env
+        .get_modules()
+        .flat_map(|module_env| module_env.into_functions())
+        .find(|fun_env| fun_env.get_name_str() == "inline_specs")
+        .expect("function");
+    let anchors = env
+        .get_extension::<SpecAnchors>()
+        .expect("anchors")
+        .anchors(fun_env.get_qualified_id());
+    let condition_counts = anchors
+        .values()
+        .map(|anchor| anchor.condition_locs.len())
+        .collect::<Vec<_>>();
+    assert_eq!(condition_counts, vec![1, 2]);
+}
@@ -0,0 +1,137 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the estimation of the operand stack depth, frame size, and call depth of generated
+//! functions, and for the warnings about functions exceeding the thresholds of the options.
+
+use move_compiler_v2::{
+    compilation_stats::CompilationStats,
+    run_move_compiler_collecting,
+    stack_depth::{CallDepth, FunctionFrame},
+    CompilerOutput, Options,
+};
+use std::collections::BTreeMap;
+
+const SOURCE: &str = "module 0x42::m {
+    public fun sum(a: u64, b: u64, c: u64, d: u64): u64 {
+        a + b + c + d
+    }
+
+    public fun nested(a: u64, b: u64, c: u64, d: u64): u64 {
+        sum(a, b, c, sum(a, b, c, d))
+    }
+
+    public fun top(a: u64): u64 {
+        nested(a, a, a, a)
+    }
+
+    public fun even(n: u64): bool {
+        if (n == 0) true else odd(n - 1)
+    }
+
+    fun odd(n: u64): bool {
+        if (n == 0) false else even(n - 1)
+    }
+}
+";
+
+fn compile(max_stack_depth: Option<usize>, max_call_depth: Option<usize>) -> CompilerOutput {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        max_stack_depth,
+        max_call_depth,
+        ..Options::default()
+    };
+    run_move_compiler_collecting(options).expect("compiles")
+}
+
+fn frames(output: &CompilerOutput) -> BTreeMap<String, FunctionFrame> {
+    output
+        .env
+        .get_extension::<CompilationStats>()
+        .expect("statistics")
+        .frames()
+}
+
+/// Returns the messages of the warnings with the code, sorted.
+fn warnings(output: &CompilerOutput, code: &str) -> Vec<String> {
+    let mut messages = output
+        .diagnostics
+        .iter()
+        .filter(|d| d.code.as_deref() == Some(code))
+        .map(|d| d.message.clone())
+        .collect::<Vec<_>>();
+    messages.sort();
+    messages
+}
+
+#[test]
+fn stack_depths_follow_the_nesting_of_expressions() {
+    let frames = frames(&compile(None, None));
+    // The arguments of both calls of `sum` are pushed at once, while the additions of `sum`
+    // consume the stack as they go.
+    assert_eq!(frames["0x42::m::nested"].max_stack_depth, 4);
+    assert_eq!(frames["0x42::m::sum"].max_stack_depth, 2);
+    assert_eq!(frames["0x42::m::top"].max_stack_depth, 4);
+    assert!(frames["0x42::m::sum"].frame_size >= 4);
+    assert!(frames["0x42::m::top"].frame_size >= 1);
+}
+
+#[test]
+fn call_depths_follow_the_calls_and_recursion_is_unbounded() {
+    let frames = frames(&compile(None, None));
+    let call_depths = frames
+        .iter()
+        .map(|(name, frame)| (name.as_str(), frame.call_depth))
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(
+        call_depths,
+        BTreeMap::from([
+            ("0x42::m::even", CallDepth::Unbounded),
+            ("0x42::m::nested", CallDepth::Bounded(2)),
+            ("0x42::m::odd", CallDepth::Unbounded),
+            ("0x42::m::sum", CallDepth::Bounded(1)),
+            ("0x42::m::top", CallDepth::Bounded(3)),
+        ])
+    );
+    assert_eq!(CallDepth::Unbounded.to_string(), "unbounded");
+}
+
+#[test]
+fn exceeded_thresholds_are_reported() {
+    let output = compile(None, None);
+    assert!(warnings(&output, "W03005").is_empty());
+    assert!(warnings(&output, "W03006").is_empty());
+    let output = compile(Some(3), Some(2));
+    // `top` pushes the four arguments of `nested` at once.
+    assert_eq!(warnings(&output, "W03005"), vec![
+        "function `0x42::m::nested` needs an operand stack of depth 4, exceeding the maximum of 3"
+            .to_owned(),
+        "function `0x42::m::top` needs an operand stack of depth 4, exceeding the maximum of 3"
+            .to_owned(),
+    ]);
+    assert_eq!(warnings(&output, "W03006"), vec![
+        "function `0x42::m::even` leads to calls of unbounded depth, since it can reach a cycle \
+         of calls, exceeding the maximum of 2"
+            .to_owned(),
+        "function `0x42::m::odd` leads to calls of unbounded depth, since it can reach a cycle \
+         of calls, exceeding the maximum of 2"
+            .to_owned(),
+        "function `0x42::m::top` leads to calls of depth 3, exceeding the maximum of 2".to_owned(),
+    ]);
+}
+
+#[test]
+fn thresholds_are_parsed_from_the_command_line() {
+    let options = Options::try_parse_command_line([
+        "move-compiler-v2",
+        "--max-stack-depth",
+        "16",
+        "--max-call-depth",
+        "8",
+    ])
+    .expect("options");
+    assert_eq!(options.max_stack_depth, Some(16));
+    assert_eq!(options.max_call_depth, Some(8));
+    assert_eq!(Options::default().max_stack_depth, None);
+}
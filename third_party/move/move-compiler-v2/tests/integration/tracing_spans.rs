@@ -0,0 +1,159 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the tracing spans and events of a compilation.
+
+use crate::common;
+use move_compiler_v2::Options;
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+const SOURCE: &str = "module 0x42::m {
+    public fun f(x: u64): u64 {
+        let y = 1;
+        x
+    }
+
+    public fun g(): u64 {
+        f(1)
+    }
+}
+";
+
+/// A span or event, with its fields and the index of its parent span.
+#[derive(Debug)]
+struct Recorded {
+    name: &'static str,
+    fields: BTreeMap<String, String>,
+    parent: Option<usize>,
+}
+
+impl Visit for Recorded {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields
+            .insert(field.name().to_owned(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields
+            .insert(field.name().to_owned(), value.to_owned());
+    }
+}
+
+/// A layer which records all spans and events.
+#[derive(Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<Recorded>>>,
+    events: Arc<Mutex<Vec<Recorded>>>,
+}
+
+/// The index of a span in `Recorder::spans`, stored in the extensions of the span.
+struct SpanIndex(usize);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("span");
+        let mut recorded = Recorded {
+            name: attrs.metadata().name(),
+            fields: BTreeMap::new(),
+            parent: span
+                .parent()
+                .and_then(|parent| parent.extensions().get::<SpanIndex>().map(|i| i.0)),
+        };
+        attrs.record(&mut recorded);
+        let mut spans = self.spans.lock().unwrap();
+        span.extensions_mut().insert(SpanIndex(spans.len()));
+        spans.push(recorded)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut recorded = Recorded {
+            name: event.metadata().name(),
+            fields: BTreeMap::new(),
+            parent: ctx
+                .event_span(event)
+                .and_then(|span| span.extensions().get::<SpanIndex>().map(|i| i.0)),
+        };
+        event.record(&mut recorded);
+        self.events.lock().unwrap().push(recorded)
+    }
+}
+
+/// Compiles the source with a recording subscriber in effect.
+fn compile_recording() -> Recorder {
+    let recorder = Recorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        common::compile(Options {
+            source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+            trace_filter: Some("debug".to_owned()),
+            ..Options::default()
+        });
+    });
+    recorder
+}
+
+#[test]
+fn processors_run_in_spans_per_function_within_pipeline_phase() {
+    let recorder = compile_recording();
+    let spans = recorder.spans.lock().unwrap();
+    let phases = spans
+        .iter()
+        .filter(|s| s.name == "phase")
+        .map(|s| (s.fields["phase"].as_str(), s.parent))
+        .collect::<Vec<_>>();
+    assert_eq!(phases, vec![
+        ("checking", None),
+        ("bytecode generation", None),
+        ("bytecode pipeline", None),
+        ("file format generation", None),
+        ("bytecode verification", None),
+    ]);
+    let pipeline = spans
+        .iter()
+        .position(|s| s.name == "phase" && s.fields["phase"] == "bytecode pipeline")
+        .unwrap();
+    let processor_spans = spans
+        .iter()
+        .filter(|s| s.name == "processor")
+        .collect::<Vec<_>>();
+    assert!(processor_spans.iter().all(|s| s.parent == Some(pipeline)));
+    let functions_of = |processor: &str| {
+        processor_spans
+            .iter()
+            .filter(|s| s.fields["name"] == processor)
+            .map(|s| s.fields["function"].as_str())
+            .collect::<Vec<_>>()
+    };
+    // Callees are processed before their callers.
+    assert_eq!(functions_of("UnusedVarsChecker"), vec!["m::f", "m::g"]);
+    assert_eq!(functions_of("ReferenceSafetyProcessor"), vec![
+        "m::f", "m::g"
+    ]);
+}
+
+#[test]
+fn diagnostics_are_emitted_in_span_of_processor() {
+    let recorder = compile_recording();
+    let spans = recorder.spans.lock().unwrap();
+    let events = recorder.events.lock().unwrap();
+    let warnings = events
+        .iter()
+        .filter(|e| e.fields.get("code").map(String::as_str) == Some("W02050"))
+        .collect::<Vec<_>>();
+    assert_eq!(warnings.len(), 1, "{:?}", events);
+    let span = &spans[warnings[0].parent.expect("span of event")];
+    assert_eq!(span.name, "processor");
+    assert_eq!(span.fields["name"], "UnusedVarsChecker");
+    assert_eq!(span.fields["function"], "m::f");
+    assert_eq!(warnings[0].fields["location"], "m.move:3:13");
+}
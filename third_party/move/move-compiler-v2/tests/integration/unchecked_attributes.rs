@@ -0,0 +1,69 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for skipping the checks of individual attributes via `Options::unchecked_attributes`.
+
+use crate::common;
+use move_compiler_v2::Options;
+
+const SOURCE: &str = "module 0x42::m {
+    #[experimental]
+    public fun f(): u64 { 1 }
+
+    #[experimentl]
+    public fun g(): u64 { 2 }
+}
+";
+
+/// Compiles the source, returning the messages of the diagnostics and of their labels, whether
+/// compilation succeeded or not.
+fn messages(skip_attribute_checks: bool, unchecked_attributes: &[&str]) -> Vec<String> {
+    common::collected_diagnostics(Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        skip_attribute_checks,
+        unchecked_attributes: unchecked_attributes.iter().map(|a| a.to_string()).collect(),
+        ..Options::default()
+    })
+    .into_iter()
+    .flat_map(|d| std::iter::once(d.message).chain(d.labels.into_iter().map(|l| l.message)))
+    .collect()
+}
+
+#[test]
+fn only_unchecked_attributes_are_accepted() {
+    let messages = messages(false, &["experimental"]);
+    assert!(
+        !messages.iter().any(|m| m.contains("'experimental'")),
+        "{:?}",
+        messages
+    );
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.starts_with("Attribute name 'experimentl' is unknown")),
+        "{:?}",
+        messages
+    );
+}
+
+#[test]
+fn all_attributes_are_checked_by_default() {
+    let messages = messages(false, &[]);
+    for name in ["experimental", "experimentl"] {
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.starts_with(&format!("Attribute name '{}' is unknown", name))),
+            "{:?}",
+            messages
+        );
+    }
+}
+
+#[test]
+fn skipping_all_attribute_checks_still_works() {
+    assert_eq!(messages(true, &[]), Vec::<String>::new());
+    assert_eq!(messages(true, &["experimental"]), vec![
+        "`--unchecked-attributes` has no effect since `--skip-attribute-checks` is set".to_owned()
+    ]);
+}
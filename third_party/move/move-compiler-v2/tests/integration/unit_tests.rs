@@ -0,0 +1,192 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs the unit tests of a package with the test plan produced by compiler v2, and checks the
+//! resolution of the arguments of test attributes.
+
+use crate::common;
+use move_compiler::{
+    compiled_unit::CompiledUnitEnum,
+    unit_test::{ExpectedFailure, ExpectedMoveError},
+};
+use move_compiler_v2::{run_move_compiler_collecting, unit_test, Options};
+use move_core_types::{account_address::AccountAddress, value::MoveValue};
+use move_unit_test::UnitTestingConfig;
+
+const SOURCE: &str = "module 0x42::counter {
+    const EOVERFLOW: u64 = 1;
+
+    public fun inc(x: u64): u64 {
+        if (x == 10) abort EOVERFLOW;
+        x + 1
+    }
+
+    #[test_only]
+    fun start(): u64 {
+        9
+    }
+
+    #[test]
+    fun inc_works() {
+        assert!(inc(start()) == 10, 0)
+    }
+
+    #[test(account = @0x1)]
+    fun takes_signer(account: signer) {
+        let _ = account;
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 0x42::counter::EOVERFLOW)]
+    fun inc_overflows() {
+        inc(10);
+    }
+
+    #[test]
+    #[expected_failure(arithmetic_error, location = Self)]
+    fun arithmetic_overflow() {
+        let x = 18446744073709551615;
+        let _ = x + 1;
+    }
+
+    #[test]
+    fun fails() {
+        abort 7
+    }
+}
+";
+
+fn options(compile_test_code: bool) -> Options {
+    Options {
+        source_buffers: vec![("counter.move".to_owned(), SOURCE.to_owned())],
+        compile_test_code,
+        ..Options::default()
+    }
+}
+
+#[test]
+fn run_tests_with_v2_plan() {
+    let (env, units) = common::compile(options(true));
+    let plan = unit_test::test_plan(&env, units);
+    let module_plan = plan
+        .module_tests
+        .values()
+        .next()
+        .expect("module with tests");
+    assert_eq!(module_plan.tests.keys().collect::<Vec<_>>(), vec![
+        "arithmetic_overflow",
+        "fails",
+        "inc_overflows",
+        "inc_works",
+        "takes_signer"
+    ]);
+    assert_eq!(module_plan.tests["takes_signer"].arguments.len(), 1);
+    assert!(matches!(
+        module_plan.tests["inc_overflows"].expected_failure,
+        Some(ExpectedFailure::ExpectedWithError(_))
+    ));
+
+    let (output, all_passed) = UnitTestingConfig::default_with_bound(None)
+        .run_and_report_unit_tests(plan, None, None, vec![])
+        .expect("tests run");
+    let output = String::from_utf8(output).expect("utf8");
+    assert!(!all_passed, "{}", output);
+    for test in [
+        "arithmetic_overflow",
+        "inc_overflows",
+        "inc_works",
+        "takes_signer",
+    ] {
+        assert!(
+            output.contains(&format!("[ PASS    ] 0x42::counter::{}", test)),
+            "{}",
+            output
+        );
+    }
+    assert!(
+        output.contains("[ FAIL    ] 0x42::counter::fails"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn test_code_is_only_compiled_on_request() {
+    let (env, units) = common::compile(options(false));
+    assert!(env.get_extension::<unit_test::ModuleTestPlans>().is_none());
+    let CompiledUnitEnum::Module(module) = &units[0].clone().into_compiled_unit() else {
+        panic!("expected a module")
+    };
+    assert_eq!(module.module.function_defs.len(), 1);
+}
+
+#[test]
+fn named_addresses_and_constants_are_resolved() {
+    let source = "module 0x42::m {
+    const EFAIL: u64 = 3;
+
+    #[test(admin = @admin)]
+    #[expected_failure(abort_code = 0x42::m::EFAIL)]
+    fun fails(admin: signer) {
+        let _ = admin;
+        abort EFAIL
+    }
+}
+";
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        named_address_mapping: vec!["admin=0xcafe".to_owned()],
+        compile_test_code: true,
+        ..Options::default()
+    };
+    let output = run_move_compiler_collecting(options).expect("compiles");
+    let plan = unit_test::build_test_plan(&output.env, &output.annotated_units);
+    let test = &plan
+        .module_tests
+        .values()
+        .next()
+        .expect("module with tests")
+        .tests["fails"];
+    assert_eq!(test.arguments, vec![MoveValue::Address(
+        AccountAddress::from_hex_literal("0xcafe").unwrap()
+    )]);
+    let Some(ExpectedFailure::ExpectedWithError(ExpectedMoveError(_, Some(code), _))) =
+        &test.expected_failure
+    else {
+        panic!("expected an abort code")
+    };
+    assert_eq!(*code, 3);
+    let (_, all_passed) = UnitTestingConfig::default_with_bound(None)
+        .run_and_report_unit_tests(plan, None, None, vec![])
+        .expect("tests run");
+    assert!(all_passed);
+}
+
+#[test]
+fn non_constant_abort_codes_are_errors() {
+    let source = "module 0x42::m {
+    public fun code(): u64 { 3 }
+
+    #[test]
+    #[expected_failure(abort_code = 0x42::m::code)]
+    fun fails() {
+        abort 3
+    }
+}
+";
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        compile_test_code: true,
+        ..Options::default()
+    };
+    let errors = run_move_compiler_collecting(options)
+        .err()
+        .expect("compilation fails");
+    assert!(
+        errors
+            .iter()
+            .any(|d| d.message == "unknown constant `0x42::m::code`"),
+        "{:?}",
+        errors
+    );
+}
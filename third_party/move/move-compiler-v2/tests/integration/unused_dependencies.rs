@@ -0,0 +1,103 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the warning about dependencies which contribute nothing to the build.
+
+use crate::common;
+use move_compiler_v2::{run_move_compiler_collecting, Options};
+
+const USED: &str = "module 0x42::used {
+    struct R has drop { x: u64 }
+    public fun f(): u64 { 1 }
+}
+";
+
+const UNUSED: &str = "module 0x42::unused {
+    public fun g(): u64 { 2 }
+}
+";
+
+/// Compiles the source with the dependencies, returning the messages of the warnings about
+/// unused dependencies.
+fn unused_dependency_warnings(source: &str, dependencies: Vec<String>, test: bool) -> Vec<String> {
+    let output = run_move_compiler_collecting(Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        dependencies,
+        compile_test_code: test,
+        ..Options::default()
+    })
+    .expect("compiles");
+    output
+        .diagnostics
+        .into_iter()
+        .filter(|d| d.code.as_deref() == Some("W01008"))
+        .map(|d| d.message)
+        .collect()
+}
+
+#[test]
+fn unused_dependency_file_is_reported() {
+    let (_dir, paths) = common::write_files(&[("used.move", USED), ("unused.move", UNUSED)]);
+    let source = "module 0x42::m {
+    public fun h(): u64 { 0x42::used::f() }
+}
+";
+    assert_eq!(
+        unused_dependency_warnings(source, paths.clone(), false),
+        vec![format!(
+            "dependency `{}` is not used by the compiled modules",
+            paths[1]
+        )]
+    );
+}
+
+#[test]
+fn dependencies_used_in_types_and_transitively_are_not_reported() {
+    let (_dir, paths) = common::write_files(&[
+        ("used.move", USED),
+        (
+            "wrapper.move",
+            "module 0x42::wrapper {
+    public fun g(): u64 { 0x42::used::f() }
+}
+",
+        ),
+    ]);
+    // `used` is used in a type, and via `wrapper`.
+    let source = "module 0x42::m {
+    public fun h(_r: &0x42::used::R): u64 { 0x42::wrapper::g() }
+}
+";
+    assert!(unused_dependency_warnings(source, paths, false).is_empty());
+}
+
+#[test]
+fn dependency_directory_is_reported_if_no_file_is_used() {
+    let (dir, _) = common::write_files(&[("unused.move", UNUSED)]);
+    let root = dir.path().to_string_lossy().to_string();
+    let source = "module 0x42::m {
+    public fun h(): u64 { 1 }
+}
+";
+    assert_eq!(
+        unused_dependency_warnings(source, vec![root.clone()], false),
+        vec![format!(
+            "dependency `{}` is not used by the compiled modules",
+            root
+        )]
+    );
+}
+
+#[test]
+fn test_only_usage_counts_in_test_mode() {
+    let (_dir, paths) = common::write_files(&[("unused.move", UNUSED)]);
+    let source = "module 0x42::m {
+    public fun h(): u64 { 1 }
+
+    #[test_only]
+    public fun t(): u64 { 0x42::unused::g() }
+}
+";
+    assert!(unused_dependency_warnings(source, paths.clone(), true).is_empty());
+    assert_eq!(unused_dependency_warnings(source, paths, false).len(), 1);
+}
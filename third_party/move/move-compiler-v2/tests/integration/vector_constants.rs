@@ -0,0 +1,99 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for folding vectors of constants into loads from the constant pool, checking which
+//! vectors are folded, and that folded vectors are equal to vectors constructed at runtime by
+//! running the unit tests of a module.
+
+use crate::common;
+use move_binary_format::file_format::Bytecode;
+use move_compiler::compiled_unit::AnnotatedCompiledUnit;
+use move_compiler_v2::{Experiment, Options};
+use move_model::model::GlobalEnv;
+
+const SOURCE: &str = "module 0x42::m {
+    struct S has copy, drop {
+        x: u64
+    }
+
+    public fun numbers(): vector<u64> {
+        vector[1, 2, 3]
+    }
+
+    public fun bytes(): vector<u8> {
+        vector[104, 105]
+    }
+
+    public fun nested(): vector<vector<u8>> {
+        vector[b\"ab\", vector[1, 2]]
+    }
+
+    public fun structs(): vector<S> {
+        vector[S { x: 1 }]
+    }
+
+    public fun dynamic(a: u64): vector<u64> {
+        vector[a, 2, 3]
+    }
+
+    fun byte(b: u8): u8 {
+        b
+    }
+
+    #[test]
+    fun folded_vectors_are_equal_to_built_ones() {
+        assert!(numbers() == dynamic(1), 0);
+        assert!(bytes() == vector[byte(104), byte(105)], 1);
+        assert!(nested() == vector[vector[byte(97), 98], vector[byte(1), 2]], 2);
+        assert!(structs() == vector[S { x: 1 }], 3);
+    }
+}
+";
+
+fn compile(
+    max_constant_size: Option<usize>,
+    compile_test_code: bool,
+) -> (GlobalEnv, Vec<AnnotatedCompiledUnit>) {
+    let options = Options {
+        source_buffers: vec![("m.move".to_owned(), SOURCE.to_owned())],
+        experiments: vec![Experiment::CONSTANT_FOLDING.to_owned()],
+        max_constant_size,
+        compile_test_code,
+        ..Options::default()
+    };
+    common::compile(options)
+}
+
+/// Returns the functions of the only unit which construct a vector at runtime.
+fn packing_functions(max_constant_size: Option<usize>) -> Vec<String> {
+    let (_, units) = compile(max_constant_size, false);
+    let module = common::compiled_module(&units[0]);
+    common::defined_functions(&module)
+        .into_iter()
+        .filter(|name| {
+            common::function_code(&module, name)
+                .iter()
+                .any(|bc| matches!(bc, Bytecode::VecPack(..)))
+        })
+        .collect()
+}
+
+#[test]
+fn vectors_of_constants_are_folded() {
+    assert_eq!(packing_functions(None), vec!["structs", "dynamic"]);
+}
+
+#[test]
+fn vectors_exceeding_the_constant_size_are_not_folded() {
+    // The serialized `numbers` take 25 bytes, the others at most 7 bytes.
+    assert_eq!(packing_functions(Some(16)), vec![
+        "numbers", "structs", "dynamic"
+    ]);
+}
+
+#[test]
+fn folded_vectors_pass_tests() {
+    let (env, units) = compile(None, true);
+    let (output, all_passed) = common::run_unit_tests(&env, units);
+    assert!(all_passed, "{}", output);
+}
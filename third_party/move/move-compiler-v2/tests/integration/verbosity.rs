@@ -0,0 +1,96 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for the verbosity of the output written to the error writer.
+
+use crate::common;
+use move_compiler_v2::Options;
+
+const SOURCE: &str = "module 0x42::m {
+    public fun f(x: u64): u64 {
+        let y = 1;
+        x
+    }
+}
+";
+
+/// Compiles the source with the given command line arguments and statistics printed, returning
+/// whether compilation succeeded and the output.
+fn compile(source: &str, args: &[&str]) -> (bool, String) {
+    let args = std::iter::once("--print-stats")
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>();
+    common::compile_reporting(Options {
+        source_buffers: vec![("m.move".to_owned(), source.to_owned())],
+        ..common::parse_options(&args)
+    })
+}
+
+const PROGRESS: [&str; 7] = [
+    "phase: checking",
+    "checked 1 target and 0 dependency module(s)",
+    "phase: bytecode generation",
+    "phase: bytecode pipeline",
+    "phase: file format generation",
+    "generated 1 unit(s)",
+    "phase: bytecode verification",
+];
+
+#[test]
+fn quiet_only_reports_errors() {
+    assert_eq!(compile(SOURCE, &["-q"]), (true, String::new()));
+    let (ok, output) = compile("module 0x42::m { fun f(): u64 { true } }", &["-q"]);
+    assert!(!ok);
+    assert!(output.starts_with("error: expected `u64`"), "{}", output);
+}
+
+#[test]
+fn default_reports_warnings_and_requested_output() {
+    let (ok, output) = compile(SOURCE, &[]);
+    assert!(ok);
+    assert!(output.contains("warning[W02050]"), "{}", output);
+    assert!(output.contains("generated 1 module(s)"), "{}", output);
+    assert!(!output.contains("phase: "), "{}", output);
+}
+
+#[test]
+fn verbose_reports_progress() {
+    for args in [&["-v"][..], &["-vv"], &["--verbosity", "2"]] {
+        let (ok, output) = compile(SOURCE, args);
+        assert!(ok);
+        let lines = output.lines().collect::<Vec<_>>();
+        let progress = lines
+            .iter()
+            .filter(|line| PROGRESS.contains(line))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(progress, PROGRESS, "{}", output);
+        assert!(output.contains("warning[W02050]"), "{}", output);
+        assert!(output.contains("generated 1 module(s)"), "{}", output);
+    }
+}
+
+#[test]
+fn verbose_reports_written_files() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let dir_arg = dir.path().display().to_string();
+    let (ok, output) = compile(SOURCE, &[
+        "-v",
+        "--dump-disassembly",
+        "--dump-bytecode-dir",
+        &dir_arg,
+    ]);
+    assert!(ok);
+    let written = dir.path().join("0x42_m.mvasm");
+    assert!(
+        output.contains(&format!("dumped disassembly to `{}`", written.display())),
+        "{}",
+        output
+    );
+    let (_, output) = compile(SOURCE, &[
+        "--dump-disassembly",
+        "--dump-bytecode-dir",
+        &dir_arg,
+    ]);
+    assert!(!output.contains("dumped disassembly"), "{}", output);
+}
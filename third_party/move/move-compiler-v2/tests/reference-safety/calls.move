@@ -0,0 +1,27 @@
+module 0x42::calls {
+    struct S has drop { f: u64 }
+
+    fun id_mut(x: &mut u64): &mut u64 { x }
+
+    fun take_mut(_x: &mut u64) {}
+
+    fun borrow_across_call(s: &mut S) {
+        let r = id_mut(&mut s.f);
+        let r2 = &s.f;
+        *r = 1;
+        let _ = *r2;
+    }
+
+    fun pass_borrowed_mut(x: &mut u64) {
+        let r: &u64 = x;
+        take_mut(x);
+        let _ = *r;
+    }
+
+    fun valid_sequential_calls(x: &mut u64): u64 {
+        let r = id_mut(x);
+        *r = 1;
+        take_mut(x);
+        *x
+    }
+}
@@ -0,0 +1,54 @@
+module 0x42::fields {
+    struct S has drop { f: u64, g: u64 }
+
+    fun disjoint_fields(s: &mut S): u64 {
+        let f = &mut s.f;
+        let g = &mut s.g;
+        *f = 1;
+        *g = 2;
+        s.f
+    }
+
+    fun same_field_mut(s: &mut S) {
+        let f1 = &mut s.f;
+        let f2 = &mut s.f;
+        *f1 = 1;
+        *f2 = 2;
+    }
+
+    fun read_parent_while_field_borrowed(s: &mut S) {
+        let f = &mut s.f;
+        let x = *s;
+        *f = x.g;
+    }
+
+    fun write_parent_while_field_borrowed(s: &mut S) {
+        let f = &s.f;
+        *s = S { f: 1, g: 2 };
+        let _ = *f;
+    }
+
+    fun mut_borrow_local_twice(): u64 {
+        let x = 1;
+        let r1 = &mut x;
+        let r2 = &mut x;
+        *r1 = 2;
+        *r2 = 3;
+        x
+    }
+
+    fun assign_while_borrowed(): u64 {
+        let x = 1;
+        let r = &x;
+        x = 2;
+        *r + x
+    }
+
+    fun copy_while_mutably_borrowed(): u64 {
+        let x = 1;
+        let r = &mut x;
+        let y = x;
+        *r = y;
+        x
+    }
+}
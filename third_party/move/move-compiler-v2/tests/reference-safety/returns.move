@@ -0,0 +1,44 @@
+module 0x42::returns {
+    struct R has key { f: u64 }
+
+    fun return_local_ref(): &u64 {
+        let x = 1;
+        &x
+    }
+
+    fun return_param_ref(x: u64): &mut u64 {
+        &mut x
+    }
+
+    fun return_global_ref(a: address): &R acquires R {
+        borrow_global<R>(a)
+    }
+
+    fun return_field_of_param(r: &R): &u64 {
+        &r.f
+    }
+
+    fun move_from_while_borrowed(a: address): u64 acquires R {
+        let r = borrow_global<R>(a);
+        let R { f } = move_from<R>(a);
+        r.f + f
+    }
+
+    fun freeze_while_mutably_borrowed(r: &mut R): u64 {
+        let f = &mut r.f;
+        let r2: &R = r;
+        *f = 1;
+        r2.f
+    }
+
+    fun read_f(a: address): u64 acquires R {
+        borrow_global<R>(a).f
+    }
+
+    fun call_acquiring_while_borrowed(a: address): u64 acquires R {
+        let r = borrow_global_mut<R>(a);
+        let x = read_f(a);
+        r.f = x;
+        x
+    }
+}
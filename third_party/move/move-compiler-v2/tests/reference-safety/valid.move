@@ -0,0 +1,37 @@
+module 0x42::valid {
+    struct S has copy, drop { f: u64, g: u64 }
+
+    fun sequential_borrows(): u64 {
+        let x = 1;
+        let r1 = &mut x;
+        *r1 = 2;
+        let r2 = &mut x;
+        *r2 = 3;
+        x
+    }
+
+    fun shared_borrows(s: &S): u64 {
+        let f1 = &s.f;
+        let f2 = &s.f;
+        *f1 + *f2
+    }
+
+    fun borrow_in_branch(c: bool, s: &mut S) {
+        let r = if (c) &mut s.f else &mut s.g;
+        *r = 1;
+        s.f = 2;
+    }
+
+    fun loop_borrow(s: &mut S) {
+        let i = 0;
+        while (i < 10) {
+            let f = &mut s.f;
+            *f = *f + 1;
+            i = i + 1;
+        }
+    }
+
+    fun pass_through(s: &mut S): &mut u64 {
+        &mut s.f
+    }
+}
@@ -0,0 +1,22 @@
+module 0x42::vectors {
+    use std::vector;
+
+    fun push_while_element_borrowed(v: &mut vector<u64>) {
+        let e = vector::borrow_mut(v, 0);
+        vector::push_back(v, 1);
+        *e = 2;
+    }
+
+    fun read_while_element_borrowed(v: &mut vector<u64>): u64 {
+        let e = vector::borrow_mut(v, 0);
+        let n = vector::length(v);
+        *e = n;
+        n
+    }
+
+    fun valid_element_update(v: &mut vector<u64>) {
+        let e = vector::borrow_mut(v, 0);
+        *e = 1;
+        vector::push_back(v, 2);
+    }
+}
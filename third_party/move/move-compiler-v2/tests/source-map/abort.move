@@ -0,0 +1,19 @@
+module 0x42::m {
+    struct S<T> has drop {
+        x: u64,
+        y: T,
+    }
+
+    fun check(s: S<bool>, limit: u64): u64 {
+        let sum = s.x + limit;
+        if (s.y) {
+            abort 1
+        };
+        assert!(sum < 100, 2);
+        sum
+    }
+
+    fun call_check(limit: u64): u64 {
+        check(S { x: 1, y: false }, limit)
+    }
+}
@@ -0,0 +1,10 @@
+// flags: --emit-debug-info
+module 0x42::m {
+    fun count(n: u64): u64 {
+        let my_counter = 0;
+        while (my_counter < n) {
+            my_counter = my_counter + 1
+        };
+        my_counter * 2
+    }
+}
@@ -3,12 +3,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use codespan_reporting::{diagnostic::Severity, term::termcolor::Buffer};
-use move_binary_format::{binary_views::BinaryIndexedView, file_format as FF};
+use move_binary_format::{
+    access::ModuleAccess, binary_views::BinaryIndexedView, file_format as FF,
+};
+use move_bytecode_source_map::source_map::{SourceMap, SourceName};
 use move_command_line_common::files::FileHash;
-use move_compiler::compiled_unit::CompiledUnit;
+use move_compiler::compiled_unit::{AnnotatedCompiledUnit, CompiledUnit};
 use move_compiler_v2::{
+    diagnostic_codes,
     pipeline::{
-        livevar_analysis_processor::LiveVarAnalysisProcessor, visibility_checker::VisibilityChecker,
+        ability_checker::AbilityChecker, acquires_inference::AcquiresInference,
+        cfg_simplifier::CfgSimplifier, constant_folding::ConstantFolder,
+        copy_propagation::CopyPropagation, dead_store_elimination::DeadStoreElimination,
+        explicit_drop::ExplicitDrop, function_inlining::FunctionInliner,
+        livevar_analysis_processor::LiveVarAnalysisProcessor,
+        reference_safety_processor::ReferenceSafetyProcessor,
+        unreachable_code_remover::UnreachableCodeRemover, unused_vars_checker::UnusedVarsChecker,
+        visibility_checker::VisibilityChecker,
     },
     run_file_format_gen, Options,
 };
@@ -40,6 +51,15 @@ struct TestConfig {
     generate_file_format: bool,
     /// Whether we should dump annotated targets for each stage of the pipeline.
     dump_annotated_targets: bool,
+    /// Whether we should dump the source map of the generated file format.
+    dump_source_map: bool,
+    /// Whether we should run the bytecode verifier on the generated file format. Functions
+    /// named in `// inject-error: <function>` directives are corrupted before, to test the
+    /// reporting of verification failures.
+    verify_bytecode: bool,
+    /// Whether the full compiler driver (`run_move_compiler`) should be run instead of the
+    /// individual phases, dumping the diagnostics it reports.
+    run_driver: bool,
 }
 
 fn path_from_crate_root(path: &str) -> String {
@@ -57,17 +77,32 @@ fn test_runner(path: &Path) -> datatest_stable::Result<()> {
     let mut sources = extract_test_directives(path, "// dep:")?;
     sources.push(path.to_string_lossy().to_string());
     let deps = vec![path_from_crate_root("../move-stdlib/sources")];
+    // Additional command line flags for the compiler, as in `// flags: --flag value`.
+    let flags = extract_test_directives(path, "// flags:")?
+        .into_iter()
+        .flat_map(|f| {
+            f.split_whitespace()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
     // For each experiment, run the test at `path`.
     for experiment in experiments {
         // Construct options, compiler and collect output.
-        let options = Options {
+        let mut options = Options {
             testing: true,
             sources: sources.clone(),
             dependencies: deps.clone(),
             named_address_mapping: vec!["std=0x1".to_string()],
-            ..Options::default()
+            ..Options::try_parse_command_line(
+                std::iter::once("move-compiler-v2".to_string()).chain(flags.iter().cloned()),
+            )?
         };
+        // The standard library is a dependency of all tests, most of which do not use it.
+        options
+            .suppressed_warnings
+            .push(diagnostic_codes::UNUSED_DEPENDENCY.code.to_owned());
         TestConfig::get_config_from_path(path).run(path, experiment, options)?
     }
     Ok(())
@@ -84,6 +119,9 @@ impl TestConfig {
                 pipeline,
                 generate_file_format: false,
                 dump_annotated_targets: false,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
             }
         } else if path.contains("/bytecode-generator/") {
             Self {
@@ -92,15 +130,148 @@ impl TestConfig {
                 pipeline,
                 generate_file_format: false,
                 dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
             }
         } else if path.contains("/file-format-generator/") {
             pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: true,
+                dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/constant-folding/") {
+            pipeline.add_processor(Box::new(ConstantFolder {}));
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: true,
+                dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/copy-propagation/") {
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(CopyPropagation {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: true,
+                dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/dead-store-elimination/") {
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(DeadStoreElimination {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: true,
+                dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/cfg-simplification/") {
+            pipeline.add_processor(Box::new(CfgSimplifier {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: false,
+                dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/function-inlining/") {
+            pipeline.add_processor(Box::new(FunctionInliner {}));
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(ExplicitDrop {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: true,
+                dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: true,
+                run_driver: false,
+            }
+        } else if path.contains("/explicit-drop/") {
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(ExplicitDrop {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
             Self {
                 type_check_only: false,
                 dump_ast: false,
                 pipeline,
                 generate_file_format: true,
                 dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/source-map/") {
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(ExplicitDrop {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: true,
+                dump_annotated_targets: false,
+                dump_source_map: true,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/bytecode-verifier/") {
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(ExplicitDrop {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: true,
+                dump_annotated_targets: false,
+                dump_source_map: false,
+                verify_bytecode: true,
+                run_driver: false,
+            }
+        } else if path.contains("/unreachable-code/") {
+            pipeline.add_processor(Box::new(UnreachableCodeRemover {}));
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(ExplicitDrop {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: true,
+                dump_annotated_targets: true,
+                dump_source_map: false,
+                verify_bytecode: true,
+                run_driver: false,
             }
         } else if path.contains("/visibility-checker/") {
             pipeline.add_processor(Box::new(VisibilityChecker {}));
@@ -110,6 +281,71 @@ impl TestConfig {
                 pipeline,
                 generate_file_format: false,
                 dump_annotated_targets: false,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/ability-checker/") {
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(AbilityChecker {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: false,
+                dump_annotated_targets: false,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/acquires-checker/") {
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: false,
+                dump_annotated_targets: false,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/reference-safety/") {
+            pipeline.add_processor(Box::new(LiveVarAnalysisProcessor {}));
+            pipeline.add_processor(Box::new(AcquiresInference {}));
+            pipeline.add_processor(Box::new(ReferenceSafetyProcessor {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: false,
+                dump_annotated_targets: false,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/unused-vars/") {
+            pipeline.add_processor(Box::new(UnusedVarsChecker {}));
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: false,
+                dump_annotated_targets: false,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: false,
+            }
+        } else if path.contains("/driver/") {
+            Self {
+                type_check_only: false,
+                dump_ast: false,
+                pipeline,
+                generate_file_format: false,
+                dump_annotated_targets: false,
+                dump_source_map: false,
+                verify_bytecode: false,
+                run_driver: true,
             }
         } else {
             panic!(
@@ -132,6 +368,16 @@ impl TestConfig {
         // in closures.
         let test_output = RefCell::new(String::new());
 
+        if self.run_driver {
+            Self::run_driver(&mut test_output.borrow_mut(), options);
+            let baseline_path = path.with_extension(exp_file_ext);
+            baseline_test::verify_or_update_baseline(
+                baseline_path.as_path(),
+                &test_output.borrow(),
+            )?;
+            return Ok(());
+        }
+
         // Run context checker
         let env = move_compiler_v2::run_checker(options)?;
         let ok = Self::check_diags(&mut test_output.borrow_mut(), &env);
@@ -185,14 +431,39 @@ impl TestConfig {
                 );
                 let ok = Self::check_diags(&mut test_output.borrow_mut(), &env);
                 if ok && self.generate_file_format {
-                    let units = run_file_format_gen(&env, &targets);
+                    let mut units = run_file_format_gen(&env, &targets);
+                    if self.verify_bytecode && !env.has_errors() {
+                        let corrupted = extract_test_directives(path, "// inject-error:")?;
+                        Self::inject_verifier_errors(&mut units, &corrupted);
+                        move_compiler_v2::run_bytecode_verifier(&env, &units);
+                    }
                     let out = &mut test_output.borrow_mut();
                     out.push_str("\n============ disassembled file-format ==================\n");
-                    Self::check_diags(out, &env);
+                    let ok = Self::check_diags(out, &env);
                     for compiled_unit in units {
-                        if let CompiledUnit::Module(compiled_mod) = compiled_unit {
-                            let cont = Self::disassemble(&compiled_mod.module)?;
-                            out.push_str(&cont)
+                        if ok {
+                            Self::check_serialized_version(&compiled_unit)?;
+                        }
+                        match compiled_unit {
+                            CompiledUnit::Module(compiled_mod) => {
+                                let cont = Self::disassemble(BinaryIndexedView::Module(
+                                    &compiled_mod.module,
+                                ))?;
+                                out.push_str(&cont);
+                                if self.dump_source_map {
+                                    out.push_str(&Self::dump_source_map(
+                                        &env,
+                                        &compiled_mod.module,
+                                        &compiled_mod.source_map,
+                                    )?)
+                                }
+                            },
+                            CompiledUnit::Script(compiled_script) => {
+                                let cont = Self::disassemble(BinaryIndexedView::Script(
+                                    &compiled_script.script,
+                                ))?;
+                                out.push_str(&cont)
+                            },
                         }
                     }
                 }
@@ -206,6 +477,127 @@ impl TestConfig {
         Ok(())
     }
 
+    /// Runs the full compiler driver, recording the diagnostics it reports and the outcome.
+    fn run_driver(out: &mut String, mut options: Options) {
+        // Redirect bytecode, model, and disassembly dumps, and the error map, into a fresh
+        // directory, and record the files written there.
+        let dumps = options.dump_bytecode
+            || options.dump_model
+            || options.dump_disassembly
+            || options.dump_bytecode_diff
+            || options.dump_bytecode_diff_annotations;
+        let dump_dir = if dumps || options.emit_error_map.is_some() {
+            let source = Path::new(&options.sources[options.sources.len() - 1]);
+            let dir = std::env::temp_dir().join(format!(
+                "move-compiler-v2-dump-{}-{}",
+                std::process::id(),
+                source.file_stem().unwrap_or_default().to_string_lossy()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            if dumps {
+                options.dump_bytecode_dir = Some(dir.clone())
+            }
+            if options.emit_error_map.is_some() {
+                options.emit_error_map = Some(dir.join("error_map.json"))
+            }
+            Some(dir)
+        } else {
+            None
+        };
+        let mut error_writer = Buffer::no_color();
+        if options.check_only {
+            let result = move_compiler_v2::run_move_compiler_check_only(&mut error_writer, options);
+            Self::record_dumped_files(out, dump_dir);
+            let diag = String::from_utf8_lossy(&error_writer.into_inner()).to_string();
+            if !diag.is_empty() {
+                *out += &format!("\nDiagnostics:\n{}", diag);
+            }
+            match result {
+                Ok(env) => {
+                    *out += &format!(
+                        "\nResult: checked {} module(s)\n",
+                        env.get_target_modules().len()
+                    )
+                },
+                Err(e) => *out += &format!("\nResult: {}\n", e),
+            }
+            return;
+        }
+        let result = move_compiler_v2::run_move_compiler(&mut error_writer, options);
+        Self::record_dumped_files(out, dump_dir);
+        let diag = String::from_utf8_lossy(&error_writer.into_inner()).to_string();
+        if !diag.is_empty() {
+            *out += &format!("\nDiagnostics:\n{}", diag);
+        }
+        match result {
+            Ok((env, units)) => {
+                *out += &format!("\nResult: compiled {} unit(s)\n", units.len());
+                let position = |loc: &location::Loc| {
+                    env.get_location(&env.to_loc(loc))
+                        .map(|pos| format!("{}:{}", pos.line.0 + 1, pos.column.0 + 1))
+                        .unwrap_or_else(|| "<unknown>".to_owned())
+                };
+                for unit in units {
+                    match unit {
+                        AnnotatedCompiledUnit::Module(module) => {
+                            *out += &format!(
+                                "module {} at {}\n",
+                                module.named_module.name,
+                                position(&module.loc)
+                            );
+                            for (loc, name, _) in &module.function_infos {
+                                *out += &format!("  fun {} at {}\n", name, position(&loc));
+                            }
+                        },
+                        AnnotatedCompiledUnit::Script(script) => {
+                            *out += &format!(
+                                "script {} at {}\n",
+                                script.named_script.name,
+                                position(&script.loc)
+                            );
+                        },
+                    }
+                }
+            },
+            Err(e) => {
+                *out += &format!("\nResult: {}\n", e);
+            },
+        }
+    }
+
+    /// Records the files written into the dump directory, if any, and removes it.
+    fn record_dumped_files(out: &mut String, dump_dir: Option<PathBuf>) {
+        if let Some(dir) = dump_dir {
+            let mut files = std::fs::read_dir(&dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.file_name().to_string_lossy().to_string())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            files.sort();
+            *out += "\nDumped files:\n";
+            for file in files {
+                *out += &format!("{}\n", file);
+                // Show the content of diffs, model dumps, disassembly, and the error map, which
+                // are small.
+                if file.ends_with(".diff")
+                    || file.ends_with(".model")
+                    || file.ends_with(".mvasm")
+                    || file.ends_with(".json")
+                {
+                    let content = std::fs::read_to_string(dir.join(&file)).unwrap_or_default();
+                    for line in content.lines() {
+                        *out += format!("  | {}", line).trim_end();
+                        *out += "\n";
+                    }
+                }
+            }
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
     /// Callback from the framework to register formatters for annotations.
     fn register_formatters(target: &FunctionTarget) {
         LiveVarAnalysisProcessor::register_formatters(target)
@@ -223,13 +615,101 @@ impl TestConfig {
         ok
     }
 
-    fn disassemble(module: &FF::CompiledModule) -> anyhow::Result<String> {
-        let diss = Disassembler::from_view(
-            BinaryIndexedView::Module(module),
-            location::Loc::new(FileHash::empty(), 0, 0),
-        )?;
+    fn disassemble(view: BinaryIndexedView) -> anyhow::Result<String> {
+        let diss = Disassembler::from_view(view, location::Loc::new(FileHash::empty(), 0, 0))?;
         diss.disassemble()
     }
+
+    /// Corrupts the code of the named functions by inserting a `Pop` on the empty stack.
+    fn inject_verifier_errors(units: &mut [CompiledUnit], fun_names: &[String]) {
+        for unit in units {
+            if let CompiledUnit::Module(compiled_mod) = unit {
+                let module = &mut compiled_mod.module;
+                for idx in 0..module.function_defs.len() {
+                    let handle = module.function_handle_at(module.function_defs[idx].function);
+                    let name = module.identifier_at(handle.name).to_string();
+                    if let Some(code) = &mut module.function_defs[idx].code {
+                        if fun_names.contains(&name) {
+                            code.code.insert(0, FF::Bytecode::Pop)
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that the unit can be serialized at its bytecode version, and that this version
+    /// is recorded in the header of the binary.
+    fn check_serialized_version(unit: &CompiledUnit) -> anyhow::Result<()> {
+        let (version, mut bytes) = (
+            match unit {
+                CompiledUnit::Module(m) => m.module.version,
+                CompiledUnit::Script(s) => s.script.version,
+            },
+            vec![],
+        );
+        match unit {
+            CompiledUnit::Module(m) => m.module.serialize_for_version(Some(version), &mut bytes)?,
+            CompiledUnit::Script(s) => s.script.serialize_for_version(Some(version), &mut bytes)?,
+        }
+        // The header consists of a 4 byte magic, followed by the version in little endian.
+        anyhow::ensure!(
+            bytes.get(4..8) == Some(&version.to_le_bytes()[..]),
+            "serialized binary does not have version {}",
+            version
+        );
+        Ok(())
+    }
+
+    /// Dumps the source map of a module, showing for each instruction the source position it
+    /// is mapped to.
+    fn dump_source_map(
+        env: &GlobalEnv,
+        module: &FF::CompiledModule,
+        source_map: &SourceMap,
+    ) -> anyhow::Result<String> {
+        let position = |loc: &location::Loc| {
+            env.get_file_and_location(&env.to_loc(loc))
+                .map(|(file, pos)| {
+                    let file = Path::new(&file)
+                        .file_name()
+                        .map(|f| f.to_string_lossy().to_string())
+                        .unwrap_or(file);
+                    format!("{}:{}:{}", file, pos.line.0 + 1, pos.column.0 + 1)
+                })
+                .unwrap_or_else(|| "<unknown>".to_owned())
+        };
+        let names = |names: &[SourceName]| {
+            names
+                .iter()
+                .map(|(name, loc)| format!("{}@{}", name, position(loc)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut out = "\n============ source map ==================\n".to_owned();
+        for (idx, def) in module.function_defs.iter().enumerate() {
+            let handle = module.function_handle_at(def.function);
+            let fun_map =
+                source_map.get_function_source_map(FF::FunctionDefinitionIndex::new(idx as u16))?;
+            out.push_str(&format!(
+                "\nfun {} at {}\n",
+                module.identifier_at(handle.name),
+                position(&fun_map.definition_location)
+            ));
+            out.push_str(&format!("  parameters: {}\n", names(&fun_map.parameters)));
+            out.push_str(&format!("  locals: {}\n", names(&fun_map.locals)));
+            if let Some(code) = &def.code {
+                for (offset, bc) in code.code.iter().enumerate() {
+                    let loc = fun_map
+                        .get_code_location(offset as FF::CodeOffset)
+                        .map(|loc| position(&loc))
+                        .unwrap_or_else(|| "<unmapped>".to_owned());
+                    out.push_str(&format!("  {}: {:?} at {}\n", offset, bc, loc));
+                }
+            }
+        }
+        Ok(out)
+    }
 }
 
 datatest_stable::harness!(test_runner, "tests", r".*\.move$");
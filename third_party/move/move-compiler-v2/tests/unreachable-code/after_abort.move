@@ -0,0 +1,34 @@
+module 0x42::m {
+    fun after_abort(x: u64): u64 {
+        abort 0;
+        x + 1
+    }
+
+    fun after_return(x: u64): u64 {
+        if (x > 0) {
+            return x;
+            x = x + 1;
+        };
+        x
+    }
+
+    // No warnings: the code following the `abort` and the `return`s is generated.
+    fun only_abort(): u64 {
+        abort 1
+    }
+
+    fun returns_in_branches(c: bool): u64 {
+        if (c) return 1 else return 2
+    }
+
+    fun returns_in_loop(x: u64): u64 {
+        loop {
+            if (x > 10) return x;
+            x = x + 1
+        }
+    }
+
+    fun infinite() {
+        loop {}
+    }
+}
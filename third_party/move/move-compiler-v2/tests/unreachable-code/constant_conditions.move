@@ -0,0 +1,20 @@
+module 0x42::m {
+    fun if_true(x: u64): u64 {
+        if (true) x else x + 1
+    }
+
+    fun if_false(x: u64): u64 {
+        if (false) {
+            x = x * 2
+        };
+        x
+    }
+
+    fun while_false(x: u64): u64 {
+        while (false) {
+            x = x + 1
+        };
+        x
+    }
+
+}
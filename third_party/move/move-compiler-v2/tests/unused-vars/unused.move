@@ -0,0 +1,55 @@
+module 0x42::unused {
+    struct S has drop { f: u64 }
+
+    fun unused_param(x: u64, y: u64): u64 {
+        x
+    }
+
+    fun unused_local(): u64 {
+        let x = 1;
+        let y = 2;
+        y
+    }
+
+    fun shadowed(): u64 {
+        let x = 1;
+        let x = 2;
+        x
+    }
+
+    fun shadowed_used(): u64 {
+        let x = 1;
+        let y = {
+            let x = x + 1;
+            x
+        };
+        y
+    }
+
+    fun underscore(_x: u64): u64 {
+        let _y = 1;
+        let _ = 2;
+        3
+    }
+
+    fun destructure(s: S): u64 {
+        let S { f } = s;
+        0
+    }
+
+    fun assigned_only(): u64 {
+        let x;
+        x = 1;
+        2
+    }
+
+    fun discarded(): u64 {
+        let x = 1;
+        let y = 2;
+        x;
+        _ = y;
+        3
+    }
+
+    native fun native_params(x: u64, y: address): u64;
+}
@@ -0,0 +1,20 @@
+module 0x2::A {
+    entry fun private_entry() {}
+    public(friend) entry fun friend_entry() {}
+    public entry fun public_entry() {}
+}
+
+module 0x2::B {
+    fun calls() {
+        0x2::A::private_entry();
+        0x2::A::friend_entry();
+        0x2::A::public_entry();
+    }
+}
+
+script {
+    fun main() {
+        0x2::A::private_entry();
+        0x2::A::public_entry();
+    }
+}
@@ -0,0 +1,14 @@
+module 0x2::A {
+    friend 0x2::B;
+    public(friend) fun f() {}
+}
+
+module 0x2::B {
+    friend 0x2::C;
+    public(friend) fun g() {}
+}
+
+module 0x2::C {
+    friend 0x2::A;
+    public(friend) fun h() {}
+}
@@ -0,0 +1,11 @@
+module 0x2::A {
+    friend 0x2::A;
+    friend 0x3::B;
+    // Friends need not be part of the compilation.
+    friend 0x2::Unknown;
+    public(friend) fun f() {}
+}
+
+module 0x3::B {
+    fun g() { 0x2::A::f() }
+}
@@ -0,0 +1,50 @@
+//# publish
+module 0x42::counter {
+    struct Counter has key { value: u64 }
+
+    struct Stats has key { updates: u64 }
+
+    public fun init(s: &signer) {
+        move_to(s, Counter { value: 0 });
+        move_to(s, Stats { updates: 0 });
+    }
+
+    fun bump(addr: address) acquires Counter {
+        let c = borrow_global_mut<Counter>(addr);
+        c.value = c.value + 1;
+    }
+
+    fun record_update(addr: address) acquires Stats {
+        let stats = borrow_global_mut<Stats>(addr);
+        stats.updates = stats.updates + 1;
+    }
+
+    public fun increment(addr: address) acquires Counter, Stats {
+        bump(addr);
+        record_update(addr);
+    }
+
+    public fun value(addr: address): u64 acquires Counter {
+        borrow_global<Counter>(addr).value
+    }
+
+    public fun remove(addr: address): (u64, u64) acquires Counter, Stats {
+        let Counter { value } = move_from<Counter>(addr);
+        let Stats { updates } = move_from<Stats>(addr);
+        (value, updates)
+    }
+}
+
+//# run --signers 0x42
+script {
+    use 0x42::counter;
+    fun main(s: signer) {
+        counter::init(&s);
+        counter::increment(@0x42);
+        counter::increment(@0x42);
+        assert!(counter::value(@0x42) == 2, 1);
+        let (value, updates) = counter::remove(@0x42);
+        assert!(value == 2, 2);
+        assert!(updates == 2, 3);
+    }
+}
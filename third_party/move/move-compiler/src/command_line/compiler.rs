@@ -42,6 +42,7 @@ pub struct Compiler<'a> {
     interface_files_dir_opt: Option<String>,
     pre_compiled_lib: Option<&'a FullyCompiledProgram>,
     compiled_module_named_address_mapping: BTreeMap<CompiledModuleId, String>,
+    source_buffers: BTreeMap<Symbol, String>,
     flags: Flags,
     known_attributes: BTreeSet<String>,
 }
@@ -136,6 +137,7 @@ impl<'a> Compiler<'a> {
             interface_files_dir_opt: None,
             pre_compiled_lib: None,
             compiled_module_named_address_mapping: BTreeMap::new(),
+            source_buffers: BTreeMap::new(),
             flags,
             known_attributes: known_attributes.clone(),
         }
@@ -197,6 +199,15 @@ impl<'a> Compiler<'a> {
         self
     }
 
+    /// Sets the contents of source files which are not read from the file system. A path of
+    /// the targets or dependencies which is a key of `source_buffers` denotes the given source,
+    /// and is used as the file name in diagnostics.
+    pub fn set_source_buffers(mut self, source_buffers: BTreeMap<Symbol, String>) -> Self {
+        assert!(self.source_buffers.is_empty());
+        self.source_buffers = source_buffers;
+        self
+    }
+
     pub fn run<const TARGET: Pass>(
         self,
     ) -> anyhow::Result<(
@@ -206,22 +217,28 @@ impl<'a> Compiler<'a> {
         let Self {
             maps,
             targets,
-            mut deps,
+            deps,
             interface_files_dir_opt,
             pre_compiled_lib,
             compiled_module_named_address_mapping,
+            source_buffers,
             flags,
             mut known_attributes,
         } = self;
+        // Only dependencies in the file system can contain bytecode.
+        let (buffered_deps, mut deps): (Vec<_>, Vec<_>) = deps
+            .into_iter()
+            .partition(|p| source_buffers.contains_key(&p.path));
         generate_interface_files_for_deps(
             &mut deps,
             interface_files_dir_opt,
             &compiled_module_named_address_mapping,
         )?;
+        deps.extend(buffered_deps);
         add_attributes_for_flavor(&flags, &mut known_attributes);
         let mut compilation_env = CompilationEnv::new(flags, known_attributes);
         let (source_text, pprog_and_comments_res) =
-            parse_program(&mut compilation_env, maps, targets, deps)?;
+            parse_program(&mut compilation_env, maps, targets, deps, &source_buffers)?;
         let res: Result<_, Diagnostics> = pprog_and_comments_res.and_then(|(pprog, comments)| {
             SteppedCompiler::new_at_parser(compilation_env, pre_compiled_lib, pprog)
                 .run::<TARGET>()
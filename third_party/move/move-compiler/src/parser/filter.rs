@@ -122,7 +122,7 @@ pub trait FilterContext {
 }
 
 /// This filters out module member from `prog` based on supplied `FilterContext` implementation
-pub fn filter_program<T: FilterContext>(context: &mut T, prog: P::Program) -> P::Program {
+pub fn filter_program<T: FilterContext + ?Sized>(context: &mut T, prog: P::Program) -> P::Program {
     let P::Program {
         named_address_maps,
         source_definitions,
@@ -170,7 +170,7 @@ pub fn filter_program<T: FilterContext>(context: &mut T, prog: P::Program) -> P:
     }
 }
 
-fn filter_definition<T: FilterContext>(
+fn filter_definition<T: FilterContext + ?Sized>(
     context: &mut T,
     def: P::Definition,
     is_source_def: bool,
@@ -188,7 +188,7 @@ fn filter_definition<T: FilterContext>(
     }
 }
 
-fn filter_address<T: FilterContext>(
+fn filter_address<T: FilterContext + ?Sized>(
     context: &mut T,
     address_def: P::AddressDefinition,
     is_source_def: bool,
@@ -215,7 +215,7 @@ fn filter_address<T: FilterContext>(
     })
 }
 
-fn filter_script<T: FilterContext>(
+fn filter_script<T: FilterContext + ?Sized>(
     context: &mut T,
     script_def: P::Script,
     is_source_def: bool,
@@ -262,7 +262,7 @@ fn filter_script<T: FilterContext>(
     })
 }
 
-fn filter_module<T: FilterContext>(
+fn filter_module<T: FilterContext + ?Sized>(
     context: &mut T,
     module_def: P::ModuleDefinition,
     is_source_def: bool,
@@ -293,7 +293,7 @@ fn filter_module<T: FilterContext>(
     })
 }
 
-fn filter_module_member<T: FilterContext>(
+fn filter_module_member<T: FilterContext + ?Sized>(
     context: &mut T,
     module_member: P::ModuleMember,
     is_source_def: bool,
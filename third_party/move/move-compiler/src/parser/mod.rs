@@ -4,11 +4,11 @@
 
 pub mod ast;
 pub mod comments;
-pub(crate) mod filter;
+pub mod filter;
 pub mod keywords;
 pub mod lexer;
 pub(crate) mod merge_spec_modules;
-pub(crate) mod syntax;
+pub mod syntax;
 
 use crate::{
     attr_derivation,
@@ -21,7 +21,7 @@ use comments::*;
 use move_command_line_common::files::{find_move_filenames, FileHash};
 use move_symbol_pool::Symbol;
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fs::File,
     io::Read,
 };
@@ -31,12 +31,14 @@ pub(crate) fn parse_program(
     named_address_maps: NamedAddressMaps,
     targets: Vec<IndexedPackagePath>,
     deps: Vec<IndexedPackagePath>,
+    source_buffers: &BTreeMap<Symbol, String>,
 ) -> anyhow::Result<(
     FilesSourceText,
     Result<(parser::ast::Program, CommentMap), Diagnostics>,
 )> {
     fn find_move_filenames_with_address_mapping(
         paths_with_mapping: Vec<IndexedPackagePath>,
+        source_buffers: &BTreeMap<Symbol, String>,
     ) -> anyhow::Result<Vec<IndexedPackagePath>> {
         let mut res = vec![];
         for IndexedPackagePath {
@@ -45,6 +47,14 @@ pub(crate) fn parse_program(
             named_address_map: named_address_mapping,
         } in paths_with_mapping
         {
+            if source_buffers.contains_key(&path) {
+                res.push(IndexedPackagePath {
+                    package,
+                    path,
+                    named_address_map: named_address_mapping,
+                });
+                continue;
+            }
             res.extend(
                 find_move_filenames(&[path.as_str()], true)?
                     .into_iter()
@@ -61,8 +71,8 @@ pub(crate) fn parse_program(
         Ok(res)
     }
 
-    let targets = find_move_filenames_with_address_mapping(targets)?;
-    let mut deps = find_move_filenames_with_address_mapping(deps)?;
+    let targets = find_move_filenames_with_address_mapping(targets, source_buffers)?;
+    let mut deps = find_move_filenames_with_address_mapping(deps, source_buffers)?;
     ensure_targets_deps_dont_intersect(compilation_env, &targets, &mut deps)?;
     let mut files: FilesSourceText = HashMap::new();
     let mut source_definitions = Vec::new();
@@ -76,7 +86,8 @@ pub(crate) fn parse_program(
         named_address_map,
     } in targets
     {
-        let (defs, comments, ds, file_hash) = parse_file(compilation_env, &mut files, path)?;
+        let (defs, comments, ds, file_hash) =
+            parse_file(compilation_env, &mut files, path, source_buffers)?;
         source_definitions.extend(defs.into_iter().map(|def| PackageDefinition {
             package,
             named_address_map,
@@ -92,7 +103,7 @@ pub(crate) fn parse_program(
         named_address_map,
     } in deps
     {
-        let (defs, _, ds, _) = parse_file(compilation_env, &mut files, path)?;
+        let (defs, _, ds, _) = parse_file(compilation_env, &mut files, path, source_buffers)?;
         lib_definitions.extend(defs.into_iter().map(|def| PackageDefinition {
             package,
             named_address_map,
@@ -175,6 +186,7 @@ fn parse_file(
     compilation_env: &mut CompilationEnv,
     files: &mut FilesSourceText,
     fname: Symbol,
+    source_buffers: &BTreeMap<Symbol, String>,
 ) -> anyhow::Result<(
     Vec<parser::ast::Definition>,
     MatchedFileCommentMap,
@@ -182,10 +194,16 @@ fn parse_file(
     FileHash,
 )> {
     let mut diags = Diagnostics::new();
-    let mut f = File::open(fname.as_str())
-        .map_err(|err| std::io::Error::new(err.kind(), format!("{}: {}", err, fname)))?;
-    let mut source_buffer = String::new();
-    f.read_to_string(&mut source_buffer)?;
+    let source_buffer = match source_buffers.get(&fname) {
+        Some(source_buffer) => source_buffer.clone(),
+        None => {
+            let mut f = File::open(fname.as_str())
+                .map_err(|err| std::io::Error::new(err.kind(), format!("{}: {}", err, fname)))?;
+            let mut source_buffer = String::new();
+            f.read_to_string(&mut source_buffer)?;
+            source_buffer
+        },
+    };
     let file_hash = FileHash::new(&source_buffer);
     let buffer = match verify_string(file_hash, &source_buffer) {
         Err(ds) => {
@@ -577,14 +577,39 @@ fn parse_attribute_value(context: &mut Context) -> Result<AttributeValue, Box<Di
     Ok(sp(ma.loc, AttributeValue_::ModuleAccess(ma)))
 }
 
+// Parse the name of an attribute, which may be qualified, as in `lint::allow`
+//      AttributeName = <Identifier> ("::" <Identifier>)*
+fn parse_attribute_name(context: &mut Context) -> Result<Name, Box<Diagnostic>> {
+    let start_loc = context.tokens.start_loc();
+    // `inline` is a keyword, but also the name of an attribute.
+    let mut name = if context.tokens.peek() == Tok::Inline {
+        context.tokens.advance()?;
+        "inline".to_owned()
+    } else {
+        parse_identifier(context)?.value.to_string()
+    };
+    while context.tokens.peek() == Tok::ColonColon {
+        context.tokens.advance()?;
+        name.push_str("::");
+        name.push_str(parse_identifier(context)?.value.as_str());
+    }
+    let end_loc = context.tokens.previous_end_loc();
+    Ok(spanned(
+        context.tokens.file_hash(),
+        start_loc,
+        end_loc,
+        name.into(),
+    ))
+}
+
 // Parse a single attribute
 //      Attribute =
-//          <Identifier>
-//          | <Identifier> "=" <AttributeValue>
-//          | <Identifier> "(" Comma<Attribute> ")"
+//          <AttributeName>
+//          | <AttributeName> "=" <AttributeValue>
+//          | <AttributeName> "(" Comma<Attribute> ")"
 fn parse_attribute(context: &mut Context) -> Result<Attribute, Box<Diagnostic>> {
     let start_loc = context.tokens.start_loc();
-    let n = parse_identifier(context)?;
+    let n = parse_attribute_name(context)?;
     let attr_ = match context.tokens.peek() {
         Tok::Equal => {
             context.tokens.advance()?;
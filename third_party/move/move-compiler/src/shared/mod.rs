@@ -607,6 +607,8 @@ pub mod known_attributes {
         Verification(VerificationAttribute),
         Native(NativeAttribute),
         Deprecation(DeprecationAttribute),
+        Lint(LintAttribute),
+        Optimization(OptimizationAttribute),
     }
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -638,6 +640,20 @@ pub mod known_attributes {
         Deprecated,
     }
 
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum LintAttribute {
+        // Suppresses the named lint warnings within the annotated item
+        Allow,
+        // Warns about calls of the annotated function whose result is discarded
+        MustUse,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum OptimizationAttribute {
+        // Requests that calls to the function are inlined by optimizing compilers
+        Inline,
+    }
+
     impl fmt::Display for AttributePosition {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             match self {
@@ -672,6 +688,9 @@ pub mod known_attributes {
                 DeprecationAttribute::DEPRECATED_NAME => {
                     Self::Deprecation(DeprecationAttribute::Deprecated)
                 },
+                LintAttribute::ALLOW => Self::Lint(LintAttribute::Allow),
+                LintAttribute::MUST_USE => Self::Lint(LintAttribute::MustUse),
+                OptimizationAttribute::INLINE => Self::Optimization(OptimizationAttribute::Inline),
                 _ => return None,
             })
         }
@@ -692,6 +711,8 @@ pub mod known_attributes {
             VerificationAttribute::add_attribute_names(table);
             NativeAttribute::add_attribute_names(table);
             DeprecationAttribute::add_attribute_names(table);
+            LintAttribute::add_attribute_names(table);
+            OptimizationAttribute::add_attribute_names(table);
         }
 
         fn name(&self) -> &str {
@@ -700,6 +721,8 @@ pub mod known_attributes {
                 Self::Verification(a) => a.name(),
                 Self::Native(a) => a.name(),
                 Self::Deprecation(a) => a.name(),
+                Self::Lint(a) => a.name(),
+                Self::Optimization(a) => a.name(),
             }
         }
 
@@ -709,6 +732,8 @@ pub mod known_attributes {
                 Self::Verification(a) => a.expected_positions(),
                 Self::Native(a) => a.expected_positions(),
                 Self::Deprecation(a) => a.expected_positions(),
+                Self::Lint(a) => a.expected_positions(),
+                Self::Optimization(a) => a.expected_positions(),
             }
         }
     }
@@ -879,4 +904,67 @@ pub mod known_attributes {
             }
         }
     }
+
+    impl LintAttribute {
+        pub const ALLOW: &'static str = "lint::allow";
+        pub const MUST_USE: &'static str = "lint::must_use";
+        const ALL_ATTRIBUTE_NAMES: [&'static str; 2] = [Self::ALLOW, Self::MUST_USE];
+    }
+    impl AttributeKind for LintAttribute {
+        fn add_attribute_names(table: &mut BTreeSet<String>) {
+            for str in Self::ALL_ATTRIBUTE_NAMES {
+                table.insert(str.to_string());
+            }
+        }
+
+        fn name(&self) -> &str {
+            match self {
+                Self::Allow => Self::ALLOW,
+                Self::MustUse => Self::MUST_USE,
+            }
+        }
+
+        fn expected_positions(&self) -> &'static BTreeSet<AttributePosition> {
+            static ALLOW_POSITIONS: Lazy<BTreeSet<AttributePosition>> = Lazy::new(|| {
+                IntoIterator::into_iter([
+                    AttributePosition::Module,
+                    AttributePosition::Script,
+                    AttributePosition::Function,
+                ])
+                .collect()
+            });
+            static MUST_USE_POSITIONS: Lazy<BTreeSet<AttributePosition>> =
+                Lazy::new(|| IntoIterator::into_iter([AttributePosition::Function]).collect());
+            match self {
+                Self::Allow => &ALLOW_POSITIONS,
+                Self::MustUse => &MUST_USE_POSITIONS,
+            }
+        }
+    }
+
+    impl OptimizationAttribute {
+        const ALL_ATTRIBUTE_NAMES: [&'static str; 1] = [Self::INLINE];
+        pub const INLINE: &'static str = "inline";
+    }
+    impl AttributeKind for OptimizationAttribute {
+        fn add_attribute_names(table: &mut BTreeSet<String>) {
+            for str in Self::ALL_ATTRIBUTE_NAMES {
+                table.insert(str.to_string());
+            }
+        }
+
+        fn name(&self) -> &str {
+            match self {
+                Self::Inline => Self::INLINE,
+            }
+        }
+
+        fn expected_positions(&self) -> &'static BTreeSet<AttributePosition> {
+            static INLINE_POSITIONS: Lazy<BTreeSet<AttributePosition>> =
+                Lazy::new(|| IntoIterator::into_iter([AttributePosition::Function]).collect());
+            match self {
+                Self::Inline => &INLINE_POSITIONS,
+            }
+        }
+    }
 }
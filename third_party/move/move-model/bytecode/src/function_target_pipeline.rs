@@ -60,6 +60,9 @@ pub enum FunctionVariant {
     /// of verification have one of those. There can be multiple verification variants,
     /// each identified by a unique flavor.
     Verification(VerificationFlavor),
+    /// A variant which is derived from the baseline by optimizations of the compiler, from
+    /// which code is generated instead of the baseline if it exists.
+    Optimized,
 }
 
 impl FunctionVariant {
@@ -75,6 +78,7 @@ impl std::fmt::Display for FunctionVariant {
             Baseline => write!(f, "baseline"),
             Verification(VerificationFlavor::Regular) => write!(f, "verification"),
             Verification(v) => write!(f, "verification[{}]", v),
+            Optimized => write!(f, "optimized"),
         }
     }
 }
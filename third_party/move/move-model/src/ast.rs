@@ -70,6 +70,14 @@ pub enum AttributeValue {
     Name(NodeId, Option<ModuleName>, Symbol),
 }
 
+impl AttributeValue {
+    pub fn node_id(&self) -> NodeId {
+        match self {
+            AttributeValue::Value(id, _) | AttributeValue::Name(id, _, _) => *id,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Attribute {
     Apply(NodeId, Symbol, Vec<Attribute>),
@@ -83,6 +91,12 @@ impl Attribute {
         }
     }
 
+    pub fn node_id(&self) -> NodeId {
+        match self {
+            Attribute::Assign(id, _, _) | Attribute::Apply(id, _, _) => *id,
+        }
+    }
+
     pub fn has(attrs: &[Attribute], pred: impl Fn(&Attribute) -> bool) -> bool {
         attrs.iter().any(pred)
     }
@@ -2242,6 +2242,11 @@ impl<'env, 'translator, 'module_translator> ExpTranslator<'env, 'translator, 'mo
             );
             self.new_error_exp()
         } else {
+            self.parent
+                .parent
+                .env
+                .used_named_constants
+                .insert(sym.clone());
             let ConstEntry { ty, value, .. } = entry;
             let ty = self.check_type(loc, &ty, expected_type, "");
             let id = self.new_node_id_with_type_loc(&ty, loc);
@@ -134,6 +134,7 @@ pub(crate) struct FunEntry {
     pub kind: FunctionKind,
     pub type_params: Vec<TypeParameter>,
     pub params: Vec<Parameter>,
+    pub param_locs: Vec<Loc>,
     pub result_type: Type,
     pub is_pure: bool,
     pub attributes: Vec<Attribute>,
@@ -448,6 +449,100 @@ impl<'env> ModelBuilder<'env> {
         }
     }
 
+    /// Defines the declarations of the modules of the env, as the translation of the modules
+    /// has defined them, so that code can be checked against an existing model. Schemas are
+    /// not part of the model, so they are not defined.
+    pub fn define_env_declarations(&mut self) {
+        let env: &GlobalEnv = self.env;
+        for module in &env.module_data {
+            let module_id = module.id;
+            let qualified = |symbol| QualifiedSymbol {
+                module_name: module.name.clone(),
+                symbol,
+            };
+            self.module_table.insert(module.name.clone(), module_id);
+            for (struct_id, data) in &module.struct_data {
+                let fields = data
+                    .field_data
+                    .values()
+                    .map(|field| {
+                        (
+                            field.name,
+                            (field.loc.clone(), field.offset, field.ty.clone()),
+                        )
+                    })
+                    .collect();
+                self.struct_table.insert(qualified(data.name), StructEntry {
+                    loc: data.loc.clone(),
+                    module_id,
+                    struct_id: *struct_id,
+                    type_params: data.type_params.clone(),
+                    abilities: data.abilities,
+                    fields: Some(fields),
+                    attributes: data.attributes.clone(),
+                });
+                self.reverse_struct_table
+                    .insert((module_id, *struct_id), qualified(data.name));
+            }
+            for (fun_id, data) in &module.function_data {
+                self.fun_table.insert(qualified(data.name), FunEntry {
+                    loc: data.loc.clone(),
+                    module_id,
+                    fun_id: *fun_id,
+                    visibility: data.visibility,
+                    is_native: data.is_native,
+                    kind: data.kind,
+                    type_params: data.type_params.clone(),
+                    params: data.params.clone(),
+                    param_locs: data.param_locs.clone(),
+                    result_type: data.result_type.clone(),
+                    is_pure: false,
+                    attributes: data.attributes.clone(),
+                    inline_specs: BTreeMap::new(),
+                });
+            }
+            for data in module.named_constants.values() {
+                self.const_table.insert(qualified(data.name), ConstEntry {
+                    loc: data.loc.clone(),
+                    ty: data.type_.clone(),
+                    value: data.value.clone(),
+                    visibility: EntryVisibility::SpecAndImpl,
+                });
+            }
+            for (var_id, decl) in &module.spec_vars {
+                self.spec_var_table
+                    .insert(qualified(decl.name), SpecVarEntry {
+                        loc: decl.loc.clone(),
+                        module_id,
+                        var_id: *var_id,
+                        type_params: decl.type_params.clone(),
+                        type_: decl.type_.clone(),
+                    });
+            }
+            for (spec_fun_id, decl) in &module.spec_funs {
+                // Move functions are also spec functions, under their name prefixed with `$`.
+                let name = if decl.is_move_fun {
+                    let name = env.symbol_pool().string(decl.name);
+                    env.symbol_pool().make(name.trim_start_matches('$'))
+                } else {
+                    decl.name
+                };
+                self.spec_fun_table
+                    .entry(qualified(name))
+                    .or_default()
+                    .push(SpecOrBuiltinFunEntry {
+                        loc: decl.loc.clone(),
+                        oper: Operation::SpecFunction(module_id, *spec_fun_id, None),
+                        type_params: decl.type_params.clone(),
+                        type_param_constraints: BTreeMap::default(),
+                        params: decl.params.clone(),
+                        result_type: decl.result_type.clone(),
+                        visibility: EntryVisibility::Spec,
+                    });
+            }
+        }
+    }
+
     /// Pass model-level information to the global env
     pub fn populate_env(&mut self) {
         // register all intrinsic declarations
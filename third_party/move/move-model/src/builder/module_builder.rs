@@ -452,11 +452,22 @@ impl<'env, 'translator> ModuleBuilder<'env, 'translator> {
         let attrs = self.translate_attributes(&def.attributes);
         let abilities = self.translate_abilities(&def.abilities);
         let mut et = ExpTranslator::new(self);
-        let type_params = et.analyze_and_add_type_params(
-            def.type_parameters
-                .iter()
-                .map(|s| (&s.name, &s.constraints)),
-        );
+        let type_params = et
+            .analyze_and_add_type_params(
+                def.type_parameters
+                    .iter()
+                    .map(|s| (&s.name, &s.constraints)),
+            )
+            .into_iter()
+            .zip(def.type_parameters.iter())
+            .map(|(TypeParameter(name, kind), param)| {
+                if param.is_phantom {
+                    TypeParameter(name, TypeParameterKind::new_phantom(kind.abilities))
+                } else {
+                    TypeParameter(name, kind)
+                }
+            })
+            .collect();
         et.parent.parent.define_struct(
             et.to_loc(&def.loc),
             attrs,
@@ -485,19 +496,14 @@ impl<'env, 'translator> ModuleBuilder<'env, 'translator> {
             .analyze_and_add_type_params(def.signature.type_parameters.iter().map(|(n, a)| (n, a)));
         et.enter_scope();
         let params = et.analyze_and_add_params(&def.signature.parameters, true);
+        let param_locs = def
+            .signature
+            .parameters
+            .iter()
+            .map(|(v, _)| et.to_loc(&v.0.loc))
+            .collect();
         let result_type = et.translate_type(&def.signature.return_type);
-        let kind = if def.entry.is_some() {
-            FunctionKind::Entry
-        } else if def.inline {
-            FunctionKind::Inline
-        } else {
-            FunctionKind::Regular
-        };
-        let visibility = match def.visibility {
-            EA::Visibility::Public(_) => Visibility::Public,
-            EA::Visibility::Friend(_) => Visibility::Friend,
-            EA::Visibility::Internal => Visibility::Private,
-        };
+        let (kind, visibility) = fun_kind_and_visibility(def);
         let is_native = matches!(def.body.value, EA::FunctionBody_::Native);
         let loc = et.to_loc(&def.loc);
         et.parent.parent.define_fun(qsym.clone(), FunEntry {
@@ -509,6 +515,7 @@ impl<'env, 'translator> ModuleBuilder<'env, 'translator> {
             kind,
             type_params: type_params.clone(),
             params: params.clone(),
+            param_locs,
             result_type: result_type.clone(),
             is_pure: false,
             attributes,
@@ -1240,31 +1247,15 @@ impl<'env, 'translator> ModuleBuilder<'env, 'translator> {
             let spec_block_map = entry.inline_specs.clone();
 
             let body_translator = |et: &mut ExpTranslator, as_spec_fun: bool| {
-                if as_spec_fun {
-                    et.set_translate_fun_as_spec_fun()
-                } else {
-                    et.set_translate_move_fun()
-                }
-                let loc = et.to_loc(&body.loc);
-                for (pos, TypeParameter(name, _)) in type_params.iter().enumerate() {
-                    et.define_type_param(&loc, *name, Type::new_param(pos), false);
-                }
-                et.enter_scope();
-                for (idx, Parameter(n, ty)) in params.iter().enumerate() {
-                    et.define_local(&loc, *n, ty.clone(), None, Some(idx));
-                }
-                let access_specifiers = if !as_spec_fun {
-                    // Translate access specifiers
-                    et.translate_access_specifiers(&def.access_specifiers)
-                } else {
-                    None
-                };
-                let mut result = et.translate_seq(&loc, seq, &result_type);
-                et.finalize_types();
-                if !as_spec_fun {
-                    result = et.post_process_spec_blocks(result.into_exp()).into();
-                }
-                (result, access_specifiers)
+                translate_fun_body(
+                    et,
+                    def,
+                    seq,
+                    &type_params,
+                    &params,
+                    &result_type,
+                    as_spec_fun,
+                )
             };
 
             // Attempt to translate as specification function
@@ -1307,6 +1298,53 @@ impl<'env, 'translator> ModuleBuilder<'env, 'translator> {
         self.spec_fun_index += 1; // TODO: why is this at the end? Document or move close to use
     }
 
+    /// Checks the body of the Move function of this module with the given name against the
+    /// declarations of the parent, with `def` as the definition of the function. Returns false,
+    /// without checking the body, if `def` declares the function with another signature than
+    /// the parent. Errors are reported to the env, but the function is not updated in the env.
+    pub fn recheck_fun(&mut self, name: Symbol, def: &EA::Function) -> bool {
+        let full_name = self.qualified_by_module(name);
+        let Some(entry) = self.parent.fun_table.get(&full_name).cloned() else {
+            return false;
+        };
+        let (kind, visibility) = fun_kind_and_visibility(def);
+        let is_native = matches!(def.body.value, EA::FunctionBody_::Native);
+        let (type_params, params, result_type) = self.decl_ana_signature(&def.signature, true);
+        if (
+            &type_params,
+            &params,
+            &result_type,
+            kind,
+            visibility,
+            is_native,
+        ) != (
+            &entry.type_params,
+            &entry.params,
+            &entry.result_type,
+            entry.kind,
+            entry.visibility,
+            entry.is_native,
+        ) {
+            return false;
+        }
+        if let EA::FunctionBody_::Defined(seq) = &def.body.value {
+            let mut et = ExpTranslator::new(self);
+            et.set_spec_block_map(def.specs.clone());
+            et.set_result_type(result_type.clone());
+            et.set_fun_name(full_name);
+            translate_fun_body(
+                &mut et,
+                def,
+                seq,
+                &type_params,
+                &params,
+                &result_type,
+                false,
+            );
+        }
+        true
+    }
+
     /// Propagate the impurity of Move functions from callees to callers so
     /// that we can detect pure-looking Move functions which calls impure
     /// Move functions.
@@ -3640,6 +3678,7 @@ impl<'env, 'translator> ModuleBuilder<'env, 'translator> {
                 attributes: entry.attributes.clone(),
                 type_params: entry.type_params.clone(),
                 params: entry.params.clone(),
+                param_locs: entry.param_locs.clone(),
                 result_type: entry.result_type.clone(),
                 access_specifiers,
                 spec: spec.into(),
@@ -3700,6 +3739,62 @@ impl<'env, 'translator> ModuleBuilder<'env, 'translator> {
     }
 }
 
+/// Returns the kind and the visibility of a Move function.
+fn fun_kind_and_visibility(def: &EA::Function) -> (FunctionKind, Visibility) {
+    let kind = if def.entry.is_some() {
+        FunctionKind::Entry
+    } else if def.inline {
+        FunctionKind::Inline
+    } else {
+        FunctionKind::Regular
+    };
+    let visibility = match def.visibility {
+        EA::Visibility::Public(_) => Visibility::Public,
+        EA::Visibility::Friend(_) => Visibility::Friend,
+        EA::Visibility::Internal => Visibility::Private,
+    };
+    (kind, visibility)
+}
+
+/// Translates the body of a Move function with the given signature, either as a Move function
+/// or as a specification function, returning the translated body and, for a Move function,
+/// its access specifiers.
+fn translate_fun_body(
+    et: &mut ExpTranslator,
+    def: &EA::Function,
+    seq: &EA::Sequence,
+    type_params: &[TypeParameter],
+    params: &[Parameter],
+    result_type: &Type,
+    as_spec_fun: bool,
+) -> (ExpData, Option<Vec<AccessSpecifier>>) {
+    if as_spec_fun {
+        et.set_translate_fun_as_spec_fun()
+    } else {
+        et.set_translate_move_fun()
+    }
+    let loc = et.to_loc(&def.body.loc);
+    for (pos, TypeParameter(name, _)) in type_params.iter().enumerate() {
+        et.define_type_param(&loc, *name, Type::new_param(pos), false);
+    }
+    et.enter_scope();
+    for (idx, Parameter(n, ty)) in params.iter().enumerate() {
+        et.define_local(&loc, *n, ty.clone(), None, Some(idx));
+    }
+    let access_specifiers = if !as_spec_fun {
+        // Translate access specifiers
+        et.translate_access_specifiers(&def.access_specifiers)
+    } else {
+        None
+    };
+    let mut result = et.translate_seq(&loc, seq, result_type);
+    et.finalize_types();
+    if !as_spec_fun {
+        result = et.post_process_spec_blocks(result.into_exp()).into();
+    }
+    (result, access_specifiers)
+}
+
 /// Extract all accesses of a schema from a schema expression.
 pub(crate) fn extract_schema_access<'a>(exp: &'a EA::Exp, res: &mut Vec<&'a EA::ModuleAccess>) {
     match &exp.value {
@@ -7,11 +7,11 @@
 use crate::{
     ast::ModuleName,
     builder::{model_builder::ModelBuilder, module_builder::BytecodeModule},
-    model::{FunId, GlobalEnv, Loc, ModuleId, StructId},
+    model::{FunId, GlobalEnv, Loc, ModuleId, QualifiedId, StructId},
     options::ModelBuilderOptions,
 };
 use builder::module_builder::ModuleBuilder;
-use codespan::ByteIndex;
+use codespan::{ByteIndex, FileId};
 use codespan_reporting::diagnostic::{Diagnostic, Label, LabelStyle};
 use itertools::Itertools;
 #[allow(unused_imports)]
@@ -24,19 +24,28 @@ use move_binary_format::{
         ModuleHandleIndex, Signature, SignatureIndex, Visibility,
     },
 };
+use move_command_line_common::files::FileHash;
 use move_compiler::{
     self,
+    cfgir::ast as G,
     compiled_unit::{self, AnnotatedCompiledScript, AnnotatedCompiledUnit},
     diagnostics::{codes::Severity, Diagnostics},
     expansion::ast::{self as E, ModuleIdent, ModuleIdent_},
+    hlir::ast as H,
     naming::ast as N,
-    parser::ast::{self as P, ModuleName as ParserModuleName},
+    parser::{
+        ast::{self as P, ModuleName as ParserModuleName},
+        filter::{filter_program, FilterContext},
+    },
     shared::{
         parse_named_address, unique_map::UniqueMap, CompilationEnv, Identifier as IdentifierTrait,
         NumericalAddress, PackagePaths,
     },
     typing::ast as T,
-    Compiler, Flags, PASS_COMPILATION, PASS_EXPANSION, PASS_INLINING, PASS_PARSER,
+    unit_test::filter_test_members,
+    verification::ast_filter,
+    Compiler, Flags, FullyCompiledProgram, PASS_COMPILATION, PASS_EXPANSION, PASS_INLINING,
+    PASS_PARSER,
 };
 use move_core_types::{account_address::AccountAddress, identifier::Identifier};
 use move_ir_types::location::sp;
@@ -68,37 +77,370 @@ pub mod well_known;
 #[derive(Debug, Clone)]
 pub struct PackageInfo {
     pub sources: Vec<String>,
+    /// Sources which are not read from the file system, as pairs of a virtual path and the
+    /// source text. The virtual path is used as the file name in the `GlobalEnv`.
+    pub source_buffers: Vec<(String, String)>,
     pub address_map: BTreeMap<String, NumericalAddress>,
 }
 
 /// Builds the Move model for the v2 compiler. This builds the model, compiling both code
 /// and specs from sources into typed-checked AST. No bytecode is attached to the model.
 /// This currently uses the v1 compiler as the parser (up to expansion AST), after that
-/// a new type checker.
+/// a new type checker. Items annotated with `#[test]` or `#[test_only]` are only part of the
+/// model if `compile_test_code` is set. If a filter is given, it is applied to the parsed
+/// sources before expansion, so the items it removes are not part of the model either.
 pub fn run_model_builder_in_compiler_mode(
     source: PackageInfo,
     deps: Vec<PackageInfo>,
     skip_attribute_checks: bool,
     known_attributes: &BTreeSet<String>,
+    compile_test_code: bool,
+    filter: Option<&mut dyn FilterContext>,
 ) -> anyhow::Result<GlobalEnv> {
-    let to_package_paths = |PackageInfo {
-                                sources,
-                                address_map,
-                            }| PackagePaths {
-        name: None,
-        paths: sources,
-        named_address_map: address_map,
+    let mut buffers = BTreeMap::new();
+    let mut to_package_paths = |PackageInfo {
+                                    mut sources,
+                                    source_buffers,
+                                    address_map,
+                                }| {
+        for (path, content) in source_buffers {
+            sources.push(path.clone());
+            buffers.insert(MoveSymbol::from(path), content);
+        }
+        PackagePaths {
+            name: None,
+            paths: sources,
+            named_address_map: address_map,
+        }
     };
-    run_model_builder_with_options_and_compilation_flags(
-        vec![to_package_paths(source)],
-        deps.into_iter().map(to_package_paths).collect(),
+    let targets = vec![to_package_paths(source)];
+    let deps = deps.into_iter().map(to_package_paths).collect();
+    let compiler = Compiler::from_package_paths(
+        targets,
+        deps,
+        Flags::model_compilation()
+            .set_skip_attribute_checks(skip_attribute_checks)
+            .set_keep_testing_functions(compile_test_code),
+        known_attributes,
+    )
+    .set_source_buffers(buffers);
+    run_model_builder_with_compiler(
+        compiler,
         ModelBuilderOptions {
             compile_via_model: true,
             ..ModelBuilderOptions::default()
         },
-        Flags::model_compilation().set_skip_attribute_checks(skip_attribute_checks),
+        filter,
+    )
+}
+
+/// The outcome of re-checking a function with `run_function_checker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionCheckOutcome {
+    /// The body of the function has been checked.
+    Checked,
+    /// The edited sources have errors which keep them from being expanded, so the function
+    /// has not been checked.
+    SourceErrors,
+    /// The edited sources no longer declare the function, or declare it with another
+    /// signature, so it has not been checked.
+    SignatureChanged,
+}
+
+/// Checks the body of a Move function of the env again after its source, from the start of
+/// its declaration to the end of its body, has been replaced with `new_source`. The body is
+/// checked against the declarations of the env, without checking any other function again.
+/// For this, the file of the function is parsed and expanded again, with the edit in place.
+/// The other files of the env are parsed once, on the first re-check of a function in this
+/// file, and kept as an extension of the env; they are not expanded, only the declarations of
+/// their modules are looked up. The edited source is added to the env as a file of the name of
+/// the original one, whose source is replaced on later re-checks, and the locations in the
+/// edited function refer to it. Diagnostics are added to the env, but the function is not
+/// updated in the env. The remaining parameters are as for `run_model_builder_in_compiler_mode`,
+/// and must be the same for all re-checks of an env.
+pub fn run_function_checker(
+    env: &mut GlobalEnv,
+    fun_id: QualifiedId<FunId>,
+    new_source: &str,
+    skip_attribute_checks: bool,
+    known_attributes: &BTreeSet<String>,
+    compile_test_code: bool,
+    filter: Option<&mut dyn FilterContext>,
+) -> anyhow::Result<FunctionCheckOutcome> {
+    let fun_env = env.get_function(fun_id);
+    let module_name = fun_env.module_env.get_name().clone();
+    let fun_name = fun_env.get_name();
+    let edited_loc = fun_env.get_loc();
+    let edited_file_id = edited_loc.file_id();
+    let mut filter = filter;
+    let flags = Flags::model_compilation()
+        .set_skip_attribute_checks(skip_attribute_checks)
+        .set_keep_testing_functions(compile_test_code);
+
+    // Step 1: parse the files of the env other than the edited one, unless this was done for
+    // an earlier re-check of a function in the same file.
+    let unedited = match env.get_extension::<UneditedFiles>() {
+        Some(unedited) if unedited.edited_file_id == edited_file_id => unedited,
+        _ => {
+            let Some(unedited) = parse_unedited_files(
+                env,
+                edited_file_id,
+                flags.clone(),
+                known_attributes,
+                filter.as_deref_mut(),
+            )?
+            else {
+                return Ok(FunctionCheckOutcome::SourceErrors);
+            };
+            env.set_extension(unedited);
+            env.get_extension::<UneditedFiles>().expect("extension")
+        },
+    };
+
+    // Step 2: parse and expand the edited file, looking up the declarations of the other
+    // files in the parsed program of step 1.
+    let mut source = env.get_file_source(edited_file_id).to_owned();
+    let span = edited_loc.span();
+    source.replace_range(span.start().to_usize()..span.end().to_usize(), new_source);
+    let file_name = env.get_file(edited_file_id).to_string_lossy().to_string();
+    let compiler = Compiler::from_package_paths(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![MoveSymbol::from(file_name.as_str())],
+            named_address_map: file_address_map(env, edited_file_id),
+        }],
+        vec![],
+        flags,
         known_attributes,
     )
+    .set_source_buffers(BTreeMap::from([(
+        MoveSymbol::from(file_name.as_str()),
+        source,
+    )]))
+    .set_pre_compiled_lib(&unedited.program);
+    let (files, comments_and_compiler_res) = compiler.run::<PASS_PARSER>()?;
+    for (fhash, (_, fsrc)) in &files {
+        add_edited_source(env, edited_file_id, *fhash, fsrc)
+    }
+    let compiler = match comments_and_compiler_res {
+        Err(diags) => {
+            add_move_lang_diagnostics(env, diags);
+            return Ok(FunctionCheckOutcome::SourceErrors);
+        },
+        Ok((_, compiler)) => compiler,
+    };
+    let (compiler, parsed_prog) = compiler.into_ast();
+    let parsed_prog = match filter {
+        Some(filter) => filter_program(filter, parsed_prog),
+        None => parsed_prog,
+    };
+    let expansion_ast = match compiler.at_parser(parsed_prog).run::<PASS_EXPANSION>() {
+        Err(diags) => {
+            add_move_lang_diagnostics(env, diags);
+            return Ok(FunctionCheckOutcome::SourceErrors);
+        },
+        Ok(mut compiler) => {
+            let compiler_env: &mut CompilationEnv = compiler.compilation_env();
+            if let Err(diags) = compiler_env.check_diags_at_or_above_severity(Severity::Warning) {
+                let has_errors = diags.max_severity() > Some(Severity::Warning);
+                add_move_lang_diagnostics(env, diags);
+                if has_errors {
+                    return Ok(FunctionCheckOutcome::SourceErrors);
+                }
+            }
+            compiler.into_ast().1
+        },
+    };
+
+    // Step 3: check the body of the edited function against the declarations of the env.
+    let fun_def = expansion_ast
+        .modules
+        .into_iter()
+        .find(|(mident, _)| {
+            let E::Address::Numerical(_, addr) = &mident.value.address else {
+                return false;
+            };
+            module_name
+                == ModuleName::from_address_bytes_and_name(
+                    addr.value,
+                    env.symbol_pool().make(&mident.value.module.0.value),
+                )
+        })
+        .and_then(|(_, mut module_def)| {
+            module_def.functions.remove_(&MoveSymbol::from(
+                env.symbol_pool().string(fun_name).as_str(),
+            ))
+        });
+    let Some(fun_def) = fun_def else {
+        return Ok(FunctionCheckOutcome::SignatureChanged);
+    };
+    let mut builder = ModelBuilder::new(env);
+    builder.define_env_declarations();
+    let mut module_builder = ModuleBuilder::new(&mut builder, fun_id.module_id, module_name);
+    Ok(if module_builder.recheck_fun(fun_name, &fun_def) {
+        FunctionCheckOutcome::Checked
+    } else {
+        FunctionCheckOutcome::SignatureChanged
+    })
+}
+
+/// The parsed program of the files of an env other than the file of the functions re-checked by
+/// `run_function_checker`, kept as an extension of the env.
+struct UneditedFiles {
+    /// The file of the re-checked functions, which is not part of the program.
+    edited_file_id: FileId,
+    /// The program, as a pre-compiled library for the compiler. Only the parsed program is
+    /// populated, as this is all that expansion looks up in the library.
+    program: FullyCompiledProgram,
+}
+
+/// Parses the files of the env other than the edited file, grouped into packages by their
+/// address mapping. Returns `None` if they cannot be parsed, with the errors added to the env.
+fn parse_unedited_files(
+    env: &mut GlobalEnv,
+    edited_file_id: FileId,
+    flags: Flags,
+    known_attributes: &BTreeSet<String>,
+    filter: Option<&mut (dyn FilterContext + '_)>,
+) -> anyhow::Result<Option<UneditedFiles>> {
+    // Files added by earlier re-checks have the names of the original files, which are added
+    // first, so only the original files are parsed.
+    let mut files = BTreeMap::new();
+    for file_id in env.get_source_file_ids().into_iter().sorted() {
+        files
+            .entry(env.get_file(file_id).to_string_lossy().to_string())
+            .or_insert(file_id);
+    }
+    let mut packages = BTreeMap::new();
+    let mut buffers = BTreeMap::new();
+    for (file_name, file_id) in files {
+        if file_id == edited_file_id {
+            continue;
+        }
+        let address_map = file_address_map(env, file_id);
+        let key = address_map
+            .iter()
+            .map(|(name, addr)| (name.clone(), addr.into_inner()))
+            .collect::<Vec<_>>();
+        packages
+            .entry(key)
+            .or_insert_with(|| PackagePaths {
+                name: None,
+                paths: vec![],
+                named_address_map: address_map,
+            })
+            .paths
+            .push(MoveSymbol::from(file_name.as_str()));
+        buffers.insert(
+            MoveSymbol::from(file_name.as_str()),
+            env.get_file_source(file_id).to_owned(),
+        );
+    }
+    let compiler = Compiler::from_package_paths(
+        packages.into_values().collect(),
+        vec![],
+        flags,
+        known_attributes,
+    )
+    .set_source_buffers(buffers);
+    let (files, comments_and_compiler_res) = compiler.run::<PASS_PARSER>()?;
+    let compiler = match comments_and_compiler_res {
+        Err(diags) => {
+            add_move_lang_diagnostics(env, diags);
+            return Ok(None);
+        },
+        Ok((_, compiler)) => compiler,
+    };
+    let (mut compiler, parsed_prog) = compiler.into_ast();
+    let parsed_prog = match filter {
+        Some(filter) => filter_program(filter, parsed_prog),
+        None => parsed_prog,
+    };
+    // Test and verification code is removed as it is when the edited file is expanded.
+    let compilation_env = compiler.compilation_env();
+    let parsed_prog = filter_test_members::program(compilation_env, parsed_prog);
+    let P::Program {
+        named_address_maps,
+        mut source_definitions,
+        lib_definitions,
+    } = ast_filter::program(compilation_env, parsed_prog);
+    source_definitions.extend(lib_definitions);
+    Ok(Some(UneditedFiles {
+        edited_file_id,
+        program: FullyCompiledProgram {
+            files,
+            parser: P::Program {
+                named_address_maps,
+                source_definitions,
+                lib_definitions: vec![],
+            },
+            expansion: E::Program {
+                modules: UniqueMap::new(),
+                scripts: BTreeMap::new(),
+            },
+            naming: N::Program {
+                modules: UniqueMap::new(),
+                scripts: BTreeMap::new(),
+            },
+            typing: T::Program {
+                modules: UniqueMap::new(),
+                scripts: BTreeMap::new(),
+            },
+            inlining: T::Program {
+                modules: UniqueMap::new(),
+                scripts: BTreeMap::new(),
+            },
+            hlir: H::Program {
+                modules: UniqueMap::new(),
+                scripts: BTreeMap::new(),
+            },
+            cfgir: G::Program {
+                modules: UniqueMap::new(),
+                scripts: BTreeMap::new(),
+            },
+            compiled: vec![],
+        },
+    }))
+}
+
+/// Returns the named address mapping of a file of the env.
+fn file_address_map(env: &GlobalEnv, file_id: FileId) -> BTreeMap<String, NumericalAddress> {
+    env.file_alias_map
+        .get(&file_id)
+        .map(|aliases| {
+            aliases
+                .iter()
+                .map(|(name, addr)| (env.symbol_pool().string(*name).to_string(), *addr))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Adds the edited source of a file to the env, as a file with the name and the address aliases
+/// of the original one. The file added by an earlier re-check of a function in the same file
+/// gets the new source instead, so the env does not grow with each re-check.
+fn add_edited_source(env: &mut GlobalEnv, file_id: FileId, file_hash: FileHash, source: &str) {
+    if env.get_file_id(file_hash).is_some() {
+        return;
+    }
+    let file_name = env.get_file(file_id).to_owned();
+    let added = env
+        .get_source_file_ids()
+        .into_iter()
+        .find(|id| *id != file_id && env.get_file(*id) == file_name);
+    match added {
+        Some(added) => env.update_source(added, file_hash, source),
+        None => {
+            let aliases = env.file_alias_map[&file_id].clone();
+            env.add_source(
+                file_hash,
+                aliases,
+                file_name.to_string_lossy().as_ref(),
+                source,
+                false,
+            );
+        },
+    }
 }
 
 // =================================================================================================
@@ -136,15 +478,27 @@ pub fn run_model_builder_with_options_and_compilation_flags<
     options: ModelBuilderOptions,
     flags: Flags,
     known_attributes: &BTreeSet<String>,
+) -> anyhow::Result<GlobalEnv> {
+    run_model_builder_with_compiler(
+        Compiler::from_package_paths(move_sources, deps, flags, known_attributes),
+        options,
+        None,
+    )
+}
+
+/// Build the move model for the sources and with the compilation flags of the given compiler,
+/// removing the parsed items the filter removes, if any.
+fn run_model_builder_with_compiler(
+    compiler: Compiler,
+    options: ModelBuilderOptions,
+    filter: Option<&mut dyn FilterContext>,
 ) -> anyhow::Result<GlobalEnv> {
     let mut env = GlobalEnv::new();
     let compile_via_model = options.compile_via_model;
     env.set_extension(options);
 
     // Step 1: parse the program to get comments and a separation of targets and dependencies.
-    let (files, comments_and_compiler_res) =
-        Compiler::from_package_paths(move_sources, deps, flags, known_attributes)
-            .run::<PASS_PARSER>()?;
+    let (files, comments_and_compiler_res) = compiler.run::<PASS_PARSER>()?;
     let (comment_map, compiler) = match comments_and_compiler_res {
         Err(diags) => {
             // Add source files so that the env knows how to translate locations of parse errors
@@ -217,6 +571,10 @@ pub fn run_model_builder_with_options_and_compilation_flags<
     }
 
     // Step 2: run the compiler up to expansion
+    let parsed_prog = match filter {
+        Some(filter) => filter_program(filter, parsed_prog),
+        None => parsed_prog,
+    };
     let parsed_prog = {
         let P::Program {
             named_address_maps,
@@ -18,8 +18,8 @@
 use crate::{
     ast::{
         AccessSpecifier, Address, AddressSpecifier, Attribute, ConditionKind, Exp, ExpData,
-        FriendDecl, GlobalInvariant, ModuleName, PropertyBag, PropertyValue, ResourceSpecifier,
-        Spec, SpecBlockInfo, SpecFunDecl, SpecVarDecl, UseDecl, Value,
+        FriendDecl, GlobalInvariant, ModuleName, PropertyBag, PropertyValue, QualifiedSymbol,
+        ResourceSpecifier, Spec, SpecBlockInfo, SpecFunDecl, SpecVarDecl, UseDecl, Value,
     },
     code_writer::CodeWriter,
     emit, emitln,
@@ -518,6 +518,10 @@ pub struct GlobalEnv {
     /// are represented without type instantiation because we assume the backend can handle
     /// generics in the expression language.
     pub(crate) used_spec_funs: BTreeSet<QualifiedId<SpecFunId>>,
+    /// A set containing the named constants which are used in expressions, including specs.
+    /// Uses of constants are replaced by their values when expressions are built, so they
+    /// are recorded here.
+    pub(crate) used_named_constants: BTreeSet<QualifiedSymbol>,
     /// An annotation of all intrinsic declarations
     pub(crate) intrinsics: IntrinsicsAnnotation,
     /// A type-indexed container for storing extension data in the environment.
@@ -580,6 +584,7 @@ impl GlobalEnv {
             global_invariants: Default::default(),
             global_invariants_for_memory: Default::default(),
             used_spec_funs: BTreeSet::new(),
+            used_named_constants: BTreeSet::new(),
             intrinsics: Default::default(),
             extensions: Default::default(),
             stdlib_address: None,
@@ -718,6 +723,17 @@ impl GlobalEnv {
         file_id
     }
 
+    /// Replaces the source of a file of this environment, which then has the given hash.
+    /// Locations in the file refer to the new source afterwards.
+    pub fn update_source(&mut self, file_id: FileId, file_hash: FileHash, source: &str) {
+        self.source_files.update(file_id, source.to_string());
+        if let Some(old_hash) = self.reverse_file_hash_map.insert(file_id, file_hash) {
+            self.file_hash_map.remove(&old_hash);
+        }
+        let file_name = self.get_file(file_id).to_string_lossy().to_string();
+        self.file_hash_map.insert(file_hash, (file_name, file_id));
+    }
+
     fn resolve_std_address_alias(
         &self,
         def: Option<Address>,
@@ -825,6 +841,60 @@ impl GlobalEnv {
         self.diags.borrow_mut().clear();
     }
 
+    /// Replaces the accumulated diagnostics, with whether they have been reported, returning
+    /// the previous ones. This allows tools to process the diagnostics of a step on their own.
+    pub fn replace_diags(
+        &self,
+        diags: Vec<(Diagnostic<FileId>, bool)>,
+    ) -> Vec<(Diagnostic<FileId>, bool)> {
+        std::mem::replace(&mut *self.diags.borrow_mut(), diags)
+    }
+
+    /// Removes the accumulated diagnostics which do not satisfy the predicate.
+    pub fn retain_diags(&self, mut pred: impl FnMut(&Diagnostic<FileId>) -> bool) {
+        self.diags.borrow_mut().retain(|(d, _)| pred(d))
+    }
+
+    /// Adds the notes returned by `notes_of` to the accumulated diagnostics which have not been
+    /// reported yet. This allows tools to explain diagnostics of the model builder.
+    pub fn add_diag_notes(&self, mut notes_of: impl FnMut(&Diagnostic<FileId>) -> Vec<String>) {
+        for (diag, reported) in self.diags.borrow_mut().iter_mut() {
+            if !*reported {
+                let notes = notes_of(diag);
+                diag.notes.extend(notes)
+            }
+        }
+    }
+
+    /// Collapses identical diagnostics which have not been reported yet into the first of
+    /// them, and adds a note with the number of occurrences to it. Diagnostics are identical
+    /// if they agree in severity, code, message, all labels, and notes.
+    pub fn deduplicate_diags(&self) {
+        let mut diags = self.diags.borrow_mut();
+        let mut first_of: BTreeMap<String, usize> = BTreeMap::new();
+        let mut counts = vec![0; diags.len()];
+        for (idx, (diag, reported)) in diags.iter().enumerate() {
+            if !*reported {
+                counts[*first_of.entry(format!("{:?}", diag)).or_insert(idx)] += 1
+            }
+        }
+        let mut idx = 0;
+        diags.retain_mut(|(diag, reported)| {
+            let count = counts[idx];
+            idx += 1;
+            if count > 1 {
+                diag.notes.push(format!("reported {} times", count))
+            }
+            *reported || count > 0
+        })
+    }
+
+    /// Sorts the accumulated diagnostics by the given key. The sort is stable, so diagnostics
+    /// with equal keys keep their order, and the labels of each diagnostic are not reordered.
+    pub fn sort_diags_by_key<K: Ord>(&self, mut key: impl FnMut(&Diagnostic<FileId>) -> K) {
+        self.diags.borrow_mut().sort_by_key(|(diag, _)| key(diag))
+    }
+
     /// Returns the unknown location.
     pub fn unknown_loc(&self) -> Loc {
         self.unknown_loc.clone()
@@ -973,6 +1043,18 @@ impl GlobalEnv {
             .count()
     }
 
+    /// Returns the diagnostics from the given position on, in the order in which they were
+    /// added. Together with `diag_count(Severity::Help)`, this allows tools to observe the
+    /// diagnostics which a step adds.
+    pub fn get_diags_from(&self, start: usize) -> Vec<Diagnostic<FileId>> {
+        self.diags
+            .borrow()
+            .iter()
+            .skip(start)
+            .map(|(d, _)| d.clone())
+            .collect()
+    }
+
     /// Returns the number of errors.
     pub fn error_count(&self) -> usize {
         self.diag_count(Severity::Error)
@@ -986,6 +1068,26 @@ impl GlobalEnv {
             .any(|(d, _)| d.severity >= Severity::Warning)
     }
 
+    /// Raises the severity of all diagnostics which have the `from` severity to `to`.
+    pub fn promote_diag_severity(&self, from: Severity, to: Severity) {
+        self.promote_diag_severity_with_filter(from, to, |_| true)
+    }
+
+    /// Raises the severity of the diagnostics which have the `from` severity and pass through
+    /// `filter` to `to`.
+    pub fn promote_diag_severity_with_filter(
+        &self,
+        from: Severity,
+        to: Severity,
+        mut filter: impl FnMut(&Diagnostic<FileId>) -> bool,
+    ) {
+        for (diag, _) in self.diags.borrow_mut().iter_mut() {
+            if diag.severity == from && filter(diag) {
+                diag.severity = to
+            }
+        }
+    }
+
     /// Writes accumulated diagnostics of given or higher severity.
     pub fn report_diag<W: WriteColor>(&self, writer: &mut W, severity: Severity) {
         self.report_diag_with_filter(writer, |d| d.severity >= severity)
@@ -995,9 +1097,48 @@ impl GlobalEnv {
     pub fn report_diag_with_filter<W: WriteColor, F: FnMut(&Diagnostic<FileId>) -> bool>(
         &self,
         writer: &mut W,
-        mut filter: F,
+        filter: F,
     ) {
         let mut shown = BTreeSet::new();
+        self.report_diag_with_emitter(filter, |diag| {
+            // Avoid showing the same message twice. This can happen e.g. because of
+            // duplication of expressions via schema inclusion.
+            if shown.insert(format!("{:?}", diag)) {
+                emit(writer, &Config::default(), &self.source_files, diag)
+                    .expect("emit must not fail");
+            }
+        })
+    }
+
+    /// Writes the diagnostic, which need not be accumulated in the environment, in the format
+    /// of `report_diag`.
+    pub fn emit_diag<W: WriteColor>(&self, writer: &mut W, diag: &Diagnostic<FileId>) {
+        emit(writer, &Config::default(), &self.source_files, diag).expect("emit must not fail")
+    }
+
+    /// Returns the accumulated diagnostics which pass through `filter` and have not been
+    /// reported yet, without marking them as reported. This allows tools to render diagnostics
+    /// in an additional format.
+    pub fn get_unreported_diags<F>(&self, mut filter: F) -> Vec<Diagnostic<FileId>>
+    where
+        F: FnMut(&Diagnostic<FileId>) -> bool,
+    {
+        self.diags
+            .borrow()
+            .iter()
+            .filter(|(d, reported)| !*reported && filter(d))
+            .map(|(d, _)| d.clone())
+            .collect()
+    }
+
+    /// Passes accumulated diagnostics which pass through `filter` and have not been reported
+    /// yet to `emitter`, and marks them as reported. This allows tools to render diagnostics
+    /// in their own format. The emitter must not add new diagnostics to the environment.
+    pub fn report_diag_with_emitter<F, E>(&self, mut filter: F, mut emitter: E)
+    where
+        F: FnMut(&Diagnostic<FileId>) -> bool,
+        E: FnMut(&Diagnostic<FileId>),
+    {
         for (diag, reported) in self
             .diags
             .borrow_mut()
@@ -1005,12 +1146,7 @@ impl GlobalEnv {
             .filter(|(d, _)| filter(d))
         {
             if !*reported {
-                // Avoid showing the same message twice. This can happen e.g. because of
-                // duplication of expressions via schema inclusion.
-                if shown.insert(format!("{:?}", diag)) {
-                    emit(writer, &Config::default(), &self.source_files, diag)
-                        .expect("emit must not fail");
-                }
+                emitter(diag);
                 *reported = true;
             }
         }
@@ -1137,12 +1273,15 @@ impl GlobalEnv {
             },
             Type::Vector(et) => AbilitySet::VECTOR.intersect(self.type_abilities(et, ty_params)),
             Type::Struct(mid, sid, inst) => {
+                // The declared abilities hold conditionally on the abilities of the non-phantom
+                // type arguments, where `key` requires `store` of the arguments.
                 let struct_env = self.get_struct(mid.qualified(*sid));
-                let mut abilities = struct_env.get_abilities();
-                for inst_ty in inst {
-                    abilities = abilities.intersect(self.type_abilities(inst_ty, ty_params))
-                }
-                abilities
+                AbilitySet::polymorphic_abilities(
+                    struct_env.get_abilities(),
+                    (0..inst.len()).map(|i| struct_env.is_phantom_parameter(i)),
+                    inst.iter().map(|ty| self.type_abilities(ty, ty_params)),
+                )
+                .unwrap_or(AbilitySet::EMPTY)
             },
             Type::TypeParameter(i) => {
                 if let Some(tp) = ty_params.get(*i as usize) {
@@ -1315,6 +1454,17 @@ impl GlobalEnv {
         mod_data.source_map = Some(source_map);
     }
 
+    /// Adds a friend declaration to a module. This is used for modules whose friend
+    /// declarations are not part of their source, like modules which are only available
+    /// as bytecode.
+    pub fn add_friend_decl(&mut self, module_id: ModuleId, decl: FriendDecl) {
+        let mod_data = &mut self.module_data[module_id.0 as usize];
+        if let Some(friend_id) = decl.module_id {
+            mod_data.friend_modules.insert(friend_id);
+        }
+        mod_data.friend_decls.push(decl);
+    }
+
     fn get_called_funs_from_bytecode(
         &self,
         module: &CompiledModule,
@@ -2167,6 +2317,11 @@ impl<'env> ModuleEnv<'env> {
         &self.data.use_decls
     }
 
+    /// Returns the friend declarations of this module.
+    pub fn get_friend_decls(&self) -> &[FriendDecl] {
+        &self.data.friend_decls
+    }
+
     /// Does this module have a friend with `module_id`?
     pub fn has_friend(&self, module_id: &ModuleId) -> bool {
         self.data.friend_modules.contains(module_id)
@@ -3134,6 +3289,17 @@ impl<'env> NamedConstantEnv<'env> {
     pub fn get_value(&self) -> Value {
         self.data.value.clone()
     }
+
+    /// Returns true if this constant is used in an expression, including specs.
+    pub fn is_used(&self) -> bool {
+        self.module_env
+            .env
+            .used_named_constants
+            .contains(&QualifiedSymbol {
+                module_name: self.module_env.get_name().clone(),
+                symbol: self.data.name,
+            })
+    }
 }
 
 // =================================================================================================
@@ -3233,6 +3399,9 @@ pub struct FunctionData {
     /// Parameters
     pub(crate) params: Vec<Parameter>,
 
+    /// Locations of the parameters.
+    pub(crate) param_locs: Vec<Loc>,
+
     /// Result type of the function, uses `Type::Tuple` for multiple values.
     pub(crate) result_type: Type,
 
@@ -3657,6 +3826,16 @@ impl<'env> FunctionEnv<'env> {
         self.data.params.clone()
     }
 
+    /// Returns the location of the parameter at the given index, falling back to the
+    /// location of the function if it is not known.
+    pub fn get_parameter_loc(&self, idx: usize) -> Loc {
+        self.data
+            .param_locs
+            .get(idx)
+            .cloned()
+            .unwrap_or_else(|| self.get_loc())
+    }
+
     /// Returns the result type of this function, which is a tuple for multiple results.
     pub fn get_result_type(&self) -> Type {
         self.data.result_type.clone()
@@ -624,7 +624,9 @@ impl<'env> FunctionTranslator<'env> {
         let (args, rets) = self.generate_function_args_and_returns();
 
         let (suffix, attribs) = match &fun_target.data.variant {
-            FunctionVariant::Baseline => ("".to_string(), "{:inline 1} ".to_string()),
+            FunctionVariant::Baseline | FunctionVariant::Optimized => {
+                ("".to_string(), "{:inline 1} ".to_string())
+            },
             FunctionVariant::Verification(flavor) => {
                 let mut attribs = vec![format!(
                     "{{:timeLimit {}}} ",
@@ -56,6 +56,7 @@ impl FunctionTargetProcessor for InconsistencyCheckInstrumenter {
         }
         let flavor = match &data.variant {
             FunctionVariant::Baseline
+            | FunctionVariant::Optimized
             | FunctionVariant::Verification(VerificationFlavor::Inconsistency(..)) => {
                 // instrumentation only applies to non-inconsistency verification variants
                 return data;
@@ -527,7 +527,7 @@ impl<'a> Instrumenter<'a> {
                             .set_loc_and_vc_info(loc, REQUIRES_FAILS_MESSAGE);
                         Assert
                     },
-                    FunctionVariant::Baseline => Assume,
+                    FunctionVariant::Baseline | FunctionVariant::Optimized => Assume,
                 };
                 self.builder.emit_with(|id| Prop(id, prop_kind, cond));
             }
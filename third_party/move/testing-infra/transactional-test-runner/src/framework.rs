@@ -699,6 +699,11 @@ fn compile_source_unit_v2(
             .map(|(alias, addr)| format!("{}={}", alias, addr))
             .collect(),
         known_attributes: known_attributes.clone(),
+        // The dependencies are all directories of the pre-compiled program, of which a unit
+        // typically uses only some.
+        suppressed_warnings: vec![move_compiler_v2::diagnostic_codes::UNUSED_DEPENDENCY
+            .code
+            .to_owned()],
         ..move_compiler_v2::Options::default()
     };
     let mut error_writer = termcolor::Buffer::no_color();
@@ -14,10 +14,7 @@ use move_compiler::{
     PASS_CFGIR,
 };
 use move_coverage::coverage_map::{output_map_to_file, CoverageMap};
-use move_package::{
-    compilation::{build_plan::BuildPlan, compiled_package::build_and_report_v2_driver},
-    BuildConfig,
-};
+use move_package::{compilation::build_plan::BuildPlan, BuildConfig};
 use move_unit_test::UnitTestingConfig;
 use move_vm_runtime::tracing::{LOGGING_FILE_WRITER, TRACING_ENABLED};
 use move_vm_test_utils::gas_schedule::CostTable;
@@ -162,6 +159,7 @@ pub fn run_move_unit_tests<W: Write + Send>(
     writer: &mut W,
 ) -> Result<UnitTestResult> {
     let mut test_plan = None;
+    let mut v2_test_plan = None;
     build_config.test_mode = true;
     build_config.dev_mode = true;
     build_config.generate_move_model = test_validation::needs_validation();
@@ -206,7 +204,8 @@ pub fn run_move_unit_tests<W: Write + Send>(
     // Compile the package. We need to intercede in the compilation, process being performed by the
     // Move package system, to first grab the compilation env, construct the test plan from it, and
     // then save it, before resuming the rest of the compilation and returning the results and
-    // control back to the Move package system.
+    // control back to the Move package system. With compiler v2, the test plan is built from
+    // the model and the compiled units after compilation.
     let (_, model_opt) = build_plan.compile_with_driver(
         writer,
         &build_config.compiler_config,
@@ -233,7 +232,15 @@ pub fn run_move_unit_tests<W: Write + Send>(
             test_plan = Some((built_test_plan, files.clone(), units.clone()));
             Ok((files, units))
         },
-        build_and_report_v2_driver,
+        |options| {
+            let mut writer = StandardStream::stderr(termcolor::ColorChoice::Auto);
+            let Ok((env, units)) = move_compiler_v2::run_move_compiler(&mut writer, options) else {
+                // Error reported, exit
+                std::process::exit(1)
+            };
+            v2_test_plan = Some(move_compiler_v2::unit_test::build_test_plan(&env, &units));
+            Ok((move_compiler_v2::make_files_source_text(&env), units))
+        },
     )?;
 
     // If configured, run extra validation
@@ -252,11 +259,15 @@ pub fn run_move_unit_tests<W: Write + Send>(
         }
     }
 
-    let (test_plan, mut files, units) = test_plan.unwrap();
-    files.extend(dep_file_map);
-    let test_plan = test_plan.unwrap();
-    let no_tests = test_plan.is_empty();
-    let test_plan = TestPlan::new(test_plan, files, units);
+    let mut test_plan = match v2_test_plan {
+        Some(test_plan) => test_plan,
+        None => {
+            let (test_plan, files, units) = test_plan.unwrap();
+            TestPlan::new(test_plan.unwrap(), files, units)
+        },
+    };
+    test_plan.files.extend(dep_file_map);
+    let no_tests = test_plan.module_tests.is_empty();
 
     let trace_path = pkg_path.join(".trace");
     let coverage_map_path = pkg_path
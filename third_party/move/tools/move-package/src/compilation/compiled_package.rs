@@ -668,6 +668,7 @@ impl CompiledPackage {
                         .into_iter()
                         .map(|(k, v)| format!("{}={}", k, v))
                         .collect(),
+                    compile_test_code: flags.is_testing(),
                     ..Default::default()
                 };
                 compiler_driver_v2(options)?